@@ -0,0 +1,45 @@
+# Sweeps the generator's output and reports how long the station keeps every
+# module powered. Build the bindings first with `maturin develop --release`.
+#
+#     python examples/parameter_sweep.py
+#
+# Plots the result if matplotlib is installed.
+import space_station_3d as ss
+
+HOURS = 2
+SEEDS = range(5)
+
+
+def seconds_fully_powered(report, modules):
+    for sample in report["samples"]:
+        if sample["telemetry"]["powered_modules"] < modules:
+            return sample["tick"] * report["tick_seconds"]
+    return report["ticks"] * report["tick_seconds"]
+
+
+modules = ss.Station().module_count()
+outputs = [20.0 * step for step in range(11)]
+results = []
+for output in outputs:
+    runs = [
+        ss.simulate(
+            int(HOURS * 3600 / ss.TICK_SECONDS),
+            sample_every=60,
+            seed=seed,
+            tunables={"power.generator_output": output},
+        )
+        for seed in SEEDS
+    ]
+    seconds = sum(seconds_fully_powered(run, modules) for run in runs) / len(runs)
+    results.append(seconds)
+    print(f"generator {output:6.1f}: all modules powered for {seconds / 60:6.1f} min")
+
+try:
+    import matplotlib.pyplot as plt
+except ImportError:
+    pass
+else:
+    plt.plot(outputs, [s / 60 for s in results], marker="o")
+    plt.xlabel("power.generator_output")
+    plt.ylabel("minutes before load shedding")
+    plt.show()
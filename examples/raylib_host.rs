@@ -0,0 +1,84 @@
+// A host application that owns its own window and frame loop and draws a
+// custom station into it. The library doesn't open windows itself, so the
+// host decides how modules look; here each one is a coloured box.
+//
+//     cargo run --example raylib_host
+use anyhow::Result;
+use glam::Vec3;
+use raylib::prelude::*;
+use space_station_3d::station::{ModuleType, SpaceStation};
+use space_station_3d::station_builder::{Direction, StationBuilder};
+use space_station_3d::stepping::{SimStepper, STEP_DT};
+
+fn module_color(module_type: ModuleType) -> Color {
+    match module_type {
+        ModuleType::CommandCenter => Color::GOLD,
+        ModuleType::PowerPlant | ModuleType::SolarArray | ModuleType::Battery => Color::ORANGE,
+        ModuleType::Laboratory => Color::SKYBLUE,
+        ModuleType::LivingQuarters => Color::LIME,
+        ModuleType::Airlock | ModuleType::EscapePod => Color::RED,
+        ModuleType::Storage => Color::BROWN,
+        ModuleType::Corridor | ModuleType::Hub => Color::LIGHTGRAY,
+    }
+}
+
+fn to_vector3(v: Vec3) -> Vector3 {
+    Vector3::new(v.x, v.y, v.z)
+}
+
+fn draw_station(d: &mut impl RaylibDraw3D, station: &SpaceStation) {
+    for (i, module) in station.modules().iter().enumerate() {
+        let position = to_vector3(module.transform.position);
+        d.draw_cube(position, 4.0, 3.0, 4.0, module_color(module.module_type));
+        d.draw_cube_wires(position, 4.0, 3.0, 4.0, Color::DARKGRAY);
+        // Each link once
+        for other in station.neighbours(i).into_iter().filter(|&other| other > i) {
+            let other = to_vector3(station.modules()[other].transform.position);
+            d.draw_line_3D(position, other, Color::WHITE);
+        }
+    }
+}
+
+fn main() -> Result<()> {
+    let mut station = StationBuilder::new()
+        .root(ModuleType::CommandCenter, Vec3::ZERO)
+        .branch(|b| b.corridor(Direction::North).module(ModuleType::Laboratory))
+        .branch(|b| b.corridor(Direction::South).module(ModuleType::Storage))
+        .corridor(Direction::East)
+        .module(ModuleType::PowerPlant)
+        .build()?;
+
+    let (mut rl, thread) = raylib::init().size(1280, 720).title("Station host").build();
+    rl.set_target_fps(60);
+    let camera = Camera3D::perspective(
+        Vector3::new(30.0, 30.0, 30.0),
+        Vector3::zero(),
+        Vector3::new(0.0, 1.0, 0.0),
+        45.0,
+    );
+    let mut stepper = SimStepper::new();
+
+    while !rl.window_should_close() {
+        // The host's frame time drives the simulation at its fixed rate
+        for _ in 0..stepper.take_steps(rl.get_frame_time()) {
+            station.update(STEP_DT);
+            station.drain_events();
+        }
+
+        let telemetry = station.telemetry();
+        let mut d = rl.begin_drawing(&thread);
+        d.clear_background(Color::BLACK);
+        {
+            let mut d = d.begin_mode3D(camera);
+            draw_station(&mut d, &station);
+        }
+        let status = format!(
+            "Power {:.0}/{:.0} kW  Oxygen {:.1}%",
+            telemetry.power_consumption,
+            telemetry.power_generation,
+            telemetry.oxygen_level * 100.0
+        );
+        d.draw_text(&status, 10, 10, 20, Color::WHITE);
+    }
+    Ok(())
+}
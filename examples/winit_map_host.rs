@@ -0,0 +1,212 @@
+// A map-only host built on winit: it embeds the simulation and draws a flat
+// top-down map of it, not the game's 3D renderer, which runs in its own
+// raylib window. The library's map layout gives the shapes; the host fills
+// them into a software framebuffer. Hover a module to name it in the title.
+//
+//     cargo run --example winit_map_host
+use std::num::NonZeroU32;
+use std::rc::Rc;
+use std::time::Instant;
+use anyhow::Result;
+use glam::{Vec2, Vec3};
+use softbuffer::{Context, Surface};
+use space_station_3d::map::{MapLayout, MapView};
+use space_station_3d::station::{ModuleType, SpaceStation};
+use space_station_3d::station_builder::{Direction, StationBuilder};
+use space_station_3d::stepping::{SimStepper, STEP_DT};
+use winit::application::ApplicationHandler;
+use winit::dpi::LogicalSize;
+use winit::event::WindowEvent;
+use winit::event_loop::{ActiveEventLoop, EventLoop};
+use winit::window::{Window, WindowId};
+
+const BACKGROUND: u32 = 0x0008_0a10;
+const CONNECTION: u32 = 0x00c0_c0c0;
+
+// Window and surface only exist between resumed and exit
+struct Display {
+    window: Rc<Window>,
+    surface: Surface<Rc<Window>, Rc<Window>>,
+}
+
+struct Host {
+    station: SpaceStation,
+    layout: MapLayout,
+    stepper: SimStepper,
+    last_frame: Instant,
+    cursor: Option<Vec2>,
+    display: Option<Display>,
+}
+
+impl Host {
+    fn new(station: SpaceStation) -> Self {
+        Self {
+            layout: MapLayout::build(&station),
+            station,
+            stepper: SimStepper::new(),
+            last_frame: Instant::now(),
+            cursor: None,
+            display: None,
+        }
+    }
+
+    fn redraw(&mut self) {
+        // The host's frame time drives the simulation at its fixed rate
+        let now = Instant::now();
+        for _ in 0..self.stepper.take_steps((now - self.last_frame).as_secs_f32()) {
+            self.station.update(STEP_DT);
+            self.station.drain_events();
+        }
+        self.last_frame = now;
+
+        let Some(display) = &mut self.display else {
+            return;
+        };
+        let size = display.window.inner_size();
+        let (Some(width), Some(height)) = (NonZeroU32::new(size.width), NonZeroU32::new(size.height)) else {
+            return;
+        };
+        display.surface.resize(width, height).expect("failed to resize surface");
+        let mut frame = display.surface.buffer_mut().expect("failed to get framebuffer");
+        let mut canvas = Canvas {
+            pixels: &mut frame,
+            width: size.width as usize,
+            height: size.height as usize,
+        };
+        canvas.pixels.fill(BACKGROUND);
+
+        // Fit the whole station in the window, as the full-screen map does
+        let screen_size = Vec2::new(size.width as f32, size.height as f32);
+        let fit = (screen_size * 0.9 / self.layout.size().max(Vec2::splat(1.0))).min_element();
+        let view = MapView {
+            focus: self.layout.center(),
+            pixels_per_meter: fit,
+            screen_center: screen_size * 0.5,
+        };
+        let hovered = self.cursor.and_then(|cursor| self.layout.module_at(&view, cursor));
+
+        for &(from, to) in &self.layout.connections {
+            canvas.line(view.to_screen(from), view.to_screen(to), CONNECTION);
+        }
+        for module in &self.layout.modules {
+            let outline: Vec<Vec2> = module.outline.iter().map(|&point| view.to_screen(point)).collect();
+            let color = if hovered == Some(module.index) { module.color * 0.5 + 0.5 } else { module.color };
+            canvas.fill_convex(&outline, to_pixel(color));
+        }
+        frame.present().expect("failed to present framebuffer");
+
+        let telemetry = self.station.telemetry();
+        let hovered = hovered.map(|index| self.station.modules()[index].module_type);
+        display.window.set_title(&title(telemetry.power_consumption, telemetry.power_generation, telemetry.oxygen_level, hovered));
+    }
+}
+
+impl ApplicationHandler for Host {
+    fn resumed(&mut self, event_loop: &ActiveEventLoop) {
+        if self.display.is_some() {
+            return;
+        }
+        let attributes = Window::default_attributes()
+            .with_title("Station map")
+            .with_inner_size(LogicalSize::new(1280.0, 720.0));
+        let window = Rc::new(event_loop.create_window(attributes).expect("failed to create window"));
+        let context = Context::new(window.clone()).expect("failed to create softbuffer context");
+        let surface = Surface::new(&context, window.clone()).expect("failed to create surface");
+        self.display = Some(Display { window, surface });
+    }
+
+    fn window_event(&mut self, event_loop: &ActiveEventLoop, _window: WindowId, event: WindowEvent) {
+        match event {
+            WindowEvent::CloseRequested => event_loop.exit(),
+            WindowEvent::CursorMoved { position, .. } => self.cursor = Some(Vec2::new(position.x as f32, position.y as f32)),
+            WindowEvent::CursorLeft { .. } => self.cursor = None,
+            WindowEvent::RedrawRequested => self.redraw(),
+            _ => {}
+        }
+    }
+
+    // Redraw continuously so the simulation keeps running
+    fn about_to_wait(&mut self, _event_loop: &ActiveEventLoop) {
+        if let Some(display) = &self.display {
+            display.window.request_redraw();
+        }
+    }
+}
+
+struct Canvas<'a> {
+    pixels: &'a mut [u32],
+    width: usize,
+    height: usize,
+}
+
+impl Canvas<'_> {
+    fn plot(&mut self, x: i32, y: i32, color: u32) {
+        if x >= 0 && y >= 0 && (x as usize) < self.width && (y as usize) < self.height {
+            self.pixels[y as usize * self.width + x as usize] = color;
+        }
+    }
+
+    fn line(&mut self, from: Vec2, to: Vec2, color: u32) {
+        let steps = (to - from).abs().max_element().ceil().max(1.0) as i32;
+        for step in 0..=steps {
+            let point = from.lerp(to, step as f32 / steps as f32);
+            self.plot(point.x as i32, point.y as i32, color);
+        }
+    }
+
+    // Module outlines are convex, so a pixel is inside when it sits on the
+    // same side of every edge
+    fn fill_convex(&mut self, points: &[Vec2], color: u32) {
+        if points.len() < 3 {
+            return;
+        }
+        let min = points.iter().fold(Vec2::splat(f32::MAX), |min, &p| min.min(p)).max(Vec2::ZERO);
+        let max = points.iter().fold(Vec2::splat(f32::MIN), |max, &p| max.max(p));
+        let max = max.min(Vec2::new(self.width as f32 - 1.0, self.height as f32 - 1.0));
+        for y in min.y as i32..=max.y as i32 {
+            for x in min.x as i32..=max.x as i32 {
+                let pixel = Vec2::new(x as f32 + 0.5, y as f32 + 0.5);
+                let sides = points
+                    .iter()
+                    .zip(points.iter().cycle().skip(1))
+                    .map(|(&a, &b)| (b - a).perp_dot(pixel - a));
+                let (mut inside_left, mut inside_right) = (true, true);
+                for side in sides {
+                    inside_left &= side >= 0.0;
+                    inside_right &= side <= 0.0;
+                }
+                if inside_left || inside_right {
+                    self.plot(x, y, color);
+                }
+            }
+        }
+    }
+}
+
+// softbuffer pixels are 0RGB
+fn to_pixel(color: Vec3) -> u32 {
+    let [r, g, b] = (color.clamp(Vec3::ZERO, Vec3::ONE) * 255.0).to_array().map(|c| c as u32);
+    r << 16 | g << 8 | b
+}
+
+fn title(consumption: f32, generation: f32, oxygen: f32, hovered: Option<ModuleType>) -> String {
+    let status = format!("Power {:.0}/{:.0} kW  Oxygen {:.1}%", consumption, generation, oxygen * 100.0);
+    match hovered {
+        Some(module_type) => format!("Station map - {}  [{:?}]", status, module_type),
+        None => format!("Station map - {}", status),
+    }
+}
+
+fn main() -> Result<()> {
+    let station = StationBuilder::new()
+        .root(ModuleType::CommandCenter, Vec3::ZERO)
+        .branch(|b| b.corridor(Direction::North).module(ModuleType::Laboratory))
+        .branch(|b| b.corridor(Direction::South).module(ModuleType::Storage))
+        .corridor(Direction::East)
+        .module(ModuleType::PowerPlant)
+        .build()?;
+
+    let event_loop = EventLoop::new()?;
+    event_loop.run_app(&mut Host::new(station))?;
+    Ok(())
+}
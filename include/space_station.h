@@ -0,0 +1,103 @@
+#ifndef SPACE_STATION_H
+#define SPACE_STATION_H
+
+/* Generated by cbindgen from src/ffi.rs; don't edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define SS_ABI_VERSION 3
+
+#define SS_EVENT_KIND_LEN 32
+
+#define SS_EVENT_DESCRIPTION_LEN 128
+
+#define SS_MAX_PENDING_EVENTS 1024
+
+typedef enum SsElementState {
+  SsElementState_Failed = -2,
+  SsElementState_Invalid = -1,
+  SsElementState_Inactive = 0,
+  SsElementState_Active = 1,
+  SsElementState_Transitioning = 2,
+  SsElementState_Locked = 3,
+  SsElementState_Warning = 4,
+  SsElementState_Emergency = 5,
+  SsElementState_Malfunction = 6,
+} SsElementState;
+
+typedef enum SsInteraction {
+  SsInteraction_Accepted = 0,
+  SsInteraction_Refused = 1,
+  SsInteraction_Invalid = 2,
+  SsInteraction_Failed = 3,
+} SsInteraction;
+
+typedef struct SsStation SsStation;
+
+typedef struct SsStatus {
+  double mission_seconds;
+  float power_generation;
+  float power_consumption;
+  float grid_stability;
+  float oxygen_level;
+  float temperature;
+  float pressure;
+  float structural_integrity;
+  uint32_t module_count;
+  uint32_t sealed_modules;
+} SsStatus;
+
+typedef struct SsModuleId {
+  uint32_t slot;
+  uint32_t generation;
+} SsModuleId;
+
+typedef struct SsEvent {
+  char kind[SS_EVENT_KIND_LEN];
+  char description[SS_EVENT_DESCRIPTION_LEN];
+  int32_t severity;
+  bool has_position;
+  float position[3];
+} SsEvent;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+uint32_t ss_abi_version(void);
+
+struct SsStation *ss_station_create(uint64_t seed);
+
+void ss_station_destroy(struct SsStation *station);
+
+bool ss_station_tick(struct SsStation *station, uint32_t ticks);
+
+float ss_tick_seconds(void);
+
+bool ss_station_status(const struct SsStation *station, struct SsStatus *out);
+
+uint32_t ss_station_module_count(const struct SsStation *station);
+
+bool ss_station_module_id(const struct SsStation *station, uint32_t index, struct SsModuleId *out);
+
+uint32_t ss_station_element_count(const struct SsStation *station, struct SsModuleId module);
+
+enum SsElementState ss_station_element_state(const struct SsStation *station,
+                                             struct SsModuleId module,
+                                             uint32_t element);
+
+enum SsInteraction ss_station_interact(struct SsStation *station,
+                                       struct SsModuleId module,
+                                       uint32_t element);
+
+bool ss_station_poll_event(struct SsStation *station, struct SsEvent *out);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* SPACE_STATION_H */
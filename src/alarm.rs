@@ -0,0 +1,115 @@
+use std::f32::consts::TAU;
+
+// Times a second the cabin lights swell to full red and back
+const PULSE_RATE: f32 = 0.75;
+// How red they stay between swells
+const PULSE_FLOOR: f32 = 0.5;
+
+// The klaxon: a whoop rising from LOW to HIGH Hz, then a gap, over and over
+const SAMPLE_RATE: u32 = 22050;
+const WHOOP_LOW: f32 = 400.0;
+const WHOOP_HIGH: f32 = 900.0;
+const WHOOP_SECONDS: f32 = 0.9;
+const GAP_SECONDS: f32 = 0.3;
+const VOLUME: f32 = 0.4;
+
+// The station alarm. While it's sounding the cabin lights pulse red and
+// the klaxon repeats.
+#[derive(Debug, Default)]
+pub struct Alarm {
+    sounding: bool,
+    elapsed: f32,
+}
+
+impl Alarm {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Follows whether anything's raising it; true on the update it goes off
+    pub fn update(&mut self, raised: bool, delta_time: f32) -> bool {
+        let started = raised && !self.sounding;
+        if started {
+            self.elapsed = 0.0;
+        }
+        self.sounding = raised;
+        if raised {
+            self.elapsed += delta_time;
+        }
+        started
+    }
+
+    pub fn sounding(&self) -> bool {
+        self.sounding
+    }
+
+    // How far over to red the cabin lights are, 0 to 1
+    pub fn redness(&self) -> f32 {
+        if !self.sounding {
+            return 0.0;
+        }
+        let swell = 0.5 - 0.5 * (self.elapsed * PULSE_RATE * TAU).cos();
+        PULSE_FLOOR + (1.0 - PULSE_FLOOR) * swell
+    }
+}
+
+// One whoop and its gap as a 16-bit mono WAV, for the game to loop
+pub fn klaxon_wav() -> Vec<u8> {
+    let whoop = (WHOOP_SECONDS * SAMPLE_RATE as f32) as usize;
+    let gap = (GAP_SECONDS * SAMPLE_RATE as f32) as usize;
+    let mut phase = 0.0f32;
+    let samples: Vec<i16> = (0..whoop + gap)
+        .map(|i| {
+            if i >= whoop {
+                return 0;
+            }
+            let t = i as f32 / whoop as f32;
+            phase += (WHOOP_LOW + (WHOOP_HIGH - WHOOP_LOW) * t) / SAMPLE_RATE as f32;
+            // Square-ish for the harsh edge, faded in and out so it doesn't click
+            let tone = (phase * TAU).sin().clamp(-0.6, 0.6) / 0.6;
+            let fade = (t * 20.0).min((1.0 - t) * 20.0).min(1.0);
+            (tone * fade * VOLUME * i16::MAX as f32) as i16
+        })
+        .collect();
+
+    let data = samples.len() as u32 * 2;
+    let mut wav = Vec::with_capacity(44 + data as usize);
+    wav.extend_from_slice(b"RIFF");
+    wav.extend_from_slice(&(36 + data).to_le_bytes());
+    wav.extend_from_slice(b"WAVEfmt ");
+    wav.extend_from_slice(&16u32.to_le_bytes());
+    // PCM, one channel
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&1u16.to_le_bytes());
+    wav.extend_from_slice(&SAMPLE_RATE.to_le_bytes());
+    wav.extend_from_slice(&(SAMPLE_RATE * 2).to_le_bytes());
+    wav.extend_from_slice(&2u16.to_le_bytes());
+    wav.extend_from_slice(&16u16.to_le_bytes());
+    wav.extend_from_slice(b"data");
+    wav.extend_from_slice(&data.to_le_bytes());
+    for sample in samples {
+        wav.extend_from_slice(&sample.to_le_bytes());
+    }
+    wav
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynamic_lights::LightSet;
+
+    #[test]
+    fn the_alarm_pulses_the_lights_red_and_the_klaxon_is_a_wav() {
+        let mut alarm = Alarm::new();
+        assert!(alarm.update(true, 0.1));
+        assert!(!alarm.update(true, 0.1));
+        let mut lights = LightSet::new(1.0);
+        lights.alarm = alarm.redness();
+        assert!(lights.cabin().x > lights.cabin().y * 2.0);
+        alarm.update(false, 0.1);
+        assert_eq!(alarm.redness(), 0.0);
+        let wav = klaxon_wav();
+        assert_eq!(&wav[..4], b"RIFF");
+        assert_eq!(wav.len(), 44 + u32::from_le_bytes(wav[40..44].try_into().unwrap()) as usize);
+    }
+}
@@ -0,0 +1,293 @@
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+
+// Playtest analytics, from the [analytics] section of config.toml. Off
+// unless the player turns it on, and it only ever writes to a local file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AnalyticsConfig {
+    pub enabled: bool,
+    pub path: PathBuf,
+}
+
+impl Default for AnalyticsConfig {
+    fn default() -> Self {
+        Self { enabled: false, path: PathBuf::from("analytics.jsonl") }
+    }
+}
+
+// Drills, evacuations and incidents all go through the same funnel
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ScenarioKind {
+    Drill,
+    Evacuation,
+    Incident,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DeathCause {
+    // The suit ran out of air on a spacewalk
+    Suffocation,
+    // Crew who didn't make it off in an evacuation
+    LeftBehind,
+}
+
+impl DeathCause {
+    pub fn id(self) -> &'static str {
+        match self {
+            DeathCause::Suffocation => "suffocation",
+            DeathCause::LeftBehind => "left_behind",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum AnalyticsEvent {
+    ScenarioStarted { kind: ScenarioKind, scenario: String },
+    // `passed` by the scenario's own measure: a passing grade, enough souls off
+    ScenarioCompleted { kind: ScenarioKind, scenario: String, passed: bool, seconds: f32 },
+    Death { cause: DeathCause, count: u32 },
+    // Once a session, timed from its start
+    FirstRepair { seconds: f32 },
+}
+
+// A line of the file. Sessions are a random number, nothing that points
+// back at the player or their machine.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AnalyticsRecord {
+    pub session: u64,
+    // Seconds into the session
+    pub at: f32,
+    #[serde(flatten)]
+    pub event: AnalyticsEvent,
+}
+
+#[derive(Debug)]
+pub struct Analytics {
+    config: AnalyticsConfig,
+    session: u64,
+    elapsed: f32,
+    repaired: bool,
+}
+
+impl Analytics {
+    pub fn new(config: AnalyticsConfig) -> Self {
+        Self { config, session: rand::random(), elapsed: 0.0, repaired: false }
+    }
+
+    pub fn config(&self) -> &AnalyticsConfig {
+        &self.config
+    }
+
+    // Picks up an edited config; the session carries on
+    pub fn set_config(&mut self, config: AnalyticsConfig) {
+        self.config = config;
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        self.elapsed += delta_time;
+    }
+
+    // Appends the event to the file, if the player's opted in
+    pub fn record(&mut self, event: AnalyticsEvent) -> Result<()> {
+        if !self.config.enabled {
+            return Ok(());
+        }
+        let record = AnalyticsRecord { session: self.session, at: self.elapsed, event };
+        let line = serde_json::to_string(&record).context("failed to serialize analytics event")?;
+        let path = &self.config.path;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("failed to open {}", path.display()))?;
+        writeln!(file, "{}", line).with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    // Records the session's first finished repair; later ones are ignored
+    pub fn repaired(&mut self) -> Result<()> {
+        if self.repaired {
+            return Ok(());
+        }
+        self.repaired = true;
+        self.record(AnalyticsEvent::FirstRepair { seconds: self.elapsed })
+    }
+}
+
+// How often a scenario was started and finished, and how often passed
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Funnel {
+    pub started: u32,
+    pub completed: u32,
+    pub passed: u32,
+    total_seconds: f32,
+}
+
+impl Funnel {
+    pub fn average_seconds(&self) -> Option<f32> {
+        (self.completed > 0).then(|| self.total_seconds / self.completed as f32)
+    }
+}
+
+// Everything in a file of records, added up
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AnalyticsSummary {
+    pub sessions: usize,
+    pub funnels: BTreeMap<(ScenarioKind, String), Funnel>,
+    pub deaths: BTreeMap<DeathCause, u32>,
+    // One per session that got as far as a repair, shortest first
+    pub first_repairs: Vec<f32>,
+}
+
+impl AnalyticsSummary {
+    // Lines that don't parse (a half-written last line, say) are skipped
+    pub fn read(path: &Path) -> Result<Self> {
+        let source = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+        Ok(Self::from_records(source.lines().filter_map(|line| serde_json::from_str(line).ok())))
+    }
+
+    pub fn from_records(records: impl IntoIterator<Item = AnalyticsRecord>) -> Self {
+        let mut summary = Self::default();
+        let mut sessions = Vec::new();
+        for record in records {
+            if !sessions.contains(&record.session) {
+                sessions.push(record.session);
+            }
+            match record.event {
+                AnalyticsEvent::ScenarioStarted { kind, scenario } => {
+                    summary.funnels.entry((kind, scenario)).or_default().started += 1;
+                }
+                AnalyticsEvent::ScenarioCompleted { kind, scenario, passed, seconds } => {
+                    let funnel = summary.funnels.entry((kind, scenario)).or_default();
+                    funnel.completed += 1;
+                    funnel.passed += passed as u32;
+                    funnel.total_seconds += seconds;
+                }
+                AnalyticsEvent::Death { cause, count } => *summary.deaths.entry(cause).or_default() += count,
+                AnalyticsEvent::FirstRepair { seconds } => summary.first_repairs.push(seconds),
+            }
+        }
+        summary.sessions = sessions.len();
+        summary.first_repairs.sort_by(f32::total_cmp);
+        summary
+    }
+
+    pub fn median_first_repair(&self) -> Option<f32> {
+        self.first_repairs.get(self.first_repairs.len() / 2).copied()
+    }
+
+    pub fn to_text(&self) -> String {
+        let mut lines = vec![format!("{} sessions", self.sessions)];
+        for ((kind, scenario), funnel) in &self.funnels {
+            let average = funnel.average_seconds().map_or_else(|| "-".to_string(), |seconds| format!("{:.0}s", seconds));
+            lines.push(format!(
+                "{:?} {}: {} started, {} finished, {} passed, {} average",
+                kind, scenario, funnel.started, funnel.completed, funnel.passed, average
+            ));
+        }
+        for (cause, count) in &self.deaths {
+            lines.push(format!("deaths by {}: {}", cause.id(), count));
+        }
+        if let Some(median) = self.median_first_repair() {
+            lines.push(format!("first repair after {:.0}s (median of {})", median, self.first_repairs.len()));
+        }
+        lines.join("\n")
+    }
+
+    // One row per scenario, then deaths and the repair timing, for a spreadsheet
+    pub fn export_csv(&self, path: &Path) -> Result<()> {
+        let mut csv = String::from("metric,kind,name,started,completed,passed,average_seconds\n");
+        for ((kind, scenario), funnel) in &self.funnels {
+            let average = funnel.average_seconds().map_or_else(String::new, |seconds| format!("{:.1}", seconds));
+            csv.push_str(&format!(
+                "scenario,{:?},\"{}\",{},{},{},{}\n",
+                kind,
+                scenario.replace('"', "\"\""),
+                funnel.started,
+                funnel.completed,
+                funnel.passed,
+                average
+            ));
+        }
+        for (cause, count) in &self.deaths {
+            csv.push_str(&format!("death,,{},{},,,\n", cause.id(), count));
+        }
+        if let Some(median) = self.median_first_repair() {
+            csv.push_str(&format!("first_repair,,median,{},,,{:.1}\n", self.first_repairs.len(), median));
+        }
+        fs::write(path, csv).with_context(|| format!("failed to write {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn analytics_stay_off_until_opted_in_and_add_up_into_funnels() {
+        let path = std::env::temp_dir().join(format!("analytics_{}.jsonl", std::process::id()));
+        let started = |scenario: &str| AnalyticsEvent::ScenarioStarted {
+            kind: ScenarioKind::Drill,
+            scenario: scenario.to_string(),
+        };
+        let mut analytics = Analytics::new(AnalyticsConfig { enabled: false, path: path.clone() });
+        analytics.record(started("Fire")).unwrap();
+        analytics.repaired().unwrap();
+        assert!(!path.exists());
+
+        // Two sessions: one passes the drill, the other gives up on it and
+        // loses someone on a spacewalk. Only the first repair counts.
+        let config = AnalyticsConfig { enabled: true, path: path.clone() };
+        let mut first = Analytics::new(config.clone());
+        first.update(30.0);
+        first.record(started("Fire")).unwrap();
+        first.update(45.0);
+        first.repaired().unwrap();
+        first.update(60.0);
+        first.repaired().unwrap();
+        first
+            .record(AnalyticsEvent::ScenarioCompleted {
+                kind: ScenarioKind::Drill,
+                scenario: "Fire".to_string(),
+                passed: true,
+                seconds: 80.0,
+            })
+            .unwrap();
+        let mut second = Analytics::new(config);
+        second.record(started("Fire")).unwrap();
+        second.record(AnalyticsEvent::Death { cause: DeathCause::Suffocation, count: 1 }).unwrap();
+        second.update(120.0);
+        second.repaired().unwrap();
+
+        // Lines carry no more than a session number, the time and the event
+        let source = fs::read_to_string(&path).unwrap();
+        assert_eq!(source.lines().count(), 6);
+        assert!(source.lines().next().unwrap().contains(r#""event":"scenario_started","kind":"drill","scenario":"Fire""#));
+        let summary = AnalyticsSummary::read(&path).unwrap();
+        assert_eq!(summary.sessions, 2);
+        let funnel = &summary.funnels[&(ScenarioKind::Drill, "Fire".to_string())];
+        assert_eq!((funnel.started, funnel.completed, funnel.passed), (2, 1, 1));
+        assert_eq!(funnel.average_seconds(), Some(80.0));
+        assert_eq!(summary.deaths[&DeathCause::Suffocation], 1);
+        assert_eq!(summary.first_repairs, vec![75.0, 120.0]);
+
+        let csv = path.with_extension("csv");
+        summary.export_csv(&csv).unwrap();
+        let exported = fs::read_to_string(&csv).unwrap();
+        assert!(exported.contains("scenario,Drill,\"Fire\",2,1,1,80.0"));
+        assert!(exported.contains("death,,suffocation,1,,,"));
+        fs::remove_file(&path).unwrap();
+        fs::remove_file(&csv).unwrap();
+    }
+}
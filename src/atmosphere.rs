@@ -0,0 +1,110 @@
+use serde::{Deserialize, Serialize};
+
+// Sea-level air, which every module is filled with to start
+pub const NOMINAL_OXYGEN: f32 = 0.21;
+pub const NOMINAL_NITROGEN: f32 = 0.79;
+pub const NOMINAL_TEMPERATURE: f32 = 293.15;
+
+// The air in one module, as partial pressures in atm. Amounts of gas are
+// partial pressure times the module's volume, so the same gas spreads
+// thinner in a bigger module.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Atmosphere {
+    pub oxygen: f32,
+    pub co2: f32,
+    pub nitrogen: f32,
+    // Kelvin
+    pub temperature: f32,
+}
+
+impl Atmosphere {
+    pub fn nominal() -> Self {
+        Self {
+            oxygen: NOMINAL_OXYGEN,
+            co2: 0.0,
+            nitrogen: NOMINAL_NITROGEN,
+            temperature: NOMINAL_TEMPERATURE,
+        }
+    }
+
+    pub fn pressure(&self) -> f32 {
+        self.oxygen + self.co2 + self.nitrogen
+    }
+
+    // Oxygen as a fraction of a normal supply; what the HUD calls "oxygen"
+    pub fn oxygen_level(&self) -> f32 {
+        self.oxygen / NOMINAL_OXYGEN
+    }
+
+    // Share of the air that's CO2, 0 in a vacuum
+    pub fn co2_fraction(&self) -> f32 {
+        let pressure = self.pressure();
+        if pressure > 0.0 {
+            self.co2 / pressure
+        } else {
+            0.0
+        }
+    }
+
+    // Loses `fraction` of every gas, as through a hull breach
+    pub fn vent(&mut self, fraction: f32) {
+        let keep = (1.0 - fraction).clamp(0.0, 1.0);
+        self.oxygen *= keep;
+        self.co2 *= keep;
+        self.nitrogen *= keep;
+    }
+
+    // Breathing: oxygen in, the same amount of CO2 out
+    pub fn breathe(&mut self, amount: f32) {
+        let amount = amount.min(self.oxygen);
+        self.oxygen -= amount;
+        self.co2 += amount;
+    }
+
+    // Scrubbing: CO2 split back into oxygen, up to `amount`
+    pub fn scrub(&mut self, amount: f32) {
+        let amount = amount.min(self.co2);
+        self.co2 -= amount;
+        self.oxygen += amount;
+    }
+
+    // Tops oxygen and nitrogen up towards sea level from the station's
+    // tanks, by at most `amount` of each
+    pub fn replenish(&mut self, amount: f32) {
+        self.oxygen += amount.min((NOMINAL_OXYGEN - self.oxygen).max(0.0));
+        self.nitrogen += amount.min((NOMINAL_NITROGEN - self.nitrogen).max(0.0));
+    }
+}
+
+// Lets air through an open connection between two modules of the given
+// volumes. The pressures close `fraction` of the gap to where they'd settle;
+// gas carries its mix and temperature from the side it leaves.
+pub fn equalize(a: &mut Atmosphere, volume_a: f32, b: &mut Atmosphere, volume_b: f32, fraction: f32) {
+    let settled = (a.pressure() * volume_a + b.pressure() * volume_b) / (volume_a + volume_b);
+    // Gas amount moving from a to b; negative flows the other way
+    let flow = (a.pressure() - settled) * volume_a * fraction.clamp(0.0, 1.0);
+    if flow > 0.0 {
+        transfer(a, volume_a, b, volume_b, flow);
+    } else if flow < 0.0 {
+        transfer(b, volume_b, a, volume_a, -flow);
+    }
+}
+
+fn transfer(from: &mut Atmosphere, from_volume: f32, to: &mut Atmosphere, to_volume: f32, amount: f32) {
+    let pressure = from.pressure();
+    if pressure <= 0.0 {
+        return;
+    }
+    let share = (amount / (pressure * from_volume)).min(1.0);
+    let to_amount = to.pressure() * to_volume;
+    to.temperature = (to.temperature * to_amount + from.temperature * amount) / (to_amount + amount);
+    for (source, dest) in [
+        (&mut from.oxygen, &mut to.oxygen),
+        (&mut from.co2, &mut to.co2),
+        (&mut from.nitrogen, &mut to.nitrogen),
+    ] {
+        let moved = *source * share;
+        *source -= moved;
+        *dest += moved * from_volume / to_volume;
+    }
+}
@@ -0,0 +1,67 @@
+use crate::benchmark::Flythrough;
+use crate::fly_camera::CameraPose;
+use crate::station::SpaceStation;
+
+// One lap of the station; the tour loops until someone touches a control
+const TOUR_SECONDS: f32 = 90.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttractChange {
+    Started,
+    Stopped,
+}
+
+#[derive(Debug)]
+struct Tour {
+    path: Flythrough,
+    elapsed: f32,
+}
+
+// Screensaver: after the configured idle time the camera tours the station
+// on the benchmark's spline path with the HUD hidden, until any input
+#[derive(Debug)]
+pub struct AttractMode {
+    // 0 turns it off
+    pub idle_seconds: f32,
+    idle: f32,
+    tour: Option<Tour>,
+}
+
+impl AttractMode {
+    pub fn new(idle_seconds: f32) -> Self {
+        Self {
+            idle_seconds,
+            idle: 0.0,
+            tour: None,
+        }
+    }
+
+    pub fn active(&self) -> bool {
+        self.tour.is_some()
+    }
+
+    // Call every frame with whether the player did anything
+    pub fn update(&mut self, station: &SpaceStation, any_input: bool, delta_time: f32) -> Option<AttractChange> {
+        if any_input {
+            self.idle = 0.0;
+            return self.tour.take().map(|_| AttractChange::Stopped);
+        }
+        if let Some(tour) = &mut self.tour {
+            tour.elapsed = (tour.elapsed + delta_time) % tour.path.duration();
+            return None;
+        }
+        self.idle += delta_time;
+        if self.idle_seconds <= 0.0 || self.idle < self.idle_seconds {
+            return None;
+        }
+        self.tour = Some(Tour {
+            path: Flythrough::through(station, TOUR_SECONDS),
+            elapsed: 0.0,
+        });
+        Some(AttractChange::Started)
+    }
+
+    pub fn pose(&self) -> Option<CameraPose> {
+        self.tour.as_ref().map(|tour| tour.path.pose(tour.elapsed))
+    }
+}
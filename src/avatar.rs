@@ -0,0 +1,342 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use glam::{Vec3, Vec4};
+use serde::{Deserialize, Serialize};
+use crate::chat::Emote;
+use crate::material::Material;
+
+// Longest name tag shown; longer ones are cut short
+pub const MAX_TAG_LENGTH: usize = 24;
+// Name tags float this far above the helmet
+pub const TAG_HEIGHT: f32 = 2.05;
+
+// Suit colours: the fabric, the trim round the joints and the accent stripes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SuitScheme {
+    #[default]
+    Orange,
+    White,
+    Navy,
+    Crimson,
+    Olive,
+    Graphite,
+}
+
+impl SuitScheme {
+    pub const ALL: [SuitScheme; 6] = [
+        SuitScheme::Orange,
+        SuitScheme::White,
+        SuitScheme::Navy,
+        SuitScheme::Crimson,
+        SuitScheme::Olive,
+        SuitScheme::Graphite,
+    ];
+
+    pub fn id(self) -> &'static str {
+        match self {
+            SuitScheme::Orange => "orange",
+            SuitScheme::White => "white",
+            SuitScheme::Navy => "navy",
+            SuitScheme::Crimson => "crimson",
+            SuitScheme::Olive => "olive",
+            SuitScheme::Graphite => "graphite",
+        }
+    }
+
+    pub fn parse(id: &str) -> Option<Self> {
+        SuitScheme::ALL.into_iter().find(|scheme| scheme.id() == id)
+    }
+
+    // Fabric, trim and accent
+    pub fn colors(self) -> (Vec3, Vec3, Vec3) {
+        match self {
+            SuitScheme::Orange => (Vec3::new(0.95, 0.45, 0.1), Vec3::new(0.85, 0.85, 0.85), Vec3::new(0.2, 0.2, 0.25)),
+            SuitScheme::White => (Vec3::new(0.92, 0.92, 0.9), Vec3::new(0.5, 0.52, 0.55), Vec3::new(0.15, 0.35, 0.8)),
+            SuitScheme::Navy => (Vec3::new(0.12, 0.18, 0.4), Vec3::new(0.75, 0.75, 0.78), Vec3::new(0.95, 0.75, 0.15)),
+            SuitScheme::Crimson => (Vec3::new(0.65, 0.1, 0.12), Vec3::new(0.25, 0.25, 0.28), Vec3::new(0.95, 0.95, 0.95)),
+            SuitScheme::Olive => (Vec3::new(0.4, 0.45, 0.25), Vec3::new(0.3, 0.28, 0.22), Vec3::new(0.9, 0.6, 0.2)),
+            SuitScheme::Graphite => (Vec3::new(0.22, 0.23, 0.25), Vec3::new(0.1, 0.1, 0.12), Vec3::new(0.2, 0.85, 0.75)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Helmet {
+    // Hard shell with a clear visor
+    #[default]
+    Standard,
+    // Clear all round
+    Bubble,
+    // Gold-tinted sun visor down
+    SunVisor,
+    // Standard with a lamp on each side
+    Lamps,
+}
+
+impl Helmet {
+    pub const ALL: [Helmet; 4] = [Helmet::Standard, Helmet::Bubble, Helmet::SunVisor, Helmet::Lamps];
+
+    pub fn id(self) -> &'static str {
+        match self {
+            Helmet::Standard => "standard",
+            Helmet::Bubble => "bubble",
+            Helmet::SunVisor => "sun_visor",
+            Helmet::Lamps => "lamps",
+        }
+    }
+
+    pub fn parse(id: &str) -> Option<Self> {
+        Helmet::ALL.into_iter().find(|helmet| helmet.id() == id)
+    }
+
+    // Visor colour and how much of it shows through
+    fn visor(self) -> (Vec3, f32) {
+        match self {
+            Helmet::Standard | Helmet::Lamps => (Vec3::new(0.6, 0.75, 0.85), 0.5),
+            Helmet::Bubble => (Vec3::new(0.8, 0.9, 1.0), 0.25),
+            Helmet::SunVisor => (Vec3::new(0.85, 0.65, 0.2), 0.9),
+        }
+    }
+}
+
+// The patch on the shoulder, and the colour of the name tag with it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    Commander,
+    Engineer,
+    Medic,
+    Scientist,
+    Pilot,
+    #[default]
+    Visitor,
+}
+
+impl Role {
+    pub const ALL: [Role; 6] = [Role::Commander, Role::Engineer, Role::Medic, Role::Scientist, Role::Pilot, Role::Visitor];
+
+    pub fn id(self) -> &'static str {
+        match self {
+            Role::Commander => "commander",
+            Role::Engineer => "engineer",
+            Role::Medic => "medic",
+            Role::Scientist => "scientist",
+            Role::Pilot => "pilot",
+            Role::Visitor => "visitor",
+        }
+    }
+
+    pub fn parse(id: &str) -> Option<Self> {
+        Role::ALL.into_iter().find(|role| role.id() == id)
+    }
+
+    pub fn color(self) -> Vec3 {
+        match self {
+            Role::Commander => Vec3::new(0.95, 0.8, 0.2),
+            Role::Engineer => Vec3::new(0.95, 0.5, 0.1),
+            Role::Medic => Vec3::new(0.9, 0.15, 0.15),
+            Role::Scientist => Vec3::new(0.2, 0.75, 0.4),
+            Role::Pilot => Vec3::new(0.25, 0.55, 0.95),
+            Role::Visitor => Vec3::new(0.7, 0.7, 0.7),
+        }
+    }
+
+    // Stitched on the patch
+    pub fn glyph(self) -> &'static str {
+        match self {
+            Role::Commander => "CDR",
+            Role::Engineer => "ENG",
+            Role::Medic => "MED",
+            Role::Scientist => "SCI",
+            Role::Pilot => "PLT",
+            Role::Visitor => "VIS",
+        }
+    }
+}
+
+// How a player's avatar looks, as they picked it. Kept in their profile and
+// sent to everyone else with the station state.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AvatarLook {
+    pub suit: SuitScheme,
+    pub helmet: Helmet,
+    pub role: Role,
+    // Shown over their head; their player name if empty
+    pub name_tag: String,
+}
+
+impl AvatarLook {
+    pub fn set_name_tag(&mut self, tag: &str) {
+        self.name_tag = tag.trim().chars().take(MAX_TAG_LENGTH).collect();
+    }
+
+    pub fn tag<'a>(&'a self, name: &'a str) -> &'a str {
+        if self.name_tag.is_empty() { name } else { &self.name_tag }
+    }
+
+    // The suit's materials with this look's colours over the stock finishes
+    pub fn materials(&self) -> AvatarMaterials {
+        let (fabric, trim, accent) = self.suit.colors();
+        let (visor, opacity) = self.helmet.visor();
+        let mut visor = Material::create_metal(visor);
+        visor.alpha = opacity;
+        AvatarMaterials {
+            suit: Material::new(Vec4::ONE, 0.0, 0.9, 1.0).with_albedo(fabric),
+            trim: Material::new(Vec4::ONE, 0.6, 0.4, 1.0).with_albedo(trim),
+            accent: Material::new(Vec4::ONE, 0.0, 0.7, 1.0).with_albedo(accent),
+            visor,
+        }
+    }
+
+    // The role patch on the left shoulder and the accent stripe across the
+    // chest, in the avatar's own space (feet at the origin, facing -Z)
+    pub fn decals(&self) -> Vec<Decal> {
+        let (_, _, accent) = self.suit.colors();
+        vec![
+            Decal {
+                offset: Vec3::new(-0.3, 1.35, 0.0),
+                normal: Vec3::NEG_X,
+                size: 0.12,
+                color: self.role.color(),
+                text: Some(self.role.glyph()),
+            },
+            Decal { offset: Vec3::new(0.0, 1.2, -0.31), normal: Vec3::NEG_Z, size: 0.2, color: accent, text: None },
+        ]
+    }
+}
+
+#[derive(Debug)]
+pub struct AvatarMaterials {
+    pub suit: Material,
+    pub trim: Material,
+    pub accent: Material,
+    pub visor: Material,
+}
+
+// Something flat stuck on the suit
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decal {
+    pub offset: Vec3,
+    pub normal: Vec3,
+    pub size: f32,
+    pub color: Vec3,
+    pub text: Option<&'static str>,
+}
+
+// Shoulders, and where the hands hang at rest, in the avatar's own space
+pub const SHOULDERS: [Vec3; 2] = [Vec3::new(-0.38, 1.4, 0.0), Vec3::new(0.38, 1.4, 0.0)];
+const REST: [Vec3; 2] = [Vec3::new(-0.42, 0.85, 0.0), Vec3::new(0.42, 0.85, 0.0)];
+
+// Left and right hand for a gesture from `Chat::gesture`, eased in and back
+// out over its length
+pub fn hands(gesture: Option<(Emote, f32)>) -> [Vec3; 2] {
+    let Some((emote, progress)) = gesture else {
+        return REST;
+    };
+    let raise = (progress.clamp(0.0, 1.0) * std::f32::consts::PI).sin();
+    let [left, right] = REST;
+    let posed = match emote {
+        Emote::Wave => {
+            let sway = (progress * std::f32::consts::TAU * 3.0).sin() * 0.15;
+            [left, Vec3::new(0.55 + sway, 1.9, 0.0)]
+        }
+        Emote::Point => [left, Vec3::new(0.35, 1.4, -0.65)],
+        Emote::ThumbsUp => [left, Vec3::new(0.3, 1.25, -0.4)],
+        Emote::Shrug => [Vec3::new(-0.55, 1.15, -0.15), Vec3::new(0.55, 1.15, -0.15)],
+        Emote::Salute => [left, Vec3::new(0.12, 1.72, -0.18)],
+        Emote::Cheer => [Vec3::new(-0.45, 2.05, 0.0), Vec3::new(0.45, 2.05, 0.0)],
+    };
+    [left.lerp(posed[0], raise), right.lerp(posed[1], raise)]
+}
+
+// Per-user directory player profiles are kept in
+pub fn profile_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("space_station_3d")
+        .join("profiles")
+}
+
+// File names are the player name with anything odd swapped out
+fn profile_path(dir: &Path, name: &str) -> PathBuf {
+    let file: String = name.chars().map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' }).collect();
+    dir.join(format!("{}.toml", file))
+}
+
+// A player's look from their profile, the default look if they haven't one
+pub fn load_look(dir: &Path, name: &str) -> Result<AvatarLook> {
+    let path = profile_path(dir, name);
+    if !path.exists() {
+        return Ok(AvatarLook::default());
+    }
+    let source = fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+    toml::from_str(&source).with_context(|| format!("invalid profile {}", path.display()))
+}
+
+pub fn save_look(dir: &Path, name: &str, look: &AvatarLook) -> Result<()> {
+    fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let path = profile_path(dir, name);
+    let contents = toml::to_string(look).context("failed to serialize profile")?;
+    fs::write(&path, contents).with_context(|| format!("failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::replication::{Interest, ReplicaState, Replicator};
+    use crate::station::SpaceStation;
+
+    #[test]
+    fn avatar_looks_are_kept_per_profile_and_sent_to_every_client() {
+        let dir = std::env::temp_dir().join(format!("profiles_{}", std::process::id()));
+        assert_eq!(load_look(&dir, "ana").unwrap(), AvatarLook::default());
+        let mut look = AvatarLook { suit: SuitScheme::Navy, helmet: Helmet::SunVisor, role: Role::Medic, ..AvatarLook::default() };
+        look.set_name_tag("  Dr Ana, on call all week long  ");
+        assert_eq!((look.tag("ana"), AvatarLook::default().tag("ana")), ("Dr Ana, on call all week", "ana"));
+        save_look(&dir, "ana", &look).unwrap();
+        assert_eq!(load_look(&dir, "ana").unwrap(), look);
+        fs::remove_dir_all(&dir).unwrap();
+
+        // Colours go on as overrides of the stock finishes, the patch in the
+        // role's colour
+        let materials = look.materials();
+        assert_eq!(materials.suit.albedo.truncate(), SuitScheme::Navy.colors().0);
+        assert_eq!(materials.suit.roughness, AvatarLook::default().materials().suit.roughness);
+        assert!(materials.visor.alpha > AvatarLook::default().materials().visor.alpha);
+        assert!(look.decals().iter().any(|decal| decal.color == Role::Medic.color() && decal.text == Some("MED")));
+
+        // Even a client that can see no modules hears about every look, and
+        // after that only when one changes
+        let station = SpaceStation::create_default_layout();
+        let mut replicator = Replicator::new();
+        replicator.set_avatar("ana", look.clone());
+        replicator.connect("ben", Interest { position: Vec3::splat(500.0), radius: 1.0 });
+        let mut client = ReplicaState::default();
+        let mut send = |replicator: &mut Replicator, tick: u64| {
+            let (name, packet) = replicator.update(tick, &station.snapshot()).unwrap().remove(0);
+            replicator.ack(&name, packet.tick);
+            client = client.apply(&packet).unwrap();
+            (packet, client.clone())
+        };
+        let (_, state) = send(&mut replicator, 3);
+        assert_eq!(state.avatar("ana"), Some(look.clone()));
+        assert!(state.avatar("ben").is_none());
+        let (quiet, _) = send(&mut replicator, 6);
+        assert!(quiet.globals.is_none_or(|globals| globals.get("avatars").is_none()));
+        look.helmet = Helmet::Bubble;
+        replicator.set_avatar("ana", look.clone());
+        let (changed, state) = send(&mut replicator, 9);
+        assert_eq!(changed.globals.unwrap()["avatars"]["ana"], serde_json::json!({ "helmet": "bubble" }));
+        assert_eq!(state.avatar("ana"), Some(look));
+
+        // Gestures ease the hands out and back
+        assert_eq!(hands(None), hands(Some((Emote::Salute, 0.0))));
+        let [_, raised] = hands(Some((Emote::Cheer, 0.5)));
+        assert!(raised.y > SHOULDERS[1].y);
+        assert!(hands(Some((Emote::Cheer, 1.0)))[1].distance(hands(None)[1]) < 1e-4);
+    }
+}
@@ -0,0 +1,282 @@
+use glam::{Quat, Vec3};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+#[derive(Debug, Clone)]
+pub struct GalacticBandConfig {
+    // Normal of the great circle the band follows
+    pub axis: Vec3,
+    pub width: f32,
+    pub color: Vec3,
+    pub brightness: f32,
+    pub puff_count: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct BackdropConfig {
+    pub seed: u64,
+    pub distance: f32,
+    pub nebula_count: u32,
+    pub puffs_per_nebula: u32,
+    pub nebula_palette: Vec<Vec3>,
+    pub nebula_opacity: f32,
+    pub galactic_band: Option<GalacticBandConfig>,
+    // Mean seconds between comets; zero disables them
+    pub comet_interval: f32,
+}
+
+impl Default for BackdropConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0x4e45_4255,
+            distance: 450.0,
+            nebula_count: 3,
+            puffs_per_nebula: 40,
+            nebula_palette: vec![
+                Vec3::new(0.55, 0.25, 0.7),
+                Vec3::new(0.2, 0.35, 0.8),
+                Vec3::new(0.8, 0.3, 0.4),
+            ],
+            nebula_opacity: 0.12,
+            galactic_band: Some(GalacticBandConfig {
+                axis: Vec3::new(0.3, 1.0, 0.2).normalize(),
+                width: 0.18,
+                color: Vec3::new(0.9, 0.85, 0.75),
+                brightness: 0.08,
+                puff_count: 300,
+            }),
+            comet_interval: 90.0,
+        }
+    }
+}
+
+// Every name `preset` knows
+pub const PRESETS: [&str; 4] = ["default", "emerald_drift", "galactic_core", "dark_sky"];
+
+impl BackdropConfig {
+    // Named presets so scenarios can give each orbit a distinct sky
+    pub fn preset(name: &str) -> Option<Self> {
+        let base = Self::default();
+        match name {
+            "default" => Some(base),
+            "emerald_drift" => Some(Self {
+                seed: 0x454d_4552,
+                nebula_count: 5,
+                nebula_palette: vec![Vec3::new(0.2, 0.7, 0.5), Vec3::new(0.1, 0.4, 0.6)],
+                nebula_opacity: 0.16,
+                comet_interval: 45.0,
+                ..base
+            }),
+            "galactic_core" => Some(Self {
+                seed: 0x434f_5245,
+                nebula_count: 2,
+                nebula_palette: vec![Vec3::new(0.9, 0.6, 0.3), Vec3::new(0.7, 0.3, 0.2)],
+                galactic_band: Some(GalacticBandConfig {
+                    axis: Vec3::new(0.0, 1.0, 0.4).normalize(),
+                    width: 0.3,
+                    color: Vec3::new(1.0, 0.85, 0.6),
+                    brightness: 0.14,
+                    puff_count: 600,
+                }),
+                ..base
+            }),
+            "dark_sky" => Some(Self {
+                nebula_count: 0,
+                galactic_band: None,
+                comet_interval: 0.0,
+                ..base
+            }),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Billboard {
+    pub position: Vec3,
+    pub size: f32,
+    pub color: Vec3,
+    pub alpha: f32,
+}
+
+#[derive(Debug, Clone)]
+struct Puff {
+    direction: Vec3,
+    distance: f32,
+    size: f32,
+    color: Vec3,
+    alpha: f32,
+    camera_follow: f32,
+}
+
+#[derive(Debug, Clone)]
+struct Comet {
+    direction: Vec3,
+    axis: Vec3,
+    angular_speed: f32,
+    age: f32,
+    lifetime: f32,
+}
+
+#[derive(Debug)]
+pub struct Backdrop {
+    pub config: BackdropConfig,
+    puffs: Vec<Puff>,
+    comets: Vec<Comet>,
+    rng: StdRng,
+    next_comet: f32,
+    attitude: Quat,
+}
+
+impl Backdrop {
+    pub fn generate(config: BackdropConfig) -> Self {
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let mut puffs = Vec::new();
+
+        // Nebulae: clusters of large soft puffs spread over a few parallax
+        // layers. Without colours there are none.
+        let nebulae = if config.nebula_palette.is_empty() { 0 } else { config.nebula_count };
+        for i in 0..nebulae {
+            let center = random_direction(&mut rng);
+            let color = config.nebula_palette[i as usize % config.nebula_palette.len()];
+            for _ in 0..config.puffs_per_nebula {
+                let offset = random_direction(&mut rng) * rng.gen_range(0.0..0.25);
+                let layer = rng.gen_range(0..3) as f32;
+                puffs.push(Puff {
+                    direction: (center + offset).normalize(),
+                    distance: config.distance * (0.8 + 0.1 * layer),
+                    size: rng.gen_range(20.0..70.0),
+                    color: color * rng.gen_range(0.7..1.2),
+                    alpha: config.nebula_opacity * rng.gen_range(0.4..1.0),
+                    camera_follow: 0.95 + 0.025 * layer,
+                });
+            }
+        }
+
+        // Galactic band: puffs scattered around a great circle, if it has any width
+        if let Some(band) = config.galactic_band.as_ref().filter(|band| band.width > 0.0) {
+            let axis = band.axis.normalize_or_zero();
+            let tangent = axis.any_orthonormal_vector();
+            for _ in 0..band.puff_count {
+                let angle = rng.gen_range(0.0..std::f32::consts::TAU);
+                let spread = rng.gen_range(-band.width..band.width) * rng.gen::<f32>();
+                let along = Quat::from_axis_angle(axis, angle) * tangent;
+                puffs.push(Puff {
+                    direction: (along + axis * spread).normalize(),
+                    distance: config.distance,
+                    size: rng.gen_range(10.0..35.0),
+                    color: band.color,
+                    alpha: band.brightness * (1.0 - spread.abs() / band.width),
+                    camera_follow: 1.0,
+                });
+            }
+        }
+
+        let next_comet = next_comet_delay(&mut rng, config.comet_interval);
+
+        Self {
+            config,
+            puffs,
+            comets: Vec::new(),
+            rng,
+            next_comet,
+            attitude: Quat::IDENTITY,
+        }
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        for comet in &mut self.comets {
+            comet.age += delta_time;
+            comet.direction = Quat::from_axis_angle(comet.axis, comet.angular_speed * delta_time) * comet.direction;
+        }
+        self.comets.retain(|comet| comet.age < comet.lifetime);
+
+        if self.config.comet_interval > 0.0 {
+            self.next_comet -= delta_time;
+            if self.next_comet <= 0.0 {
+                let direction = random_direction(&mut self.rng);
+                let axis = direction.cross(random_direction(&mut self.rng)).normalize_or_zero();
+                self.comets.push(Comet {
+                    direction,
+                    axis,
+                    angular_speed: self.rng.gen_range(0.01..0.04),
+                    age: 0.0,
+                    lifetime: self.rng.gen_range(20.0..40.0),
+                });
+                self.next_comet = next_comet_delay(&mut self.rng, self.config.comet_interval);
+            }
+        }
+    }
+
+    pub fn billboards(&self, camera_position: Vec3) -> Vec<Billboard> {
+        let rotation = self.attitude.inverse();
+        let mut billboards: Vec<Billboard> = self
+            .puffs
+            .iter()
+            .map(|puff| Billboard {
+                position: camera_position * puff.camera_follow + rotation * puff.direction * puff.distance,
+                size: puff.size,
+                color: puff.color,
+                alpha: puff.alpha,
+            })
+            .collect();
+
+        // Comets: a bright head with a tail trailing back along the path
+        for comet in &self.comets {
+            let fade = (comet.age.min(comet.lifetime - comet.age) / 3.0).clamp(0.0, 1.0);
+            for segment in 0..12 {
+                let back = Quat::from_axis_angle(comet.axis, -(segment as f32) * 0.004);
+                let t = segment as f32 / 12.0;
+                billboards.push(Billboard {
+                    position: camera_position + rotation * (back * comet.direction) * self.config.distance * 0.9,
+                    size: 3.0 * (1.0 - t * 0.6),
+                    color: Vec3::new(0.8, 0.9, 1.0),
+                    alpha: fade * (1.0 - t),
+                });
+            }
+        }
+
+        billboards
+    }
+}
+
+fn next_comet_delay(rng: &mut StdRng, mean: f32) -> f32 {
+    if mean <= 0.0 {
+        return f32::INFINITY;
+    }
+    mean * rng.gen_range(0.5..1.5)
+}
+
+fn random_direction(rng: &mut StdRng) -> Vec3 {
+    let z: f32 = rng.gen_range(-1.0..1.0);
+    let theta: f32 = rng.gen_range(0.0..std::f32::consts::TAU);
+    let r = (1.0 - z * z).sqrt();
+    Vec3::new(r * theta.cos(), z, r * theta.sin())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_named_preset_generates() {
+        for name in PRESETS {
+            let config = BackdropConfig::preset(name).unwrap_or_else(|| panic!("no preset {name}"));
+            Backdrop::generate(config).billboards(Vec3::ZERO);
+        }
+    }
+
+    #[test]
+    fn a_bare_palette_or_a_zero_width_band_just_leaves_it_out() {
+        let mut config = BackdropConfig { nebula_palette: Vec::new(), ..BackdropConfig::default() };
+        if let Some(band) = &mut config.galactic_band {
+            band.width = 0.0;
+        }
+        assert!(Backdrop::generate(config.clone()).billboards(Vec3::ZERO).is_empty());
+
+        if let Some(band) = &mut config.galactic_band {
+            band.width = -0.2;
+        }
+        assert!(Backdrop::generate(config).billboards(Vec3::ZERO).is_empty());
+    }
+}
@@ -0,0 +1,269 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+use anyhow::{bail, Context, Result};
+use glam::Vec3;
+use serde::Serialize;
+use crate::fly_camera::CameraPose;
+use crate::station::SpaceStation;
+
+const USAGE: &str = "usage: space_station_3d --benchmark [--seconds N] [--out FILE]";
+
+// Camera height above each module's floor on the way round
+const EYE_HEIGHT: f32 = 1.5;
+// Particle effects set off ahead of the camera, and how often
+const BURST_KINDS: [&str; 4] = ["fire", "sparks", "smoke", "debris"];
+const BURST_INTERVAL: f32 = 0.25;
+pub const BURST_DURATION: f32 = 4.0;
+const BURST_AHEAD: f32 = 3.0;
+
+// Command line for `--benchmark`. The rest of the game's flags (`--seed`,
+// `--gpu`) still apply, so anything else is left for them.
+#[derive(Debug, Clone)]
+pub struct BenchmarkOptions {
+    // Length of the flythrough
+    pub seconds: f32,
+    // JSON report; the summary is printed either way
+    pub output: Option<PathBuf>,
+}
+
+impl BenchmarkOptions {
+    // None unless `--benchmark` is among the arguments
+    pub fn from_args(args: &[String]) -> Result<Option<Self>> {
+        if !args.iter().any(|arg| arg == "--benchmark") {
+            return Ok(None);
+        }
+        let mut options = Self {
+            seconds: 60.0,
+            output: None,
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().with_context(|| format!("{} needs a value\n{}", arg, USAGE));
+            match arg.as_str() {
+                "--seconds" => options.seconds = value()?.parse().context("--seconds must be a number")?,
+                "--out" => options.output = Some(value()?.into()),
+                _ => {}
+            }
+        }
+        if options.seconds.is_nan() || options.seconds <= 0.0 {
+            bail!("--seconds must be positive\n{}", USAGE);
+        }
+        Ok(Some(options))
+    }
+}
+
+// Camera path through the station: out along each connection from the
+// command center and back, so every module and corridor gets flown through
+#[derive(Debug, Clone)]
+pub struct Flythrough {
+    waypoints: Vec<Vec3>,
+    duration: f32,
+}
+
+impl Flythrough {
+    pub fn through(station: &SpaceStation, duration: f32) -> Self {
+        let mut waypoints = Vec::new();
+        let mut visited = vec![false; station.modules().len()];
+        if !station.modules().is_empty() {
+            visit(station, 0, &mut visited, &mut waypoints);
+        }
+        if waypoints.len() < 2 {
+            // Nothing to fly between; turn on the spot instead
+            waypoints = vec![Vec3::new(0.0, EYE_HEIGHT, 0.0), Vec3::new(0.1, EYE_HEIGHT, 0.0)];
+        }
+        Self { waypoints, duration }
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.duration
+    }
+
+    // Where the camera is `time` seconds in, looking along the path
+    pub fn pose(&self, time: f32) -> CameraPose {
+        let position = self.position(time);
+        let ahead = self.position(time + 0.25) - position;
+        let ahead = if ahead.length_squared() > 1e-6 { ahead } else { Vec3::X };
+        CameraPose {
+            position,
+            yaw: ahead.z.atan2(ahead.x),
+            // A slow nod so floors and ceilings get drawn too
+            pitch: (time * 0.7).sin() * 0.25,
+        }
+    }
+
+    fn position(&self, time: f32) -> Vec3 {
+        let segments = self.waypoints.len() - 1;
+        let t = (time / self.duration).clamp(0.0, 1.0) * segments as f32;
+        let i = (t.floor() as usize).min(segments - 1);
+        let at = |j: usize| self.waypoints[j.min(segments)];
+        // Catmull-Rom, so the turns at each waypoint are smooth
+        let (p0, p1, p2, p3) = (at(i.saturating_sub(1)), at(i), at(i + 1), at(i + 2));
+        let s = t - i as f32;
+        0.5 * (2.0 * p1
+            + (p2 - p0) * s
+            + (2.0 * p0 - 5.0 * p1 + 4.0 * p2 - p3) * s * s
+            + (3.0 * p1 - p0 - 3.0 * p2 + p3) * s * s * s)
+    }
+}
+
+fn visit(station: &SpaceStation, module: usize, visited: &mut [bool], waypoints: &mut Vec<Vec3>) {
+    visited[module] = true;
+    let here = station.modules()[module].transform.position + Vec3::Y * EYE_HEIGHT;
+    waypoints.push(here);
+    for next in station.neighbours(module) {
+        if !visited[next] {
+            visit(station, next, visited, waypoints);
+            waypoints.push(here);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SystemTiming {
+    pub name: &'static str,
+    pub avg_ms: f32,
+    pub max_ms: f32,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct BenchmarkReport {
+    pub frames: usize,
+    pub seconds: f32,
+    pub min_ms: f32,
+    pub avg_ms: f32,
+    pub p99_ms: f32,
+    pub systems: Vec<SystemTiming>,
+}
+
+impl BenchmarkReport {
+    pub fn summary(&self) -> String {
+        let fps = if self.avg_ms > 0.0 { 1000.0 / self.avg_ms } else { 0.0 };
+        let mut lines = vec![
+            format!("Benchmark: {} frames over {:.1} s", self.frames, self.seconds),
+            format!(
+                "Frame time  min {:.2} ms  avg {:.2} ms  p99 {:.2} ms  ({:.1} fps)",
+                self.min_ms, self.avg_ms, self.p99_ms, fps
+            ),
+            "CPU time per frame:".to_string(),
+        ];
+        for system in &self.systems {
+            lines.push(format!("  {:<12} avg {:.3} ms  max {:.3} ms", system.name, system.avg_ms, system.max_ms));
+        }
+        lines.join("\n")
+    }
+}
+
+// A `--benchmark` run: drives the camera, sets off particle effects along
+// the way and collects frame and per-system timings until the path ends
+#[derive(Debug)]
+pub struct Benchmark {
+    pub options: BenchmarkOptions,
+    path: Flythrough,
+    elapsed: f32,
+    frame_times: Vec<f32>,
+    // Totals and worst frame, in the order systems were first recorded
+    systems: Vec<(&'static str, Duration, Duration)>,
+    // Time spent in each system this frame
+    frame_systems: Vec<(&'static str, Duration)>,
+    system_frames: usize,
+    next_burst: f32,
+    bursts: usize,
+}
+
+impl Benchmark {
+    pub fn new(station: &SpaceStation, options: BenchmarkOptions) -> Self {
+        Self {
+            path: Flythrough::through(station, options.seconds),
+            options,
+            elapsed: 0.0,
+            frame_times: Vec::new(),
+            systems: Vec::new(),
+            frame_systems: Vec::new(),
+            system_frames: 0,
+            next_burst: 0.0,
+            bursts: 0,
+        }
+    }
+
+    pub fn pose(&self) -> CameraPose {
+        self.path.pose(self.elapsed)
+    }
+
+    // Adds to a system's time for the current frame
+    pub fn record(&mut self, system: &'static str, time: Duration) {
+        match self.frame_systems.iter_mut().find(|(name, _)| *name == system) {
+            Some((_, total)) => *total += time,
+            None => self.frame_systems.push((system, time)),
+        }
+    }
+
+    // Call once a frame with the last frame's time; false once the path is done
+    pub fn advance(&mut self, frame_time: f32) -> bool {
+        // The first frame includes loading, so it isn't counted
+        if self.elapsed > 0.0 {
+            self.frame_times.push(frame_time);
+        }
+        self.system_frames += 1;
+        for (system, time) in self.frame_systems.drain(..) {
+            match self.systems.iter_mut().find(|(name, _, _)| *name == system) {
+                Some((_, total, max)) => {
+                    *total += time;
+                    *max = (*max).max(time);
+                }
+                None => self.systems.push((system, time, time)),
+            }
+        }
+        self.elapsed += frame_time;
+        self.elapsed < self.path.duration()
+    }
+
+    // Particle effects due this frame, as (kind, position)
+    pub fn bursts(&mut self) -> Vec<(&'static str, Vec3)> {
+        let mut due = Vec::new();
+        while self.next_burst <= self.elapsed {
+            let pose = self.path.pose(self.next_burst);
+            let kind = BURST_KINDS[self.bursts % BURST_KINDS.len()];
+            due.push((kind, pose.position + pose.forward() * BURST_AHEAD));
+            self.bursts += 1;
+            self.next_burst += BURST_INTERVAL;
+        }
+        due
+    }
+
+    pub fn report(&self) -> BenchmarkReport {
+        let mut sorted: Vec<f32> = self.frame_times.iter().map(|t| t * 1000.0).collect();
+        sorted.sort_by(f32::total_cmp);
+        let frames = sorted.len();
+        let avg_ms = if frames > 0 { sorted.iter().sum::<f32>() / frames as f32 } else { 0.0 };
+        let p99_index = ((frames as f32 * 0.99).ceil() as usize).saturating_sub(1);
+        let per_frame = self.system_frames.max(1) as f32;
+        BenchmarkReport {
+            frames,
+            seconds: self.elapsed,
+            min_ms: sorted.first().copied().unwrap_or(0.0),
+            avg_ms,
+            p99_ms: sorted.get(p99_index).copied().unwrap_or(0.0),
+            systems: self
+                .systems
+                .iter()
+                .map(|&(name, total, max)| SystemTiming {
+                    name,
+                    avg_ms: total.as_secs_f32() * 1000.0 / per_frame,
+                    max_ms: max.as_secs_f32() * 1000.0,
+                })
+                .collect(),
+        }
+    }
+
+    // Prints the summary and writes the JSON report if one was asked for
+    pub fn finish(&self) -> Result<BenchmarkReport> {
+        let report = self.report();
+        println!("{}", report.summary());
+        if let Some(path) = &self.options.output {
+            let json = serde_json::to_string_pretty(&report).context("failed to serialize report")?;
+            fs::write(path, json).with_context(|| format!("failed to write {}", path.display()))?;
+        }
+        Ok(report)
+    }
+}
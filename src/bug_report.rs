@@ -0,0 +1,196 @@
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use anyhow::{Context, Result};
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipWriter};
+use crate::gpu::GpuInfo;
+use crate::save::SaveGame;
+use crate::station::SpaceStation;
+
+// Everything needed to reproduce a player's report, bundled into one zip
+#[derive(Debug, Default)]
+pub struct BugReport {
+    pub system: SystemInfo,
+    state: String,
+    event_log: String,
+    // Loadable from the save menu once copied into a slot
+    save: Option<SaveGame>,
+    files: Vec<(String, PathBuf)>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SystemInfo {
+    pub version: String,
+    pub os: String,
+    pub arch: String,
+    pub cpu_count: usize,
+    pub screen_size: (i32, i32),
+    pub fps: u32,
+    pub gpu: Option<GpuInfo>,
+}
+
+impl SystemInfo {
+    pub fn collect(screen_size: (i32, i32), fps: u32) -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            os: std::env::consts::OS.to_string(),
+            arch: std::env::consts::ARCH.to_string(),
+            cpu_count: std::thread::available_parallelism().map_or(1, |n| n.get()),
+            screen_size,
+            fps,
+            gpu: None,
+        }
+    }
+
+    pub fn with_gpu(mut self, gpu: Option<GpuInfo>) -> Self {
+        self.gpu = gpu;
+        self
+    }
+
+    fn to_text(&self) -> String {
+        let gpu = self.gpu.as_ref().map_or_else(|| "gpu: unknown\n".to_string(), GpuInfo::to_text);
+        format!(
+            "version: {}\nos: {}\narch: {}\ncpus: {}\nscreen: {}x{}\nfps: {}\n{}",
+            self.version,
+            self.os,
+            self.arch,
+            self.cpu_count,
+            self.screen_size.0,
+            self.screen_size.1,
+            self.fps,
+            gpu,
+        )
+    }
+}
+
+impl BugReport {
+    pub fn new(system: SystemInfo) -> Self {
+        Self {
+            system,
+            ..Default::default()
+        }
+    }
+
+    // Snapshot of the simulation plus the full event log
+    pub fn with_station(mut self, station: &SpaceStation) -> Self {
+        self.state = station.dump_state();
+
+        self.event_log = station
+            .log()
+            .entries()
+            .map(|entry| format!("{}\n", entry))
+            .collect();
+        self
+    }
+
+    // The game as it stood, as a save file
+    pub fn with_save(mut self, save: SaveGame) -> Self {
+        self.save = Some(save);
+        self
+    }
+
+    // Adds a file from disk under `name`; missing files are skipped when writing
+    pub fn attach<P: Into<PathBuf>>(mut self, name: &str, path: P) -> Self {
+        self.files.push((name.to_string(), path.into()));
+        self
+    }
+
+    // Writes the bundle into `dir` and returns the path of the zip
+    pub fn write_bundle(&self, dir: &Path) -> Result<PathBuf> {
+        fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+
+        let stamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |elapsed| elapsed.as_secs());
+        let path = dir.join(format!("bug_report_{}.zip", stamp));
+        let file = File::create(&path).with_context(|| format!("failed to create {}", path.display()))?;
+
+        let mut zip = ZipWriter::new(file);
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+
+        zip.start_file("system.txt", options)?;
+        zip.write_all(self.system.to_text().as_bytes())?;
+        zip.start_file("state.txt", options)?;
+        zip.write_all(self.state.as_bytes())?;
+        zip.start_file("event_log.txt", options)?;
+        zip.write_all(self.event_log.as_bytes())?;
+        if let Some(save) = &self.save {
+            zip.start_file("save.toml", options)?;
+            zip.write_all(toml::to_string(save).context("failed to serialize save")?.as_bytes())?;
+        }
+
+        for (name, source) in &self.files {
+            if !source.exists() {
+                continue;
+            }
+            let bytes = fs::read(source).with_context(|| format!("failed to read {}", source.display()))?;
+            zip.start_file(name.as_str(), options)?;
+            zip.write_all(&bytes)?;
+        }
+
+        zip.finish()?;
+        Ok(path)
+    }
+}
+
+// Per-user directory bug reports are written to
+pub fn report_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("space_station_3d")
+        .join("bug_reports")
+}
+
+// POSTs a finished bundle to a collection endpoint
+pub fn upload(bundle: &Path, endpoint: &str) -> Result<()> {
+    let bytes = fs::read(bundle).with_context(|| format!("failed to read {}", bundle.display()))?;
+    ureq::post(endpoint)
+        .set("Content-Type", "application/zip")
+        .send_bytes(&bytes)
+        .with_context(|| format!("failed to upload bug report to {}", endpoint))?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
+    use glam::Vec3;
+    use zip::ZipArchive;
+    use super::*;
+    use crate::markers::Markers;
+    use crate::research::Research;
+
+    #[test]
+    fn bundles_carry_a_loadable_save_and_the_settings_in_use() {
+        let dir = std::env::temp_dir().join(format!("bug_report_{}", std::process::id()));
+        let mut station = SpaceStation::create_default_layout();
+        station.damage_module(7, 0.4);
+        fs::create_dir_all(&dir).unwrap();
+        let config = dir.join("config.toml");
+        fs::write(&config, "[simulation]\nseed = 7\n").unwrap();
+        let save = SaveGame::capture(&station, &Markers::new(), &Research::default(), Vec3::new(1.0, 2.0, 3.0), 0.5);
+        let path = BugReport::new(SystemInfo::collect((1280, 720), 60))
+            .with_station(&station)
+            .with_save(save.clone())
+            .attach("config.toml", &config)
+            .attach("tunables.toml", "assets/tunables.toml")
+            .attach("missing.txt", dir.join("missing.txt"))
+            .write_bundle(&dir)
+            .unwrap();
+
+        let mut zip = ZipArchive::new(File::open(&path).unwrap()).unwrap();
+        let mut names: Vec<&str> = zip.file_names().collect();
+        names.sort_unstable();
+        assert_eq!(names, ["config.toml", "event_log.txt", "save.toml", "state.txt", "system.txt", "tunables.toml"]);
+
+        // Taken out into a slot, the save loads like any other
+        let mut source = String::new();
+        zip.by_name("save.toml").unwrap().read_to_string(&mut source).unwrap();
+        let slot = dir.join("slot.toml");
+        fs::write(&slot, source).unwrap();
+        assert_eq!(SaveGame::read(&slot).unwrap(), save);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
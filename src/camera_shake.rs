@@ -0,0 +1,139 @@
+use glam::{Quat, Vec3};
+use noise::{NoiseFn, Perlin};
+use crate::events::StationEvent;
+use crate::incidents::IncidentKind;
+
+// Highest global shake setting; 1 is as designed, 0 turns shake off
+pub const MAX_INTENSITY: f32 = 2.0;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ShakeOffset {
+    pub position: Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub roll: f32,
+}
+
+impl ShakeOffset {
+    // Returns the shaken (eye, target, up) for a camera at `position` looking
+    // along `forward`, with `up` the way the floor's facing
+    pub fn apply(&self, position: Vec3, forward: Vec3, up: Vec3) -> (Vec3, Vec3, Vec3) {
+        let right = forward.cross(up).normalize_or_zero();
+        let rotation = Quat::from_axis_angle(up, self.yaw) * Quat::from_axis_angle(right, self.pitch);
+        let shaken_forward = rotation * forward;
+        let up = Quat::from_axis_angle(shaken_forward.normalize_or_zero(), self.roll) * up;
+
+        let eye = position + self.position;
+        (eye, eye + shaken_forward, up)
+    }
+}
+
+// Trauma-based shake: events add trauma, trauma decays linearly, and the
+// visible shake scales with trauma squared so small bumps stay subtle.
+#[derive(Debug)]
+pub struct CameraShake {
+    pub intensity: f32,
+    pub decay: f32,
+    pub max_offset: f32,
+    pub max_angle: f32,
+    pub frequency: f32,
+    trauma: f32,
+    time: f32,
+    noise: Perlin,
+}
+
+impl Default for CameraShake {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CameraShake {
+    pub fn new() -> Self {
+        Self {
+            intensity: 1.0,
+            decay: 0.8,
+            max_offset: 0.15,
+            max_angle: 0.05,
+            frequency: 12.0,
+            trauma: 0.0,
+            time: 0.0,
+            noise: Perlin::new(7),
+        }
+    }
+
+    // From the [camera] setting, kept within 0..MAX_INTENSITY; nonsense turns it off
+    pub fn set_intensity(&mut self, intensity: f32) {
+        self.intensity = if intensity.is_nan() { 0.0 } else { intensity.clamp(0.0, MAX_INTENSITY) };
+    }
+
+    pub fn add_trauma(&mut self, amount: f32) {
+        self.trauma = (self.trauma + amount).clamp(0.0, 1.0);
+    }
+
+    pub fn trauma(&self) -> f32 {
+        self.trauma
+    }
+
+    pub fn handle_event(&mut self, event: &StationEvent, listener: Vec3) {
+        let (base, radius) = match event {
+            StationEvent::HullBreach { .. } => (0.6, 30.0),
+            StationEvent::Explosion { magnitude, .. } => (0.4 * magnitude, 20.0 * magnitude.max(0.5)),
+            StationEvent::AirlockDecompression { .. } | StationEvent::Decompression { .. } => (0.35, 15.0),
+            StationEvent::Incident { kind: IncidentKind::Micrometeorite, .. } => (0.25, 25.0),
+            StationEvent::StructuralStrain { .. } => (0.1, 15.0),
+            StationEvent::ConnectionFailed { .. } => (0.6, 40.0),
+            _ => return,
+        };
+        let Some(position) = event.position() else {
+            return;
+        };
+
+        // Fall off linearly with distance from the source
+        let distance = listener.distance(position);
+        let falloff = (1.0 - distance / radius).clamp(0.0, 1.0);
+        self.add_trauma(base * falloff);
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        self.time += delta_time;
+        self.trauma = (self.trauma - self.decay * delta_time).max(0.0);
+    }
+
+    pub fn offset(&self) -> ShakeOffset {
+        let shake = self.trauma * self.trauma * self.intensity;
+        if shake <= 0.0 {
+            return ShakeOffset::default();
+        }
+
+        let t = (self.time * self.frequency) as f64;
+        let sample = |channel: f64| self.noise.get([channel * 17.0, t]) as f32;
+
+        ShakeOffset {
+            position: Vec3::new(sample(0.0), sample(1.0), sample(2.0)) * self.max_offset * shake,
+            yaw: sample(3.0) * self.max_angle * shake,
+            pitch: sample(4.0) * self.max_angle * shake,
+            roll: sample(5.0) * self.max_angle * shake,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn intensity_is_clamped_and_zero_turns_shake_off() {
+        let mut shake = CameraShake::new();
+        shake.set_intensity(5.0);
+        assert_eq!(shake.intensity, MAX_INTENSITY);
+        shake.set_intensity(f32::NAN);
+        assert_eq!(shake.intensity, 0.0);
+        shake.set_intensity(-1.0);
+        assert_eq!(shake.intensity, 0.0);
+
+        shake.add_trauma(1.0);
+        shake.update(0.1);
+        assert_eq!(shake.offset().position, Vec3::ZERO);
+    }
+}
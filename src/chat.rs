@@ -0,0 +1,379 @@
+use std::collections::{BTreeMap, VecDeque};
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+
+// Messages kept for the overlay to scroll back through
+const HISTORY: usize = 100;
+// Longest message taken; anything over is cut short
+pub const MAX_LENGTH: usize = 160;
+// How long a gesture plays on the avatar
+pub const GESTURE_SECONDS: f32 = 2.5;
+// Bubbles go up this far above a speaker's head
+pub const BUBBLE_HEIGHT: f32 = 2.2;
+// Filtered when the server has the profanity filter on, with common endings
+const FILTERED_WORDS: &[&str] = &[
+    "arse", "arsehole", "ass", "asshole", "bastard", "bitch", "bollocks", "crap", "damn", "dick", "fuck", "piss",
+    "shit", "wanker",
+];
+const ENDINGS: &[&str] = &["", "s", "es", "ed", "er", "ers", "ing", "y"];
+
+// What the server allows, from the [chat] section of config.toml, along
+// with who the player here is on it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ChatConfig {
+    pub name: String,
+    pub team: String,
+    pub profanity_filter: bool,
+    // How far a proximity message or an emote carries, in metres
+    pub proximity_radius: f32,
+    pub bubble_seconds: f32,
+    // Names the server won't pass messages on from
+    pub muted: Vec<String>,
+}
+
+impl Default for ChatConfig {
+    fn default() -> Self {
+        Self {
+            name: "player".to_string(),
+            team: "crew".to_string(),
+            profanity_filter: true,
+            proximity_radius: 15.0,
+            bubble_seconds: 6.0,
+            muted: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Channel {
+    // Everyone on the sender's team, wherever they are
+    Team,
+    // Everyone within earshot, with a bubble over the sender
+    Proximity,
+}
+
+impl Channel {
+    pub fn tag(self) -> &'static str {
+        match self {
+            Channel::Team => "TEAM",
+            Channel::Proximity => "NEAR",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Emote {
+    Wave,
+    Point,
+    ThumbsUp,
+    Shrug,
+    Salute,
+    Cheer,
+}
+
+impl Emote {
+    pub const ALL: [Emote; 6] = [Emote::Wave, Emote::Point, Emote::ThumbsUp, Emote::Shrug, Emote::Salute, Emote::Cheer];
+
+    pub fn id(self) -> &'static str {
+        match self {
+            Emote::Wave => "wave",
+            Emote::Point => "point",
+            Emote::ThumbsUp => "thumbs_up",
+            Emote::Shrug => "shrug",
+            Emote::Salute => "salute",
+            Emote::Cheer => "cheer",
+        }
+    }
+
+    pub fn parse(id: &str) -> Option<Emote> {
+        Emote::ALL.into_iter().find(|emote| emote.id() == id)
+    }
+
+    // As it reads in chat, after the name
+    pub fn action(self) -> &'static str {
+        match self {
+            Emote::Wave => "waves",
+            Emote::Point => "points",
+            Emote::ThumbsUp => "gives a thumbs up",
+            Emote::Shrug => "shrugs",
+            Emote::Salute => "salutes",
+            Emote::Cheer => "cheers",
+        }
+    }
+}
+
+// Why the server didn't pass a message on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChatRefusal {
+    NotJoined,
+    Muted,
+    Empty,
+}
+
+impl ChatRefusal {
+    pub fn describe(self) -> &'static str {
+        match self {
+            ChatRefusal::NotJoined => "not in the chat",
+            ChatRefusal::Muted => "muted by the server",
+            ChatRefusal::Empty => "nothing to say",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChatMessage {
+    pub from: String,
+    pub channel: Channel,
+    // Filtered already, if the filter's on
+    pub text: String,
+    pub emote: Option<Emote>,
+    // Who it went to, the sender included
+    pub recipients: Vec<String>,
+}
+
+impl ChatMessage {
+    pub fn line(&self) -> String {
+        match self.emote {
+            Some(emote) => format!("[{}] * {} {}", self.channel.tag(), self.from, emote.action()),
+            None => format!("[{}] {}: {}", self.channel.tag(), self.from, self.text),
+        }
+    }
+}
+
+// Text over someone's head for a while, from proximity chat or an emote
+#[derive(Debug, Clone, PartialEq)]
+pub struct Bubble {
+    pub from: String,
+    pub text: String,
+    pub remaining: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Member {
+    team: String,
+    position: Vec3,
+    // The emote playing, and how long it has left
+    gesture: Option<(Emote, f32)>,
+}
+
+// The server's side of chat: who's in it and where, routing each message to
+// the team or to whoever's near, and applying mutes and the filter. Nothing
+// carries messages between machines yet; everyone in it is on this one.
+#[derive(Debug)]
+pub struct Chat {
+    config: ChatConfig,
+    members: BTreeMap<String, Member>,
+    history: VecDeque<ChatMessage>,
+    bubbles: Vec<Bubble>,
+}
+
+impl Chat {
+    pub fn new(config: ChatConfig) -> Self {
+        Self { config, members: BTreeMap::new(), history: VecDeque::new(), bubbles: Vec::new() }
+    }
+
+    pub fn config(&self) -> &ChatConfig {
+        &self.config
+    }
+
+    // Picks up an edited config; mutes from the old one are dropped
+    pub fn set_config(&mut self, config: ChatConfig) {
+        self.config = config;
+    }
+
+    pub fn join(&mut self, name: &str, team: &str, position: Vec3) {
+        self.members.insert(name.to_string(), Member { team: team.to_string(), position, gesture: None });
+    }
+
+    pub fn leave(&mut self, name: &str) -> bool {
+        self.bubbles.retain(|bubble| bubble.from != name);
+        self.members.remove(name).is_some()
+    }
+
+    pub fn set_position(&mut self, name: &str, position: Vec3) {
+        if let Some(member) = self.members.get_mut(name) {
+            member.position = position;
+        }
+    }
+
+    // Name, team and position of everyone in
+    pub fn members(&self) -> impl Iterator<Item = (&str, &str, Vec3)> {
+        self.members.iter().map(|(name, member)| (name.as_str(), member.team.as_str(), member.position))
+    }
+
+    pub fn is_muted(&self, name: &str) -> bool {
+        self.config.muted.iter().any(|muted| muted == name)
+    }
+
+    // Mutes or unmutes someone; false if that's how they were already
+    pub fn set_muted(&mut self, name: &str, muted: bool) -> bool {
+        if self.is_muted(name) == muted {
+            return false;
+        }
+        if muted {
+            self.config.muted.push(name.to_string());
+        } else {
+            self.config.muted.retain(|other| other != name);
+        }
+        true
+    }
+
+    pub fn set_filter(&mut self, on: bool) {
+        self.config.profanity_filter = on;
+    }
+
+    pub fn send(&mut self, from: &str, channel: Channel, text: &str) -> Result<&ChatMessage, ChatRefusal> {
+        let text: String = text.trim().chars().take(MAX_LENGTH).collect();
+        if text.is_empty() {
+            return Err(ChatRefusal::Empty);
+        }
+        let text = if self.config.profanity_filter { filter(&text) } else { text };
+        self.deliver(from, channel, text, None)
+    }
+
+    // Plays a gesture on the sender's avatar, for everyone near to see
+    pub fn emote(&mut self, from: &str, emote: Emote) -> Result<&ChatMessage, ChatRefusal> {
+        self.deliver(from, Channel::Proximity, format!("*{}*", emote.action()), Some(emote))
+    }
+
+    fn deliver(&mut self, from: &str, channel: Channel, text: String, emote: Option<Emote>) -> Result<&ChatMessage, ChatRefusal> {
+        let Some(sender) = self.members.get(from) else {
+            return Err(ChatRefusal::NotJoined);
+        };
+        if self.is_muted(from) {
+            return Err(ChatRefusal::Muted);
+        }
+        let radius = self.config.proximity_radius;
+        let recipients = self
+            .members
+            .iter()
+            .filter(|(_, member)| match channel {
+                Channel::Team => member.team == sender.team,
+                Channel::Proximity => member.position.distance(sender.position) <= radius,
+            })
+            .map(|(name, _)| name.clone())
+            .collect();
+        if channel == Channel::Proximity {
+            self.bubbles.retain(|bubble| bubble.from != from);
+            let remaining = self.config.bubble_seconds;
+            self.bubbles.push(Bubble { from: from.to_string(), text: text.clone(), remaining });
+        }
+        if let (Some(emote), Some(member)) = (emote, self.members.get_mut(from)) {
+            member.gesture = Some((emote, GESTURE_SECONDS));
+        }
+        if self.history.len() == HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back(ChatMessage { from: from.to_string(), channel, text, emote, recipients });
+        Ok(self.history.back().expect("just pushed"))
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        for bubble in &mut self.bubbles {
+            bubble.remaining -= delta_time;
+        }
+        self.bubbles.retain(|bubble| bubble.remaining > 0.0);
+        for member in self.members.values_mut() {
+            if let Some((_, remaining)) = &mut member.gesture {
+                *remaining -= delta_time;
+            }
+            member.gesture = member.gesture.filter(|&(_, remaining)| remaining > 0.0);
+        }
+    }
+
+    // What `name` has been sent, oldest first
+    pub fn inbox<'a>(&'a self, name: &'a str) -> impl DoubleEndedIterator<Item = &'a ChatMessage> {
+        self.history.iter().filter(move |message| message.recipients.iter().any(|to| to == name))
+    }
+
+    // Bubbles `name` can see, with where to draw them and how faded
+    pub fn bubbles_for(&self, name: &str) -> Vec<(Vec3, &str, f32)> {
+        let Some(viewer) = self.members.get(name) else {
+            return Vec::new();
+        };
+        self.bubbles
+            .iter()
+            .filter_map(|bubble| {
+                let speaker = self.members.get(&bubble.from)?;
+                let near = speaker.position.distance(viewer.position) <= self.config.proximity_radius;
+                near.then(|| (speaker.position + Vec3::Y * BUBBLE_HEIGHT, bubble.text.as_str(), bubble.remaining.min(1.0)))
+            })
+            .collect()
+    }
+
+    // The gesture someone's avatar is playing and how far through it is, 0
+    // to 1, for the animation layer to pose them by
+    pub fn gesture(&self, name: &str) -> Option<(Emote, f32)> {
+        let (emote, remaining) = self.members.get(name)?.gesture?;
+        Some((emote, 1.0 - remaining / GESTURE_SECONDS))
+    }
+}
+
+// Stars out filtered words but their first letter, whatever the case or
+// punctuation round them
+pub fn filter(text: &str) -> String {
+    text.split(' ')
+        .map(|word| {
+            let letters: String = word.chars().filter(|c| c.is_alphabetic()).collect::<String>().to_lowercase();
+            let rude = FILTERED_WORDS
+                .iter()
+                .any(|stem| ENDINGS.iter().any(|ending| letters == format!("{}{}", stem, ending)));
+            if !rude {
+                return word.to_string();
+            }
+            let mut first = true;
+            word.chars()
+                .map(|c| {
+                    if !c.is_alphabetic() {
+                        c
+                    } else if first {
+                        first = false;
+                        c
+                    } else {
+                        '*'
+                    }
+                })
+                .collect()
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn chat_routes_by_team_and_distance_and_moderates() {
+        let mut chat = Chat::new(ChatConfig { bubble_seconds: 2.0, ..ChatConfig::default() });
+        chat.join("ana", "crew", Vec3::ZERO);
+        chat.join("ben", "crew", Vec3::new(0.0, 0.0, -40.0));
+        chat.join("cho", "visitors", Vec3::new(5.0, 0.0, 0.0));
+        assert_eq!(chat.send("dev", Channel::Team, "hi").unwrap_err(), ChatRefusal::NotJoined);
+        assert_eq!(chat.send("ana", Channel::Team, "   ").unwrap_err(), ChatRefusal::Empty);
+
+        // Team reaches across the station, proximity only who's near
+        assert_eq!(chat.send("ana", Channel::Team, "leak in the lab").unwrap().recipients, ["ana", "ben"]);
+        assert_eq!(chat.send("ana", Channel::Proximity, "over here").unwrap().recipients, ["ana", "cho"]);
+        assert_eq!(chat.inbox("ben").count(), 1);
+        assert_eq!(chat.bubbles_for("cho"), [(Vec3::Y * BUBBLE_HEIGHT, "over here", 1.0)]);
+        assert!(chat.bubbles_for("ben").is_empty());
+
+        assert_eq!(chat.send("cho", Channel::Proximity, "Bloody HELL, DAMNED airlock!").unwrap().text, "Bloody HELL, D***** airlock!");
+        chat.set_filter(false);
+        assert_eq!(chat.send("cho", Channel::Proximity, "shit").unwrap().text, "shit");
+        assert!(chat.set_muted("cho", true) && !chat.set_muted("cho", true));
+        assert_eq!(chat.send("cho", Channel::Team, "let me talk").unwrap_err(), ChatRefusal::Muted);
+
+        // Emotes play out on the avatar and bubbles fade away
+        assert_eq!(chat.emote("ana", Emote::Wave).unwrap().line(), "[NEAR] * ana waves");
+        chat.update(GESTURE_SECONDS / 2.0);
+        let (emote, progress) = chat.gesture("ana").unwrap();
+        assert_eq!(emote, Emote::Wave);
+        assert!((progress - 0.5).abs() < 1e-4);
+        chat.update(GESTURE_SECONDS);
+        assert!(chat.gesture("ana").is_none());
+        assert!(chat.bubbles_for("cho").is_empty());
+    }
+}
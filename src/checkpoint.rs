@@ -0,0 +1,137 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use crate::drill::DrillProgress;
+use crate::save::SaveGame;
+
+// The station and the drill as they stood at the last milestone of a
+// running scenario, to go back to if it fails. One file per scenario, kept
+// apart from the save slots and deleted when the scenario's over.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub scenario: String,
+    // What was reached, for the prompt
+    pub milestone: String,
+    pub drill: DrillProgress,
+    pub save: SaveGame,
+}
+
+impl Checkpoint {
+    pub fn read(dir: &Path, scenario: &str) -> Result<Self> {
+        let path = path(dir, scenario);
+        let source = fs::read_to_string(&path).with_context(|| format!("failed to read {}", path.display()))?;
+        toml::from_str(&source).with_context(|| format!("invalid checkpoint {}", path.display()))
+    }
+
+    // Over the scenario's last one, through a temporary file like saves
+    pub fn write(&self, dir: &Path) -> Result<()> {
+        fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+        let path = path(dir, &self.scenario);
+        let contents = toml::to_string(self).context("failed to serialize checkpoint")?;
+        let temp = path.with_extension("tmp");
+        fs::write(&temp, contents).with_context(|| format!("failed to write {}", temp.display()))?;
+        fs::rename(&temp, &path).with_context(|| format!("failed to replace {}", path.display()))
+    }
+}
+
+pub fn exists(dir: &Path, scenario: &str) -> bool {
+    path(dir, scenario).exists()
+}
+
+// Drops a scenario's checkpoint, if it has one
+pub fn clear(dir: &Path, scenario: &str) -> Result<()> {
+    let path = path(dir, scenario);
+    if !path.exists() {
+        return Ok(());
+    }
+    fs::remove_file(&path).with_context(|| format!("failed to remove {}", path.display()))
+}
+
+// Per-user directory checkpoints go in, next to the saves
+pub fn checkpoint_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("space_station_3d")
+        .join("checkpoints")
+}
+
+// Scenario names with anything odd swapped out
+fn path(dir: &Path, scenario: &str) -> PathBuf {
+    let file: String = scenario.chars().map(|c| if c.is_alphanumeric() || c == '-' { c } else { '_' }).collect();
+    dir.join(format!("{}.toml", file))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Vec3;
+    use crate::drill::{Drill, DrillEvent, DrillScenario};
+    use crate::markers::Markers;
+    use crate::module_id::ModuleId;
+    use crate::research::Research;
+    use crate::resources::Resource;
+    use crate::station::SpaceStation;
+
+    #[test]
+    fn failed_drills_go_back_to_their_last_checkpoint() {
+        let dir = std::env::temp_dir().join(format!("checkpoints_{}", std::process::id()));
+        let event = |at, module| DrillEvent { at, module: ModuleId::from_layout(module), damage: 0.2, explosion: None };
+        let scenario = DrillScenario {
+            name: "Hull breach / night shift".to_string(),
+            description: String::new(),
+            time_limit: 30.0,
+            resource_budget: 10.0,
+            events: vec![event(20.0, 9), event(1.0, 5), event(2.0, 8)],
+        };
+        let mut station = SpaceStation::create_default_layout();
+        let mut drill = Drill::start(scenario.clone(), &station);
+        let lab = station.modules()[5].transform.position;
+        let far = Vec3::splat(500.0);
+
+        // Answering the lab's emergency is a milestone, checkpointed with the
+        // station as it stood
+        assert!(!drill.update(&mut station, far, 1.5));
+        assert!(drill.take_milestone().is_none());
+        assert!(!drill.update(&mut station, lab, 0.1));
+        assert_eq!(drill.take_milestone().as_deref(), Some("5: Laboratory secured"));
+        assert!(drill.take_milestone().is_none());
+        let checkpoint = Checkpoint {
+            scenario: scenario.name.clone(),
+            milestone: "5: Laboratory secured".to_string(),
+            drill: drill.progress(),
+            save: SaveGame::capture(&station, &Markers::new(), &Research::default(), lab, 0.0),
+        };
+        assert!(!exists(&dir, &scenario.name));
+        checkpoint.write(&dir).unwrap();
+        assert!(exists(&dir, &scenario.name));
+        let integrity = station.modules()[8].structural_integrity;
+
+        // Then it goes badly: the quarters burn and the rest are never answered
+        assert!(station.spend_resource(Resource::SpareParts, 10.0));
+        assert!(station.ignite(6));
+        while !drill.update(&mut station, far, 1.0) {}
+        let result = drill.finish(&station);
+        assert!(result.failed(), "{:?}", result.record);
+        assert!(station.modules()[9].structural_integrity < 1.0);
+
+        // Going back puts the station and the clock back to the milestone,
+        // with the events still to come still to come
+        let read = Checkpoint::read(&dir, &scenario.name).unwrap();
+        assert_eq!(read, checkpoint);
+        let mut station = SpaceStation::from_snapshot(&read.save.station);
+        let mut drill = Drill::resume(scenario.clone(), read.drill);
+        assert_eq!(drill.elapsed(), 1.6);
+        assert_eq!(station.modules()[8].structural_integrity, integrity);
+        assert_eq!(station.modules()[9].structural_integrity, 1.0);
+        let plant = station.modules()[8].transform.position;
+        assert!(!drill.update(&mut station, plant, 1.0));
+        assert_eq!(drill.take_milestone().as_deref(), Some("8: PowerPlant secured"));
+
+        // Finishing the scenario clears it away, and nothing else in there
+        clear(&dir, &scenario.name).unwrap();
+        assert!(!exists(&dir, &scenario.name));
+        clear(&dir, &scenario.name).unwrap();
+        fs::remove_dir(&dir).unwrap();
+    }
+}
@@ -0,0 +1,129 @@
+use std::collections::VecDeque;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+use anyhow::{Context, Result};
+
+// Frames are downscaled to this width before they're buffered
+pub const CLIP_WIDTH: i32 = 320;
+
+#[derive(Debug)]
+pub struct ClipFrame {
+    pub width: u16,
+    pub height: u16,
+    pub rgba: Vec<u8>,
+}
+
+// Rolling buffer of the last few seconds of gameplay at a low frame rate.
+// Frames are shared, so taking a snapshot for export doesn't copy pixels.
+#[derive(Debug)]
+pub struct ClipRecorder {
+    pub enabled: bool,
+    fps: u32,
+    capacity: usize,
+    timer: f32,
+    frames: VecDeque<Arc<ClipFrame>>,
+}
+
+impl ClipRecorder {
+    pub fn new(seconds: f32, fps: u32) -> Self {
+        let capacity = (seconds * fps as f32).ceil().max(1.0) as usize;
+        Self {
+            enabled: true,
+            fps,
+            capacity,
+            timer: 0.0,
+            frames: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    // Advances the capture timer; true when this frame should be captured
+    pub fn should_capture(&mut self, delta_time: f32) -> bool {
+        if !self.enabled {
+            return false;
+        }
+        self.timer += delta_time;
+        let interval = 1.0 / self.fps as f32;
+        if self.timer < interval {
+            return false;
+        }
+        self.timer %= interval;
+        true
+    }
+
+    pub fn push_frame(&mut self, frame: ClipFrame) {
+        if self.frames.len() == self.capacity {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(Arc::new(frame));
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn duration(&self) -> f32 {
+        self.frames.len() as f32 / self.fps as f32
+    }
+
+    pub fn snapshot(&self) -> Clip {
+        Clip {
+            fps: self.fps,
+            frames: self.frames.iter().cloned().collect(),
+        }
+    }
+}
+
+// Frozen copy of the buffer, safe to encode on another thread
+#[derive(Debug, Clone)]
+pub struct Clip {
+    fps: u32,
+    frames: Vec<Arc<ClipFrame>>,
+}
+
+impl Clip {
+    pub fn write_gif(&self, path: &Path) -> Result<()> {
+        let Some(first) = self.frames.first() else {
+            anyhow::bail!("no frames captured yet");
+        };
+
+        let file = File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+        let mut encoder = gif::Encoder::new(file, first.width, first.height, &[])?;
+        encoder.set_repeat(gif::Repeat::Infinite)?;
+
+        // GIF delays are in hundredths of a second
+        let delay = (100 / self.fps.max(1)) as u16;
+        for clip_frame in &self.frames {
+            // A resize mid-clip changes the frame size; skip frames that no longer match
+            if clip_frame.width != first.width || clip_frame.height != first.height {
+                continue;
+            }
+            let mut rgba = clip_frame.rgba.clone();
+            let mut frame = gif::Frame::from_rgba_speed(clip_frame.width, clip_frame.height, &mut rgba, 10);
+            frame.delay = delay;
+            encoder.write_frame(&frame)?;
+        }
+        Ok(())
+    }
+}
+
+// Per-user directory clips are exported to
+pub fn clip_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("space_station_3d")
+        .join("clips")
+}
+
+pub fn export_path(dir: &Path) -> Result<PathBuf> {
+    fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs());
+    Ok(dir.join(format!("clip_{}.gif", stamp)))
+}
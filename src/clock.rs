@@ -0,0 +1,314 @@
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+
+pub const SECONDS_PER_MINUTE: u64 = 60;
+pub const SECONDS_PER_HOUR: u64 = 3_600;
+pub const SECONDS_PER_DAY: u64 = 86_400;
+
+// Mission elapsed time (MET) in whole milliseconds since mission start.
+// No time zone is involved; saves store the raw millisecond count.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default)]
+pub struct MissionTime {
+    millis: u64,
+}
+
+impl MissionTime {
+    pub const ZERO: MissionTime = MissionTime { millis: 0 };
+
+    pub fn from_millis(millis: u64) -> Self {
+        Self { millis }
+    }
+
+    pub fn from_secs(secs: u64) -> Self {
+        Self { millis: secs * 1000 }
+    }
+
+    pub fn from_dhms(days: u64, hours: u64, minutes: u64, seconds: u64) -> Self {
+        Self::from_secs(
+            days * SECONDS_PER_DAY
+                + hours * SECONDS_PER_HOUR
+                + minutes * SECONDS_PER_MINUTE
+                + seconds,
+        )
+    }
+
+    pub fn as_millis(&self) -> u64 {
+        self.millis
+    }
+
+    pub fn as_secs(&self) -> u64 {
+        self.millis / 1000
+    }
+
+    pub fn as_secs_f32(&self) -> f32 {
+        self.millis as f32 / 1000.0
+    }
+
+    // Day 0 is launch day
+    pub fn days(&self) -> u64 {
+        self.as_secs() / SECONDS_PER_DAY
+    }
+
+    pub fn hours(&self) -> u64 {
+        (self.as_secs() % SECONDS_PER_DAY) / SECONDS_PER_HOUR
+    }
+
+    pub fn minutes(&self) -> u64 {
+        (self.as_secs() % SECONDS_PER_HOUR) / SECONDS_PER_MINUTE
+    }
+
+    pub fn seconds(&self) -> u64 {
+        self.as_secs() % SECONDS_PER_MINUTE
+    }
+
+    pub fn seconds_of_day(&self) -> u64 {
+        self.as_secs() % SECONDS_PER_DAY
+    }
+
+    pub fn saturating_sub(&self, other: MissionTime) -> Duration {
+        Duration::from_millis(self.millis.saturating_sub(other.millis))
+    }
+
+    // e.g. "MET 003/14:22:05"
+    pub fn format_met(&self) -> String {
+        format!("MET {}", self)
+    }
+}
+
+impl std::ops::Add<Duration> for MissionTime {
+    type Output = MissionTime;
+
+    fn add(self, rhs: Duration) -> MissionTime {
+        MissionTime::from_millis(self.millis + rhs.as_millis() as u64)
+    }
+}
+
+impl fmt::Display for MissionTime {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{:03}/{:02}:{:02}:{:02}",
+            self.days(),
+            self.hours(),
+            self.minutes(),
+            self.seconds()
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseTimeError(String);
+
+impl fmt::Display for ParseTimeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid mission time '{}', expected DDD/HH:MM:SS", self.0)
+    }
+}
+
+impl std::error::Error for ParseTimeError {}
+
+impl FromStr for MissionTime {
+    type Err = ParseTimeError;
+
+    // Accepts "DDD/HH:MM:SS" with or without the "MET" prefix
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseTimeError(s.to_string());
+        let trimmed = s.trim().trim_start_matches("MET").trim();
+
+        let (days, clock) = trimmed.split_once('/').ok_or_else(err)?;
+        let mut parts = clock.split(':');
+        let mut next = || -> Result<u64, ParseTimeError> {
+            parts.next().ok_or_else(err)?.parse().map_err(|_| err())
+        };
+
+        let days: u64 = days.parse().map_err(|_| err())?;
+        let (hours, minutes, seconds) = (next()?, next()?, next()?);
+        if hours >= 24 || minutes >= 60 || seconds >= 60 {
+            return Err(err());
+        }
+
+        Ok(Self::from_dhms(days, hours, minutes, seconds))
+    }
+}
+
+// Calendar date of mission start as year, ordinal day and seconds into the day
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StationEpoch {
+    pub year: i32,
+    pub day_of_year: u32,
+    pub seconds_of_day: u32,
+}
+
+impl Default for StationEpoch {
+    fn default() -> Self {
+        Self {
+            year: 2150,
+            day_of_year: 1,
+            seconds_of_day: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StationDate {
+    pub year: i32,
+    pub day_of_year: u32,
+    pub hour: u32,
+    pub minute: u32,
+    pub second: u32,
+}
+
+impl fmt::Display for StationDate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}-{:03} {:02}:{:02}:{:02}",
+            self.year, self.day_of_year, self.hour, self.minute, self.second
+        )
+    }
+}
+
+fn days_in_year(year: i32) -> u32 {
+    let leap = (year % 4 == 0 && year % 100 != 0) || year % 400 == 0;
+    if leap {
+        366
+    } else {
+        365
+    }
+}
+
+impl StationEpoch {
+    pub fn date_at(&self, time: MissionTime) -> StationDate {
+        let total = self.seconds_of_day as u64 + time.as_secs();
+        let seconds = total % SECONDS_PER_DAY;
+
+        let mut year = self.year;
+        let mut day = self.day_of_year as u64 + total / SECONDS_PER_DAY;
+        while day > days_in_year(year) as u64 {
+            day -= days_in_year(year) as u64;
+            year += 1;
+        }
+
+        StationDate {
+            year,
+            day_of_year: day as u32,
+            hour: (seconds / SECONDS_PER_HOUR) as u32,
+            minute: ((seconds % SECONDS_PER_HOUR) / SECONDS_PER_MINUTE) as u32,
+            second: (seconds % SECONDS_PER_MINUTE) as u32,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MissionClock {
+    epoch: StationEpoch,
+    now: MissionTime,
+    // Sub-millisecond remainder so small frame deltas don't drift
+    remainder: f64,
+}
+
+impl Default for MissionClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MissionClock {
+    pub fn new() -> Self {
+        Self::with_epoch(StationEpoch::default())
+    }
+
+    pub fn with_epoch(epoch: StationEpoch) -> Self {
+        Self {
+            epoch,
+            now: MissionTime::ZERO,
+            remainder: 0.0,
+        }
+    }
+
+    pub fn resume(epoch: StationEpoch, now: MissionTime) -> Self {
+        Self {
+            epoch,
+            now,
+            remainder: 0.0,
+        }
+    }
+
+    pub fn advance(&mut self, delta_time: f32) {
+        let millis = self.remainder + delta_time.max(0.0) as f64 * 1000.0;
+        let whole = millis.floor();
+        self.remainder = millis - whole;
+        self.now = MissionTime::from_millis(self.now.as_millis() + whole as u64);
+    }
+
+    pub fn now(&self) -> MissionTime {
+        self.now
+    }
+
+    pub fn epoch(&self) -> StationEpoch {
+        self.epoch
+    }
+
+    pub fn station_date(&self) -> StationDate {
+        self.epoch.date_at(self.now)
+    }
+
+    pub fn format_hud(&self) -> String {
+        format!("{} | {}", self.now.format_met(), self.station_date())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mission_time_formats_and_parses_back() {
+        let time = MissionTime::from_dhms(3, 14, 22, 5);
+        assert_eq!(time.format_met(), "MET 003/14:22:05");
+        assert_eq!("MET 003/14:22:05".parse(), Ok(time));
+        assert_eq!(" 003/14:22:05 ".parse(), Ok(time));
+        assert_eq!((time.days(), time.hours(), time.minutes(), time.seconds()), (3, 14, 22, 5));
+    }
+
+    #[test]
+    fn bad_mission_times_are_rejected() {
+        for bad in ["", "14:22:05", "003/14:22", "003/24:00:00", "003/14:60:00", "003/14:22:xx", "abc/14:22:05"] {
+            let err = bad.parse::<MissionTime>().unwrap_err();
+            assert_eq!(err, ParseTimeError(bad.to_string()));
+        }
+    }
+
+    #[test]
+    fn dates_run_through_february_29_in_leap_years_only() {
+        // Day 60 is 29 February in a leap year and 1 March otherwise
+        let leap = StationEpoch { year: 2152, day_of_year: 59, seconds_of_day: 0 };
+        let date = leap.date_at(MissionTime::from_dhms(1, 0, 0, 0));
+        assert_eq!((date.year, date.day_of_year), (2152, 60));
+        let end = leap.date_at(MissionTime::from_dhms(366 - 59, 0, 0, 0));
+        assert_eq!((end.year, end.day_of_year), (2152, 366));
+        let next = leap.date_at(MissionTime::from_dhms(366 - 58, 0, 0, 0));
+        assert_eq!((next.year, next.day_of_year), (2153, 1));
+
+        let common = StationEpoch { year: 2151, day_of_year: 365, seconds_of_day: 0 };
+        assert_eq!(common.date_at(MissionTime::from_dhms(1, 0, 0, 0)).year, 2152);
+        // Centuries aren't leap years unless they divide by 400
+        assert_eq!(days_in_year(2100), 365);
+        assert_eq!(days_in_year(2400), 366);
+    }
+
+    #[test]
+    fn the_clock_carries_sub_millisecond_frames() {
+        let epoch = StationEpoch { year: 2150, day_of_year: 1, seconds_of_day: SECONDS_PER_DAY as u32 - 1 };
+        let mut clock = MissionClock::with_epoch(epoch);
+        // Under a millisecond a frame, and exact in binary
+        for _ in 0..1024 {
+            clock.advance(1.0 / 1024.0);
+        }
+        assert_eq!(clock.now(), MissionTime::from_secs(1));
+        assert_eq!(clock.station_date().to_string(), "2150-002 00:00:00");
+        assert_eq!(clock.format_hud(), "MET 000/00:00:01 | 2150-002 00:00:00");
+    }
+}
@@ -0,0 +1,302 @@
+use std::fs;
+use std::path::Path;
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use crate::events::StationEvent;
+use crate::incidents::{IncidentKind, Incidents};
+use crate::objectives::{ObjectiveDefinition, Objectives};
+use crate::station::{ElementState, InteractionType, SpaceStation};
+
+// Signal time to the ground and back through the relay satellites
+pub const RELAY_SECONDS: f32 = 2.5;
+// Seconds a message takes to come down over a perfect link; a weaker one
+// takes longer
+const MESSAGE_SECONDS: f32 = 4.0;
+// Below this there's no lock and nothing comes through
+pub const MIN_LINK: f32 = 0.15;
+// Alignment lost per second as the dish drifts, and how much faster a
+// damaged comms module lets it go
+const DRIFT: f32 = 1.0 / 900.0;
+const DAMAGE_DRIFT: f32 = 4.0;
+// Alignment gained per adjustment from the comms console
+pub const ALIGN_STEP: f32 = 0.25;
+
+// Something mission control sends, from assets/comms.toml
+#[derive(Debug, Clone, Deserialize)]
+pub struct MessageDefinition {
+    pub id: String,
+    // Seconds into the session it's sent; it arrives once it's come down
+    pub sent_at: f32,
+    #[serde(default = "default_sender")]
+    pub from: String,
+    pub subject: String,
+    #[serde(default)]
+    pub body: String,
+    // Set going when it arrives
+    #[serde(default)]
+    pub incident: Option<IncidentKind>,
+    #[serde(default)]
+    pub objective: Option<ObjectiveDefinition>,
+}
+
+fn default_sender() -> String {
+    "Mission Control".to_string()
+}
+
+#[derive(Debug, Deserialize)]
+struct CommsFile {
+    #[serde(rename = "message", default)]
+    messages: Vec<MessageDefinition>,
+}
+
+pub fn load_messages<P: AsRef<Path>>(path: P) -> Result<Vec<MessageDefinition>> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path).with_context(|| format!("failed to read messages from {}", path.display()))?;
+    let file: CommsFile = toml::from_str(&source).context("invalid message definitions")?;
+    for (i, message) in file.messages.iter().enumerate() {
+        if file.messages[..i].iter().any(|other| other.id == message.id) {
+            bail!("message '{}' is defined twice", message.id);
+        }
+    }
+    Ok(file.messages)
+}
+
+// A message on its way down: relay time left, then how much has come in
+#[derive(Debug, Clone, PartialEq)]
+struct InFlight {
+    message: usize,
+    latency: f32,
+    received: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Received {
+    pub message: usize,
+    // Seconds into the session it arrived
+    pub at: f32,
+    pub read: bool,
+}
+
+// The station's end of the link to the ground: the dish's alignment, which
+// drifts and is trimmed from the comms console, the messages in transit
+// and the inbox they land in
+#[derive(Debug)]
+pub struct Comms {
+    messages: Vec<MessageDefinition>,
+    elapsed: f32,
+    next: usize,
+    alignment: f32,
+    in_flight: Vec<InFlight>,
+    inbox: Vec<Received>,
+}
+
+impl Comms {
+    pub fn new(mut messages: Vec<MessageDefinition>) -> Self {
+        messages.sort_by(|a, b| a.sent_at.total_cmp(&b.sent_at));
+        Self { messages, elapsed: 0.0, next: 0, alignment: 1.0, in_flight: Vec::new(), inbox: Vec::new() }
+    }
+
+    pub fn alignment(&self) -> f32 {
+        self.alignment
+    }
+
+    // Best the dish can be trimmed to: a bent mast won't point true
+    pub fn alignment_limit(station: &SpaceStation) -> f32 {
+        comms_module(station).map_or(0.0, |module| station.modules()[module].structural_integrity)
+    }
+
+    // How good the link is, 0 to 1: the dish's alignment through a working
+    // comms set, less whatever a flare's doing to it
+    pub fn link(&self, station: &SpaceStation) -> f32 {
+        let Some(module_idx) = comms_module(station) else {
+            return 0.0;
+        };
+        let module = &station.modules()[module_idx];
+        let working = module.powered
+            && module.interactive_elements.iter().any(|element| {
+                element.element_type == InteractionType::Communications && element.state != ElementState::Malfunction
+            });
+        if !working {
+            return 0.0;
+        }
+        self.alignment * (1.0 - station.interference().level()).clamp(0.0, 1.0)
+    }
+
+    // Seconds a message sent now would take, None without a lock
+    pub fn delay(&self, station: &SpaceStation) -> Option<f32> {
+        let link = self.link(station);
+        (link >= MIN_LINK).then(|| RELAY_SECONDS + MESSAGE_SECONDS / link)
+    }
+
+    // Trims the dish a step towards true; false if it's as good as it'll get
+    pub fn align(&mut self, station: &SpaceStation) -> bool {
+        let limit = Self::alignment_limit(station);
+        if self.alignment >= limit {
+            return false;
+        }
+        self.alignment = (self.alignment + ALIGN_STEP).min(limit);
+        true
+    }
+
+    // Sends what's due, brings in what the link allows and sets going what
+    // arrived messages call for. Returns the inbox places of new arrivals.
+    pub fn update(
+        &mut self,
+        station: &mut SpaceStation,
+        objectives: &mut Objectives,
+        mut incidents: Option<&mut Incidents>,
+        delta_time: f32,
+    ) -> Vec<usize> {
+        self.elapsed += delta_time;
+        let limit = Self::alignment_limit(station);
+        let drift = DRIFT * (1.0 + DAMAGE_DRIFT * (1.0 - limit));
+        self.alignment = (self.alignment - drift * delta_time).clamp(0.0, limit);
+
+        while let Some(message) = self.messages.get(self.next) {
+            if message.sent_at > self.elapsed {
+                break;
+            }
+            self.in_flight.push(InFlight { message: self.next, latency: RELAY_SECONDS, received: 0.0 });
+            self.next += 1;
+        }
+
+        let link = self.link(station);
+        let mut arrived = Vec::new();
+        for flight in &mut self.in_flight {
+            flight.latency -= delta_time;
+            if flight.latency > 0.0 || link < MIN_LINK {
+                continue;
+            }
+            flight.received += link * delta_time / MESSAGE_SECONDS;
+            if flight.received >= 1.0 {
+                arrived.push(flight.message);
+            }
+        }
+        self.in_flight.retain(|flight| flight.received < 1.0);
+
+        let mut places = Vec::new();
+        for index in arrived {
+            let message = &self.messages[index];
+            station.emit_event(StationEvent::MessageReceived { from: message.from.clone(), subject: message.subject.clone() });
+            if let Some(objective) = &message.objective {
+                objectives.add(objective.clone());
+            }
+            if let (Some(kind), Some(incidents)) = (message.incident, incidents.as_deref_mut()) {
+                incidents.raise(kind, station, objectives);
+            }
+            places.push(self.inbox.len());
+            self.inbox.push(Received { message: index, at: self.elapsed, read: false });
+        }
+        places
+    }
+
+    pub fn in_transit(&self) -> usize {
+        self.in_flight.len()
+    }
+
+    // Newest first
+    pub fn inbox(&self) -> impl Iterator<Item = (&MessageDefinition, &Received)> {
+        self.inbox.iter().rev().map(|received| (&self.messages[received.message], received))
+    }
+
+    pub fn unread(&self) -> usize {
+        self.inbox.iter().filter(|received| !received.read).count()
+    }
+
+    pub fn mark_read(&mut self) {
+        for received in &mut self.inbox {
+            received.read = true;
+        }
+    }
+}
+
+// The first module with a comms set in it
+fn comms_module(station: &SpaceStation) -> Option<usize> {
+    station.modules().iter().position(|module| {
+        module.interactive_elements.iter().any(|element| element.element_type == InteractionType::Communications)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::director::Pacing;
+    use crate::incidents::IncidentSchedule;
+    use crate::stepping::STEP_DT;
+
+    #[test]
+    fn messages_come_down_as_well_as_the_dish_is_trimmed() {
+        assert!(!load_messages("assets/comms.toml").unwrap().is_empty());
+        let message = |id: &str, sent_at: f32| MessageDefinition {
+            id: id.to_string(),
+            sent_at,
+            from: "Mission Control".to_string(),
+            subject: id.to_string(),
+            body: String::new(),
+            incident: None,
+            objective: None,
+        };
+        let mut flare = message("flare", 20.0);
+        flare.incident = Some(IncidentKind::SolarFlare);
+        let mut station = SpaceStation::create_default_layout();
+        let mut objectives = Objectives::default();
+        let mut incidents = Incidents::new(IncidentSchedule {
+            mean_interval: 1e9,
+            grace: 1e9,
+            difficulty: 1.0,
+            odds: Vec::new(),
+            pacing: Pacing::default(),
+            flavor: Vec::new(),
+        });
+        let mut comms = Comms::new(vec![flare, message("hello", 1.0)]);
+
+        // Relay time, then the message itself over a perfect link
+        let delay = comms.delay(&station).unwrap();
+        assert!((delay - (RELAY_SECONDS + 4.0)).abs() < 1e-3);
+        let mut arrived = Vec::new();
+        for _ in 0..((1.0 + delay - 0.5) / STEP_DT) as usize {
+            arrived.extend(comms.update(&mut station, &mut objectives, Some(&mut incidents), STEP_DT));
+        }
+        assert_eq!((comms.in_transit(), comms.unread()), (1, 0));
+        for _ in 0..(1.0 / STEP_DT) as usize {
+            arrived.extend(comms.update(&mut station, &mut objectives, Some(&mut incidents), STEP_DT));
+        }
+        assert_eq!((arrived, comms.unread()), (vec![0], 1));
+        assert!(station.drain_events().iter().any(|event| matches!(
+            event,
+            StationEvent::MessageReceived { subject, .. } if subject == "hello"
+        )));
+
+        // A dead comms set has no lock, so the flare warning waits
+        let (module, element) = (0, 1);
+        station.set_element_state(module, element, ElementState::Malfunction);
+        assert_eq!(comms.delay(&station), None);
+        for _ in 0..(30.0 / STEP_DT) as usize {
+            comms.update(&mut station, &mut objectives, Some(&mut incidents), STEP_DT);
+        }
+        assert_eq!(comms.in_transit(), 1);
+        assert!(incidents.active().is_empty());
+        station.set_element_state(module, element, ElementState::Active);
+        for _ in 0..(10.0 / STEP_DT) as usize {
+            comms.update(&mut station, &mut objectives, Some(&mut incidents), STEP_DT);
+        }
+        assert_eq!(comms.in_transit(), 0);
+        assert_eq!(incidents.active()[0].kind, IncidentKind::SolarFlare);
+        assert_eq!(comms.inbox().next().unwrap().0.id, "flare");
+
+        // Drift is faster on a damaged module, which also can't be
+        // trimmed back to true
+        let mut healthy = Comms::new(Vec::new());
+        healthy.update(&mut SpaceStation::create_default_layout(), &mut objectives, None, 90.0);
+        station.module_mut(module).unwrap().structural_integrity = 0.6;
+        let mut damaged = Comms::new(Vec::new());
+        damaged.update(&mut station, &mut objectives, None, 90.0);
+        assert!(damaged.alignment() < healthy.alignment());
+        assert!(damaged.alignment() <= 0.6);
+        while damaged.align(&station) {}
+        assert!((damaged.alignment() - 0.6).abs() < 1e-5);
+
+        comms.mark_read();
+        assert_eq!(comms.unread(), 0);
+    }
+}
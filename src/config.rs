@@ -0,0 +1,347 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use crate::analytics::AnalyticsConfig;
+use crate::backdrop::{self, BackdropConfig};
+use crate::chat::ChatConfig;
+use crate::gpu::GpuPreference;
+use crate::handheld::{HandheldMode, ShadowQuality};
+use crate::quick_menu::{self, QuickAction};
+use crate::voice::VoiceConfig;
+
+// Engine and player settings from config.toml in the working directory.
+// Everything has a default, so the file (and any key in it) is optional.
+// Balancing numbers for the simulation live in the tunables file it points at.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub window: WindowConfig,
+    pub graphics: GraphicsConfig,
+    pub ui: UiConfig,
+    pub controls: ControlsConfig,
+    pub camera: CameraConfig,
+    pub quick_menu: QuickMenuConfig,
+    pub particles: ParticleLimits,
+    pub simulation: SimulationConfig,
+    pub haptics: HapticsConfig,
+    pub chat: ChatConfig,
+    pub voice: VoiceConfig,
+    pub analytics: AnalyticsConfig,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowConfig {
+    pub width: i32,
+    pub height: i32,
+    // 0 leaves the frame rate uncapped
+    pub fps_cap: u32,
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        Self {
+            width: 800,
+            height: 600,
+            fps_cap: 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GraphicsConfig {
+    // The handheld preset replaces everything else here, and the particle
+    // limits, with settings for a Steam Deck
+    pub handheld: HandheldMode,
+    // 0.25 to 1; the 3D scene is drawn at this fraction of the window's size
+    pub render_scale: f32,
+    // HUD and menu zoom; 0 picks one to fit the screen
+    pub ui_scale: f32,
+    pub shadows: ShadowQuality,
+    // Sky preset from backdrop.rs, or "none"; a layout file can pick its own
+    pub backdrop: String,
+    // Multiples of the starfield's usual star count and brightness
+    pub star_density: f32,
+    pub star_brightness: f32,
+    // default, discrete, integrated or an index from `--list-gpus`; read at startup
+    pub gpu: String,
+}
+
+impl Default for GraphicsConfig {
+    fn default() -> Self {
+        Self {
+            handheld: HandheldMode::Auto,
+            render_scale: 1.0,
+            ui_scale: 1.0,
+            shadows: ShadowQuality::High,
+            backdrop: "default".to_string(),
+            star_density: 1.0,
+            star_brightness: 1.0,
+            gpu: "default".to_string(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UiConfig {
+    // A file in assets/lang, without the extension
+    pub language: String,
+    // Seconds without input before the attract-mode tour starts; 0 never
+    pub attract_after: f32,
+}
+
+impl Default for UiConfig {
+    fn default() -> Self {
+        Self {
+            language: crate::locale::DEFAULT_LANGUAGE.to_string(),
+            attract_after: 120.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ControlsConfig {
+    // Radians of turn per pixel of mouse movement
+    pub mouse_sensitivity: f32,
+    // Units per second
+    pub walk_speed: f32,
+    pub fly_speed: f32,
+}
+
+impl Default for ControlsConfig {
+    fn default() -> Self {
+        Self {
+            mouse_sensitivity: 0.003,
+            walk_speed: 6.0,
+            fly_speed: 6.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CameraConfig {
+    // Scales all camera shake: 0 off, 1 as designed, up to 2
+    pub shake_intensity: f32,
+}
+
+impl Default for CameraConfig {
+    fn default() -> Self {
+        Self { shake_intensity: 1.0 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuickMenuConfig {
+    // Clockwise from the top; see `QuickAction::from_name`
+    pub slots: Vec<String>,
+}
+
+impl Default for QuickMenuConfig {
+    fn default() -> Self {
+        Self {
+            slots: quick_menu::DEFAULT_SLOTS.iter().map(|slot| slot.to_string()).collect(),
+        }
+    }
+}
+
+impl QuickMenuConfig {
+    // Checked when the config is loaded, so unknown names can't get this far
+    pub fn actions(&self) -> Vec<QuickAction> {
+        quick_menu::parse_slots(&self.slots).unwrap_or_default()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParticleLimits {
+    pub per_emitter: usize,
+    // Event effects alive at once; the oldest goes when another is spawned
+    pub max_emitters: usize,
+}
+
+impl Default for ParticleLimits {
+    fn default() -> Self {
+        Self {
+            per_emitter: 100,
+            max_emitters: 32,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SimulationConfig {
+    pub tunables: PathBuf,
+    // Used when no `--seed` is given; without either every session is different
+    pub seed: Option<u64>,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        Self {
+            tunables: PathBuf::from("assets/tunables.toml"),
+            seed: None,
+        }
+    }
+}
+
+// Controller rumble strengths, 0..1. `master` is the accessibility slider
+// over everything; the rest scale one kind of event each.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HapticsConfig {
+    pub master: f32,
+    pub door: f32,
+    pub power: f32,
+    pub impact: f32,
+    pub heartbeat: f32,
+}
+
+impl Default for HapticsConfig {
+    fn default() -> Self {
+        Self {
+            master: 1.0,
+            door: 1.0,
+            power: 1.0,
+            impact: 1.0,
+            heartbeat: 1.0,
+        }
+    }
+}
+
+impl Config {
+    // A missing file gives the defaults; a broken one is an error
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let source = match fs::read_to_string(path) {
+            Ok(source) => source,
+            Err(err) if err.kind() == ErrorKind::NotFound => return Ok(Self::default()),
+            Err(err) => return Err(err).with_context(|| format!("failed to read config from {}", path.display())),
+        };
+        let config: Self = toml::from_str(&source).context("invalid config")?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<()> {
+        if self.window.width < 320 || self.window.height < 240 {
+            bail!("window must be at least 320x240, not {}x{}", self.window.width, self.window.height);
+        }
+        if !(0.25..=1.0).contains(&self.graphics.render_scale) {
+            bail!("render_scale must be between 0.25 and 1, not {}", self.graphics.render_scale);
+        }
+        if self.graphics.ui_scale != 0.0 && !(0.5..=4.0).contains(&self.graphics.ui_scale) {
+            bail!("ui_scale must be 0 (fit to screen) or between 0.5 and 4, not {}", self.graphics.ui_scale);
+        }
+        if self.graphics.backdrop != "none" && BackdropConfig::preset(&self.graphics.backdrop).is_none() {
+            bail!("no backdrop preset {:?}; try {}", self.graphics.backdrop, backdrop::PRESETS.join(", "));
+        }
+        if !(0.0..=4.0).contains(&self.graphics.star_density) {
+            bail!("star_density must be between 0 and 4, not {}", self.graphics.star_density);
+        }
+        if !(0.0..=2.0).contains(&self.graphics.star_brightness) {
+            bail!("star_brightness must be between 0 and 2, not {}", self.graphics.star_brightness);
+        }
+        if GpuPreference::from_name(&self.graphics.gpu).is_none() {
+            bail!("gpu must be default, discrete, integrated or a number from --list-gpus, not {}", self.graphics.gpu);
+        }
+        if self.ui.attract_after.is_nan() || self.ui.attract_after < 0.0 {
+            bail!("attract_after can't be negative");
+        }
+        if self.controls.mouse_sensitivity <= 0.0 {
+            bail!("mouse_sensitivity must be positive");
+        }
+        if self.controls.walk_speed < 0.0 || self.controls.fly_speed < 0.0 {
+            bail!("movement speeds can't be negative");
+        }
+        quick_menu::parse_slots(&self.quick_menu.slots)?;
+        let haptics = &self.haptics;
+        for (name, value) in [
+            ("master", haptics.master),
+            ("door", haptics.door),
+            ("power", haptics.power),
+            ("impact", haptics.impact),
+            ("heartbeat", haptics.heartbeat),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                bail!("haptics {} must be between 0 and 1, not {}", name, value);
+            }
+        }
+        if self.chat.name.trim().is_empty() || self.chat.name.contains(char::is_whitespace) {
+            bail!("chat name must be one word, not '{}'", self.chat.name);
+        }
+        if self.chat.proximity_radius <= 0.0 || self.chat.bubble_seconds < 0.0 {
+            bail!("chat proximity_radius must be positive and bubble_seconds can't be negative");
+        }
+        let voice = &self.voice;
+        for (name, value) in [
+            ("open_mic_threshold", voice.open_mic_threshold),
+            ("volume", voice.volume),
+            ("radio_volume", voice.radio_volume),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                bail!("voice {} must be between 0 and 1, not {}", name, value);
+            }
+        }
+        if voice.hold_seconds < 0.0 || voice.hearing_range <= 0.0 {
+            bail!("voice hold_seconds can't be negative and hearing_range must be positive");
+        }
+        if self.simulation.seed.is_some_and(|seed| seed > crate::rng::MAX_SEED) {
+            bail!("seed can be at most {}", crate::rng::MAX_SEED);
+        }
+        Ok(())
+    }
+}
+
+// How often the config file is checked for edits, in seconds
+const POLL_INTERVAL: f32 = 1.0;
+
+// Reloads the config whenever its file changes on disk
+#[derive(Debug)]
+pub struct ConfigFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    since_poll: f32,
+}
+
+impl ConfigFile {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref().to_path_buf();
+        Self {
+            modified: modified_time(&path),
+            path,
+            since_poll: 0.0,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Some when the file changed since the last load
+    pub fn poll(&mut self, delta_time: f32) -> Option<Result<Config>> {
+        self.since_poll += delta_time;
+        if self.since_poll < POLL_INTERVAL {
+            return None;
+        }
+        self.since_poll = 0.0;
+        let modified = modified_time(&self.path);
+        if modified.is_none() || modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+        Some(Config::load(&self.path))
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
@@ -0,0 +1,1145 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fs;
+use std::path::Path;
+use anyhow::{anyhow, bail, Context, Result};
+use glam::Vec3;
+use crate::crew::Crew;
+use crate::analytics::{Analytics, AnalyticsSummary};
+use crate::avatar::{self, Helmet, Role, SuitScheme};
+use crate::chat::{Channel, Chat, Emote};
+use crate::effects::EventEffects;
+use crate::graphviz::GraphKind;
+use crate::fluid::{Liquid, Spills};
+use crate::incidents::{IncidentKind, Incidents};
+use crate::joints::Props;
+use crate::lockstep;
+use crate::markers::{MarkerKind, Markers};
+use crate::objectives::Objectives;
+use crate::module_id::ModuleId;
+use crate::paint::{StyleGuide, Surface};
+use crate::panorama::{PanoramaCapture, DEFAULT_FACE_SIZE};
+use crate::replication::{Interest, Replicator};
+use crate::research::Research;
+use crate::resources::Resource;
+use crate::ring;
+use crate::save::{self, SaveGame, SaveSlot};
+use crate::state_diff::{SnapshotHistory, StateDiff};
+use crate::station::{ElementState, InteractionType, ModuleType, SpaceStation, StationSnapshot};
+use crate::stepping::{self, SimStepper};
+use crate::usd::{self, SceneRecorder, USD_FPS};
+
+const MAX_OUTPUT_LINES: usize = 200;
+
+// Scripts can call other scripts, but not forever
+const MAX_EXEC_DEPTH: usize = 8;
+
+// Everything a command is allowed to touch
+pub struct ConsoleContext<'a> {
+    pub station: &'a mut SpaceStation,
+    pub effects: &'a mut EventEffects,
+    pub markers: &'a mut Markers,
+    pub player: &'a mut Vec3,
+    pub stepper: &'a mut SimStepper,
+    pub history: &'a SnapshotHistory,
+    // Shown in the diff panel until cleared
+    pub diff: &'a mut Option<StateDiff>,
+    pub scene_recorder: &'a mut SceneRecorder,
+    // Rendered over the next few frames once set
+    pub panorama: &'a mut Option<PanoramaCapture>,
+    pub props: &'a mut Props,
+    pub spills: &'a mut Spills,
+    pub crew: &'a mut Crew,
+    pub style_guide: &'a StyleGuide,
+    // None when there's no incident schedule
+    pub incidents: Option<&'a mut Incidents>,
+    pub objectives: &'a mut Objectives,
+    pub replicator: &'a mut Replicator,
+    pub research: &'a mut Research,
+    pub chat: &'a mut Chat,
+    // Where the player's look is saved when they change it
+    pub profiles: &'a Path,
+    pub analytics: &'a Analytics,
+}
+
+pub type CommandHandler = Box<dyn Fn(&mut ConsoleContext, &[&str]) -> Result<String>>;
+
+struct Command {
+    usage: &'static str,
+    handler: CommandHandler,
+}
+
+// Drop-down developer console. Commands are registered by name; `exec <file>`
+// runs a file of commands, one per line, with `#` comments.
+pub struct Console {
+    pub open: bool,
+    input: String,
+    output: VecDeque<String>,
+    history: Vec<String>,
+    history_cursor: Option<usize>,
+    commands: BTreeMap<String, Command>,
+}
+
+impl Default for Console {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Console {
+    pub fn new() -> Self {
+        Self {
+            open: false,
+            input: String::new(),
+            output: VecDeque::new(),
+            history: Vec::new(),
+            history_cursor: None,
+            commands: BTreeMap::new(),
+        }
+    }
+
+    // Console with the standard simulation commands
+    pub fn with_default_commands() -> Self {
+        let mut console = Self::new();
+        register_default_commands(&mut console);
+        console
+    }
+
+    pub fn register<F>(&mut self, name: &str, usage: &'static str, handler: F)
+    where
+        F: Fn(&mut ConsoleContext, &[&str]) -> Result<String> + 'static,
+    {
+        self.commands.insert(
+            name.to_string(),
+            Command {
+                usage,
+                handler: Box::new(handler),
+            },
+        );
+    }
+
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    pub fn output(&self) -> impl DoubleEndedIterator<Item = &String> {
+        self.output.iter()
+    }
+
+    pub fn type_char(&mut self, c: char) {
+        if !c.is_control() {
+            self.input.push(c);
+        }
+        self.history_cursor = None;
+    }
+
+    pub fn backspace(&mut self) {
+        self.input.pop();
+    }
+
+    pub fn history_previous(&mut self) {
+        if self.history.is_empty() {
+            return;
+        }
+        let cursor = match self.history_cursor {
+            Some(cursor) => cursor.saturating_sub(1),
+            None => self.history.len() - 1,
+        };
+        self.history_cursor = Some(cursor);
+        self.input = self.history[cursor].clone();
+    }
+
+    pub fn history_next(&mut self) {
+        match self.history_cursor {
+            Some(cursor) if cursor + 1 < self.history.len() => {
+                self.history_cursor = Some(cursor + 1);
+                self.input = self.history[cursor + 1].clone();
+            }
+            _ => {
+                self.history_cursor = None;
+                self.input.clear();
+            }
+        }
+    }
+
+    pub fn print(&mut self, line: impl Into<String>) {
+        if self.output.len() == MAX_OUTPUT_LINES {
+            self.output.pop_front();
+        }
+        self.output.push_back(line.into());
+    }
+
+    // Runs whatever has been typed
+    pub fn submit(&mut self, context: &mut ConsoleContext) {
+        let line = std::mem::take(&mut self.input);
+        self.history_cursor = None;
+        if line.trim().is_empty() {
+            return;
+        }
+        self.history.push(line.clone());
+        self.print(format!("> {}", line));
+        self.execute(&line, context);
+    }
+
+    pub fn execute(&mut self, line: &str, context: &mut ConsoleContext) {
+        if let Err(err) = self.execute_at_depth(line, context, 0) {
+            self.print(format!("error: {:#}", err));
+        }
+    }
+
+    fn execute_at_depth(&mut self, line: &str, context: &mut ConsoleContext, depth: usize) -> Result<()> {
+        let args: Vec<&str> = line.split_whitespace().collect();
+        let Some((&name, args)) = args.split_first() else {
+            return Ok(());
+        };
+        if name.starts_with('#') {
+            return Ok(());
+        }
+
+        match name {
+            "help" => {
+                let usages: Vec<String> = self.commands.values().map(|c| c.usage.to_string()).collect();
+                self.print("help | clear | exec <file>");
+                for usage in usages {
+                    self.print(usage);
+                }
+            }
+            "clear" => self.output.clear(),
+            "exec" => {
+                let [path] = args else {
+                    bail!("usage: exec <file>");
+                };
+                if depth >= MAX_EXEC_DEPTH {
+                    bail!("exec nested too deeply");
+                }
+                let script = fs::read_to_string(path).with_context(|| format!("failed to read {}", path))?;
+                for script_line in script.lines() {
+                    self.execute_at_depth(script_line, context, depth + 1)?;
+                }
+            }
+            _ => {
+                let command = self
+                    .commands
+                    .get(name)
+                    .ok_or_else(|| anyhow!("unknown command '{}', try help", name))?;
+                let message = (command.handler)(context, args)
+                    .with_context(|| format!("usage: {}", command.usage))?;
+                for message_line in message.lines() {
+                    self.print(message_line.to_string());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn parse_f32(arg: Option<&&str>, name: &str) -> Result<f32> {
+    let arg = arg.ok_or_else(|| anyhow!("missing {}", name))?;
+    arg.parse().with_context(|| format!("'{}' is not a number", arg))
+}
+
+fn parse_vec3(args: &[&str]) -> Result<Vec3> {
+    Ok(Vec3::new(
+        parse_f32(args.first(), "x")?,
+        parse_f32(args.get(1), "y")?,
+        parse_f32(args.get(2), "z")?,
+    ))
+}
+
+// `module:3`, `module:lab` (matches a module type by prefix) or a plain index
+fn parse_module(station: &SpaceStation, arg: &str) -> Result<usize> {
+    let name = arg.strip_prefix("module:").unwrap_or(arg);
+    if let Ok(index) = name.parse::<usize>() {
+        if index < station.modules().len() {
+            return Ok(index);
+        }
+        bail!("no module {}", index);
+    }
+
+    let name = name.to_lowercase();
+    station
+        .modules()
+        .iter()
+        .position(|m| format!("{:?}", m.module_type).to_lowercase().starts_with(&name))
+        .ok_or_else(|| anyhow!("no module matching '{}'", name))
+}
+
+// The module's number as typed in commands, or "gone" if it's been taken away
+fn numbered(station: &SpaceStation, module: ModuleId) -> String {
+    station.module_index(module).map_or_else(|| "gone".to_string(), |index| index.to_string())
+}
+
+// `now`, `tick:<n>`, `quick` or `slot:<n>`
+fn resolve_snapshot(context: &ConsoleContext, source: &str) -> Result<StationSnapshot> {
+    if source == "now" {
+        return Ok(context.station.snapshot());
+    }
+    if let Some(tick) = source.strip_prefix("tick:") {
+        let tick: u64 = tick.parse().with_context(|| format!("'{}' is not a tick", tick))?;
+        return context.history.get(tick).cloned().ok_or_else(|| match context.history.range() {
+            Some((first, last)) => anyhow!("tick {} isn't recorded (have {}-{})", tick, first, last),
+            None => anyhow!("no ticks recorded yet"),
+        });
+    }
+    let slot = match source {
+        "quick" => SaveSlot::Quick,
+        _ => {
+            let n = source
+                .strip_prefix("slot:")
+                .and_then(|n| n.parse().ok())
+                .ok_or_else(|| anyhow!("unknown state '{}'", source))?;
+            SaveSlot::Numbered(n)
+        }
+    };
+    Ok(SaveGame::read(&slot.path(&save::save_dir()))?.station)
+}
+
+fn register_default_commands(console: &mut Console) {
+    console.register("give_power", "give_power <kW>", |context, args| {
+        let amount = parse_f32(args.first(), "kW")?;
+        let target = context
+            .station
+            .modules()
+            .iter()
+            .position(|m| m.module_type == ModuleType::PowerPlant)
+            .ok_or_else(|| anyhow!("station has no power plant"))?;
+        let module = context.station.module_mut(target).unwrap();
+        module.power_generation = (module.power_generation + amount).max(0.0);
+        Ok(format!("power plant {} now generates {:.1} kW", target, module.power_generation))
+    });
+
+    console.register("teleport", "teleport <module:name|module:index|x y z>", |context, args| {
+        let destination = match args {
+            [module] => {
+                let index = parse_module(context.station, module)?;
+                // Eye height above the module floor
+                context.station.modules()[index].transform.position + Vec3::new(0.0, 1.5, 0.0)
+            }
+            _ => parse_vec3(args)?,
+        };
+        *context.player = destination;
+        Ok(format!("teleported to {}", destination))
+    });
+
+    console.register("spawn_emitter", "spawn_emitter <sparks|fire|smoke|debris> <x> <y> <z> [seconds]", |context, args| {
+        let kind = args.first().ok_or_else(|| anyhow!("missing kind"))?;
+        let position = parse_vec3(&args[1..])?;
+        let duration = match args.get(4) {
+            Some(_) => parse_f32(args.get(4), "seconds")?,
+            None => 5.0,
+        };
+        if !context.effects.spawn(kind, position, duration) {
+            bail!("unknown emitter kind '{}'", kind);
+        }
+        Ok(format!("spawned {} at {}", kind, position))
+    });
+
+    console.register("set_integrity", "set_integrity <0-1> [module]", |context, args| {
+        let value = parse_f32(args.first(), "integrity")?.clamp(0.0, 1.0);
+        let index = match args.get(1) {
+            Some(module) => parse_module(context.station, module)?,
+            None => context.station.nearest_module(*context.player).ok_or_else(|| anyhow!("no modules"))?,
+        };
+        let module = context.station.module_mut(index).unwrap();
+        module.structural_integrity = value;
+        Ok(format!("module {} integrity set to {:.2}", index, value))
+    });
+
+    console.register("pause", "pause", |context, _| {
+        context.stepper.toggle_pause();
+        Ok(if context.stepper.paused { "simulation paused" } else { "simulation resumed" }.to_string())
+    });
+
+    console.register("step", "step [ticks]", |context, args| {
+        let ticks = match args.first() {
+            Some(arg) => arg.parse().with_context(|| format!("'{}' is not a tick count", arg))?,
+            None => 1,
+        };
+        context.stepper.step(ticks);
+        Ok(format!("stepping {} tick(s) from tick {}", ticks, context.stepper.tick()))
+    });
+
+    console.register("dump", "dump", |context, _| {
+        let path = stepping::write_dump(context.station, context.stepper.tick(), Path::new(stepping::DUMP_DIR))?;
+        Ok(format!("state written to {}", path.display()))
+    });
+
+    console.register("diff", "diff [from] [to] | diff off  (now, tick:<n>, quick, slot:<n>)", |context, args| {
+        let previous = format!("tick:{}", context.stepper.tick().saturating_sub(1));
+        let (from, to) = match args {
+            ["off"] => {
+                *context.diff = None;
+                return Ok(String::new());
+            }
+            [] => (previous.as_str(), "now"),
+            [from] => (*from, "now"),
+            [from, to] => (*from, *to),
+            _ => bail!("too many arguments"),
+        };
+        let diff = StateDiff::between(
+            from,
+            &resolve_snapshot(context, from)?,
+            to,
+            &resolve_snapshot(context, to)?,
+        )?;
+        let summary = diff.lines().join("\n");
+        *context.diff = Some(diff);
+        Ok(summary)
+    });
+
+    // Until there's a transport these are dry-run clients: every packet
+    // counts as delivered, which is enough to size the bandwidth. `lockstep`
+    // plays a host and guest against each other from the station as it is.
+    const NET_USAGE: &str =
+        "net | net watch <name> [module] [radius] | net drop <name> | net lockstep [seconds] [latency ms]";
+    console.register("net", NET_USAGE, |context, args| {
+        match args {
+            [] => {}
+            ["lockstep", rest @ ..] => {
+                let seconds = if rest.is_empty() { 10.0 } else { parse_f32(rest.first(), "seconds")? };
+                let latency = if rest.len() < 2 { 100.0 } else { parse_f32(rest.get(1), "latency")? };
+                let ticks = (seconds.max(0.0) / stepping::STEP_DT).round() as u64;
+                let latency = (latency.max(0.0) / 1000.0 / stepping::STEP_DT).round() as u64;
+                let rehearsal = lockstep::rehearse(&context.station.snapshot(), ticks, latency, &[]);
+                let outcome = match rehearsal.desync {
+                    Some(tick) => format!("hashes parted at tick {}", tick),
+                    None => format!("{} hashes swapped, all agreed", rehearsal.checksums_sent),
+                };
+                return Ok(format!(
+                    "lockstep: {} ticks, {} stalled, {:.2} kB/s (whole snapshots {:.1} kB/s), {}",
+                    rehearsal.ticks,
+                    rehearsal.stalls,
+                    rehearsal.bytes_per_second() / 1024.0,
+                    rehearsal.snapshot_bytes_per_second() / 1024.0,
+                    outcome,
+                ));
+            }
+            ["watch", name, rest @ ..] => {
+                let position = match rest.first() {
+                    Some(module) => {
+                        let index: usize = module.parse().map_err(|_| anyhow!("invalid module '{}'", module))?;
+                        let module = context.station.modules().get(index).ok_or_else(|| anyhow!("no module {}", index))?;
+                        module.transform.position
+                    }
+                    None => *context.player,
+                };
+                let mut interest = Interest::new(position);
+                if let Some(radius) = rest.get(1) {
+                    interest.radius = parse_f32(Some(radius), "radius")?;
+                }
+                context.replicator.connect(name, interest);
+            }
+            ["drop", name] => {
+                if !context.replicator.disconnect(name) {
+                    bail!("no client '{}'", name);
+                }
+            }
+            _ => bail!("usage: {}", NET_USAGE),
+        }
+        let lines: Vec<String> = context
+            .replicator
+            .clients()
+            .map(|(name, interest, metrics)| {
+                format!(
+                    "{}: {:.1} kB/s (whole snapshots {:.1} kB/s), {} modules within {:.0} m, {} packets, {} keyframes",
+                    name,
+                    metrics.bytes_per_second() as f32 / 1024.0,
+                    metrics.naive_bytes_per_second() as f32 / 1024.0,
+                    metrics.modules,
+                    interest.radius,
+                    metrics.packets,
+                    metrics.keyframes,
+                )
+            })
+            .collect();
+        Ok(if lines.is_empty() { "no clients".to_string() } else { lines.join("\n") })
+    });
+
+    const RESEARCH_USAGE: &str = "research | research queue <experiment> [module] | research cancel <n> | research unlock <upgrade>";
+    console.register("research", RESEARCH_USAGE, |context, args| {
+        match args {
+            [] => {}
+            ["queue", id, rest @ ..] => {
+                let module = match rest.first() {
+                    Some(module) => module.parse().map_err(|_| anyhow!("invalid module '{}'", module))?,
+                    None => context
+                        .station
+                        .modules()
+                        .iter()
+                        .position(|module| module.module_type == ModuleType::Laboratory)
+                        .ok_or_else(|| anyhow!("no laboratory"))?,
+                };
+                context.research.enqueue(context.station, id, module)?;
+            }
+            ["cancel", index] => {
+                let index = index.parse().map_err(|_| anyhow!("invalid queue place '{}'", index))?;
+                if context.research.cancel(context.station, index).is_none() {
+                    bail!("nothing queued at {}", index);
+                }
+            }
+            ["unlock", id] => context.research.unlock(context.station, id)?,
+            _ => bail!("usage: {}", RESEARCH_USAGE),
+        }
+        let research = &*context.research;
+        let mut lines = vec![format!("{} research points", research.points())];
+        for (i, queued) in research.queue().iter().enumerate() {
+            let Some(experiment) = research.experiment(&queued.experiment) else {
+                continue;
+            };
+            let module = context.station.module_index(queued.module).map_or("-".to_string(), |m| m.to_string());
+            let percent = queued.progress / experiment.hours * 100.0;
+            lines.push(format!("{}: {} in module {}, {:.0}%", i, experiment.name, module, percent));
+        }
+        for experiment in research.experiments() {
+            lines.push(format!(
+                "experiment {}: {}, {:.0} h on the {:?}, {:.0} kW, {} points",
+                experiment.id, experiment.name, experiment.hours, experiment.bench, experiment.power, experiment.points
+            ));
+        }
+        for upgrade in research.upgrades() {
+            let state = if research.is_unlocked(&upgrade.id) { "fitted".to_string() } else { format!("{} points", upgrade.cost) };
+            lines.push(format!("upgrade {}: {} ({:?}), {}", upgrade.id, upgrade.name, upgrade.module, state));
+        }
+        Ok(lines.join("\n"))
+    });
+
+    console.register("say", "say <text>", |context, args| {
+        let name = context.chat.config().name.clone();
+        let message = context.chat.send(&name, Channel::Proximity, &args.join(" ")).map_err(|e| anyhow!(e.describe()))?;
+        Ok(message.line())
+    });
+
+    console.register("team", "team <text>", |context, args| {
+        let name = context.chat.config().name.clone();
+        let message = context.chat.send(&name, Channel::Team, &args.join(" ")).map_err(|e| anyhow!(e.describe()))?;
+        Ok(message.line())
+    });
+
+    console.register("emote", "emote <wave|point|thumbs_up|shrug|salute|cheer>", |context, args| {
+        let emote = args.first().and_then(|id| Emote::parse(id)).ok_or_else(|| {
+            let ids: Vec<&str> = Emote::ALL.iter().map(|emote| emote.id()).collect();
+            anyhow!("usage: emote <{}>", ids.join("|"))
+        })?;
+        let name = context.chat.config().name.clone();
+        let message = context.chat.emote(&name, emote).map_err(|e| anyhow!(e.describe()))?;
+        Ok(message.line())
+    });
+
+    // Others in the chat are dry-run members standing in modules, until
+    // something carries messages between machines
+    const CHAT_USAGE: &str = "chat | chat join <name> <team> [module] | chat leave <name> | chat as <name> <near|team|emote> <text> | chat mute <name> | chat unmute <name> | chat filter <on|off>";
+    console.register("chat", CHAT_USAGE, |context, args| {
+        match args {
+            [] => {}
+            ["join", name, team, rest @ ..] => {
+                let position = match rest.first() {
+                    Some(module) => {
+                        let index: usize = module.parse().map_err(|_| anyhow!("invalid module '{}'", module))?;
+                        let module = context.station.modules().get(index).ok_or_else(|| anyhow!("no module {}", index))?;
+                        module.transform.position
+                    }
+                    None => *context.player,
+                };
+                context.chat.join(name, team, position);
+            }
+            ["leave", name] => {
+                if !context.chat.leave(name) {
+                    bail!("'{}' isn't in the chat", name);
+                }
+            }
+            ["as", name, "emote", id] => {
+                let emote = Emote::parse(id).ok_or_else(|| anyhow!("no emote '{}'", id))?;
+                return Ok(context.chat.emote(name, emote).map_err(|e| anyhow!(e.describe()))?.line());
+            }
+            ["as", name, channel, text @ ..] => {
+                let channel = match *channel {
+                    "near" => Channel::Proximity,
+                    "team" => Channel::Team,
+                    _ => bail!("usage: {}", CHAT_USAGE),
+                };
+                let message = context.chat.send(name, channel, &text.join(" ")).map_err(|e| anyhow!(e.describe()))?;
+                return Ok(format!("{} (to {})", message.line(), message.recipients.join(", ")));
+            }
+            ["mute", name] => {
+                if !context.chat.set_muted(name, true) {
+                    bail!("'{}' is already muted", name);
+                }
+            }
+            ["unmute", name] => {
+                if !context.chat.set_muted(name, false) {
+                    bail!("'{}' isn't muted", name);
+                }
+            }
+            ["filter", "on"] => context.chat.set_filter(true),
+            ["filter", "off"] => context.chat.set_filter(false),
+            _ => bail!("usage: {}", CHAT_USAGE),
+        }
+        let chat = &*context.chat;
+        let filter = if chat.config().profanity_filter { "on" } else { "off" };
+        let mut lines = vec![format!("profanity filter {}, proximity {:.0} m", filter, chat.config().proximity_radius)];
+        for (name, team, position) in chat.members() {
+            let muted = if chat.is_muted(name) { ", muted" } else { "" };
+            lines.push(format!("{} ({}) at {}{}", name, team, position, muted));
+        }
+        Ok(lines.join("\n"))
+    });
+
+    // Changes the player's own look, kept in their profile, or with `as` a
+    // dry-run player's
+    const AVATAR_USAGE: &str = "avatar | avatar [as <name>] <suit|helmet|role|tag> <value>";
+    console.register("avatar", AVATAR_USAGE, |context, args| {
+        let own = context.chat.config().name.clone();
+        let (name, change) = match args {
+            ["as", name, change @ ..] => (name.to_string(), change),
+            change => (own.clone(), change),
+        };
+        let mut look = context.replicator.avatar(&name).cloned().unwrap_or_default();
+        match change {
+            [] => {}
+            ["suit", id] => look.suit = SuitScheme::parse(id).ok_or_else(|| anyhow!("no suit '{}'", id))?,
+            ["helmet", id] => look.helmet = Helmet::parse(id).ok_or_else(|| anyhow!("no helmet '{}'", id))?,
+            ["role", id] => look.role = Role::parse(id).ok_or_else(|| anyhow!("no role '{}'", id))?,
+            ["tag", text @ ..] => look.set_name_tag(&text.join(" ")),
+            _ => bail!("usage: {}", AVATAR_USAGE),
+        }
+        if !change.is_empty() {
+            if name == own {
+                avatar::save_look(context.profiles, &name, &look)?;
+            }
+            context.replicator.set_avatar(&name, look.clone());
+        }
+        let ids = |ids: Vec<&str>| ids.join(", ");
+        Ok(format!(
+            "{}: '{}', {} suit, {} helmet, {} patch\nsuits: {}\nhelmets: {}\nroles: {}",
+            name,
+            look.tag(&name),
+            look.suit.id(),
+            look.helmet.id(),
+            look.role.id(),
+            ids(SuitScheme::ALL.iter().map(|suit| suit.id()).collect()),
+            ids(Helmet::ALL.iter().map(|helmet| helmet.id()).collect()),
+            ids(Role::ALL.iter().map(|role| role.id()).collect()),
+        ))
+    });
+
+    console.register("markers", "markers", |context, _| {
+        let lines: Vec<String> = context
+            .markers
+            .markers()
+            .iter()
+            .map(|m| format!("{}: {} '{}' at {}", m.id, m.kind.id(), m.label, m.position()))
+            .collect();
+        Ok(lines.join("\n"))
+    });
+
+    console.register("marker", "marker add <kind> [label] | marker name <id> <label> | marker remove <id>", |context, args| {
+        let parse_id = |arg: Option<&&str>| -> Result<u32> {
+            arg.ok_or_else(|| anyhow!("missing marker id"))?
+                .parse()
+                .map_err(|_| anyhow!("marker id must be a whole number"))
+        };
+        match args {
+            ["add", kind, label @ ..] => {
+                let kind = MarkerKind::from_id(kind).ok_or_else(|| anyhow!("unknown marker kind '{}'", kind))?;
+                let label = if label.is_empty() { kind.id().to_string() } else { label.join(" ") };
+                let id = context.markers.place(kind, &label, *context.player);
+                Ok(format!("placed marker {} at {}", id, context.player))
+            }
+            ["name", id, label @ ..] if !label.is_empty() => {
+                let id = parse_id(Some(id))?;
+                if !context.markers.rename(id, &label.join(" ")) {
+                    bail!("no marker {}", id);
+                }
+                Ok(format!("renamed marker {}", id))
+            }
+            ["remove", rest @ ..] => {
+                let id = parse_id(rest.first())?;
+                let marker = context.markers.remove(id).ok_or_else(|| anyhow!("no marker {}", id))?;
+                Ok(format!("removed '{}'", marker.label))
+            }
+            _ => bail!("expected add, name or remove"),
+        }
+    });
+
+    console.register("modules", "modules", |context, _| {
+        let lines: Vec<String> = context
+            .station
+            .modules()
+            .iter()
+            .enumerate()
+            .map(|(i, m)| format!("{}: {:?} at {}", i, m.module_type, m.transform.position))
+            .collect();
+        Ok(lines.join("\n"))
+    });
+
+    console.register("graph", "graph <modules|power|atmosphere> <file.dot>", |context, args| {
+        let [kind, path] = args else {
+            bail!("expected a graph and a file");
+        };
+        let kind = GraphKind::parse(kind).ok_or_else(|| anyhow!("unknown graph '{}'", kind))?;
+        fs::write(path, context.station.export_graphviz(kind)).with_context(|| format!("failed to write {}", path))?;
+        Ok(format!("wrote {}", path))
+    });
+
+    console.register("export_gltf", "export_gltf <file.glb|file.gltf>", |context, args| {
+        let [path] = args else {
+            bail!("expected a file");
+        };
+        context.station.export_gltf(path)?;
+        Ok(format!("wrote {}", path))
+    });
+
+    console.register("record_usd", "record_usd start | stop <file.usda> | still <file.usda>", |context, args| {
+        match args {
+            ["start"] => {
+                context.scene_recorder.start();
+                Ok(format!("recording at {} fps", USD_FPS))
+            }
+            ["stop", path] => {
+                let frames = context.scene_recorder.stop().ok_or_else(|| anyhow!("not recording"))?;
+                usd::export(context.station, &frames, Path::new(path))?;
+                Ok(format!("wrote {} frames to {}", frames.len(), path))
+            }
+            ["still", path] => {
+                usd::export(context.station, &[], Path::new(path))?;
+                Ok(format!("wrote {}", path))
+            }
+            _ => bail!("expected start, stop or still"),
+        }
+    });
+
+    console.register("ignite", "ignite <module>", |context, args| {
+        let [module] = args else {
+            bail!("expected a module");
+        };
+        let index = parse_module(context.station, module)?;
+        if !context.station.ignite(index) {
+            bail!("module {} is already burning", index);
+        }
+        Ok(format!("fire started in module {}", index))
+    });
+
+    console.register("flare", "flare [intensity 0-1] [seconds]", |context, args| {
+        let intensity = match args.first() {
+            Some(intensity) => parse_f32(Some(intensity), "intensity")?,
+            None => 1.0,
+        };
+        let duration = match args.get(1) {
+            Some(duration) => parse_f32(Some(duration), "seconds")?,
+            None => 60.0,
+        };
+        context.station.begin_flare(intensity, duration);
+        Ok(format!("solar flare at {:.0}% for {}s", intensity.clamp(0.0, 1.0) * 100.0, duration))
+    });
+
+    console.register("incident", "incident <micrometeorite|flare|surge|malfunction>", |context, args| {
+        let [kind] = args else {
+            bail!("expected an incident kind");
+        };
+        let kind = IncidentKind::parse(kind).ok_or_else(|| anyhow!("unknown incident '{}'", kind))?;
+        let Some(incidents) = context.incidents.as_deref_mut() else {
+            bail!("no incident schedule loaded");
+        };
+        if !incidents.raise(kind, context.station, context.objectives) {
+            bail!("nothing on the station for a {} to happen to", kind.name().to_lowercase());
+        }
+        Ok(format!("{} raised", kind.name().to_lowercase()))
+    });
+
+    console.register("director", "director", |context, _| {
+        let Some(incidents) = context.incidents.as_deref() else {
+            bail!("no incident schedule loaded");
+        };
+        let director = incidents.director();
+        Ok(format!(
+            "{}, tension {:.0}%, pace x{:.2}, {} incident(s) open",
+            director.phase().name(),
+            director.tension() * 100.0,
+            director.escalation(&incidents.schedule().pacing),
+            incidents.active().len()
+        ))
+    });
+
+    console.register("shelter", "shelter <module|off>", |context, args| {
+        let module = match args {
+            ["off"] => None,
+            [module] => Some(parse_module(context.station, module)?),
+            _ => bail!("expected a module or off"),
+        };
+        context.station.set_storm_shelter(module);
+        Ok(match module {
+            Some(index) => format!("module {} is the storm shelter, {:.1} halvings", index, context.station.shielding(index)),
+            None => "storm shelter stood down".to_string(),
+        })
+    });
+
+    console.register("dose", "dose", |context, _| {
+        let outside = format!("outside {:.2} mSv/h", context.station.exterior_radiation() * 3600.0);
+        let doses = context.crew.members().iter().map(|member| format!("{}: {:.2} mSv", member.definition.name, member.dose));
+        Ok(std::iter::once(outside).chain(doses).collect::<Vec<_>>().join("\n"))
+    });
+
+    console.register("rings", "rings [ring rpm]", |context, args| {
+        if let [ring, rpm] = args {
+            let index = ring.parse().map_err(|_| anyhow!("invalid ring '{}'", ring))?;
+            if !context.station.set_ring_rpm(index, parse_f32(Some(rpm), "rpm")?) {
+                bail!("no ring {}", index);
+            }
+        }
+        let station = &*context.station;
+        let lines: Vec<String> = station
+            .ring_specs()
+            .iter()
+            .zip(station.rings())
+            .enumerate()
+            .map(|(i, (spec, ring))| {
+                let gravity: Vec<String> = spec
+                    .modules
+                    .iter()
+                    .filter_map(|&id| station.module_index(id))
+                    .map(|module| {
+                        let g = ring::in_g(ring.gravity(station.modules()[module].transform.position));
+                        format!("{} {:.2} g", module, g)
+                    })
+                    .collect();
+                format!("{}: {} at {:.1} rpm, {}", i, ring.name, ring.rpm, gravity.join(", "))
+            })
+            .collect();
+        Ok(if lines.is_empty() { "no ring sections".to_string() } else { lines.join("\n") })
+    });
+
+    // Sums up the playtest file, or writes the sums out for a spreadsheet
+    console.register("analytics", "analytics [export <file.csv>]", |context, args| {
+        let config = context.analytics.config();
+        if !config.path.exists() {
+            let hint = if config.enabled { "" } else { " (enabled = true under [analytics] to record)" };
+            bail!("nothing recorded in {}{}", config.path.display(), hint);
+        }
+        let summary = AnalyticsSummary::read(&config.path)?;
+        match args {
+            [] => Ok(summary.to_text()),
+            ["export", file] => {
+                summary.export_csv(Path::new(file))?;
+                Ok(format!("wrote {} sessions to {}", summary.sessions, file))
+            }
+            _ => bail!("usage: analytics [export <file.csv>]"),
+        }
+    });
+
+    console.register("lights", "lights [module on|off|percent]", |context, args| {
+        if let [module, setting] = args {
+            let module = parse_module(context.station, module)?;
+            let controls: Vec<usize> = context.station.modules()[module]
+                .interactive_elements
+                .iter()
+                .enumerate()
+                .filter(|(_, element)| element.element_type == InteractionType::LightControl)
+                .map(|(i, _)| i)
+                .collect();
+            if controls.is_empty() {
+                bail!("module {} has no light controls", module);
+            }
+            match *setting {
+                "on" | "off" => {
+                    let state = if *setting == "on" { ElementState::Active } else { ElementState::Inactive };
+                    for element in controls {
+                        context.station.set_element_state(module, element, state);
+                    }
+                }
+                _ => {
+                    let percent = parse_f32(Some(setting), "percent")?;
+                    context.station.set_light_level(module, percent / 100.0);
+                }
+            }
+        }
+        let lines: Vec<String> = context
+            .station
+            .modules()
+            .iter()
+            .enumerate()
+            .filter(|(_, module)| module.interactive_elements.iter().any(|e| e.element_type == InteractionType::LightControl))
+            .map(|(i, module)| {
+                let state = if module.lights_on() { "on" } else if module.powered { "off" } else { "no power" };
+                format!("{}: {:?} {} at {:.0}%", i, module.module_type, state, module.light_level * 100.0)
+            })
+            .collect();
+        Ok(lines.join("\n"))
+    });
+
+    // Hardest-pressed first
+    console.register("loads", "loads", |context, _| {
+        let mut joints = context.station.joints().to_vec();
+        joints.sort_by(|a, b| b.ratio().total_cmp(&a.ratio()));
+        let lines: Vec<String> = joints
+            .iter()
+            .map(|joint| {
+                let (a, b) = joint.modules;
+                let state = if joint.overloaded_for > 0.0 { " OVERLOADED" } else if joint.creaking { " creaking" } else { "" };
+                format!("{}-{}: {:.0} of {:.0} kN·m ({:.0}%){}", a, b, joint.load, joint.capacity, joint.ratio() * 100.0, state)
+            })
+            .collect();
+        Ok(if lines.is_empty() { "no connections".to_string() } else { lines.join("\n") })
+    });
+
+    console.register("remote", "remote <module> <element>", |context, args| {
+        let [module, element] = args else {
+            bail!("expected a module and an element");
+        };
+        let module = parse_module(context.station, module)?;
+        let element: usize = element.parse().map_err(|_| anyhow!("invalid element '{}'", element))?;
+        match context.station.remote_activate(module, element) {
+            Some(element_type) => Ok(format!("{:?} in module {} accepted the command", element_type, module)),
+            None => bail!("element {} in module {} didn't respond", element, module),
+        }
+    });
+
+    console.register("stores", "stores", |context, _| {
+        let ledger = context.station.resources();
+        let rates = context.station.resource_rates();
+        let lines: Vec<String> = Resource::ALL
+            .into_iter()
+            .zip(rates)
+            .map(|(resource, rate)| {
+                let total = ledger.total(resource);
+                format!("{}: {:.1}{} ({:+.2}/h)", resource.name(), total, resource.unit(), rate * 3600.0)
+            })
+            .collect();
+        Ok(lines.join("\n"))
+    });
+
+    console.register("transfer", "transfer <from> <to> <water|food|parts|o2> <amount>", |context, args| {
+        let [from, to, resource, amount] = args else {
+            bail!("expected two modules, a resource and an amount");
+        };
+        let from = parse_module(context.station, from)?;
+        let to = parse_module(context.station, to)?;
+        let resource = Resource::parse(resource).ok_or_else(|| anyhow!("unknown resource '{}'", resource))?;
+        let amount = parse_f32(Some(amount), "amount")?;
+        let moved = context.station.transfer_resource(from, to, resource, amount);
+        if moved <= 0.0 {
+            bail!("nothing moved; both modules need stores, and room at the far end");
+        }
+        Ok(format!("moved {:.1}{} {}", moved, resource.unit(), resource.name()))
+    });
+
+    console.register("crew", "crew", |context, _| {
+        let lines: Vec<String> = context
+            .crew
+            .members()
+            .iter()
+            .map(|member| {
+                format!(
+                    "{}: {} in module {} (sleep {:.0}%, food {:.0}%, air {:.0}%)",
+                    member.definition.name,
+                    member.activity.name(),
+                    numbered(context.station, member.module),
+                    member.sleep * 100.0,
+                    member.food * 100.0,
+                    member.oxygen * 100.0
+                )
+            })
+            .collect();
+        Ok(lines.join("\n"))
+    });
+
+    console.register("service", "service <module> <element>", |context, args| {
+        let [module, element] = args else {
+            bail!("expected a module and an element");
+        };
+        let module = parse_module(context.station, module)?;
+        let index: usize = element.parse().map_err(|_| anyhow!("invalid element '{}'", element))?;
+        let Some(element) = context.station.modules()[module].interactive_elements.get(index) else {
+            bail!("module {} has no element {}", module, index);
+        };
+        if element.element_type.service_hours().is_none() {
+            bail!("{:?} doesn't need servicing", element.element_type);
+        }
+        let id = context.station.modules()[module].id;
+        if !context.crew.schedule_maintenance(id, index) {
+            bail!("a service is already scheduled for it");
+        }
+        Ok(format!("service of {:?} in module {} scheduled", element.element_type, module))
+    });
+
+    // Lists the O2 generators, CO2 scrubbers and water recyclers, or switches
+    // one or fits it a new filter (a spare part)
+    console.register("life_support", "life_support [module unit on|off|filter]", |context, args| {
+        match args {
+            [] => {}
+            [module, unit, action] => {
+                let module = parse_module(context.station, module)?;
+                let index: usize = unit.parse().map_err(|_| anyhow!("invalid unit '{}'", unit))?;
+                let units = &context.station.modules()[module].life_support_units;
+                let Some(name) = units.get(index).map(|unit| unit.kind.name()) else {
+                    bail!("module {} has no unit {}", module, index);
+                };
+                match *action {
+                    "on" | "off" => {
+                        if !context.station.switch_life_support(module, index, *action == "on") {
+                            bail!("{} has failed; it needs a new filter", name);
+                        }
+                    }
+                    "filter" => {
+                        if !context.station.replace_filter(module, index) {
+                            bail!("no spare parts for a filter");
+                        }
+                    }
+                    other => bail!("expected on, off or filter, got '{}'", other),
+                }
+            }
+            _ => bail!("usage: life_support [module unit on|off|filter]"),
+        }
+        let lines: Vec<String> = context
+            .station
+            .modules()
+            .iter()
+            .enumerate()
+            .flat_map(|(i, module)| {
+                module.life_support_units.iter().enumerate().map(move |(j, unit)| {
+                    format!(
+                        "[{}:{}] {} {:?}: throughput {:.2}, filter {:.0}%, {:.1} kW",
+                        i,
+                        j,
+                        unit.kind.name(),
+                        unit.state,
+                        unit.throughput,
+                        unit.filter * 100.0,
+                        unit.power_draw(),
+                    )
+                })
+            })
+            .collect();
+        Ok(if lines.is_empty() { "no life support hardware".to_string() } else { lines.join("\n") })
+    });
+
+    console.register("tasks", "tasks", |context, _| {
+        let lines: Vec<String> = context
+            .crew
+            .tasks()
+            .iter()
+            .map(|task| {
+                let assignee = task
+                    .assignee
+                    .and_then(|i| context.crew.members().get(i))
+                    .map_or("unassigned", |member| member.definition.name.as_str());
+                let module = numbered(context.station, task.module);
+                format!("element {} in module {}: {:.0}% ({})", task.element, module, task.progress * 100.0, assignee)
+            })
+            .collect();
+        if lines.is_empty() {
+            return Ok("no maintenance scheduled".to_string());
+        }
+        Ok(lines.join("\n"))
+    });
+
+    console.register("grime", "grime", |context, _| {
+        let lines: Vec<String> = context
+            .station
+            .modules()
+            .iter()
+            .enumerate()
+            .filter(|(_, module)| module.worst_grime() > 0.0)
+            .map(|(i, module)| format!("module {} {:?}: worst {:.0}%", i, module.module_type, module.worst_grime() * 100.0))
+            .collect();
+        if lines.is_empty() {
+            return Ok("the station is spotless".to_string());
+        }
+        Ok(lines.join("\n"))
+    });
+
+    console.register("palette", "palette", |context, _| {
+        let lines: Vec<String> = context
+            .style_guide
+            .swatches()
+            .iter()
+            .map(|swatch| {
+                let surfaces: Vec<&str> = swatch.surfaces.iter().map(|surface| surface.id()).collect();
+                format!("{:<16} {}", swatch.name, surfaces.join(", "))
+            })
+            .collect();
+        if lines.is_empty() {
+            return Ok("the style guide is empty".to_string());
+        }
+        Ok(lines.join("\n"))
+    });
+
+    console.register("paint", "paint <module> <walls|trim> <swatch|none>", |context, args| {
+        let [module, surface, swatch] = args else {
+            bail!("expected a module, a surface and a swatch");
+        };
+        let module = parse_module(context.station, module)?;
+        let surface = Surface::parse(surface).ok_or_else(|| anyhow!("unknown surface '{}'", surface))?;
+        if *swatch == "none" {
+            context.station.strip_paint(module, surface);
+            return Ok(format!("module {} {} back to standard", module, surface.id()));
+        }
+        let Some(swatch) = context.style_guide.get(swatch) else {
+            bail!("'{}' isn't in the style guide", swatch);
+        };
+        if !context.station.paint_module(module, surface, swatch) {
+            bail!("the style guide doesn't allow {} on {}", swatch.name, surface.id());
+        }
+        Ok(format!("module {} {} painted {}", module, surface.id(), swatch.name))
+    });
+
+    console.register("spill", "spill <module> [litres] [water|coolant]", |context, args| {
+        let Some((module, rest)) = args.split_first() else {
+            bail!("expected a module");
+        };
+        let index = parse_module(context.station, module)?;
+        let litres = match rest.first() {
+            Some(litres) => parse_f32(Some(litres), "litres")?,
+            None => 2.0,
+        };
+        let liquid = match rest.get(1).copied() {
+            None | Some("water") => Liquid::Water,
+            Some("coolant") => Liquid::Coolant,
+            Some(other) => bail!("unknown liquid '{}'", other),
+        };
+        let module = &context.station.modules()[index];
+        let position = module.transform.position + Vec3::Y * 1.5;
+        context.spills.spill(module.id, liquid, position, Vec3::ZERO, litres);
+        Ok(format!("spilled {} L of {} in module {}", litres, liquid.name(), index))
+    });
+
+    console.register("prop", "prop <name> <speed|off>", |context, args| {
+        let [name, speed] = args else {
+            bail!("expected a prop and a speed");
+        };
+        let index = context.props.find(name).ok_or_else(|| anyhow!("no prop named {}", name))?;
+        let speed = match *speed {
+            "off" => None,
+            _ => Some(parse_f32(Some(speed), "speed")?),
+        };
+        if !context.props.drive(index, speed) {
+            bail!("{} has no motor", name);
+        }
+        Ok(match speed {
+            Some(speed) => format!("{} driven at {}", name, speed),
+            None => format!("{} motor off", name),
+        })
+    });
+
+    console.register("panorama", "panorama <file.png> [stereo] [face size]", |context, args| {
+        let Some((path, options)) = args.split_first() else {
+            bail!("expected a file");
+        };
+        if context.panorama.is_some() {
+            bail!("a panorama is already being taken");
+        }
+        let stereo = options.first() == Some(&"stereo");
+        let face_size = match options.get(usize::from(stereo)) {
+            Some(size) => size
+                .parse::<u32>()
+                .ok()
+                .filter(|&size| size >= 16)
+                .ok_or_else(|| anyhow!("bad face size '{}'", size))?,
+            None => DEFAULT_FACE_SIZE,
+        };
+        *context.panorama = Some(PanoramaCapture::new(path.into(), face_size, stereo));
+        Ok(format!("taking a {}x{} panorama", face_size * 4, face_size * if stereo { 4 } else { 2 }))
+    });
+}
@@ -0,0 +1,329 @@
+use glam::Vec3;
+use crate::crew::Crew;
+use crate::event_log::LogSeverity;
+use crate::ports::DockingPort;
+use crate::procgen::{flush_position, overlaps};
+use crate::resources::Resource;
+use crate::station::{ModuleType, SpaceStation, StationModule, MAX_CONNECTION_DISTANCE};
+
+// What build mode offers, in the order the wheel goes through them. There's
+// only ever the one command centre.
+pub const BUILDABLE: [ModuleType; 10] = [
+    ModuleType::Corridor,
+    ModuleType::Hub,
+    ModuleType::Airlock,
+    ModuleType::LivingQuarters,
+    ModuleType::Laboratory,
+    ModuleType::Storage,
+    ModuleType::EscapePod,
+    ModuleType::SolarArray,
+    ModuleType::Battery,
+    ModuleType::PowerPlant,
+];
+
+const SIDES: [Vec3; 4] = [Vec3::NEG_Z, Vec3::X, Vec3::Z, Vec3::NEG_X];
+// Share of its cost a module gives back taken down intact
+const SALVAGE_SHARE: f32 = 0.5;
+// How squarely you have to face a neighbour to take it down
+const SALVAGE_AIM: f32 = 0.7;
+
+// Spare parts a module takes to build
+pub fn build_cost(module_type: ModuleType) -> f32 {
+    match module_type {
+        ModuleType::Corridor => 4.0,
+        ModuleType::Hub => 6.0,
+        ModuleType::Airlock | ModuleType::EscapePod | ModuleType::SolarArray => 8.0,
+        _ => 12.0,
+    }
+}
+
+// Spare parts back for taking a module down, fewer the worse it's damaged
+pub fn salvage(module_type: ModuleType, structural_integrity: f32) -> f32 {
+    (build_cost(module_type) * SALVAGE_SHARE * structural_integrity.clamp(0.0, 1.0)).floor()
+}
+
+// Why the ghost can't be built where it is, or a module can't be taken down
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildProblem {
+    // No free docking port facing that way
+    NoPort,
+    TooFar,
+    // It would overlap another module
+    Blocked,
+    CantAfford,
+    // Nothing connected that way to take down
+    NoTarget,
+    // The command centre stays
+    Essential,
+    // Taking it would cut other modules off
+    Strands,
+    // Someone's in it, headed there, or lives or works there
+    Occupied,
+}
+
+impl BuildProblem {
+    // Locale key for the message
+    pub fn id(self) -> &'static str {
+        match self {
+            BuildProblem::NoPort => "build.no_port",
+            BuildProblem::TooFar => "build.too_far",
+            BuildProblem::Blocked => "build.blocked",
+            BuildProblem::CantAfford => "build.cant_afford",
+            BuildProblem::NoTarget => "build.no_target",
+            BuildProblem::Essential => "build.essential",
+            BuildProblem::Strands => "build.strands",
+            BuildProblem::Occupied => "build.occupied",
+        }
+    }
+}
+
+// Where the module would go: wall to wall with `host` on the `direction` side
+#[derive(Debug, Clone)]
+pub struct Ghost {
+    pub module_type: ModuleType,
+    pub host: usize,
+    pub direction: Vec3,
+    pub position: Vec3,
+    // Min and max corners
+    pub bounds: (Vec3, Vec3),
+    pub problem: Option<BuildProblem>,
+}
+
+// The free docking ports on a module, placed, for something to be built onto
+pub fn ports(station: &SpaceStation, module: usize) -> Vec<DockingPort> {
+    let Some(host) = station.modules().get(module) else {
+        return Vec::new();
+    };
+    let all = host.module_type.docking_ports();
+    station.free_ports(module).into_iter().map(|port| all[port].placed(host)).collect()
+}
+
+// Build mode: pick a module type, aim at a side of the module you're in and
+// build onto it, paying in spare parts
+#[derive(Debug)]
+pub struct Construction {
+    pub active: bool,
+    selected: usize,
+}
+
+impl Default for Construction {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Construction {
+    pub fn new() -> Self {
+        Self { active: false, selected: 0 }
+    }
+
+    pub fn toggle(&mut self) {
+        self.active = !self.active;
+    }
+
+    pub fn module_type(&self) -> ModuleType {
+        BUILDABLE[self.selected]
+    }
+
+    // Steps through BUILDABLE, wrapping round
+    pub fn cycle(&mut self, steps: i32) {
+        let count = BUILDABLE.len() as i32;
+        self.selected = (self.selected as i32 + steps).rem_euclid(count) as usize;
+    }
+
+    // The ghost off whichever side of the module at `eye` is most in the
+    // direction of `look`
+    pub fn ghost(&self, station: &SpaceStation, eye: Vec3, look: Vec3) -> Option<Ghost> {
+        let host = station.nearest_module(eye)?;
+        let flat = Vec3::new(look.x, 0.0, look.z);
+        let direction = SIDES.into_iter().max_by(|a, b| a.dot(flat).total_cmp(&b.dot(flat)))?;
+        Some(self.ghost_at(station, host, direction))
+    }
+
+    pub fn ghost_at(&self, station: &SpaceStation, host: usize, direction: Vec3) -> Ghost {
+        let module_type = self.module_type();
+        let (position, reach) = flush_position(station, host, direction, module_type);
+        let bounds = StationModule::new(module_type, position).bounds();
+        let free = ports(station, host).iter().any(|port| port.facing.dot(direction) > 0.99);
+        let problem = if !free {
+            Some(BuildProblem::NoPort)
+        } else if reach > MAX_CONNECTION_DISTANCE {
+            Some(BuildProblem::TooFar)
+        } else if station.modules().iter().any(|module| overlaps(&bounds, &module.bounds())) {
+            Some(BuildProblem::Blocked)
+        } else if station.resources().total(Resource::SpareParts) < build_cost(module_type) {
+            Some(BuildProblem::CantAfford)
+        } else {
+            None
+        };
+        Ghost { module_type, host, direction, position, bounds, problem }
+    }
+
+    // Pays for the ghost's module and builds it, connected to its host
+    pub fn build(&self, station: &mut SpaceStation, ghost: &Ghost) -> Result<usize, BuildProblem> {
+        if let Some(problem) = ghost.problem {
+            return Err(problem);
+        }
+        if !station.spend_resource(Resource::SpareParts, build_cost(ghost.module_type)) {
+            return Err(BuildProblem::CantAfford);
+        }
+        let module = station.add_module(ghost.module_type, ghost.position);
+        station.connect_modules(ghost.host, module);
+        station.log_event(
+            LogSeverity::Info,
+            format!("Built a {:?} onto module {} as module {}", ghost.module_type, ghost.host, module),
+        );
+        Ok(module)
+    }
+
+    // The neighbour of the module at `eye` that `look` is most squarely
+    // towards, to be taken down
+    pub fn salvage_target(&self, station: &SpaceStation, eye: Vec3, look: Vec3) -> Option<usize> {
+        let host = station.nearest_module(eye)?;
+        let modules = station.modules();
+        let flat = Vec3::new(look.x, 0.0, look.z).normalize_or_zero();
+        let here = modules[host].transform.position;
+        station
+            .neighbours(host)
+            .into_iter()
+            .map(|other| (other, (modules[other].transform.position - here).normalize_or_zero().dot(flat)))
+            .filter(|&(_, aim)| aim >= SALVAGE_AIM)
+            .max_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(other, _)| other)
+    }
+
+    // Takes a module down and puts what it salvages in stores; returns the
+    // parts recovered
+    pub fn deconstruct(&self, station: &mut SpaceStation, crew: &Crew, module: usize) -> Result<f32, BuildProblem> {
+        let Some(target) = station.modules().get(module) else {
+            return Err(BuildProblem::NoTarget);
+        };
+        if target.module_type == ModuleType::CommandCenter {
+            return Err(BuildProblem::Essential);
+        }
+        if !station.would_strand(module).is_empty() {
+            return Err(BuildProblem::Strands);
+        }
+        if crew.relies_on(target.id) {
+            return Err(BuildProblem::Occupied);
+        }
+        let Some(removal) = station.remove_module(module) else {
+            return Err(BuildProblem::NoTarget);
+        };
+        let parts = salvage(removal.module_type, removal.structural_integrity);
+        let stored = station.stow_anywhere(Resource::SpareParts, parts);
+        station.log_event(
+            LogSeverity::Info,
+            format!("Took down a {:?}, salvaging {:.0} spare parts", removal.module_type, stored),
+        );
+        Ok(stored)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crew::CrewDefinition;
+    use crate::events::StationEvent;
+    use crate::station::InteractionType;
+
+    fn lab_hand(shift_start: f32) -> CrewDefinition {
+        CrewDefinition { name: "Lindqvist".to_string(), quarters: 6, workstation: 5, shift_start }
+    }
+
+    #[test]
+    fn build_mode_builds_onto_free_ports_and_pays_in_parts() {
+        let mut station = SpaceStation::create_default_layout();
+        let mut build = Construction::new();
+        // The lab connects north and south, the airlock is full
+        let lab_ports: Vec<Vec3> = ports(&station, 5).iter().map(|p| p.facing).collect();
+        assert_eq!(lab_ports, vec![Vec3::X, Vec3::NEG_X]);
+        assert!(ports(&station, 9).is_empty());
+        assert_eq!(build.ghost_at(&station, 5, Vec3::Z).problem, Some(BuildProblem::NoPort));
+
+        // Facing east from inside the lab builds a corridor onto its east side
+        let ghost = build.ghost(&station, Vec3::new(0.0, 1.5, -16.0), Vec3::new(0.9, 0.0, 0.2)).unwrap();
+        assert_eq!((ghost.host, ghost.direction, ghost.problem), (5, Vec3::X, None));
+        let parts = station.resources().total(Resource::SpareParts);
+        let corridor = build.build(&mut station, &ghost).unwrap();
+        assert_eq!(station.modules()[corridor].module_type, ModuleType::Corridor);
+        assert!(station.neighbours(corridor).contains(&5));
+        assert_eq!(station.resources().total(Resource::SpareParts), parts - build_cost(ModuleType::Corridor));
+        assert!(station.door_between(5, corridor).is_some());
+        assert_eq!(build.ghost_at(&station, 5, Vec3::X).problem, Some(BuildProblem::NoPort));
+
+        // Something already standing where it would go
+        let spot = build.ghost_at(&station, 5, Vec3::NEG_X).position;
+        let mut crowded = SpaceStation::create_default_layout();
+        crowded.add_module(ModuleType::Corridor, spot);
+        assert_eq!(build.ghost_at(&crowded, 5, Vec3::NEG_X).problem, Some(BuildProblem::Blocked));
+
+        // Two big modules can't be wall to wall and still connect
+        build.cycle(-1);
+        assert_eq!(build.module_type(), ModuleType::PowerPlant);
+        assert_eq!(build.ghost_at(&station, 5, Vec3::NEG_X).problem, Some(BuildProblem::TooFar));
+        build.cycle(1);
+        station.spend_resource(Resource::SpareParts, station.resources().total(Resource::SpareParts));
+        let ghost = build.ghost_at(&station, 5, Vec3::NEG_X);
+        assert_eq!(ghost.problem, Some(BuildProblem::CantAfford));
+        assert_eq!(build.build(&mut station, &ghost), Err(BuildProblem::CantAfford));
+    }
+
+    #[test]
+    fn removing_a_module_vents_open_neighbours_and_refunds_in_build_mode() {
+        let mut station = SpaceStation::create_default_layout();
+        assert_eq!(station.would_strand(5), vec![9]);
+        assert_eq!(station.would_strand(7), vec![11]);
+        station.drain_events();
+
+        // The lab's hatch onto the airlock is open, so the lab loses air
+        let lab_oxygen = station.modules()[5].atmosphere.oxygen_level();
+        let lab_elements = station.modules()[5].interactive_elements.len();
+        let removal = station.remove_module(9).unwrap();
+        assert_eq!((removal.module_type, removal.vented.clone(), removal.stranded.clone()), (ModuleType::Airlock, vec![5], vec![]));
+        assert!(station.modules()[5].atmosphere.oxygen_level() < lab_oxygen);
+        assert!(station.drain_events().iter().any(|e| matches!(e, StationEvent::Decompression { module: 5, .. })));
+        assert_eq!(station.modules()[5].interactive_elements.len(), lab_elements - 1);
+        assert_eq!(station.neighbours(5), vec![1]);
+        assert_eq!((station.modules().len(), station.doors().len(), station.connectors().len()), (11, 10, 10));
+        for (index, door) in station.doors().iter().enumerate() {
+            let (first, _) = station.door_modules(index).unwrap();
+            let element = &station.modules()[first].interactive_elements[door.element];
+            assert_eq!(element.element_type, InteractionType::Door);
+        }
+        assert!(station.modules().iter().flat_map(|m| &m.connected_modules).all(|&other| station.module_index(other).is_some()));
+
+        // The pods moved down one; shutting the stores' hatch first keeps its air in
+        let (quarters_pod, stores_pod) = (9, 10);
+        assert_eq!(station.modules()[stores_pod].module_type, ModuleType::EscapePod);
+        let door = station.door_between(7, stores_pod).unwrap();
+        station.set_door_locked(door, true);
+        let stores_oxygen = station.modules()[7].atmosphere.oxygen_level();
+        assert!(station.remove_module(stores_pod).unwrap().vented.is_empty());
+        assert_eq!(station.modules()[7].atmosphere.oxygen_level(), stores_oxygen);
+
+        // Build mode won't take what's essential, load-bearing or in use
+        let build = Construction::new();
+        let mut crew = Crew::new();
+        crew.add(lab_hand(8.0), &station).unwrap();
+        assert_eq!(build.deconstruct(&mut station, &crew, 0), Err(BuildProblem::Essential));
+        assert_eq!(build.deconstruct(&mut station, &crew, 2), Err(BuildProblem::Strands));
+        assert_eq!(build.deconstruct(&mut station, &crew, 5), Err(BuildProblem::Occupied));
+        assert_eq!(build.deconstruct(&mut station, &crew, 12), Err(BuildProblem::NoTarget));
+        let eye = Vec3::new(16.0, 1.5, 0.0);
+        assert_eq!(build.salvage_target(&station, eye, Vec3::X), Some(quarters_pod));
+        let parts = station.resources().total(Resource::SpareParts);
+        let salvaged = build.deconstruct(&mut station, &crew, quarters_pod).unwrap();
+        assert_eq!(salvaged, salvage(ModuleType::EscapePod, 1.0));
+        assert_eq!(station.resources().total(Resource::SpareParts), parts + salvaged);
+        assert_eq!(station.module_index(crew.members()[0].quarters), Some(6));
+
+        // Taking the stores down carries their stock out first
+        let mut station = SpaceStation::create_default_layout();
+        station.add_module(ModuleType::Storage, Vec3::new(100.0, 0.0, 0.0));
+        let water = station.resources().total(Resource::Water);
+        station.remove_module(7).unwrap();
+        assert_eq!(station.resources().stores()[0].module, station.modules()[11].id);
+        assert_eq!(station.resources().total(Resource::Water), water);
+    }
+}
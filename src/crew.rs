@@ -0,0 +1,775 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::path::Path;
+use anyhow::{bail, Context, Result};
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+use crate::clock::MissionTime;
+use crate::event_log::LogSeverity;
+use crate::grime;
+use crate::module_id::ModuleId;
+use crate::navigation::passable;
+use crate::radiation::DoseLevel;
+use crate::resources::Resource;
+use crate::station::SpaceStation;
+
+// The crew keep a compressed day, an hour to the minute, so their routine
+// plays out within a session
+pub const CREW_HOUR: f32 = 60.0;
+pub const WALK_SPEED: f32 = 1.4;
+// Oxygen level below which crew leave for better air
+const GASPING: f32 = 0.6;
+// Needs at which crew drop what they're doing to sleep or eat
+const EXHAUSTED: f32 = 0.05;
+const STARVING: f32 = 0.15;
+// How long needs take to run down and to be met, in crew hours
+const HOURS_AWAKE: f32 = 18.0;
+const HOURS_ASLEEP: f32 = 8.0;
+const HOURS_BETWEEN_MEALS: f32 = 20.0;
+const HOURS_EATING: f32 = 0.5;
+// Crew hours a service takes at full effectiveness
+const HOURS_SERVICING: f32 = 1.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Activity {
+    Working,
+    Eating,
+    Resting,
+    Sleeping,
+    // Leaving a module whose air is running out
+    Fleeing,
+    // Sitting out a flare in the storm shelter
+    Sheltering,
+    // On a maintenance task in place of their usual work
+    Servicing,
+    // Cleaning a grimy module, likewise
+    Cleaning,
+    // Heading for an escape pod, and strapped in aboard one
+    Evacuating,
+    Evacuated,
+}
+
+impl Activity {
+    pub fn name(self) -> &'static str {
+        match self {
+            Activity::Working => "working",
+            Activity::Eating => "eating",
+            Activity::Resting => "resting",
+            Activity::Sleeping => "sleeping",
+            Activity::Fleeing => "fleeing",
+            Activity::Sheltering => "sheltering",
+            Activity::Servicing => "servicing",
+            Activity::Cleaning => "cleaning",
+            Activity::Evacuating => "evacuating",
+            Activity::Evacuated => "evacuated",
+        }
+    }
+}
+
+// A crew member as the crew file describes them
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CrewDefinition {
+    pub name: String,
+    // Module indices they sleep and eat in, and work in, as the station
+    // stands when they're added
+    pub quarters: usize,
+    pub workstation: usize,
+    // Crew hour their work shift starts
+    #[serde(default = "default_shift_start")]
+    pub shift_start: f32,
+}
+
+fn default_shift_start() -> f32 {
+    8.0
+}
+
+#[derive(Debug, Deserialize)]
+struct CrewFile {
+    #[serde(rename = "crew", default)]
+    crew: Vec<CrewDefinition>,
+}
+
+// Needs run 0, desperate, to 1, met
+#[derive(Debug, Clone)]
+pub struct CrewMember {
+    pub definition: CrewDefinition,
+    pub quarters: ModuleId,
+    pub workstation: ModuleId,
+    pub module: ModuleId,
+    pub position: Vec3,
+    pub activity: Activity,
+    pub sleep: f32,
+    pub food: f32,
+    pub oxygen: f32,
+    // Radiation taken in so far, in mSv
+    pub dose: f32,
+    destination: ModuleId,
+    // Modules still to pass through to reach it
+    route: VecDeque<ModuleId>,
+    // The module they're cleaning, until it's clean or their shift ends
+    cleaning: Option<ModuleId>,
+    // Where an evacuation has sent them, ahead of anything else
+    muster: Option<ModuleId>,
+}
+
+impl CrewMember {
+    // How much use they are at work: fine until a need is half gone
+    pub fn effectiveness(&self) -> f32 {
+        (self.sleep.min(self.food).min(self.oxygen) * 2.0).min(1.0)
+    }
+
+    pub fn arrived(&self) -> bool {
+        self.module == self.destination && self.route.is_empty()
+    }
+
+    pub fn destination(&self) -> ModuleId {
+        self.destination
+    }
+
+    // What the schedule has them doing at `hour`: an 8 hour shift, a meal,
+    // time off, 8 hours' sleep and breakfast
+    pub fn scheduled(&self, hour: f32) -> Activity {
+        let into_day = (hour - self.definition.shift_start).rem_euclid(24.0);
+        match into_day {
+            h if h < 8.0 => Activity::Working,
+            h if h < 9.0 => Activity::Eating,
+            h if h < 15.0 => Activity::Resting,
+            h if h < 23.0 => Activity::Sleeping,
+            _ => Activity::Eating,
+        }
+    }
+}
+
+// Hour of the crew day, 0 to 24
+pub fn crew_hour(now: MissionTime) -> f32 {
+    (now.as_secs_f32() / CREW_HOUR) % 24.0
+}
+
+// A service ordered for an element. Whoever's on shift first takes it on
+// instead of their usual work, and hands it back if their shift ends first.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MaintenanceTask {
+    pub module: ModuleId,
+    pub element: usize,
+    pub assignee: Option<usize>,
+    // 0 to 1
+    pub progress: f32,
+}
+
+// Everyone aboard and the services queued, for saves
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CrewState {
+    pub members: Vec<SavedCrewMember>,
+    pub tasks: Vec<MaintenanceTask>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedCrewMember {
+    pub definition: CrewDefinition,
+    pub quarters: ModuleId,
+    pub workstation: ModuleId,
+    pub module: ModuleId,
+    pub position: [f32; 3],
+    pub activity: Activity,
+    pub sleep: f32,
+    pub food: f32,
+    pub oxygen: f32,
+    pub dose: f32,
+    pub destination: ModuleId,
+    pub route: Vec<ModuleId>,
+    #[serde(default)]
+    pub cleaning: Option<ModuleId>,
+    #[serde(default)]
+    pub muster: Option<ModuleId>,
+}
+
+// The station's crew. They go where their schedule or their needs send
+// them, and the station sees them as each module's head count and as how
+// well their workstations are run.
+#[derive(Debug, Default)]
+pub struct Crew {
+    members: Vec<CrewMember>,
+    tasks: Vec<MaintenanceTask>,
+}
+
+impl Crew {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P, station: &SpaceStation) -> Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("failed to read crew from {}", path.display()))?;
+        let file: CrewFile = toml::from_str(&source).context("invalid crew definitions")?;
+
+        let mut crew = Self::new();
+        for definition in file.crew {
+            if let Err(err) = crew.add(definition, station) {
+                eprintln!("Warning: {:#}", err);
+            }
+        }
+        Ok(crew)
+    }
+
+    // They start the day rested and fed, in their quarters
+    pub fn add(&mut self, definition: CrewDefinition, station: &SpaceStation) -> Result<usize> {
+        let (Some(quarters), Some(workstation)) =
+            (station.module_id(definition.quarters), station.module_id(definition.workstation))
+        else {
+            bail!("{} is assigned to a missing module", definition.name);
+        };
+        let index = self.members.len();
+        let module = quarters;
+        self.members.push(CrewMember {
+            position: spot(station, definition.quarters, index),
+            definition,
+            quarters,
+            workstation,
+            module,
+            activity: Activity::Resting,
+            sleep: 1.0,
+            food: 1.0,
+            oxygen: 1.0,
+            dose: 0.0,
+            destination: module,
+            route: VecDeque::new(),
+            cleaning: None,
+            muster: None,
+        });
+        Ok(index)
+    }
+
+    pub fn members(&self) -> &[CrewMember] {
+        &self.members
+    }
+
+    pub fn tasks(&self) -> &[MaintenanceTask] {
+        &self.tasks
+    }
+
+    // Queues a service; false if one's already queued for the element
+    pub fn schedule_maintenance(&mut self, module: ModuleId, element: usize) -> bool {
+        if self.tasks.iter().any(|task| task.module == module && task.element == element) {
+            return false;
+        }
+        self.tasks.push(MaintenanceTask { module, element, assignee: None, progress: 0.0 });
+        true
+    }
+
+    // Sends a crew member to `module` whatever their schedule or needs say,
+    // or back to their routine with None
+    pub fn muster(&mut self, index: usize, module: Option<ModuleId>) {
+        if let Some(member) = self.members.get_mut(index) {
+            member.muster = module;
+        }
+    }
+
+    // Straps a mustered crew member in where they are; they go nowhere and
+    // need nothing from then on
+    pub fn board(&mut self, index: usize) {
+        if let Some(member) = self.members.get_mut(index) {
+            member.activity = Activity::Evacuated;
+            member.route.clear();
+            member.destination = member.module;
+        }
+    }
+
+    // Calls off an evacuation: everyone goes back to their routine, those
+    // already aboard a pod included
+    pub fn stand_down(&mut self) {
+        for member in &mut self.members {
+            member.muster = None;
+            if member.activity == Activity::Evacuated {
+                member.activity = Activity::Resting;
+            }
+        }
+    }
+
+    // Whether anyone's in a module, on their way there, or lives or works in it
+    pub fn relies_on(&self, module: ModuleId) -> bool {
+        self.members.iter().any(|member| {
+            member.module == module
+                || member.destination == module
+                || member.route.contains(&module)
+                || member.quarters == module
+                || member.workstation == module
+        })
+    }
+
+    pub fn update(&mut self, station: &mut SpaceStation, delta_time: f32) {
+        let hour = crew_hour(station.clock().now());
+        let stocked = station.resources().total(Resource::Food) > 0.0;
+        let parts = station.resources().total(Resource::SpareParts) >= 1.0;
+        let shelter = station.storm_shelter().filter(|_| station.interference().active());
+        let mut serviced = Vec::new();
+        let mut cleaning: Vec<ModuleId> = self.members.iter().filter_map(|member| member.cleaning).collect();
+        for (index, member) in self.members.iter_mut().enumerate() {
+            // Anyone left in a module that's gone is stuck
+            let Some(at) = station.module_index(member.module) else {
+                continue;
+            };
+            let here = &station.modules()[at];
+            if member.activity == Activity::Evacuated {
+                continue;
+            }
+            member.oxygen = here.atmosphere.oxygen_level().clamp(0.0, 1.0);
+            let level = DoseLevel::of(member.dose);
+            member.dose += station.radiation(at) * delta_time;
+            if DoseLevel::of(member.dose) > level {
+                let severity = if level == DoseLevel::Normal { LogSeverity::Warning } else { LogSeverity::Critical };
+                let message = format!("{} has taken {:.0} mSv of radiation", member.definition.name, member.dose);
+                station.log_event(severity, message);
+                if DoseLevel::of(member.dose) == DoseLevel::Danger {
+                    station.record_injury();
+                }
+            }
+
+            // An evacuation before anything, then needs before the schedule:
+            // air, shelter from a flare, then sleep, then food
+            let (activity, destination) = if let Some(pod) = member.muster {
+                (Activity::Evacuating, pod)
+            } else if member.oxygen < GASPING {
+                (Activity::Fleeing, best_air(station, at))
+            } else if let Some(shelter) = shelter.and_then(|shelter| station.module_id(shelter)) {
+                (Activity::Sheltering, shelter)
+            } else if member.sleep <= EXHAUSTED {
+                (Activity::Sleeping, member.quarters)
+            } else if member.food <= STARVING && stocked {
+                (Activity::Eating, member.quarters)
+            } else {
+                let activity = member.scheduled(hour);
+                let task = (activity == Activity::Working && parts)
+                    .then(|| claim(&mut self.tasks, index))
+                    .flatten();
+                match (activity, task) {
+                    (_, Some(task)) => (Activity::Servicing, self.tasks[task].module),
+                    (Activity::Working, None) => match janitor(member, station, &mut cleaning) {
+                        Some(module) => (Activity::Cleaning, module),
+                        None => (activity, member.workstation),
+                    },
+                    _ => (activity, member.quarters),
+                }
+            };
+            member.activity = activity;
+            if activity != Activity::Cleaning {
+                if let Some(module) = member.cleaning.take() {
+                    cleaning.retain(|&other| other != module);
+                }
+            }
+            if activity != Activity::Servicing {
+                for task in self.tasks.iter_mut().filter(|task| task.assignee == Some(index)) {
+                    task.assignee = None;
+                }
+            }
+            let blocked = member
+                .route
+                .front()
+                .is_some_and(|&next| station.module_index(next).is_none_or(|next| !passable(station, at, next)));
+            if destination != member.destination || blocked {
+                // Nowhere to go stays put
+                let found = station.module_index(destination).and_then(|to| station.find_path(at, to));
+                member.destination = if found.is_some() { destination } else { member.module };
+                member.route = found
+                    .map(|path| path.into_iter().skip(1).filter_map(|i| station.module_id(i)).collect())
+                    .unwrap_or_default();
+            }
+            let from = member.position;
+            walk(member, station, index, delta_time);
+            station.tread(member.position, from.distance(member.position));
+
+            // Needs run down, and are met by doing the right thing in the right place
+            let settled = member.arrived();
+            if activity == Activity::Sleeping && settled {
+                member.sleep += delta_time / (HOURS_ASLEEP * CREW_HOUR);
+            } else {
+                member.sleep -= delta_time / (HOURS_AWAKE * CREW_HOUR);
+            }
+            if activity == Activity::Eating && settled && stocked {
+                member.food += delta_time / (HOURS_EATING * CREW_HOUR);
+            } else {
+                member.food -= delta_time / (HOURS_BETWEEN_MEALS * CREW_HOUR);
+            }
+            member.sleep = member.sleep.clamp(0.0, 1.0);
+            member.food = member.food.clamp(0.0, 1.0);
+
+            if activity == Activity::Servicing && settled {
+                let effectiveness = member.effectiveness();
+                if let Some(task) = self.tasks.iter_mut().find(|task| task.assignee == Some(index)) {
+                    task.progress += effectiveness * delta_time / (HOURS_SERVICING * CREW_HOUR);
+                    if task.progress >= 1.0 {
+                        serviced.push((task.module, task.element));
+                    }
+                }
+            }
+            if activity == Activity::Cleaning && settled {
+                let at = station.module_index(member.module).unwrap_or(at);
+                station.scrub_module(at, member.effectiveness() * grime::CLEAN_RATE * delta_time);
+            }
+        }
+        for (module, element) in serviced {
+            self.tasks.retain(|task| (task.module, task.element) != (module, element));
+            let Some(at) = station.module_index(module) else {
+                continue;
+            };
+            if !station.service_element(at, element) {
+                station.log_event(
+                    LogSeverity::Warning,
+                    format!("Service of element {} in module {} fell through", element, at),
+                );
+            }
+        }
+        self.report(station);
+    }
+
+    pub fn state(&self) -> CrewState {
+        let members = self
+            .members
+            .iter()
+            .map(|member| SavedCrewMember {
+                definition: member.definition.clone(),
+                quarters: member.quarters,
+                workstation: member.workstation,
+                module: member.module,
+                position: member.position.to_array(),
+                activity: member.activity,
+                sleep: member.sleep,
+                food: member.food,
+                oxygen: member.oxygen,
+                dose: member.dose,
+                destination: member.destination,
+                route: member.route.iter().copied().collect(),
+                cleaning: member.cleaning,
+                muster: member.muster,
+            })
+            .collect();
+        CrewState {
+            members,
+            tasks: self.tasks.clone(),
+        }
+    }
+
+    // Takes on the saved crew in place of this one
+    pub fn restore(&mut self, state: &CrewState) {
+        self.members = state
+            .members
+            .iter()
+            .map(|saved| CrewMember {
+                definition: saved.definition.clone(),
+                quarters: saved.quarters,
+                workstation: saved.workstation,
+                module: saved.module,
+                position: Vec3::from(saved.position),
+                activity: saved.activity,
+                sleep: saved.sleep,
+                food: saved.food,
+                oxygen: saved.oxygen,
+                dose: saved.dose,
+                destination: saved.destination,
+                route: saved.route.iter().copied().collect(),
+                cleaning: saved.cleaning,
+                muster: saved.muster,
+            })
+            .collect();
+        self.tasks = state.tasks.clone();
+    }
+
+    // Head counts, and each workstation run as well as the crew assigned to
+    // it who are there working; modules nobody's assigned to run themselves
+    fn report(&self, station: &mut SpaceStation) {
+        for i in 0..station.modules().len() {
+            let id = station.modules()[i].id;
+            let here = self.members.iter().filter(|member| member.module == id);
+            let assigned: Vec<&CrewMember> = self.members.iter().filter(|member| member.workstation == id).collect();
+            let working: f32 = assigned
+                .iter()
+                .filter(|member| member.activity == Activity::Working && member.arrived())
+                .map(|member| member.effectiveness())
+                .sum();
+            let crew = here.count() as u32;
+            if let Some(module) = station.module_mut(i) {
+                module.crew = crew;
+                module.productivity = if assigned.is_empty() { 1.0 } else { working / assigned.len() as f32 };
+            }
+        }
+    }
+}
+
+// The task a crew member already has, or the first nobody's taken
+fn claim(tasks: &mut [MaintenanceTask], member: usize) -> Option<usize> {
+    if let Some(mine) = tasks.iter().position(|task| task.assignee == Some(member)) {
+        return Some(mine);
+    }
+    let free = tasks.iter().position(|task| task.assignee.is_none())?;
+    tasks[free].assignee = Some(member);
+    Some(free)
+}
+
+// The module a crew member on shift should be cleaning: the one they're on
+// until it's clean, or else the first that needs it and nobody else has
+fn janitor(member: &mut CrewMember, station: &SpaceStation, cleaning: &mut Vec<ModuleId>) -> Option<ModuleId> {
+    let modules = station.modules();
+    if let Some(module) = member.cleaning {
+        let here = station.module_index(module).map(|at| &modules[at]);
+        if here.is_some_and(|here| here.worst_grime() > grime::CLEAN) {
+            return Some(module);
+        }
+        cleaning.retain(|&other| other != module);
+        member.cleaning = None;
+    }
+    let module = modules
+        .iter()
+        .find(|module| !cleaning.contains(&module.id) && module.worst_grime() >= grime::NEEDS_CLEANING)?
+        .id;
+    cleaning.push(module);
+    member.cleaning = Some(module);
+    Some(module)
+}
+
+// Where in a module a crew member stands, so they don't all pile up in the middle
+fn spot(station: &SpaceStation, module: usize, index: usize) -> Vec3 {
+    let centre = station.modules()[module].transform.position;
+    let angle = index as f32 * 2.4;
+    centre + Vec3::new(angle.cos(), 0.0, angle.sin()) * 1.2
+}
+
+// Heads for the next module's centre, or their spot once there
+fn walk(member: &mut CrewMember, station: &SpaceStation, index: usize, delta_time: f32) {
+    let mut step = WALK_SPEED * delta_time;
+    while step > 0.0 {
+        let target = match member.route.front().map(|&next| station.module_index(next)) {
+            Some(Some(next)) => station.modules()[next].transform.position,
+            // The module's gone, or a save was loaded
+            Some(None) => {
+                member.route.clear();
+                continue;
+            }
+            None => match station.module_index(member.module) {
+                Some(at) => spot(station, at, index),
+                None => return,
+            },
+        };
+        let offset = target - member.position;
+        let distance = offset.length();
+        if distance > step {
+            member.position += offset / distance * step;
+            return;
+        }
+        member.position = target;
+        step -= distance;
+        match member.route.pop_front() {
+            Some(next) => member.module = next,
+            None => return,
+        }
+    }
+}
+
+// The module with the best air they can get to, which may be where they are
+fn best_air(station: &SpaceStation, from: usize) -> ModuleId {
+    let modules = station.modules();
+    let best = (0..modules.len())
+        .filter(|&i| i == from || station.find_path(from, i).is_some())
+        .max_by(|&a, &b| {
+            let air = |i: usize| modules[i].atmosphere.oxygen_level();
+            air(a).total_cmp(&air(b))
+        })
+        .unwrap_or(from);
+    modules[best].id
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Vec2;
+    use crate::radiation;
+    use crate::resources;
+    use crate::station::{ElementState, InteractionType, ModuleType};
+    use crate::tunables::StationTunables;
+
+    fn lab_hand(shift_start: f32) -> CrewDefinition {
+        CrewDefinition { name: "Lindqvist".to_string(), quarters: 6, workstation: 5, shift_start }
+    }
+
+    fn radiator(station: &SpaceStation, module: usize) -> usize {
+        station.modules()[module]
+            .interactive_elements
+            .iter()
+            .position(|element| element.element_type == InteractionType::Radiator)
+            .unwrap()
+    }
+
+    #[test]
+    fn crew_walk_the_connection_graph_to_their_shift() {
+        let mut station = SpaceStation::create_default_layout();
+        assert_eq!(station.find_path(6, 5), Some(vec![6, 2, 0, 1, 5]));
+        let mut crew = Crew::new();
+        crew.add(lab_hand(0.0), &station).unwrap();
+        crew.update(&mut station, 0.1);
+        assert_eq!(crew.members()[0].activity, Activity::Working);
+        assert_eq!(station.modules()[5].productivity, 0.0);
+
+        for _ in 0..600 {
+            crew.update(&mut station, 0.1);
+        }
+        assert_eq!(crew.members()[0].module, station.modules()[5].id);
+        assert!(crew.members()[0].arrived());
+        assert_eq!((station.modules()[5].crew, station.modules()[6].crew), (1, 0));
+        assert!(station.modules()[5].productivity > 0.9);
+        // Modules nobody works in run themselves
+        assert_eq!(station.modules()[8].productivity, 1.0);
+
+        let door = station.door_between(0, 1).unwrap();
+        station.set_door_locked(door, true);
+        assert_eq!(station.find_path(0, 5), None);
+    }
+
+    #[test]
+    fn crew_leave_thin_air_and_staffing_drives_lab_output() {
+        let mut station = SpaceStation::create_default_layout();
+        let mut crew = Crew::new();
+        crew.add(lab_hand(0.0), &station).unwrap();
+        for _ in 0..600 {
+            crew.update(&mut station, 0.1);
+        }
+        station.module_mut(5).unwrap().atmosphere.oxygen *= 0.3;
+        crew.update(&mut station, 0.1);
+        assert_eq!(crew.members()[0].activity, Activity::Fleeing);
+        assert_ne!(crew.members()[0].destination(), station.modules()[5].id);
+        assert_eq!(station.modules()[5].productivity, 0.0);
+
+        let equipment = station.modules()[5]
+            .interactive_elements
+            .iter()
+            .position(|element| element.element_type == InteractionType::LabEquipment)
+            .unwrap();
+        station.set_element_state(5, equipment, ElementState::Active);
+        let tunables = StationTunables::default();
+        let idle = resources::rates(station.modules(), &tunables)[1];
+        station.module_mut(5).unwrap().productivity = 1.0;
+        let staffed = resources::rates(station.modules(), &tunables)[1];
+        assert!((staffed - idle - tunables.food_growth).abs() < 1e-6);
+    }
+
+    #[test]
+    fn crew_on_shift_carry_out_scheduled_services() {
+        let mut station = SpaceStation::create_default_layout();
+        let cooler = radiator(&station, 8);
+        station.module_mut(8).unwrap().interactive_elements[cooler].hours = 50.0;
+        let mut crew = Crew::new();
+        crew.add(lab_hand(0.0), &station).unwrap();
+        assert!(crew.schedule_maintenance(station.modules()[8].id, cooler));
+        assert!(!crew.schedule_maintenance(station.modules()[8].id, cooler));
+        crew.update(&mut station, 0.1);
+        assert_eq!(crew.members()[0].activity, Activity::Servicing);
+        assert_eq!(crew.tasks()[0].assignee, Some(0));
+
+        for _ in 0..1200 {
+            crew.update(&mut station, 0.1);
+        }
+        assert!(crew.tasks().is_empty());
+        assert_eq!(station.modules()[8].interactive_elements[cooler].hours, 0.0);
+        assert_eq!(crew.members()[0].activity, Activity::Working);
+    }
+
+    #[test]
+    fn footprints_and_handprints_build_up_until_the_crew_clean_them() {
+        let mut station = SpaceStation::create_default_layout();
+        let centre = station.modules()[8].transform.position;
+        for _ in 0..100 {
+            station.tread(centre, 1.0);
+        }
+        let uv = station.modules()[8].floor_uv(centre);
+        assert!(station.modules()[8].grime.at(uv) > 0.99);
+        assert!(station.modules()[8].floor_point(uv).distance(centre) < 0.001);
+        // The cells beside it get half, and the far corner nothing
+        assert!(station.modules()[8].grime.at(uv + Vec2::X / grime::MASK_SIZE as f32) > 0.49);
+        assert_eq!(station.modules()[8].grime.at(Vec2::ZERO), 0.0);
+        let cooler = radiator(&station, 8);
+        station.activate_element(8, cooler);
+        assert_eq!(station.modules()[8].interactive_elements[cooler].grime, grime::TOUCH);
+        let restored = SpaceStation::from_snapshot(&station.snapshot());
+        assert_eq!(restored.modules()[8].grime, station.modules()[8].grime);
+        assert_eq!(restored.modules()[8].interactive_elements[cooler].grime, grime::TOUCH);
+
+        let mut crew = Crew::new();
+        crew.add(lab_hand(0.0), &station).unwrap();
+        crew.update(&mut station, 0.1);
+        assert_eq!(crew.members()[0].activity, Activity::Cleaning);
+        assert_eq!(crew.members()[0].destination(), station.modules()[8].id);
+        for _ in 0..3000 {
+            crew.update(&mut station, 0.1);
+        }
+        assert!(station.modules()[8].grime.at(uv) < 0.2);
+        assert_eq!(station.modules()[8].interactive_elements[cooler].grime, 0.0);
+        assert_eq!(crew.members()[0].activity, Activity::Working);
+    }
+
+    #[test]
+    fn module_ids_outlast_renumbering_and_go_stale_with_their_module() {
+        let mut station = SpaceStation::create_default_layout();
+        let mut crew = Crew::new();
+        crew.add(lab_hand(8.0), &station).unwrap();
+        let (corridor, lab, quarters) = (station.modules()[4].id, station.modules()[5].id, station.modules()[6].id);
+
+        // Everything after the corridor moves down one; the ids still find it
+        station.remove_module(4).unwrap();
+        assert_eq!(station.module_index(corridor), None);
+        assert_eq!((station.module_index(lab), station.module_index(quarters)), (Some(4), Some(5)));
+        crew.update(&mut station, 1.0);
+        assert_eq!(crew.members()[0].module, quarters);
+        assert!(crew.relies_on(lab) && !crew.relies_on(corridor));
+
+        // Its slot goes to the next module, but the old id doesn't find that
+        let added = station.add_module(ModuleType::Storage, Vec3::new(100.0, 0.0, 0.0));
+        let id = station.modules()[added].id;
+        assert_eq!(id.slot, corridor.slot);
+        assert_ne!(id, corridor);
+        assert_eq!(station.module_index(corridor), None);
+
+        // Saves keep them; saves from before ids get fresh ones
+        let restored = SpaceStation::from_snapshot(&station.snapshot());
+        assert!(restored.modules().iter().zip(station.modules()).all(|(a, b)| a.id == b.id));
+        assert_eq!(restored.module_index(lab), Some(4));
+        let mut snapshot = station.snapshot();
+        snapshot.modules.iter_mut().for_each(|module| module.id = None);
+        let restored = SpaceStation::from_snapshot(&snapshot);
+        let indices: Vec<_> = restored.modules().iter().map(|module| restored.module_index(module.id)).collect();
+        assert_eq!(indices, (0..restored.modules().len()).map(Some).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn crew_sit_out_flares_in_the_storm_shelter() {
+        let mut station = SpaceStation::create_default_layout();
+        let tunables = StationTunables::default();
+        let mut crew = Crew::new();
+        crew.add(lab_hand(0.0), &station).unwrap();
+        assert_eq!(station.exterior_radiation(), tunables.radiation_background);
+        // Stores in the walls stop more than the lab's do
+        assert!(station.radiation(7) < station.radiation(5));
+        assert!(station.set_storm_shelter(Some(7)));
+        assert_eq!(station.shielding(7), ModuleType::Storage.shielding() + tunables.shelter_shielding);
+
+        // Half way through, the flare's at its height
+        station.begin_flare(1.0, 100.0);
+        station.update(50.0);
+        let outside = tunables.radiation_background + tunables.flare_radiation;
+        assert!((station.exterior_radiation() - outside).abs() < 1e-5);
+        let lab = station.radiation(5);
+        assert!(lab > radiation::RATE_WARNING && lab < outside);
+        station.module_mut(5).unwrap().structural_integrity = 0.5;
+        assert!(station.radiation(5) > lab);
+
+        crew.update(&mut station, 0.1);
+        assert_eq!(crew.members()[0].activity, Activity::Sheltering);
+        assert_eq!(crew.members()[0].destination(), station.modules()[7].id);
+        assert!(crew.members()[0].dose > 0.0);
+        let restored = SpaceStation::from_snapshot(&station.snapshot());
+        assert_eq!(restored.storm_shelter(), Some(7));
+
+        // Back to work once it's passed
+        station.update(60.0);
+        crew.update(&mut station, 0.1);
+        assert_eq!(crew.members()[0].activity, Activity::Working);
+    }
+}
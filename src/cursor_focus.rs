@@ -0,0 +1,69 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CursorChange {
+    Capture,
+    Release,
+}
+
+// Decides whether the mouse is locked for mouse look. The player can free it
+// with the toggle (TAB by default); menus free it while they're open; losing
+// window focus frees it until the player clicks back into the window, so
+// alt-tabbing away never leaves the mouse stuck.
+#[derive(Debug)]
+pub struct CursorFocus {
+    captured: bool,
+    // Freed with the toggle; stays free until toggled back
+    released_by_player: bool,
+    // Focus went away; a click in the window brings the lock back
+    lost_focus: bool,
+}
+
+impl Default for CursorFocus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CursorFocus {
+    // The game starts with the cursor locked
+    pub fn new() -> Self {
+        Self {
+            captured: true,
+            released_by_player: false,
+            lost_focus: false,
+        }
+    }
+
+    pub fn captured(&self) -> bool {
+        self.captured
+    }
+
+    // Released by focus loss and only waiting on a click
+    pub fn waiting_for_click(&self) -> bool {
+        self.lost_focus && !self.released_by_player
+    }
+
+    // Takes effect on the next update
+    pub fn toggle(&mut self) {
+        if self.captured {
+            self.released_by_player = true;
+        } else {
+            self.released_by_player = false;
+            self.lost_focus = false;
+        }
+    }
+
+    // Call every frame; says when the real cursor needs locking or freeing
+    pub fn update(&mut self, focused: bool, menu_open: bool, clicked: bool) -> Option<CursorChange> {
+        if !focused {
+            self.lost_focus = true;
+        } else if self.lost_focus && clicked {
+            self.lost_focus = false;
+        }
+        let wanted = focused && !self.lost_focus && !menu_open && !self.released_by_player;
+        if wanted == self.captured {
+            return None;
+        }
+        self.captured = wanted;
+        Some(if wanted { CursorChange::Capture } else { CursorChange::Release })
+    }
+}
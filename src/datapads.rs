@@ -0,0 +1,311 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+use anyhow::{Context, Result};
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+use crate::module_id::{self, ModuleId};
+use crate::station::{InteractionType, SpaceStation};
+
+// A collectible log entry. Placed relative to a module so layouts can move
+// without breaking the narrative data.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DatapadDefinition {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub author: String,
+    pub text: String,
+    #[serde(deserialize_with = "module_id::layout_index")]
+    pub module: ModuleId,
+    #[serde(default)]
+    pub offset: [f32; 3],
+    // Granted when the datapad is read
+    #[serde(default)]
+    pub clearance: Option<String>,
+    #[serde(default)]
+    pub code: Option<String>,
+    // Locked elements in this module are released when the datapad is read
+    #[serde(default, deserialize_with = "module_id::optional_layout_index")]
+    pub unlocks_module: Option<ModuleId>,
+}
+
+// Elements of one type in a module that need a clearance to work, until a
+// datapad grants it
+#[derive(Debug, Clone, Deserialize)]
+pub struct Restriction {
+    #[serde(deserialize_with = "module_id::layout_index")]
+    pub module: ModuleId,
+    pub element: InteractionType,
+    pub clearance: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct DatapadFile {
+    #[serde(rename = "datapad", default)]
+    datapads: Vec<DatapadDefinition>,
+    #[serde(rename = "restricted", default)]
+    restrictions: Vec<Restriction>,
+}
+
+#[derive(Debug, Clone)]
+pub struct PlacedDatapad {
+    pub definition: DatapadDefinition,
+    pub position: Vec3,
+    pub collected: bool,
+}
+
+// The journal, for saves: the ids of the datapads read, in order, and the
+// clearances they've granted
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DatapadState {
+    pub journal: Vec<String>,
+    #[serde(default)]
+    pub clearances: Vec<String>,
+}
+
+// Datapads in the world plus the player's journal of the ones already read
+#[derive(Debug, Default)]
+pub struct Datapads {
+    placed: Vec<PlacedDatapad>,
+    journal: Vec<usize>,
+    clearances: HashSet<String>,
+    codes: Vec<(String, String)>,
+}
+
+impl Datapads {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P, station: &mut SpaceStation) -> Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("failed to read datapads from {}", path.display()))?;
+        let file: DatapadFile = toml::from_str(&source).context("invalid datapad definitions")?;
+
+        for restriction in &file.restrictions {
+            let restricted = station
+                .module_index(restriction.module)
+                .map_or(0, |module| station.restrict(module, restriction.element, &restriction.clearance));
+            if restricted == 0 {
+                eprintln!("Warning: no {:?} in module {:?} to restrict", restriction.element, restriction.module);
+            }
+        }
+
+        let mut datapads = Self::new();
+        for definition in file.datapads {
+            let Some(module) = station.module_index(definition.module).map(|index| &station.modules()[index]) else {
+                eprintln!("Warning: datapad {} is in missing module {:?}", definition.id, definition.module);
+                continue;
+            };
+            let position = module.transform.matrix().transform_point3(Vec3::from(definition.offset));
+            datapads.place(definition, position);
+        }
+        Ok(datapads)
+    }
+
+    // For procedural placement; scenario files go through `load`
+    pub fn place(&mut self, definition: DatapadDefinition, position: Vec3) {
+        self.placed.push(PlacedDatapad {
+            definition,
+            position,
+            collected: false,
+        });
+    }
+
+    pub fn placed(&self) -> &[PlacedDatapad] {
+        &self.placed
+    }
+
+    pub fn get(&self, index: usize) -> Option<&DatapadDefinition> {
+        self.placed.get(index).map(|pad| &pad.definition)
+    }
+
+    pub fn nearest(&self, position: Vec3, reach: f32) -> Option<usize> {
+        self.placed
+            .iter()
+            .enumerate()
+            .filter(|(_, pad)| !pad.collected)
+            .map(|(i, pad)| (i, pad.position.distance(position)))
+            .filter(|&(_, distance)| distance <= reach)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+    }
+
+    // Picks a datapad up, files it in the journal and applies what it grants
+    pub fn collect(&mut self, index: usize, station: &mut SpaceStation) -> Option<&DatapadDefinition> {
+        let pad = self.placed.get_mut(index)?;
+        if pad.collected {
+            return None;
+        }
+        pad.collected = true;
+        self.journal.push(index);
+
+        let definition = &pad.definition;
+        if let Some(clearance) = &definition.clearance {
+            self.clearances.insert(clearance.clone());
+        }
+        if let Some(code) = &definition.code {
+            self.codes.push((definition.title.clone(), code.clone()));
+        }
+        if let Some(module) = definition.unlocks_module.and_then(|id| station.module_index(id)) {
+            station.unlock_module(module);
+        }
+        Some(definition)
+    }
+
+    // Read datapads, in the order they were found
+    pub fn journal(&self) -> impl Iterator<Item = &DatapadDefinition> {
+        self.journal.iter().map(|&i| &self.placed[i].definition)
+    }
+
+    pub fn journal_len(&self) -> usize {
+        self.journal.len()
+    }
+
+    pub fn journal_entry(&self, n: usize) -> Option<&DatapadDefinition> {
+        self.journal.get(n).map(|&i| &self.placed[i].definition)
+    }
+
+    pub fn has_clearance(&self, clearance: &str) -> bool {
+        self.clearances.contains(clearance)
+    }
+
+    pub fn clearances(&self) -> impl Iterator<Item = &str> {
+        self.clearances.iter().map(String::as_str)
+    }
+
+    // The clearance an element needs that hasn't been found yet, if any
+    pub fn missing_clearance<'a>(&self, station: &'a SpaceStation, module: usize, element: usize) -> Option<&'a str> {
+        station.clearance_needed(module, element).filter(|clearance| !self.has_clearance(clearance))
+    }
+
+    // (source title, code) pairs
+    pub fn codes(&self) -> &[(String, String)] {
+        &self.codes
+    }
+
+    pub fn state(&self) -> DatapadState {
+        let mut clearances: Vec<String> = self.clearances.iter().cloned().collect();
+        clearances.sort();
+        DatapadState {
+            journal: self.journal().map(|definition| definition.id.clone()).collect(),
+            clearances,
+        }
+    }
+
+    // Picks up a saved journal; what the datapads did to the station is in
+    // its own save. Datapads no longer placed are dropped.
+    pub fn restore(&mut self, state: &DatapadState) {
+        for pad in &mut self.placed {
+            pad.collected = false;
+        }
+        self.journal.clear();
+        self.codes.clear();
+        self.clearances = state.clearances.iter().cloned().collect();
+        for id in &state.journal {
+            let Some(index) = self.placed.iter().position(|pad| &pad.definition.id == id && !pad.collected) else {
+                continue;
+            };
+            let pad = &mut self.placed[index];
+            pad.collected = true;
+            self.journal.push(index);
+            if let Some(code) = &pad.definition.code {
+                self.codes.push((pad.definition.title.clone(), code.clone()));
+            }
+        }
+    }
+}
+
+// Splits text into lines of at most `width` characters, breaking on spaces
+pub fn wrap_text(text: &str, width: usize) -> Vec<String> {
+    let mut lines = Vec::new();
+    for paragraph in text.lines() {
+        let mut line = String::new();
+        for word in paragraph.split_whitespace() {
+            if !line.is_empty() && line.len() + 1 + word.len() > width {
+                lines.push(std::mem::take(&mut line));
+            }
+            if !line.is_empty() {
+                line.push(' ');
+            }
+            line.push_str(word);
+        }
+        lines.push(line);
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::station::ElementState;
+
+    #[test]
+    fn restricted_storage_opens_once_the_quartermasters_memo_is_read() {
+        let mut station = SpaceStation::create_default_layout();
+        let mut datapads = Datapads::load("assets/datapads.toml", &mut station).unwrap();
+        let storage = 7;
+        let access = station.modules()[storage]
+            .interactive_elements
+            .iter()
+            .position(|element| element.element_type == InteractionType::StorageAccess)
+            .unwrap();
+        assert_eq!(datapads.missing_clearance(&station, storage, access), Some("storage"));
+        assert_eq!(datapads.missing_clearance(&station, 0, 0), None);
+
+        // The commander's log only says why; the memo carries the clearance
+        let find = |datapads: &Datapads, id: &str| {
+            datapads.placed().iter().position(|pad| pad.definition.id == id).unwrap()
+        };
+        let log = find(&datapads, "commander_log_12");
+        datapads.collect(log, &mut station).unwrap();
+        assert_eq!(datapads.missing_clearance(&station, storage, access), Some("storage"));
+        let memo = find(&datapads, "quartermaster_memo");
+        datapads.collect(memo, &mut station).unwrap();
+        assert!(datapads.has_clearance("storage"));
+        assert_eq!(datapads.missing_clearance(&station, storage, access), None);
+
+        // The restriction is the station's, so it's saved with it
+        let reloaded = SpaceStation::from_snapshot(&station.snapshot());
+        assert_eq!(reloaded.clearance_needed(storage, access), Some("storage"));
+
+        // And the clearance is the journal's: loaded over a session without
+        // it, storage opens; loaded from before the memo, it shuts again
+        let saved = datapads.state();
+        assert_eq!(saved.clearances, vec!["storage".to_string()]);
+        let mut other = SpaceStation::create_default_layout();
+        let mut restored = Datapads::load("assets/datapads.toml", &mut other).unwrap();
+        restored.restore(&saved);
+        assert_eq!(restored.missing_clearance(&reloaded, storage, access), None);
+        restored.restore(&DatapadState { journal: vec!["commander_log_12".to_string()], clearances: Vec::new() });
+        assert_eq!(restored.missing_clearance(&reloaded, storage, access), Some("storage"));
+    }
+
+    #[test]
+    fn datapads_keep_their_module_when_an_earlier_one_is_removed() {
+        let mut station = SpaceStation::create_default_layout();
+        let storage = station.modules()[7].id;
+        station.set_element_state(7, 0, ElementState::Locked);
+        let mut datapads = Datapads::new();
+        let definition = DatapadDefinition {
+            id: "stores_override".to_string(),
+            title: "Stores override".to_string(),
+            author: String::new(),
+            text: String::new(),
+            module: storage,
+            offset: [0.0; 3],
+            clearance: None,
+            code: None,
+            unlocks_module: Some(storage),
+        };
+        datapads.place(definition, Vec3::ZERO);
+
+        // Storage is 6 once the lab's gone, and that's what gets unlocked
+        station.remove_module(5).unwrap();
+        assert_eq!(station.module_index(datapads.get(0).unwrap().module), Some(6));
+        datapads.collect(0, &mut station).unwrap();
+        assert_eq!(station.element_state(6, 0), Some(ElementState::Inactive));
+    }
+}
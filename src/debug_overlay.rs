@@ -0,0 +1,148 @@
+use std::collections::VecDeque;
+use glam::Vec3;
+use crate::effects::EventEffects;
+use crate::haptics::Rumble;
+use crate::reverb::ReverbParams;
+use crate::station::SpaceStation;
+use crate::texture_streaming::ResidencyStats;
+
+// Frames kept for the frame time graph
+pub const FRAME_HISTORY: usize = 240;
+
+const MEGABYTE: f32 = 1024.0 * 1024.0;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FrameStats {
+    pub average: f32,
+    pub min: f32,
+    pub max: f32,
+}
+
+// F3 developer overlay. Frame times are recorded even while hidden so the
+// graph is already full when it's opened.
+#[derive(Debug)]
+pub struct DebugOverlay {
+    pub visible: bool,
+    // Renderer name, filled in once the window is up
+    pub gpu: Option<String>,
+    // Set by whatever renderer streams its textures
+    pub textures: Option<ResidencyStats>,
+    // Listener's module and the reverb being applied there
+    pub reverb: Option<(usize, ReverbParams)>,
+    // Controller motor levels this frame
+    pub rumble: Rumble,
+    frame_times: VecDeque<f32>,
+}
+
+impl Default for DebugOverlay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DebugOverlay {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            gpu: None,
+            textures: None,
+            reverb: None,
+            rumble: Rumble::default(),
+            frame_times: VecDeque::with_capacity(FRAME_HISTORY),
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub fn record_frame(&mut self, delta_time: f32) {
+        if self.frame_times.len() == FRAME_HISTORY {
+            self.frame_times.pop_front();
+        }
+        self.frame_times.push_back(delta_time);
+    }
+
+    // Oldest first, in seconds
+    pub fn frame_times(&self) -> impl Iterator<Item = f32> + '_ {
+        self.frame_times.iter().copied()
+    }
+
+    pub fn frame_stats(&self) -> FrameStats {
+        if self.frame_times.is_empty() {
+            return FrameStats::default();
+        }
+        let mut stats = FrameStats {
+            average: 0.0,
+            min: f32::MAX,
+            max: 0.0,
+        };
+        for &time in &self.frame_times {
+            stats.average += time;
+            stats.min = stats.min.min(time);
+            stats.max = stats.max.max(time);
+        }
+        stats.average /= self.frame_times.len() as f32;
+        stats
+    }
+
+    // Text rows shown beside the graph
+    pub fn lines(
+        &self,
+        station: &SpaceStation,
+        effects: &EventEffects,
+        draw_calls: usize,
+        player: Vec3,
+        yaw: f32,
+    ) -> Vec<String> {
+        let frame = self.frame_stats();
+        let telemetry = station.telemetry();
+
+        let mut lines = vec![
+            format!(
+                "Frame {:.2} ms (min {:.2}, max {:.2})",
+                frame.average * 1000.0,
+                frame.min * 1000.0,
+                frame.max * 1000.0
+            ),
+            format!("Draw calls ~{}", draw_calls),
+            format!("GPU {}", self.gpu.as_deref().unwrap_or("unknown")),
+            format!(
+                "Player {:.2} {:.2} {:.2}  yaw {:.1}",
+                player.x,
+                player.y,
+                player.z,
+                yaw.to_degrees().rem_euclid(360.0)
+            ),
+            format!(
+                "Power {:.1} / {:.1} kW  O2 {:.1}%",
+                telemetry.power_consumption,
+                telemetry.power_generation,
+                telemetry.oxygen_level * 100.0
+            ),
+            format!("Particles {} in {} emitters", effects.particle_count(), effects.emitters().len()),
+        ];
+        if let Some(stats) = &self.textures {
+            lines.push(format!(
+                "Textures {}: {:.1} / {:.1} MB, {} starved, +{} -{}",
+                stats.textures,
+                stats.resident_bytes as f32 / MEGABYTE,
+                stats.budget_bytes as f32 / MEGABYTE,
+                stats.starved,
+                stats.loads,
+                stats.evictions
+            ));
+        }
+        if let Some((module, reverb)) = &self.reverb {
+            lines.push(format!(
+                "Reverb module {}: decay {:.2}s wet {:.2} damping {:.2}",
+                module, reverb.decay, reverb.wet, reverb.damping
+            ));
+        }
+        lines.push(format!("Rumble low {:.2} high {:.2}", self.rumble.low, self.rumble.high));
+        for effect in effects.emitters() {
+            lines.push(format!("  {}: {}", effect.name, effect.emitter.particles.len()));
+        }
+        lines
+    }
+}
@@ -0,0 +1,260 @@
+use rand::Rng;
+use serde::Deserialize;
+use crate::incidents::{IncidentKind, IncidentSchedule};
+use crate::station::SpaceStation;
+
+// Tension a fire, breach or power deficit on the station adds per second
+const EMERGENCY_TENSION: f32 = 0.01;
+// How much rarer the bigger incidents get as tension builds
+const RESTRAINT: f32 = 4.0;
+
+// The shape of a session: tension builds with incidents to a peak, the
+// director holds off until they're dealt with, lets things stay quiet a
+// while, then builds again, a little faster each time round
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Pacing {
+    // Tension, 0 to 1, at which nothing new is started
+    pub peak: f32,
+    // What it has to fall back under before building up again
+    pub calm: f32,
+    // Least seconds of quiet after a peak
+    pub quiet: f32,
+    // Tension shed per second while nothing's going on
+    pub relax_rate: f32,
+    // Incidents come this many times as often once `escalation_time`
+    // seconds have gone by
+    pub escalation: f32,
+    pub escalation_time: f32,
+    // Average seconds between flavor events in the quiet spells
+    pub flavor_interval: f32,
+}
+
+impl Default for Pacing {
+    fn default() -> Self {
+        Self {
+            peak: 0.7,
+            calm: 0.25,
+            quiet: 240.0,
+            relax_rate: 0.004,
+            escalation: 2.0,
+            escalation_time: 3600.0,
+            flavor_interval: 120.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Phase {
+    BuildUp,
+    // Nothing new until what's going on is dealt with
+    Peak,
+    Relax { remaining: f32 },
+}
+
+impl Phase {
+    pub fn name(self) -> &'static str {
+        match self {
+            Phase::BuildUp => "build-up",
+            Phase::Peak => "peak",
+            Phase::Relax { .. } => "relax",
+        }
+    }
+}
+
+// What the director wants to happen now
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Cue {
+    Incident(IncidentKind),
+    // An index into the schedule's flavor lines
+    Flavor(usize),
+}
+
+#[derive(Debug)]
+pub struct Director {
+    tension: f32,
+    elapsed: f32,
+    phase: Phase,
+}
+
+impl Director {
+    // Quiet for `grace` seconds to start with
+    pub fn new(grace: f32) -> Self {
+        Self { tension: 0.0, elapsed: 0.0, phase: Phase::Relax { remaining: grace } }
+    }
+
+    pub fn tension(&self) -> f32 {
+        self.tension
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    // How many times as often incidents come now as at the start
+    pub fn escalation(&self, pacing: &Pacing) -> f32 {
+        let progress = (self.elapsed / pacing.escalation_time.max(1.0)).min(1.0);
+        1.0 + (pacing.escalation - 1.0).max(0.0) * progress
+    }
+
+    // An incident went off, for however much tension it adds
+    pub fn raised(&mut self, tension: f32) {
+        self.tension = (self.tension + tension).clamp(0.0, 1.0);
+    }
+
+    // Tension builds with `unresolved` incidents and station emergencies,
+    // and falls away without them. Returns what should happen now, if
+    // anything.
+    pub fn update(
+        &mut self,
+        schedule: &IncidentSchedule,
+        station: &mut SpaceStation,
+        unresolved: usize,
+        delta_time: f32,
+    ) -> Option<Cue> {
+        let pacing = &schedule.pacing;
+        self.elapsed += delta_time;
+        let emergency = station.in_emergency();
+        if emergency {
+            self.tension += EMERGENCY_TENSION * delta_time;
+        } else if unresolved == 0 {
+            self.tension -= pacing.relax_rate * delta_time;
+        }
+        self.tension = self.tension.clamp(0.0, 1.0);
+
+        match self.phase {
+            Phase::BuildUp => {
+                if self.tension >= pacing.peak {
+                    self.phase = Phase::Peak;
+                    return None;
+                }
+                // Slower as it gets tenser, faster as the session goes on
+                let rate = schedule.difficulty.max(0.0) / schedule.mean_interval.max(1.0)
+                    * self.escalation(pacing)
+                    * (1.0 - self.tension);
+                if station.rng().gen::<f32>() >= rate * delta_time {
+                    return None;
+                }
+                self.pick(schedule, station).map(Cue::Incident)
+            }
+            Phase::Peak => {
+                if unresolved == 0 && !emergency {
+                    self.phase = Phase::Relax { remaining: pacing.quiet };
+                }
+                None
+            }
+            Phase::Relax { remaining } => {
+                let remaining = remaining - delta_time;
+                if remaining <= 0.0 && self.tension <= pacing.calm {
+                    self.phase = Phase::BuildUp;
+                    return None;
+                }
+                self.phase = Phase::Relax { remaining };
+                if schedule.flavor.is_empty() || station.rng().gen::<f32>() >= delta_time / pacing.flavor_interval.max(1.0) {
+                    return None;
+                }
+                Some(Cue::Flavor(station.rng().gen_range(0..schedule.flavor.len())))
+            }
+        }
+    }
+
+    // A kind by the schedule's odds, the ones that would add the most
+    // tension held back the tenser it already is
+    fn pick(&self, schedule: &IncidentSchedule, station: &mut SpaceStation) -> Option<IncidentKind> {
+        let weights: Vec<f32> = schedule
+            .odds
+            .iter()
+            .map(|odds| odds.weight.max(0.0) * (1.0 - self.tension).powf(schedule.tension(odds.kind) * RESTRAINT))
+            .collect();
+        let total: f32 = weights.iter().sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let mut roll = station.rng().gen_range(0.0..total);
+        schedule.odds.iter().zip(&weights).find_map(|(odds, &weight)| {
+            roll -= weight;
+            (roll < 0.0).then_some(odds.kind)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Vec3;
+    use crate::events::StationEvent;
+    use crate::incidents::{IncidentOdds, Incidents};
+    use crate::objectives::Objectives;
+    use crate::station::ElementState;
+    use crate::tunables::Tunables;
+
+    #[test]
+    fn the_director_goes_quiet_after_a_peak_and_builds_up_again() {
+        let mut station = SpaceStation::create_default_layout();
+        let mut objectives = Objectives::default();
+        let breakdowns = vec![IncidentOdds { kind: IncidentKind::Malfunction, weight: 1.0, tension: Some(0.8) }];
+        let pacing = Pacing { quiet: 100.0, flavor_interval: 5.0, ..Pacing::default() };
+        let schedule = IncidentSchedule {
+            mean_interval: 60.0,
+            grace: 100.0,
+            difficulty: 1.0,
+            odds: breakdowns,
+            pacing,
+            flavor: vec!["Someone's left the fridge open".to_string()],
+        };
+        let mut incidents = Incidents::new(schedule);
+
+        // Flares are the director's business once it's scheduling them,
+        // which this one isn't
+        assert!(!incidents.directs_flares());
+        let mut tunables = Tunables::default();
+        tunables.station.flare_chance = 1000.0;
+        station.set_tunables(&tunables);
+        station.set_flares_directed(true);
+        station.update(1.0);
+        assert!(!station.interference().active());
+
+        // Only flavor in the grace period
+        for _ in 0..99 {
+            assert_eq!(incidents.update(&mut station, &mut objectives, 1.0), None);
+        }
+        let events = station.drain_events();
+        assert!(events.iter().any(|e| matches!(e, StationEvent::Notice { .. })));
+        assert!(!events.iter().any(|e| matches!(e, StationEvent::Incident { .. })));
+
+        // One big one takes it to the peak, and nothing more comes until
+        // it's dealt with
+        incidents.update(&mut station, &mut objectives, 1.0);
+        assert_eq!(incidents.director().phase(), Phase::BuildUp);
+        assert!(incidents.raise(IncidentKind::Malfunction, &mut station, &mut objectives));
+        for _ in 0..600 {
+            assert_eq!(incidents.update(&mut station, &mut objectives, 1.0), None);
+        }
+        assert_eq!(incidents.director().phase(), Phase::Peak);
+        assert!(incidents.director().tension() >= 0.8);
+
+        let (module, element) = station
+            .modules()
+            .iter()
+            .enumerate()
+            .find_map(|(i, m)| m.interactive_elements.iter().position(|e| e.state == ElementState::Malfunction).map(|j| (i, j)))
+            .unwrap();
+        station.set_element_state(module, element, ElementState::Inactive);
+        objectives.update(&mut station, Vec3::ZERO);
+        incidents.update(&mut station, &mut objectives, 1.0);
+        assert!(matches!(incidents.director().phase(), Phase::Relax { .. }));
+
+        // It stays quiet until the tension's worn off, then builds again,
+        // more often as the session goes on
+        let before = incidents.director().escalation(&incidents.schedule().pacing);
+        let mut raised = None;
+        for second in 0..3600 {
+            if incidents.update(&mut station, &mut objectives, 1.0).is_some() {
+                raised = Some(second);
+                break;
+            }
+        }
+        assert!(raised.is_some_and(|second| second >= 100));
+        assert!(incidents.director().escalation(&incidents.schedule().pacing) > before);
+    }
+}
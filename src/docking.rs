@@ -0,0 +1,636 @@
+use std::fs;
+use std::path::Path;
+use anyhow::{Context, Result};
+use glam::{Quat, Vec2, Vec3};
+use serde::Deserialize;
+use crate::event_log::LogSeverity;
+use crate::module_id::ModuleId;
+use crate::ports::DockingPort;
+use crate::resources::Resource;
+use crate::station::{ModuleType, SpaceStation};
+
+// Full deflection on the hand controllers: m/s² along any axis, and rad/s²
+// about any axis
+pub const TRANSLATE_ACCELERATION: f32 = 0.3;
+pub const ROTATE_ACCELERATION: f32 = 0.12;
+// The translation thrusters sit this far aft of the centre of mass, so
+// pushing sideways or up and down also turns the ship a little
+const THRUSTER_ARM: f32 = 1.5;
+const COUPLING: f32 = 0.15;
+// Centre of mass to the docking collar, along the ship's nose
+pub const NOSE_LENGTH: f32 = 3.0;
+// Where an arriving ship starts out on the approach axis, how far off it,
+// how far turned from it, and how fast it's already closing
+const START_RANGE: f32 = 60.0;
+const START_OFFSET: Vec3 = Vec3::new(4.0, -2.0, 0.0);
+const START_YAW: f32 = 0.15;
+const START_CLOSING: f32 = 0.5;
+
+// What the collar can take at contact and still latch
+pub const MAX_CLOSING: f32 = 0.3;
+pub const MAX_LATERAL: f32 = 0.3;
+pub const MAX_MISALIGNMENT: f32 = 5.0;
+pub const MAX_SPIN: f32 = 1.0;
+// Hull lost at the airlock per m/s of closing in a bad contact
+const COLLISION_DAMAGE: f32 = 0.15;
+// Share of its closing speed a ship comes off a bad contact with
+const RESTITUTION: f32 = 0.3;
+// Once it's latched: the hooks pull the collar hard against the hatch, the
+// vestibule between the hatches is brought up to pressure, the cargo goes
+// across, then it backs out at this speed
+pub const CAPTURE_SECONDS: f32 = 5.0;
+pub const EQUALISE_SECONDS: f32 = 10.0;
+pub const UNLOAD_SECONDS: f32 = 30.0;
+const DEPART_SPEED: f32 = 1.0;
+// How far one press at the comms console moves a ship on the schedule
+pub const RESCHEDULE_STEP: f32 = 600.0;
+
+// Autopilot: fast down the axis while it's far out, slowing to a crawl for
+// contact, and only once it's inside the approach cone and lined up
+const CRUISE_SPEED: f32 = 1.5;
+const CONTACT_SPEED: f32 = 0.12;
+const BRAKING: f32 = 0.05;
+const CONE: f32 = 0.15;
+const LATERAL_GAIN: f32 = 0.25;
+const VELOCITY_GAIN: f32 = 1.5;
+const ATTITUDE_GAIN: f32 = 0.6;
+const RATE_GAIN: f32 = 3.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShipKind {
+    Supply,
+    Shuttle,
+}
+
+impl ShipKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ShipKind::Supply => "supply ship",
+            ShipKind::Shuttle => "shuttle",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct Consignment {
+    pub resource: Resource,
+    pub amount: f32,
+}
+
+// A ship due at the station
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Arrival {
+    pub name: String,
+    pub ship: ShipKind,
+    // Mission seconds it turns up on approach
+    pub at: f32,
+    // Unloaded into stores once it's docked
+    #[serde(default)]
+    pub cargo: Vec<Consignment>,
+    // Due again this many seconds after it was due last, for regular runs
+    #[serde(default)]
+    pub every: Option<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ArrivalFile {
+    #[serde(rename = "arrival", default)]
+    arrivals: Vec<Arrival>,
+}
+
+pub fn load_arrivals<P: AsRef<Path>>(path: P) -> Result<Vec<Arrival>> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path)
+        .with_context(|| format!("failed to read arrivals from {}", path.display()))?;
+    let file: ArrivalFile = toml::from_str(&source).context("invalid arrival definitions")?;
+    let mut arrivals = file.arrivals;
+    arrivals.sort_by(|a, b| a.at.total_cmp(&b.at));
+    Ok(arrivals)
+}
+
+// Hand controller deflections, -1 to 1, in the ship's own axes: translate
+// x right, y up, z back (so forward is negative); rotate pitch, yaw, roll
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RcsCommand {
+    pub translate: Vec3,
+    pub rotate: Vec3,
+}
+
+// A ship in free flight. Its nose, with the docking collar, is its -Z.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Ship {
+    pub position: Vec3,
+    pub velocity: Vec3,
+    pub orientation: Quat,
+    // rad/s in its own axes
+    pub spin: Vec3,
+}
+
+impl Ship {
+    pub fn nose(&self) -> Vec3 {
+        self.orientation * Vec3::NEG_Z
+    }
+
+    pub fn collar(&self) -> Vec3 {
+        self.position + self.nose() * NOSE_LENGTH
+    }
+
+    // Fires the thrusters for `delta_time`. Thrust is in the ship's axes, so
+    // turning changes where translating pushes it, and translating off the
+    // centre of mass turns it.
+    pub fn fire(&mut self, command: RcsCommand, delta_time: f32) {
+        let translate = command.translate.clamp(Vec3::splat(-1.0), Vec3::ONE) * TRANSLATE_ACCELERATION;
+        let rotate = command.rotate.clamp(Vec3::splat(-1.0), Vec3::ONE) * ROTATE_ACCELERATION;
+        let coupled = (Vec3::Z * THRUSTER_ARM).cross(translate) * COUPLING;
+        self.spin += (rotate + coupled) * delta_time;
+        self.velocity += self.orientation * translate * delta_time;
+        self.position += self.velocity * delta_time;
+        self.orientation = (self.orientation * Quat::from_scaled_axis(self.spin * delta_time)).normalize();
+    }
+}
+
+// The approach as the HUD shows it, against the berth: right and up are as
+// seen from the ship looking in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Readout {
+    // Collar to hatch along the axis, and how fast that's shrinking
+    pub range: f32,
+    pub closing: f32,
+    // Collar off the axis, and drifting, in m and m/s
+    pub lateral: Vec2,
+    pub drift: Vec2,
+    // Nose off the axis, in degrees right and up
+    pub attitude: Vec2,
+    // Degrees from square, and how fast it's turning in degrees per second
+    pub misalignment: f32,
+    pub spin: f32,
+}
+
+impl Readout {
+    pub fn of(ship: &Ship, port: &DockingPort) -> Self {
+        let axis = port.facing;
+        let right = Vec3::Y.cross(axis).try_normalize().unwrap_or(Vec3::X);
+        let up = axis.cross(right);
+        let offset = ship.collar() - port.position;
+        let nose = ship.nose();
+        let along = -nose.dot(axis);
+        Readout {
+            range: offset.dot(axis),
+            closing: -ship.velocity.dot(axis),
+            lateral: Vec2::new(offset.dot(right), offset.dot(up)),
+            drift: Vec2::new(ship.velocity.dot(right), ship.velocity.dot(up)),
+            attitude: Vec2::new(nose.dot(right).atan2(along), nose.dot(up).atan2(along)) * (180.0 / std::f32::consts::PI),
+            misalignment: along.clamp(-1.0, 1.0).acos().to_degrees(),
+            spin: ship.spin.length().to_degrees(),
+        }
+    }
+
+    // Each docking criterion, in HUD order: closing, lateral, misalignment, spin
+    pub fn within(&self) -> [bool; 4] {
+        [
+            self.closing <= MAX_CLOSING,
+            self.lateral.length() <= MAX_LATERAL,
+            self.misalignment <= MAX_MISALIGNMENT,
+            self.spin <= MAX_SPIN,
+        ]
+    }
+
+    pub fn dockable(&self) -> bool {
+        self.within().iter().all(|&ok| ok)
+    }
+}
+
+// Flies the ship in: turns it square to the berth, nulls its drift onto the
+// axis, then closes, slowing for contact
+pub fn autopilot(ship: &Ship, port: &DockingPort) -> RcsCommand {
+    let axis = port.facing;
+    let readout = Readout::of(ship, port);
+    let inverse = ship.orientation.inverse();
+
+    let error = ship.nose().cross(-axis);
+    let wanted_spin = inverse * error * ATTITUDE_GAIN;
+    let rotate = (wanted_spin - ship.spin) * RATE_GAIN;
+
+    let offset = ship.collar() - port.position;
+    let lateral = offset - axis * readout.range;
+    let lined_up = lateral.length() <= CONE * readout.range.max(0.0) + MAX_LATERAL * 0.5
+        && readout.misalignment <= MAX_MISALIGNMENT * 0.5;
+    let closing = if lined_up {
+        (readout.range * BRAKING).clamp(CONTACT_SPEED, CRUISE_SPEED)
+    } else {
+        0.0
+    };
+    let wanted_velocity = -lateral * LATERAL_GAIN - axis * closing;
+    let translate = inverse * (wanted_velocity - ship.velocity) * (VELOCITY_GAIN / TRANSLATE_ACCELERATION);
+    RcsCommand { translate: translate.clamp(Vec3::splat(-1.0), Vec3::ONE), rotate: rotate.clamp(Vec3::splat(-1.0), Vec3::ONE) }
+}
+
+// An airlock's outside hatch, the one facing most directly away from what
+// it's connected to, for ships to dock at
+pub fn berth(station: &SpaceStation, airlock: usize) -> Option<DockingPort> {
+    let module = station.modules().get(airlock)?;
+    if module.module_type != ModuleType::Airlock {
+        return None;
+    }
+    let inward = station
+        .neighbours(airlock)
+        .first()
+        .map(|&other| (station.modules()[other].transform.position - module.transform.position).normalize_or_zero())
+        .unwrap_or(Vec3::ZERO);
+    let ports = module.module_type.docking_ports();
+    (0..ports.len())
+        .filter(|&port| !station.connectors().iter().any(|connector| connector.uses(module.id, port)))
+        .map(|port| ports[port].placed(module))
+        .min_by(|a, b| a.facing.dot(inward).total_cmp(&b.facing.dot(inward)))
+}
+
+// Where a ship's got to, from coming in to backing out again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DockingStage {
+    Inbound,
+    Capture,
+    Equalise,
+    Unloading,
+    Departing,
+}
+
+impl DockingStage {
+    pub fn id(self) -> &'static str {
+        match self {
+            DockingStage::Inbound => "inbound",
+            DockingStage::Capture => "capture",
+            DockingStage::Equalise => "equalise",
+            DockingStage::Unloading => "unloading",
+            DockingStage::Departing => "departing",
+        }
+    }
+
+    // How long it's held at the berth; the flying stages take as long as
+    // they take
+    fn seconds(self) -> Option<f32> {
+        match self {
+            DockingStage::Capture => Some(CAPTURE_SECONDS),
+            DockingStage::Equalise => Some(EQUALISE_SECONDS),
+            DockingStage::Unloading => Some(UNLOAD_SECONDS),
+            DockingStage::Inbound | DockingStage::Departing => None,
+        }
+    }
+}
+
+// A ship at an airlock's berth, on its way in, tied up or leaving
+#[derive(Debug, Clone)]
+pub struct Approach {
+    pub arrival: Arrival,
+    pub airlock: ModuleId,
+    pub port: DockingPort,
+    pub ship: Ship,
+    // The player's at the controls; otherwise the autopilot is
+    pub manual: bool,
+    pub stage: DockingStage,
+    // Seconds into the stage
+    pub elapsed: f32,
+}
+
+impl Approach {
+    pub fn readout(&self) -> Readout {
+        Readout::of(&self.ship, &self.port)
+    }
+
+    // Seconds to the end of a stage at the berth
+    pub fn remaining(&self) -> Option<f32> {
+        self.stage.seconds().map(|seconds| (seconds - self.elapsed).max(0.0))
+    }
+}
+
+// What `Docking::update` changed, for the game to report. Airlocks are
+// numbered as the station stood at the time.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DockingChange {
+    Approaching { name: String, airlock: usize },
+    Docked { name: String, airlock: usize },
+    // Came in outside the criteria; the autopilot backs it off to try again
+    Collided { name: String, airlock: usize, closing: f32 },
+    // Unloaded and backing off; what didn't fit in the stores stays aboard
+    Unloaded { name: String, airlock: usize, left_aboard: f32 },
+}
+
+// Ships arriving on schedule. Each is flown in to the first airlock with a
+// berth, by the autopilot unless the player takes the helm, latches on and
+// unloads into stores, then leaves again before the next one's let in.
+#[derive(Debug, Default)]
+pub struct Docking {
+    // Still to come, soonest first
+    arrivals: Vec<Arrival>,
+    approach: Option<Approach>,
+}
+
+impl Docking {
+    pub fn new(mut arrivals: Vec<Arrival>) -> Self {
+        arrivals.sort_by(|a, b| a.at.total_cmp(&b.at));
+        Self { arrivals, approach: None }
+    }
+
+    pub fn approach(&self) -> Option<&Approach> {
+        self.approach.as_ref()
+    }
+
+    // Ships still to come, soonest first
+    pub fn schedule(&self) -> &[Arrival] {
+        &self.arrivals
+    }
+
+    // Moves the `index`th ship still to come to `at` mission seconds, no
+    // sooner than `now`; returns where it's landed on the schedule
+    pub fn reschedule(&mut self, index: usize, at: f32, now: f32) -> Option<usize> {
+        if index >= self.arrivals.len() {
+            return None;
+        }
+        let mut arrival = self.arrivals.remove(index);
+        arrival.at = at.max(now);
+        Some(self.queue(arrival))
+    }
+
+    // After any already due at the same time
+    fn queue(&mut self, arrival: Arrival) -> usize {
+        let index = self.arrivals.partition_point(|queued| queued.at <= arrival.at);
+        self.arrivals.insert(index, arrival);
+        index
+    }
+
+    pub fn manual(&self) -> bool {
+        self.approach.as_ref().is_some_and(|approach| approach.manual)
+    }
+
+    // False with nothing on approach, or once it's latched
+    pub fn take_helm(&mut self) -> bool {
+        let Some(approach) = self.approach.as_mut().filter(|approach| approach.stage == DockingStage::Inbound) else {
+            return false;
+        };
+        approach.manual = true;
+        true
+    }
+
+    pub fn release_helm(&mut self) {
+        if let Some(approach) = &mut self.approach {
+            approach.manual = false;
+        }
+    }
+
+    // Waves off the ship at the berth. One that hasn't unloaded yet comes
+    // round again from the start.
+    pub fn cancel(&mut self) {
+        if let Some(approach) = self.approach.take() {
+            if approach.stage != DockingStage::Departing {
+                self.queue(approach.arrival);
+            }
+        }
+    }
+
+    // Brings in the next ship once it's due, flies the one on approach, on
+    // `command` while the player has the helm, and sees it through its time
+    // at the berth
+    pub fn update(&mut self, station: &mut SpaceStation, command: RcsCommand, delta_time: f32) -> Option<DockingChange> {
+        let Some(approach) = &mut self.approach else {
+            return self.bring_in(station);
+        };
+        // A ship bound for an airlock that's been taken away is waved off
+        let Some(airlock) = station.module_index(approach.airlock) else {
+            self.cancel();
+            return None;
+        };
+        if approach.stage != DockingStage::Inbound {
+            return self.berthed(station, airlock, delta_time);
+        }
+        let command = if approach.manual { command } else { autopilot(&approach.ship, &approach.port) };
+        approach.ship.fire(command, delta_time);
+
+        let readout = approach.readout();
+        if readout.range > 0.0 {
+            return None;
+        }
+        let name = approach.arrival.name.clone();
+        if readout.dockable() {
+            // Held square on the hatch from here on
+            let ship = &mut approach.ship;
+            ship.position -= approach.port.facing * readout.range;
+            ship.velocity = Vec3::ZERO;
+            ship.spin = Vec3::ZERO;
+            approach.manual = false;
+            approach.stage = DockingStage::Capture;
+            approach.elapsed = 0.0;
+            station.log_event(LogSeverity::Info, format!("{} docked at airlock {}", name, airlock));
+            return Some(DockingChange::Docked { name, airlock });
+        }
+
+        // Bounced off: back out along the axis, and the autopilot has it
+        let axis = approach.port.facing;
+        let ship = &mut approach.ship;
+        ship.position -= axis * readout.range;
+        ship.velocity += axis * readout.closing * (1.0 + RESTITUTION);
+        approach.manual = false;
+        station.damage_module(airlock, readout.closing * COLLISION_DAMAGE);
+        station.log_event(
+            LogSeverity::Warning,
+            format!("{} struck airlock {} at {:.2} m/s", name, airlock, readout.closing),
+        );
+        Some(DockingChange::Collided { name, airlock, closing: readout.closing })
+    }
+
+    // Capture, equalising and unloading are timed. The cargo goes into the
+    // stores at the end of unloading and the ship backs off; once it's out
+    // where it came in from, the berth's free for the next.
+    fn berthed(&mut self, station: &mut SpaceStation, airlock: usize, delta_time: f32) -> Option<DockingChange> {
+        let approach = self.approach.as_mut()?;
+        if approach.stage == DockingStage::Departing {
+            approach.ship.fire(RcsCommand::default(), delta_time);
+            if approach.readout().range > START_RANGE {
+                self.approach = None;
+            }
+            return None;
+        }
+        approach.elapsed += delta_time;
+        if approach.remaining().is_some_and(|left| left > 0.0) {
+            return None;
+        }
+        approach.elapsed = 0.0;
+        let name = approach.arrival.name.clone();
+        match approach.stage {
+            DockingStage::Capture => {
+                approach.stage = DockingStage::Equalise;
+                station.log_event(LogSeverity::Info, format!("Airlock {} equalising with {}", airlock, name));
+                None
+            }
+            DockingStage::Equalise => {
+                approach.stage = DockingStage::Unloading;
+                station.log_event(LogSeverity::Info, format!("Airlock {} hatches open to {}", airlock, name));
+                None
+            }
+            _ => {
+                approach.stage = DockingStage::Departing;
+                approach.ship.velocity = approach.port.facing * DEPART_SPEED;
+                let mut left_aboard = 0.0;
+                for consignment in &approach.arrival.cargo {
+                    left_aboard += consignment.amount - station.stow_anywhere(consignment.resource, consignment.amount);
+                }
+                if left_aboard > 0.0 {
+                    let message = format!("{} undocked with {:.0} left aboard; the stores are full", name, left_aboard);
+                    station.log_event(LogSeverity::Warning, message);
+                } else {
+                    station.log_event(LogSeverity::Info, format!("{} unloaded and undocked from airlock {}", name, airlock));
+                }
+                // A regular run is put back on the schedule
+                let arrival = approach.arrival.clone();
+                if let Some(every) = arrival.every.filter(|&every| every > 0.0) {
+                    self.queue(Arrival { at: arrival.at + every, ..arrival });
+                }
+                Some(DockingChange::Unloaded { name, airlock, left_aboard })
+            }
+        }
+    }
+
+    fn bring_in(&mut self, station: &mut SpaceStation) -> Option<DockingChange> {
+        let arrival = self.arrivals.first()?;
+        if station.clock().now().as_secs_f32() < arrival.at {
+            return None;
+        }
+        // It holds off until there's somewhere to dock
+        let (airlock, port) = (0..station.modules().len()).find_map(|i| berth(station, i).map(|port| (i, port)))?;
+        let right = Vec3::Y.cross(port.facing).try_normalize().unwrap_or(Vec3::X);
+        let start = port.position
+            + port.facing * (START_RANGE + NOSE_LENGTH)
+            + right * START_OFFSET.x
+            + Vec3::Y * START_OFFSET.y;
+        let square = Quat::from_rotation_arc(Vec3::NEG_Z, -port.facing);
+        let ship = Ship {
+            position: start,
+            velocity: -port.facing * START_CLOSING,
+            orientation: Quat::from_rotation_y(START_YAW) * square,
+            spin: Vec3::ZERO,
+        };
+        let arrival = self.arrivals.remove(0);
+        let name = arrival.name.clone();
+        station.log_event(
+            LogSeverity::Info,
+            format!("{} ({}) on approach to airlock {}", name, arrival.ship.name(), airlock),
+        );
+        let id = station.modules()[airlock].id;
+        self.approach = Some(Approach {
+            arrival,
+            airlock: id,
+            port,
+            ship,
+            manual: false,
+            stage: DockingStage::Inbound,
+            elapsed: 0.0,
+        });
+        Some(DockingChange::Approaching { name, airlock })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::comms::Comms;
+    use crate::research::Research;
+    use crate::station::InteractionType;
+    use crate::terminal::{Terminal, TerminalPage};
+
+    fn supply_run() -> Arrival {
+        Arrival {
+            name: "Kestrel".to_string(),
+            ship: ShipKind::Supply,
+            at: 0.0,
+            cargo: vec![Consignment { resource: Resource::SpareParts, amount: 10.0 }],
+            every: None,
+        }
+    }
+
+    #[test]
+    fn arriving_ships_dock_on_autopilot_and_bounce_off_a_bad_approach() {
+        let mut station = SpaceStation::create_default_layout();
+        let hatch = berth(&station, 9).unwrap();
+        assert_eq!(hatch.facing, Vec3::NEG_Z);
+        assert!(berth(&station, 5).is_none());
+
+        // Thrusting sideways off the centre of mass turns the ship too
+        let mut ship = Ship { position: Vec3::ZERO, velocity: Vec3::ZERO, orientation: Quat::IDENTITY, spin: Vec3::ZERO };
+        ship.fire(RcsCommand { translate: Vec3::X, rotate: Vec3::ZERO }, 1.0);
+        assert!(ship.velocity.x > 0.0 && ship.spin.y != 0.0);
+
+        let mut docking = Docking::new(vec![supply_run(), supply_run()]);
+        let parts = station.resources().total(Resource::SpareParts);
+        let quiet = RcsCommand::default();
+        assert_eq!(docking.update(&mut station, quiet, 0.1), Some(DockingChange::Approaching { name: "Kestrel".to_string(), airlock: 9 }));
+        assert!(!docking.approach().unwrap().readout().dockable());
+        let mut docked = None;
+        for _ in 0..6000 {
+            if let Some(change) = docking.update(&mut station, quiet, 0.1) {
+                docked = Some(change);
+                break;
+            }
+        }
+        assert_eq!(docked, Some(DockingChange::Docked { name: "Kestrel".to_string(), airlock: 9 }));
+        assert!(!docking.take_helm());
+
+        // Latched, it equalises and unloads, then backs off for the next
+        let mut changes = Vec::new();
+        let berthed = CAPTURE_SECONDS + EQUALISE_SECONDS + UNLOAD_SECONDS;
+        for _ in 0..(berthed * 10.0) as usize + 5 {
+            changes.extend(docking.update(&mut station, quiet, 0.1));
+        }
+        assert_eq!(changes, vec![DockingChange::Unloaded { name: "Kestrel".to_string(), airlock: 9, left_aboard: 0.0 }]);
+        assert_eq!(station.resources().total(Resource::SpareParts), parts + 10.0);
+        assert_eq!(docking.approach().unwrap().stage, DockingStage::Departing);
+        assert_eq!(docking.update(&mut station, quiet, 0.1), None);
+        while docking.approach().is_some() {
+            docking.update(&mut station, quiet, 0.1);
+        }
+
+        // Flown straight in at full thrust it hits too hard, and the
+        // autopilot takes back over
+        docking.update(&mut station, quiet, 0.1);
+        assert!(docking.take_helm());
+        let full = RcsCommand { translate: Vec3::NEG_Z, rotate: Vec3::ZERO };
+        let mut struck = None;
+        for _ in 0..2000 {
+            if let Some(change) = docking.update(&mut station, full, 0.1) {
+                struck = Some(change);
+                break;
+            }
+        }
+        assert!(matches!(struck, Some(DockingChange::Collided { airlock: 9, closing, .. }) if closing > MAX_CLOSING));
+        assert!(!docking.manual());
+        assert!(station.modules()[9].structural_integrity < 1.0);
+
+        // Regular runs come round again, and the comms console moves them
+        // about on the schedule
+        let mut docking = Docking::new(vec![
+            Arrival { name: "Tern".to_string(), at: 900.0, ..supply_run() },
+            Arrival { every: Some(600.0), ..supply_run() },
+        ]);
+        assert_eq!(docking.schedule()[0].name, "Kestrel");
+        while !matches!(docking.update(&mut station, quiet, 0.1), Some(DockingChange::Unloaded { .. })) {}
+        let names: Vec<_> = docking.schedule().iter().map(|arrival| (arrival.name.as_str(), arrival.at)).collect();
+        assert_eq!(names, vec![("Kestrel", 600.0), ("Tern", 900.0)]);
+        assert_eq!(docking.reschedule(0, 1200.0, 0.0), Some(1));
+        assert_eq!(docking.reschedule(1, -50.0, 100.0), Some(0));
+        assert_eq!(docking.schedule()[0].at, 100.0);
+        assert_eq!(docking.reschedule(2, 0.0, 0.0), None);
+        // Waved off once it's unloaded, it isn't put back
+        docking.cancel();
+        assert_eq!(docking.schedule().len(), 2);
+        let mut comms = Terminal::new();
+        let (element, _) = station.modules()[0]
+            .interactive_elements
+            .iter()
+            .enumerate()
+            .find(|(_, element)| element.element_type == InteractionType::Communications)
+            .unwrap();
+        comms.open(0, element, InteractionType::Communications);
+        assert_eq!(comms.page(), TerminalPage::Traffic);
+        comms.scroll_down();
+        assert_eq!(comms.selected_arrival(), Some(1));
+        assert!(comms.screen(&station, &docking, &Research::default(), &Comms::new(Vec::new())).unwrap().lines.iter().any(|line| line.starts_with("> Tern")));
+    }
+}
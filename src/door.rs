@@ -0,0 +1,67 @@
+use glam::{Mat4, Quat, Vec3};
+use serde::{Deserialize, Serialize};
+use crate::geometry::Mesh;
+use crate::module_id::ModuleId;
+use crate::station::{ElementState, InteractiveElement};
+
+pub const DOOR_WIDTH: f32 = 1.6;
+pub const DOOR_HEIGHT: f32 = 2.2;
+// Painted steel, for exporters that need a colour
+pub const DOOR_COLOR: Vec3 = Vec3::new(0.59, 0.61, 0.65);
+
+// The door in a connection between two modules. It's worked through a Door
+// element in the first module, so power, cooldowns, locking and failures go
+// through the same state machine as every other switch: Active is open,
+// and Transitioning slides the panel.
+#[derive(Debug)]
+pub struct Door {
+    // The element belongs to the first, which had the lower index when the
+    // door was fitted (and still does: removals keep the order)
+    pub modules: (ModuleId, ModuleId),
+    pub element: usize,
+    // Centre of the doorway, and the way through it from the first module
+    pub position: Vec3,
+    pub facing: Vec3,
+    pub mesh: Mesh,
+}
+
+impl Door {
+    pub fn new(modules: (ModuleId, ModuleId), element: usize, from: Vec3, to: Vec3) -> Self {
+        Self {
+            modules,
+            element,
+            position: (from + to) * 0.5,
+            facing: (to - from).try_normalize().unwrap_or(Vec3::Z),
+            mesh: Mesh::create_door(DOOR_WIDTH, DOOR_HEIGHT),
+        }
+    }
+
+    pub fn joins(&self, a: ModuleId, b: ModuleId) -> bool {
+        self.modules == (a, b) || self.modules == (b, a)
+    }
+
+    // Places the mesh: square across the doorway when shut, slid sideways
+    // into the wall by `openness` of its width
+    pub fn panel_matrix(&self, openness: f32) -> Mat4 {
+        let rotation = Quat::from_rotation_arc(Vec3::Z, self.facing);
+        let bottom = self.position - rotation * Vec3::Y * (DOOR_HEIGHT * 0.5);
+        Mat4::from_rotation_translation(rotation, bottom) * Mat4::from_translation(Vec3::X * DOOR_WIDTH * openness)
+    }
+}
+
+// 0 shut to 1 open. Locked, jammed and failed doors are shut.
+pub fn openness(element: &InteractiveElement) -> f32 {
+    match (element.state, element.target) {
+        (ElementState::Active, _) => 1.0,
+        (ElementState::Transitioning(progress), Some(ElementState::Active)) => progress,
+        (ElementState::Transitioning(progress), Some(ElementState::Inactive)) => 1.0 - progress,
+        _ => 0.0,
+    }
+}
+
+// Which element works the door on a connection, for saves
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DoorSnapshot {
+    pub modules: [ModuleId; 2],
+    pub element: usize,
+}
@@ -0,0 +1,428 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+use crate::events::StationEvent;
+use crate::module_id::{self, ModuleId};
+use crate::station::SpaceStation;
+
+// Being this close to a module's centre counts as responding to it
+const RESPONSE_RADIUS: f32 = 6.0;
+
+// Full response marks up to this many seconds, none after RESPONSE_CUTOFF
+const RESPONSE_TARGET: f32 = 10.0;
+const RESPONSE_CUTOFF: f32 = 60.0;
+
+const RESPONSE_POINTS: f32 = 40.0;
+const RESOURCE_POINTS: f32 = 20.0;
+const INJURY_POINTS: f32 = 20.0;
+const MODULE_POINTS: f32 = 20.0;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DrillScenario {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub time_limit: f32,
+    #[serde(default = "default_resource_budget")]
+    pub resource_budget: f32,
+    #[serde(rename = "event", default)]
+    pub events: Vec<DrillEvent>,
+}
+
+fn default_resource_budget() -> f32 {
+    100.0
+}
+
+// A scripted emergency: damage to `module` at `at` seconds into the drill,
+// optionally with an explosion. Enough damage breaches the hull.
+#[derive(Debug, Clone, Deserialize)]
+pub struct DrillEvent {
+    pub at: f32,
+    #[serde(deserialize_with = "module_id::layout_index")]
+    pub module: ModuleId,
+    pub damage: f32,
+    #[serde(default)]
+    pub explosion: Option<f32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DrillFile {
+    #[serde(rename = "drill", default)]
+    drills: Vec<DrillScenario>,
+}
+
+pub fn load_scenarios<P: AsRef<Path>>(path: P) -> Result<Vec<DrillScenario>> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path)
+        .with_context(|| format!("failed to read drills from {}", path.display()))?;
+    let file: DrillFile = toml::from_str(&source).context("invalid drill definitions")?;
+    Ok(file.drills)
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Emergency {
+    module: ModuleId,
+    started: f32,
+    responded: Option<f32>,
+}
+
+#[derive(Debug)]
+pub struct Drill {
+    scenario: DrillScenario,
+    elapsed: f32,
+    next_event: usize,
+    emergencies: Vec<Emergency>,
+    resources_spent: f32,
+    injuries: u32,
+    modules_lost_at_start: usize,
+    // The station's running totals of resources used and crew hurt as of the
+    // last update, to count what's new against
+    tallied: Option<(f32, u32)>,
+    // Set when an emergency's answered, until taken for a checkpoint
+    milestone: Option<String>,
+}
+
+// Where a drill had got to, kept in a checkpoint to pick it up again from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DrillProgress {
+    elapsed: f32,
+    next_event: usize,
+    emergencies: Vec<Emergency>,
+    resources_spent: f32,
+    injuries: u32,
+    modules_lost_at_start: usize,
+}
+
+impl Drill {
+    pub fn start(scenario: DrillScenario, station: &SpaceStation) -> Self {
+        let mut events = scenario.events.clone();
+        events.sort_by(|a, b| a.at.total_cmp(&b.at));
+        Self {
+            scenario: DrillScenario { events, ..scenario },
+            elapsed: 0.0,
+            next_event: 0,
+            emergencies: Vec::new(),
+            resources_spent: 0.0,
+            injuries: 0,
+            modules_lost_at_start: modules_lost(station),
+            tallied: Some((station.resources_used(), station.injuries())),
+            milestone: None,
+        }
+    }
+
+    // Carries on from a checkpoint; the station should be put back first
+    pub fn resume(scenario: DrillScenario, progress: DrillProgress) -> Self {
+        let mut events = scenario.events.clone();
+        events.sort_by(|a, b| a.at.total_cmp(&b.at));
+        Self {
+            scenario: DrillScenario { events, ..scenario },
+            elapsed: progress.elapsed,
+            next_event: progress.next_event,
+            emergencies: progress.emergencies,
+            resources_spent: progress.resources_spent,
+            injuries: progress.injuries,
+            modules_lost_at_start: progress.modules_lost_at_start,
+            tallied: None,
+            milestone: None,
+        }
+    }
+
+    pub fn progress(&self) -> DrillProgress {
+        DrillProgress {
+            elapsed: self.elapsed,
+            next_event: self.next_event,
+            emergencies: self.emergencies.clone(),
+            resources_spent: self.resources_spent,
+            injuries: self.injuries,
+            modules_lost_at_start: self.modules_lost_at_start,
+        }
+    }
+
+    // The milestone reached since last asked, if any: the point to checkpoint
+    pub fn take_milestone(&mut self) -> Option<String> {
+        self.milestone.take()
+    }
+
+    pub fn scenario(&self) -> &DrillScenario {
+        &self.scenario
+    }
+
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+
+    // Carried back to the stores, it's refunded
+    fn record_resource_use(&mut self, amount: f32) {
+        self.resources_spent = (self.resources_spent + amount).max(0.0);
+    }
+
+    fn record_injury(&mut self, count: u32) {
+        self.injuries += count;
+    }
+
+    // Fires due events and tracks the player's response. Returns true once the
+    // drill is over: time ran out, or everything fired and was responded to.
+    pub fn update(&mut self, station: &mut SpaceStation, player: Vec3, delta_time: f32) -> bool {
+        self.elapsed += delta_time;
+
+        while let Some(event) = self.scenario.events.get(self.next_event) {
+            if event.at > self.elapsed {
+                break;
+            }
+            let index = station.module_index(event.module);
+            if index.is_some_and(|index| station.damage_module(index, event.damage)) {
+                if let (Some(magnitude), Some(module)) = (event.explosion, index.map(|index| &station.modules()[index])) {
+                    let position = module.transform.position;
+                    station.emit_event(StationEvent::Explosion { position, magnitude });
+                }
+                self.emergencies.push(Emergency {
+                    module: event.module,
+                    started: self.elapsed,
+                    responded: None,
+                });
+            }
+            self.next_event += 1;
+        }
+
+        for emergency in &mut self.emergencies {
+            if emergency.responded.is_some() {
+                continue;
+            }
+            let Some(index) = station.module_index(emergency.module) else {
+                continue;
+            };
+            let module = &station.modules()[index];
+            if module.transform.position.distance(player) <= RESPONSE_RADIUS {
+                emergency.responded = Some(self.elapsed);
+                self.milestone = Some(format!("{}: {:?} secured", index, module.module_type));
+            }
+        }
+
+        // Whatever the station's used up and whoever's been hurt since the
+        // last update counts against the drill
+        let totals = (station.resources_used(), station.injuries());
+        if let Some((used, injuries)) = self.tallied {
+            self.record_resource_use(totals.0 - used);
+            self.record_injury(totals.1.saturating_sub(injuries));
+        }
+        self.tallied = Some(totals);
+
+        let all_fired = self.next_event >= self.scenario.events.len();
+        let all_answered = self.emergencies.iter().all(|e| e.responded.is_some());
+        self.elapsed >= self.scenario.time_limit || (all_fired && all_answered)
+    }
+
+    pub fn finish(self, station: &SpaceStation) -> DrillResult {
+        let response_times: Vec<f32> = self
+            .emergencies
+            .iter()
+            .filter_map(|e| e.responded.map(|t| t - e.started))
+            .collect();
+        let unanswered = self.emergencies.len() - response_times.len();
+        let average_response = if response_times.is_empty() {
+            None
+        } else {
+            Some(response_times.iter().sum::<f32>() / response_times.len() as f32)
+        };
+
+        // Each emergency scores on its own; unanswered ones score nothing
+        let response_score = if self.emergencies.is_empty() {
+            1.0
+        } else {
+            let total: f32 = response_times
+                .iter()
+                .map(|t| 1.0 - ((t - RESPONSE_TARGET) / (RESPONSE_CUTOFF - RESPONSE_TARGET)).clamp(0.0, 1.0))
+                .sum();
+            total / self.emergencies.len() as f32
+        };
+        let resource_score = 1.0 - (self.resources_spent / self.scenario.resource_budget.max(1.0)).clamp(0.0, 1.0);
+        let injury_score = 1.0 - (self.injuries as f32 * 0.5).min(1.0);
+        let modules_lost = modules_lost(station).saturating_sub(self.modules_lost_at_start);
+        let module_score = 1.0 - (modules_lost as f32 * 0.5).min(1.0);
+
+        let breakdown = vec![
+            ScoreLine::new("Response time", response_score * RESPONSE_POINTS, RESPONSE_POINTS),
+            ScoreLine::new("Resources", resource_score * RESOURCE_POINTS, RESOURCE_POINTS),
+            ScoreLine::new("Crew injuries", injury_score * INJURY_POINTS, INJURY_POINTS),
+            ScoreLine::new("Modules lost", module_score * MODULE_POINTS, MODULE_POINTS),
+        ];
+        let score = breakdown.iter().map(|line| line.points).sum::<f32>().round() as u32;
+
+        DrillResult {
+            record: DrillRecord {
+                scenario: self.scenario.name,
+                score,
+                grade: grade(score).to_string(),
+                average_response,
+                unanswered,
+                resources_spent: self.resources_spent,
+                injuries: self.injuries,
+                modules_lost,
+            },
+            breakdown,
+        }
+    }
+}
+
+fn modules_lost(station: &SpaceStation) -> usize {
+    station.modules().iter().filter(|m| !m.atmosphere_sealed).count()
+}
+
+pub fn grade(score: u32) -> &'static str {
+    match score {
+        95.. => "S",
+        85..=94 => "A",
+        70..=84 => "B",
+        55..=69 => "C",
+        40..=54 => "D",
+        _ => "F",
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ScoreLine {
+    pub label: &'static str,
+    pub points: f32,
+    pub max: f32,
+}
+
+impl ScoreLine {
+    fn new(label: &'static str, points: f32, max: f32) -> Self {
+        Self { label, points, max }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct DrillResult {
+    pub record: DrillRecord,
+    pub breakdown: Vec<ScoreLine>,
+}
+
+impl DrillResult {
+    // Failing grade, which lets the player go back to a checkpoint
+    pub fn failed(&self) -> bool {
+        self.record.grade == grade(0)
+    }
+}
+
+// The part of a result kept between sessions
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DrillRecord {
+    pub scenario: String,
+    pub score: u32,
+    pub grade: String,
+    pub average_response: Option<f32>,
+    pub unanswered: usize,
+    pub resources_spent: f32,
+    pub injuries: u32,
+    pub modules_lost: usize,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct DrillHistory {
+    #[serde(rename = "attempt", default)]
+    attempts: Vec<DrillRecord>,
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl DrillHistory {
+    // Starts empty if the file doesn't exist yet
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let mut history = if path.exists() {
+            let source = fs::read_to_string(path)?;
+            toml::from_str(&source).context("invalid drill history")?
+        } else {
+            Self::default()
+        };
+        history.path = Some(path.to_path_buf());
+        Ok(history)
+    }
+
+    pub fn attempts<'a>(&'a self, scenario: &'a str) -> impl Iterator<Item = &'a DrillRecord> + 'a {
+        self.attempts.iter().filter(move |a| a.scenario == scenario)
+    }
+
+    pub fn best(&self, scenario: &str) -> Option<&DrillRecord> {
+        self.attempts.iter().filter(|a| a.scenario == scenario).max_by_key(|a| a.score)
+    }
+
+    pub fn last(&self, scenario: &str) -> Option<&DrillRecord> {
+        self.attempts.iter().rev().find(|a| a.scenario == scenario)
+    }
+
+    pub fn record(&mut self, record: DrillRecord) -> Result<()> {
+        self.attempts.push(record);
+        let Some(path) = &self.path else {
+            return Ok(());
+        };
+        let source = toml::to_string(self)?;
+        fs::write(path, source).with_context(|| format!("failed to write {}", path.display()))
+    }
+}
+
+// What the post-drill screen shows: this attempt against earlier ones
+#[derive(Debug, Clone)]
+pub struct DrillReport {
+    pub result: DrillResult,
+    pub previous: Option<DrillRecord>,
+    pub best: Option<DrillRecord>,
+}
+
+impl DrillReport {
+    // Must be built before the result is added to the history
+    pub fn new(result: DrillResult, history: &DrillHistory) -> Self {
+        let scenario = &result.record.scenario;
+        Self {
+            previous: history.last(scenario).cloned(),
+            best: history.best(scenario).cloned(),
+            result,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stepping::STEP_DT;
+
+    #[test]
+    fn drills_are_charged_for_what_the_station_uses_and_the_crew_it_hurts() {
+        let quarters = 6;
+        let scenario = DrillScenario {
+            name: "Quarters breach".to_string(),
+            description: String::new(),
+            time_limit: 20.0,
+            resource_budget: 4.0,
+            events: vec![DrillEvent { at: 1.0, module: ModuleId::from_layout(quarters), damage: 0.9, explosion: None }],
+        };
+        let mut station = SpaceStation::create_default_layout();
+        let crew = station.modules()[quarters].crew;
+        assert!(crew > 0);
+        let mut drill = Drill::start(scenario, &station);
+        let far = Vec3::splat(500.0);
+
+        // The breach vents the quarters with the crew still in them, and a
+        // new filter and a fire in the lab come out of the drill too
+        let (unit, lab) = (0, 5);
+        let mut elapsed = 0.0;
+        while !drill.update(&mut station, far, STEP_DT) {
+            station.update(STEP_DT);
+            elapsed += STEP_DT;
+            if (elapsed - 2.0).abs() < STEP_DT / 2.0 {
+                assert!(station.replace_filter(quarters, unit));
+                assert!(station.ignite(lab));
+            }
+        }
+        assert!(station.injuries() > crew);
+        let result = drill.finish(&station);
+        assert_eq!(result.record.injuries, station.injuries());
+        assert!(result.record.resources_spent >= 1.0, "{:?}", result.record);
+        let line = |label| result.breakdown.iter().find(|line| line.label == label).unwrap().clone();
+        assert!(line("Resources").points < line("Resources").max);
+        assert_eq!(line("Crew injuries").points, 0.0);
+    }
+}
@@ -0,0 +1,336 @@
+use std::collections::VecDeque;
+use glam::Vec3;
+use crate::event_log::LogSeverity;
+use crate::grime;
+use crate::module_id::ModuleId;
+use crate::navigation::passable;
+use crate::repair::Component;
+use crate::station::{ModuleType, SpaceStation};
+
+// Faster than the crew walk, and over their heads
+pub const FLY_SPEED: f32 = 3.0;
+const CRUISE_HEIGHT: f32 = 2.4;
+// Where it holds station over whoever called it
+const ABOVE_CALLER: f32 = 0.7;
+// Seconds a full battery lasts away from the dock, and takes to charge
+const BATTERY_SECONDS: f32 = 300.0;
+const CHARGE_SECONDS: f32 = 90.0;
+// It turns for home at this charge, and won't set out on less than twice it
+const LOW_BATTERY: f32 = 0.2;
+// Each pass of work puts this much condition back on a worn component
+const PASS_SECONDS: f32 = 5.0;
+const PASS_AMOUNT: f32 = 0.05;
+// Past this much wear a component is worth a pass
+const WORN: f32 = 0.999;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DroneState {
+    Docked,
+    // On its way to whoever called it
+    Flying,
+    // Holding station there: patching the module's most worn component, or
+    // cleaning once nothing's worn
+    Working,
+    Returning,
+}
+
+impl DroneState {
+    pub fn name(self) -> &'static str {
+        match self {
+            DroneState::Docked => "docked",
+            DroneState::Flying => "flying",
+            DroneState::Working => "working",
+            DroneState::Returning => "returning",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DroneRefusal {
+    // Not enough charge left from its last trip
+    Charging,
+    // No way through from where it is to the caller
+    Unreachable,
+}
+
+impl DroneRefusal {
+    // UI string ID
+    pub fn message_id(self) -> &'static str {
+        match self {
+            DroneRefusal::Charging => "drone.charging",
+            DroneRefusal::Unreachable => "drone.unreachable",
+        }
+    }
+}
+
+// The station's maintenance drone. Called, it flies the connection graph to
+// the caller as the crew walk it and works on the module there until it's
+// sent home or its battery runs low.
+#[derive(Debug, Clone)]
+pub struct Drone {
+    pub dock: ModuleId,
+    pub module: ModuleId,
+    pub position: Vec3,
+    pub state: DroneState,
+    // 0 flat to 1 full
+    pub battery: f32,
+    // Where it was called to, and the module that's in
+    target: Vec3,
+    target_module: ModuleId,
+    // Modules still to fly through to get there
+    route: VecDeque<ModuleId>,
+    // Seconds into the current pass of work
+    pass: f32,
+}
+
+impl Drone {
+    // Docked in the first storage module, or else the first module
+    pub fn new(station: &SpaceStation) -> Self {
+        let modules = station.modules();
+        let dock = modules
+            .iter()
+            .find(|module| module.module_type == ModuleType::Storage)
+            .or(modules.first())
+            .map_or(ModuleId::UNPLACED, |module| module.id);
+        let position = station.module_index(dock).map_or(Vec3::ZERO, |at| cruise_point(station, at));
+        Self {
+            dock,
+            module: dock,
+            position,
+            state: DroneState::Docked,
+            battery: 1.0,
+            target: position,
+            target_module: dock,
+            route: VecDeque::new(),
+            pass: 0.0,
+        }
+    }
+
+    // Sends it to `position`, from the dock or wherever it's got to
+    pub fn call(&mut self, station: &SpaceStation, position: Vec3) -> Result<(), DroneRefusal> {
+        if self.battery < LOW_BATTERY * 2.0 {
+            return Err(DroneRefusal::Charging);
+        }
+        let at = station.module_index(self.module).ok_or(DroneRefusal::Unreachable)?;
+        let to = station.nearest_module(position).ok_or(DroneRefusal::Unreachable)?;
+        let path = station.find_path(at, to).ok_or(DroneRefusal::Unreachable)?;
+        self.route = path.into_iter().skip(1).filter_map(|i| station.module_id(i)).collect();
+        self.target = position + Vec3::Y * ABOVE_CALLER;
+        self.target_module = station.modules()[to].id;
+        self.state = DroneState::Flying;
+        self.pass = 0.0;
+        Ok(())
+    }
+
+    // Sends it back to the dock; false if it's there or on its way already
+    pub fn recall(&mut self, station: &SpaceStation) -> bool {
+        if matches!(self.state, DroneState::Docked | DroneState::Returning) {
+            return false;
+        }
+        self.head_home(station);
+        true
+    }
+
+    pub fn update(&mut self, station: &mut SpaceStation, delta_time: f32) {
+        let Some(at) = station.module_index(self.module) else {
+            // Its module's gone, or another station was loaded: a fresh one
+            // is issued at the dock
+            if station.module_index(self.dock).is_none() {
+                *self = Drone::new(station);
+            } else {
+                self.module = self.dock;
+                self.position = self.home_point(station);
+                self.state = DroneState::Docked;
+                self.route.clear();
+            }
+            return;
+        };
+        if self.state == DroneState::Docked {
+            self.battery = (self.battery + delta_time / CHARGE_SECONDS).min(1.0);
+            return;
+        }
+        self.battery = (self.battery - delta_time / BATTERY_SECONDS).max(0.0);
+        if self.battery <= LOW_BATTERY && self.state != DroneState::Returning {
+            station.log_event(LogSeverity::Warning, "Maintenance drone battery low; returning to dock");
+            self.head_home(station);
+        }
+
+        // A door locked since it set out sends it round another way, or
+        // leaves it where it is
+        let blocked = self
+            .route
+            .front()
+            .is_some_and(|&next| station.module_index(next).is_none_or(|next| !passable(station, at, next)));
+        if blocked {
+            let goal = if self.state == DroneState::Returning { self.dock } else { self.target_module };
+            match station.module_index(goal).and_then(|goal| station.find_path(at, goal)) {
+                Some(path) => self.route = path.into_iter().skip(1).filter_map(|i| station.module_id(i)).collect(),
+                None if self.state == DroneState::Returning => self.route.clear(),
+                None => {
+                    self.route.clear();
+                    self.target = cruise_point(station, at);
+                    self.target_module = self.module;
+                }
+            }
+        }
+
+        let destination = if self.state == DroneState::Returning { self.home_point(station) } else { self.target };
+        if !self.fly(station, destination, delta_time) {
+            return;
+        }
+        match self.state {
+            DroneState::Flying => {
+                self.state = DroneState::Working;
+                let at = station.module_index(self.module).unwrap_or(at);
+                station.log_event(LogSeverity::Info, format!("Maintenance drone on station in module {}", at));
+            }
+            DroneState::Returning if self.module == self.dock => {
+                self.state = DroneState::Docked;
+                station.log_event(LogSeverity::Info, "Maintenance drone docked");
+            }
+            DroneState::Working => self.work(station, delta_time),
+            _ => {}
+        }
+    }
+
+    fn head_home(&mut self, station: &SpaceStation) {
+        self.state = DroneState::Returning;
+        let path = station
+            .module_index(self.module)
+            .zip(station.module_index(self.dock))
+            .and_then(|(at, dock)| station.find_path(at, dock));
+        self.route = path.map(|path| path.into_iter().skip(1).filter_map(|i| station.module_id(i)).collect()).unwrap_or_default();
+    }
+
+    fn home_point(&self, station: &SpaceStation) -> Vec3 {
+        station.module_index(self.dock).map_or(self.position, |dock| cruise_point(station, dock))
+    }
+
+    // Heads for the next module's centre, or `destination` once there; true
+    // when it's arrived
+    fn fly(&mut self, station: &SpaceStation, destination: Vec3, delta_time: f32) -> bool {
+        let mut step = FLY_SPEED * delta_time;
+        loop {
+            let target = match self.route.front().map(|&next| station.module_index(next)) {
+                Some(Some(next)) => cruise_point(station, next),
+                Some(None) => {
+                    self.route.clear();
+                    continue;
+                }
+                None => destination,
+            };
+            let offset = target - self.position;
+            let distance = offset.length();
+            if distance > step {
+                self.position += offset / distance * step;
+                return false;
+            }
+            self.position = target;
+            step -= distance;
+            match self.route.pop_front() {
+                Some(next) => self.module = next,
+                None => return true,
+            }
+        }
+    }
+
+    // A pass at a time on the most worn component, then the grime
+    fn work(&mut self, station: &mut SpaceStation, delta_time: f32) {
+        self.pass += delta_time;
+        if self.pass < PASS_SECONDS {
+            return;
+        }
+        self.pass -= PASS_SECONDS;
+        let Some(at) = station.module_index(self.module) else {
+            return;
+        };
+        let module = &station.modules()[at];
+        let worst = Component::ALL.into_iter().min_by(|&a, &b| module.condition(a).total_cmp(&module.condition(b)));
+        match worst {
+            Some(component) if module.condition(component) < WORN => {
+                station.repair_component(at, component, PASS_AMOUNT);
+            }
+            _ if module.worst_grime() > grime::CLEAN => {
+                station.scrub_module(at, grime::CLEAN_RATE * PASS_SECONDS);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn cruise_point(station: &SpaceStation, module: usize) -> Vec3 {
+    station.modules()[module].transform.position + Vec3::Y * CRUISE_HEIGHT
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(drone: &mut Drone, station: &mut SpaceStation, seconds: f32) {
+        for _ in 0..(seconds * 10.0) as usize {
+            drone.update(station, 0.1);
+        }
+    }
+
+    #[test]
+    fn called_drone_flies_out_patches_the_module_and_docks_again() {
+        let mut station = SpaceStation::create_default_layout();
+        let mut drone = Drone::new(&station);
+        assert_eq!(drone.dock, station.modules()[7].id);
+        station.module_mut(5).unwrap().wiring = 0.5;
+
+        let caller = station.modules()[5].transform.position + Vec3::Y * 1.5;
+        drone.call(&station, caller).unwrap();
+        assert_eq!(drone.state, DroneState::Flying);
+        run(&mut drone, &mut station, 20.0);
+        assert_eq!(drone.state, DroneState::Working);
+        assert_eq!(drone.module, station.modules()[5].id);
+        assert!(drone.position.distance(caller + Vec3::Y * ABOVE_CALLER) < 1e-3);
+
+        run(&mut drone, &mut station, 30.0);
+        let wiring = station.modules()[5].wiring;
+        assert!(wiring > 0.7 && wiring < 1.0, "{}", wiring);
+
+        assert!(drone.recall(&station));
+        assert!(!drone.recall(&station));
+        run(&mut drone, &mut station, 20.0);
+        assert_eq!((drone.state, drone.module), (DroneState::Docked, drone.dock));
+        let battery = drone.battery;
+        run(&mut drone, &mut station, 5.0);
+        assert!(drone.battery > battery);
+    }
+
+    #[test]
+    fn drone_refuses_on_a_low_battery_or_with_no_way_through() {
+        let mut station = SpaceStation::create_default_layout();
+        let mut drone = Drone::new(&station);
+        let lab = station.modules()[5].transform.position;
+
+        let door = station.door_between(0, 1).unwrap();
+        station.set_door_locked(door, true);
+        assert_eq!(drone.call(&station, lab), Err(DroneRefusal::Unreachable));
+        station.set_door_locked(door, false);
+
+        drone.battery = LOW_BATTERY;
+        assert_eq!(drone.call(&station, lab), Err(DroneRefusal::Charging));
+        drone.battery = LOW_BATTERY * 2.0;
+        drone.call(&station, lab).unwrap();
+        run(&mut drone, &mut station, 3.0);
+        drone.battery = LOW_BATTERY;
+        drone.update(&mut station, 0.1);
+        assert_eq!(drone.state, DroneState::Returning);
+    }
+
+    #[test]
+    fn drone_is_reissued_at_the_dock_when_its_module_goes() {
+        let mut station = SpaceStation::create_default_layout();
+        let mut drone = Drone::new(&station);
+        drone.call(&station, station.modules()[9].transform.position).unwrap();
+        run(&mut drone, &mut station, 30.0);
+        assert_eq!(drone.module, station.modules()[9].id);
+
+        station.remove_module(9).unwrap();
+        drone.update(&mut station, 0.1);
+        assert_eq!((drone.state, drone.module), (DroneState::Docked, drone.dock));
+    }
+}
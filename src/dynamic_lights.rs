@@ -0,0 +1,362 @@
+use std::f32::consts::PI;
+use glam::Vec3;
+use crate::station::{InteractionType, SpaceStation, StationTelemetry};
+
+// Cabin lighting when the grid can't keep up: emergency strips only
+pub const EMERGENCY_AMBIENT: f32 = 0.12;
+// What the cabin lights turn to while the alarm's sounding
+pub const ALARM_RED: Vec3 = Vec3::new(1.0, 0.12, 0.08);
+
+// Ceiling lights a LightControl switches, at full dimmer
+const CEILING_COLOR: Vec3 = Vec3::new(1.0, 0.96, 0.9);
+const CEILING_INTENSITY: f32 = 0.8;
+const CEILING_RANGE: f32 = 9.0;
+// The same fittings on their battery-backed red emergency circuit
+const EMERGENCY_INTENSITY: f32 = 0.35;
+
+// Helmet battery, as a fraction of a full charge per second
+const HELMET_DRAIN: f32 = 1.0 / 240.0;
+const HELMET_RECHARGE: f32 = 1.0 / 600.0;
+
+// Lights that move or get switched during play. The raylib scene has no
+// per-pixel lighting, so surfaces are tinted by the light reaching their
+// centre and each light draws a pool where it lands (like the sun patches).
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Light {
+    pub position: Vec3,
+    // Zero for a point light
+    pub direction: Vec3,
+    pub color: Vec3,
+    pub intensity: f32,
+    pub range: f32,
+    // Half-angle of a spot light's cone, in radians
+    pub cone: f32,
+}
+
+impl Light {
+    pub fn point(position: Vec3, color: Vec3, intensity: f32, range: f32) -> Self {
+        Self {
+            position,
+            direction: Vec3::ZERO,
+            color,
+            intensity,
+            range,
+            cone: PI,
+        }
+    }
+
+    pub fn spot(position: Vec3, direction: Vec3, color: Vec3, intensity: f32, range: f32, cone: f32) -> Self {
+        Self {
+            position,
+            direction: direction.normalize_or_zero(),
+            color,
+            intensity,
+            range,
+            cone,
+        }
+    }
+
+    // How much of this light lands on a surface at `point` facing `normal`
+    pub fn reach(&self, point: Vec3, normal: Vec3) -> f32 {
+        let offset = point - self.position;
+        let distance = offset.length();
+        if distance >= self.range || distance <= f32::EPSILON {
+            return 0.0;
+        }
+        let to_light = -offset / distance;
+        let facing = normal.dot(to_light).max(0.0);
+        // Smooth falloff to zero at the range
+        let falloff = (1.0 - distance / self.range).powi(2);
+        let cone = if self.direction == Vec3::ZERO {
+            1.0
+        } else {
+            // Soft edge over the outer fifth of the cone
+            let angle = self.direction.dot(-to_light).clamp(-1.0, 1.0).acos();
+            ((self.cone - angle) / (self.cone * 0.2)).clamp(0.0, 1.0)
+        };
+        self.intensity * facing * falloff * cone
+    }
+}
+
+// Everything lighting the scene this frame
+#[derive(Debug, Clone, Default)]
+pub struct LightSet {
+    pub ambient: f32,
+    // How far the cabin lights have gone over to alarm red, 0 to 1
+    pub alarm: f32,
+    lights: Vec<Light>,
+}
+
+impl LightSet {
+    pub fn new(ambient: f32) -> Self {
+        Self {
+            ambient,
+            alarm: 0.0,
+            lights: Vec::new(),
+        }
+    }
+
+    pub fn add(&mut self, light: Light) {
+        self.lights.push(light);
+    }
+
+    pub fn lights(&self) -> &[Light] {
+        &self.lights
+    }
+
+    pub fn illuminate(&self, point: Vec3, normal: Vec3) -> Vec3 {
+        self.lights
+            .iter()
+            .fold(self.cabin(), |total, light| total + light.color * light.reach(point, normal))
+    }
+
+    // The cabin lights on their own, red while the alarm's going
+    pub fn cabin(&self) -> Vec3 {
+        Vec3::splat(self.ambient).lerp(ALARM_RED * self.ambient, self.alarm.clamp(0.0, 1.0))
+    }
+
+    // A surface colour as lit here; light past white doesn't brighten further
+    pub fn shade(&self, base: Vec3, point: Vec3, normal: Vec3) -> Vec3 {
+        (base * self.illuminate(point, normal)).min(Vec3::ONE)
+    }
+}
+
+// Cabin lights run off the grid: full when it's healthy, dimming as it
+// wobbles and down to emergency lighting when the module has been shed
+pub fn cabin_ambient(telemetry: &StationTelemetry, powered: bool) -> f32 {
+    if !powered {
+        return EMERGENCY_AMBIENT;
+    }
+    0.4 + 0.6 * telemetry.grid_stability.clamp(0.0, 1.0)
+}
+
+// A light at each module's LightControl while it's switched on, at the
+// module's dimmer. While the alarm's going they turn red, and modules with
+// theirs off or without power come up on the emergency circuit.
+pub fn ceiling_lights(station: &SpaceStation, alarm: f32) -> Vec<Light> {
+    let alarm = alarm.clamp(0.0, 1.0);
+    let mut lights = Vec::new();
+    for (module_idx, module) in station.modules().iter().enumerate() {
+        let on = module.lights_on();
+        let intensity = if on { CEILING_INTENSITY * module.light_level } else { 0.0 };
+        let emergency = EMERGENCY_INTENSITY * alarm;
+        if intensity.max(emergency) <= f32::EPSILON {
+            continue;
+        }
+        let color = CEILING_COLOR.lerp(ALARM_RED, alarm);
+        for (element_idx, element) in module.interactive_elements.iter().enumerate() {
+            if element.element_type != InteractionType::LightControl {
+                continue;
+            }
+            if let Some(position) = station.element_position(module_idx, element_idx) {
+                lights.push(Light::point(position, color, intensity.max(emergency), CEILING_RANGE));
+            }
+        }
+    }
+    lights
+}
+
+// An axis-aligned room, seen from inside
+#[derive(Debug, Clone, Copy)]
+pub struct Room {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Room {
+    // Where a ray from inside the room meets a wall, and the wall's inward normal
+    pub fn hit(&self, origin: Vec3, direction: Vec3) -> Option<(Vec3, Vec3)> {
+        let mut nearest: Option<(f32, Vec3)> = None;
+        for axis in 0..3 {
+            let d = direction[axis];
+            if d.abs() <= f32::EPSILON {
+                continue;
+            }
+            let (plane, normal_sign) = if d > 0.0 { (self.max[axis], -1.0) } else { (self.min[axis], 1.0) };
+            let t = (plane - origin[axis]) / d;
+            if t > 0.0 && nearest.is_none_or(|(best, _)| t < best) {
+                let mut normal = Vec3::ZERO;
+                normal[axis] = normal_sign;
+                nearest = Some((t, normal));
+            }
+        }
+        nearest.map(|(t, normal)| (origin + direction * t, normal))
+    }
+}
+
+// The bright patch a light throws on a wall
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LightPool {
+    pub center: Vec3,
+    pub normal: Vec3,
+    pub radius: f32,
+    pub color: Vec3,
+    // 0..1, for the pool's alpha
+    pub strength: f32,
+}
+
+// Spot lights land where they point; point lights pool on the floor below
+pub fn light_pool(light: &Light, room: &Room) -> Option<LightPool> {
+    let point = light.direction == Vec3::ZERO;
+    let aim = if point { Vec3::NEG_Y } else { light.direction };
+    let (center, normal) = room.hit(light.position, aim)?;
+    let distance = center.distance(light.position);
+    if distance >= light.range {
+        return None;
+    }
+    // Glancing hits spread a spot light's pool out
+    let facing = normal.dot(-aim).max(0.2);
+    let radius = if point {
+        light.range * 0.4
+    } else {
+        distance * light.cone.tan() / facing.sqrt()
+    };
+    Some(LightPool {
+        center,
+        normal,
+        radius,
+        color: light.color,
+        strength: light.reach(center, normal).min(1.0),
+    })
+}
+
+// Suit-powered spot light on the helmet. It drains the suit battery while on
+// and trickle-charges while off; it switches itself off when flat.
+#[derive(Debug)]
+pub struct HelmetLight {
+    pub on: bool,
+    battery: f32,
+}
+
+impl Default for HelmetLight {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl HelmetLight {
+    pub fn new() -> Self {
+        Self { on: false, battery: 1.0 }
+    }
+
+    // False if the battery is too flat to switch on
+    pub fn toggle(&mut self) -> bool {
+        if !self.on && self.battery <= 0.0 {
+            return false;
+        }
+        self.on = !self.on;
+        true
+    }
+
+    // True on the frame the battery runs out
+    pub fn update(&mut self, delta_time: f32) -> bool {
+        if self.on {
+            self.battery = (self.battery - HELMET_DRAIN * delta_time).max(0.0);
+            if self.battery <= 0.0 {
+                self.on = false;
+                return true;
+            }
+        } else {
+            self.battery = (self.battery + HELMET_RECHARGE * delta_time).min(1.0);
+        }
+        false
+    }
+
+    // 0..1
+    pub fn battery(&self) -> f32 {
+        self.battery
+    }
+
+    pub fn light(&self, eye: Vec3, forward: Vec3) -> Option<Light> {
+        // The beam browns out over the last tenth of the charge
+        let strength = (self.battery * 10.0).min(1.0);
+        self.on.then(|| Light::spot(eye, forward, Vec3::new(1.0, 0.97, 0.9), 1.5 * strength, 12.0, 0.35))
+    }
+}
+
+// Portable work lights set down around the station
+#[derive(Debug, Default)]
+pub struct WorkLights {
+    positions: Vec<Vec3>,
+}
+
+impl WorkLights {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn place(&mut self, position: Vec3) {
+        self.positions.push(position);
+    }
+
+    pub fn positions(&self) -> &[Vec3] {
+        &self.positions
+    }
+
+    pub fn nearest(&self, position: Vec3, reach: f32) -> Option<usize> {
+        self.positions
+            .iter()
+            .enumerate()
+            .map(|(i, p)| (i, p.distance(position)))
+            .filter(|(_, distance)| *distance <= reach)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+    }
+
+    pub fn pick_up(&mut self, index: usize) -> Option<Vec3> {
+        (index < self.positions.len()).then(|| self.positions.remove(index))
+    }
+
+    pub fn lights(&self) -> impl Iterator<Item = Light> + '_ {
+        self.positions
+            .iter()
+            .map(|&position| Light::point(position + Vec3::Y * 0.3, Vec3::new(1.0, 0.9, 0.7), 1.2, 5.0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::station::ElementState;
+
+    #[test]
+    fn light_switches_own_their_modules_lights_and_go_red_in_an_alarm() {
+        let mut station = SpaceStation::create_default_layout();
+        let quarters = 6;
+        let switch = station.modules()[quarters]
+            .interactive_elements
+            .iter()
+            .position(|element| element.element_type == InteractionType::LightControl)
+            .unwrap();
+        assert!(ceiling_lights(&station, 0.0).is_empty());
+
+        // Switching on puts a light at the switch, which the dimmer turns down
+        // along with what it draws
+        let dark = station.modules()[quarters].power_draw();
+        station.set_element_state(quarters, switch, ElementState::Active);
+        let lit = station.modules()[quarters].power_draw();
+        let lights = ceiling_lights(&station, 0.0);
+        assert_eq!(lights.len(), 1);
+        assert_eq!(Some(lights[0].position), station.element_position(quarters, switch));
+        assert!(station.set_light_level(quarters, 0.5));
+        assert!(!station.set_light_level(5, 0.5));
+        let dimmed = ceiling_lights(&station, 0.0);
+        assert!((dimmed[0].intensity - lights[0].intensity * 0.5).abs() < 1e-5);
+        assert!(station.modules()[quarters].power_draw() < lit && station.modules()[quarters].power_draw() > dark);
+        let restored = SpaceStation::from_snapshot(&station.snapshot());
+        assert_eq!(restored.modules()[quarters].light_level, 0.5);
+
+        // Shed modules go dark, until the alarm brings every fitting up red
+        station.module_mut(quarters).unwrap().powered = false;
+        assert!(ceiling_lights(&station, 0.0).is_empty());
+        let alarm = ceiling_lights(&station, 1.0);
+        let switches = station
+            .modules()
+            .iter()
+            .flat_map(|module| &module.interactive_elements)
+            .filter(|element| element.element_type == InteractionType::LightControl)
+            .count();
+        assert_eq!(alarm.len(), switches);
+        assert!(alarm.iter().all(|light| light.color.abs_diff_eq(ALARM_RED, 1e-5) && light.intensity > 0.0));
+    }
+}
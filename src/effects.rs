@@ -0,0 +1,196 @@
+use std::time::Duration;
+use glam::Vec3;
+use crate::config::ParticleLimits;
+use crate::events::StationEvent;
+use crate::incidents::IncidentKind;
+use rand::Rng;
+use crate::particle::{EmissionPattern, ParticleEmitter, ParticleType};
+use crate::rng::SimRng;
+use crate::tunables::ParticleTunables;
+
+// A short-lived emitter spawned by a station event
+pub struct EventEmitter {
+    pub name: String,
+    pub emitter: ParticleEmitter,
+    remaining: f32,
+    // Emission rate it was built with, which `sustain` scales
+    full_rate: f32,
+}
+
+impl EventEmitter {
+    pub fn is_finished(&self) -> bool {
+        self.remaining <= 0.0 && self.emitter.particles.is_empty()
+    }
+}
+
+// Particle effects for station events: sparks from explosions, venting debris
+// from hull breaches. Emitters stop emitting after a few seconds and are
+// dropped once their last particle dies.
+pub struct EventEffects {
+    emitters: Vec<EventEmitter>,
+    spawned: usize,
+    // Seeds each new emitter
+    rng: SimRng,
+    limits: ParticleLimits,
+}
+
+impl EventEffects {
+    pub fn new(rng: SimRng) -> Self {
+        Self {
+            emitters: Vec::new(),
+            spawned: 0,
+            rng,
+            limits: ParticleLimits::default(),
+        }
+    }
+
+    // Applies to emitters spawned from now on
+    pub fn set_limits(&mut self, limits: ParticleLimits) {
+        self.limits = limits;
+    }
+
+    pub fn handle_event(&mut self, event: &StationEvent) {
+        let (kind, position, duration) = match event {
+            StationEvent::Explosion { position, magnitude } => ("sparks", *position, 1.0 + magnitude),
+            StationEvent::HullBreach { position, .. } => ("debris", *position, 6.0),
+            StationEvent::Decompression { position, .. } => ("debris", *position, 3.0),
+            StationEvent::ConnectionFailed { position, .. } => ("debris", *position, 4.0),
+            StationEvent::Incident { kind: IncidentKind::Micrometeorite, position: Some(position), .. } => {
+                ("debris", *position, 2.0)
+            }
+            // The arc that lit it; the flames are kept going by `sustain`
+            StationEvent::FireStarted { position, .. } => ("sparks", *position, 1.0),
+            // The pod's clamps letting go; its plume is kept going by `sustain`
+            StationEvent::PodLaunched { position, .. } => ("sparks", *position, 0.5),
+            _ => return,
+        };
+        self.spawn(kind, position, duration);
+    }
+
+    // Keeps an emitter going for as long as it's called each frame, as for
+    // a fire that's still burning, emitting at `strength` of its full rate
+    // from wherever `position` has got to; once the calls stop it runs down
+    // like any other. `key` tells one sustained emitter from another.
+    pub fn sustain(&mut self, key: &str, kind: &str, position: Vec3, strength: f32) {
+        let index = match self.emitters.iter().position(|effect| effect.name == key) {
+            Some(index) => index,
+            None => {
+                if !self.spawn(kind, position, 0.0) {
+                    return;
+                }
+                let index = self.emitters.len() - 1;
+                self.emitters[index].name = key.to_string();
+                index
+            }
+        };
+        let effect = &mut self.emitters[index];
+        effect.emitter.position = position;
+        // Long enough to bridge a frame or two
+        effect.remaining = 0.25;
+        effect.emitter.emission_rate = effect.full_rate * strength.clamp(0.1, 1.0);
+        effect.emitter.emission_interval = Duration::from_secs_f32(1.0 / effect.emitter.emission_rate);
+    }
+
+    // Starts an emitter of a named kind; false if the kind is unknown
+    pub fn spawn(&mut self, kind: &str, position: Vec3, duration: f32) -> bool {
+        let mut emitter = match kind {
+            "sparks" => ParticleEmitter::builder()
+                .position(position)
+                .direction(Vec3::Y)
+                .particle_type(ParticleType::Spark)
+                .emission_pattern(EmissionPattern::Sphere { radius: 0.5 })
+                .emission_rate(40.0)
+                .initial_velocity(3.0)
+                .particle_size(0.05)
+                .particle_lifetime(Duration::from_secs_f32(0.8))
+                .seed(self.rng.gen())
+                .build(),
+            "fire" => ParticleEmitter::builder()
+                .position(position)
+                .direction(Vec3::Y)
+                .particle_type(ParticleType::Fire)
+                .emission_pattern(EmissionPattern::Sphere { radius: 0.3 })
+                .emission_rate(30.0)
+                .initial_velocity(0.8)
+                .particle_size(0.12)
+                .particle_lifetime(Duration::from_secs_f32(1.2))
+                .seed(self.rng.gen())
+                .build(),
+            "smoke" => ParticleEmitter::builder()
+                .position(position)
+                .direction(Vec3::Y)
+                .particle_type(ParticleType::Smoke)
+                .emission_pattern(EmissionPattern::Sphere { radius: 0.4 })
+                .emission_rate(10.0)
+                .initial_velocity(0.4)
+                .particle_size(0.2)
+                .particle_lifetime(Duration::from_secs_f32(4.0))
+                .seed(self.rng.gen())
+                .build(),
+            // An escape pod's motor
+            "plume" => ParticleEmitter::builder()
+                .position(position)
+                .direction(Vec3::Y)
+                .particle_type(ParticleType::Glow)
+                .emission_pattern(EmissionPattern::Sphere { radius: 0.4 })
+                .emission_rate(60.0)
+                .initial_velocity(2.0)
+                .particle_size(0.15)
+                .particle_lifetime(Duration::from_secs_f32(0.6))
+                .seed(self.rng.gen())
+                .build(),
+            "debris" => ParticleEmitter::builder()
+                .position(position)
+                .direction(Vec3::Y)
+                .particle_type(ParticleType::Debris)
+                .emission_pattern(EmissionPattern::Sphere { radius: 1.5 })
+                .emission_rate(20.0)
+                .initial_velocity(1.5)
+                .particle_size(0.1)
+                .particle_lifetime(Duration::from_secs_f32(2.5))
+                .seed(self.rng.gen())
+                .build(),
+            _ => return false,
+        };
+
+        emitter.emission_interval = Duration::from_secs_f32(1.0 / emitter.emission_rate);
+        emitter.max_particles = self.limits.per_emitter;
+
+        // Make room by dropping the oldest effects
+        let excess = (self.emitters.len() + 1).saturating_sub(self.limits.max_emitters.max(1));
+        self.emitters.drain(..excess);
+
+        self.spawned += 1;
+        self.emitters.push(EventEmitter {
+            name: format!("{}#{}", kind, self.spawned),
+            full_rate: emitter.emission_rate,
+            emitter,
+            remaining: duration,
+        });
+        true
+    }
+
+    pub fn update(&mut self, delta_time: f32, tunables: &ParticleTunables) {
+        for effect in &mut self.emitters {
+            if effect.remaining > 0.0 {
+                effect.remaining -= delta_time;
+                effect.emitter.update(delta_time, tunables);
+            } else {
+                // Let the existing particles play out without emitting more
+                effect.emitter.particles.retain_mut(|particle| {
+                    particle.update(delta_time, tunables);
+                    particle.age < particle.lifetime
+                });
+            }
+        }
+        self.emitters.retain(|effect| !effect.is_finished());
+    }
+
+    pub fn emitters(&self) -> &[EventEmitter] {
+        &self.emitters
+    }
+
+    pub fn particle_count(&self) -> usize {
+        self.emitters.iter().map(|effect| effect.emitter.particles.len()).sum()
+    }
+}
@@ -0,0 +1,134 @@
+// Share of a flare spent building up and dying away
+const RAMP: f32 = 0.15;
+// How often noisy readings change, per second
+const NOISE_RATE: f32 = 8.0;
+// Characters corrupted screens show instead of text
+const GLITCH_CHARS: &[char] = &['#', '%', '&', '@', '$', '?', '/', '\\', '|', '='];
+
+// Cheap repeatable noise in -1..1, for effects that have to look random
+// without drawing on the simulation's generator
+pub fn noise(a: u32, b: u32) -> f32 {
+    let mut x = a.wrapping_mul(0x9E37_79B1) ^ b.wrapping_mul(0x85EB_CA77);
+    x ^= x >> 15;
+    x = x.wrapping_mul(0x2C1B_3C6D);
+    x ^= x >> 12;
+    x = x.wrapping_mul(0x297A_2D39);
+    x ^= x >> 15;
+    (x as f32 / u32::MAX as f32) * 2.0 - 1.0
+}
+
+// Electromagnetic interference from a solar flare: it builds, holds and
+// fades over the flare's duration. While it lasts sensors read noisy,
+// screens glitch, and commands sent over the station network can be lost.
+#[derive(Debug, Clone)]
+pub struct Interference {
+    peak: f32,
+    duration: f32,
+    elapsed: f32,
+}
+
+impl Default for Interference {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Interference {
+    pub fn new() -> Self {
+        Self {
+            peak: 0.0,
+            duration: 0.0,
+            elapsed: 0.0,
+        }
+    }
+
+    // Starts a flare, replacing any under way; `peak` is 0 to 1
+    pub fn begin(&mut self, peak: f32, duration: f32) {
+        self.peak = peak.clamp(0.0, 1.0);
+        self.duration = duration.max(0.0);
+        self.elapsed = 0.0;
+    }
+
+    pub fn active(&self) -> bool {
+        self.elapsed < self.duration
+    }
+
+    // 0 with no flare, up to its peak at the height of one
+    pub fn level(&self) -> f32 {
+        if !self.active() {
+            return 0.0;
+        }
+        let progress = self.elapsed / self.duration.max(f32::EPSILON);
+        let envelope = (progress / RAMP).min((1.0 - progress) / RAMP).min(1.0);
+        self.peak * envelope
+    }
+
+    // True on the update the flare ends
+    pub fn update(&mut self, delta_time: f32) -> bool {
+        if !self.active() {
+            return false;
+        }
+        self.elapsed += delta_time;
+        !self.active()
+    }
+
+    // A sensor reading of `value`, off by up to `spread` at full
+    // interference; `channel` keeps different readings from moving together
+    pub fn sensor(&self, value: f32, spread: f32, channel: u32) -> f32 {
+        let level = self.level();
+        if level <= 0.0 {
+            return value;
+        }
+        let tick = (self.elapsed * NOISE_RATE) as u32;
+        value + spread * level * noise(tick, channel)
+    }
+}
+
+// A line of text as a screen under `level` interference shows it on frame
+// `frame`: some characters scrambled, and a sideways jolt in pixels
+pub fn glitch_line(line: &str, level: f32, frame: u32, row: u32) -> (String, f32) {
+    if level <= 0.0 {
+        return (line.to_string(), 0.0);
+    }
+    // Rows tear in short bursts rather than shimmering every frame
+    let burst = frame / 4;
+    let torn = noise(burst, row).abs() < level * 0.5;
+    let offset = if torn { noise(burst, row + 1000) * 24.0 * level } else { 0.0 };
+    let text = line
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let roll = noise(frame / 2 + row * 7919, i as u32);
+            if c != ' ' && roll.abs() < level * 0.15 {
+                GLITCH_CHARS[((roll.abs() * 1000.0) as usize) % GLITCH_CHARS.len()]
+            } else {
+                c
+            }
+        })
+        .collect();
+    (text, offset)
+}
+
+// Static over the HUD on frame `frame`: bands as (top, height, alpha), all
+// 0 to 1 of the screen height. At high levels the whole HUD drops out now
+// and then.
+pub fn static_bands(level: f32, frame: u32) -> Vec<(f32, f32, f32)> {
+    if level <= 0.0 {
+        return Vec::new();
+    }
+    if noise(frame / 3, 77).abs() < level * level * 0.08 {
+        return vec![(0.0, 1.0, 0.85)];
+    }
+    let count = (level * 6.0).ceil() as u32;
+    (0..count)
+        .filter_map(|i| {
+            let shown = noise(frame, i + 500).abs() < level;
+            shown.then(|| {
+                let top = noise(frame, i).abs();
+                let height = 0.004 + noise(frame, i + 100).abs() * 0.03 * level;
+                let alpha = 0.15 + noise(frame, i + 200).abs() * 0.45 * level;
+                (top, height, alpha)
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,269 @@
+use glam::Vec3;
+use crate::station::{ElementState, InteractionType, ModuleType, SpaceStation};
+
+// Suit air for one trip, in seconds
+pub const SUIT_OXYGEN: f32 = 300.0;
+const LOW_OXYGEN: f32 = 0.25;
+// Jetpack acceleration at full thrust, and how many seconds of it a tank holds
+const THRUST: f32 = 2.0;
+const FUEL_SECONDS: f32 = 90.0;
+const MAX_SPEED: f32 = 8.0;
+pub const TETHER_LENGTH: f32 = 30.0;
+// Pumping the airlock down or back up
+pub const CYCLE_SECONDS: f32 = 6.0;
+// The outer hatch is this far out from the airlock's centre, away from the
+// module it's docked to, and usable from this close
+const HATCH_DISTANCE: f32 = 4.0;
+pub const HATCH_REACH: f32 = 3.0;
+const EYE_HEIGHT: f32 = 1.5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EvaPhase {
+    Inside,
+    CyclingOut { airlock: usize, remaining: f32 },
+    Outside { airlock: usize },
+    CyclingIn { airlock: usize, remaining: f32 },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EvaEvent {
+    // The outer hatch opened; the player is at `hatch`
+    Outside { hatch: Vec3 },
+    // Back in and repressurised; the player is at `position`
+    Inside { position: Vec3 },
+    LowOxygen,
+    // The suit ran dry and its emergency recall brought the player back in
+    OxygenOut,
+    FuelOut,
+    TetherTaut,
+}
+
+// Spacewalks out of an Airlock module: the airlock cycles, the player
+// floats free on a jetpack with a suit air timer, optionally clipped to a
+// tether at the hatch, and cycles back in the same way
+#[derive(Debug)]
+pub struct Eva {
+    phase: EvaPhase,
+    pub position: Vec3,
+    velocity: Vec3,
+    // Seconds of air left
+    oxygen: f32,
+    // 0..1
+    fuel: f32,
+    pub tethered: bool,
+    warned_low_oxygen: bool,
+    tether_taut: bool,
+}
+
+impl Default for Eva {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Eva {
+    pub fn new() -> Self {
+        Self {
+            phase: EvaPhase::Inside,
+            position: Vec3::ZERO,
+            velocity: Vec3::ZERO,
+            oxygen: SUIT_OXYGEN,
+            fuel: 1.0,
+            tethered: true,
+            warned_low_oxygen: false,
+            tether_taut: false,
+        }
+    }
+
+    pub fn phase(&self) -> EvaPhase {
+        self.phase
+    }
+
+    pub fn outside(&self) -> bool {
+        matches!(self.phase, EvaPhase::Outside { .. })
+    }
+
+    // Cycling; the player can't move until it's done
+    pub fn cycling(&self) -> bool {
+        matches!(self.phase, EvaPhase::CyclingOut { .. } | EvaPhase::CyclingIn { .. })
+    }
+
+    pub fn oxygen(&self) -> f32 {
+        self.oxygen / SUIT_OXYGEN
+    }
+
+    pub fn fuel(&self) -> f32 {
+        self.fuel
+    }
+
+    pub fn speed(&self) -> f32 {
+        self.velocity.length()
+    }
+
+    // Distance out from the hatch, for the tether readout
+    pub fn tether_out(&self, station: &SpaceStation) -> f32 {
+        match self.phase {
+            EvaPhase::Outside { airlock } => {
+                hatch_position(station, airlock).map_or(0.0, |hatch| hatch.distance(self.position))
+            }
+            _ => 0.0,
+        }
+    }
+
+    // Starts cycling out through an airlock's control; false if it isn't one
+    pub fn begin_exit(&mut self, station: &mut SpaceStation, airlock: usize) -> bool {
+        let is_airlock = station.modules().get(airlock).is_some_and(|m| m.module_type == ModuleType::Airlock);
+        if self.phase != EvaPhase::Inside || !is_airlock {
+            return false;
+        }
+        self.phase = EvaPhase::CyclingOut { airlock, remaining: CYCLE_SECONDS };
+        set_control_state(station, airlock, ElementState::Transitioning(0.0));
+        true
+    }
+
+    // Starts cycling back in; false unless the player is at the hatch
+    pub fn begin_entry(&mut self, station: &mut SpaceStation) -> bool {
+        let EvaPhase::Outside { airlock } = self.phase else {
+            return false;
+        };
+        match hatch_position(station, airlock) {
+            Some(hatch) if hatch.distance(self.position) <= HATCH_REACH => {
+                self.phase = EvaPhase::CyclingIn { airlock, remaining: CYCLE_SECONDS };
+                self.velocity = Vec3::ZERO;
+                set_control_state(station, airlock, ElementState::Transitioning(0.0));
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub fn toggle_tether(&mut self) -> bool {
+        self.tethered = !self.tethered;
+        self.tether_taut = false;
+        self.tethered
+    }
+
+    // `thrust` is world-space, up to 1 per axis; `brake` fires against the drift
+    pub fn update(&mut self, station: &mut SpaceStation, thrust: Vec3, brake: bool, delta_time: f32) -> Vec<EvaEvent> {
+        let mut events = Vec::new();
+        match self.phase {
+            EvaPhase::Inside => {}
+            EvaPhase::CyclingOut { airlock, remaining } | EvaPhase::CyclingIn { airlock, remaining } => {
+                let remaining = remaining - delta_time;
+                let leaving = matches!(self.phase, EvaPhase::CyclingOut { .. });
+                if remaining > 0.0 {
+                    let progress = 1.0 - remaining / CYCLE_SECONDS;
+                    set_control_state(station, airlock, ElementState::Transitioning(progress));
+                    self.phase = if leaving {
+                        EvaPhase::CyclingOut { airlock, remaining }
+                    } else {
+                        EvaPhase::CyclingIn { airlock, remaining }
+                    };
+                } else if leaving {
+                    set_control_state(station, airlock, ElementState::Inactive);
+                    let hatch = hatch_position(station, airlock).unwrap_or(self.position);
+                    self.phase = EvaPhase::Outside { airlock };
+                    self.position = hatch;
+                    self.velocity = Vec3::ZERO;
+                    self.oxygen = SUIT_OXYGEN;
+                    self.fuel = 1.0;
+                    self.warned_low_oxygen = false;
+                    self.tether_taut = false;
+                    events.push(EvaEvent::Outside { hatch });
+                } else {
+                    set_control_state(station, airlock, ElementState::Inactive);
+                    let position = station
+                        .modules()
+                        .get(airlock)
+                        .map_or(self.position, |m| m.transform.position + Vec3::Y * EYE_HEIGHT);
+                    self.phase = EvaPhase::Inside;
+                    self.position = position;
+                    events.push(EvaEvent::Inside { position });
+                }
+            }
+            EvaPhase::Outside { airlock } => {
+                self.oxygen = (self.oxygen - delta_time).max(0.0);
+                if self.oxygen() < LOW_OXYGEN && !self.warned_low_oxygen {
+                    self.warned_low_oxygen = true;
+                    events.push(EvaEvent::LowOxygen);
+                }
+                if self.oxygen <= 0.0 {
+                    // Emergency recall: back to the hatch and cycled in without the player
+                    if let Some(hatch) = hatch_position(station, airlock) {
+                        self.position = hatch;
+                    }
+                    self.phase = EvaPhase::CyclingIn { airlock, remaining: CYCLE_SECONDS };
+                    self.velocity = Vec3::ZERO;
+                    events.push(EvaEvent::OxygenOut);
+                    return events;
+                }
+
+                self.fly(thrust, brake, delta_time, &mut events);
+                if self.tethered {
+                    if let Some(hatch) = hatch_position(station, airlock) {
+                        self.hold_tether(hatch, &mut events);
+                    }
+                }
+            }
+        }
+        events
+    }
+
+    fn fly(&mut self, thrust: Vec3, brake: bool, delta_time: f32, events: &mut Vec<EvaEvent>) {
+        let mut acceleration = thrust.clamp_length_max(1.0) * THRUST;
+        if brake && self.velocity.length() > 0.01 {
+            // Don't overshoot into drifting the other way
+            let stop = self.velocity.length() / delta_time.max(1e-4);
+            acceleration -= self.velocity.normalize() * THRUST.min(stop);
+        }
+        if self.fuel > 0.0 && acceleration != Vec3::ZERO {
+            self.fuel -= acceleration.length() / THRUST * delta_time / FUEL_SECONDS;
+            if self.fuel <= 0.0 {
+                self.fuel = 0.0;
+                events.push(EvaEvent::FuelOut);
+            }
+            self.velocity = (self.velocity + acceleration * delta_time).clamp_length_max(MAX_SPEED);
+        }
+        self.position += self.velocity * delta_time;
+    }
+
+    // A taut tether stops the drift away from the hatch but not along it
+    fn hold_tether(&mut self, hatch: Vec3, events: &mut Vec<EvaEvent>) {
+        let offset = self.position - hatch;
+        if offset.length() <= TETHER_LENGTH {
+            self.tether_taut = false;
+            return;
+        }
+        let out = offset.normalize();
+        self.position = hatch + out * TETHER_LENGTH;
+        self.velocity -= out * self.velocity.dot(out).max(0.0);
+        if !self.tether_taut {
+            self.tether_taut = true;
+            events.push(EvaEvent::TetherTaut);
+        }
+    }
+}
+
+// Outside the airlock, on the far side from the module it's docked to
+pub fn hatch_position(station: &SpaceStation, airlock: usize) -> Option<Vec3> {
+    let module = station.modules().get(airlock)?;
+    let center = module.transform.position;
+    let outward = station
+        .neighbours(airlock)
+        .first()
+        .and_then(|&docked| station.modules().get(docked))
+        .map_or(Vec3::Z, |docked| (center - docked.transform.position).normalize_or_zero());
+    Some(center + outward * HATCH_DISTANCE + Vec3::Y * EYE_HEIGHT)
+}
+
+fn set_control_state(station: &mut SpaceStation, airlock: usize, state: ElementState) {
+    let control = station.modules().get(airlock).and_then(|module| {
+        module
+            .interactive_elements
+            .iter()
+            .position(|element| element.element_type == InteractionType::AirlockControl)
+    });
+    if let Some(element) = control {
+        station.set_element_state(airlock, element, state);
+    }
+}
@@ -0,0 +1,367 @@
+use std::fs;
+use std::path::Path;
+use anyhow::{Context, Result};
+use glam::Vec3;
+use serde::Deserialize;
+use crate::crew::{Activity, Crew};
+use crate::event_log::LogSeverity;
+use crate::events::StationEvent;
+use crate::module_id::ModuleId;
+use crate::station::{ModuleType, SpaceStation};
+
+// Seconds from a pod sealing its hatch to launch
+pub const LAUNCH_COUNTDOWN: f32 = 10.0;
+// A launched pod's motor pushes it away this hard, in m/s², for this long,
+// then it coasts
+const LAUNCH_ACCELERATION: f32 = 4.0;
+pub const BURN_SECONDS: f32 = 5.0;
+// Where the motor's plume comes out, behind the pod's centre
+const NOZZLE_OFFSET: f32 = 1.8;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EvacuationScenario {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    // Seconds from the alert until every pod with anyone aboard launches
+    pub time_limit: f32,
+    // Crew who have to get away for it to count as a success
+    pub required: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct EvacuationFile {
+    #[serde(rename = "evacuation", default)]
+    evacuations: Vec<EvacuationScenario>,
+}
+
+pub fn load_scenarios<P: AsRef<Path>>(path: P) -> Result<Vec<EvacuationScenario>> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path)
+        .with_context(|| format!("failed to read evacuations from {}", path.display()))?;
+    let file: EvacuationFile = toml::from_str(&source).context("invalid evacuation definitions")?;
+    Ok(file.evacuations)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PodPhase {
+    // Hatch open, taking on crew
+    Docked,
+    // Hatch sealed, counting down to launch
+    Countdown { remaining: f32 },
+    // Seconds since it left
+    Launched { elapsed: f32 },
+}
+
+// An escape pod and who's aboard or on their way to it, by crew index
+#[derive(Debug, Clone)]
+pub struct Pod {
+    pub module: ModuleId,
+    pub seats: usize,
+    pub phase: PodPhase,
+    pub aboard: Vec<usize>,
+    pub bound: Vec<usize>,
+    // Where it was docked, and the way it flies off: out from the module it
+    // was docked to
+    origin: Vec3,
+    heading: Vec3,
+}
+
+impl Pod {
+    pub fn seats_left(&self) -> usize {
+        self.seats.saturating_sub(self.aboard.len() + self.bound.len())
+    }
+
+    // How far it's flown from its dock
+    pub fn travelled(&self) -> f32 {
+        let PodPhase::Launched { elapsed } = self.phase else {
+            return 0.0;
+        };
+        let burn = elapsed.min(BURN_SECONDS);
+        let coast = (elapsed - BURN_SECONDS).max(0.0);
+        0.5 * LAUNCH_ACCELERATION * burn * burn + LAUNCH_ACCELERATION * BURN_SECONDS * coast
+    }
+
+    pub fn position(&self) -> Vec3 {
+        self.origin + self.heading * self.travelled()
+    }
+
+    // Where its plume comes out, while the motor's burning
+    pub fn plume(&self) -> Option<(Vec3, Vec3)> {
+        match self.phase {
+            PodPhase::Launched { elapsed } if elapsed < BURN_SECONDS => {
+                Some((self.position() - self.heading * NOZZLE_OFFSET, -self.heading))
+            }
+            _ => None,
+        }
+    }
+}
+
+// A station-wide evacuation: every crew member is sent to the nearest pod
+// with a seat left by the nav graph, and pods launch once full, once
+// everyone who's coming is aboard, or when time runs out
+#[derive(Debug)]
+pub struct Evacuation {
+    scenario: EvacuationScenario,
+    elapsed: f32,
+    pods: Vec<Pod>,
+}
+
+impl Evacuation {
+    pub fn start(scenario: EvacuationScenario, station: &mut SpaceStation) -> Self {
+        let modules = station.modules();
+        let pods: Vec<Pod> = (0..modules.len())
+            .filter(|&i| modules[i].module_type == ModuleType::EscapePod)
+            .map(|i| {
+                let origin = modules[i].transform.position;
+                let heading = station
+                    .neighbours(i)
+                    .first()
+                    .map(|&dock| (origin - modules[dock].transform.position).normalize_or_zero())
+                    .filter(|heading| *heading != Vec3::ZERO)
+                    .unwrap_or(Vec3::Y);
+                Pod {
+                    module: modules[i].id,
+                    seats: modules[i].module_type.seats(),
+                    phase: PodPhase::Docked,
+                    aboard: Vec::new(),
+                    bound: Vec::new(),
+                    origin,
+                    heading,
+                }
+            })
+            .collect();
+        if !pods.is_empty() {
+            station.log_event(LogSeverity::Critical, format!("Evacuation alert: {}", scenario.name));
+        }
+        Self { scenario, elapsed: 0.0, pods }
+    }
+
+    pub fn scenario(&self) -> &EvacuationScenario {
+        &self.scenario
+    }
+
+    pub fn pods(&self) -> &[Pod] {
+        &self.pods
+    }
+
+    // Seconds until the pods go regardless
+    pub fn remaining(&self) -> f32 {
+        (self.scenario.time_limit - self.elapsed).max(0.0)
+    }
+
+    // Crew aboard pods that got away
+    pub fn souls_evacuated(&self) -> usize {
+        self.pods
+            .iter()
+            .filter(|pod| matches!(pod.phase, PodPhase::Launched { .. }))
+            .map(|pod| pod.aboard.len())
+            .sum()
+    }
+
+    // Sends crew to pods, boards those who've got there, and counts down and
+    // flies the pods. Returns true once it's over: every pod that's going has
+    // gone, and nobody left can reach one in time.
+    pub fn update(&mut self, station: &mut SpaceStation, crew: &mut Crew, delta_time: f32) -> bool {
+        self.elapsed += delta_time;
+        let out_of_time = self.elapsed >= self.scenario.time_limit;
+
+        // Crew whose pod has sealed without them, or who can't reach it any
+        // more, look for another; once time's up they're left behind
+        for pod in &mut self.pods {
+            let docked = pod.phase == PodPhase::Docked && !out_of_time;
+            pod.bound.retain(|&member| {
+                let reachable = docked && hops(station, crew.members()[member].module, pod.module).is_some();
+                if !reachable {
+                    crew.muster(member, None);
+                }
+                reachable
+            });
+        }
+        for member in 0..crew.members().len() {
+            if out_of_time || self.pods.iter().any(|pod| pod.aboard.contains(&member) || pod.bound.contains(&member)) {
+                continue;
+            }
+            let from = crew.members()[member].module;
+            let nearest = self
+                .pods
+                .iter()
+                .enumerate()
+                .filter(|(_, pod)| pod.phase == PodPhase::Docked && pod.seats_left() > 0)
+                .filter_map(|(i, pod)| hops(station, from, pod.module).map(|hops| (i, hops)))
+                .min_by_key(|&(_, hops)| hops);
+            if let Some((i, _)) = nearest {
+                self.pods[i].bound.push(member);
+                crew.muster(member, Some(self.pods[i].module));
+            }
+        }
+
+        // Board whoever's got to their pod
+        for pod in &mut self.pods {
+            let (arrived, still_coming): (Vec<usize>, Vec<usize>) = pod.bound.iter().partition(|&&member| {
+                let member = &crew.members()[member];
+                member.activity == Activity::Evacuating && member.module == pod.module && member.arrived()
+            });
+            for &member in &arrived {
+                crew.board(member);
+            }
+            pod.aboard.extend(arrived);
+            pod.bound = still_coming;
+        }
+
+        for pod in &mut self.pods {
+            let Some(at) = station.module_index(pod.module) else {
+                continue;
+            };
+            match pod.phase {
+                PodPhase::Docked => {
+                    // Full, or nobody else is coming
+                    let full = pod.aboard.len() >= pod.seats;
+                    if full || (pod.bound.is_empty() && !pod.aboard.is_empty()) {
+                        pod.phase = PodPhase::Countdown { remaining: LAUNCH_COUNTDOWN };
+                        seal(station, at);
+                        station.log_event(
+                            LogSeverity::Warning,
+                            format!("Escape pod {} sealed, launching in {:.0} s", at, LAUNCH_COUNTDOWN),
+                        );
+                    }
+                }
+                PodPhase::Countdown { remaining } => {
+                    let remaining = remaining - delta_time;
+                    if remaining > 0.0 {
+                        pod.phase = PodPhase::Countdown { remaining };
+                    } else {
+                        pod.phase = PodPhase::Launched { elapsed: 0.0 };
+                        station.emit_event(StationEvent::PodLaunched {
+                            module: at,
+                            position: pod.origin,
+                            souls: pod.aboard.len(),
+                        });
+                    }
+                }
+                PodPhase::Launched { elapsed } => {
+                    pod.phase = PodPhase::Launched { elapsed: elapsed + delta_time };
+                }
+            }
+            if let Some(module) = station.module_mut(at) {
+                module.transform.position = pod.position();
+            }
+        }
+
+        // Nobody's on their way to a pod, and no pod with anyone aboard is
+        // still to go
+        self.pods.iter().all(|pod| {
+            pod.bound.is_empty() && (matches!(pod.phase, PodPhase::Launched { .. }) || pod.aboard.is_empty())
+        })
+    }
+
+    pub fn finish(self, crew: &Crew) -> EvacuationResult {
+        let souls = self.souls_evacuated();
+        EvacuationResult {
+            scenario: self.scenario.name,
+            souls,
+            crew: crew.members().len(),
+            required: self.scenario.required,
+            elapsed: self.elapsed,
+        }
+    }
+}
+
+// How many modules there are on the way from one module to another, if
+// there's a way
+fn hops(station: &SpaceStation, from: ModuleId, to: ModuleId) -> Option<usize> {
+    let (from, to) = (station.module_index(from)?, station.module_index(to)?);
+    station.find_path(from, to).map(|path| path.len())
+}
+
+// Shuts and locks the pod's hatches so nobody's left half through one
+fn seal(station: &mut SpaceStation, module: usize) {
+    let doors: Vec<usize> = (0..station.doors().len())
+        .filter(|&door| station.door_modules(door).is_some_and(|(a, b)| a == module || b == module))
+        .collect();
+    for door in doors {
+        station.set_door_locked(door, true);
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct EvacuationResult {
+    pub scenario: String,
+    pub souls: usize,
+    pub crew: usize,
+    pub required: usize,
+    pub elapsed: f32,
+}
+
+impl EvacuationResult {
+    pub fn succeeded(&self) -> bool {
+        self.souls >= self.required
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::crew::CrewDefinition;
+
+    fn lab_hand() -> CrewDefinition {
+        CrewDefinition { name: "Lindqvist".to_string(), quarters: 6, workstation: 5, shift_start: 8.0 }
+    }
+
+    fn evacuation_drill(time_limit: f32) -> EvacuationScenario {
+        EvacuationScenario { name: "Abandon station".to_string(), description: String::new(), time_limit, required: 4 }
+    }
+
+    #[test]
+    fn evacuation_fills_the_nearest_pods_and_launches_them() {
+        let mut station = SpaceStation::create_default_layout();
+        let mut crew = Crew::new();
+        for _ in 0..4 {
+            crew.add(lab_hand(), &station).unwrap();
+        }
+        let (quarters_pod, stores_pod) = (10, 11);
+        assert_eq!(station.modules()[quarters_pod].module_type.seats(), 3);
+        let mut evacuation = Evacuation::start(evacuation_drill(300.0), &mut station);
+        crew.update(&mut station, 0.1);
+        assert!(!evacuation.update(&mut station, &mut crew, 0.1));
+        // Three seats next door to the quarters, the last one round by the stores
+        assert_eq!(evacuation.pods()[0].bound, vec![0, 1, 2]);
+        assert_eq!(evacuation.pods()[1].bound, vec![3]);
+        let mut done = false;
+        for _ in 0..1000 {
+            crew.update(&mut station, 0.1);
+            if evacuation.update(&mut station, &mut crew, 0.1) {
+                done = true;
+                break;
+            }
+        }
+        assert!(done);
+        assert!(crew.members().iter().all(|member| member.activity == Activity::Evacuated));
+        assert!(evacuation.pods().iter().all(|pod| matches!(pod.phase, PodPhase::Launched { .. })));
+        // Sealed behind them, and flown off
+        let hatch = station.door_between(6, quarters_pod).unwrap();
+        assert_eq!(station.door_openness(hatch), 0.0);
+        for _ in 0..40 {
+            evacuation.update(&mut station, &mut crew, 0.1);
+        }
+        let flown = station.modules()[stores_pod].transform.position - Vec3::new(8.0, 0.0, 17.5);
+        assert!(flown.x > 25.0 && flown.z.abs() < 1e-3, "{}", flown);
+        assert!(evacuation.pods()[1].plume().is_some());
+        let result = evacuation.finish(&crew);
+        assert_eq!((result.souls, result.crew), (4, 4));
+        assert!(result.succeeded());
+
+        // Too little time to get anyone to a pod
+        let mut station = SpaceStation::create_default_layout();
+        let mut crew = Crew::new();
+        crew.add(lab_hand(), &station).unwrap();
+        let mut evacuation = Evacuation::start(evacuation_drill(1.0), &mut station);
+        for _ in 0..20 {
+            crew.update(&mut station, 0.1);
+            evacuation.update(&mut station, &mut crew, 0.1);
+        }
+        assert!(evacuation.update(&mut station, &mut crew, 0.1));
+        assert_ne!(crew.members()[0].activity, Activity::Evacuating);
+        assert!(!evacuation.finish(&crew).succeeded());
+    }
+}
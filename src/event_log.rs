@@ -0,0 +1,113 @@
+use std::collections::VecDeque;
+use std::fmt;
+use crate::clock::MissionTime;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum LogSeverity {
+    Info,
+    Warning,
+    Critical,
+}
+
+impl LogSeverity {
+    pub fn label(&self) -> &'static str {
+        match self {
+            LogSeverity::Info => "INFO",
+            LogSeverity::Warning => "WARN",
+            LogSeverity::Critical => "CRIT",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LogEntry {
+    pub time: MissionTime,
+    pub severity: LogSeverity,
+    pub message: String,
+}
+
+impl fmt::Display for LogEntry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "[{}] {} {}", self.time.format_met(), self.severity.label(), self.message)
+    }
+}
+
+#[derive(Debug)]
+pub struct EventLog {
+    entries: VecDeque<LogEntry>,
+    capacity: usize,
+}
+
+impl EventLog {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::with_capacity(capacity),
+            capacity,
+        }
+    }
+
+    // A log with no capacity keeps nothing
+    pub fn push(&mut self, time: MissionTime, severity: LogSeverity, message: impl Into<String>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.entries.len() >= self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(LogEntry {
+            time,
+            severity,
+            message: message.into(),
+        });
+    }
+
+    pub fn entries(&self) -> impl Iterator<Item = &LogEntry> {
+        self.entries.iter()
+    }
+
+    // Most recent entries, oldest first
+    pub fn recent(&self, count: usize) -> impl Iterator<Item = &LogEntry> {
+        self.entries.iter().skip(self.entries.len().saturating_sub(count))
+    }
+
+    pub fn since(&self, time: MissionTime) -> impl Iterator<Item = &LogEntry> {
+        self.entries.iter().filter(move |entry| entry.time >= time)
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self::new(512)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn the_oldest_entries_go_first_and_no_capacity_keeps_nothing() {
+        let mut log = EventLog::new(2);
+        for (secs, message) in [(1, "one"), (2, "two"), (3, "three")] {
+            log.push(MissionTime::from_secs(secs), LogSeverity::Info, message);
+        }
+        let kept: Vec<&str> = log.entries().map(|entry| entry.message.as_str()).collect();
+        assert_eq!(kept, ["two", "three"]);
+
+        let mut log = EventLog::new(0);
+        log.push(MissionTime::ZERO, LogSeverity::Critical, "dropped");
+        assert_eq!(log.entries().count(), 0);
+    }
+}
@@ -0,0 +1,236 @@
+use glam::Vec3;
+use crate::event_log::LogSeverity;
+use crate::incidents::IncidentKind;
+use crate::life_support::LifeSupportKind;
+use crate::resources::Resource;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StationEvent {
+    HullBreach { module: usize, position: Vec3 },
+    Explosion { position: Vec3, magnitude: f32 },
+    AirlockDecompression { module: usize, position: Vec3 },
+    ModuleDamaged { module: usize, position: Vec3, integrity: f32 },
+    PowerDeficit { deficit: f32 },
+    DoorLocked { module: usize, position: Vec3 },
+    ObjectiveCompleted { id: String, title: String },
+    // An element finished switching; `active` is the state it landed on
+    ElementActivated { module: usize, element: usize, position: Vec3, active: bool },
+    ElementFailed { module: usize, element: usize, position: Vec3 },
+    // The grid cut a module off to carry the rest of the load
+    Brownout { module: usize, position: Vec3 },
+    // A fire caught, from a fault or through a door, and went out
+    FireStarted { module: usize, position: Vec3 },
+    FireOut { module: usize, position: Vec3 },
+    // A module's air got hot enough to start cooking its equipment
+    Overheating { module: usize, position: Vec3, temperature: f32 },
+    // A solar flare began, `intensity` 0 to 1 at its height, and died away
+    SolarFlare { intensity: f32 },
+    SolarFlareEnded,
+    // Stores of a resource fell below its low mark, or ran out
+    ResourceShortage { resource: Resource, remaining: f32 },
+    // An escape pod left the station with `souls` crew aboard
+    PodLaunched { module: usize, position: Vec3, souls: usize },
+    // A module lost air through a hatch left open onto one taken off the station
+    Decompression { module: usize, position: Vec3 },
+    // The incident scheduler sounded the alarm; flares strike no one module
+    Incident { kind: IncidentKind, module: Option<usize>, position: Option<Vec3> },
+    // Something for the log in a quiet spell, from the event director
+    Notice { text: String },
+    // A connection creaking under `load` times what it's rated for, and one
+    // that's torn apart; `modules` lower-numbered first
+    StructuralStrain { modules: (usize, usize), position: Vec3, load: f32 },
+    ConnectionFailed { modules: (usize, usize), position: Vec3 },
+    // A lab finished an experiment for `points`, or lost power and spoilt it
+    ExperimentCompleted { module: usize, position: Vec3, name: String, points: u32 },
+    ExperimentFailed { module: usize, position: Vec3, name: String },
+    // Mission control got a message through to the comms console
+    MessageReceived { from: String, subject: String },
+    // A life support unit's filter clogged solid and it shut down
+    LifeSupportFailed { module: usize, position: Vec3, kind: LifeSupportKind },
+}
+
+impl StationEvent {
+    // Where it happened, for events tied to a place
+    pub fn position(&self) -> Option<Vec3> {
+        match self {
+            StationEvent::HullBreach { position, .. } => Some(*position),
+            StationEvent::Explosion { position, .. } => Some(*position),
+            StationEvent::AirlockDecompression { position, .. } => Some(*position),
+            StationEvent::ModuleDamaged { position, .. } => Some(*position),
+            StationEvent::PowerDeficit { .. } => None,
+            StationEvent::DoorLocked { position, .. } => Some(*position),
+            StationEvent::ObjectiveCompleted { .. } => None,
+            StationEvent::ElementActivated { position, .. } => Some(*position),
+            StationEvent::ElementFailed { position, .. } => Some(*position),
+            StationEvent::Brownout { position, .. } => Some(*position),
+            StationEvent::FireStarted { position, .. } => Some(*position),
+            StationEvent::FireOut { position, .. } => Some(*position),
+            StationEvent::Overheating { position, .. } => Some(*position),
+            StationEvent::SolarFlare { .. } => None,
+            StationEvent::SolarFlareEnded => None,
+            StationEvent::ResourceShortage { .. } => None,
+            StationEvent::PodLaunched { position, .. } => Some(*position),
+            StationEvent::Decompression { position, .. } => Some(*position),
+            StationEvent::Incident { position, .. } => *position,
+            StationEvent::Notice { .. } => None,
+            StationEvent::StructuralStrain { position, .. } => Some(*position),
+            StationEvent::ConnectionFailed { position, .. } => Some(*position),
+            StationEvent::ExperimentCompleted { position, .. } => Some(*position),
+            StationEvent::ExperimentFailed { position, .. } => Some(*position),
+            StationEvent::MessageReceived { .. } => None,
+            StationEvent::LifeSupportFailed { position, .. } => Some(*position),
+        }
+    }
+
+    // Stable name used by data files (hints, scripts) to refer to event kinds
+    pub fn kind(&self) -> &'static str {
+        match self {
+            StationEvent::HullBreach { .. } => "hull_breach",
+            StationEvent::Explosion { .. } => "explosion",
+            StationEvent::AirlockDecompression { .. } => "airlock_decompression",
+            StationEvent::ModuleDamaged { .. } => "module_damaged",
+            StationEvent::PowerDeficit { .. } => "power_deficit",
+            StationEvent::DoorLocked { .. } => "door_locked",
+            StationEvent::ObjectiveCompleted { .. } => "objective_completed",
+            StationEvent::ElementActivated { .. } => "element_activated",
+            StationEvent::ElementFailed { .. } => "element_failed",
+            StationEvent::Brownout { .. } => "brownout",
+            StationEvent::FireStarted { .. } => "fire_started",
+            StationEvent::FireOut { .. } => "fire_out",
+            StationEvent::Overheating { .. } => "overheating",
+            StationEvent::SolarFlare { .. } => "solar_flare",
+            StationEvent::SolarFlareEnded => "solar_flare_ended",
+            StationEvent::ResourceShortage { .. } => "resource_shortage",
+            StationEvent::PodLaunched { .. } => "pod_launched",
+            StationEvent::Decompression { .. } => "decompression",
+            StationEvent::Incident { .. } => "incident",
+            StationEvent::Notice { .. } => "notice",
+            StationEvent::StructuralStrain { .. } => "structural_strain",
+            StationEvent::ConnectionFailed { .. } => "connection_failed",
+            StationEvent::ExperimentCompleted { .. } => "experiment_completed",
+            StationEvent::ExperimentFailed { .. } => "experiment_failed",
+            StationEvent::MessageReceived { .. } => "message_received",
+            StationEvent::LifeSupportFailed { .. } => "life_support_failed",
+        }
+    }
+
+    pub fn severity(&self) -> LogSeverity {
+        match self {
+            StationEvent::HullBreach { .. } => LogSeverity::Critical,
+            StationEvent::Explosion { .. } => LogSeverity::Critical,
+            StationEvent::AirlockDecompression { .. } => LogSeverity::Warning,
+            StationEvent::ModuleDamaged { .. } => LogSeverity::Warning,
+            StationEvent::PowerDeficit { .. } => LogSeverity::Warning,
+            StationEvent::DoorLocked { .. } => LogSeverity::Info,
+            StationEvent::ObjectiveCompleted { .. } => LogSeverity::Info,
+            StationEvent::ElementActivated { .. } => LogSeverity::Info,
+            StationEvent::ElementFailed { .. } => LogSeverity::Warning,
+            StationEvent::Brownout { .. } => LogSeverity::Warning,
+            StationEvent::FireStarted { .. } => LogSeverity::Critical,
+            StationEvent::FireOut { .. } => LogSeverity::Info,
+            StationEvent::Overheating { .. } => LogSeverity::Warning,
+            StationEvent::SolarFlare { .. } => LogSeverity::Warning,
+            StationEvent::SolarFlareEnded => LogSeverity::Info,
+            StationEvent::ResourceShortage { remaining, .. } if *remaining <= 0.0 => LogSeverity::Critical,
+            StationEvent::ResourceShortage { .. } => LogSeverity::Warning,
+            StationEvent::PodLaunched { .. } => LogSeverity::Warning,
+            StationEvent::Decompression { .. } => LogSeverity::Critical,
+            StationEvent::Incident { kind: IncidentKind::Micrometeorite, .. } => LogSeverity::Critical,
+            StationEvent::Incident { .. } => LogSeverity::Warning,
+            StationEvent::Notice { .. } => LogSeverity::Info,
+            StationEvent::StructuralStrain { .. } => LogSeverity::Warning,
+            StationEvent::ConnectionFailed { .. } => LogSeverity::Critical,
+            StationEvent::ExperimentCompleted { .. } => LogSeverity::Info,
+            StationEvent::ExperimentFailed { .. } => LogSeverity::Warning,
+            StationEvent::MessageReceived { .. } => LogSeverity::Info,
+            StationEvent::LifeSupportFailed { .. } => LogSeverity::Warning,
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            StationEvent::HullBreach { module, .. } => format!("Hull breach in module {}", module),
+            StationEvent::Explosion { magnitude, .. } => format!("Explosion (magnitude {:.1})", magnitude),
+            StationEvent::AirlockDecompression { module, .. } => {
+                format!("Airlock {} decompressing", module)
+            }
+            StationEvent::ModuleDamaged { module, integrity, .. } => {
+                format!("Module {} damaged ({:.0}% integrity)", module, integrity * 100.0)
+            }
+            StationEvent::PowerDeficit { deficit } => format!("Power deficit: {:.1} kW short", deficit),
+            StationEvent::DoorLocked { module, .. } => format!("Door locked in module {}", module),
+            StationEvent::ObjectiveCompleted { title, .. } => format!("Objective complete: {}", title),
+            StationEvent::ElementActivated { module, element, active, .. } => {
+                let state = if *active { "on" } else { "off" };
+                format!("Element {} in module {} switched {}", element, module, state)
+            }
+            StationEvent::ElementFailed { module, element, .. } => {
+                format!("Element {} in module {} failed", element, module)
+            }
+            StationEvent::Brownout { module, .. } => format!("Module {} lost power", module),
+            StationEvent::FireStarted { module, .. } => format!("Fire in module {}", module),
+            StationEvent::FireOut { module, .. } => format!("Fire in module {} is out", module),
+            StationEvent::Overheating { module, temperature, .. } => {
+                format!("Module {} is overheating ({:.0} C)", module, temperature - 273.15)
+            }
+            StationEvent::SolarFlare { intensity } => {
+                format!("Solar flare: interference at {:.0}%", intensity * 100.0)
+            }
+            StationEvent::SolarFlareEnded => "Solar flare has passed".to_string(),
+            StationEvent::ResourceShortage { resource, remaining } if *remaining <= 0.0 => {
+                format!("{} has run out", resource.name())
+            }
+            StationEvent::ResourceShortage { resource, remaining } => {
+                format!("{} running low: {:.0}{} left", resource.name(), remaining, resource.unit())
+            }
+            StationEvent::PodLaunched { module, souls, .. } => {
+                format!("Escape pod {} launched with {} aboard", module, souls)
+            }
+            StationEvent::Decompression { module, .. } => format!("Module {} decompressed through an open hatch", module),
+            StationEvent::Incident { kind, module: Some(module), .. } => format!("ALARM: {} in module {}", kind.name(), module),
+            StationEvent::Incident { kind, .. } => format!("ALARM: {}", kind.name()),
+            StationEvent::Notice { text } => text.clone(),
+            StationEvent::StructuralStrain { modules: (a, b), load, .. } => {
+                format!("Connection between modules {} and {} is creaking ({:.0}% of rated load)", a, b, load * 100.0)
+            }
+            StationEvent::ConnectionFailed { modules: (a, b), .. } => {
+                format!("Connection between modules {} and {} has torn apart", a, b)
+            }
+            StationEvent::ExperimentCompleted { name, points, .. } => {
+                format!("Experiment complete: {} (+{} research points)", name, points)
+            }
+            StationEvent::ExperimentFailed { name, module, .. } => {
+                format!("Experiment spoilt: {} lost power in module {}", name, module)
+            }
+            StationEvent::MessageReceived { from, subject } => format!("Message from {}: {}", from, subject),
+            StationEvent::LifeSupportFailed { module, kind, .. } => {
+                format!("{} in module {} clogged and shut down", kind.name(), module)
+            }
+        }
+    }
+}
+
+// Events raised during a simulation step. Consumers drain the bus once per
+// frame and fan the events out to whichever systems care about them.
+#[derive(Debug, Default)]
+pub struct EventBus {
+    pending: Vec<StationEvent>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn emit(&mut self, event: StationEvent) {
+        self.pending.push(event);
+    }
+
+    pub fn drain(&mut self) -> Vec<StationEvent> {
+        std::mem::take(&mut self.pending)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pending.is_empty()
+    }
+}
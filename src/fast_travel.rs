@@ -0,0 +1,177 @@
+use std::collections::HashSet;
+use glam::Vec3;
+use crate::crew::WALK_SPEED;
+use crate::module_id::ModuleId;
+use crate::station::SpaceStation;
+
+// How long the camera takes getting there; the screen dips to black halfway
+pub const TRANSITION_SECONDS: f32 = 1.2;
+
+// Why a trip can't be taken
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Refusal {
+    // A fire, a breach or the grid shedding load
+    Emergency,
+    // The mission, a drill or a spacewalk rules it out for now
+    Restricted,
+    AlreadyThere,
+    Unvisited,
+    Unpressurized,
+    NoRoute,
+}
+
+impl Refusal {
+    // Locale key for the message
+    pub fn id(self) -> &'static str {
+        match self {
+            Refusal::Emergency => "travel.emergency",
+            Refusal::Restricted => "travel.restricted",
+            Refusal::AlreadyThere => "travel.already_there",
+            Refusal::Unvisited => "travel.unvisited",
+            Refusal::Unpressurized => "travel.unpressurized",
+            Refusal::NoRoute => "travel.no_route",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Trip {
+    pub module: usize,
+    pub from: Vec3,
+    pub to: Vec3,
+    // What the walk would have taken; the simulation is run on by this much
+    pub walk_seconds: f32,
+    elapsed: f32,
+}
+
+// Skipping the walk to any module the player has been in before. The
+// simulation is run on by however long the walk would have taken, while the
+// camera makes a short hop there instead.
+#[derive(Debug, Default)]
+pub struct FastTravel {
+    visited: HashSet<ModuleId>,
+    trip: Option<Trip>,
+}
+
+impl FastTravel {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn visit(&mut self, module: ModuleId) {
+        self.visited.insert(module);
+    }
+
+    pub fn visited(&self, module: ModuleId) -> bool {
+        self.visited.contains(&module)
+    }
+
+    pub fn trip(&self) -> Option<&Trip> {
+        self.trip.as_ref()
+    }
+
+    // Whether the trip could be taken, and the way it would walk if so.
+    // `allowed` is false while the mission or anything else rules it out.
+    pub fn plan(&self, station: &SpaceStation, from: usize, to: usize, allowed: bool) -> Result<Vec<usize>, Refusal> {
+        if station.in_emergency() {
+            return Err(Refusal::Emergency);
+        }
+        if !allowed || self.trip.is_some() {
+            return Err(Refusal::Restricted);
+        }
+        if from == to {
+            return Err(Refusal::AlreadyThere);
+        }
+        if !station.module_id(to).is_some_and(|id| self.visited(id)) {
+            return Err(Refusal::Unvisited);
+        }
+        if !station.modules().get(to).is_some_and(|module| module.atmosphere_sealed) {
+            return Err(Refusal::Unpressurized);
+        }
+        station.find_path(from, to).ok_or(Refusal::NoRoute)
+    }
+
+    // Starts the hop from `eye`, arriving at the same height in the middle
+    // of `to`; returns the seconds of walking it stands in for
+    pub fn begin(&mut self, station: &SpaceStation, eye: Vec3, from: usize, to: usize, allowed: bool) -> Result<f32, Refusal> {
+        let path = self.plan(station, from, to, allowed)?;
+        let modules = station.modules();
+        let distance: f32 = path
+            .windows(2)
+            .map(|pair| modules[pair[0]].transform.position.distance(modules[pair[1]].transform.position))
+            .sum();
+        let walk_seconds = distance / WALK_SPEED;
+        let centre = modules[to].transform.position;
+        self.trip = Some(Trip {
+            module: to,
+            from: eye,
+            to: Vec3::new(centre.x, eye.y, centre.z),
+            walk_seconds,
+            elapsed: 0.0,
+        });
+        Ok(walk_seconds)
+    }
+
+    // Where the camera is along the hop, until it lands
+    pub fn update(&mut self, delta_time: f32) -> Option<Vec3> {
+        let trip = self.trip.as_mut()?;
+        trip.elapsed += delta_time;
+        let t = (trip.elapsed / TRANSITION_SECONDS).clamp(0.0, 1.0);
+        let position = trip.from.lerp(trip.to, t * t * (3.0 - 2.0 * t));
+        if t >= 1.0 {
+            self.trip = None;
+        }
+        Some(position)
+    }
+
+    // How dark the screen is, 0 to 1, blackest halfway through the hop
+    pub fn fade(&self) -> f32 {
+        self.trip.as_ref().map_or(0.0, |trip| {
+            let t = (trip.elapsed / TRANSITION_SECONDS).clamp(0.0, 1.0);
+            1.0 - (2.0 * t - 1.0).abs()
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stepping::{SimStepper, STEP_DT};
+
+    #[test]
+    fn fast_travel_goes_to_visited_pressurized_modules_outside_emergencies() {
+        let mut station = SpaceStation::create_default_layout();
+        let mut travel = FastTravel::new();
+        travel.visit(station.modules()[0].id);
+        assert_eq!(travel.plan(&station, 0, 5, true), Err(Refusal::Unvisited));
+        travel.visit(station.modules()[5].id);
+        assert_eq!(travel.plan(&station, 0, 0, true), Err(Refusal::AlreadyThere));
+        assert_eq!(travel.plan(&station, 0, 5, false), Err(Refusal::Restricted));
+        station.module_mut(5).unwrap().atmosphere_sealed = false;
+        assert_eq!(travel.plan(&station, 5, 0, true), Err(Refusal::Emergency));
+        station.module_mut(5).unwrap().atmosphere_sealed = true;
+        station.ignite(7);
+        assert_eq!(travel.plan(&station, 5, 0, true), Err(Refusal::Emergency));
+        let station = SpaceStation::create_default_layout();
+
+        // The lab is two 8 m hops from the command centre
+        let eye = Vec3::new(1.0, 1.5, 0.0);
+        let walk = travel.begin(&station, eye, 0, 5, true).unwrap();
+        assert!((walk - 16.0 / crate::crew::WALK_SPEED).abs() < 0.01);
+        assert_eq!(travel.plan(&station, 0, 5, true), Err(Refusal::Restricted));
+        let halfway = travel.update(TRANSITION_SECONDS * 0.5).unwrap();
+        assert!(travel.fade() > 0.99 && halfway.z < 0.0);
+        assert_eq!(travel.update(TRANSITION_SECONDS), Some(Vec3::new(0.0, 1.5, -16.0)));
+        assert!(travel.trip().is_none() && travel.fade() == 0.0);
+
+        // The walk's ticks run over the next few frames, even while paused
+        let mut stepper = SimStepper::new();
+        stepper.toggle_pause();
+        stepper.fast_forward(walk);
+        let mut ticks = 0;
+        while stepper.fast_forwarding() {
+            ticks += stepper.take_steps(1.0 / 60.0);
+        }
+        assert_eq!(ticks, (walk / STEP_DT).round() as u32);
+    }
+}
@@ -0,0 +1,403 @@
+// C ABI over the headless simulation, for driving the station from C, C++
+// or Python (ctypes/cffi) without binding to Rust. The header is generated:
+//
+//     cbindgen --config cbindgen.toml --output include/space_station.h src/ffi.rs
+//
+// Stations are opaque handles from `ss_station_create` and must be freed
+// with `ss_station_destroy`. Every call takes a handle that may be null;
+// null handles and out-of-range indices are reported, never dereferenced.
+// Modules are named by id, which stays good while others are added or
+// removed; `ss_station_module_id` lists the current ones. A panic never
+// crosses into the caller: the call fails instead, as it would on a null
+// handle, and SsInteraction and SsElementState say so with Failed.
+use std::collections::VecDeque;
+use std::os::raw::c_char;
+use std::panic::{self, AssertUnwindSafe};
+use crate::events::StationEvent;
+use crate::event_log::LogSeverity;
+use crate::module_id::ModuleId;
+use crate::station::{ElementState, SpaceStation};
+use crate::stepping::STEP_DT;
+
+// Bumped whenever a signature or struct layout below changes
+pub const SS_ABI_VERSION: u32 = 3;
+
+pub const SS_EVENT_KIND_LEN: usize = 32;
+pub const SS_EVENT_DESCRIPTION_LEN: usize = 128;
+// Events kept for a host that isn't polling; the oldest go first
+pub const SS_MAX_PENDING_EVENTS: usize = 1024;
+
+// Opaque to C
+pub struct SsStation {
+    station: SpaceStation,
+    // Raised by ticks and interactions, waiting for `ss_station_poll_event`
+    events: VecDeque<StationEvent>,
+}
+
+impl SsStation {
+    fn collect_events(&mut self) {
+        self.events.extend(self.station.drain_events());
+        let excess = self.events.len().saturating_sub(SS_MAX_PENDING_EVENTS);
+        self.events.drain(..excess);
+    }
+
+    fn module_index(&self, module: SsModuleId) -> Option<usize> {
+        self.station.module_index(module.into())
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SsStatus {
+    pub mission_seconds: f64,
+    pub power_generation: f32,
+    pub power_consumption: f32,
+    pub grid_stability: f32,
+    pub oxygen_level: f32,
+    pub temperature: f32,
+    pub pressure: f32,
+    pub structural_integrity: f32,
+    pub module_count: u32,
+    pub sealed_modules: u32,
+}
+
+// A module's id; stale once the module's gone
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct SsModuleId {
+    pub slot: u32,
+    pub generation: u32,
+}
+
+impl From<ModuleId> for SsModuleId {
+    fn from(id: ModuleId) -> Self {
+        SsModuleId {
+            slot: id.slot,
+            generation: id.generation,
+        }
+    }
+}
+
+impl From<SsModuleId> for ModuleId {
+    fn from(id: SsModuleId) -> Self {
+        ModuleId {
+            slot: id.slot,
+            generation: id.generation,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SsElementState {
+    // The call panicked
+    Failed = -2,
+    // The module or element doesn't exist
+    Invalid = -1,
+    Inactive = 0,
+    Active = 1,
+    Transitioning = 2,
+    Locked = 3,
+    Warning = 4,
+    Emergency = 5,
+    Malfunction = 6,
+}
+
+impl From<ElementState> for SsElementState {
+    fn from(state: ElementState) -> Self {
+        match state {
+            ElementState::Inactive => SsElementState::Inactive,
+            ElementState::Active => SsElementState::Active,
+            ElementState::Transitioning(_) => SsElementState::Transitioning,
+            ElementState::Locked => SsElementState::Locked,
+            ElementState::Warning => SsElementState::Warning,
+            ElementState::Emergency => SsElementState::Emergency,
+            ElementState::Malfunction => SsElementState::Malfunction,
+        }
+    }
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SsInteraction {
+    // The element took the request
+    Accepted = 0,
+    // It's passive, locked, busy, cooling down or failed
+    Refused = 1,
+    // No such station, module or element
+    Invalid = 2,
+    // The call panicked; the station may be left part way through it
+    Failed = 3,
+}
+
+// Strings are NUL-terminated and cut short to fit
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+pub struct SsEvent {
+    // Stable name, e.g. "hull_breach"
+    pub kind: [c_char; SS_EVENT_KIND_LEN],
+    pub description: [c_char; SS_EVENT_DESCRIPTION_LEN],
+    // 0 info, 1 warning, 2 critical
+    pub severity: i32,
+    pub has_position: bool,
+    pub position: [f32; 3],
+}
+
+// Cut short on a character boundary, so the C side never gets half a
+// UTF-8 sequence
+fn copy_str<const N: usize>(text: &str, out: &mut [c_char; N]) {
+    let mut end = text.len().min(N - 1);
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    let bytes = &text.as_bytes()[..end];
+    for (dst, &src) in out.iter_mut().zip(bytes) {
+        *dst = src as c_char;
+    }
+    out[bytes.len()] = 0;
+}
+
+// Runs an exported function's body, handing back `failed` if it panics
+// rather than unwinding across the C ABI
+fn guard<T>(failed: T, body: impl FnOnce() -> T) -> T {
+    panic::catch_unwind(AssertUnwindSafe(body)).unwrap_or(failed)
+}
+
+#[no_mangle]
+pub extern "C" fn ss_abi_version() -> u32 {
+    guard(0, || SS_ABI_VERSION)
+}
+
+// The default station layout, seeded so runs are reproducible; null if it
+// couldn't be made
+#[no_mangle]
+pub extern "C" fn ss_station_create(seed: u64) -> Option<Box<SsStation>> {
+    guard(None, || {
+        let mut station = SpaceStation::create_default_layout();
+        station.reseed(seed);
+        Some(Box::new(SsStation {
+            station,
+            events: VecDeque::new(),
+        }))
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn ss_station_destroy(station: Option<Box<SsStation>>) {
+    guard((), || drop(station));
+}
+
+// Steps the simulation `ticks` times at the fixed 60 Hz rate
+#[no_mangle]
+pub extern "C" fn ss_station_tick(station: Option<&mut SsStation>, ticks: u32) -> bool {
+    guard(false, || {
+        let Some(station) = station else {
+            return false;
+        };
+        for _ in 0..ticks {
+            station.station.update(STEP_DT);
+            station.collect_events();
+        }
+        true
+    })
+}
+
+// Seconds per tick, for callers converting to mission time
+#[no_mangle]
+pub extern "C" fn ss_tick_seconds() -> f32 {
+    guard(0.0, || STEP_DT)
+}
+
+#[no_mangle]
+pub extern "C" fn ss_station_status(station: Option<&SsStation>, out: Option<&mut SsStatus>) -> bool {
+    guard(false, || {
+        let (Some(station), Some(out)) = (station, out) else {
+            return false;
+        };
+        let telemetry = station.station.telemetry();
+        *out = SsStatus {
+            mission_seconds: station.station.clock().now().as_millis() as f64 / 1000.0,
+            power_generation: telemetry.power_generation,
+            power_consumption: telemetry.power_consumption,
+            grid_stability: telemetry.grid_stability,
+            oxygen_level: telemetry.oxygen_level,
+            temperature: telemetry.temperature,
+            pressure: telemetry.pressure,
+            structural_integrity: telemetry.structural_integrity,
+            module_count: telemetry.module_count as u32,
+            sealed_modules: telemetry.sealed_modules as u32,
+        };
+        true
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn ss_station_module_count(station: Option<&SsStation>) -> u32 {
+    guard(0, || station.map_or(0, |station| station.station.modules().len() as u32))
+}
+
+// The id of the `index`th module in the station's current list, for
+// walking them; false past the end
+#[no_mangle]
+pub extern "C" fn ss_station_module_id(station: Option<&SsStation>, index: u32, out: Option<&mut SsModuleId>) -> bool {
+    guard(false, || {
+        let (Some(station), Some(out)) = (station, out) else {
+            return false;
+        };
+        let Some(id) = station.station.module_id(index as usize) else {
+            return false;
+        };
+        *out = id.into();
+        true
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn ss_station_element_count(station: Option<&SsStation>, module: SsModuleId) -> u32 {
+    guard(0, || {
+        station
+            .and_then(|station| station.module_index(module).map(|index| &station.station.modules()[index]))
+            .map_or(0, |module| module.interactive_elements.len() as u32)
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn ss_station_element_state(station: Option<&SsStation>, module: SsModuleId, element: u32) -> SsElementState {
+    guard(SsElementState::Failed, || {
+        station
+            .and_then(|station| station.station.element_state(station.module_index(module)?, element as usize))
+            .map_or(SsElementState::Invalid, SsElementState::from)
+    })
+}
+
+// Uses an element, as the player pressing the interact key would
+#[no_mangle]
+pub extern "C" fn ss_station_interact(station: Option<&mut SsStation>, module: SsModuleId, element: u32) -> SsInteraction {
+    guard(SsInteraction::Failed, || {
+        let Some(station) = station else {
+            return SsInteraction::Invalid;
+        };
+        let Some(module) = station.module_index(module) else {
+            return SsInteraction::Invalid;
+        };
+        let element = element as usize;
+        if station.station.element_state(module, element).is_none() {
+            return SsInteraction::Invalid;
+        }
+        let taken = station.station.activate_element(module, element).is_some();
+        station.collect_events();
+        if taken {
+            SsInteraction::Accepted
+        } else {
+            SsInteraction::Refused
+        }
+    })
+}
+
+// Takes the oldest pending event into `out`; false when there are none
+#[no_mangle]
+pub extern "C" fn ss_station_poll_event(station: Option<&mut SsStation>, out: Option<&mut SsEvent>) -> bool {
+    guard(false, || {
+        let (Some(station), Some(out)) = (station, out) else {
+            return false;
+        };
+        let Some(event) = station.events.pop_front() else {
+            return false;
+        };
+        copy_str(event.kind(), &mut out.kind);
+        copy_str(&event.describe(), &mut out.description);
+        out.severity = match event.severity() {
+            LogSeverity::Info => 0,
+            LogSeverity::Warning => 1,
+            LogSeverity::Critical => 2,
+        };
+        let position = event.position();
+        out.has_position = position.is_some();
+        out.position = position.unwrap_or_default().to_array();
+        true
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn blank_event() -> SsEvent {
+        SsEvent {
+            kind: [0; SS_EVENT_KIND_LEN],
+            description: [0; SS_EVENT_DESCRIPTION_LEN],
+            severity: -1,
+            has_position: false,
+            position: [0.0; 3],
+        }
+    }
+
+    fn text(chars: &[c_char]) -> String {
+        let bytes: Vec<u8> = chars.iter().take_while(|&&c| c != 0).map(|&c| c as u8).collect();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn a_station_is_made_ticked_and_polled_through_the_c_abi() {
+        let mut station = ss_station_create(7).unwrap();
+        let mut status = SsStatus::default();
+        assert!(ss_station_tick(Some(&mut station), 60));
+        assert!(ss_station_status(Some(&station), Some(&mut status)));
+        assert!((status.mission_seconds - 1.0).abs() < 1e-3, "{}", status.mission_seconds);
+        assert_eq!(status.module_count, ss_station_module_count(Some(&station)));
+
+        // A breach comes back out as an event, named and described
+        let mut event = blank_event();
+        while ss_station_poll_event(Some(&mut station), Some(&mut event)) {}
+        station.station.damage_module(5, 1.0);
+        assert!(ss_station_tick(Some(&mut station), 1));
+        let mut kinds = Vec::new();
+        while ss_station_poll_event(Some(&mut station), Some(&mut event)) {
+            assert!(!text(&event.description).is_empty());
+            kinds.push(text(&event.kind));
+        }
+        assert!(kinds.iter().any(|kind| kind == "hull_breach"), "{:?}", kinds);
+
+        // Null handles and stale modules are turned away, not dereferenced
+        assert!(!ss_station_tick(None, 1));
+        assert!(!ss_station_poll_event(None, Some(&mut event)));
+        let mut lab = SsModuleId::default();
+        assert!(ss_station_module_id(Some(&station), 5, Some(&mut lab)));
+        station.station.remove_module(5).unwrap();
+        assert_eq!(ss_station_interact(Some(&mut station), lab, 0), SsInteraction::Invalid);
+        assert_eq!(ss_station_element_state(Some(&station), lab, 0), SsElementState::Invalid);
+        ss_station_destroy(Some(station));
+    }
+
+    #[test]
+    fn events_pile_up_only_so_far_for_a_host_that_never_polls() {
+        let mut station = ss_station_create(0).unwrap();
+        for n in 0..SS_MAX_PENDING_EVENTS + 10 {
+            station.station.emit_event(StationEvent::ObjectiveCompleted { id: n.to_string(), title: n.to_string() });
+        }
+        station.collect_events();
+        assert_eq!(station.events.len(), SS_MAX_PENDING_EVENTS);
+        let mut event = blank_event();
+        assert!(ss_station_poll_event(Some(&mut station), Some(&mut event)));
+        assert!(text(&event.description).contains("10"), "{}", text(&event.description));
+    }
+
+    #[test]
+    fn strings_are_cut_short_on_a_character_boundary() {
+        let mut out = [1 as c_char; 6];
+        copy_str("hello world", &mut out);
+        assert_eq!(text(&out), "hello");
+        // "é" is two bytes, and only one of them would fit
+        copy_str("abcdé", &mut out);
+        assert_eq!(text(&out), "abcd");
+        assert_eq!(out[4], 0);
+        copy_str("", &mut out);
+        assert_eq!(out[0], 0);
+    }
+
+    #[test]
+    fn a_panic_fails_the_call_instead_of_unwinding_into_the_caller() {
+        assert_eq!(guard(SsInteraction::Failed, || panic!("boom")), SsInteraction::Failed);
+        assert!(guard(true, || true));
+    }
+}
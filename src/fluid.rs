@@ -0,0 +1,419 @@
+use glam::{Vec2, Vec3};
+use rand::Rng;
+use crate::events::StationEvent;
+use crate::module_id::ModuleId;
+use crate::rng::{self, SimRng};
+use crate::station::{ModuleType, SpaceStation};
+
+// Past this many blobs new spills are turned away
+const MAX_BLOBS: usize = 64;
+// Litres; smaller than this and a blob has evaporated
+const MIN_VOLUME: f32 = 0.01;
+// Share of speed kept per second drifting through the air
+const DRAG: f32 = 0.7;
+// Speed kept bouncing off a wall, and the speed that splits a blob
+const RESTITUTION: f32 = 0.3;
+const SPLIT_SPEED: f32 = 2.5;
+// Blobs closer than this share of their combined radius run together
+const MERGE_OVERLAP: f32 = 0.8;
+// Surface tension: how fast a blob wobbles, and how fast that dies away
+const WOBBLE_RATE: f32 = 9.0;
+const WOBBLE_DAMPING: f32 = 2.0;
+const MAX_WOBBLE: f32 = 0.35;
+// Litres let out per unit of damage to a module carrying liquid
+const LEAK_LITRES: f32 = 6.0;
+// The wet vac's reach and the angle it pulls over, how hard it pulls,
+// and how much it swallows per second from blobs at the nozzle
+pub const VACUUM_REACH: f32 = 2.5;
+const VACUUM_CONE: f32 = 0.8;
+const VACUUM_PULL: f32 = 6.0;
+const VACUUM_INTAKE: f32 = 0.3;
+const VACUUM_RATE: f32 = 1.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Liquid {
+    Water,
+    Coolant,
+}
+
+impl Liquid {
+    pub fn name(self) -> &'static str {
+        match self {
+            Liquid::Water => "water",
+            Liquid::Coolant => "coolant",
+        }
+    }
+
+    pub fn color(self) -> Vec3 {
+        match self {
+            Liquid::Water => Vec3::new(0.55, 0.75, 0.95),
+            Liquid::Coolant => Vec3::new(0.3, 0.9, 0.55),
+        }
+    }
+
+    // What a damaged module's pipes have in them, if anything
+    pub fn carried_by(module_type: ModuleType) -> Option<Liquid> {
+        match module_type {
+            ModuleType::PowerPlant => Some(Liquid::Coolant),
+            ModuleType::LivingQuarters | ModuleType::Laboratory => Some(Liquid::Water),
+            _ => None,
+        }
+    }
+}
+
+// A floating ball of liquid, held together by surface tension
+#[derive(Debug, Clone, PartialEq)]
+pub struct Blob {
+    pub liquid: Liquid,
+    pub module: ModuleId,
+    pub position: Vec3,
+    pub velocity: Vec3,
+    // Litres
+    pub volume: f32,
+    // How far it's squashed from round, and where it is in the wobble
+    wobble: f32,
+    phase: f32,
+}
+
+impl Blob {
+    // Metres, for a sphere of its volume
+    pub fn radius(&self) -> f32 {
+        (self.volume * 0.001 * 3.0 / (4.0 * std::f32::consts::PI)).cbrt()
+    }
+
+    // Stretch along and across the way it's wobbling, volume kept about the same
+    pub fn stretch(&self) -> Vec2 {
+        let squash = self.wobble * self.phase.sin();
+        Vec2::new(1.0 + squash, 1.0 / (1.0 + squash))
+    }
+
+    fn kick(&mut self, amount: f32) {
+        self.wobble = (self.wobble + amount).min(MAX_WOBBLE);
+    }
+}
+
+// Spilled liquids. Free of gravity they drift as blobs that merge when they
+// touch, splash apart when they hit a wall hard, and wobble for a while
+// after either. Each blob stays in the module it was spilled in.
+#[derive(Debug)]
+pub struct Spills {
+    blobs: Vec<Blob>,
+    rng: SimRng,
+}
+
+impl Spills {
+    pub fn new(rng: SimRng) -> Self {
+        Self { blobs: Vec::new(), rng }
+    }
+
+    pub fn blobs(&self) -> &[Blob] {
+        &self.blobs
+    }
+
+    pub fn total_volume(&self) -> f32 {
+        self.blobs.iter().map(|blob| blob.volume).sum()
+    }
+
+    // Lets out `volume` litres at `position`, broken into a few blobs
+    // spraying out around `velocity`
+    pub fn spill(&mut self, module: ModuleId, liquid: Liquid, position: Vec3, velocity: Vec3, volume: f32) {
+        let pieces = self.rng.gen_range(2..=4);
+        for _ in 0..pieces {
+            if self.blobs.len() >= MAX_BLOBS {
+                return;
+            }
+            let spray = rng::random_direction(&mut self.rng);
+            let mut blob = Blob {
+                liquid,
+                module,
+                position,
+                velocity: velocity + spray * self.rng.gen_range(0.2..0.8),
+                volume: volume / pieces as f32,
+                wobble: MAX_WOBBLE,
+                phase: self.rng.gen_range(0.0..std::f32::consts::TAU),
+            };
+            // Clear of each other, so they don't run straight back together
+            blob.position += spray * blob.radius() * 2.0;
+            self.blobs.push(blob);
+        }
+    }
+
+    // Damage to modules with pipes in them springs a leak
+    pub fn handle_event(&mut self, event: &StationEvent, station: &SpaceStation) {
+        let StationEvent::ModuleDamaged { module, position, integrity } = event else {
+            return;
+        };
+        let Some(module) = station.modules().get(*module) else {
+            return;
+        };
+        let Some(liquid) = Liquid::carried_by(module.module_type) else {
+            return;
+        };
+        let velocity = rng::random_direction(&mut self.rng) * 0.5;
+        let volume = LEAK_LITRES * (1.0 - integrity).max(0.1);
+        self.spill(module.id, liquid, *position + Vec3::Y * 1.5, velocity, volume);
+    }
+
+    pub fn update(&mut self, station: &SpaceStation, delta_time: f32) {
+        let mut splashes = Vec::new();
+        for blob in &mut self.blobs {
+            blob.velocity *= (1.0 - (1.0 - DRAG) * delta_time).max(0.0);
+            blob.position += blob.velocity * delta_time;
+            blob.phase = (blob.phase + WOBBLE_RATE * delta_time) % std::f32::consts::TAU;
+            blob.wobble *= (1.0 - WOBBLE_DAMPING * delta_time).max(0.0);
+
+            let Some(module) = station.module_index(blob.module).map(|index| &station.modules()[index]) else {
+                blob.volume = 0.0;
+                continue;
+            };
+            let (min, max) = module.bounds();
+            let radius = blob.radius();
+            let (min, max) = (min + Vec3::splat(radius), (max - Vec3::splat(radius)).max(min + Vec3::splat(radius)));
+            let inside = blob.position.clamp(min, max);
+            if inside == blob.position {
+                continue;
+            }
+            // Off the wall, losing the speed it hit with
+            let normal = (inside - blob.position).normalize_or_zero();
+            let impact = -blob.velocity.dot(normal);
+            blob.position = inside;
+            if impact <= 0.0 {
+                continue;
+            }
+            blob.velocity += normal * impact * (1.0 + RESTITUTION);
+            blob.kick(impact * 0.2);
+            if impact > SPLIT_SPEED && blob.volume > MIN_VOLUME * 4.0 {
+                splashes.push((blob.clone(), normal, impact));
+                blob.volume *= 0.5;
+            }
+        }
+
+        // Hard hits throw off a droplet half the blob's size, sideways
+        for (blob, normal, impact) in splashes {
+            if self.blobs.len() >= MAX_BLOBS {
+                break;
+            }
+            let side = normal.cross(rng::random_direction(&mut self.rng)).normalize_or_zero();
+            let volume = blob.volume * 0.5;
+            let velocity = blob.velocity + side * impact * 0.5;
+            let position = blob.position + side * blob.radius() * 2.0;
+            self.blobs.push(Blob { volume, position, velocity, ..blob });
+        }
+
+        self.merge();
+        self.blobs.retain(|blob| blob.volume >= MIN_VOLUME);
+    }
+
+    // Touching blobs of the same spill pool into one, keeping their volume
+    // and momentum; the bigger one decides what it is
+    fn merge(&mut self) {
+        let mut i = 0;
+        while i < self.blobs.len() {
+            let mut j = i + 1;
+            while j < self.blobs.len() {
+                let (a, b) = (&self.blobs[i], &self.blobs[j]);
+                let touching = a.module == b.module
+                    && a.position.distance(b.position) < (a.radius() + b.radius()) * MERGE_OVERLAP;
+                if !touching {
+                    j += 1;
+                    continue;
+                }
+                let b = self.blobs.swap_remove(j);
+                let a = &mut self.blobs[i];
+                let volume = a.volume + b.volume;
+                a.position = (a.position * a.volume + b.position * b.volume) / volume;
+                a.velocity = (a.velocity * a.volume + b.velocity * b.volume) / volume;
+                if b.volume > a.volume {
+                    a.liquid = b.liquid;
+                }
+                a.volume = volume;
+                a.kick(MAX_WOBBLE * 0.5);
+            }
+            i += 1;
+        }
+    }
+
+    // The wet vac pointed along `direction` from `nozzle`: blobs in front
+    // are drawn in, and what reaches the nozzle is swallowed. Returns the
+    // litres taken up.
+    pub fn vacuum(&mut self, nozzle: Vec3, direction: Vec3, delta_time: f32) -> f32 {
+        let direction = direction.normalize_or_zero();
+        let mut taken = 0.0;
+        for blob in &mut self.blobs {
+            let to_nozzle = nozzle - blob.position;
+            let distance = to_nozzle.length();
+            if distance > VACUUM_REACH || -to_nozzle.dot(direction) < distance * VACUUM_CONE {
+                continue;
+            }
+            if distance < VACUUM_INTAKE + blob.radius() {
+                let swallowed = blob.volume.min(VACUUM_RATE * delta_time);
+                blob.volume -= swallowed;
+                taken += swallowed;
+                blob.kick(delta_time);
+            } else {
+                let pull = VACUUM_PULL * (1.0 - distance / VACUUM_REACH);
+                blob.velocity += to_nozzle / distance * pull * delta_time;
+            }
+        }
+        self.blobs.retain(|blob| blob.volume >= MIN_VOLUME);
+        taken
+    }
+}
+
+// A blob projected onto the screen, for `metaball_cells`
+#[derive(Debug, Clone, Copy)]
+pub struct ScreenBlob {
+    pub centre: Vec2,
+    // Pixels
+    pub radius: f32,
+    pub stretch: Vec2,
+    pub color: Vec3,
+}
+
+// One filled square of the metaball surface, with its shaded colour
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MetaballCell {
+    pub position: Vec2,
+    pub size: f32,
+    pub color: Vec3,
+    pub alpha: f32,
+}
+
+// Most cells across the area the blobs cover; wider areas get coarser cells
+const MAX_CELLS: f32 = 96.0;
+const MIN_CELL: f32 = 3.0;
+
+// Screen-space metaballs. Each blob adds radius² / distance² to a field
+// that's 1 on the edge of a lone blob, so blobs drawn close together bulge
+// towards each other and bridge before they merge. The field is sampled on
+// a grid of cells over the blobs and lit from the top left by its slope.
+pub fn metaball_cells(blobs: &[ScreenBlob], screen: Vec2) -> Vec<MetaballCell> {
+    let mut cells = Vec::new();
+    if blobs.is_empty() {
+        return cells;
+    }
+    let reach = |blob: &ScreenBlob| blob.radius * blob.stretch.max_element() * 1.5;
+    let min = blobs.iter().fold(screen, |min, blob| min.min(blob.centre - Vec2::splat(reach(blob))));
+    let max = blobs.iter().fold(Vec2::ZERO, |max, blob| max.max(blob.centre + Vec2::splat(reach(blob))));
+    let (min, max) = (min.max(Vec2::ZERO), max.min(screen));
+    if min.x >= max.x || min.y >= max.y {
+        return cells;
+    }
+    let size = ((max - min).max_element() / MAX_CELLS).max(MIN_CELL);
+
+    let field = |point: Vec2| {
+        let mut total = 0.0;
+        let mut color = Vec3::ZERO;
+        for blob in blobs {
+            let offset = (point - blob.centre) / blob.stretch;
+            let contribution = blob.radius * blob.radius / offset.length_squared().max(1.0);
+            total += contribution;
+            color += blob.color * contribution;
+        }
+        (total, color / total.max(f32::EPSILON))
+    };
+
+    let light = Vec2::new(-1.0, -1.0).normalize();
+    let mut y = min.y;
+    while y < max.y {
+        let mut x = min.x;
+        while x < max.x {
+            let point = Vec2::new(x, y) + Vec2::splat(size * 0.5);
+            let (value, color) = field(point);
+            if value >= 1.0 {
+                // The field falls away from the middle, so its slope points
+                // out of the surface
+                let slope = Vec2::new(
+                    field(point - Vec2::X * size).0 - field(point + Vec2::X * size).0,
+                    field(point - Vec2::Y * size).0 - field(point + Vec2::Y * size).0,
+                );
+                let facing = slope.normalize_or_zero().dot(light);
+                let shade = 0.75 + 0.35 * facing;
+                let highlight = if facing > 0.8 && value < 1.6 { 0.3 } else { 0.0 };
+                cells.push(MetaballCell {
+                    position: Vec2::new(x, y),
+                    size,
+                    color: (color * shade + Vec3::splat(highlight)).min(Vec3::ONE),
+                    // Thinner towards the edge
+                    alpha: (0.45 + (value - 1.0) * 0.4).min(0.85),
+                });
+            }
+            x += size;
+        }
+        y += size;
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEP: f32 = 1.0 / 60.0;
+
+    #[test]
+    fn spilled_blobs_merge_split_on_walls_and_vacuum_up() {
+        let station = SpaceStation::create_default_layout();
+        let mut spills = Spills::new(rng::seeded(7));
+        let (module, middle) = (station.modules()[0].id, station.modules()[0].transform.position + Vec3::Y * 2.0);
+        spills.spill(module, Liquid::Water, middle - Vec3::X, Vec3::X * 2.0, 1.5);
+        spills.spill(module, Liquid::Water, middle + Vec3::X, Vec3::NEG_X * 2.0, 1.5);
+        let spilled = spills.blobs().len();
+        assert!(spilled >= 4);
+        for _ in 0..120 {
+            spills.update(&station, STEP);
+        }
+        assert!(spills.blobs().len() < spilled);
+        assert!((spills.total_volume() - 3.0).abs() < 1e-3);
+
+        // Thrown at a wall it splashes apart, still all there
+        spills.spill(module, Liquid::Coolant, middle, Vec3::X * 20.0, 4.0);
+        let (_, max) = station.modules()[0].bounds();
+        let mut most = 0;
+        for _ in 0..30 {
+            spills.update(&station, STEP);
+            most = most.max(spills.blobs().len());
+            assert!(spills.blobs().iter().all(|blob| blob.position.x <= max.x));
+        }
+        assert!(most > 5);
+        assert!((spills.total_volume() - 7.0).abs() < 1e-2);
+
+        // Held a metre off each blob in turn, from the middle of the room,
+        // the wet vac takes it all
+        let mut taken = 0.0;
+        for _ in 0..60 {
+            let Some(blob) = spills.blobs().first() else {
+                break;
+            };
+            let toward = (blob.position - middle).try_normalize().unwrap_or(Vec3::X);
+            let nozzle = blob.position - toward;
+            for _ in 0..120 {
+                taken += spills.vacuum(nozzle, toward, STEP);
+                spills.update(&station, STEP);
+            }
+        }
+        assert!(spills.blobs().is_empty());
+        assert!((taken - 7.0).abs() < 0.1);
+        assert_eq!(spills.vacuum(middle, Vec3::X, STEP), 0.0);
+    }
+
+    #[test]
+    fn metaballs_bridge_between_close_blobs() {
+        let blob = |x: f32| ScreenBlob {
+            centre: Vec2::new(x, 100.0),
+            radius: 20.0,
+            stretch: Vec2::ONE,
+            color: Vec3::ONE,
+        };
+        let screen = Vec2::new(400.0, 200.0);
+        let covers = |cells: &[MetaballCell], point: Vec2| {
+            cells.iter().any(|cell| (point - cell.position).cmpge(Vec2::ZERO).all() && (point - cell.position).cmplt(Vec2::splat(cell.size)).all())
+        };
+        let apart = metaball_cells(&[blob(100.0), blob(300.0)], screen);
+        assert!(covers(&apart, Vec2::new(100.0, 100.0)));
+        assert!(!covers(&apart, Vec2::new(200.0, 100.0)));
+        let close = metaball_cells(&[blob(175.0), blob(225.0)], screen);
+        assert!(covers(&close, Vec2::new(200.0, 100.0)));
+        assert!(metaball_cells(&[], screen).is_empty());
+    }
+}
@@ -0,0 +1,125 @@
+use glam::{Vec2, Vec3};
+
+pub const BOOKMARK_SLOTS: usize = 9;
+
+const MIN_SPEED_MULTIPLIER: f32 = 0.05;
+const MAX_SPEED_MULTIPLIER: f32 = 50.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraPose {
+    pub position: Vec3,
+    pub yaw: f32,
+    pub pitch: f32,
+}
+
+impl CameraPose {
+    pub fn forward(&self) -> Vec3 {
+        Vec3::new(
+            self.yaw.cos() * self.pitch.cos(),
+            self.pitch.sin(),
+            self.yaw.sin() * self.pitch.cos(),
+        )
+    }
+}
+
+// One frame of input, already read from the keyboard/mouse by the caller.
+// `movement` is local: x = right, y = up, z = forward.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct FlyInput {
+    pub movement: Vec3,
+    pub look: Vec2,
+    pub scroll: f32,
+    pub boost: bool,
+}
+
+// Developer free-fly camera. It has its own pose, ignores collision and never
+// touches the player controller, so toggling it off returns to the player view.
+#[derive(Debug)]
+pub struct FlyCamera {
+    pub active: bool,
+    pub pose: CameraPose,
+    pub base_speed: f32,
+    pub look_speed: f32,
+    speed_multiplier: f32,
+    bookmarks: [Option<CameraPose>; BOOKMARK_SLOTS],
+}
+
+impl Default for FlyCamera {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl FlyCamera {
+    pub fn new() -> Self {
+        Self {
+            active: false,
+            pose: CameraPose {
+                position: Vec3::ZERO,
+                yaw: 0.0,
+                pitch: 0.0,
+            },
+            base_speed: 6.0,
+            look_speed: 0.003,
+            speed_multiplier: 1.0,
+            bookmarks: [None; BOOKMARK_SLOTS],
+        }
+    }
+
+    // Starts flying from `from` (usually the player's current view)
+    pub fn toggle(&mut self, from: CameraPose) {
+        self.active = !self.active;
+        if self.active {
+            self.pose = from;
+        }
+    }
+
+    pub fn speed_multiplier(&self) -> f32 {
+        self.speed_multiplier
+    }
+
+    pub fn update(&mut self, input: &FlyInput, delta_time: f32) {
+        if !self.active {
+            return;
+        }
+
+        // Each scroll notch scales speed by 25%
+        if input.scroll != 0.0 {
+            self.speed_multiplier = (self.speed_multiplier * 1.25f32.powf(input.scroll))
+                .clamp(MIN_SPEED_MULTIPLIER, MAX_SPEED_MULTIPLIER);
+        }
+
+        self.pose.yaw += input.look.x * self.look_speed;
+        self.pose.pitch = (self.pose.pitch - input.look.y * self.look_speed).clamp(-1.5, 1.5);
+
+        let forward = self.pose.forward();
+        let right = forward.cross(Vec3::Y).normalize_or_zero();
+        let direction = right * input.movement.x + Vec3::Y * input.movement.y + forward * input.movement.z;
+
+        let mut speed = self.base_speed * self.speed_multiplier;
+        if input.boost {
+            speed *= 4.0;
+        }
+        self.pose.position += direction.normalize_or_zero() * speed * delta_time;
+    }
+
+    pub fn save_bookmark(&mut self, slot: usize) -> bool {
+        match self.bookmarks.get_mut(slot) {
+            Some(bookmark) => {
+                *bookmark = Some(self.pose);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn recall_bookmark(&mut self, slot: usize) -> bool {
+        match self.bookmarks.get(slot).copied().flatten() {
+            Some(pose) => {
+                self.pose = pose;
+                true
+            }
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,261 @@
+use std::fs;
+use std::path::Path;
+use anyhow::{Context, Result};
+use glam::Vec3;
+use serde_json::{json, Value};
+use crate::door::DOOR_COLOR;
+use crate::geometry::Mesh;
+use crate::material::Material;
+use crate::station::SpaceStation;
+
+// glTF enums
+const FLOAT: u32 = 5126;
+const UNSIGNED_INT: u32 = 5125;
+const ARRAY_BUFFER: u32 = 34962;
+const ELEMENT_ARRAY_BUFFER: u32 = 34963;
+
+const GLB_MAGIC: u32 = 0x4654_6C67;
+const GLB_JSON: u32 = 0x4E4F_534A;
+const GLB_BIN: u32 = 0x004E_4942;
+
+// Collects the binary buffer and the JSON arrays that index into it
+#[derive(Debug)]
+struct Builder {
+    buffer: Vec<u8>,
+    buffer_views: Vec<Value>,
+    accessors: Vec<Value>,
+    meshes: Vec<Value>,
+    materials: Vec<Value>,
+    nodes: Vec<Value>,
+}
+
+impl Builder {
+    fn new() -> Self {
+        Self {
+            buffer: Vec::new(),
+            buffer_views: Vec::new(),
+            accessors: Vec::new(),
+            meshes: Vec::new(),
+            materials: Vec::new(),
+            nodes: Vec::new(),
+        }
+    }
+
+    // Everything written is 4-byte values, so views stay aligned
+    fn view(&mut self, data: impl Iterator<Item = [u8; 4]>, target: u32) -> usize {
+        let offset = self.buffer.len();
+        self.buffer.extend(data.flatten());
+        self.buffer_views.push(json!({
+            "buffer": 0,
+            "byteOffset": offset,
+            "byteLength": self.buffer.len() - offset,
+            "target": target,
+        }));
+        self.buffer_views.len() - 1
+    }
+
+    fn accessor(&mut self, view: usize, component_type: u32, count: usize, kind: &str) -> usize {
+        self.accessors.push(json!({
+            "bufferView": view,
+            "componentType": component_type,
+            "count": count,
+            "type": kind,
+        }));
+        self.accessors.len() - 1
+    }
+
+    fn material(&mut self, name: &str, material: &Material) -> usize {
+        let albedo = material.albedo;
+        let emissive = material.emission().clamp(Vec3::ZERO, Vec3::ONE);
+        let mut value = json!({
+            "name": name,
+            "pbrMetallicRoughness": {
+                "baseColorFactor": [albedo.x, albedo.y, albedo.z, albedo.w * material.alpha],
+                "metallicFactor": material.metallic,
+                "roughnessFactor": material.roughness,
+            },
+            "emissiveFactor": emissive.to_array(),
+            // Modules are built to be seen from inside as well as out
+            "doubleSided": true,
+        });
+        if albedo.w * material.alpha < 1.0 {
+            value["alphaMode"] = json!("BLEND");
+        }
+        self.materials.push(value);
+        self.materials.len() - 1
+    }
+
+    // None for an empty mesh, which glTF can't hold
+    fn mesh(&mut self, name: &str, mesh: &Mesh, material: usize) -> Option<usize> {
+        if mesh.vertices.is_empty() || mesh.indices.is_empty() {
+            return None;
+        }
+        let count = mesh.vertices.len();
+        let floats = |values: Vec<f32>| values.into_iter().map(f32::to_le_bytes);
+
+        let positions: Vec<f32> = mesh.vertices.iter().flat_map(|v| v.position).collect();
+        let view = self.view(floats(positions), ARRAY_BUFFER);
+        let position = self.accessor(view, FLOAT, count, "VEC3");
+        // Readers need the bounds of every position accessor
+        let (min, max) = mesh.vertices.iter().fold((Vec3::INFINITY, Vec3::NEG_INFINITY), |(min, max), v| {
+            (min.min(Vec3::from(v.position)), max.max(Vec3::from(v.position)))
+        });
+        self.accessors[position]["min"] = json!(min.to_array());
+        self.accessors[position]["max"] = json!(max.to_array());
+
+        let normals: Vec<f32> = mesh.vertices.iter().flat_map(|v| v.normal).collect();
+        let view = self.view(floats(normals), ARRAY_BUFFER);
+        let normal = self.accessor(view, FLOAT, count, "VEC3");
+
+        let tex_coords: Vec<f32> = mesh.vertices.iter().flat_map(|v| v.tex_coord).collect();
+        let view = self.view(floats(tex_coords), ARRAY_BUFFER);
+        let tex_coord = self.accessor(view, FLOAT, count, "VEC2");
+
+        let view = self.view(mesh.indices.iter().map(|i| i.to_le_bytes()), ELEMENT_ARRAY_BUFFER);
+        let indices = self.accessor(view, UNSIGNED_INT, mesh.indices.len(), "SCALAR");
+
+        self.meshes.push(json!({
+            "name": name,
+            "primitives": [{
+                "attributes": { "POSITION": position, "NORMAL": normal, "TEXCOORD_0": tex_coord },
+                "indices": indices,
+                "material": material,
+            }],
+        }));
+        Some(self.meshes.len() - 1)
+    }
+
+    fn node(&mut self, node: Value) -> usize {
+        self.nodes.push(node);
+        self.nodes.len() - 1
+    }
+
+    // The glTF JSON, pointing at the buffer by `uri` or, in a GLB, at the
+    // binary chunk
+    fn document(&self, uri: Option<&str>, children: Vec<usize>) -> Value {
+        let mut buffer = json!({ "byteLength": self.buffer.len() });
+        if let Some(uri) = uri {
+            buffer["uri"] = json!(uri);
+        }
+        let mut nodes = self.nodes.clone();
+        nodes.push(json!({ "name": "Station", "children": children }));
+        json!({
+            "asset": { "version": "2.0", "generator": "space_station_3d" },
+            "scene": 0,
+            "scenes": [{ "nodes": [nodes.len() - 1] }],
+            "nodes": nodes,
+            "meshes": self.meshes,
+            "materials": self.materials,
+            "accessors": self.accessors,
+            "bufferViews": self.buffer_views,
+            "buffers": [buffer],
+        })
+    }
+}
+
+// Every module's mesh in place with its material, and every door panel as
+// far open as it is now
+fn build(station: &SpaceStation) -> (Builder, Vec<usize>) {
+    let mut builder = Builder::new();
+    let mut children = Vec::new();
+
+    for (i, module) in station.modules().iter().enumerate() {
+        let name = format!("{}: {:?}", i, module.module_type);
+        let material = builder.material(&name, &module.material);
+        let Some(mesh) = builder.mesh(&name, &module.mesh, material) else {
+            continue;
+        };
+        let transform = &module.transform;
+        children.push(builder.node(json!({
+            "name": name,
+            "mesh": mesh,
+            "translation": transform.position.to_array(),
+            "rotation": transform.rotation.to_array(),
+            "scale": transform.scale.to_array(),
+        })));
+    }
+
+    // Every door has the same panel, so they share one mesh
+    if let Some(first) = station.doors().first() {
+        let material = builder.material("Door", &Material::create_metal(DOOR_COLOR));
+        if let Some(mesh) = builder.mesh("Door", &first.mesh, material) {
+            for (i, door) in station.doors().iter().enumerate() {
+                let Some((a, b)) = station.door_modules(i) else {
+                    continue;
+                };
+                children.push(builder.node(json!({
+                    "name": format!("Door {}-{}", a, b),
+                    "mesh": mesh,
+                    "matrix": door.panel_matrix(station.door_openness(i)).to_cols_array(),
+                })));
+            }
+        }
+    }
+    (builder, children)
+}
+
+fn pad(bytes: &mut Vec<u8>, with: u8) {
+    while !bytes.len().is_multiple_of(4) {
+        bytes.push(with);
+    }
+}
+
+// The whole station as one binary glTF
+pub fn to_glb(station: &SpaceStation) -> Vec<u8> {
+    let (builder, children) = build(station);
+    let mut json = builder.document(None, children).to_string().into_bytes();
+    pad(&mut json, b' ');
+    let mut bin = builder.buffer;
+    pad(&mut bin, 0);
+
+    let length = 12 + 8 + json.len() + 8 + bin.len();
+    let mut glb = Vec::with_capacity(length);
+    for word in [GLB_MAGIC, 2, length as u32, json.len() as u32, GLB_JSON] {
+        glb.extend(word.to_le_bytes());
+    }
+    glb.extend(json);
+    for word in [bin.len() as u32, GLB_BIN] {
+        glb.extend(word.to_le_bytes());
+    }
+    glb.extend(bin);
+    glb
+}
+
+// `.glb` writes one binary file; anything else writes JSON glTF with its
+// buffer in a `.bin` alongside
+pub fn export(station: &SpaceStation, path: &Path) -> Result<()> {
+    if path.extension().is_some_and(|ext| ext.eq_ignore_ascii_case("glb")) {
+        return fs::write(path, to_glb(station)).with_context(|| format!("failed to write {}", path.display()));
+    }
+    let (builder, children) = build(station);
+    let bin_path = path.with_extension("bin");
+    let uri = bin_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .context("export path needs a file name")?;
+    let document = serde_json::to_string_pretty(&builder.document(Some(uri), children))
+        .context("failed to serialize glTF")?;
+    fs::write(&bin_path, &builder.buffer).with_context(|| format!("failed to write {}", bin_path.display()))?;
+    fs::write(path, document).with_context(|| format!("failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gltf_export_holds_every_module_and_door() {
+        let station = SpaceStation::create_default_layout();
+        let glb = to_glb(&station);
+        let word = |at: usize| u32::from_le_bytes(glb[at..at + 4].try_into().unwrap()) as usize;
+        assert_eq!(&glb[..4], b"glTF");
+        assert_eq!(word(8), glb.len());
+        let json: serde_json::Value = serde_json::from_slice(&glb[20..20 + word(12)]).unwrap();
+        let nodes = json["nodes"].as_array().unwrap();
+        // Modules, doors and the root
+        assert_eq!(nodes.len(), station.modules().len() + station.doors().len() + 1);
+        assert_eq!(json["meshes"].as_array().unwrap().len(), station.modules().len() + 1);
+        let bin = 20 + word(12);
+        assert_eq!(word(bin), json["buffers"][0]["byteLength"].as_u64().unwrap() as usize);
+    }
+}
@@ -0,0 +1,279 @@
+use anyhow::{anyhow, bail, Context, Result};
+use ash::vk;
+
+const NVIDIA_VENDOR: u32 = 0x10de;
+const INTEL_VENDOR: u32 = 0x8086;
+
+// Which GPU to ask for on machines with more than one. The adapters are
+// listed through Vulkan; rendering goes through OpenGL, so the choice is
+// passed on through the driver hints read when the context is created.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum GpuPreference {
+    // Whatever the driver picks
+    #[default]
+    Default,
+    Discrete,
+    Integrated,
+    // An index into the list `--list-gpus` prints
+    Adapter(usize),
+}
+
+impl GpuPreference {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "default" => Some(GpuPreference::Default),
+            "discrete" => Some(GpuPreference::Discrete),
+            "integrated" => Some(GpuPreference::Integrated),
+            _ => name.parse().ok().map(GpuPreference::Adapter),
+        }
+    }
+
+    // `--gpu default|discrete|integrated|<n>` from the command line, if given
+    pub fn from_args(args: &[String]) -> Result<Option<Self>> {
+        let Some(i) = args.iter().position(|arg| arg == "--gpu") else {
+            return Ok(None);
+        };
+        let value = args.get(i + 1).context("--gpu needs a value")?;
+        match Self::from_name(value) {
+            Some(preference) => Ok(Some(preference)),
+            None => bail!("--gpu must be default, discrete, integrated or a number from --list-gpus, not {}", value),
+        }
+    }
+
+    // The adapter to ask the driver for; None leaves it to the driver. Of
+    // several that fit, the one with the newest Vulkan support wins.
+    pub fn pick(self, adapters: &[Adapter]) -> Option<&Adapter> {
+        let newest_of = |kind: AdapterKind| {
+            adapters.iter().filter(|adapter| adapter.kind == kind).max_by_key(|adapter| adapter.api_version)
+        };
+        match self {
+            GpuPreference::Default => None,
+            GpuPreference::Discrete => newest_of(AdapterKind::Discrete),
+            GpuPreference::Integrated => newest_of(AdapterKind::Integrated),
+            GpuPreference::Adapter(index) => adapters.get(index),
+        }
+    }
+
+    // Without a Vulkan loader to list adapters, falls back to Mesa's plain
+    // PRIME hint. Has to run before the window is created; hints the user
+    // already set in the environment are left alone.
+    pub fn apply(self) {
+        let prime = match self {
+            GpuPreference::Default => return,
+            GpuPreference::Discrete => "1",
+            GpuPreference::Integrated => "0",
+            GpuPreference::Adapter(index) => {
+                eprintln!("Warning: can't pick GPU {} without a Vulkan loader to list them", index);
+                return;
+            }
+        };
+        if cfg!(target_os = "linux") {
+            // Mesa's PRIME offloading
+            if std::env::var_os("DRI_PRIME").is_none() {
+                std::env::set_var("DRI_PRIME", prime);
+            }
+        } else {
+            eprintln!(
+                "Warning: GPU selection isn't supported on {}; choose the GPU in the system graphics settings",
+                std::env::consts::OS
+            );
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdapterKind {
+    Discrete,
+    Integrated,
+    Virtual,
+    Cpu,
+    Other,
+}
+
+impl AdapterKind {
+    fn name(self) -> &'static str {
+        match self {
+            AdapterKind::Discrete => "discrete",
+            AdapterKind::Integrated => "integrated",
+            AdapterKind::Virtual => "virtual",
+            AdapterKind::Cpu => "software",
+            AdapterKind::Other => "other",
+        }
+    }
+}
+
+// A GPU as the Vulkan loader sees it
+#[derive(Debug, Clone, PartialEq)]
+pub struct Adapter {
+    pub name: String,
+    pub kind: AdapterKind,
+    pub vendor_id: u32,
+    pub device_id: u32,
+    pub driver_version: u32,
+    pub api_version: u32,
+}
+
+impl Adapter {
+    pub fn describe(&self) -> String {
+        format!(
+            "{} ({}, {:04x}:{:04x}, driver {}, Vulkan {}.{})",
+            self.name,
+            self.kind.name(),
+            self.vendor_id,
+            self.device_id,
+            self.driver(),
+            vk::api_version_major(self.api_version),
+            vk::api_version_minor(self.api_version),
+        )
+    }
+
+    // Driver versions are packed differently by each vendor
+    pub fn driver(&self) -> String {
+        let version = self.driver_version;
+        match self.vendor_id {
+            NVIDIA_VENDOR => format!("{}.{}.{}", version >> 22, (version >> 14) & 0xff, (version >> 6) & 0xff),
+            INTEL_VENDOR if cfg!(windows) => format!("{}.{}", version >> 14, version & 0x3fff),
+            _ => format!(
+                "{}.{}.{}",
+                vk::api_version_major(version),
+                vk::api_version_minor(version),
+                vk::api_version_patch(version)
+            ),
+        }
+    }
+
+    // Points the GL driver at this adapter; has to run before the window is
+    // created. Hints the user already set in the environment are left alone.
+    pub fn request(&self) {
+        if !cfg!(target_os = "linux") {
+            eprintln!(
+                "Warning: GPU selection isn't supported on {}; choose the GPU in the system graphics settings",
+                std::env::consts::OS
+            );
+            return;
+        }
+        // Mesa's PRIME offloading takes a vendor:device pair
+        set_default_var("DRI_PRIME", &format!("{:04x}:{:04x}", self.vendor_id, self.device_id));
+        // NVIDIA's own driver has its offloading switches instead
+        if self.vendor_id == NVIDIA_VENDOR {
+            set_default_var("__NV_PRIME_RENDER_OFFLOAD", "1");
+            set_default_var("__GLX_VENDOR_LIBRARY_NAME", "nvidia");
+        }
+    }
+}
+
+fn set_default_var(name: &str, value: &str) {
+    if std::env::var_os(name).is_none() {
+        std::env::set_var(name, value);
+    }
+}
+
+// Every GPU the Vulkan loader can see, in its order
+pub fn enumerate_adapters() -> Result<Vec<Adapter>> {
+    // The loader's error already repeats its cause, so it isn't chained
+    let entry = unsafe { ash::Entry::load() }.map_err(|err| anyhow!("no Vulkan loader to list GPUs with: {}", err))?;
+    let app = vk::ApplicationInfo::default().api_version(vk::API_VERSION_1_0);
+    let create_info = vk::InstanceCreateInfo::default().application_info(&app);
+    let instance = unsafe { entry.create_instance(&create_info, None) }.context("failed to create a Vulkan instance")?;
+    let devices = unsafe { instance.enumerate_physical_devices() };
+    let adapters = devices.map(|devices| {
+        devices
+            .into_iter()
+            .map(|device| {
+                let properties = unsafe { instance.get_physical_device_properties(device) };
+                let name = properties.device_name_as_c_str().map_or_else(|_| "unknown".to_string(), |name| name.to_string_lossy().into_owned());
+                Adapter {
+                    name,
+                    kind: match properties.device_type {
+                        vk::PhysicalDeviceType::DISCRETE_GPU => AdapterKind::Discrete,
+                        vk::PhysicalDeviceType::INTEGRATED_GPU => AdapterKind::Integrated,
+                        vk::PhysicalDeviceType::VIRTUAL_GPU => AdapterKind::Virtual,
+                        vk::PhysicalDeviceType::CPU => AdapterKind::Cpu,
+                        _ => AdapterKind::Other,
+                    },
+                    vendor_id: properties.vendor_id,
+                    device_id: properties.device_id,
+                    driver_version: properties.driver_version,
+                    api_version: properties.api_version,
+                }
+            })
+            .collect()
+    });
+    unsafe { instance.destroy_instance(None) };
+    adapters.context("failed to list Vulkan devices")
+}
+
+// What the driver reports for the current context
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct GpuInfo {
+    pub vendor: String,
+    pub renderer: String,
+    // OpenGL version string, which includes the driver version
+    pub version: String,
+    pub shading_language: String,
+    pub max_texture_size: i32,
+}
+
+impl GpuInfo {
+    pub fn describe(&self) -> String {
+        format!("{} ({}), OpenGL {}", self.renderer, self.vendor, self.version)
+    }
+
+    pub fn to_text(&self) -> String {
+        format!(
+            "gpu: {}\ngpu vendor: {}\nopengl: {}\nglsl: {}\nmax texture size: {}\n",
+            self.renderer, self.vendor, self.version, self.shading_language, self.max_texture_size,
+        )
+    }
+
+    // Mesa's llvmpipe and friends; usually means the real driver failed to load
+    pub fn is_software(&self) -> bool {
+        let renderer = self.renderer.to_ascii_lowercase();
+        ["llvmpipe", "softpipe", "swrast", "software"]
+            .iter()
+            .any(|name| renderer.contains(name))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adapter(name: &str, kind: AdapterKind, api_version: u32) -> Adapter {
+        Adapter { name: name.to_string(), kind, vendor_id: NVIDIA_VENDOR, device_id: 0x2503, driver_version: 0, api_version }
+    }
+
+    #[test]
+    fn preferences_parse_from_names_and_numbers() {
+        assert_eq!(GpuPreference::from_name("discrete"), Some(GpuPreference::Discrete));
+        assert_eq!(GpuPreference::from_name("2"), Some(GpuPreference::Adapter(2)));
+        assert_eq!(GpuPreference::from_name("fastest"), None);
+        let args = ["--gpu".to_string(), "1".to_string()];
+        assert_eq!(GpuPreference::from_args(&args).unwrap(), Some(GpuPreference::Adapter(1)));
+        assert!(GpuPreference::from_args(&args[..1]).is_err());
+    }
+
+    #[test]
+    fn picks_the_newest_adapter_of_the_kind_asked_for() {
+        let adapters = [
+            adapter("igpu", AdapterKind::Integrated, vk::API_VERSION_1_3),
+            adapter("old", AdapterKind::Discrete, vk::API_VERSION_1_1),
+            adapter("new", AdapterKind::Discrete, vk::API_VERSION_1_3),
+            adapter("llvmpipe", AdapterKind::Cpu, vk::API_VERSION_1_3),
+        ];
+        let name = |preference: GpuPreference| preference.pick(&adapters).map(|adapter| adapter.name.as_str());
+        assert_eq!(name(GpuPreference::Discrete), Some("new"));
+        assert_eq!(name(GpuPreference::Integrated), Some("igpu"));
+        assert_eq!(name(GpuPreference::Adapter(3)), Some("llvmpipe"));
+        assert_eq!(name(GpuPreference::Adapter(4)), None);
+        assert_eq!(name(GpuPreference::Default), None);
+        assert_eq!(GpuPreference::Discrete.pick(&adapters[..1]), None);
+    }
+
+    #[test]
+    fn describes_nvidia_driver_versions_their_way() {
+        let mut gpu = adapter("GeForce RTX 3060", AdapterKind::Discrete, vk::make_api_version(0, 1, 3, 0));
+        gpu.driver_version = (535 << 22) | (104 << 14) | (5 << 6);
+        assert_eq!(gpu.describe(), "GeForce RTX 3060 (discrete, 10de:2503, driver 535.104.5, Vulkan 1.3)");
+    }
+}
@@ -0,0 +1,147 @@
+use std::fmt::Write;
+use crate::station::{ElementState, SpaceStation, StationModule};
+
+// Which network `SpaceStation::export_graphviz` draws
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GraphKind {
+    // Modules and the connections between them
+    Modules,
+    // Generators and the battery feeding the grid, the grid feeding modules
+    Power,
+    // Air paths through the doors between modules, and breaches to space
+    Atmosphere,
+}
+
+impl GraphKind {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "modules" => Some(GraphKind::Modules),
+            "power" => Some(GraphKind::Power),
+            "atmosphere" | "air" => Some(GraphKind::Atmosphere),
+            _ => None,
+        }
+    }
+}
+
+// DOT source for one of the station's networks, for rendering with
+// `dot -Tsvg`. In every graph unpowered modules are dashed and breached
+// ones red; in the atmosphere graph shut doors are dotted.
+pub fn export(station: &SpaceStation, kind: GraphKind) -> String {
+    let mut dot = String::new();
+    let modules = station.modules();
+    match kind {
+        GraphKind::Modules => {
+            dot.push_str("graph modules {\n    node [shape=box];\n");
+            for (i, module) in modules.iter().enumerate() {
+                let label = format!("{}: {:?}\\n{:.0}%", i, module.module_type, module.structural_integrity * 100.0);
+                node(&mut dot, i, module, &label);
+            }
+            for (i, j) in connections(station) {
+                let _ = writeln!(dot, "    m{} -- m{};", i, j);
+            }
+        }
+        GraphKind::Power => {
+            let telemetry = station.telemetry();
+            dot.push_str("digraph power {\n    rankdir=LR;\n    node [shape=box];\n");
+            let _ = writeln!(
+                dot,
+                "    grid [shape=circle, label=\"Grid\\n{:.0}%\"{}];",
+                telemetry.grid_stability * 100.0,
+                if telemetry.grid_stability < 1.0 { ", color=orange" } else { "" }
+            );
+            let _ = writeln!(
+                dot,
+                "    battery [shape=cylinder, label=\"Battery\\n{:.0}%\"];\n    battery -> grid [dir=both];",
+                telemetry.battery_level * 100.0
+            );
+            for (i, module) in modules.iter().enumerate() {
+                let label = format!("{}: {:?}", i, module.module_type);
+                node(&mut dot, i, module, &label);
+                let output = module.power_output();
+                if output > 0.0 {
+                    let _ = writeln!(dot, "    m{} -> grid [label=\"{:.0} kW\"];", i, output);
+                }
+                let draw = module.power_draw();
+                if draw > 0.0 {
+                    let style = if module.powered { "" } else { ", style=dashed" };
+                    let _ = writeln!(dot, "    grid -> m{} [label=\"{:.0} kW\"{}];", i, draw, style);
+                }
+            }
+        }
+        GraphKind::Atmosphere => {
+            dot.push_str("graph atmosphere {\n    node [shape=ellipse];\n");
+            dot.push_str("    space [shape=doublecircle, label=\"Space\"];\n");
+            for (i, module) in modules.iter().enumerate() {
+                let air = &module.atmosphere;
+                let label = format!(
+                    "{}: {:?}\\n{:.2} atm, O2 {:.0}%",
+                    i,
+                    module.module_type,
+                    air.pressure(),
+                    air.oxygen_level() * 100.0
+                );
+                node(&mut dot, i, module, &label);
+                if !module.atmosphere_sealed {
+                    let _ = writeln!(dot, "    m{} -- space [color=red, penwidth=2, label=\"breach\"];", i);
+                }
+            }
+            for (index, door) in station.doors().iter().enumerate() {
+                let Some((a, b)) = station.door_modules(index) else {
+                    continue;
+                };
+                let locked = station.element_state(a, door.element) == Some(ElementState::Locked);
+                let attributes = if locked {
+                    " [style=dotted, label=\"locked\"]"
+                } else if station.door_openness(index) <= 0.0 {
+                    " [style=dotted, label=\"shut\"]"
+                } else {
+                    ""
+                };
+                let _ = writeln!(dot, "    m{} -- m{}{};", a, b, attributes);
+            }
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+fn node(dot: &mut String, index: usize, module: &StationModule, label: &str) {
+    let mut attributes = format!("label=\"{}\"", label);
+    if !module.powered {
+        attributes.push_str(", style=dashed");
+    }
+    if !module.atmosphere_sealed {
+        attributes.push_str(", color=red, fontcolor=red");
+    }
+    let _ = writeln!(dot, "    m{} [{}];", index, attributes);
+}
+
+// Each connection once, lower index first
+fn connections(station: &SpaceStation) -> Vec<(usize, usize)> {
+    let mut pairs: Vec<(usize, usize)> = (0..station.modules().len())
+        .flat_map(|i| station.neighbours(i).into_iter().map(move |j| (i.min(j), i.max(j))))
+        .collect();
+    pairs.sort_unstable();
+    pairs.dedup();
+    pairs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn graphviz_marks_breached_and_unpowered_modules() {
+        let mut station = SpaceStation::create_default_layout();
+        station.module_mut(1).unwrap().atmosphere_sealed = false;
+        station.module_mut(2).unwrap().powered = false;
+        let modules = station.export_graphviz(GraphKind::Modules);
+        assert!(modules.starts_with("graph modules {"));
+        assert!(modules.contains("m0 -- m1;"));
+        assert!(modules.contains("m1 [label=\"1: Corridor\\n100%\", color=red"));
+        assert!(modules.contains("m2 [label=\"2: Corridor\\n100%\", style=dashed]"));
+        assert!(station.export_graphviz(GraphKind::Atmosphere).contains("m1 -- space"));
+        assert!(!station.export_graphviz(GraphKind::Atmosphere).contains("m0 -- space"));
+        assert!(station.export_graphviz(GraphKind::Power).starts_with("digraph power {"));
+    }
+}
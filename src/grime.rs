@@ -0,0 +1,89 @@
+use glam::{Vec2, Vec3};
+use serde::{Deserialize, Serialize};
+
+// Cells along each side of a floor mask
+pub const MASK_SIZE: usize = 16;
+// Left on the floor per metre walked over it, and on a handle or switch
+// each time it's used
+pub const TREAD_PER_METRE: f32 = 0.01;
+pub const TOUCH: f32 = 0.02;
+// Taken off every surface per second of cleaning at full effectiveness
+pub const CLEAN_RATE: f32 = 0.005;
+// A module gets a crew member cleaning it once its worst patch is this bad,
+// and they keep at it until everything's under CLEAN
+pub const NEEDS_CLEANING: f32 = 0.5;
+pub const CLEAN: f32 = 0.05;
+// What it looks like, and how much of a surface's colour it covers at worst
+const GRIME_COLOR: Vec3 = Vec3::new(0.2, 0.17, 0.13);
+const MAX_COVER: f32 = 0.6;
+
+// How worn-in a module's floor is where people walk, as a low-res mask
+// over its footprint, 0 clean to 1 filthy. Empty until first walked on.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct GrimeMask {
+    cells: Vec<f32>,
+}
+
+impl GrimeMask {
+    // Works grime into the cell at `uv` (0 to 1 across the footprint), and
+    // half as much into the cells beside it
+    pub fn paint(&mut self, uv: Vec2, amount: f32) {
+        let Some((x, z)) = cell_at(uv) else {
+            return;
+        };
+        if self.cells.is_empty() {
+            self.cells = vec![0.0; MASK_SIZE * MASK_SIZE];
+        }
+        let (x, z) = (x as i32, z as i32);
+        for (dx, dz, share) in [(0, 0, 1.0), (1, 0, 0.5), (-1, 0, 0.5), (0, 1, 0.5), (0, -1, 0.5)] {
+            let (cx, cz) = (x + dx, z + dz);
+            if (0..MASK_SIZE as i32).contains(&cx) && (0..MASK_SIZE as i32).contains(&cz) {
+                let cell = &mut self.cells[cz as usize * MASK_SIZE + cx as usize];
+                *cell = (*cell + amount * share).min(1.0);
+            }
+        }
+    }
+
+    pub fn cell(&self, x: usize, z: usize) -> f32 {
+        self.cells.get(z * MASK_SIZE + x).copied().unwrap_or(0.0)
+    }
+
+    pub fn at(&self, uv: Vec2) -> f32 {
+        cell_at(uv).map_or(0.0, |(x, z)| self.cell(x, z))
+    }
+
+    pub fn worst(&self) -> f32 {
+        self.cells.iter().copied().fold(0.0, f32::max)
+    }
+
+    // Takes `amount` off every cell, and empties the mask once it's spotless
+    pub fn scrub(&mut self, amount: f32) {
+        for cell in &mut self.cells {
+            *cell = (*cell - amount).max(0.0);
+        }
+        if self.cells.iter().all(|&cell| cell == 0.0) {
+            self.cells.clear();
+        }
+    }
+
+    // Each dirty cell's middle, in uv, with how dirty it is
+    pub fn dirty_cells(&self) -> impl Iterator<Item = (Vec2, f32)> + '_ {
+        self.cells.iter().enumerate().filter(|(_, &grime)| grime > 0.0).map(|(i, &grime)| {
+            let (x, z) = (i % MASK_SIZE, i / MASK_SIZE);
+            (Vec2::new(x as f32 + 0.5, z as f32 + 0.5) / MASK_SIZE as f32, grime)
+        })
+    }
+}
+
+fn cell_at(uv: Vec2) -> Option<(usize, usize)> {
+    if !(0.0..=1.0).contains(&uv.x) || !(0.0..=1.0).contains(&uv.y) {
+        return None;
+    }
+    let cell = (uv * MASK_SIZE as f32).floor().min(Vec2::splat((MASK_SIZE - 1) as f32));
+    Some((cell.x as usize, cell.y as usize))
+}
+
+// A surface's colour with grime worked into it
+pub fn soiled(color: Vec3, grime: f32) -> Vec3 {
+    color.lerp(GRIME_COLOR, grime.clamp(0.0, 1.0) * MAX_COVER)
+}
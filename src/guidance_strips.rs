@@ -0,0 +1,91 @@
+use std::fs;
+use std::path::Path;
+use anyhow::{Context, Result};
+use glam::Vec3;
+use serde::Deserialize;
+use crate::dynamic_lights::LightSet;
+use crate::material::Material;
+use crate::module_id::{self, ModuleId};
+use crate::station::SpaceStation;
+
+// Paint colour, and the afterglow at full charge
+const STRIP_COLOR: Vec3 = Vec3::new(0.35, 0.4, 0.3);
+const STRIP_GLOW: Vec3 = Vec3::new(0.3, 0.9, 0.45);
+
+#[derive(Debug, Deserialize)]
+struct StripDefinition {
+    #[serde(deserialize_with = "module_id::layout_index")]
+    module: ModuleId,
+    from: [f32; 3],
+    to: [f32; 3],
+}
+
+#[derive(Debug, Deserialize)]
+struct StripFile {
+    #[serde(rename = "strip", default)]
+    strips: Vec<StripDefinition>,
+}
+
+// A photoluminescent guidance strip painted along the floor
+#[derive(Debug, Clone)]
+pub struct GuidanceStrip {
+    pub module: ModuleId,
+    pub from: Vec3,
+    pub to: Vec3,
+    pub material: Material,
+}
+
+impl GuidanceStrip {
+    pub fn midpoint(&self) -> Vec3 {
+        (self.from + self.to) * 0.5
+    }
+}
+
+// Floor strips that soak up the cabin lights and keep glowing for a few
+// minutes after they fail, so a blacked-out module can still be crossed
+#[derive(Debug, Default)]
+pub struct GuidanceStrips {
+    strips: Vec<GuidanceStrip>,
+}
+
+impl GuidanceStrips {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P, station: &SpaceStation) -> Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("failed to read guidance strips from {}", path.display()))?;
+        let file: StripFile = toml::from_str(&source).context("invalid guidance strip definitions")?;
+
+        let mut strips = Self::new();
+        for strip in file.strips {
+            let Some(module) = station.module_index(strip.module).map(|index| &station.modules()[index]) else {
+                eprintln!("Warning: guidance strip is in missing module {:?}", strip.module);
+                continue;
+            };
+            let matrix = module.transform.matrix();
+            strips.strips.push(GuidanceStrip {
+                module: strip.module,
+                from: matrix.transform_point3(Vec3::from(strip.from)),
+                to: matrix.transform_point3(Vec3::from(strip.to)),
+                material: Material::create_photoluminescent(STRIP_COLOR, STRIP_GLOW),
+            });
+        }
+        Ok(strips)
+    }
+
+    pub fn strips(&self) -> &[GuidanceStrip] {
+        &self.strips
+    }
+
+    // Charges or drains each strip by the light reaching it this frame
+    pub fn update(&mut self, lights: &LightSet, delta_time: f32) {
+        for strip in &mut self.strips {
+            let light = lights.illuminate(strip.midpoint(), Vec3::Y);
+            let level = (light.x + light.y + light.z) / 3.0;
+            strip.material.update_emissive(level, delta_time);
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use serde::{Deserialize, Serialize};
+use crate::config::{Config, ParticleLimits};
+
+// The Steam Deck's panel, which the handheld preset is tuned for
+pub const HANDHELD_SCREEN: (i32, i32) = (1280, 800);
+// The HUD and menus are laid out for at least this much room, so the UI is
+// never scaled up past the point where a window this size would no longer fit
+pub const MIN_UI_SIZE: (i32, i32) = (800, 600);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum HandheldMode {
+    // On when the game looks like it's running on a Steam Deck
+    #[default]
+    Auto,
+    On,
+    Off,
+}
+
+// How much of the projected light and shadow gets drawn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShadowQuality {
+    // No sunlight patches or light pools
+    Off,
+    // Sunlight comes through the window as one patch, without the frame's shadow
+    Low,
+    High,
+}
+
+// What the renderer and HUD actually use, after the preset is applied
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisplaySettings {
+    pub handheld: bool,
+    // Fraction of the window's resolution the 3D scene is drawn at
+    pub render_scale: f32,
+    pub ui_scale: f32,
+    pub shadows: ShadowQuality,
+    pub particles: ParticleLimits,
+}
+
+impl DisplaySettings {
+    pub fn resolve(config: &Config, handheld: bool, screen: (i32, i32)) -> Self {
+        if handheld {
+            return Self {
+                handheld,
+                render_scale: 0.75,
+                ui_scale: fit_ui_scale(screen),
+                shadows: ShadowQuality::Low,
+                particles: ParticleLimits {
+                    per_emitter: 40,
+                    max_emitters: 12,
+                },
+            };
+        }
+        let graphics = &config.graphics;
+        Self {
+            handheld,
+            render_scale: graphics.render_scale,
+            // 0 asks for whatever fits the screen
+            ui_scale: if graphics.ui_scale > 0.0 { graphics.ui_scale } else { fit_ui_scale(screen) },
+            shadows: graphics.shadows,
+            particles: config.particles.clone(),
+        }
+    }
+
+    // Size of the offscreen target the scene is drawn into
+    pub fn scene_size(&self, screen: (i32, i32)) -> (i32, i32) {
+        let scale = |size: i32| ((size as f32 * self.render_scale).round() as i32).max(1);
+        (scale(screen.0), scale(screen.1))
+    }
+
+    // Screen size in HUD units; the HUD is drawn zoomed in by `ui_scale`
+    pub fn ui_size(&self, screen: (i32, i32)) -> (i32, i32) {
+        ((screen.0 as f32 / self.ui_scale) as i32, (screen.1 as f32 / self.ui_scale) as i32)
+    }
+}
+
+// The largest scale that still leaves the HUD MIN_UI_SIZE to lay out in,
+// never below 1. On the Deck's 1280x800 that's 4/3, so 20px text comes out
+// at about 27px and menus still fit in the 960x600 that's left.
+pub fn fit_ui_scale(screen: (i32, i32)) -> f32 {
+    let x = screen.0 as f32 / MIN_UI_SIZE.0 as f32;
+    let y = screen.1 as f32 / MIN_UI_SIZE.1 as f32;
+    x.min(y).max(1.0)
+}
+
+// Steam sets `SteamDeck=1` for games it starts on the Deck; failing that, a
+// monitor with the Deck's resolution is a good enough guess
+pub fn detect(steam_deck_env: Option<&str>, monitor: (i32, i32)) -> bool {
+    steam_deck_env == Some("1") || monitor == HANDHELD_SCREEN
+}
+
+pub fn enabled(mode: HandheldMode, steam_deck_env: Option<&str>, monitor: (i32, i32)) -> bool {
+    match mode {
+        HandheldMode::Auto => detect(steam_deck_env, monitor),
+        HandheldMode::On => true,
+        HandheldMode::Off => false,
+    }
+}
@@ -0,0 +1,178 @@
+use glam::Vec3;
+use crate::config::HapticsConfig;
+use crate::events::StationEvent;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HapticCategory {
+    Door,
+    Power,
+    Impact,
+    Heartbeat,
+}
+
+// One burst of the two motors: the heavy low-frequency one and the light
+// high-frequency one, each 0..1
+#[derive(Debug, Clone, Copy)]
+pub struct Pulse {
+    pub start: f32,
+    pub duration: f32,
+    pub low: f32,
+    pub high: f32,
+}
+
+const fn pulse(start: f32, duration: f32, low: f32, high: f32) -> Pulse {
+    Pulse { start, duration, low, high }
+}
+
+// A short solid knock
+pub const DOOR_THUNK: &[Pulse] = &[pulse(0.0, 0.12, 0.8, 0.2)];
+// A buzz that swells and cuts out
+pub const REACTOR_SURGE: &[Pulse] = &[pulse(0.0, 0.3, 0.15, 0.4), pulse(0.3, 0.5, 0.35, 0.8)];
+// A hard hit with a rumbling tail
+pub const IMPACT: &[Pulse] = &[pulse(0.0, 0.2, 1.0, 0.6), pulse(0.2, 0.6, 0.4, 0.1)];
+// Lub-dub
+pub const HEARTBEAT: &[Pulse] = &[pulse(0.0, 0.08, 0.6, 0.0), pulse(0.18, 0.08, 0.4, 0.0)];
+
+// Seconds between beats at the first sign of trouble and at empty
+const HEARTBEAT_SLOW: f32 = 1.1;
+const HEARTBEAT_FAST: f32 = 0.55;
+// Oxygen fraction the heartbeat starts at
+pub const HEARTBEAT_OXYGEN: f32 = 0.25;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Rumble {
+    pub low: f32,
+    pub high: f32,
+}
+
+#[derive(Debug)]
+struct Playing {
+    pattern: &'static [Pulse],
+    category: HapticCategory,
+    strength: f32,
+    elapsed: f32,
+}
+
+impl Playing {
+    fn length(&self) -> f32 {
+        self.pattern.iter().map(|p| p.start + p.duration).fold(0.0, f32::max)
+    }
+}
+
+// Turns what happens on the station into controller rumble. Events far
+// from the player are felt less, each category has its own strength, and
+// the master slider scales the lot (0 turns rumble off entirely).
+#[derive(Debug)]
+pub struct Haptics {
+    config: HapticsConfig,
+    playing: Vec<Playing>,
+    // 0 when the player has enough air, rising to 1 as it runs out
+    heartbeat: f32,
+    until_beat: f32,
+}
+
+impl Haptics {
+    pub fn new(config: HapticsConfig) -> Self {
+        Self {
+            config,
+            playing: Vec::new(),
+            heartbeat: 0.0,
+            until_beat: 0.0,
+        }
+    }
+
+    pub fn set_config(&mut self, config: HapticsConfig) {
+        self.config = config;
+    }
+
+    pub fn play(&mut self, category: HapticCategory, pattern: &'static [Pulse], strength: f32) {
+        if strength <= 0.0 {
+            return;
+        }
+        self.playing.push(Playing {
+            pattern,
+            category,
+            strength: strength.min(1.0),
+            elapsed: 0.0,
+        });
+    }
+
+    // Plays a pattern for something happening at `source`, fading out to
+    // nothing `radius` away from the listener
+    pub fn play_at(
+        &mut self,
+        category: HapticCategory,
+        pattern: &'static [Pulse],
+        source: Vec3,
+        listener: Vec3,
+        radius: f32,
+    ) {
+        let falloff = (1.0 - listener.distance(source) / radius).clamp(0.0, 1.0);
+        self.play(category, pattern, falloff);
+    }
+
+    pub fn handle_event(&mut self, event: &StationEvent, listener: Vec3) {
+        let (category, pattern, radius) = match event {
+            StationEvent::DoorLocked { .. } => (HapticCategory::Door, DOOR_THUNK, 6.0),
+            StationEvent::HullBreach { .. } => (HapticCategory::Impact, IMPACT, 30.0),
+            StationEvent::Explosion { magnitude, .. } => (HapticCategory::Impact, IMPACT, 20.0 * magnitude.max(0.5)),
+            StationEvent::ModuleDamaged { .. } => (HapticCategory::Impact, IMPACT, 15.0),
+            StationEvent::ConnectionFailed { .. } => (HapticCategory::Impact, IMPACT, 40.0),
+            // The whole station feels the reactor struggling
+            StationEvent::PowerDeficit { .. } => {
+                self.play(HapticCategory::Power, REACTOR_SURGE, 1.0);
+                return;
+            }
+            _ => return,
+        };
+        if let Some(position) = event.position() {
+            self.play_at(category, pattern, position, listener, radius);
+        }
+    }
+
+    // The oxygen fraction the player is breathing from; below
+    // HEARTBEAT_OXYGEN a heartbeat starts and quickens as it falls
+    pub fn set_oxygen(&mut self, oxygen: f32) {
+        self.heartbeat = (1.0 - oxygen / HEARTBEAT_OXYGEN).clamp(0.0, 1.0);
+    }
+
+    fn category_scale(&self, category: HapticCategory) -> f32 {
+        match category {
+            HapticCategory::Door => self.config.door,
+            HapticCategory::Power => self.config.power,
+            HapticCategory::Impact => self.config.impact,
+            HapticCategory::Heartbeat => self.config.heartbeat,
+        }
+    }
+
+    // Motor levels for this frame
+    pub fn update(&mut self, delta_time: f32) -> Rumble {
+        if self.heartbeat > 0.0 {
+            self.until_beat -= delta_time;
+            if self.until_beat <= 0.0 {
+                self.until_beat = HEARTBEAT_SLOW + (HEARTBEAT_FAST - HEARTBEAT_SLOW) * self.heartbeat;
+                self.play(HapticCategory::Heartbeat, HEARTBEAT, 0.5 + 0.5 * self.heartbeat);
+            }
+        } else {
+            self.until_beat = 0.0;
+        }
+
+        let mut rumble = Rumble::default();
+        for playing in &mut self.playing {
+            playing.elapsed += delta_time;
+        }
+        self.playing.retain(|playing| playing.elapsed < playing.length());
+        for playing in &self.playing {
+            let scale = playing.strength * self.category_scale(playing.category) * self.config.master;
+            for pulse in playing.pattern {
+                if (pulse.start..pulse.start + pulse.duration).contains(&playing.elapsed) {
+                    rumble.low += pulse.low * scale;
+                    rumble.high += pulse.high * scale;
+                }
+            }
+        }
+        rumble.low = rumble.low.min(1.0);
+        rumble.high = rumble.high.min(1.0);
+        rumble
+    }
+}
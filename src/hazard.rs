@@ -0,0 +1,157 @@
+use glam::Vec3;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use crate::module_id::ModuleId;
+use crate::rng::SimRng;
+use crate::station::{ElementState, InteractionType, StationModule};
+use crate::tunables::StationTunables;
+
+// Intensity a fire starts at, whether lit by a fault or spread through a door
+const IGNITION_INTENSITY: f32 = 0.1;
+
+// A fire burning in a module, 0 out to 1 fully involved. It feeds on the
+// module's oxygen, heats its air and eats at its hull and wiring.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fire {
+    pub module: ModuleId,
+    // Where it started: the faulty element, or the door it came through
+    pub position: Vec3,
+    pub intensity: f32,
+}
+
+// What `update_fires` changed, for the station to report, by where the
+// module is in the list
+#[derive(Debug, Clone, PartialEq)]
+pub enum FireChange {
+    Started { module: usize, position: Vec3 },
+    Out { module: usize, position: Vec3 },
+}
+
+// Electrical faults and fires. A malfunctioning element may arc and light a
+// fire; fires grow while there's oxygen to feed them and die back without
+// it, so venting a module puts one out, as does its suppression system.
+// Through an open door a fire can catch in the next module.
+//
+// `doors` is each connection's modules, as indices, and how far open its
+// door is.
+pub fn update_fires(
+    fires: &mut Vec<Fire>,
+    modules: &mut [StationModule],
+    doors: &[(usize, usize, f32)],
+    tunables: &StationTunables,
+    rng: &mut SimRng,
+    delta_time: f32,
+) -> Vec<FireChange> {
+    let mut changes = Vec::new();
+    let burning = |fires: &[Fire], module: ModuleId| fires.iter().any(|fire| fire.module == module);
+
+    // Faults arcing over
+    for (i, module) in modules.iter().enumerate() {
+        if burning(fires, module.id) || module.atmosphere.oxygen_level() < tunables.fire_min_oxygen {
+            continue;
+        }
+        let faults = module
+            .interactive_elements
+            .iter()
+            .filter(|element| element.state == ElementState::Malfunction);
+        for element in faults {
+            if rng.gen::<f32>() < tunables.fire_ignition_chance * delta_time {
+                let position = module.transform.matrix().transform_point3(element.position);
+                fires.push(Fire { module: module.id, position, intensity: IGNITION_INTENSITY });
+                changes.push(FireChange::Started { module: i, position });
+                break;
+            }
+        }
+    }
+
+    for fire in fires.iter_mut() {
+        let Some(module) = modules.iter_mut().find(|module| module.id == fire.module) else {
+            fire.intensity = 0.0;
+            continue;
+        };
+        let volume = module.module_type.volume();
+        // Grows with oxygen to spare, starves below the minimum
+        let oxygen = module.atmosphere.oxygen_level();
+        fire.intensity += tunables.fire_growth * (oxygen - tunables.fire_min_oxygen) * delta_time;
+        if suppressing(module) {
+            fire.intensity -= tunables.fire_suppression_rate * delta_time;
+        }
+        fire.intensity = fire.intensity.min(1.0);
+        if fire.intensity <= 0.0 {
+            continue;
+        }
+
+        // Combustion turns oxygen into CO2, much as breathing does
+        let air = &mut module.atmosphere;
+        air.breathe(fire.intensity * tunables.fire_oxygen_use * delta_time / volume);
+        air.temperature += fire.intensity * tunables.fire_heat * delta_time / volume;
+        let damage = fire.intensity * tunables.fire_damage_rate * delta_time;
+        module.structural_integrity = (module.structural_integrity - damage).max(0.0);
+        module.wiring = (module.wiring - damage).max(0.0);
+    }
+
+    // Catching through open doors
+    let mut spread = Vec::new();
+    for fire in fires.iter() {
+        for &(a, b, openness) in doors {
+            let other = match fire.module {
+                module if module == modules[a].id => b,
+                module if module == modules[b].id => a,
+                _ => continue,
+            };
+            let id = modules[other].id;
+            if openness <= 0.0 || burning(fires, id) || spread.iter().any(|fire: &Fire| fire.module == id) {
+                continue;
+            }
+            if rng.gen::<f32>() < fire.intensity * openness * tunables.fire_spread_chance * delta_time {
+                let position = (modules[a].transform.position + modules[b].transform.position) * 0.5;
+                spread.push(Fire { module: id, position, intensity: IGNITION_INTENSITY });
+                changes.push(FireChange::Started { module: other, position });
+            }
+        }
+    }
+
+    fires.retain(|fire| {
+        let out = fire.intensity <= 0.0;
+        if let Some(module) = modules.iter().position(|module| module.id == fire.module).filter(|_| out) {
+            changes.push(FireChange::Out { module, position: fire.position });
+        }
+        !out
+    });
+    fires.extend(spread);
+    changes
+}
+
+// Whether a module's suppression system is discharging
+fn suppressing(module: &StationModule) -> bool {
+    module.interactive_elements.iter().any(|element| {
+        element.element_type == InteractionType::FireSuppression && element.state == ElementState::Active
+    })
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FireSnapshot {
+    pub module: ModuleId,
+    pub position: [f32; 3],
+    pub intensity: f32,
+}
+
+impl From<&Fire> for FireSnapshot {
+    fn from(fire: &Fire) -> Self {
+        Self {
+            module: fire.module,
+            position: fire.position.to_array(),
+            intensity: fire.intensity,
+        }
+    }
+}
+
+impl From<&FireSnapshot> for Fire {
+    fn from(saved: &FireSnapshot) -> Self {
+        Self {
+            module: saved.module,
+            position: Vec3::from(saved.position),
+            intensity: saved.intensity,
+        }
+    }
+}
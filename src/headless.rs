@@ -0,0 +1,192 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use crate::rng;
+use crate::rotation;
+use crate::save::SaveGame;
+use crate::station::{SpaceStation, StationSnapshot, StationTelemetry};
+use crate::stepping::STEP_DT;
+use crate::tunables::Tunables;
+
+const USAGE: &str = "usage: space_station_3d --headless [--ticks N] [--sample-every N] [--seed N] \
+[--tunables FILE] [--load SAVE] [--damage MODULE:AMOUNT]... [--out FILE] \
+[--rotation FILE [--rounds N] [--stats FILE] [--votes FILE]]";
+
+// Command line for `--headless`
+#[derive(Debug, Clone)]
+pub struct HeadlessOptions {
+    pub ticks: u64,
+    // Record telemetry every this many ticks; 0 records only the end state
+    pub sample_every: u64,
+    // Runs with the same seed and options produce the same report. Without
+    // one the save's seed is kept, or 0 for the default layout.
+    pub seed: Option<u64>,
+    pub tunables: Option<PathBuf>,
+    // Start from a save instead of the default layout
+    pub save: Option<PathBuf>,
+    // Applied before the first tick, to exercise breach and leak rules
+    pub damage: Vec<(usize, f32)>,
+    // JSON goes to stdout without one
+    pub output: Option<PathBuf>,
+    // Run as a standing server playing the scenarios in this file in turn,
+    // for this many rounds or with 0 until stopped, instead of one run
+    pub rotation: Option<PathBuf>,
+    pub rounds: u64,
+    // Kept across rounds and restarts
+    pub stats: Option<PathBuf>,
+    // Read between rounds for votes on the next scenario
+    pub votes: Option<PathBuf>,
+}
+
+impl HeadlessOptions {
+    // None unless `--headless` is among the arguments
+    pub fn from_args<I: IntoIterator<Item = String>>(args: I) -> Result<Option<Self>> {
+        let args: Vec<String> = args.into_iter().collect();
+        if !args.iter().any(|arg| arg == "--headless") {
+            return Ok(None);
+        }
+
+        let mut options = Self {
+            ticks: 3600,
+            sample_every: 60,
+            seed: None,
+            tunables: None,
+            save: None,
+            damage: Vec::new(),
+            output: None,
+            rotation: None,
+            rounds: 0,
+            stats: None,
+            votes: None,
+        };
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let mut value = || args.next().with_context(|| format!("{} needs a value\n{}", arg, USAGE));
+            match arg.as_str() {
+                "--headless" => {}
+                "--seed" => options.seed = Some(rng::parse_seed(&value()?)?),
+                "--ticks" => options.ticks = value()?.parse().context("--ticks must be a whole number")?,
+                "--sample-every" => {
+                    options.sample_every = value()?.parse().context("--sample-every must be a whole number")?
+                }
+                "--tunables" => options.tunables = Some(value()?.into()),
+                "--load" => options.save = Some(value()?.into()),
+                "--damage" => {
+                    let spec = value()?;
+                    let (module, amount) = spec
+                        .split_once(':')
+                        .with_context(|| format!("--damage expects MODULE:AMOUNT, got {}", spec))?;
+                    options.damage.push((
+                        module.parse().context("--damage module must be an index")?,
+                        amount.parse().context("--damage amount must be a number")?,
+                    ));
+                }
+                "--out" => options.output = Some(value()?.into()),
+                "--rotation" => options.rotation = Some(value()?.into()),
+                "--rounds" => options.rounds = value()?.parse().context("--rounds must be a whole number")?,
+                "--stats" => options.stats = Some(value()?.into()),
+                "--votes" => options.votes = Some(value()?.into()),
+                other => bail!("unknown argument {}\n{}", other, USAGE),
+            }
+        }
+        Ok(Some(options))
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TelemetrySample {
+    pub tick: u64,
+    pub telemetry: StationTelemetry,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct RecordedEvent {
+    pub tick: u64,
+    pub kind: &'static str,
+    pub description: String,
+}
+
+// Everything a headless run produces, written out as JSON
+#[derive(Debug, Clone, Serialize)]
+pub struct HeadlessReport {
+    pub seed: u64,
+    pub ticks: u64,
+    pub tick_seconds: f32,
+    pub samples: Vec<TelemetrySample>,
+    pub events: Vec<RecordedEvent>,
+    pub final_telemetry: StationTelemetry,
+    pub final_state: StationSnapshot,
+}
+
+// Library entry point: steps the station at the fixed sim rate, recording
+// telemetry and every event raised along the way
+pub fn simulate(station: &mut SpaceStation, ticks: u64, sample_every: u64) -> HeadlessReport {
+    let mut samples = Vec::new();
+    let mut events = Vec::new();
+    for tick in 1..=ticks {
+        station.update(STEP_DT);
+        events.extend(station.drain_events().into_iter().map(|event| RecordedEvent {
+            tick,
+            kind: event.kind(),
+            description: event.describe(),
+        }));
+        if sample_every > 0 && tick % sample_every == 0 {
+            samples.push(TelemetrySample {
+                tick,
+                telemetry: station.telemetry(),
+            });
+        }
+    }
+    HeadlessReport {
+        seed: station.seed(),
+        ticks,
+        tick_seconds: STEP_DT,
+        samples,
+        events,
+        final_telemetry: station.telemetry(),
+        final_state: station.snapshot(),
+    }
+}
+
+// A save or the default layout, set up to run
+pub fn build_station(save: Option<&Path>, tunables: Option<&Path>, seed: Option<u64>, damage: &[(usize, f32)]) -> Result<SpaceStation> {
+    let mut station = match save {
+        Some(path) => SpaceStation::from_snapshot(&SaveGame::read(path)?.station),
+        None => SpaceStation::create_default_layout(),
+    };
+    if let Some(path) = tunables {
+        station.set_tunables(&Tunables::load(path)?);
+    }
+    if let Some(seed) = seed {
+        station.reseed(seed);
+    }
+    for &(module, amount) in damage {
+        if !station.damage_module(module, amount) {
+            bail!("--damage: there is no module {}", module);
+        }
+    }
+    Ok(station)
+}
+
+// `--headless`: builds the station from the options, runs it and writes the report
+pub fn run(options: &HeadlessOptions) -> Result<()> {
+    if let Some(path) = &options.rotation {
+        let seed = options.seed.unwrap_or(0);
+        return rotation::serve(path, options.rounds, seed, options.stats.as_deref(), options.votes.as_deref());
+    }
+    let mut station = build_station(
+        options.save.as_deref(),
+        options.tunables.as_deref(),
+        options.seed,
+        &options.damage,
+    )?;
+
+    let report = simulate(&mut station, options.ticks, options.sample_every);
+    let json = serde_json::to_string_pretty(&report).context("failed to serialize report")?;
+    match &options.output {
+        Some(path) => fs::write(path, json).with_context(|| format!("failed to write {}", path.display()))?,
+        None => println!("{}", json),
+    }
+    Ok(())
+}
@@ -0,0 +1,117 @@
+use std::collections::{HashSet, VecDeque};
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use crate::events::StationEvent;
+
+// A tip shown the first time its trigger fires. `trigger` matches
+// `StationEvent::kind()` or any name passed to `HintEngine::trigger`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct HintDefinition {
+    pub id: String,
+    pub trigger: String,
+    pub title: String,
+    pub text: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct HintFile {
+    #[serde(rename = "hint", default)]
+    hints: Vec<HintDefinition>,
+}
+
+// Persisted "don't show again" choices
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct HintState {
+    #[serde(default)]
+    suppressed: Vec<String>,
+}
+
+#[derive(Debug)]
+pub struct HintEngine {
+    pub enabled: bool,
+    definitions: Vec<HintDefinition>,
+    shown: HashSet<String>,
+    suppressed: HashSet<String>,
+    queue: VecDeque<usize>,
+    state_path: Option<PathBuf>,
+}
+
+impl HintEngine {
+    pub fn from_toml(source: &str) -> Result<Self> {
+        let file: HintFile = toml::from_str(source).context("invalid hint definitions")?;
+        Ok(Self {
+            enabled: true,
+            definitions: file.hints,
+            shown: HashSet::new(),
+            suppressed: HashSet::new(),
+            queue: VecDeque::new(),
+            state_path: None,
+        })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("failed to read hints from {}", path.display()))?;
+        Self::from_toml(&source)
+    }
+
+    // Remembers dismissals in `path`, loading any earlier choices from it
+    pub fn with_state_file<P: AsRef<Path>>(mut self, path: P) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        if path.exists() {
+            let source = fs::read_to_string(&path)?;
+            let state: HintState = toml::from_str(&source).context("invalid hint state")?;
+            self.suppressed.extend(state.suppressed);
+        }
+        self.state_path = Some(path);
+        Ok(self)
+    }
+
+    pub fn handle_event(&mut self, event: &StationEvent) {
+        self.trigger(event.kind());
+    }
+
+    pub fn trigger(&mut self, trigger: &str) {
+        if !self.enabled {
+            return;
+        }
+
+        for (i, hint) in self.definitions.iter().enumerate() {
+            if hint.trigger != trigger || self.shown.contains(&hint.id) || self.suppressed.contains(&hint.id) {
+                continue;
+            }
+            self.shown.insert(hint.id.clone());
+            self.queue.push_back(i);
+        }
+    }
+
+    pub fn current(&self) -> Option<&HintDefinition> {
+        self.queue.front().map(|&i| &self.definitions[i])
+    }
+
+    pub fn dismiss(&mut self) {
+        self.queue.pop_front();
+    }
+
+    pub fn dismiss_forever(&mut self) -> Result<()> {
+        if let Some(i) = self.queue.pop_front() {
+            self.suppressed.insert(self.definitions[i].id.clone());
+            self.save_state()?;
+        }
+        Ok(())
+    }
+
+    fn save_state(&self) -> Result<()> {
+        let Some(path) = &self.state_path else {
+            return Ok(());
+        };
+
+        let mut suppressed: Vec<String> = self.suppressed.iter().cloned().collect();
+        suppressed.sort();
+        let source = toml::to_string(&HintState { suppressed })?;
+        fs::write(path, source).with_context(|| format!("failed to write {}", path.display()))
+    }
+}
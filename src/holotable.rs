@@ -0,0 +1,222 @@
+use glam::{Mat4, Vec3};
+use crate::module_id::ModuleId;
+use crate::signage::designations;
+use crate::station::{ElementState, ModuleType, SpaceStation, StationModule};
+
+// Where the hologram floats above the table, relative to the command
+// centre's floor, and how wide the whole station is drawn
+const TABLE_OFFSET: Vec3 = Vec3::new(-1.8, 0.0, -1.8);
+const TABLE_HEIGHT: f32 = 1.0;
+const HOLOGRAM_HEIGHT: f32 = 1.45;
+const HOLOGRAM_SPAN: f32 = 1.6;
+// Miniatures are small; pointing is forgiven by this much all round
+const PICK_MARGIN: f32 = 0.04;
+// How far away the table can be pointed at from
+pub const HOLOTABLE_REACH: f32 = 3.5;
+
+// What a miniature's colour says about its module
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HoloStatus {
+    Nominal,
+    // Something failed or running down, but holding air
+    Degraded,
+    Unpowered,
+    // Breached, or the air or hull is going
+    Critical,
+}
+
+impl HoloStatus {
+    pub fn of(module: &StationModule) -> Self {
+        if !module.atmosphere_sealed || module.structural_integrity < 0.5 || module.atmosphere.oxygen_level() < 0.6 {
+            HoloStatus::Critical
+        } else if !module.powered {
+            HoloStatus::Unpowered
+        } else if module.structural_integrity < 0.9
+            || module.interactive_elements.iter().any(|element| element.state == ElementState::Malfunction)
+        {
+            HoloStatus::Degraded
+        } else {
+            HoloStatus::Nominal
+        }
+    }
+
+    pub fn color(self) -> Vec3 {
+        match self {
+            HoloStatus::Nominal => Vec3::new(0.3, 0.8, 1.0),
+            HoloStatus::Degraded => Vec3::new(1.0, 0.75, 0.2),
+            HoloStatus::Unpowered => Vec3::new(0.35, 0.4, 0.5),
+            HoloStatus::Critical => Vec3::new(1.0, 0.2, 0.15),
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            HoloStatus::Nominal => "NOMINAL",
+            HoloStatus::Degraded => "DEGRADED",
+            HoloStatus::Unpowered => "NO POWER",
+            HoloStatus::Critical => "CRITICAL",
+        }
+    }
+}
+
+// One module's miniature: its mesh goes through `matrix`
+#[derive(Debug, Clone)]
+pub struct Miniature {
+    pub module: usize,
+    pub matrix: Mat4,
+    pub status: HoloStatus,
+}
+
+// A table in the command centre with the whole station floating over it in
+// miniature, coloured by status. Pointing at a miniature and clicking picks
+// its module, and the table's side screens show that module's details.
+#[derive(Debug)]
+pub struct Holotable {
+    // Floor position of the table's centre
+    pub position: Vec3,
+    pub selected: Option<ModuleId>,
+}
+
+impl Holotable {
+    // At the first command centre, if there is one
+    pub fn place(station: &SpaceStation) -> Option<Self> {
+        let module = station.modules().iter().find(|module| module.module_type == ModuleType::CommandCenter)?;
+        Some(Self { position: module.transform.position + TABLE_OFFSET, selected: None })
+    }
+
+    pub fn top(&self) -> Vec3 {
+        self.position + Vec3::Y * TABLE_HEIGHT
+    }
+
+    // Station space to hologram space: the station's middle over the table,
+    // scaled so its widest extent fits the span
+    pub fn projection(&self, station: &SpaceStation) -> Mat4 {
+        let (min, max) = station
+            .modules()
+            .iter()
+            .map(StationModule::bounds)
+            .fold((Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)), |(lo, hi), (min, max)| (lo.min(min), hi.max(max)));
+        if min.x > max.x {
+            return Mat4::IDENTITY;
+        }
+        let size = max - min;
+        let scale = HOLOGRAM_SPAN / size.x.max(size.z).max(1.0);
+        let center = (min + max) * 0.5;
+        Mat4::from_translation(self.position + Vec3::Y * HOLOGRAM_HEIGHT)
+            * Mat4::from_scale(Vec3::splat(scale))
+            * Mat4::from_translation(-center)
+    }
+
+    pub fn miniatures(&self, station: &SpaceStation) -> Vec<Miniature> {
+        let projection = self.projection(station);
+        station
+            .modules()
+            .iter()
+            .enumerate()
+            .map(|(module, station_module)| Miniature {
+                module,
+                matrix: projection * station_module.transform.matrix(),
+                status: HoloStatus::of(station_module),
+            })
+            .collect()
+    }
+
+    // The miniature a ray from `origin` along `direction` hits first
+    pub fn pick(&self, station: &SpaceStation, origin: Vec3, direction: Vec3) -> Option<usize> {
+        let projection = self.projection(station);
+        let direction = direction.try_normalize()?;
+        station
+            .modules()
+            .iter()
+            .enumerate()
+            .filter_map(|(i, module)| {
+                let (min, max) = module.bounds();
+                let (min, max) = (projection.transform_point3(min), projection.transform_point3(max));
+                let distance = ray_box(origin, direction, min - PICK_MARGIN, max + PICK_MARGIN)?;
+                (distance <= HOLOTABLE_REACH).then_some((i, distance))
+            })
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+    }
+
+    // Picking the selected module again puts the screens back to the overview
+    pub fn select(&mut self, module: ModuleId) {
+        self.selected = if self.selected == Some(module) { None } else { Some(module) };
+    }
+
+    // The left screen: the station at a glance
+    pub fn overview_lines(&self, station: &SpaceStation) -> Vec<String> {
+        let modules = station.modules();
+        let count = |status| modules.iter().filter(|module| HoloStatus::of(module) == status).count();
+        vec![
+            format!("STATION  {} modules", modules.len()),
+            format!("{:<9}{:>3}", HoloStatus::Nominal.label(), count(HoloStatus::Nominal)),
+            format!("{:<9}{:>3}", HoloStatus::Degraded.label(), count(HoloStatus::Degraded)),
+            format!("{:<9}{:>3}", HoloStatus::Unpowered.label(), count(HoloStatus::Unpowered)),
+            format!("{:<9}{:>3}", HoloStatus::Critical.label(), count(HoloStatus::Critical)),
+        ]
+    }
+
+    // The right screen: the selected module's details, if one's selected
+    pub fn detail_lines(&self, station: &SpaceStation) -> Option<Vec<String>> {
+        let index = station.module_index(self.selected?)?;
+        let module = &station.modules()[index];
+        let failed = module
+            .interactive_elements
+            .iter()
+            .filter(|element| element.state == ElementState::Malfunction)
+            .count();
+        Some(vec![
+            format!("{}  {}", designations(station)[index], HoloStatus::of(module).label()),
+            format!("Hull      {:>5.0}%", module.structural_integrity * 100.0),
+            format!("O2        {:>5.1}%", module.atmosphere.oxygen_level() * 100.0),
+            format!("Pressure  {:>5.2} atm", module.atmosphere.pressure()),
+            format!("Temp      {:>5.1} C", module.atmosphere.temperature - 273.15),
+            format!("Power     {:>5.1} kW{}", module.power_draw(), if module.powered { "" } else { "  SHED" }),
+            format!("Crew      {:>5}", module.crew),
+            format!("Failures  {:>5}", failed),
+        ])
+    }
+}
+
+// Distance along a ray to where it enters a box, if it does
+fn ray_box(origin: Vec3, direction: Vec3, min: Vec3, max: Vec3) -> Option<f32> {
+    let inverse = direction.recip();
+    let a = (min - origin) * inverse;
+    let b = (max - origin) * inverse;
+    let near = a.min(b).max_element();
+    let far = a.max(b).min_element();
+    (far >= near.max(0.0)).then_some(near.max(0.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::procgen::LayoutParams;
+
+    #[test]
+    fn holotable_miniatures_show_status_and_pick_modules() {
+        let mut station = SpaceStation::generate_procedural(3, &LayoutParams::default());
+        let mut table = Holotable::place(&station).unwrap();
+        assert!(table.miniatures(&station).iter().all(|m| m.status == HoloStatus::Nominal));
+
+        let lab = station.modules().iter().position(|m| m.module_type == ModuleType::Laboratory).unwrap();
+        station.module_mut(lab).unwrap().atmosphere_sealed = false;
+        assert_eq!(table.miniatures(&station)[lab].status, HoloStatus::Critical);
+        station.module_mut(lab).unwrap().atmosphere_sealed = true;
+        station.module_mut(lab).unwrap().powered = false;
+        assert_eq!(table.miniatures(&station)[lab].status, HoloStatus::Unpowered);
+
+        // Pointing straight down onto a miniature picks its module
+        let over = table.projection(&station).transform_point3(station.modules()[lab].transform.position) + Vec3::Y;
+        assert_eq!(table.pick(&station, over, Vec3::NEG_Y), Some(lab));
+        assert_eq!(table.pick(&station, over, Vec3::Y), None);
+        assert!(table.detail_lines(&station).is_none());
+        table.select(station.modules()[lab].id);
+        let details = table.detail_lines(&station).unwrap();
+        assert!(details[0].starts_with("LAB-1") && details[0].ends_with("NO POWER"));
+        // Picking it again clears the screen
+        table.select(station.modules()[lab].id);
+        assert!(table.selected.is_none());
+    }
+}
@@ -0,0 +1,319 @@
+use std::fs;
+use std::path::Path;
+use anyhow::{Context, Result};
+use rand::Rng;
+use serde::Deserialize;
+use crate::director::{Cue, Director, Pacing};
+use crate::events::StationEvent;
+use crate::module_id::ModuleId;
+use crate::objectives::{Condition, ObjectiveDefinition, ObjectiveStatus, Objectives};
+use crate::station::{ElementState, SpaceStation};
+
+// Hull a micrometeorite knocks off, and wiring a power surge burns out, at
+// difficulty 1
+const STRIKE_DAMAGE: f32 = 0.3;
+const SURGE_DAMAGE: f32 = 0.4;
+// Flare strength at difficulty 1, 0 to 1
+const FLARE_INTENSITY: f32 = 0.5;
+// Percent a module's hull or wiring has to be brought back to
+const RESTORED: f32 = 90.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum IncidentKind {
+    Micrometeorite,
+    SolarFlare,
+    PowerSurge,
+    Malfunction,
+}
+
+impl IncidentKind {
+    pub const ALL: [IncidentKind; 4] = [
+        IncidentKind::Micrometeorite,
+        IncidentKind::SolarFlare,
+        IncidentKind::PowerSurge,
+        IncidentKind::Malfunction,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            IncidentKind::Micrometeorite => "Micrometeorite strike",
+            IncidentKind::SolarFlare => "Solar flare",
+            IncidentKind::PowerSurge => "Power surge",
+            IncidentKind::Malfunction => "Equipment malfunction",
+        }
+    }
+
+    // As typed in the console
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "micrometeorite" | "meteorite" => Some(IncidentKind::Micrometeorite),
+            "flare" | "solar_flare" => Some(IncidentKind::SolarFlare),
+            "surge" | "power_surge" => Some(IncidentKind::PowerSurge),
+            "malfunction" => Some(IncidentKind::Malfunction),
+            _ => None,
+        }
+    }
+
+    // How much it adds to the director's tension at difficulty 1, 0 to 1
+    pub fn tension(self) -> f32 {
+        match self {
+            IncidentKind::Micrometeorite => 0.45,
+            IncidentKind::SolarFlare => 0.3,
+            IncidentKind::PowerSurge => 0.3,
+            IncidentKind::Malfunction => 0.2,
+        }
+    }
+}
+
+// A kind of incident the scheduler can pick, and how likely it is against
+// the others
+#[derive(Debug, Clone, Deserialize)]
+pub struct IncidentOdds {
+    pub kind: IncidentKind,
+    #[serde(default = "default_weight")]
+    pub weight: f32,
+    // In place of the kind's own tension
+    #[serde(default)]
+    pub tension: Option<f32>,
+}
+
+fn default_weight() -> f32 {
+    1.0
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IncidentSchedule {
+    // Average seconds between incidents at difficulty 1
+    pub mean_interval: f32,
+    // Seconds of quiet before the first
+    #[serde(default)]
+    pub grace: f32,
+    // 0.5 easy to 2 hard: harder comes more often and does more damage
+    #[serde(default = "default_difficulty")]
+    pub difficulty: f32,
+    #[serde(rename = "incident", default)]
+    pub odds: Vec<IncidentOdds>,
+    #[serde(default)]
+    pub pacing: Pacing,
+    // Lines put out now and then in the quiet spells
+    #[serde(default)]
+    pub flavor: Vec<String>,
+}
+
+impl IncidentSchedule {
+    // What an incident of `kind` adds to the tension at this difficulty
+    pub fn tension(&self, kind: IncidentKind) -> f32 {
+        let base = self.odds.iter().find(|odds| odds.kind == kind).and_then(|odds| odds.tension).unwrap_or(kind.tension());
+        base * self.difficulty.max(0.0)
+    }
+}
+
+fn default_difficulty() -> f32 {
+    1.0
+}
+
+pub fn load_schedule<P: AsRef<Path>>(path: P) -> Result<IncidentSchedule> {
+    let path = path.as_ref();
+    let source = fs::read_to_string(path)
+        .with_context(|| format!("failed to read incidents from {}", path.display()))?;
+    toml::from_str(&source).context("invalid incident schedule")
+}
+
+// One under way; it's over once its objective's met
+#[derive(Debug, Clone)]
+pub struct Incident {
+    pub kind: IncidentKind,
+    pub module: Option<ModuleId>,
+    pub objective: String,
+    // Seconds since it was raised
+    pub age: f32,
+}
+
+// Incidents on the schedule's terms, paced by the director. Each sounds the
+// alarm and sets an objective to deal with it; the alarm keeps going until
+// every one is dealt with.
+#[derive(Debug)]
+pub struct Incidents {
+    schedule: IncidentSchedule,
+    director: Director,
+    raised: u32,
+    active: Vec<Incident>,
+    // Dealt with since last taken, and how long each took
+    resolved: Vec<(IncidentKind, f32)>,
+}
+
+impl Incidents {
+    pub fn new(schedule: IncidentSchedule) -> Self {
+        let director = Director::new(schedule.grace);
+        Self { schedule, director, raised: 0, active: Vec::new(), resolved: Vec::new() }
+    }
+
+    pub fn schedule(&self) -> &IncidentSchedule {
+        &self.schedule
+    }
+
+    pub fn director(&self) -> &Director {
+        &self.director
+    }
+
+    // Whether flares come from here, so the station needn't roll its own
+    pub fn directs_flares(&self) -> bool {
+        self.schedule.odds.iter().any(|odds| odds.kind == IncidentKind::SolarFlare && odds.weight > 0.0)
+    }
+
+    pub fn active(&self) -> &[Incident] {
+        &self.active
+    }
+
+    // Incidents dealt with since last asked, with how long each took
+    pub fn take_resolved(&mut self) -> Vec<(IncidentKind, f32)> {
+        std::mem::take(&mut self.resolved)
+    }
+
+    // Whether the alarm should be sounding
+    pub fn alarm(&self) -> bool {
+        !self.active.is_empty()
+    }
+
+    // Drops incidents that have been dealt with, and raises whatever the
+    // director calls for; returns what was raised
+    pub fn update(&mut self, station: &mut SpaceStation, objectives: &mut Objectives, delta_time: f32) -> Option<IncidentKind> {
+        for incident in &mut self.active {
+            incident.age += delta_time;
+        }
+        let resolved = &mut self.resolved;
+        self.active.retain(|incident| {
+            let active = objectives.status(&incident.objective) == Some(ObjectiveStatus::Active);
+            if !active {
+                resolved.push((incident.kind, incident.age));
+            }
+            active
+        });
+        match self.director.update(&self.schedule, station, self.active.len(), delta_time)? {
+            Cue::Incident(kind) => self.raise(kind, station, objectives).then_some(kind),
+            Cue::Flavor(line) => {
+                station.emit_event(StationEvent::Notice { text: self.schedule.flavor[line].clone() });
+                None
+            }
+        }
+    }
+
+    // Sets an incident off now; false if there's nothing on the station it
+    // could happen to
+    pub fn raise(&mut self, kind: IncidentKind, station: &mut SpaceStation, objectives: &mut Objectives) -> bool {
+        let difficulty = self.schedule.difficulty.max(0.0);
+        let modules = station.modules().len();
+        let (module, title, condition) = match kind {
+            IncidentKind::Micrometeorite => {
+                if modules == 0 {
+                    return false;
+                }
+                let module = station.rng().gen_range(0..modules);
+                station.damage_module(module, STRIKE_DAMAGE * difficulty);
+                let condition = Condition::ModuleIntegrityAbove { module, percent: RESTORED };
+                (Some(module), format!("Patch the hull of module {}", module), condition)
+            }
+            IncidentKind::SolarFlare => {
+                let duration = station.tunables().flare_duration;
+                station.begin_flare((FLARE_INTENSITY * difficulty).clamp(0.1, 1.0), duration);
+                (None, "Ride out the solar flare".to_string(), Condition::NoInterference)
+            }
+            IncidentKind::PowerSurge => {
+                let wired: Vec<usize> = (0..modules).filter(|&i| station.modules()[i].power_consumption > 0.0).collect();
+                if wired.is_empty() {
+                    return false;
+                }
+                let module = wired[station.rng().gen_range(0..wired.len())];
+                station.power_surge(module, SURGE_DAMAGE * difficulty);
+                let condition = Condition::WiringAbove { module, percent: RESTORED };
+                (Some(module), format!("Rewire module {}", module), condition)
+            }
+            IncidentKind::Malfunction => {
+                let working: Vec<(usize, usize)> = station
+                    .modules()
+                    .iter()
+                    .enumerate()
+                    .flat_map(|(i, module)| {
+                        module.interactive_elements.iter().enumerate().filter_map(move |(j, element)| {
+                            let wears = element.element_type.service_hours().is_some();
+                            (wears && element.state != ElementState::Malfunction).then_some((i, j))
+                        })
+                    })
+                    .collect();
+                if working.is_empty() {
+                    return false;
+                }
+                let (module, element) = working[station.rng().gen_range(0..working.len())];
+                let element_type = station.modules()[module].interactive_elements[element].element_type;
+                station.set_element_state(module, element, ElementState::Malfunction);
+                let condition = Condition::ElementWorking { module, element };
+                (Some(module), format!("Fix the {:?} in module {}", element_type, module), condition)
+            }
+        };
+
+        self.raised += 1;
+        self.director.raised(self.schedule.tension(kind));
+        let objective = format!("incident{}", self.raised);
+        objectives.add(ObjectiveDefinition {
+            id: objective.clone(),
+            title,
+            description: kind.name().to_string(),
+            condition,
+            next: None,
+            activate_on: None,
+            no_fast_travel: false,
+        });
+        let position = module.map(|module| station.modules()[module].transform.position);
+        station.emit_event(StationEvent::Incident { kind, module, position });
+        let module = module.and_then(|module| station.module_id(module));
+        self.active.push(Incident { kind, module, objective, age: 0.0 });
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use glam::Vec3;
+    use super::*;
+    use crate::repair::Component;
+
+    #[test]
+    fn incidents_sound_the_alarm_until_their_objectives_are_met() {
+        let mut station = SpaceStation::create_default_layout();
+        let mut objectives = Objectives::default();
+        let surges = vec![IncidentOdds { kind: IncidentKind::PowerSurge, weight: 1.0, tension: None }];
+        let schedule = IncidentSchedule {
+            mean_interval: 60.0,
+            grace: 0.0,
+            difficulty: 1.0,
+            odds: surges,
+            pacing: Pacing::default(),
+            flavor: Vec::new(),
+        };
+        let mut incidents = Incidents::new(schedule);
+        // Out of the grace period, then a whole mean interval in one go
+        assert_eq!(incidents.update(&mut station, &mut objectives, 30.0), None);
+        assert_eq!(incidents.update(&mut station, &mut objectives, 60.0), Some(IncidentKind::PowerSurge));
+        assert!(incidents.alarm());
+        let module = station.module_index(incidents.active()[0].module.unwrap()).unwrap();
+        assert!(station.modules()[module].wiring < 0.9);
+        assert_eq!(objectives.active().next().unwrap().title, format!("Rewire module {}", module));
+        let raised = station.drain_events();
+        assert!(raised.iter().any(|e| matches!(e, StationEvent::Incident { kind: IncidentKind::PowerSurge, .. })));
+
+        // Rewiring meets the objective, and that's the end of it
+        station.repair_component(module, Component::Wiring, 1.0);
+        objectives.update(&mut station, Vec3::ZERO);
+        incidents.update(&mut station, &mut objectives, 0.0);
+        assert!(!incidents.alarm());
+
+        // There's something on the station for every kind to happen to
+        for kind in IncidentKind::ALL {
+            assert!(incidents.raise(kind, &mut station, &mut objectives));
+        }
+        assert_eq!(incidents.active().len(), 4);
+        assert!(station.interference().active());
+        assert!(station.modules().iter().flat_map(|m| &m.interactive_elements).any(|e| e.state == ElementState::Malfunction));
+    }
+}
@@ -0,0 +1,706 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fs;
+use std::path::Path;
+use anyhow::{anyhow, Context, Result};
+
+// Raylib (GLFW) key code
+pub type KeyCode = i32;
+
+// Whatever polls the keyboard; main implements this on top of raylib
+pub trait KeySource {
+    fn is_pressed(&self, key: KeyCode) -> bool;
+    fn is_down(&self, key: KeyCode) -> bool;
+}
+
+// Layers of control, lowest first. Active contexts are always stacked in
+// this order, with Global on top of everything.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum InputContext {
+    Gameplay,
+    FlyCamera,
+    Seated,
+    ConsoleUi,
+    Helm,
+    Menu,
+    DevConsole,
+    Global,
+}
+
+// What a context hides from the contexts beneath it
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Consume {
+    Nothing,
+    // Only the keys it has bindings for
+    Bound,
+    All,
+}
+
+impl InputContext {
+    pub const ALL: [InputContext; 8] = [
+        InputContext::Gameplay,
+        InputContext::FlyCamera,
+        InputContext::Seated,
+        InputContext::ConsoleUi,
+        InputContext::Helm,
+        InputContext::Menu,
+        InputContext::DevConsole,
+        InputContext::Global,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            InputContext::Gameplay => "gameplay",
+            InputContext::FlyCamera => "fly_camera",
+            InputContext::Seated => "seated",
+            InputContext::ConsoleUi => "console_ui",
+            InputContext::Helm => "helm",
+            InputContext::Menu => "menu",
+            InputContext::DevConsole => "dev_console",
+            InputContext::Global => "global",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|context| context.name() == name)
+    }
+
+    pub fn consume(self) -> Consume {
+        match self {
+            InputContext::Gameplay => Consume::Bound,
+            // The fly camera and seats keep the player from wandering off
+            InputContext::FlyCamera => Consume::All,
+            InputContext::Seated => Consume::All,
+            // A terminal screen sits on top of its seat
+            InputContext::ConsoleUi => Consume::Bound,
+            // Flying a ship in, every key's for the ship
+            InputContext::Helm => Consume::All,
+            InputContext::Menu => Consume::All,
+            // Everything typed goes into the console
+            InputContext::DevConsole => Consume::All,
+            InputContext::Global => Consume::Nothing,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Action {
+    MoveForward,
+    MoveBack,
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    Interact,
+    UseItem,
+    Confirm,
+    Back,
+    NavUp,
+    NavDown,
+    NavLeft,
+    NavRight,
+    Erase,
+    ToggleMap,
+    ToggleJournal,
+    ToggleConsole,
+    ToggleCursor,
+    ToggleDebugOverlay,
+    ToggleInputDebug,
+    ToggleFlyCamera,
+    ToggleOrbitPreview,
+    CycleView,
+    StartDrill,
+    SoundEvacuation,
+    BugReport,
+    ExportClip,
+    TogglePause,
+    StepSim,
+    TimeSlower,
+    TimeFaster,
+    TimeReset,
+    DumpState,
+    QuickSave,
+    QuickLoad,
+    ToggleSaveMenu,
+    SaveSlot,
+    PlaceMarker,
+    QuickMenu,
+    ToggleHelmetLight,
+    PushToTalk,
+    ToggleTether,
+    FastTravel,
+    ToggleBuildMode,
+    Deconstruct,
+    TakeHelm,
+    Brake,
+    Modifier,
+    Bookmark(u8),
+    Slot(u8),
+}
+
+const ACTION_NAMES: &[(Action, &str)] = &[
+    (Action::MoveForward, "move_forward"),
+    (Action::MoveBack, "move_back"),
+    (Action::MoveLeft, "move_left"),
+    (Action::MoveRight, "move_right"),
+    (Action::MoveUp, "move_up"),
+    (Action::MoveDown, "move_down"),
+    (Action::Interact, "interact"),
+    (Action::UseItem, "use_item"),
+    (Action::Confirm, "confirm"),
+    (Action::Back, "back"),
+    (Action::NavUp, "nav_up"),
+    (Action::NavDown, "nav_down"),
+    (Action::NavLeft, "nav_left"),
+    (Action::NavRight, "nav_right"),
+    (Action::Erase, "erase"),
+    (Action::ToggleMap, "toggle_map"),
+    (Action::ToggleJournal, "toggle_journal"),
+    (Action::ToggleConsole, "toggle_console"),
+    (Action::ToggleCursor, "toggle_cursor"),
+    (Action::ToggleDebugOverlay, "toggle_debug_overlay"),
+    (Action::ToggleInputDebug, "toggle_input_debug"),
+    (Action::ToggleFlyCamera, "toggle_fly_camera"),
+    (Action::ToggleOrbitPreview, "toggle_orbit_preview"),
+    (Action::CycleView, "cycle_view"),
+    (Action::StartDrill, "start_drill"),
+    (Action::SoundEvacuation, "sound_evacuation"),
+    (Action::BugReport, "bug_report"),
+    (Action::ExportClip, "export_clip"),
+    (Action::TogglePause, "toggle_pause"),
+    (Action::StepSim, "step_sim"),
+    (Action::TimeSlower, "time_slower"),
+    (Action::TimeFaster, "time_faster"),
+    (Action::TimeReset, "time_reset"),
+    (Action::DumpState, "dump_state"),
+    (Action::QuickSave, "quick_save"),
+    (Action::QuickLoad, "quick_load"),
+    (Action::ToggleSaveMenu, "toggle_save_menu"),
+    (Action::SaveSlot, "save_slot"),
+    (Action::PlaceMarker, "place_marker"),
+    (Action::QuickMenu, "quick_menu"),
+    (Action::ToggleHelmetLight, "toggle_helmet_light"),
+    (Action::PushToTalk, "push_to_talk"),
+    (Action::ToggleTether, "toggle_tether"),
+    (Action::FastTravel, "fast_travel"),
+    (Action::ToggleBuildMode, "toggle_build_mode"),
+    (Action::Deconstruct, "deconstruct"),
+    (Action::TakeHelm, "take_helm"),
+    (Action::Brake, "brake"),
+    (Action::Modifier, "modifier"),
+    (Action::Bookmark(0), "bookmark_1"),
+    (Action::Bookmark(1), "bookmark_2"),
+    (Action::Bookmark(2), "bookmark_3"),
+    (Action::Bookmark(3), "bookmark_4"),
+    (Action::Bookmark(4), "bookmark_5"),
+    (Action::Bookmark(5), "bookmark_6"),
+    (Action::Bookmark(6), "bookmark_7"),
+    (Action::Bookmark(7), "bookmark_8"),
+    (Action::Bookmark(8), "bookmark_9"),
+    (Action::Slot(0), "slot_1"),
+    (Action::Slot(1), "slot_2"),
+    (Action::Slot(2), "slot_3"),
+    (Action::Slot(3), "slot_4"),
+    (Action::Slot(4), "slot_5"),
+    (Action::Slot(5), "slot_6"),
+];
+
+impl Action {
+    pub fn name(self) -> &'static str {
+        ACTION_NAMES
+            .iter()
+            .find(|(action, _)| *action == self)
+            .map_or("unknown", |(_, name)| name)
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        ACTION_NAMES.iter().find(|(_, n)| *n == name).map(|(action, _)| *action)
+    }
+}
+
+const NAMED_KEYS: &[(&str, KeyCode)] = &[
+    ("SPACE", 32),
+    ("LEFT_BRACKET", 91),
+    ("BACKSLASH", 92),
+    ("RIGHT_BRACKET", 93),
+    ("GRAVE", 96),
+    ("ESCAPE", 256),
+    ("ENTER", 257),
+    ("TAB", 258),
+    ("BACKSPACE", 259),
+    ("DELETE", 261),
+    ("RIGHT", 262),
+    ("LEFT", 263),
+    ("DOWN", 264),
+    ("UP", 265),
+    ("PAGE_UP", 266),
+    ("PAGE_DOWN", 267),
+    ("HOME", 268),
+    ("END", 269),
+    ("PAUSE", 284),
+    ("LEFT_SHIFT", 340),
+    ("LEFT_CONTROL", 341),
+    ("LEFT_ALT", 342),
+    ("RIGHT_SHIFT", 344),
+    ("RIGHT_CONTROL", 345),
+    ("RIGHT_ALT", 346),
+];
+
+// Gamepad buttons share the key code space, past every keyboard key, so they
+// can be bound like keys. The numbers are raylib's GamepadButton values.
+pub const GAMEPAD_BASE: KeyCode = 1000;
+
+const GAMEPAD_BUTTONS: &[(&str, i32)] = &[
+    ("PAD_DPAD_UP", 1),
+    ("PAD_DPAD_RIGHT", 2),
+    ("PAD_DPAD_DOWN", 3),
+    ("PAD_DPAD_LEFT", 4),
+    ("PAD_Y", 5),
+    ("PAD_B", 6),
+    ("PAD_A", 7),
+    ("PAD_X", 8),
+    ("PAD_LB", 9),
+    ("PAD_LT", 10),
+    ("PAD_RB", 11),
+    ("PAD_RT", 12),
+    ("PAD_SELECT", 13),
+    ("PAD_HOME", 14),
+    ("PAD_START", 15),
+    ("PAD_LSTICK", 16),
+    ("PAD_RSTICK", 17),
+];
+
+// The raylib gamepad button behind a code, if it is one
+pub fn gamepad_button(code: KeyCode) -> Option<i32> {
+    (code > GAMEPAD_BASE).then_some(code - GAMEPAD_BASE)
+}
+
+// Letters and digits are their ASCII codes, F1-F12 start at 290
+pub fn key_code(name: &str) -> Option<KeyCode> {
+    let name = name.to_ascii_uppercase();
+    if let Some((_, button)) = GAMEPAD_BUTTONS.iter().find(|(n, _)| *n == name) {
+        return Some(GAMEPAD_BASE + button);
+    }
+    if let [c] = name.as_bytes() {
+        if c.is_ascii_uppercase() || c.is_ascii_digit() {
+            return Some(*c as KeyCode);
+        }
+    }
+    if let Some(n) = name.strip_prefix('F').and_then(|n| n.parse::<KeyCode>().ok()) {
+        return (1..=12).contains(&n).then_some(289 + n);
+    }
+    NAMED_KEYS.iter().find(|(n, _)| *n == name).map(|(_, code)| *code)
+}
+
+pub fn key_name(code: KeyCode) -> String {
+    match code {
+        48..=57 | 65..=90 => (code as u8 as char).to_string(),
+        290..=301 => format!("F{}", code - 289),
+        _ if code > GAMEPAD_BASE => GAMEPAD_BUTTONS
+            .iter()
+            .find(|(_, button)| GAMEPAD_BASE + button == code)
+            .map_or_else(|| format!("PAD#{}", code - GAMEPAD_BASE), |(name, _)| name.to_string()),
+        _ => NAMED_KEYS
+            .iter()
+            .find(|(_, c)| *c == code)
+            .map_or_else(|| format!("#{}", code), |(name, _)| name.to_string()),
+    }
+}
+
+const DEFAULT_BINDINGS: &[(InputContext, Action, &[&str])] = &[
+    (InputContext::Gameplay, Action::MoveForward, &["W"]),
+    (InputContext::Gameplay, Action::MoveBack, &["S"]),
+    (InputContext::Gameplay, Action::MoveLeft, &["A"]),
+    (InputContext::Gameplay, Action::MoveRight, &["D"]),
+    (InputContext::Gameplay, Action::MoveDown, &["Q"]),
+    (InputContext::Gameplay, Action::MoveUp, &["E"]),
+    (InputContext::Gameplay, Action::Interact, &["F", "PAD_A"]),
+    (InputContext::Gameplay, Action::UseItem, &["R"]),
+    (InputContext::Gameplay, Action::Slot(0), &["1"]),
+    (InputContext::Gameplay, Action::Slot(1), &["2"]),
+    (InputContext::Gameplay, Action::Slot(2), &["3"]),
+    (InputContext::Gameplay, Action::Slot(3), &["4"]),
+    (InputContext::Gameplay, Action::Slot(4), &["5"]),
+    (InputContext::Gameplay, Action::Slot(5), &["6"]),
+    (InputContext::Gameplay, Action::Confirm, &["ENTER"]),
+    (InputContext::Gameplay, Action::ToggleMap, &["M"]),
+    (InputContext::Gameplay, Action::ToggleJournal, &["J"]),
+    (InputContext::Gameplay, Action::ToggleSaveMenu, &["L"]),
+    (InputContext::Gameplay, Action::PlaceMarker, &["N", "PAD_RB"]),
+    (InputContext::Gameplay, Action::QuickMenu, &["G", "PAD_LB"]),
+    (InputContext::Gameplay, Action::ToggleHelmetLight, &["H", "PAD_Y"]),
+    (InputContext::Gameplay, Action::PushToTalk, &["C"]),
+    (InputContext::Gameplay, Action::ToggleTether, &["T"]),
+    (InputContext::Gameplay, Action::FastTravel, &["V"]),
+    (InputContext::Gameplay, Action::ToggleBuildMode, &["B"]),
+    (InputContext::Gameplay, Action::Deconstruct, &["Z"]),
+    (InputContext::Gameplay, Action::Brake, &["X", "PAD_X"]),
+    // Gameplay only, so typing brackets into the console leaves time alone
+    (InputContext::Gameplay, Action::TimeSlower, &["LEFT_BRACKET"]),
+    (InputContext::Gameplay, Action::TimeFaster, &["RIGHT_BRACKET"]),
+    (InputContext::Gameplay, Action::TimeReset, &["BACKSLASH"]),
+    (InputContext::FlyCamera, Action::MoveForward, &["W"]),
+    (InputContext::FlyCamera, Action::MoveBack, &["S"]),
+    (InputContext::FlyCamera, Action::MoveLeft, &["A"]),
+    (InputContext::FlyCamera, Action::MoveRight, &["D"]),
+    (InputContext::FlyCamera, Action::MoveDown, &["Q"]),
+    (InputContext::FlyCamera, Action::MoveUp, &["E"]),
+    (InputContext::FlyCamera, Action::Bookmark(0), &["1"]),
+    (InputContext::FlyCamera, Action::Bookmark(1), &["2"]),
+    (InputContext::FlyCamera, Action::Bookmark(2), &["3"]),
+    (InputContext::FlyCamera, Action::Bookmark(3), &["4"]),
+    (InputContext::FlyCamera, Action::Bookmark(4), &["5"]),
+    (InputContext::FlyCamera, Action::Bookmark(5), &["6"]),
+    (InputContext::FlyCamera, Action::Bookmark(6), &["7"]),
+    (InputContext::FlyCamera, Action::Bookmark(7), &["8"]),
+    (InputContext::FlyCamera, Action::Bookmark(8), &["9"]),
+    // Only while observing
+    (InputContext::FlyCamera, Action::CycleView, &["C"]),
+    (InputContext::FlyCamera, Action::Interact, &["F"]),
+    (InputContext::FlyCamera, Action::PlaceMarker, &["N"]),
+    (InputContext::FlyCamera, Action::ToggleMap, &["M"]),
+    (InputContext::Seated, Action::Back, &["F", "BACKSPACE"]),
+    (InputContext::Seated, Action::TakeHelm, &["H"]),
+    (InputContext::Helm, Action::MoveForward, &["W"]),
+    (InputContext::Helm, Action::MoveBack, &["S"]),
+    (InputContext::Helm, Action::MoveLeft, &["A"]),
+    (InputContext::Helm, Action::MoveRight, &["D"]),
+    (InputContext::Helm, Action::MoveDown, &["Q"]),
+    (InputContext::Helm, Action::MoveUp, &["E"]),
+    (InputContext::Helm, Action::NavUp, &["UP"]),
+    (InputContext::Helm, Action::NavDown, &["DOWN"]),
+    (InputContext::Helm, Action::NavLeft, &["LEFT"]),
+    (InputContext::Helm, Action::NavRight, &["RIGHT"]),
+    (InputContext::Helm, Action::Back, &["BACKSPACE"]),
+    (InputContext::ConsoleUi, Action::NavUp, &["UP"]),
+    (InputContext::ConsoleUi, Action::NavDown, &["DOWN"]),
+    (InputContext::ConsoleUi, Action::NavLeft, &["LEFT"]),
+    (InputContext::ConsoleUi, Action::NavRight, &["RIGHT"]),
+    (InputContext::ConsoleUi, Action::Confirm, &["ENTER"]),
+    (InputContext::Menu, Action::Back, &["BACKSPACE", "PAD_B"]),
+    (InputContext::Menu, Action::Confirm, &["ENTER", "F", "PAD_A"]),
+    (InputContext::Menu, Action::NavUp, &["UP", "PAD_DPAD_UP"]),
+    (InputContext::Menu, Action::NavDown, &["DOWN", "PAD_DPAD_DOWN"]),
+    (InputContext::Menu, Action::NavLeft, &["LEFT", "PAD_DPAD_LEFT"]),
+    (InputContext::Menu, Action::NavRight, &["RIGHT", "PAD_DPAD_RIGHT"]),
+    (InputContext::Menu, Action::ToggleMap, &["M"]),
+    (InputContext::Menu, Action::ToggleJournal, &["J"]),
+    (InputContext::Menu, Action::ToggleSaveMenu, &["L"]),
+    (InputContext::Menu, Action::SaveSlot, &["S"]),
+    (InputContext::DevConsole, Action::Confirm, &["ENTER"]),
+    (InputContext::DevConsole, Action::Erase, &["BACKSPACE"]),
+    (InputContext::DevConsole, Action::NavUp, &["UP"]),
+    (InputContext::DevConsole, Action::NavDown, &["DOWN"]),
+    (InputContext::Global, Action::ToggleConsole, &["GRAVE"]),
+    (InputContext::Global, Action::ToggleCursor, &["TAB"]),
+    (InputContext::Global, Action::StartDrill, &["F1"]),
+    (InputContext::Global, Action::DumpState, &["F2"]),
+    (InputContext::Global, Action::ToggleDebugOverlay, &["F3"]),
+    (InputContext::Global, Action::ToggleFlyCamera, &["F4"]),
+    (InputContext::Global, Action::QuickSave, &["F5"]),
+    (InputContext::Global, Action::TogglePause, &["PAUSE"]),
+    (InputContext::Global, Action::ToggleInputDebug, &["F6"]),
+    (InputContext::Global, Action::ToggleOrbitPreview, &["F7"]),
+    (InputContext::Global, Action::BugReport, &["F8"]),
+    (InputContext::Global, Action::QuickLoad, &["F9"]),
+    (InputContext::Global, Action::ExportClip, &["F10"]),
+    (InputContext::Global, Action::StepSim, &["F11"]),
+    (InputContext::Global, Action::SoundEvacuation, &["F12"]),
+    (InputContext::Global, Action::Modifier, &["LEFT_SHIFT", "RIGHT_SHIFT"]),
+];
+
+// Added to the defaults by the handheld preset so everything can be done
+// from the pad; the sticks move and look on their own
+const GAMEPAD_BINDINGS: &[(InputContext, Action, &[&str])] = &[
+    (InputContext::Gameplay, Action::UseItem, &["PAD_RT"]),
+    (InputContext::Gameplay, Action::MoveUp, &["PAD_DPAD_UP"]),
+    (InputContext::Gameplay, Action::MoveDown, &["PAD_DPAD_DOWN"]),
+    (InputContext::Gameplay, Action::ToggleTether, &["PAD_DPAD_LEFT"]),
+    (InputContext::Gameplay, Action::ToggleJournal, &["PAD_DPAD_RIGHT"]),
+    (InputContext::Gameplay, Action::ToggleMap, &["PAD_SELECT"]),
+    (InputContext::Gameplay, Action::ToggleSaveMenu, &["PAD_START"]),
+    (InputContext::Seated, Action::Back, &["PAD_B"]),
+    (InputContext::ConsoleUi, Action::NavUp, &["PAD_DPAD_UP"]),
+    (InputContext::ConsoleUi, Action::NavDown, &["PAD_DPAD_DOWN"]),
+    (InputContext::ConsoleUi, Action::NavLeft, &["PAD_DPAD_LEFT"]),
+    (InputContext::ConsoleUi, Action::NavRight, &["PAD_DPAD_RIGHT"]),
+    (InputContext::ConsoleUi, Action::Confirm, &["PAD_A"]),
+    (InputContext::Menu, Action::ToggleMap, &["PAD_SELECT"]),
+    (InputContext::Menu, Action::ToggleSaveMenu, &["PAD_START"]),
+    (InputContext::Menu, Action::SaveSlot, &["PAD_X"]),
+];
+
+// Keys for each action, per context
+#[derive(Debug, Clone)]
+pub struct InputBindings {
+    bindings: BTreeMap<InputContext, BTreeMap<Action, Vec<KeyCode>>>,
+}
+
+impl Default for InputBindings {
+    fn default() -> Self {
+        let mut bindings = Self {
+            bindings: BTreeMap::new(),
+        };
+        for &(context, action, keys) in DEFAULT_BINDINGS {
+            let keys = keys.iter().filter_map(|name| key_code(name)).collect();
+            bindings.bind(context, action, keys);
+        }
+        bindings
+    }
+}
+
+impl InputBindings {
+    // The defaults plus a pad button for everything the pad can't reach
+    pub fn handheld() -> Self {
+        let mut bindings = Self::default();
+        for &(context, action, keys) in GAMEPAD_BINDINGS {
+            let actions = bindings.bindings.entry(context).or_default();
+            actions
+                .entry(action)
+                .or_default()
+                .extend(keys.iter().filter_map(|name| key_code(name)));
+        }
+        bindings
+    }
+
+    // Defaults with any overrides from a TOML file of
+    // `[context] action = ["KEY", ...]` tables
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        Self::default().with_overrides(path)
+    }
+
+    // Overrides from the same kind of file on top of these bindings
+    pub fn with_overrides<P: AsRef<Path>>(mut self, path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("failed to read bindings from {}", path.display()))?;
+        let file: HashMap<String, HashMap<String, Vec<String>>> =
+            toml::from_str(&source).context("invalid bindings file")?;
+
+        for (context_name, actions) in file {
+            let context = InputContext::from_name(&context_name)
+                .ok_or_else(|| anyhow!("unknown input context '{}'", context_name))?;
+            for (action_name, key_names) in actions {
+                let action = Action::from_name(&action_name)
+                    .ok_or_else(|| anyhow!("unknown action '{}' in [{}]", action_name, context_name))?;
+                let keys = key_names
+                    .iter()
+                    .map(|name| key_code(name).ok_or_else(|| anyhow!("unknown key '{}'", name)))
+                    .collect::<Result<Vec<_>>>()?;
+                self.bind(context, action, keys);
+            }
+        }
+        Ok(self)
+    }
+
+    // Replaces the keys for an action; an empty list unbinds it
+    pub fn bind(&mut self, context: InputContext, action: Action, keys: Vec<KeyCode>) {
+        self.bindings.entry(context).or_default().insert(action, keys);
+    }
+
+    pub fn keys(&self, context: InputContext, action: Action) -> &[KeyCode] {
+        self.bindings
+            .get(&context)
+            .and_then(|actions| actions.get(&action))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    pub fn actions(&self, context: InputContext) -> impl Iterator<Item = (Action, &[KeyCode])> {
+        self.bindings
+            .get(&context)
+            .into_iter()
+            .flatten()
+            .map(|(action, keys)| (*action, keys.as_slice()))
+    }
+}
+
+// Resolves keys to actions once per frame. Contexts are walked from the top of
+// the stack down; each one only sees the keys the contexts above it let through,
+// so a key can never trigger actions in two layers at once.
+#[derive(Debug)]
+pub struct Input {
+    bindings: InputBindings,
+    stack: Vec<InputContext>,
+    pressed: HashSet<(InputContext, Action)>,
+    held: HashSet<(InputContext, Action)>,
+    pub show_debug: bool,
+}
+
+impl Input {
+    pub fn new(bindings: InputBindings) -> Self {
+        Self {
+            bindings,
+            stack: vec![InputContext::Gameplay, InputContext::Global],
+            pressed: HashSet::new(),
+            held: HashSet::new(),
+            show_debug: false,
+        }
+    }
+
+    pub fn push(&mut self, context: InputContext) {
+        if let Err(index) = self.stack.binary_search(&context) {
+            self.stack.insert(index, context);
+        }
+    }
+
+    pub fn pop(&mut self, context: InputContext) {
+        // Global can't be removed
+        if context != InputContext::Global {
+            self.stack.retain(|c| *c != context);
+        }
+    }
+
+    pub fn set_active(&mut self, context: InputContext, active: bool) {
+        if active {
+            self.push(context);
+        } else {
+            self.pop(context);
+        }
+    }
+
+    pub fn is_active(&self, context: InputContext) -> bool {
+        self.stack.contains(&context)
+    }
+
+    // Bottom first
+    pub fn stack(&self) -> &[InputContext] {
+        &self.stack
+    }
+
+    // True when something above gameplay owns the mouse and movement keys
+    pub fn ui_captured(&self) -> bool {
+        self.stack
+            .iter()
+            .any(|c| matches!(c, InputContext::Seated | InputContext::Helm | InputContext::Menu | InputContext::DevConsole))
+    }
+
+    pub fn update(&mut self, keys: &impl KeySource) {
+        self.pressed.clear();
+        self.held.clear();
+
+        let mut consumed: HashSet<KeyCode> = HashSet::new();
+        for &context in self.stack.iter().rev() {
+            for (action, codes) in self.bindings.actions(context) {
+                for &code in codes.iter().filter(|code| !consumed.contains(code)) {
+                    if keys.is_pressed(code) {
+                        self.pressed.insert((context, action));
+                    }
+                    if keys.is_down(code) {
+                        self.held.insert((context, action));
+                    }
+                }
+            }
+            match context.consume() {
+                Consume::Nothing => {}
+                Consume::Bound => consumed.extend(self.bindings.actions(context).flat_map(|(_, codes)| codes)),
+                Consume::All => break,
+            }
+        }
+    }
+
+    pub fn pressed(&self, context: InputContext, action: Action) -> bool {
+        self.pressed.contains(&(context, action))
+    }
+
+    pub fn held(&self, context: InputContext, action: Action) -> bool {
+        self.held.contains(&(context, action))
+    }
+
+    // -1, 0 or 1 from a pair of held actions
+    pub fn axis(&self, context: InputContext, positive: Action, negative: Action) -> f32 {
+        self.held(context, positive) as i32 as f32 - self.held(context, negative) as i32 as f32
+    }
+
+    pub fn bindings(&self) -> &InputBindings {
+        &self.bindings
+    }
+
+    // Active stack from the top down, with each context's bindings
+    pub fn debug_lines(&self) -> Vec<String> {
+        let mut lines = Vec::new();
+        let mut blocked = false;
+        for &context in self.stack.iter().rev() {
+            let consume = match context.consume() {
+                Consume::Nothing => "passes all",
+                Consume::Bound => "consumes bound",
+                Consume::All => "consumes all",
+            };
+            let state = if blocked { ", blocked" } else { "" };
+            lines.push(format!("{} ({}{})", context.name(), consume, state));
+            for (action, codes) in self.bindings.actions(context) {
+                let keys: Vec<String> = codes.iter().map(|&code| key_name(code)).collect();
+                let marker = if self.pressed(context, action) { "*" } else { " " };
+                lines.push(format!(" {} {}: {}", marker, action.name(), keys.join(", ")));
+            }
+            blocked |= context.consume() == Consume::All;
+        }
+        lines
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every key listed went down this frame
+    struct FakeKeys(Vec<KeyCode>);
+
+    impl KeySource for FakeKeys {
+        fn is_pressed(&self, key: KeyCode) -> bool {
+            self.0.contains(&key)
+        }
+
+        fn is_down(&self, key: KeyCode) -> bool {
+            self.0.contains(&key)
+        }
+    }
+
+    fn keys(names: &[&str]) -> FakeKeys {
+        FakeKeys(names.iter().map(|name| key_code(name).unwrap()).collect())
+    }
+
+    #[test]
+    fn consuming_everything_hides_keys_from_the_contexts_below() {
+        let mut input = Input::new(InputBindings::default());
+        input.update(&keys(&["F", "W", "LEFT_BRACKET"]));
+        assert!(input.pressed(InputContext::Gameplay, Action::Interact));
+        assert!(input.held(InputContext::Gameplay, Action::MoveForward));
+        assert!(input.pressed(InputContext::Gameplay, Action::TimeSlower));
+
+        // With the console open the same keys are only typing
+        input.push(InputContext::DevConsole);
+        input.update(&keys(&["F", "W", "LEFT_BRACKET", "ENTER"]));
+        assert!(input.pressed(InputContext::DevConsole, Action::Confirm));
+        assert!(!input.pressed(InputContext::Gameplay, Action::Interact));
+        assert!(!input.held(InputContext::Gameplay, Action::MoveForward));
+        assert!(!input.pressed(InputContext::Gameplay, Action::TimeSlower));
+        // Global sits above it and still hears its own keys
+        input.update(&keys(&["GRAVE"]));
+        assert!(input.pressed(InputContext::Global, Action::ToggleConsole));
+
+        input.pop(InputContext::DevConsole);
+        input.update(&keys(&["F"]));
+        assert!(input.pressed(InputContext::Gameplay, Action::Interact));
+    }
+
+    #[test]
+    fn consuming_bound_keys_lets_the_rest_through() {
+        let mut input = Input::new(InputBindings::default());
+        input.push(InputContext::ConsoleUi);
+        input.update(&keys(&["ENTER", "W"]));
+        // ENTER confirms on the screen, not in gameplay underneath
+        assert!(input.pressed(InputContext::ConsoleUi, Action::Confirm));
+        assert!(!input.pressed(InputContext::Gameplay, Action::Confirm));
+        // The screen has nothing on W, so the player still walks
+        assert!(input.held(InputContext::Gameplay, Action::MoveForward));
+    }
+
+    #[test]
+    fn global_consumes_nothing() {
+        let mut bindings = InputBindings::default();
+        bindings.bind(InputContext::Gameplay, Action::Brake, vec![key_code("LEFT_SHIFT").unwrap()]);
+        let mut input = Input::new(bindings);
+        input.update(&keys(&["LEFT_SHIFT"]));
+        assert!(input.held(InputContext::Global, Action::Modifier));
+        assert!(input.held(InputContext::Gameplay, Action::Brake));
+    }
+}
@@ -0,0 +1,426 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use anyhow::{Context, Result};
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+use crate::module_id::{self, ModuleId};
+use crate::resources::Resource;
+use crate::station::{ElementState, InteractionType, SpaceStation};
+
+// Slots on the HUD bar
+pub const INVENTORY_SLOTS: usize = 6;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ItemKind {
+    RepairKit,
+    OxygenCanister,
+    SpareParts,
+    WorkLight,
+    // Sucks up spilled liquid while the use key is held
+    WetVac,
+}
+
+impl ItemKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            ItemKind::RepairKit => "Repair kit",
+            ItemKind::OxygenCanister => "O2 canister",
+            ItemKind::SpareParts => "Spare parts",
+            ItemKind::WorkLight => "Work light",
+            ItemKind::WetVac => "Wet vac",
+        }
+    }
+
+    pub fn max_stack(self) -> u32 {
+        match self {
+            ItemKind::RepairKit => 5,
+            ItemKind::OxygenCanister => 3,
+            ItemKind::SpareParts => 10,
+            ItemKind::WorkLight => 2,
+            ItemKind::WetVac => 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ItemStack {
+    pub kind: ItemKind,
+    pub count: u32,
+}
+
+// What the player's carrying, for saves; empty slots are left out
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InventoryState {
+    pub stacks: Vec<SavedStack>,
+    pub selected: usize,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SavedStack {
+    pub slot: usize,
+    pub kind: ItemKind,
+    pub count: u32,
+}
+
+// What the player is carrying
+#[derive(Debug)]
+pub struct Inventory {
+    slots: [Option<ItemStack>; INVENTORY_SLOTS],
+    pub selected: usize,
+}
+
+impl Default for Inventory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Inventory {
+    pub fn new() -> Self {
+        Self {
+            slots: [None; INVENTORY_SLOTS],
+            selected: 0,
+        }
+    }
+
+    pub fn slots(&self) -> &[Option<ItemStack>] {
+        &self.slots
+    }
+
+    pub fn selected_item(&self) -> Option<ItemStack> {
+        self.slots[self.selected]
+    }
+
+    pub fn select(&mut self, slot: usize) {
+        if slot < INVENTORY_SLOTS {
+            self.selected = slot;
+        }
+    }
+
+    pub fn count(&self, kind: ItemKind) -> u32 {
+        self.slots.iter().flatten().filter(|s| s.kind == kind).map(|s| s.count).sum()
+    }
+
+    // Tops up existing stacks, then fills empty slots; returns what didn't fit
+    pub fn add(&mut self, kind: ItemKind, mut count: u32) -> u32 {
+        for stack in self.slots.iter_mut().flatten().filter(|s| s.kind == kind) {
+            let moved = count.min(kind.max_stack() - stack.count);
+            stack.count += moved;
+            count -= moved;
+        }
+        for slot in self.slots.iter_mut().filter(|s| s.is_none()) {
+            if count == 0 {
+                break;
+            }
+            let moved = count.min(kind.max_stack());
+            *slot = Some(ItemStack { kind, count: moved });
+            count -= moved;
+        }
+        count
+    }
+
+    pub fn take_selected(&mut self) {
+        let slot = &mut self.slots[self.selected];
+        if let Some(stack) = slot {
+            stack.count -= 1;
+            if stack.count == 0 {
+                *slot = None;
+            }
+        }
+    }
+
+    // One from whichever stack has it; false if there's none
+    pub fn take(&mut self, kind: ItemKind) -> bool {
+        let Some(slot) = self.slots.iter_mut().find(|slot| slot.is_some_and(|s| s.kind == kind)) else {
+            return false;
+        };
+        if let Some(stack) = slot {
+            stack.count -= 1;
+            if stack.count == 0 {
+                *slot = None;
+            }
+        }
+        true
+    }
+
+    pub fn state(&self) -> InventoryState {
+        InventoryState {
+            stacks: self
+                .slots
+                .iter()
+                .enumerate()
+                .filter_map(|(slot, stack)| stack.map(|stack| SavedStack { slot, kind: stack.kind, count: stack.count }))
+                .collect(),
+            selected: self.selected,
+        }
+    }
+
+    // Picks up a saved state; stacks are cut down to what a slot holds
+    pub fn restore(&mut self, state: &InventoryState) {
+        *self = Self::new();
+        for saved in &state.stacks {
+            if let Some(slot) = self.slots.get_mut(saved.slot) {
+                let count = saved.count.min(saved.kind.max_stack());
+                *slot = (count > 0).then_some(ItemStack { kind: saved.kind, count });
+            }
+        }
+        self.select(state.selected);
+    }
+
+    // Uses the selected item on the station. It's only used up if it did
+    // something; the message says what happened either way.
+    pub fn use_selected(&mut self, station: &mut SpaceStation, player: Vec3, reach: f32) -> Option<String> {
+        let stack = self.selected_item()?;
+        // Spare parts and canisters go back on the shelf at a storage access point
+        if let (Some(resource), Some((module_idx, element_idx))) =
+            (Resource::carried_as(stack.kind), station.nearest_element(player, reach))
+        {
+            let element_type = station.modules()[module_idx].interactive_elements[element_idx].element_type;
+            if element_type == InteractionType::StorageAccess {
+                let id = station.modules()[module_idx].id;
+                if station.resources().stock(id, resource) + 1.0 > resource.capacity() {
+                    return Some(format!("No room in storage for {}", stack.kind.name()));
+                }
+                station.stow_resource(module_idx, resource, 1.0);
+                self.take_selected();
+                return Some(format!("Stowed {} in module {}", stack.kind.name(), module_idx));
+            }
+        }
+        let result = match stack.kind {
+            // Worked in over time, which the inventory can't do itself
+            ItemKind::RepairKit => Err("Repair kits need a repair job".to_string()),
+            ItemKind::OxygenCanister => {
+                let module = station.nearest_module(player)?;
+                if station.add_oxygen(module, OXYGEN_CANISTER_AMOUNT) > 0.0 {
+                    Ok(format!("Vented an O2 canister into module {}", module))
+                } else {
+                    Err("Oxygen is already full".to_string())
+                }
+            }
+            ItemKind::SpareParts => match station.nearest_element(player, reach) {
+                Some((module_idx, element_idx))
+                    if station.element_state(module_idx, element_idx) == Some(ElementState::Malfunction) =>
+                {
+                    station.set_element_state(module_idx, element_idx, ElementState::Inactive);
+                    Ok("Replaced the faulty part".to_string())
+                }
+                _ => Err("Nothing nearby needs spare parts".to_string()),
+            },
+            // Set down in the world, which the inventory can't do itself
+            ItemKind::WorkLight => Err("Work lights need setting down".to_string()),
+            ItemKind::WetVac => Err("The wet vac runs while held".to_string()),
+        };
+        match result {
+            Ok(message) => {
+                self.take_selected();
+                Some(message)
+            }
+            Err(message) => Some(message),
+        }
+    }
+}
+
+const OXYGEN_CANISTER_AMOUNT: f32 = 0.1;
+
+#[derive(Debug, Deserialize)]
+struct ItemPlacement {
+    kind: ItemKind,
+    #[serde(deserialize_with = "module_id::layout_index")]
+    module: ModuleId,
+    #[serde(default)]
+    offset: [f32; 3],
+    #[serde(default = "default_count")]
+    count: u32,
+}
+
+// Contents of a StorageAccess element
+#[derive(Debug, Deserialize)]
+struct ContainerDefinition {
+    #[serde(deserialize_with = "module_id::layout_index")]
+    module: ModuleId,
+    #[serde(default)]
+    element: usize,
+    items: Vec<ItemKind>,
+}
+
+fn default_count() -> u32 {
+    1
+}
+
+#[derive(Debug, Deserialize)]
+struct ItemFile {
+    #[serde(rename = "item", default)]
+    items: Vec<ItemPlacement>,
+    #[serde(rename = "container", default)]
+    containers: Vec<ContainerDefinition>,
+}
+
+#[derive(Debug, Clone)]
+pub struct WorldItem {
+    pub kind: ItemKind,
+    pub count: u32,
+    pub position: Vec3,
+}
+
+// Items lying around and what's in the containers, for saves
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WorldItemsState {
+    pub items: Vec<SavedItem>,
+    pub containers: Vec<SavedContainer>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct SavedItem {
+    pub kind: ItemKind,
+    pub count: u32,
+    pub position: [f32; 3],
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedContainer {
+    pub module: ModuleId,
+    pub element: usize,
+    pub items: Vec<ItemKind>,
+}
+
+// Items lying around the station and the contents of storage containers
+#[derive(Debug, Default)]
+pub struct WorldItems {
+    items: Vec<WorldItem>,
+    containers: HashMap<(ModuleId, usize), Vec<ItemKind>>,
+}
+
+impl WorldItems {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P, station: &SpaceStation) -> Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("failed to read items from {}", path.display()))?;
+        let file: ItemFile = toml::from_str(&source).context("invalid item definitions")?;
+
+        let mut world = Self::new();
+        for placement in file.items {
+            let Some(module) = station.module_index(placement.module).map(|index| &station.modules()[index]) else {
+                eprintln!("Warning: {} is in missing module {:?}", placement.kind.name(), placement.module);
+                continue;
+            };
+            let position = module.transform.matrix().transform_point3(Vec3::from(placement.offset));
+            world.place(placement.kind, placement.count, position);
+        }
+        for container in file.containers {
+            let module = station.module_index(container.module);
+            if module.and_then(|module| station.element_position(module, container.element)).is_none() {
+                eprintln!(
+                    "Warning: container at module {:?} element {} doesn't exist",
+                    container.module, container.element
+                );
+                continue;
+            }
+            world
+                .containers
+                .entry((container.module, container.element))
+                .or_default()
+                .extend(container.items);
+        }
+        Ok(world)
+    }
+
+    pub fn place(&mut self, kind: ItemKind, count: u32, position: Vec3) {
+        self.items.push(WorldItem { kind, count, position });
+    }
+
+    pub fn items(&self) -> &[WorldItem] {
+        &self.items
+    }
+
+    pub fn nearest(&self, position: Vec3, reach: f32) -> Option<usize> {
+        self.items
+            .iter()
+            .enumerate()
+            .map(|(i, item)| (i, item.position.distance(position)))
+            .filter(|&(_, distance)| distance <= reach)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+    }
+
+    // Moves as much of an item as fits into the inventory; whatever doesn't
+    // fit stays on the floor. Returns how many were picked up.
+    pub fn pick_up(&mut self, index: usize, inventory: &mut Inventory) -> Option<(ItemKind, u32)> {
+        let item = self.items.get_mut(index)?;
+        let kind = item.kind;
+        let left = inventory.add(kind, item.count);
+        let taken = item.count - left;
+        if left == 0 {
+            self.items.remove(index);
+        } else {
+            item.count = left;
+        }
+        Some((kind, taken))
+    }
+
+    pub fn container(&self, module: ModuleId, element_idx: usize) -> &[ItemKind] {
+        self.containers
+            .get(&(module, element_idx))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    // Takes everything that fits out of a container
+    pub fn take_from_container(
+        &mut self,
+        module: ModuleId,
+        element_idx: usize,
+        inventory: &mut Inventory,
+    ) -> Vec<(ItemKind, u32)> {
+        let Some(contents) = self.containers.get_mut(&(module, element_idx)) else {
+            return Vec::new();
+        };
+        let mut taken: Vec<(ItemKind, u32)> = Vec::new();
+        contents.retain(|&kind| {
+            if inventory.add(kind, 1) > 0 {
+                return true;
+            }
+            match taken.iter_mut().find(|(k, _)| *k == kind) {
+                Some((_, count)) => *count += 1,
+                None => taken.push((kind, 1)),
+            }
+            false
+        });
+        taken
+    }
+
+    // Containers in module order, so saves come out the same each time
+    pub fn state(&self) -> WorldItemsState {
+        let mut containers: Vec<SavedContainer> = self
+            .containers
+            .iter()
+            .map(|(&(module, element), items)| SavedContainer { module, element, items: items.clone() })
+            .collect();
+        containers.sort_by_key(|container| (container.module, container.element));
+        WorldItemsState {
+            items: self
+                .items
+                .iter()
+                .map(|item| SavedItem { kind: item.kind, count: item.count, position: item.position.to_array() })
+                .collect(),
+            containers,
+        }
+    }
+
+    pub fn restore(&mut self, state: &WorldItemsState) {
+        self.items = state
+            .items
+            .iter()
+            .map(|item| WorldItem { kind: item.kind, count: item.count, position: Vec3::from(item.position) })
+            .collect();
+        self.containers = state
+            .containers
+            .iter()
+            .map(|container| ((container.module, container.element), container.items.clone()))
+            .collect();
+    }
+}
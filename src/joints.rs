@@ -0,0 +1,475 @@
+use std::fs;
+use std::path::Path;
+use anyhow::{Context, Result};
+use glam::{Mat4, Quat, Vec3};
+use serde::Deserialize;
+use crate::events::StationEvent;
+use crate::module_id::{self, ModuleId};
+use crate::station::SpaceStation;
+
+// Share of a joint's speed lost per second, so swinging settles
+const DAMPING: f32 = 0.6;
+// Speed kept bouncing off a joint limit
+const RESTITUTION: f32 = 0.2;
+// Impulse (N·s) a structural jolt puts into props nearby, per unit of
+// damage or explosion magnitude, and how far it carries
+const JOLT_IMPULSE: f32 = 60.0;
+const JOLT_RADIUS: f32 = 10.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum JointKind {
+    // Swings about the axis through the anchor; position is an angle
+    Hinge,
+    // Slides along the axis; position is a distance
+    Slider,
+    // Holds the prop in place until it breaks
+    Fixed,
+}
+
+// Drives a joint towards a speed, with limited force (torque for hinges)
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct Motor {
+    pub speed: f32,
+    pub max_force: f32,
+}
+
+// An articulated prop as the props file describes it. The anchor is
+// relative to its module, or to the parent prop's body when it hangs off
+// another one (a crane hook under its trolley).
+#[derive(Debug, Clone, Deserialize)]
+pub struct PropDefinition {
+    pub name: String,
+    #[serde(deserialize_with = "module_id::layout_index")]
+    pub module: ModuleId,
+    #[serde(default)]
+    pub parent: Option<String>,
+    pub joint: JointKind,
+    #[serde(default)]
+    pub anchor: [f32; 3],
+    #[serde(default = "default_axis")]
+    pub axis: [f32; 3],
+    // Body centre from the joint, before the joint moves it
+    #[serde(default)]
+    pub offset: [f32; 3],
+    pub size: [f32; 3],
+    #[serde(default = "default_mass")]
+    pub mass: f32,
+    // Lower and upper bound of the joint position
+    #[serde(default)]
+    pub limits: Option<[f32; 2]>,
+    #[serde(default)]
+    pub motor: Option<Motor>,
+    // Impulse that tears the joint loose; unbreakable without
+    #[serde(default)]
+    pub break_impulse: Option<f32>,
+}
+
+fn default_axis() -> [f32; 3] {
+    [0.0, 0.0, 1.0]
+}
+
+fn default_mass() -> f32 {
+    10.0
+}
+
+#[derive(Debug, Deserialize)]
+struct PropFile {
+    #[serde(rename = "prop", default)]
+    props: Vec<PropDefinition>,
+}
+
+// A prop torn off its joint, tumbling until it comes to rest on the floor
+#[derive(Debug, Clone, Copy)]
+struct Loose {
+    position: Vec3,
+    velocity: Vec3,
+    rotation: Quat,
+}
+
+#[derive(Debug, Clone)]
+pub struct Prop {
+    pub definition: PropDefinition,
+    parent: Option<usize>,
+    // The joint's rest frame, in world space for props without a parent
+    // and in the parent body's space otherwise
+    rest: Mat4,
+    // Joint position and its rate of change
+    pub position: f32,
+    pub velocity: f32,
+    pub motor: Option<Motor>,
+    loose: Option<Loose>,
+    // The middle of its module's floor and which way is up from it, for
+    // where it lands once loose
+    floor: Vec3,
+    up: Vec3,
+}
+
+impl Prop {
+    pub fn broken(&self) -> bool {
+        self.loose.is_some()
+    }
+
+    fn axis(&self) -> Vec3 {
+        Vec3::from(self.definition.axis).try_normalize().unwrap_or(Vec3::Z)
+    }
+
+    fn offset(&self) -> Vec3 {
+        Vec3::from(self.definition.offset)
+    }
+
+    // What resists the joint turning or sliding
+    fn inertia(&self) -> f32 {
+        let mass = self.definition.mass.max(0.01);
+        match self.definition.joint {
+            JointKind::Hinge => {
+                let size = Vec3::from(self.definition.size);
+                mass * (self.offset().length_squared() + size.length_squared() / 12.0)
+            }
+            JointKind::Slider | JointKind::Fixed => mass,
+        }
+    }
+
+    // The body's frame relative to the joint's rest frame
+    fn joint_motion(&self) -> Mat4 {
+        match self.definition.joint {
+            JointKind::Hinge => Mat4::from_axis_angle(self.axis(), self.position),
+            JointKind::Slider => Mat4::from_translation(self.axis() * self.position),
+            JointKind::Fixed => Mat4::IDENTITY,
+        }
+    }
+}
+
+// Hatch covers, tool arms, crane hooks and the like: rigid boxes on hinge,
+// slider or fixed joints, simulated in the joint's own coordinate so they
+// can't drift apart. Children follow their parent's body but don't push
+// back on it. A joint breaks under too hard a knock, from the player or a
+// structural event, and the prop falls free.
+#[derive(Debug, Default)]
+pub struct Props {
+    props: Vec<Prop>,
+}
+
+impl Props {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P, station: &SpaceStation) -> Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("failed to read props from {}", path.display()))?;
+        let file: PropFile = toml::from_str(&source).context("invalid prop definitions")?;
+
+        let mut props = Self::new();
+        for definition in file.props {
+            if let Err(err) = props.add(definition, station) {
+                eprintln!("Warning: {:#}", err);
+            }
+        }
+        Ok(props)
+    }
+
+    // Parents have to be added before their children
+    pub fn add(&mut self, definition: PropDefinition, station: &SpaceStation) -> Result<usize> {
+        let Some(module) = station.module_index(definition.module).map(|index| &station.modules()[index]) else {
+            anyhow::bail!("prop {} is in missing module {:?}", definition.name, definition.module);
+        };
+        let floor = module.transform.position;
+        let up = module.transform.rotation * Vec3::Y;
+        let anchor = Mat4::from_translation(Vec3::from(definition.anchor));
+        let (parent, rest) = match &definition.parent {
+            Some(name) => {
+                let parent = self
+                    .find(name)
+                    .with_context(|| format!("prop {} hangs off unknown prop {}", definition.name, name))?;
+                (Some(parent), anchor)
+            }
+            None => (None, module.transform.matrix() * anchor),
+        };
+        let motor = definition.motor;
+        self.props.push(Prop {
+            definition,
+            parent,
+            rest,
+            position: 0.0,
+            velocity: 0.0,
+            motor,
+            loose: None,
+            floor,
+            up,
+        });
+        Ok(self.props.len() - 1)
+    }
+
+    pub fn props(&self) -> &[Prop] {
+        &self.props
+    }
+
+    pub fn find(&self, name: &str) -> Option<usize> {
+        self.props.iter().position(|prop| prop.definition.name == name)
+    }
+
+    // Runs a powered joint at `speed`, or lets it go slack with None. False
+    // if the prop has no motor.
+    pub fn drive(&mut self, index: usize, speed: Option<f32>) -> bool {
+        let Some(prop) = self.props.get_mut(index) else {
+            return false;
+        };
+        let Some(motor) = prop.definition.motor else {
+            return false;
+        };
+        prop.motor = speed.map(|speed| Motor { speed, ..motor });
+        true
+    }
+
+    // The joint's frame in the world, where it's attached
+    fn joint_frame(&self, index: usize) -> Mat4 {
+        let prop = &self.props[index];
+        match prop.parent {
+            Some(parent) => self.body_frame(parent) * prop.rest,
+            None => prop.rest,
+        }
+    }
+
+    // The body's frame in the world, before the offset to its centre
+    fn body_frame(&self, index: usize) -> Mat4 {
+        let prop = &self.props[index];
+        match &prop.loose {
+            Some(loose) => {
+                Mat4::from_rotation_translation(loose.rotation, loose.position) * Mat4::from_translation(-prop.offset())
+            }
+            None => self.joint_frame(index) * prop.joint_motion(),
+        }
+    }
+
+    // Where to draw the prop's box, centred on the body
+    pub fn transform(&self, index: usize) -> Mat4 {
+        self.body_frame(index) * Mat4::from_translation(self.props[index].offset())
+    }
+
+    pub fn centre(&self, index: usize) -> Vec3 {
+        self.transform(index).transform_point3(Vec3::ZERO)
+    }
+
+    // `gravity` gives the pull at a point, which in a ring section isn't
+    // straight down
+    pub fn update(&mut self, delta_time: f32, gravity: impl Fn(Vec3) -> Vec3) {
+        for index in 0..self.props.len() {
+            let frame = self.joint_frame(index);
+            let centre = self.centre(index);
+            let pull = gravity(centre);
+            let prop = &mut self.props[index];
+            if let Some(loose) = &mut prop.loose {
+                // Tumbles down to the floor and stops there
+                let half_height = prop.definition.size[1] * 0.5;
+                loose.velocity += pull * delta_time;
+                loose.position += loose.velocity * delta_time;
+                let height = (loose.position - prop.floor).dot(prop.up);
+                if height <= half_height {
+                    loose.position += prop.up * (half_height - height);
+                    loose.velocity = Vec3::ZERO;
+                }
+                continue;
+            }
+            let axis = frame.transform_vector3(prop.axis()).normalize_or_zero();
+            let mass = prop.definition.mass.max(0.01);
+            let inertia = prop.inertia();
+            let gravity = match prop.definition.joint {
+                JointKind::Hinge => (centre - frame.transform_point3(Vec3::ZERO)).cross(pull * mass).dot(axis),
+                JointKind::Slider => (pull * mass).dot(axis),
+                JointKind::Fixed => continue,
+            };
+            // Motors act like a stiff servo on speed, up to their force
+            let drive = prop.motor.map_or(0.0, |motor| {
+                ((motor.speed - prop.velocity) * inertia / delta_time.max(f32::EPSILON))
+                    .clamp(-motor.max_force, motor.max_force)
+            });
+            prop.velocity += (gravity + drive) / inertia * delta_time;
+            prop.velocity *= (1.0 - DAMPING * delta_time).max(0.0);
+            prop.position += prop.velocity * delta_time;
+
+            // Hitting a stop takes the joint's momentum, which can break it
+            if let Some([lower, upper]) = prop.definition.limits {
+                let stop = prop.position.clamp(lower, upper);
+                if stop != prop.position {
+                    let impulse = prop.velocity.abs() * (1.0 + RESTITUTION) * inertia;
+                    prop.position = stop;
+                    prop.velocity = -prop.velocity * RESTITUTION;
+                    if prop.definition.break_impulse.is_some_and(|limit| impulse > limit) {
+                        self.break_loose(index, Vec3::ZERO);
+                    }
+                }
+            }
+        }
+    }
+
+    // Knocks a prop with `impulse` (N·s) at `point`. It turns or slides as
+    // its joint allows, or breaks loose if the knock is too hard. Returns
+    // whether it broke.
+    pub fn knock(&mut self, index: usize, point: Vec3, impulse: Vec3) -> bool {
+        let frame = self.joint_frame(index);
+        let Some(prop) = self.props.get_mut(index) else {
+            return false;
+        };
+        let mass = prop.definition.mass.max(0.01);
+        if let Some(loose) = &mut prop.loose {
+            loose.velocity += impulse / mass;
+            return false;
+        }
+        if prop.definition.break_impulse.is_some_and(|limit| impulse.length() > limit) {
+            self.break_loose(index, impulse / mass);
+            return true;
+        }
+        let axis = frame.transform_vector3(prop.axis()).normalize_or_zero();
+        let inertia = prop.inertia();
+        prop.velocity += match prop.definition.joint {
+            JointKind::Hinge => (point - frame.transform_point3(Vec3::ZERO)).cross(impulse).dot(axis) / inertia,
+            JointKind::Slider => impulse.dot(axis) / inertia,
+            JointKind::Fixed => 0.0,
+        };
+        false
+    }
+
+    // The player walking into props: any box the body from `eye` down
+    // `height` passes through gets pushed along with `velocity`. Returns the
+    // names of any that broke.
+    pub fn bump(&mut self, eye: Vec3, height: f32, velocity: Vec3, mass: f32) -> Vec<String> {
+        const SAMPLES: usize = 4;
+        let mut broken = Vec::new();
+        for index in 0..self.props.len() {
+            let inverse = self.transform(index).inverse();
+            let half = Vec3::from(self.props[index].definition.size) * 0.5;
+            let contact = (0..SAMPLES)
+                .map(|i| eye - Vec3::Y * height * i as f32 / (SAMPLES - 1) as f32)
+                .find(|&point| inverse.transform_point3(point).abs().cmple(half).all());
+            if let Some(point) = contact {
+                if self.knock(index, point, velocity * mass) {
+                    broken.push(self.props[index].definition.name.clone());
+                }
+            }
+        }
+        broken
+    }
+
+    // Structural events jolt props nearby, hardest up close. Returns the
+    // names of any that broke.
+    pub fn handle_event(&mut self, event: &StationEvent) -> Vec<String> {
+        let (position, strength) = match event {
+            StationEvent::ModuleDamaged { position, integrity, .. } => (*position, 1.0 - integrity),
+            StationEvent::Explosion { position, magnitude } => (*position, *magnitude),
+            StationEvent::HullBreach { position, .. } => (*position, 1.0),
+            StationEvent::ConnectionFailed { position, .. } => (*position, 1.0),
+            _ => return Vec::new(),
+        };
+        let mut broken = Vec::new();
+        for index in 0..self.props.len() {
+            let centre = self.centre(index);
+            let away = centre - position;
+            let falloff = 1.0 - away.length() / JOLT_RADIUS;
+            if falloff <= 0.0 {
+                continue;
+            }
+            let direction = (away.normalize_or_zero() + Vec3::Y).normalize_or_zero();
+            if self.knock(index, centre, direction * JOLT_IMPULSE * strength * falloff) {
+                broken.push(self.props[index].definition.name.clone());
+            }
+        }
+        broken
+    }
+
+    // Frees a prop where it is, moving at `velocity` plus however fast its
+    // joint was carrying it. Its children go with it, still attached.
+    fn break_loose(&mut self, index: usize, velocity: Vec3) {
+        let frame = self.joint_frame(index);
+        let centre = self.centre(index);
+        let (_, rotation, _) = self.transform(index).to_scale_rotation_translation();
+        let prop = &mut self.props[index];
+        let axis = frame.transform_vector3(prop.axis()).normalize_or_zero();
+        let carried = match prop.definition.joint {
+            JointKind::Hinge => (axis * prop.velocity).cross(centre - frame.transform_point3(Vec3::ZERO)),
+            JointKind::Slider => axis * prop.velocity,
+            JointKind::Fixed => Vec3::ZERO,
+        };
+        prop.velocity = 0.0;
+        prop.loose = Some(Loose {
+            position: centre,
+            velocity: velocity + carried,
+            rotation,
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STEP: f32 = 1.0 / 60.0;
+
+    fn hanging_hook() -> PropDefinition {
+        PropDefinition {
+            name: "hook".to_string(),
+            module: ModuleId::from_layout(0),
+            parent: None,
+            joint: JointKind::Hinge,
+            anchor: [0.0, 3.0, 0.0],
+            axis: [0.0, 0.0, 1.0],
+            offset: [0.0, -1.0, 0.0],
+            size: [0.3, 0.3, 0.3],
+            mass: 20.0,
+            limits: None,
+            motor: None,
+            break_impulse: Some(400.0),
+        }
+    }
+
+    #[test]
+    fn knocked_hinges_swing_back_and_motors_hold_them() {
+        let station = SpaceStation::create_default_layout();
+        let mut props = Props::new();
+        let hook = props.add(hanging_hook(), &station).unwrap();
+        let rest = props.centre(hook);
+        assert!(!props.knock(hook, rest, Vec3::X * 20.0));
+        let mut widest = 0.0f32;
+        for _ in 0..600 {
+            props.update(STEP, |point| station.gravity_at(point));
+            widest = widest.max(props.props()[hook].position.abs());
+        }
+        assert!(widest > 0.2);
+        assert!(props.props()[hook].position.abs() < widest * 0.2);
+        assert!(props.centre(hook).distance(rest) < 0.2);
+
+        // A limit stops it, and a braked joint barely gives
+        let mut braked = hanging_hook();
+        braked.limits = Some([-0.1, 0.1]);
+        braked.motor = Some(Motor { speed: 0.0, max_force: 1000.0 });
+        let braked = props.add(braked, &station).unwrap();
+        props.knock(braked, props.centre(braked), Vec3::X * 100.0);
+        for _ in 0..60 {
+            props.update(STEP, |point| station.gravity_at(point));
+            assert!(props.props()[braked].position.abs() <= 0.1);
+        }
+        assert!(props.drive(braked, Some(1.0)));
+        assert!(!props.drive(hook, Some(1.0)));
+    }
+
+    #[test]
+    fn explosions_break_joints_and_loose_props_fall() {
+        let station = SpaceStation::create_default_layout();
+        let mut props = Props::new();
+        let hook = props.add(hanging_hook(), &station).unwrap();
+        let far = props.handle_event(&StationEvent::Explosion { position: Vec3::new(40.0, 0.0, 0.0), magnitude: 20.0 });
+        assert!(far.is_empty());
+        let broke = props.handle_event(&StationEvent::Explosion { position: Vec3::new(1.0, 1.0, 0.0), magnitude: 20.0 });
+        assert_eq!(broke, vec!["hook".to_string()]);
+        assert!(props.props()[hook].broken());
+        for _ in 0..600 {
+            props.update(STEP, |point| station.gravity_at(point));
+        }
+        assert!((props.centre(hook).y - 0.15).abs() < 0.01);
+
+        let mut unknown = hanging_hook();
+        unknown.parent = Some("crane".to_string());
+        assert!(props.add(unknown, &station).is_err());
+    }
+}
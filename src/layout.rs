@@ -0,0 +1,210 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{bail, Context, Result};
+use glam::{EulerRot, Quat, Vec3};
+use serde::{Deserialize, Serialize};
+use crate::module_id::ModuleId;
+use crate::ring::RingSpec;
+use crate::station::{ElementState, InteractionType, ModuleType, SpaceStation};
+
+// A station as authored by hand, in JSON or TOML: modules by type and
+// position, and what each connects to. Everything else (meshes, air, power,
+// the type's own elements and a door on every connection) comes with the
+// module type, as it does for the built-in layouts.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StationLayout {
+    #[serde(rename = "module", default)]
+    pub modules: Vec<LayoutModule>,
+    // Groups of the modules above spun for artificial gravity
+    #[serde(rename = "ring", default)]
+    pub rings: Vec<LayoutRing>,
+    // The sky preset for missions on this station, over config.toml's
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub backdrop: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayoutModule {
+    #[serde(rename = "type")]
+    pub module_type: ModuleType,
+    pub position: [f32; 3],
+    // Degrees about the vertical
+    #[serde(default)]
+    pub yaw: f32,
+    // Indices of earlier modules it's connected to
+    #[serde(default)]
+    pub connect: Vec<usize>,
+    // Fitted on top of the ones the type comes with
+    #[serde(default)]
+    pub elements: Vec<LayoutElement>,
+}
+
+// A ring section, with its modules by their place in the list above
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayoutRing {
+    pub name: String,
+    pub centre: [f32; 3],
+    pub axis: [f32; 3],
+    pub rpm: f32,
+    pub modules: Vec<usize>,
+    #[serde(default)]
+    pub angle: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LayoutElement {
+    #[serde(rename = "type")]
+    pub element_type: InteractionType,
+    // Relative to the module's centre
+    pub position: [f32; 3],
+    #[serde(default)]
+    pub state: Option<ElementState>,
+}
+
+impl StationLayout {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("failed to read station layout from {}", path.display()))?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => serde_json::from_str(&source).context("invalid station layout"),
+            Some("toml") => toml::from_str(&source).context("invalid station layout"),
+            _ => bail!("station layouts are .json or .toml, not {}", path.display()),
+        }
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let text = match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => serde_json::to_string_pretty(self).context("failed to serialize station layout")?,
+            Some("toml") => toml::to_string_pretty(self).context("failed to serialize station layout")?,
+            _ => bail!("station layouts are .json or .toml, not {}", path.display()),
+        };
+        fs::write(path, text).with_context(|| format!("failed to write station layout to {}", path.display()))
+    }
+
+    // The layout a station was built to, each connection listed on the later
+    // of its two modules. Wear, air and element states aren't part of it.
+    pub fn of(station: &SpaceStation) -> Self {
+        let modules = station
+            .modules()
+            .iter()
+            .enumerate()
+            .map(|(i, module)| {
+                let (yaw, _, _) = module.transform.rotation.to_euler(EulerRot::YXZ);
+                let mut connect: Vec<usize> = station.neighbours(i).into_iter().filter(|&other| other < i).collect();
+                connect.sort_unstable();
+                LayoutModule {
+                    module_type: module.module_type,
+                    position: module.transform.position.to_array(),
+                    yaw: yaw.to_degrees(),
+                    connect,
+                    elements: Vec::new(),
+                }
+            })
+            .collect();
+        let rings = station
+            .ring_specs()
+            .into_iter()
+            .map(|spec| LayoutRing {
+                modules: spec.modules.iter().filter_map(|&id| station.module_index(id)).collect(),
+                name: spec.name,
+                centre: spec.centre,
+                axis: spec.axis,
+                rpm: spec.rpm,
+                angle: spec.angle,
+            })
+            .collect();
+        Self { modules, rings, backdrop: None }
+    }
+
+    // Builds the station. Connections have to be to earlier modules, within
+    // reach, and between docking ports that line up.
+    pub fn build(&self) -> Result<SpaceStation> {
+        let mut station = SpaceStation::new();
+        for (i, planned) in self.modules.iter().enumerate() {
+            let index = station.add_module(planned.module_type, Vec3::from(planned.position));
+            let Some(module) = station.module_mut(index) else {
+                continue;
+            };
+            module.transform.rotation = Quat::from_rotation_y(planned.yaw.to_radians());
+            for element in &planned.elements {
+                module.add_element(element.element_type, Vec3::from(element.position), element.state);
+            }
+            for &other in &planned.connect {
+                if other >= i {
+                    bail!("module {} connects to module {}, which isn't before it", i, other);
+                }
+                if !station.connect_modules(other, i) {
+                    bail!("modules {} and {} are too far apart or have no docking ports that line up", other, i);
+                }
+            }
+        }
+        // A station built fresh gives each module the id for its place
+        for ring in &self.rings {
+            let spec = RingSpec {
+                name: ring.name.clone(),
+                centre: ring.centre,
+                axis: ring.axis,
+                rpm: ring.rpm,
+                modules: ring.modules.iter().map(|&index| ModuleId::from_layout(index)).collect(),
+                angle: ring.angle,
+            };
+            if station.add_ring(&spec).is_none() {
+                bail!("ring {} lists a missing module, or one that's in another ring", ring.name);
+            }
+        }
+        Ok(station)
+    }
+}
+
+// `--layout <path>` loads a station from a layout file
+pub fn path_from_args(args: &[String]) -> Result<Option<PathBuf>> {
+    let Some(i) = args.iter().position(|arg| arg == "--layout") else {
+        return Ok(None);
+    };
+    let value = args.get(i + 1).context("--layout needs a path")?;
+    Ok(Some(PathBuf::from(value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::station::{ElementSnapshot, InteractiveElement};
+
+    #[test]
+    fn layouts_rebuild_stations_and_live_types_serialize_as_snapshots() {
+        let standard = SpaceStation::create_default_layout();
+        let rebuilt = standard.layout().build().unwrap();
+        assert_eq!(rebuilt.layout(), standard.layout());
+        assert_eq!(rebuilt.doors().len(), standard.doors().len());
+
+        let authored: StationLayout = serde_json::from_str(
+            r#"{ "module": [
+                { "type": "Hub", "position": [0.0, 0.0, 0.0] },
+                { "type": "Airlock", "position": [0.0, 0.0, -7.0], "yaw": 90.0, "connect": [0],
+                  "elements": [{ "type": "Light", "position": [0.0, 2.0, 0.0], "state": "Active" }] }
+            ] }"#,
+        )
+        .unwrap();
+        let station = authored.build().unwrap();
+        let airlock = &station.modules()[1];
+        assert_eq!(station.neighbours(1), vec![0]);
+        assert!((station.layout().modules[1].yaw - 90.0).abs() < 0.01);
+        let light = airlock.interactive_elements.iter().find(|e| e.element_type == InteractionType::Light).unwrap();
+        assert_eq!(light.state, ElementState::Active);
+
+        // Connections must be to earlier modules, within reach
+        let mut broken = authored.clone();
+        broken.modules[1].position = [0.0, 0.0, -40.0];
+        assert!(broken.build().is_err());
+        broken.modules[1].connect = vec![1];
+        assert!(broken.build().is_err());
+
+        let json = serde_json::to_string(&station).unwrap();
+        let loaded: SpaceStation = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.snapshot(), station.snapshot());
+        let element: InteractiveElement = serde_json::from_str(&serde_json::to_string(light).unwrap()).unwrap();
+        assert_eq!(ElementSnapshot::from(&element), ElementSnapshot::from(light));
+    }
+}
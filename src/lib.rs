@@ -0,0 +1,105 @@
+// Simulation and game systems. Nothing here opens a window, so the station
+// can also be driven headless (see `headless::simulate`).
+pub mod alarm;
+pub mod analytics;
+pub mod atmosphere;
+pub mod attract;
+pub mod avatar;
+pub mod backdrop;
+pub mod benchmark;
+pub mod bug_report;
+pub mod camera_shake;
+pub mod chat;
+pub mod checkpoint;
+pub mod clip;
+pub mod clock;
+pub mod comms;
+pub mod config;
+pub mod console;
+pub mod construction;
+pub mod crew;
+pub mod cursor_focus;
+pub mod datapads;
+pub mod debug_overlay;
+pub mod director;
+pub mod docking;
+pub mod door;
+pub mod drone;
+pub mod drill;
+pub mod dynamic_lights;
+pub mod effects;
+pub mod emi;
+pub mod eva;
+pub mod evacuation;
+pub mod event_log;
+pub mod fast_travel;
+pub mod events;
+pub mod ffi;
+pub mod fluid;
+pub mod fly_camera;
+pub mod geometry;
+pub mod gltf;
+pub mod graphviz;
+pub mod gpu;
+pub mod grime;
+pub mod guidance_strips;
+pub mod handheld;
+pub mod haptics;
+pub mod hazard;
+pub mod headless;
+pub mod holotable;
+pub mod hints;
+pub mod incidents;
+pub mod input;
+pub mod inventory;
+pub mod joints;
+pub mod layout;
+pub mod life_support;
+pub mod locale;
+pub mod lockstep;
+pub mod maintenance;
+pub mod map;
+pub mod markers;
+pub mod material;
+pub mod module_id;
+pub mod navigation;
+pub mod notifications;
+pub mod objectives;
+pub mod observer;
+pub mod orbit;
+pub mod paint;
+pub mod ports;
+pub mod procgen;
+pub mod quick_menu;
+pub mod radial;
+pub mod radiation;
+pub mod panorama;
+pub mod particle;
+#[cfg(feature = "python")]
+pub mod python;
+pub mod reconcile;
+pub mod repair;
+pub mod research;
+pub mod replication;
+pub mod resources;
+pub mod reverb;
+pub mod ring;
+pub mod rng;
+pub mod rotation;
+pub mod save;
+pub mod signage;
+pub mod solar;
+pub mod starfield;
+pub mod state_diff;
+pub mod station;
+pub mod station_builder;
+pub mod stepping;
+pub mod structure;
+pub mod terminal;
+pub mod texture_streaming;
+pub mod thermal;
+pub mod tunables;
+pub mod usd;
+pub mod vertex;
+pub mod voice;
+pub mod window_light;
@@ -0,0 +1,160 @@
+use serde::{Deserialize, Serialize};
+use crate::station::ModuleType;
+
+// Filters clog up to this share of their life before the throughput starts
+// dropping off
+const CLEAN_SHARE: f32 = 0.5;
+// What a clogged-up filter still lets through, just before it fails
+const CLOGGED_EFFICIENCY: f32 = 0.25;
+
+// The machines that keep a module's air breathable and its water drinkable
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LifeSupportKind {
+    // Splits water for oxygen and makes up the air lost to leaks
+    OxygenGenerator,
+    // Pulls CO2 out of the air and reclaims the oxygen in it
+    Co2Scrubber,
+    // Recovers the crew's water from the air and the waste lines
+    WaterRecycler,
+}
+
+impl LifeSupportKind {
+    pub const ALL: [LifeSupportKind; 3] =
+        [LifeSupportKind::OxygenGenerator, LifeSupportKind::Co2Scrubber, LifeSupportKind::WaterRecycler];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            LifeSupportKind::OxygenGenerator => "O2 generator",
+            LifeSupportKind::Co2Scrubber => "CO2 scrubber",
+            LifeSupportKind::WaterRecycler => "Water recycler",
+        }
+    }
+
+    // As typed in the console
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "o2" | "oxygen_generator" => Some(LifeSupportKind::OxygenGenerator),
+            "co2" | "scrubber" | "co2_scrubber" => Some(LifeSupportKind::Co2Scrubber),
+            "water" | "recycler" | "water_recycler" => Some(LifeSupportKind::WaterRecycler),
+            _ => None,
+        }
+    }
+
+    // kW at a throughput of 1
+    pub fn power_draw(self) -> f32 {
+        match self {
+            LifeSupportKind::OxygenGenerator => 1.5,
+            LifeSupportKind::Co2Scrubber => 1.0,
+            LifeSupportKind::WaterRecycler => 0.5,
+        }
+    }
+
+    // Running hours one filter lasts
+    pub fn filter_hours(self) -> f32 {
+        match self {
+            LifeSupportKind::OxygenGenerator => 600.0,
+            LifeSupportKind::Co2Scrubber => 300.0,
+            LifeSupportKind::WaterRecycler => 450.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UnitState {
+    Running,
+    Off,
+    // Clogged solid; needs a new filter before it'll run again
+    Failed,
+}
+
+// One machine installed in a module
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LifeSupportUnit {
+    pub kind: LifeSupportKind,
+    // In units of the station's tunable rates for the kind (scrubber_rate,
+    // replenish_rate, water_recovery)
+    pub throughput: f32,
+    pub state: UnitState,
+    // Filter life left, 1 new to 0 clogged
+    pub filter: f32,
+}
+
+impl LifeSupportUnit {
+    pub fn new(kind: LifeSupportKind, throughput: f32) -> Self {
+        Self { kind, throughput, state: UnitState::Running, filter: 1.0 }
+    }
+
+    pub fn running(&self) -> bool {
+        self.state == UnitState::Running
+    }
+
+    pub fn power_draw(&self) -> f32 {
+        if self.running() { self.kind.power_draw() * self.throughput } else { 0.0 }
+    }
+
+    // Share of its throughput getting through the filter: all of it while the
+    // filter's fresh, falling away as it clogs
+    pub fn efficiency(&self) -> f32 {
+        if !self.running() {
+            return 0.0;
+        }
+        let clogging = (1.0 - self.filter / CLEAN_SHARE).clamp(0.0, 1.0);
+        1.0 - (1.0 - CLOGGED_EFFICIENCY) * clogging
+    }
+
+    pub fn output(&self) -> f32 {
+        self.throughput * self.efficiency()
+    }
+
+    // Wears the filter for `hours` of running; true if that clogged it and
+    // the unit failed
+    pub fn wear(&mut self, hours: f32) -> bool {
+        if !self.running() {
+            return false;
+        }
+        self.filter = (self.filter - hours / self.kind.filter_hours()).max(0.0);
+        if self.filter > 0.0 {
+            return false;
+        }
+        self.state = UnitState::Failed;
+        true
+    }
+
+    // A fresh filter; a failed unit comes back on
+    pub fn replace_filter(&mut self) {
+        self.filter = 1.0;
+        if self.state == UnitState::Failed {
+            self.state = UnitState::Running;
+        }
+    }
+
+    // False if it's failed and can't be switched until it's had a new filter
+    pub fn switch(&mut self, on: bool) -> bool {
+        if self.state == UnitState::Failed {
+            return false;
+        }
+        self.state = if on { UnitState::Running } else { UnitState::Off };
+        true
+    }
+}
+
+// What a module of each type comes with. The quarters and hub carry the
+// station's main plant; the command centre has a smaller backup set.
+pub fn standard_fit(module_type: ModuleType) -> Vec<LifeSupportUnit> {
+    let sizes: &[(LifeSupportKind, f32)] = match module_type {
+        ModuleType::LivingQuarters | ModuleType::Hub => &[
+            (LifeSupportKind::OxygenGenerator, 1.0),
+            (LifeSupportKind::Co2Scrubber, 1.0),
+            (LifeSupportKind::WaterRecycler, 1.0),
+        ],
+        ModuleType::CommandCenter => &[
+            (LifeSupportKind::OxygenGenerator, 0.5),
+            (LifeSupportKind::Co2Scrubber, 0.5),
+            (LifeSupportKind::WaterRecycler, 1.0),
+        ],
+        _ => &[],
+    };
+    sizes.iter().map(|&(kind, throughput)| LifeSupportUnit::new(kind, throughput)).collect()
+}
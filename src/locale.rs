@@ -0,0 +1,135 @@
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::fs;
+use std::path::Path;
+use anyhow::{bail, Context, Result};
+
+// Strings every language falls back to
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+// UI text by string ID, from assets/lang/<language>.toml. Tables in the file
+// nest into dotted IDs, so `[hud] controls = "..."` is `hud.controls`.
+// Placeholders are written `{name}` and filled in by `format`.
+#[derive(Debug)]
+pub struct Locale {
+    language: String,
+    strings: HashMap<String, String>,
+    // English, for IDs a translation hasn't caught up with yet
+    fallback: HashMap<String, String>,
+}
+
+impl Default for Locale {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Locale {
+    // No strings at all; every lookup shows its ID
+    pub fn new() -> Self {
+        Self {
+            language: DEFAULT_LANGUAGE.to_string(),
+            strings: HashMap::new(),
+            fallback: HashMap::new(),
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(dir: P, language: &str) -> Result<Self> {
+        let dir = dir.as_ref();
+        // Language codes become file names, so keep them to plain tags like en or pt-BR
+        if language.is_empty() || !language.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+            bail!("invalid language {:?}", language);
+        }
+        let fallback = read_strings(&dir.join(format!("{}.toml", DEFAULT_LANGUAGE)))?;
+        let strings = if language == DEFAULT_LANGUAGE {
+            HashMap::new()
+        } else {
+            read_strings(&dir.join(format!("{}.toml", language)))?
+        };
+        Ok(Self {
+            language: language.to_string(),
+            strings,
+            fallback,
+        })
+    }
+
+    pub fn language(&self) -> &str {
+        &self.language
+    }
+
+    // The text for an ID, or the ID itself if no language has it
+    pub fn get<'a>(&'a self, id: &'a str) -> &'a str {
+        self.strings
+            .get(id)
+            .or_else(|| self.fallback.get(id))
+            .map_or(id, String::as_str)
+    }
+
+    // Like `get`, with each `{name}` replaced by its value
+    pub fn format(&self, id: &str, args: &[(&str, &dyn Display)]) -> String {
+        let mut text = self.get(id).to_string();
+        for (name, value) in args {
+            text = text.replace(&format!("{{{}}}", name), &value.to_string());
+        }
+        text
+    }
+
+    // IDs the English file has and this language doesn't, for translators
+    pub fn missing(&self) -> Vec<&str> {
+        if self.language == DEFAULT_LANGUAGE {
+            return Vec::new();
+        }
+        let mut missing: Vec<&str> = self
+            .fallback
+            .keys()
+            .filter(|id| !self.strings.contains_key(*id))
+            .map(String::as_str)
+            .collect();
+        missing.sort_unstable();
+        missing
+    }
+}
+
+// Language codes with a file in the directory
+pub fn available_languages<P: AsRef<Path>>(dir: P) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut languages: Vec<String> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "toml"))
+        .filter_map(|path| path.file_stem().map(|stem| stem.to_string_lossy().into_owned()))
+        .collect();
+    languages.sort();
+    languages
+}
+
+fn read_strings(path: &Path) -> Result<HashMap<String, String>> {
+    let source = fs::read_to_string(path)
+        .with_context(|| format!("failed to read language file {}", path.display()))?;
+    let table: toml::Table = toml::from_str(&source)
+        .with_context(|| format!("invalid language file {}", path.display()))?;
+    let mut strings = HashMap::new();
+    flatten("", &table, &mut strings)
+        .with_context(|| format!("invalid language file {}", path.display()))?;
+    Ok(strings)
+}
+
+fn flatten(prefix: &str, table: &toml::Table, strings: &mut HashMap<String, String>) -> Result<()> {
+    for (key, value) in table {
+        let id = if prefix.is_empty() {
+            key.clone()
+        } else {
+            format!("{}.{}", prefix, key)
+        };
+        match value {
+            toml::Value::String(text) => {
+                strings.insert(id, text.clone());
+            }
+            toml::Value::Table(table) => flatten(&id, table, strings)?,
+            _ => bail!("{} must be a string", id),
+        }
+    }
+    Ok(())
+}
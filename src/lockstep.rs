@@ -0,0 +1,417 @@
+use std::collections::{BTreeMap, VecDeque};
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use crate::module_id::ModuleId;
+use crate::replication::SEND_INTERVAL;
+use crate::station::{SpaceStation, StationSnapshot};
+use crate::stepping::STEP_DT;
+
+// Ticks between pressing something and it taking effect, so the press can
+// reach the other machine first: 100 ms at 60 Hz
+pub const INPUT_DELAY: u64 = 6;
+// Each side hashes its station this often and sends the hash along
+pub const CHECKSUM_INTERVAL: u64 = 30;
+// Two-player co-op; the host is player 0
+pub const PLAYERS: usize = 2;
+// Executed ticks' inputs kept, in case a resync puts the clock back a little
+const KEPT_TICKS: u64 = 2 * INPUT_DELAY + 1;
+// Hashes kept waiting for the other side's
+const KEPT_CHECKSUMS: u64 = 10 * CHECKSUM_INTERVAL;
+
+// FNV-1a, which is the same on every machine (unlike std's hasher)
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+// Something a player does to the station. Only these cross the wire; both
+// machines apply them on the same tick, in player order.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "input", rename_all = "snake_case")]
+pub enum LockstepInput {
+    Activate { module: ModuleId, element: usize },
+    Repair { module: ModuleId, amount: f32 },
+    Dim { module: ModuleId, level: f32 },
+}
+
+impl LockstepInput {
+    pub fn module(self) -> ModuleId {
+        match self {
+            LockstepInput::Activate { module, .. }
+            | LockstepInput::Repair { module, .. }
+            | LockstepInput::Dim { module, .. } => module,
+        }
+    }
+
+    // False if the station turned it down, which it does on both sides
+    // alike; a module removed before the input lands turns it down too
+    pub fn apply(self, station: &mut SpaceStation) -> bool {
+        let Some(index) = station.module_index(self.module()) else {
+            return false;
+        };
+        match self {
+            LockstepInput::Activate { element, .. } => station.activate_element(index, element).is_some(),
+            LockstepInput::Repair { amount, .. } => station.repair_module(index, amount),
+            LockstepInput::Dim { level, .. } => station.set_light_level(index, level),
+        }
+    }
+}
+
+// One tick's worth from one player: what they did, for the tick it takes
+// effect, and their latest hash of the station. Frames need a transport
+// that delivers them all and in order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InputFrame {
+    pub player: usize,
+    // Bumped by each resync, so hashes from before one are let go
+    pub generation: u32,
+    pub tick: u64,
+    pub inputs: Vec<LockstepInput>,
+    pub checksum: Option<(u64, u64)>,
+}
+
+impl InputFrame {
+    // Size on the wire
+    pub fn bytes(&self) -> usize {
+        serde_json::to_vec(self).map_or(0, |bytes| bytes.len())
+    }
+}
+
+// The host's way out of a desync: its station as it stood at `tick`, which
+// both sides rebuild from
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Resync {
+    pub generation: u32,
+    pub tick: u64,
+    pub snapshot: StationSnapshot,
+}
+
+impl Resync {
+    pub fn bytes(&self) -> usize {
+        serde_json::to_vec(self).map_or(0, |bytes| bytes.len())
+    }
+}
+
+// Hash of everything a snapshot holds. The sim is deterministic for a
+// seed, so two machines that have had the same inputs agree on it.
+pub fn checksum(station: &SpaceStation) -> u64 {
+    let bytes = serde_json::to_vec(&station.snapshot()).unwrap_or_default();
+    bytes.iter().fold(FNV_OFFSET, |hash, &byte| (hash ^ byte as u64).wrapping_mul(FNV_PRIME))
+}
+
+// One machine's side of lockstep co-op: instead of sending the station, both
+// run it and only swap inputs. A tick can't run until both players' inputs
+// for it are in, and the hashes they send along catch the two drifting apart.
+#[derive(Debug)]
+pub struct Lockstep {
+    player: usize,
+    generation: u32,
+    tick: u64,
+    // Next tick this side owes the other a frame for
+    next_send: u64,
+    // Tick of the last hash sent; each goes once
+    checksum_sent: u64,
+    inputs: BTreeMap<u64, [Option<Vec<LockstepInput>>; PLAYERS]>,
+    pending: Vec<LockstepInput>,
+    checksums: BTreeMap<u64, u64>,
+    remote_checksums: BTreeMap<u64, u64>,
+    // Tick the hashes first disagreed at
+    desync: Option<u64>,
+}
+
+impl Lockstep {
+    pub fn new(player: usize) -> Self {
+        // Nobody can have pressed anything for the first ticks
+        let inputs = (0..INPUT_DELAY).map(|tick| (tick, [Some(Vec::new()), Some(Vec::new())])).collect();
+        Self {
+            player: player.min(PLAYERS - 1),
+            generation: 0,
+            tick: 0,
+            next_send: INPUT_DELAY,
+            checksum_sent: 0,
+            inputs,
+            pending: Vec::new(),
+            checksums: BTreeMap::new(),
+            remote_checksums: BTreeMap::new(),
+            desync: None,
+        }
+    }
+
+    pub fn player(&self) -> usize {
+        self.player
+    }
+
+    pub fn is_host(&self) -> bool {
+        self.player == 0
+    }
+
+    // Ticks run so far
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn desync(&self) -> Option<u64> {
+        self.desync
+    }
+
+    // Goes out in the next frame
+    pub fn press(&mut self, input: LockstepInput) {
+        self.pending.push(input);
+    }
+
+    // The frame due to the other side, if one is: the inputs pressed since
+    // the last, to take effect INPUT_DELAY ticks on
+    pub fn send(&mut self) -> Option<InputFrame> {
+        if self.next_send > self.tick + INPUT_DELAY {
+            return None;
+        }
+        let tick = self.next_send;
+        self.next_send += 1;
+        let inputs = std::mem::take(&mut self.pending);
+        self.inputs.entry(tick).or_default()[self.player] = Some(inputs.clone());
+        let checksum = self.checksums.last_key_value().map(|(&tick, &hash)| (tick, hash));
+        let checksum = checksum.filter(|&(tick, _)| tick > self.checksum_sent);
+        if let Some((tick, _)) = checksum {
+            self.checksum_sent = tick;
+        }
+        Some(InputFrame { player: self.player, generation: self.generation, tick, inputs, checksum })
+    }
+
+    pub fn receive(&mut self, frame: InputFrame) -> Result<()> {
+        if frame.player == self.player || frame.player >= PLAYERS {
+            bail!("frame from player {} can't be the other side's", frame.player);
+        }
+        if frame.tick >= self.tick.saturating_sub(KEPT_TICKS) {
+            self.inputs.entry(frame.tick).or_default()[frame.player] = Some(frame.inputs);
+        }
+        if let Some((tick, hash)) = frame.checksum.filter(|_| frame.generation == self.generation) {
+            self.remote_checksums.insert(tick, hash);
+            self.compare(tick);
+        }
+        Ok(())
+    }
+
+    // Whether both players' inputs for the next tick are in
+    pub fn ready(&self) -> bool {
+        self.inputs.get(&self.tick).is_some_and(|players| players.iter().all(Option::is_some))
+    }
+
+    // Runs the next tick if it can. Inputs go in player order, then the
+    // station steps; nothing else may touch it in between.
+    pub fn step(&mut self, station: &mut SpaceStation) -> bool {
+        if !self.ready() {
+            return false;
+        }
+        for inputs in self.inputs[&self.tick].iter().flatten() {
+            for input in inputs {
+                input.apply(station);
+            }
+        }
+        station.update(STEP_DT);
+        self.tick += 1;
+        if self.tick.is_multiple_of(CHECKSUM_INTERVAL) {
+            self.checksums.insert(self.tick, checksum(station));
+            self.compare(self.tick);
+        }
+        let oldest = self.tick.saturating_sub(KEPT_TICKS);
+        self.inputs.retain(|&tick, _| tick >= oldest);
+        let oldest = self.tick.saturating_sub(KEPT_CHECKSUMS);
+        self.checksums.retain(|&tick, _| tick >= oldest);
+        self.remote_checksums.retain(|&tick, _| tick >= oldest);
+        true
+    }
+
+    fn compare(&mut self, tick: u64) {
+        let (Some(local), Some(remote)) = (self.checksums.get(&tick), self.remote_checksums.get(&tick)) else {
+            return;
+        };
+        if local != remote && self.desync.is_none_or(|first| tick < first) {
+            self.desync = Some(tick);
+        }
+    }
+
+    // Host only: rebuilds its own station from a snapshot of itself, as the
+    // guest will from what's returned, so both go on from the same state
+    // (and the same random numbers)
+    pub fn resync(&mut self, station: &mut SpaceStation) -> Result<Resync> {
+        if !self.is_host() {
+            bail!("only the host can resync");
+        }
+        let snapshot = station.snapshot();
+        *station = SpaceStation::from_snapshot(&snapshot);
+        self.restart(self.generation + 1, self.tick);
+        Ok(Resync { generation: self.generation, tick: self.tick, snapshot })
+    }
+
+    // Guest side of a resync. Inputs already in for ticks from the resync
+    // on still stand; the host has them too.
+    pub fn apply_resync(&mut self, station: &mut SpaceStation, resync: &Resync) -> Result<()> {
+        if self.is_host() {
+            bail!("the host doesn't take resyncs");
+        }
+        *station = SpaceStation::from_snapshot(&resync.snapshot);
+        self.restart(resync.generation, resync.tick);
+        Ok(())
+    }
+
+    fn restart(&mut self, generation: u32, tick: u64) {
+        self.generation = generation;
+        self.tick = tick;
+        self.next_send = self.next_send.max(tick);
+        self.checksum_sent = tick;
+        self.inputs.retain(|&at, _| at >= tick);
+        self.checksums.clear();
+        self.remote_checksums.clear();
+        self.desync = None;
+    }
+}
+
+// How a rehearsal went
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rehearsal {
+    pub ticks: u64,
+    // Frames where a side had to wait on the other's inputs
+    pub stalls: u64,
+    pub frame_bytes: usize,
+    // What replicating whole snapshots would have cost over the same time
+    pub snapshot_bytes: usize,
+    // Hashes swapped, both ways
+    pub checksums_sent: u64,
+    pub desync: Option<u64>,
+}
+
+impl Rehearsal {
+    pub fn bytes_per_second(&self) -> f32 {
+        self.frame_bytes as f32 / (self.ticks.max(1) as f32 * STEP_DT)
+    }
+
+    pub fn snapshot_bytes_per_second(&self) -> f32 {
+        self.snapshot_bytes as f32 / (self.ticks.max(1) as f32 * STEP_DT)
+    }
+}
+
+// Runs a host and a guest side by side from the same snapshot, over a pretend
+// link `latency` ticks long, pressing what `script` says (tick, player,
+// input). There's no transport yet, so this is how lockstep gets exercised.
+pub fn rehearse(
+    snapshot: &StationSnapshot,
+    ticks: u64,
+    latency: u64,
+    script: &[(u64, usize, LockstepInput)],
+) -> Rehearsal {
+    let mut stations = [SpaceStation::from_snapshot(snapshot), SpaceStation::from_snapshot(snapshot)];
+    let mut sides = [Lockstep::new(0), Lockstep::new(1)];
+    // Frames on their way, with the frame they land on
+    let mut links: [VecDeque<(u64, InputFrame)>; PLAYERS] = [VecDeque::new(), VecDeque::new()];
+    let mut rehearsal = Rehearsal {
+        ticks: 0,
+        stalls: 0,
+        frame_bytes: 0,
+        snapshot_bytes: 0,
+        checksums_sent: 0,
+        desync: None,
+    };
+    let mut pressed = [None; PLAYERS];
+    let mut frame = 0;
+    // Gives up if a side stops getting anywhere, rather than spinning
+    while sides.iter().any(|side| side.tick() < ticks) && frame < ticks * 2 + latency * 4 + 60 {
+        for (player, side) in sides.iter_mut().enumerate() {
+            // Once a tick, however long it's stalled on
+            let tick = side.tick();
+            if pressed[player] != Some(tick) {
+                pressed[player] = Some(tick);
+                for &(_, _, input) in script.iter().filter(|(at, who, _)| *at == tick && *who == player) {
+                    side.press(input);
+                }
+            }
+            if let Some(sent) = side.send() {
+                rehearsal.frame_bytes += sent.bytes();
+                rehearsal.checksums_sent += sent.checksum.is_some() as u64;
+                links[1 - player].push_back((frame + latency, sent));
+            }
+        }
+        for (player, side) in sides.iter_mut().enumerate() {
+            while links[player].front().is_some_and(|(lands, _)| *lands <= frame) {
+                if let Some((_, received)) = links[player].pop_front() {
+                    // Frames between our own two sides are always well formed
+                    let _ = side.receive(received);
+                }
+            }
+            if side.tick() < ticks && !side.step(&mut stations[player]) {
+                rehearsal.stalls += 1;
+            }
+            stations[player].drain_events();
+        }
+        frame += 1;
+    }
+    rehearsal.ticks = sides[0].tick().min(sides[1].tick());
+    let snapshot_bytes = serde_json::to_vec(snapshot).map_or(0, |bytes| bytes.len());
+    rehearsal.snapshot_bytes = snapshot_bytes * rehearsal.ticks.div_ceil(SEND_INTERVAL) as usize;
+    rehearsal.desync = sides.iter().filter_map(Lockstep::desync).min();
+    rehearsal
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::station::{ElementState, InteractionType};
+
+    #[test]
+    fn lockstep_peers_swap_only_inputs_and_resync_when_their_hashes_part() {
+        let station = SpaceStation::create_default_layout();
+        let (index, light) = station
+            .modules()
+            .iter()
+            .enumerate()
+            .find_map(|(i, module)| {
+                let mut switches = module.interactive_elements.iter();
+                switches.position(|e| e.element_type == InteractionType::LightControl).map(|light| (i, light))
+            })
+            .unwrap();
+        let module = station.modules()[index].id;
+        let script = [
+            (10, 0, LockstepInput::Activate { module, element: light }),
+            (10, 1, LockstepInput::Dim { module, level: 0.5 }),
+            (200, 1, LockstepInput::Repair { module: station.modules()[5].id, amount: 0.1 }),
+        ];
+        let rehearsal = rehearse(&station.snapshot(), 600, 4, &script);
+        assert_eq!((rehearsal.ticks, rehearsal.desync), (600, None));
+        assert!(rehearsal.checksums_sent >= 2 * (600 / CHECKSUM_INTERVAL - 1));
+        assert!(rehearsal.bytes_per_second() * 10.0 < rehearsal.snapshot_bytes_per_second());
+
+        // Straight swaps: inputs land INPUT_DELAY ticks on, on both sides
+        let snapshot = station.snapshot();
+        let mut stations = [SpaceStation::from_snapshot(&snapshot), SpaceStation::from_snapshot(&snapshot)];
+        let mut sides = [Lockstep::new(0), Lockstep::new(1)];
+        let run = |sides: &mut [Lockstep; 2], stations: &mut [SpaceStation; 2], ticks: u64| {
+            for _ in 0..ticks {
+                for player in 0..2 {
+                    if let Some(frame) = sides[player].send() {
+                        sides[1 - player].receive(frame).unwrap();
+                    }
+                }
+                for player in 0..2 {
+                    assert!(sides[player].step(&mut stations[player]));
+                }
+            }
+        };
+        sides[1].press(LockstepInput::Activate { module, element: light });
+        run(&mut sides, &mut stations, INPUT_DELAY);
+        assert_eq!(stations[0].modules()[index].interactive_elements[light].state, ElementState::Inactive);
+        run(&mut sides, &mut stations, 1);
+        assert_ne!(stations[0].modules()[index].interactive_elements[light].state, ElementState::Inactive);
+        run(&mut sides, &mut stations, 60);
+        assert_eq!(checksum(&stations[0]), checksum(&stations[1]));
+
+        // Something outside the inputs touches one side; the next hashes
+        // catch it and the host's snapshot puts both back together
+        stations[1].damage_module(5, 0.3);
+        run(&mut sides, &mut stations, 2 * CHECKSUM_INTERVAL);
+        assert!(sides[0].desync().is_some() && sides[1].desync().is_some());
+        assert!(sides[1].resync(&mut stations[1]).is_err());
+        let resync = sides[0].resync(&mut stations[0]).unwrap();
+        sides[1].apply_resync(&mut stations[1], &resync).unwrap();
+        run(&mut sides, &mut stations, 2 * CHECKSUM_INTERVAL);
+        assert_eq!((sides[0].desync(), sides[1].desync()), (None, None));
+        assert_eq!(sides[0].tick(), sides[1].tick());
+        assert_eq!(checksum(&stations[0]), checksum(&stations[1]));
+    }
+}
@@ -1,13 +1,217 @@
+use space_station_3d::{
+    alarm, analytics, attract, avatar, backdrop, benchmark, bug_report, camera_shake, chat, checkpoint, clip, comms, config, console, construction, crew, cursor_focus, datapads, debug_overlay, docking, door, drill, drone, dynamic_lights,
+    effects, emi, eva, evacuation, event_log, events, fast_travel, fluid, fly_camera, gpu, grime, guidance_strips, handheld, haptics, headless, hints, holotable, incidents, input, inventory, joints, layout, locale, map, markers, notifications, objectives, observer, orbit,
+    paint, panorama, particle, procgen, quick_menu, radial, radiation, repair, replication, research, resources, reverb, ring, rng, save, signage, solar, starfield, state_diff, station, stepping, terminal, texture_streaming, thermal, tunables, usd, window_light,
+};
+
+use std::time::{Duration, Instant};
+use alarm::Alarm;
+use analytics::{Analytics, AnalyticsEvent, DeathCause, ScenarioKind};
+use attract::{AttractChange, AttractMode};
+use backdrop::{Backdrop, BackdropConfig};
+use benchmark::{Benchmark, BenchmarkOptions, BURST_DURATION};
+use bug_report::{BugReport, SystemInfo};
+use avatar::{AvatarLook, Helmet};
+use camera_shake::CameraShake;
+use chat::{Chat, Emote};
+use checkpoint::Checkpoint;
+use clip::{ClipFrame, ClipRecorder, CLIP_WIDTH};
+use comms::Comms;
+use config::{Config, ConfigFile};
+use cursor_focus::{CursorChange, CursorFocus};
+use crew::{Activity, Crew};
+use datapads::Datapads;
+use debug_overlay::DebugOverlay;
+use docking::{Approach, Docking, DockingChange, DockingStage, RcsCommand};
+use door::Door;
+use drone::{Drone, DroneState};
+use drill::{Drill, DrillHistory, DrillReport};
+use dynamic_lights::{cabin_ambient, ceiling_lights, light_pool, HelmetLight, LightPool, LightSet, Room, WorkLights};
+use effects::EventEffects;
+use eva::{Eva, EvaEvent, EvaPhase, CYCLE_SECONDS, TETHER_LENGTH};
+use evacuation::Evacuation;
+use event_log::LogSeverity;
+use events::StationEvent;
+use fluid::{metaball_cells, ScreenBlob, Spills};
+use fly_camera::{CameraPose, FlyCamera, FlyInput, BOOKMARK_SLOTS};
+use gpu::{GpuInfo, GpuPreference};
+use guidance_strips::{GuidanceStrip, GuidanceStrips};
+use headless::HeadlessOptions;
+use handheld::{DisplaySettings, ShadowQuality};
+use construction::{Construction, Ghost};
+use fast_travel::FastTravel;
+use haptics::{HapticCategory, Haptics, DOOR_THUNK};
+use holotable::{Holotable, Miniature, HOLOTABLE_REACH};
+use hints::HintEngine;
+use incidents::Incidents;
+use inventory::{Inventory, ItemKind, WorldItems, INVENTORY_SLOTS};
+use layout::StationLayout;
+use locale::Locale;
+use input::{Action, Input, InputBindings, InputContext, KeyCode, KeySource};
+use joints::Props;
+use map::{MapLayout, MapView, StationMap};
+use markers::{MarkerKind, Markers};
+use notifications::Notifications;
+use observer::Observer;
+use paint::{StyleGuide, Surface};
+use procgen::LayoutParams;
+use panorama::PanoramaCapture;
+use objectives::Objectives;
+use console::{Console, ConsoleContext};
+use orbit::OrbitPreview;
+use particle::ParticleType;
+use quick_menu::{QuickAction, QuickMenu};
+use radial::RadialMenu;
+use radiation::{DoseLevel, Dosimeter};
+use repair::RepairJob;
+use replication::Replicator;
+use research::Research;
+use reverb::ReverbZones;
+use save::{SaveGame, SaveMenu, SaveSlot};
+use signage::{Sign, SignKind, Signage};
 use raylib::prelude::*;
+use starfield::{Starfield, StarfieldConfig, STAR_FRAGMENT_SHADER, STAR_VERTEX_SHADER};
+use state_diff::{SnapshotHistory, StateDiff, HISTORY_TICKS};
+use station::{Activation, ElementState, InteractionType, ModuleType, SpaceStation, StationModule};
+use stepping::SimStepper;
+use resources::Resource;
+use terminal::{Terminal, TerminalPage};
+use texture_streaming::{StreamCommand, TextureStreamer};
+use thermal::Comfort;
+use tunables::{Tunables, TunablesFile, TuningPanel};
+use usd::{SceneFrame, SceneRecorder};
+use window_light::{project_sun_patch, WindowAperture};
+
+// Temporary files the F8 screenshot and clip are written to before they're bundled
+const BUG_REPORT_SCREENSHOT: &str = "bug_report_screenshot.png";
+const BUG_REPORT_CLIP: &str = "bug_report_clip.gif";
+
+// How far away the player can use an interactive element from
+const INTERACT_REACH: f32 = 2.5;
+
+// Markers go this far in front of the player, and are labelled out to the view distance
+const MARKER_DISTANCE: f32 = 1.0;
+const MARKER_VIEW_DISTANCE: f32 = 40.0;
+// Signs are legible out to here
+const SIGN_VIEW_DISTANCE: f32 = 15.0;
+// Chat lines kept on screen
+const CHAT_LINES: usize = 6;
+
+// What the player weighs when bumping into props, in kg
+const PLAYER_MASS: f32 = 80.0;
+
+// Work lights are set down this far ahead, at the player's feet
+const WORK_LIGHT_DISTANCE: f32 = 0.8;
+const EYE_HEIGHT: f32 = 1.5;
+
+// Radians per second with the right stick all the way over, and how far
+// either stick has to move before it counts
+const STICK_TURN_SPEED: f32 = 2.5;
+const STICK_DEADZONE: f32 = 0.2;
+// How quickly the view comes round to a new floor, per second
+const UP_BLEND: f32 = 4.0;
+
+// Video memory the streamed textures may keep resident, and the full size of
+// the backdrop puff they start from
+const TEXTURE_BUDGET: u64 = 32 * 1024 * 1024;
+const PUFF_SIZE: i32 = 1024;
+
+// Inside faces of the test room's walls, for where light pools land
+const TEST_ROOM: Room = Room {
+    min: glam::Vec3::new(-2.9, 0.0, -2.9),
+    max: glam::Vec3::new(2.9, 3.0, 2.9),
+};
 
 fn main() {
+    // `--headless` runs the simulation without opening a window
+    match HeadlessOptions::from_args(std::env::args().skip(1)) {
+        Ok(Some(options)) => {
+            if let Err(err) = headless::run(&options) {
+                eprintln!("Error: {:#}", err);
+                std::process::exit(1);
+            }
+            return;
+        }
+        Ok(None) => {}
+        Err(err) => {
+            eprintln!("Error: {:#}", err);
+            std::process::exit(2);
+        }
+    }
+
+    // Window, controls and limits; reloaded when the file changes
+    let mut config_file = ConfigFile::new("config.toml");
+    let mut config = Config::load(config_file.path()).unwrap_or_else(|err| {
+        eprintln!("Warning: using default config: {:#}", err);
+        Config::default()
+    });
+
+    // Same seed, same simulation; pass `--seed` to replay a run from a bug report
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let seed = match rng::seed_from_args(&args) {
+        Ok(seed) => seed.or(config.simulation.seed).unwrap_or_else(rng::session_seed),
+        Err(err) => {
+            eprintln!("Error: {:#}", err);
+            std::process::exit(2);
+        }
+    };
+
+    // `--list-gpus` prints the adapters `--gpu <n>` and the config can pick
+    if args.iter().any(|arg| arg == "--list-gpus") {
+        match gpu::enumerate_adapters() {
+            Ok(adapters) => {
+                for (index, adapter) in adapters.iter().enumerate() {
+                    println!("{}: {}", index, adapter.describe());
+                }
+            }
+            Err(err) => eprintln!("Error: {:#}", err),
+        }
+        return;
+    }
+
+    // `--gpu`, or `gpu` in the config, on machines with more than one
+    let preference = match GpuPreference::from_args(&args) {
+        Ok(preference) => preference.or_else(|| GpuPreference::from_name(&config.graphics.gpu)).unwrap_or_default(),
+        Err(err) => {
+            eprintln!("Error: {:#}", err);
+            std::process::exit(2);
+        }
+    };
+    if preference != GpuPreference::Default {
+        match gpu::enumerate_adapters() {
+            Ok(adapters) => match preference.pick(&adapters) {
+                Some(adapter) => {
+                    println!("Requesting GPU: {}", adapter.describe());
+                    adapter.request();
+                }
+                None => eprintln!("Warning: no GPU matches {:?}; leaving the choice to the driver", preference),
+            },
+            Err(err) => {
+                eprintln!("Warning: {:#}", err);
+                preference.apply();
+            }
+        }
+    }
+
+    // `--benchmark` flies a fixed path under heavy particle load and prints timings at exit
+    let benchmark_options = match BenchmarkOptions::from_args(&args) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("Error: {:#}", err);
+            std::process::exit(2);
+        }
+    };
+
     let (mut rl, thread) = raylib::init()
-        .size(800, 600)
+        .size(config.window.width, config.window.height)
         .title("Space Station 3D")
+        .resizable()
         .build();
 
-    // Enable mouse cursor lock for smoother camera rotation
+    // Enable mouse cursor lock for smoother camera rotation; TAB, menus and
+    // alt-tabbing away free it again
     rl.disable_cursor();
+    let mut cursor_focus = CursorFocus::new();
 
     // Configure camera - start inside the room
     let mut camera = Camera3D::perspective(
@@ -17,132 +221,3671 @@ fn main() {
         75.0,                        // wider FOV for better indoor view
     );
 
-    // Set target FPS
-    rl.set_target_fps(60);
+    rl.set_target_fps(config.window.fps_cap);
+
+    // Sound is optional; without a device the alarm is just the lights
+    let audio = match RaylibAudio::init_audio_device() {
+        Ok(audio) => Some(audio),
+        Err(err) => {
+            eprintln!("Warning: sound disabled: {}", err);
+            None
+        }
+    };
+    let mut klaxon = audio.as_ref().and_then(|audio| {
+        let wave = audio.new_wave_from_memory(".wav", &alarm::klaxon_wav()).ok()?;
+        audio.new_sound_from_wave(&wave).ok()
+    });
+
+    // UI text in the configured language
+    let mut locale = load_locale(&config.ui.language);
+
+    // Station simulation: owns mission time, the event log and station events.
+    // `--layout <path>` builds it from a layout file and `--procedural` grows
+    // a layout from the seed instead of the standard one.
+    let layout_path = match layout::path_from_args(&args) {
+        Ok(path) => path,
+        Err(err) => {
+            eprintln!("Error: {:#}", err);
+            std::process::exit(2);
+        }
+    };
+    let layout = layout_path.as_ref().map(|path| {
+        StationLayout::load(path).unwrap_or_else(|err| {
+            eprintln!("Error: {:#}", err);
+            std::process::exit(2);
+        })
+    });
+    let mut station = if let Some(layout) = &layout {
+        layout.build().unwrap_or_else(|err| {
+            eprintln!("Error: {:#}", err);
+            std::process::exit(2);
+        })
+    } else if args.iter().any(|arg| arg == "--procedural") {
+        SpaceStation::generate_procedural(seed, &LayoutParams::default())
+    } else {
+        SpaceStation::create_default_layout()
+    };
+    station.reseed(seed);
+    station.log_event(LogSeverity::Info, format!("Simulation seed {}", seed));
+
+    // Logged and kept for bug reports, since "which GPU" is the first support question
+    let gpu_info = query_gpu_info();
+    println!("GPU: {}", gpu_info.describe());
+    station.log_event(LogSeverity::Info, format!("GPU: {}", gpu_info.describe()));
+    if gpu_info.is_software() {
+        station.log_event(LogSeverity::Warning, "Rendering in software; the GPU driver may be missing");
+    }
+
+    // Handheld preset (auto on a Steam Deck): lower render scale, particle
+    // caps and shadows, a bigger HUD and full gamepad controls
+    let steam_deck = std::env::var("SteamDeck").ok();
+    let monitor = unsafe {
+        let monitor = raylib::ffi::GetCurrentMonitor();
+        (raylib::ffi::GetMonitorWidth(monitor), raylib::ffi::GetMonitorHeight(monitor))
+    };
+    let handheld = handheld::enabled(config.graphics.handheld, steam_deck.as_deref(), monitor);
+    if handheld {
+        // Fill the screen it was tuned for rather than the desktop default
+        rl.set_window_size(monitor.0, monitor.1);
+        station.log_event(LogSeverity::Info, format!("Handheld preset on ({}x{})", monitor.0, monitor.1));
+    }
+    let mut display = DisplaySettings::resolve(&config, handheld, (rl.get_screen_width(), rl.get_screen_height()));
+    // The scene is drawn here at the render scale, then stretched over the window
+    let scene_size = display.scene_size((rl.get_screen_width(), rl.get_screen_height()));
+    let mut scene_target = load_scene_target(&mut rl, &thread, scene_size).expect("failed to create scene render target");
+    // A size the target couldn't be rebuilt at, so it isn't retried every frame
+    let mut failed_scene_size = None;
+
+    // Balancing values, reloaded when the file changes and tweakable live
+    // from the Shift+F3 panel
+    let mut tunables_file = TunablesFile::new(&config.simulation.tunables);
+    let mut tunables = Tunables::load(tunables_file.path()).unwrap_or_else(|err| {
+        eprintln!("Warning: using default tunables: {:#}", err);
+        Tunables::default()
+    });
+    // Lab experiments, and the upgrades their points buy laid over the tunables
+    let mut research = Research::load("assets/research.toml").unwrap_or_else(|err| {
+        eprintln!("Warning: research disabled: {:#}", err);
+        Research::default()
+    });
+    station.set_tunables(&research.upgraded(&tunables));
+    let mut tuning_panel = TuningPanel::new();
+
+    // F5 quicksaves, F9 quickloads, L opens the save slot menu
+    let save_dir = save::save_dir();
+    let mut save_menu: Option<SaveMenu> = None;
+
+    // Pause, single-step (F11, Shift for 10 ticks) and dump state (F2)
+    let mut stepper = SimStepper::new();
+
+    // A snapshot a frame for the console's `diff` command and its result panel
+    let mut snapshot_history = SnapshotHistory::new(HISTORY_TICKS);
+    // Clients the console's `net` attaches, for bandwidth figures
+    let mut replicator = Replicator::new();
+    // Team and proximity chat, which the player's in from the start. It
+    // places everyone by their feet.
+    let mut chat = Chat::new(config.chat.clone());
+    let feet = glam::Vec3::new(camera.position.x, camera.position.y - EYE_HEIGHT, camera.position.z);
+    chat.join(&config.chat.name, &config.chat.team, feet);
+    // The player's suit from their profile, sent to everyone with the station
+    let profiles = avatar::profile_dir();
+    let look = avatar::load_look(&profiles, &config.chat.name).unwrap_or_else(|err| {
+        eprintln!("Warning: default suit: {:#}", err);
+        AvatarLook::default()
+    });
+    replicator.set_avatar(&config.chat.name, look);
+    // Opt-in playtest events, kept in a local file
+    let mut analytics = Analytics::new(config.analytics.clone());
+    let mut state_diff: Option<StateDiff> = None;
+
+    // Particle effects spawned by station events
+    let mut effects = EventEffects::new(rng::fork(station.rng()));
+    // Leaked water and coolant floating about, and how much the wet vac has
+    // taken up since the use key went down
+    let mut spills = Spills::new(rng::fork(station.rng()));
+    let mut vacuumed = 0.0;
+    effects.set_limits(display.particles.clone());
+
+    // Uncapped, so the numbers measure the game rather than the FPS limit
+    let mut benchmark = benchmark_options.map(|options| Benchmark::new(&station, options));
+    if benchmark.is_some() {
+        rl.set_target_fps(0);
+    }
+
+    // Station tour screensaver after a while without input
+    let mut attract = AttractMode::new(config.ui.attract_after);
+
+    // Developer console (~), for driving the simulation by hand
+    let mut console = Console::with_default_commands();
+
+    // F3 developer overlay
+    let mut debug_overlay = DebugOverlay::new();
+    debug_overlay.gpu = Some(gpu_info.renderer.clone());
+
+    // Last 10 seconds of gameplay at 10 fps; F10 exports it as a GIF
+    let mut clip_recorder = ClipRecorder::new(10.0, 10);
+    let mut scene_recorder = SceneRecorder::new();
+    // A 360° screenshot under way, from the panorama console command
+    let mut panorama: Option<PanoramaCapture> = None;
+
+    // HUD toasts for station events
+    let mut notifications = Notifications::new();
+
+    // Collectible datapads, read with F and archived in the journal (J)
+    let mut datapads = Datapads::load("assets/datapads.toml", &mut station).unwrap_or_else(|err| {
+        eprintln!("Warning: datapads disabled: {:#}", err);
+        Datapads::new()
+    });
+    let mut reading_datapad: Option<usize> = None;
+
+    // Hatch covers, tool arms and crane hooks that swing when knocked
+    let mut props = Props::load("assets/props.toml", &station).unwrap_or_else(|err| {
+        eprintln!("Warning: props disabled: {:#}", err);
+        Props::new()
+    });
+    let mut last_listener: Option<glam::Vec3> = None;
+
+    // Crew going about their day
+    let mut crew = Crew::load("assets/crew.toml", &station).unwrap_or_else(|err| {
+        eprintln!("Warning: crew disabled: {:#}", err);
+        Crew::new()
+    });
+    // Maintenance drone, docked in the stores until called (quick menu)
+    let mut drone = Drone::new(&station);
+
+    // Carried items (1-6 select, R uses) and the ones lying around or in storage
+    let mut inventory = Inventory::new();
+    // Repair kits and spare parts go in over a few seconds
+    let mut repair: Option<RepairJob> = None;
+    let mut world_items = WorldItems::load("assets/items.toml", &station).unwrap_or_else(|err| {
+        eprintln!("Warning: items disabled: {:#}", err);
+        WorldItems::new()
+    });
+    let mut journal_selection: Option<usize> = None;
+
+    // Helmet light (H) on suit power, and work lights set down from the inventory
+    let mut helmet_light = HelmetLight::new();
+    let mut work_lights = WorkLights::new();
+
+    // Spacewalks out of the airlock (F at its control): WASD/QE thrust, X brakes, T unclips the tether
+    let mut eva = Eva::new();
+
+    // Glow-in-the-dark floor strips that keep going for a while after the lights fail
+    let mut guidance_strips = GuidanceStrips::load("assets/guidance_strips.toml", &station).unwrap_or_else(|err| {
+        eprintln!("Warning: guidance strips disabled: {:#}", err);
+        GuidanceStrips::new()
+    });
+
+    // Nameplates, door labels and corridor arrows, made again whenever the layout changes
+    let mut signage = Signage::new();
+
+    // The station in miniature over a table in the command centre
+    let mut holotable = Holotable::place(&station);
+    // Modules the player has been in, and any hop to one under way
+    let mut fast_travel = FastTravel::new();
+    // B toggles build mode, for adding modules onto the one you're in
+    let mut construction = Construction::new();
+
+    // Mission objectives checked against station state every tick
+    let mut objectives = Objectives::load("assets/objectives.toml").unwrap_or_else(|err| {
+        eprintln!("Warning: objectives disabled: {:#}", err);
+        Objectives::default()
+    });
+
+    // Console screen opened from Terminal and MainComputer elements; its PAINT
+    // page recolours the module from the station style guide
+    let mut terminal = Terminal::new();
+    // The player's own, clipped to their suit
+    let mut dosimeter = Dosimeter::new();
+    match StyleGuide::load("assets/style_guide.toml") {
+        Ok(style_guide) => terminal.set_style_guide(style_guide),
+        Err(err) => eprintln!("Warning: painting disabled: {:#}", err),
+    }
+
+    // Emergency drills (F1 runs the next one) and the history they're graded against
+    let drill_scenarios = drill::load_scenarios("assets/drills.toml").unwrap_or_else(|err| {
+        eprintln!("Warning: drills disabled: {:#}", err);
+        Vec::new()
+    });
+    let mut drill_history = DrillHistory::load("drill_history.toml").unwrap_or_else(|err| {
+        eprintln!("Warning: ignoring drill history: {:#}", err);
+        DrillHistory::default()
+    });
+    let mut next_drill = 0;
+    let mut active_drill: Option<Drill> = None;
+    let mut drill_report: Option<DrillReport> = None;
+    // Drills checkpoint at each emergency answered; a failed one can go back
+    // to its last instead of starting over
+    let checkpoint_dir = checkpoint::checkpoint_dir();
+    let mut retry_scenario: Option<drill::DrillScenario> = None;
+
+    // Evacuation alerts (F12 sounds the next one), sending the crew to the pods
+    let evacuation_scenarios = evacuation::load_scenarios("assets/evacuations.toml").unwrap_or_else(|err| {
+        eprintln!("Warning: evacuations disabled: {:#}", err);
+        Vec::new()
+    });
+    let mut next_evacuation = 0;
+    let mut active_evacuation: Option<Evacuation> = None;
+
+    // Ships due at the station, flown in by autopilot; H at a command centre
+    // terminal takes the helm
+    let arrivals = docking::load_arrivals("assets/arrivals.toml").unwrap_or_else(|err| {
+        eprintln!("Warning: no ship arrivals: {:#}", err);
+        Vec::new()
+    });
+    let mut docking = Docking::new(arrivals);
+
+    // Mission control's messages, coming down as well as the dish is
+    // trimmed; Enter on the comms console's COMMS page trims it
+    let messages = comms::load_messages("assets/comms.toml").unwrap_or_else(|err| {
+        eprintln!("Warning: no messages from the ground: {:#}", err);
+        Vec::new()
+    });
+    let mut comms = Comms::new(messages);
+    let mut reading_comms = false;
+
+    // Micrometeorites, flares, surges and breakdowns paced by the director,
+    // each with an objective to put it right; the alarm goes until they're
+    // all dealt with
+    let mut incidents = match incidents::load_schedule("assets/incidents.toml") {
+        Ok(schedule) => Some(Incidents::new(schedule)),
+        Err(err) => {
+            eprintln!("Warning: incidents disabled: {:#}", err);
+            None
+        }
+    };
+    station.set_flares_directed(incidents.as_ref().is_some_and(Incidents::directs_flares));
+    let mut alarm = Alarm::new();
+
+    // Corner minimap; M opens the full-screen map (drag to pan, scroll to zoom)
+    let mut station_map = StationMap::new();
+
+    // Player markers; hold N and flick the mouse toward a kind, or the last
+    // slot to remove the nearest one
+    let mut markers = Markers::new();
+    let mut marker_wheel = RadialMenu::new(MarkerKind::ALL.len() + 1);
+
+    // Hold G for tools and other quick actions; slots come from config.toml
+    let mut quick_menu = QuickMenu::new(config.quick_menu.actions());
 
-    // Movement speed
-    let move_speed = 0.1;
-    let look_speed = 0.003;
     let mut yaw = 0.0f32;  // Tracks total horizontal rotation
+    // Away from the floor where the player's standing
+    let mut player_up = glam::Vec3::Y;
+
+    // Background stars, seen through the window and from outside alike
+    let mut starfield = Starfield::generate(StarfieldConfig {
+        density: config.graphics.star_density,
+        brightness: config.graphics.star_brightness,
+        ..StarfieldConfig::default()
+    });
+    // Every star is one instance of a single quad; the shader faces it at the
+    // camera and twinkles it
+    let star_quad = Mesh::gen_mesh_plane(&thread, 1.0, 1.0, 1, 1);
+    let mut star_material = rl.load_material_default(&thread);
+    let mut star_shader = rl.load_shader_from_memory(&thread, Some(STAR_VERTEX_SHADER), Some(STAR_FRAGMENT_SHADER));
+    // raylib 5.5 sends instance transforms to the model-matrix location
+    star_shader.locs_mut()[ShaderLocationIndex::SHADER_LOC_MATRIX_MODEL as usize] =
+        star_shader.get_shader_location_attribute("instanceTransform");
+    let star_uniforms = [
+        star_shader.get_shader_location("time"),
+        star_shader.get_shader_location("twinkleSpeed"),
+        star_shader.get_shader_location("twinkleAmount"),
+        star_shader.get_shader_location("pixelSize"),
+    ];
+    *star_material.shader_mut() = unsafe { star_shader.make_weak() };
+
+    // Optional painterly backdrop (nebulae, galactic band, comets). A layout
+    // file can give its mission a sky of its own; otherwise config.toml picks.
+    let layout_backdrop = layout.as_ref().and_then(|layout| layout.backdrop.clone());
+    let mut backdrop = load_backdrop(layout_backdrop.as_deref().unwrap_or(&config.graphics.backdrop));
+    // The puff is streamed: only the mips its on-screen size calls for are uploaded
+    let mut texture_streamer = TextureStreamer::new(TEXTURE_BUDGET);
+    let puff_id = texture_streamer.register("backdrop_puff", PUFF_SIZE as u32, PUFF_SIZE as u32);
+    let puff_mips = mip_chain(&Image::gen_image_gradient_radial(PUFF_SIZE, PUFF_SIZE, 0.0, Color::WHITE, Color::BLANK));
+    let mut puff_texture = load_streamed_texture(&mut rl, &thread, &puff_mips, texture_streamer.resident_level(puff_id).unwrap_or(0))
+        .expect("failed to create backdrop texture");
+
+    // Reverb for positional audio, following the listener from module to module
+    let mut reverb_zones = ReverbZones::load("assets/reverb.toml").unwrap_or_else(|err| {
+        eprintln!("Warning: reverb overrides disabled: {:#}", err);
+        ReverbZones::new()
+    });
+
+    // Event-driven camera shake, scaled by [camera] shake_intensity
+    let mut camera_shake = CameraShake::new();
+    camera_shake.set_intensity(config.camera.shake_intensity);
+    // Controller rumble for the same events, scaled by the [haptics] settings
+    let mut haptics = Haptics::new(config.haptics.clone());
+
+    // Sunlight through the window follows the orbit; F7 speeds it up for previewing
+    let mut orbit_preview = OrbitPreview::new();
+    let window = WindowAperture {
+        center: glam::Vec3::new(0.0, 1.5, 3.0),
+        right: glam::Vec3::new(2.0, 0.0, 0.0),
+        up: glam::Vec3::new(0.0, 1.0, 0.0),
+        normal: glam::Vec3::Z,
+        panes_x: 2,
+        panes_y: 1,
+        frame_width: 0.08,
+    };
+
+    // Developer free-fly camera (F4): scroll scales speed, 1-9 recall and Shift+1-9 save poses
+    let mut fly_camera = FlyCamera::new();
+    fly_camera.base_speed = config.controls.fly_speed;
+    fly_camera.look_speed = config.controls.mouse_sensitivity;
+    // Shift+F4 watches through it instead, as an observer
+    let mut observer = Observer::new();
+
+    // Keys are resolved through a stack of input contexts so a key bound in
+    // one context never leaks into another; F6 shows the active stack
+    let default_bindings = if display.handheld { InputBindings::handheld() } else { InputBindings::default() };
+    let bindings = default_bindings.clone().with_overrides("assets/bindings.toml").unwrap_or_else(|err| {
+        eprintln!("Warning: using default keybindings: {:#}", err);
+        default_bindings
+    });
+    let mut input = Input::new(bindings);
+
+    // Contextual tips fed from station events; dismissals persist across sessions
+    let mut hints = match HintEngine::load("assets/hints.toml")
+        .and_then(|hints| hints.with_state_file("hint_state.toml"))
+    {
+        Ok(hints) => Some(hints),
+        Err(err) => {
+            eprintln!("Warning: hints disabled: {:#}", err);
+            None
+        }
+    };
+
+    // Counts frames, to pace interference glitches
+    let mut frame_number: u32 = 0;
+
+    // Escape ends the attract tour rather than the game
+    while !rl.window_should_close() && (attract.active() || !rl.is_key_pressed(KeyboardKey::KEY_ESCAPE)) {
+        frame_number = frame_number.wrapping_add(1);
+        // Whatever ends the attract tour is swallowed, so it doesn't also act in the game
+        let touched = any_input(&mut rl);
+        let attracting = attract.active();
+        // Never during a benchmark, and not while the window is in the background
+        let touched = touched || benchmark.is_some() || !rl.is_window_focused();
+        match attract.update(&station, touched, rl.get_frame_time()) {
+            Some(AttractChange::Started) => station.log_event(LogSeverity::Info, "Attract mode started"),
+            Some(AttractChange::Stopped) | None => {}
+        }
+
+        // Stack the contexts for whatever is open, then resolve this frame's keys
+        let menu_open = tuning_panel.visible
+            || save_menu.is_some()
+            || station_map.full_screen
+            || reading_datapad.is_some()
+            || journal_selection.is_some()
+            || drill_report.is_some();
+        input.set_active(InputContext::FlyCamera, fly_camera.active);
+        input.set_active(InputContext::Seated, terminal.is_open());
+        input.set_active(InputContext::ConsoleUi, terminal.is_open());
+        input.set_active(InputContext::Helm, docking.manual());
+        input.set_active(InputContext::Menu, menu_open);
+        input.set_active(InputContext::DevConsole, console.open);
+        if attracting {
+            input.update(&NoKeys);
+        } else {
+            input.update(&RaylibKeys(&rl));
+        }
+        let modifier = input.held(InputContext::Global, Action::Modifier);
+
+        if input.pressed(InputContext::Global, Action::ToggleFlyCamera) {
+            if observer.active() || (modifier && !fly_camera.active) {
+                let id = if observer.toggle() { "observer.on" } else { "observer.off" };
+                notifications.push(LogSeverity::Info, locale.get(id).to_string());
+            }
+            fly_camera.toggle(CameraPose {
+                position: glam::Vec3::new(camera.position.x, camera.position.y, camera.position.z),
+                yaw,
+                pitch: 0.0,
+            });
+        }
+
+        if input.pressed(InputContext::Global, Action::ToggleConsole) {
+            console.toggle();
+        }
+
+        // The open console gets all typed text
+        if console.open {
+            while let Some(c) = rl.get_char_pressed() {
+                if c != '`' && c != '~' {
+                    console.type_char(c);
+                }
+            }
+            if input.pressed(InputContext::DevConsole, Action::Erase) {
+                console.backspace();
+            }
+            if input.pressed(InputContext::DevConsole, Action::NavUp) {
+                console.history_previous();
+            }
+            if input.pressed(InputContext::DevConsole, Action::NavDown) {
+                console.history_next();
+            }
+            if input.pressed(InputContext::DevConsole, Action::Confirm) {
+                let mut player = glam::Vec3::new(camera.position.x, camera.position.y, camera.position.z);
+                console.submit(&mut ConsoleContext {
+                    station: &mut station,
+                    effects: &mut effects,
+                    markers: &mut markers,
+                    player: &mut player,
+                    stepper: &mut stepper,
+                    history: &snapshot_history,
+                    diff: &mut state_diff,
+                    scene_recorder: &mut scene_recorder,
+                    panorama: &mut panorama,
+                    props: &mut props,
+                    spills: &mut spills,
+                    crew: &mut crew,
+                    style_guide: terminal.style_guide(),
+                    incidents: incidents.as_mut(),
+                    objectives: &mut objectives,
+                    replicator: &mut replicator,
+                    research: &mut research,
+                    chat: &mut chat,
+                    profiles: &profiles,
+                    analytics: &analytics,
+                });
+                camera.position = to_vector3(player);
+            }
+        }
+
+        if input.pressed(InputContext::Gameplay, Action::ToggleMap) {
+            station_map.toggle_full_screen();
+        }
+
+        if input.pressed(InputContext::Gameplay, Action::ToggleJournal) {
+            journal_selection = Some(datapads.journal_len().saturating_sub(1));
+        }
+
+        if input.pressed(InputContext::Gameplay, Action::ToggleSaveMenu) {
+            save_menu = Some(SaveMenu::open(&save_dir));
+        }
+
+        // Full-screen UI takes over the mouse as well as the keys
+        let ui_captured = input.ui_captured();
+
+        if input.pressed(InputContext::Global, Action::ToggleCursor) {
+            cursor_focus.toggle();
+        }
+        let clicked = rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_LEFT);
+        match cursor_focus.update(rl.is_window_focused(), ui_captured, clicked) {
+            Some(CursorChange::Capture) => rl.disable_cursor(),
+            Some(CursorChange::Release) => rl.enable_cursor(),
+            None => {}
+        }
 
-    while !rl.window_should_close() && !rl.is_key_pressed(KeyboardKey::KEY_ESCAPE) {
         // Mouse look
+        let move_speed = config.controls.walk_speed * rl.get_frame_time();
+        let look_speed = config.controls.mouse_sensitivity;
         let mouse_delta = rl.get_mouse_delta();
-        yaw += mouse_delta.x * look_speed;
+        // A free cursor is for clicking, not looking around
+        let look_delta = if cursor_focus.captured() {
+            glam::Vec2::new(mouse_delta.x, mouse_delta.y)
+        } else {
+            glam::Vec2::ZERO
+        };
+
+        // Hold N for the marker wheel or G for the quick menu; the mouse or
+        // right stick points at a wedge and letting go picks it
+        let eye = glam::Vec3::new(camera.position.x, camera.position.y, camera.position.z);
+        let wheel_delta = glam::Vec2::new(mouse_delta.x, mouse_delta.y);
+        let stick = gamepad_stick(&rl);
+        // The handheld preset also walks and turns with the sticks
+        let (pad_move, pad_look) = if display.handheld {
+            (deadzone(gamepad_left_stick(&rl)), deadzone(stick))
+        } else {
+            (glam::Vec2::ZERO, glam::Vec2::ZERO)
+        };
+        let can_open_wheel = !fly_camera.active && !ui_captured;
+        let wheel_held = can_open_wheel
+            && !quick_menu.wheel.open
+            && input.held(InputContext::Gameplay, Action::PlaceMarker);
+        if wheel_held && !marker_wheel.open {
+            marker_wheel.open();
+        } else if !wheel_held && marker_wheel.open {
+            match marker_wheel.close() {
+                Some(slot) if slot < MarkerKind::ALL.len() => {
+                    place_marker(&mut markers, MarkerKind::ALL[slot], eye, yaw, &locale);
+                }
+                Some(_) => notifications.push(LogSeverity::Info, remove_nearest_marker(&mut markers, eye, &locale)),
+                None => {}
+            }
+        }
+        marker_wheel.update(wheel_delta);
+        marker_wheel.steer(stick);
+
+        let quick_held = can_open_wheel && !marker_wheel.open && input.held(InputContext::Gameplay, Action::QuickMenu);
+        match quick_menu.update(quick_held, wheel_delta, stick) {
+            Some(QuickAction::NextTool) => inventory.select((inventory.selected + 1) % INVENTORY_SLOTS),
+            Some(QuickAction::PreviousTool) => {
+                inventory.select((inventory.selected + INVENTORY_SLOTS - 1) % INVENTORY_SLOTS)
+            }
+            Some(QuickAction::UseTool) => {
+                if let Some(message) = use_item(&mut inventory, &mut station, &mut work_lights, &mut repair, eye, yaw, &locale) {
+                    notifications.push(LogSeverity::Info, message);
+                }
+            }
+            Some(QuickAction::PlaceMarker(kind)) => place_marker(&mut markers, kind, eye, yaw, &locale),
+            Some(QuickAction::RemoveMarker) => {
+                notifications.push(LogSeverity::Info, remove_nearest_marker(&mut markers, eye, &locale))
+            }
+            Some(QuickAction::ToggleMap) => station_map.toggle_full_screen(),
+            Some(QuickAction::Journal) => journal_selection = Some(datapads.journal_len().saturating_sub(1)),
+            Some(QuickAction::HelmetLight) => {
+                notifications.push(LogSeverity::Info, toggle_helmet_light(&mut helmet_light, &locale))
+            }
+            Some(QuickAction::CallDrone) => match drone.call(&station, eye) {
+                Ok(()) => notifications.push(LogSeverity::Info, locale.get("drone.called")),
+                Err(refusal) => notifications.push(LogSeverity::Warning, locale.get(refusal.message_id())),
+            },
+            Some(QuickAction::Emote(emote)) => {
+                if let Err(refusal) = chat.emote(&config.chat.name, emote) {
+                    notifications.push(LogSeverity::Warning, refusal.describe());
+                }
+            }
+            None => {}
+        }
+        if fly_camera.active && !ui_captured {
+            let fly = InputContext::FlyCamera;
+            let fly_input = FlyInput {
+                movement: glam::Vec3::new(
+                    input.axis(fly, Action::MoveRight, Action::MoveLeft),
+                    input.axis(fly, Action::MoveUp, Action::MoveDown),
+                    input.axis(fly, Action::MoveForward, Action::MoveBack),
+                ),
+                look: look_delta,
+                scroll: rl.get_mouse_wheel_move(),
+                boost: modifier,
+            };
+            fly_camera.update(&fly_input, rl.get_frame_time());
+
+            for slot in 0..BOOKMARK_SLOTS {
+                if input.pressed(fly, Action::Bookmark(slot as u8)) {
+                    if modifier {
+                        fly_camera.save_bookmark(slot);
+                    } else {
+                        fly_camera.recall_bookmark(slot);
+                    }
+                }
+            }
+
+            // Observers can look but not touch: follow the crew around, read
+            // any console and leave annotations for the players
+            if observer.active() {
+                if input.pressed(fly, Action::CycleView) {
+                    observer.next_view(&crew);
+                }
+                let turn = look_delta.x * fly_camera.look_speed;
+                if let Some(pose) = observer.follow(&crew, turn, rl.get_frame_time()) {
+                    fly_camera.pose = pose;
+                }
+                if input.pressed(fly, Action::PlaceMarker) {
+                    observer.annotate(&mut markers, locale.get("marker.annotation"), &fly_camera.pose);
+                }
+                if input.pressed(fly, Action::ToggleMap) {
+                    station_map.toggle_full_screen();
+                }
+                if input.pressed(fly, Action::Interact) {
+                    let console = station
+                        .nearest_module(fly_camera.pose.position)
+                        .and_then(|module| observer::console_in(&station, module).map(|console| (module, console)));
+                    match console {
+                        Some((module, (element, element_type))) => terminal.open_read_only(module, element, element_type),
+                        None => notifications.push(LogSeverity::Info, locale.get("observer.no_console").to_string()),
+                    }
+                }
+            }
+        } else if !ui_captured && !marker_wheel.open && !quick_menu.wheel.open {
+            yaw += look_delta.x * look_speed + pad_look.x * STICK_TURN_SPEED * rl.get_frame_time();
+        }
+
+        // Which way the floor faces: straight up on the decks, toward the
+        // axis in a ring section. Eased round, so walking into a ring tilts
+        // the view rather than snapping it.
+        if eva.phase() == EvaPhase::Inside {
+            let position = glam::Vec3::new(camera.position.x, camera.position.y, camera.position.z);
+            let local_up = station.local_up(position, player_up);
+            player_up = player_up.lerp(local_up, (UP_BLEND * rl.get_frame_time()).min(1.0)).try_normalize().unwrap_or(local_up);
+        } else {
+            player_up = glam::Vec3::Y;
+        }
+        let tilt = glam::Quat::from_rotation_arc(glam::Vec3::Y, player_up);
 
         // Calculate look direction (use raw yaw for continuous rotation)
-        let look_dir = Vector3::new(yaw.cos(), 0.0, yaw.sin());
-        camera.target = Vector3::new(
-            camera.position.x + look_dir.x,
-            camera.position.y,
-            camera.position.z + look_dir.z,
-        );
+        let look_dir = to_vector3(tilt * glam::Vec3::new(yaw.cos(), 0.0, yaw.sin()));
+        camera.target = camera.position + look_dir;
+        camera.up = to_vector3(player_up);
 
-        // Basic movement
-        if rl.is_key_down(KeyboardKey::KEY_W) {
-            camera.position.x += look_dir.x * move_speed;
-            camera.position.z += look_dir.z * move_speed;
-            camera.target.x += look_dir.x * move_speed;
-            camera.target.z += look_dir.z * move_speed;
-        }
-        if rl.is_key_down(KeyboardKey::KEY_S) {
-            camera.position.x -= look_dir.x * move_speed;
-            camera.position.z -= look_dir.z * move_speed;
-            camera.target.x -= look_dir.x * move_speed;
-            camera.target.z -= look_dir.z * move_speed;
-        }
-        if rl.is_key_down(KeyboardKey::KEY_A) {
-            let right = Vector3::new(-look_dir.z, 0.0, look_dir.x);
-            camera.position.x -= right.x * move_speed;
-            camera.position.z -= right.z * move_speed;
-            camera.target.x -= right.x * move_speed;
-            camera.target.z -= right.z * move_speed;
-        }
-        if rl.is_key_down(KeyboardKey::KEY_D) {
-            let right = Vector3::new(-look_dir.z, 0.0, look_dir.x);
-            camera.position.x += right.x * move_speed;
-            camera.position.z += right.z * move_speed;
-            camera.target.x += right.x * move_speed;
-            camera.target.z += right.z * move_speed;
-        }
-        if rl.is_key_down(KeyboardKey::KEY_Q) {
-            camera.position.y -= move_speed;
-            camera.target.y -= move_speed;
-        }
-        if rl.is_key_down(KeyboardKey::KEY_E) {
-            camera.position.y += move_speed;
-            camera.target.y += move_speed;
-        }
-
-        // Allow TAB key to toggle cursor lock
-        if rl.is_key_pressed(KeyboardKey::KEY_TAB) {
-            if rl.is_cursor_hidden() {
-                rl.enable_cursor();
-            } else {
-                rl.disable_cursor();
+        // The player stays put while the fly camera or a full-screen UI is active.
+        // Outside the station the same keys fire the jetpack instead.
+        let mut eva_thrust = glam::Vec3::ZERO;
+        let mut eva_brake = false;
+        if eva.outside() && !fly_camera.active && !ui_captured {
+            let gameplay = InputContext::Gameplay;
+            let forward = glam::Vec3::new(look_dir.x, 0.0, look_dir.z);
+            let right = glam::Vec3::new(-look_dir.z, 0.0, look_dir.x);
+            eva_thrust = forward * (input.axis(gameplay, Action::MoveForward, Action::MoveBack) - pad_move.y)
+                + right * (input.axis(gameplay, Action::MoveRight, Action::MoveLeft) + pad_move.x)
+                + glam::Vec3::Y * input.axis(gameplay, Action::MoveUp, Action::MoveDown);
+            eva_brake = input.held(gameplay, Action::Brake);
+        } else if !fly_camera.active && !ui_captured && eva.phase() == EvaPhase::Inside {
+            let start = glam::Vec3::new(camera.position.x, 0.0, camera.position.z);
+            // Basic movement, along the floor whichever way it faces. Keys
+            // and the left stick, scaled by how far it's pushed.
+            let gameplay = InputContext::Gameplay;
+            let forward = tilt * glam::Vec3::new(yaw.cos(), 0.0, yaw.sin());
+            let right = tilt * glam::Vec3::new(-yaw.sin(), 0.0, yaw.cos());
+            let step = forward * (input.axis(gameplay, Action::MoveForward, Action::MoveBack) - pad_move.y)
+                + right * (input.axis(gameplay, Action::MoveRight, Action::MoveLeft) + pad_move.x)
+                + player_up * input.axis(gameplay, Action::MoveUp, Action::MoveDown);
+            let step = to_vector3(step * move_speed);
+            camera.position += step;
+            camera.target += step;
+            // Footprints where the player's been
+            let feet = glam::Vec3::new(camera.position.x, 0.0, camera.position.z);
+            station.tread(feet, start.distance(feet));
+        }
+
+        if eva.outside() && input.pressed(InputContext::Gameplay, Action::ToggleTether) {
+            let id = if eva.toggle_tether() { "eva.tether_on" } else { "eva.tether_off" };
+            notifications.push(LogSeverity::Info, locale.get(id).to_string());
+        }
+        for event in eva.update(&mut station, eva_thrust, eva_brake, rl.get_frame_time()) {
+            let (severity, id) = match event {
+                EvaEvent::Outside { .. } => {
+                    if let Some(hints) = &mut hints {
+                        hints.trigger("eva_started");
+                    }
+                    (LogSeverity::Info, "eva.outside")
+                }
+                EvaEvent::Inside { position } => {
+                    camera.position = to_vector3(position);
+                    (LogSeverity::Info, "eva.inside")
+                }
+                EvaEvent::LowOxygen => (LogSeverity::Warning, "eva.low_oxygen"),
+                EvaEvent::OxygenOut => {
+                    track(&mut analytics, AnalyticsEvent::Death { cause: DeathCause::Suffocation, count: 1 });
+                    (LogSeverity::Critical, "eva.oxygen_out")
+                }
+                EvaEvent::FuelOut => (LogSeverity::Warning, "eva.fuel_out"),
+                EvaEvent::TetherTaut => (LogSeverity::Info, "eva.tether_taut"),
+            };
+            notifications.push(severity, locale.get(id).to_string());
+        }
+        // Outside, the camera rides along with the suit
+        if matches!(eva.phase(), EvaPhase::Outside { .. } | EvaPhase::CyclingIn { .. }) {
+            camera.position = to_vector3(eva.position);
+            camera.target = camera.position + look_dir;
+        }
+
+        if input.pressed(InputContext::Global, Action::ToggleDebugOverlay) && modifier {
+            tuning_panel.toggle();
+        } else if input.pressed(InputContext::Global, Action::ToggleDebugOverlay) {
+            debug_overlay.toggle();
+        }
+
+        if input.pressed(InputContext::Global, Action::ToggleInputDebug) {
+            input.show_debug = !input.show_debug;
+        }
+
+        if input.pressed(InputContext::Global, Action::TogglePause) {
+            stepper.toggle_pause();
+        }
+        if input.pressed(InputContext::Global, Action::StepSim) {
+            stepper.step(if modifier { 10 } else { 1 });
+        }
+        // [ and ] slow the simulation down or speed it up, \ puts it back to normal
+        let time_scale = stepper.time_scale();
+        if input.pressed(InputContext::Gameplay, Action::TimeSlower) {
+            stepper.slower();
+        }
+        if input.pressed(InputContext::Gameplay, Action::TimeFaster) {
+            stepper.faster();
+        }
+        if input.pressed(InputContext::Gameplay, Action::TimeReset) {
+            stepper.set_time_scale(1.0);
+        }
+        if stepper.time_scale() != time_scale {
+            let scale = format!("{}", stepper.time_scale());
+            notifications.push(LogSeverity::Info, locale.format("hud.time_scale", &[("scale", &scale)]));
+        }
+        if input.pressed(InputContext::Global, Action::DumpState) {
+            match stepping::write_dump(&station, stepper.tick(), std::path::Path::new(stepping::DUMP_DIR)) {
+                Ok(path) => notifications.push(LogSeverity::Info, format!("State dumped to {}", path.display())),
+                Err(err) => eprintln!("Warning: failed to dump state: {:#}", err),
             }
         }
 
-        let mut d = rl.begin_drawing(&thread);
-        d.clear_background(Color::BLACK);
+        if input.pressed(InputContext::Global, Action::ToggleOrbitPreview) {
+            orbit_preview.toggle();
+        }
 
-        // 3D drawing
+        if input.pressed(InputContext::Global, Action::StartDrill) && active_drill.is_none() && !drill_scenarios.is_empty() {
+            let scenario = drill_scenarios[next_drill % drill_scenarios.len()].clone();
+            station.log_event(LogSeverity::Info, format!("Drill started: {}", scenario.name));
+            if let Err(err) = checkpoint::clear(&checkpoint_dir, &scenario.name) {
+                eprintln!("Warning: failed to clear checkpoint: {:#}", err);
+            }
+            let started = AnalyticsEvent::ScenarioStarted { kind: ScenarioKind::Drill, scenario: scenario.name.clone() };
+            track(&mut analytics, started);
+            active_drill = Some(Drill::start(scenario, &station));
+            drill_report = None;
+            retry_scenario = None;
+            next_drill += 1;
+        }
+
+        if input.pressed(InputContext::Global, Action::SoundEvacuation)
+            && active_evacuation.is_none()
+            && !evacuation_scenarios.is_empty()
         {
-            let mut d = d.begin_mode3D(camera);
-            
-            // Draw floor
-            d.draw_plane(
-                Vector3::new(0.0, 0.0, 0.0),
-                Vector2::new(6.0, 6.0),
-                Color::GRAY,
-            );
-            
-            // Draw ceiling
-            d.draw_plane(
-                Vector3::new(0.0, 3.0, 0.0),
-                Vector2::new(6.0, 6.0),
-                Color::GRAY,
+            let scenario = evacuation_scenarios[next_evacuation % evacuation_scenarios.len()].clone();
+            let evacuation = Evacuation::start(scenario, &mut station);
+            if evacuation.pods().is_empty() {
+                notifications.push(LogSeverity::Warning, locale.get("evacuation.no_pods").to_string());
+            } else {
+                let scenario = evacuation.scenario().name.clone();
+                track(&mut analytics, AnalyticsEvent::ScenarioStarted { kind: ScenarioKind::Evacuation, scenario });
+                active_evacuation = Some(evacuation);
+                next_evacuation += 1;
+            }
+        }
+
+        // Enter dismisses the current tip, Shift+Enter stops it from ever showing again
+        if let Some(hints) = &mut hints {
+            if hints.current().is_some() && input.pressed(InputContext::Gameplay, Action::Confirm) {
+                if modifier {
+                    if let Err(err) = hints.dismiss_forever() {
+                        eprintln!("Warning: failed to save hint state: {:#}", err);
+                    }
+                } else {
+                    hints.dismiss();
+                }
+            }
+        }
+
+        // F8 captures a bug report at the end of this frame
+        let capture_bug_report = input.pressed(InputContext::Global, Action::BugReport);
+
+        // A fast-travel hop carries the camera, still facing the same way
+        if let Some(position) = fast_travel.update(rl.get_frame_time()) {
+            let facing = Vector3::new(
+                camera.target.x - camera.position.x,
+                camera.target.y - camera.position.y,
+                camera.target.z - camera.position.z,
             );
+            camera.position = to_vector3(position);
+            camera.target = Vector3::new(position.x + facing.x, position.y + facing.y, position.z + facing.z);
+        }
 
-            // Draw walls (excluding window wall)
-            // Back wall
-            d.draw_cube(Vector3::new(0.0, 1.5, -3.0), 6.0, 3.0, 0.2, Color::LIGHTGRAY);
-            // Left wall
-            d.draw_cube(Vector3::new(-3.0, 1.5, 0.0), 0.2, 3.0, 6.0, Color::LIGHTGRAY);
-            // Right wall
-            d.draw_cube(Vector3::new(3.0, 1.5, 0.0), 0.2, 3.0, 6.0, Color::LIGHTGRAY);
+        let listener = glam::Vec3::new(camera.position.x, camera.position.y, camera.position.z);
 
-            // Front wall with window
-            // Bottom part
-            d.draw_cube(Vector3::new(0.0, 0.5, 3.0), 6.0, 1.0, 0.2, Color::LIGHTGRAY);
-            // Top part
-            d.draw_cube(Vector3::new(0.0, 2.5, 3.0), 6.0, 1.0, 0.2, Color::LIGHTGRAY);
-            // Left part
-            d.draw_cube(Vector3::new(-2.0, 1.5, 3.0), 2.0, 1.0, 0.2, Color::LIGHTGRAY);
-            // Right part
-            d.draw_cube(Vector3::new(2.0, 1.5, 3.0), 2.0, 1.0, 0.2, Color::LIGHTGRAY);
-            
-            // Window (semi-transparent)
-            d.draw_cube(Vector3::new(0.0, 1.5, 3.0), 2.0, 1.0, 0.1, Color::new(100, 149, 237, 100));
-            d.draw_cube_wires(Vector3::new(0.0, 1.5, 3.0), 2.0, 1.0, 0.1, Color::DARKBLUE);
+        // The hand controllers, while the player has the helm
+        let helm = InputContext::Helm;
+        let rcs = RcsCommand {
+            translate: glam::Vec3::new(
+                input.axis(helm, Action::MoveRight, Action::MoveLeft),
+                input.axis(helm, Action::MoveUp, Action::MoveDown),
+                input.axis(helm, Action::MoveBack, Action::MoveForward),
+            ),
+            rotate: glam::Vec3::new(
+                input.axis(helm, Action::NavUp, Action::NavDown),
+                input.axis(helm, Action::NavLeft, Action::NavRight),
+                0.0,
+            ),
+        };
+
+        // Fixed-rate simulation ticks; none while paused unless stepping
+        // The station holds still while the attract tour shows it off
+        let steps = stepper.take_steps(if attract.active() { 0.0 } else { rl.get_frame_time() });
+        let step_dt = stepping::STEP_DT;
+        let sim_start = Instant::now();
+        let mut particle_time = Duration::ZERO;
+        for _ in 0..steps {
+            if let Some(drill) = &mut active_drill {
+                let over = drill.update(&mut station, listener, step_dt);
+                if let Some(milestone) = drill.take_milestone().filter(|_| !over) {
+                    let checkpoint = Checkpoint {
+                        scenario: drill.scenario().name.clone(),
+                        milestone: milestone.clone(),
+                        drill: drill.progress(),
+                        save: SaveGame::capture(&station, &markers, &research, listener, yaw)
+                            .with_session(&inventory, &world_items, &datapads, &objectives, &crew),
+                    };
+                    match checkpoint.write(&checkpoint_dir) {
+                        Ok(()) => notifications.push(LogSeverity::Info, locale.format("checkpoint.reached", &[("milestone", &milestone)])),
+                        Err(err) => eprintln!("Warning: failed to write checkpoint: {:#}", err),
+                    }
+                }
+                if over {
+                    let drill = active_drill.take().unwrap();
+                    let scenario = drill.scenario().clone();
+                    let seconds = drill.elapsed();
+                    let result = drill.finish(&station);
+                    track(
+                        &mut analytics,
+                        AnalyticsEvent::ScenarioCompleted {
+                            kind: ScenarioKind::Drill,
+                            scenario: scenario.name.clone(),
+                            passed: !result.failed(),
+                            seconds,
+                        },
+                    );
+                    station.log_event(
+                        LogSeverity::Info,
+                        format!("Drill finished: {} ({})", result.record.scenario, result.record.grade),
+                    );
+                    // Passing is the end of it; failing keeps the checkpoint to go back to
+                    if result.failed() && checkpoint::exists(&checkpoint_dir, &scenario.name) {
+                        retry_scenario = Some(scenario);
+                    } else if let Err(err) = checkpoint::clear(&checkpoint_dir, &scenario.name) {
+                        eprintln!("Warning: failed to clear checkpoint: {:#}", err);
+                    }
+                    drill_report = Some(DrillReport::new(result.clone(), &drill_history));
+                    if let Err(err) = drill_history.record(result.record) {
+                        eprintln!("Warning: failed to save drill history: {:#}", err);
+                    }
+                }
+            }
+
+            // Head counts and staffing go in before the station uses them
+            crew.update(&mut station, step_dt);
+            drone.update(&mut station, step_dt);
+            research.update(&mut station, step_dt);
+            if let Some(evacuation) = &mut active_evacuation {
+                if evacuation.update(&mut station, &mut crew, step_dt) {
+                    let result = active_evacuation.take().unwrap().finish(&crew);
+                    track(
+                        &mut analytics,
+                        AnalyticsEvent::ScenarioCompleted {
+                            kind: ScenarioKind::Evacuation,
+                            scenario: result.scenario.clone(),
+                            passed: result.succeeded(),
+                            seconds: result.elapsed,
+                        },
+                    );
+                    let left_behind = result.crew.saturating_sub(result.souls) as u32;
+                    if left_behind > 0 {
+                        track(&mut analytics, AnalyticsEvent::Death { cause: DeathCause::LeftBehind, count: left_behind });
+                    }
+                    let (severity, id) = if result.succeeded() {
+                        (LogSeverity::Info, "evacuation.succeeded")
+                    } else {
+                        (LogSeverity::Critical, "evacuation.failed")
+                    };
+                    let message = locale.format(
+                        id,
+                        &[("souls", &result.souls), ("crew", &result.crew), ("required", &result.required)],
+                    );
+                    station.log_event(severity, message.clone());
+                    notifications.push(severity, message);
+                }
+            }
+            if let Some(change) = docking.update(&mut station, rcs, step_dt) {
+                let (severity, message) = match change {
+                    DockingChange::Approaching { name, airlock } => (
+                        LogSeverity::Info,
+                        locale.format("docking.approaching", &[("name", &name), ("airlock", &airlock)]),
+                    ),
+                    DockingChange::Docked { name, airlock } => (
+                        LogSeverity::Info,
+                        locale.format("docking.docked", &[("name", &name), ("airlock", &airlock)]),
+                    ),
+                    DockingChange::Collided { name, closing, .. } => (
+                        LogSeverity::Warning,
+                        locale.format("docking.collided", &[("name", &name), ("speed", &format!("{:.2}", closing))]),
+                    ),
+                    DockingChange::Unloaded { name, left_aboard, .. } if left_aboard > 0.0 => (
+                        LogSeverity::Warning,
+                        locale.format("docking.left_aboard", &[("name", &name), ("amount", &format!("{:.0}", left_aboard))]),
+                    ),
+                    DockingChange::Unloaded { name, .. } => {
+                        (LogSeverity::Info, locale.format("docking.unloaded", &[("name", &name)]))
+                    }
+                };
+                notifications.push(severity, message);
+            }
+            // Arrivals come up through the event log; incidents they call for
+            // wait until any drill or evacuation is over, like the director's
+            let calm = active_drill.is_none() && active_evacuation.is_none();
+            comms.update(&mut station, &mut objectives, incidents.as_mut().filter(|_| calm), step_dt);
+            // Drills and evacuations have enough going on
+            if active_drill.is_none() && active_evacuation.is_none() {
+                if let Some(incidents) = &mut incidents {
+                    if let Some(kind) = incidents.update(&mut station, &mut objectives, step_dt) {
+                        let scenario = kind.name().to_string();
+                        track(&mut analytics, AnalyticsEvent::ScenarioStarted { kind: ScenarioKind::Incident, scenario });
+                    }
+                    for (kind, seconds) in incidents.take_resolved() {
+                        let scenario = kind.name().to_string();
+                        let kind = ScenarioKind::Incident;
+                        track(&mut analytics, AnalyticsEvent::ScenarioCompleted { kind, scenario, passed: true, seconds });
+                    }
+                }
+            }
+            station.update(step_dt);
+            let rate = match station.nearest_module(listener).filter(|_| !eva.outside()) {
+                Some(module) => station.radiation(module),
+                None => station.exterior_radiation(),
+            };
+            dosimeter.record(rate, step_dt);
+            objectives.update(&mut station, listener);
+            let particles_start = Instant::now();
+            // Flames and smoke for as long as each fire burns
+            for fire in station.fires() {
+                let key = format!("{}.{}", fire.module.slot, fire.module.generation);
+                effects.sustain(&format!("fire{}", key), "fire", fire.position, fire.intensity);
+                let smoke = fire.position + glam::Vec3::Y;
+                effects.sustain(&format!("smoke{}", key), "smoke", smoke, fire.intensity);
+            }
+            // Launched pods' motors, for as long as they burn
+            for pod in active_evacuation.iter().flat_map(|evacuation| evacuation.pods()) {
+                if let Some((nozzle, _)) = pod.plume() {
+                    effects.sustain(&format!("plume{}", pod.module.slot), "plume", nozzle, 1.0);
+                }
+            }
+            effects.update(step_dt, &tunables.particles);
+            particle_time += particles_start.elapsed();
+            props.update(step_dt, |point| station.gravity_at(point));
+            spills.update(&station, step_dt);
+        }
+        // One snapshot a frame, however many ticks it ran, for the console's
+        // diffs and for replication once anyone's connected. There's no
+        // transport yet, so whatever's sent is taken as delivered.
+        if steps > 0 {
+            let tick = stepper.tick();
+            let snapshot = station.snapshot();
+            if !replicator.is_empty() {
+                match replicator.update(tick, &snapshot) {
+                    Ok(packets) => {
+                        for (client, packet) in packets {
+                            replicator.ack(&client, packet.tick);
+                        }
+                    }
+                    Err(err) => eprintln!("Warning: replication failed: {:#}", err),
+                }
+            }
+            snapshot_history.record(tick, snapshot);
+        }
+        // USD recordings follow simulated time, so pausing holds the shot
+        if steps > 0 && scene_recorder.should_capture(steps as f32 * step_dt) {
+            let target = glam::Vec3::new(camera.target.x, camera.target.y, camera.target.z);
+            scene_recorder.push(SceneFrame::capture(&station, &effects, Some((listener, target))));
+        }
+        if let Some(benchmark) = &mut benchmark {
+            benchmark.record("simulation", sim_start.elapsed().saturating_sub(particle_time));
+            benchmark.record("particles", particle_time);
+        }
+        // Walking into props pushes them about; teleports don't count
+        let walked = last_listener.map_or(glam::Vec3::ZERO, |last| listener - last);
+        last_listener = Some(listener);
+        if steps > 0 && rl.get_frame_time() > 0.0 && walked.length() < 1.0 && fast_travel.trip().is_none() {
+            let velocity = walked / rl.get_frame_time();
+            for name in props.bump(listener, EYE_HEIGHT, velocity, PLAYER_MASS) {
+                notifications.push(LogSeverity::Warning, format!("Knocked the {} loose", name));
+            }
+        }
+        for event in station.drain_events() {
+            spills.handle_event(&event, &station);
+            for name in props.handle_event(&event) {
+                notifications.push(LogSeverity::Warning, format!("The {} broke loose", name));
+            }
+            camera_shake.handle_event(&event, listener);
+            haptics.handle_event(&event, listener);
+            effects.handle_event(&event);
+            objectives.handle_event(&event);
+            // The player saw the switch they just threw; no need to toast it
+            if !matches!(event, StationEvent::ElementActivated { .. }) {
+                notifications.push(event.severity(), event.describe());
+            }
+            if let Some(hints) = &mut hints {
+                hints.handle_event(&event);
+            }
+        }
+        notifications.update(rl.get_frame_time());
+        chat.set_position(&config.chat.name, listener - glam::Vec3::Y * EYE_HEIGHT);
+        chat.update(rl.get_frame_time());
+        analytics.update(rl.get_frame_time());
+        if research.take_refit() {
+            station.set_tunables(&research.upgraded(&tunables));
+        }
+        match tunables_file.poll(rl.get_frame_time()) {
+            Some(Ok(reloaded)) => {
+                tunables = reloaded;
+                station.set_tunables(&research.upgraded(&tunables));
+                notifications.push(LogSeverity::Info, "Tunables reloaded");
+            }
+            Some(Err(err)) => notifications.push(LogSeverity::Warning, format!("Tunables not reloaded: {:#}", err)),
+            None => {}
+        }
+        match config_file.poll(rl.get_frame_time()) {
+            Some(Ok(reloaded)) => {
+                // Only resize when the file asks for a new size, so a window
+                // the player dragged bigger stays that way
+                if (reloaded.window.width, reloaded.window.height) != (config.window.width, config.window.height) {
+                    rl.set_window_size(reloaded.window.width, reloaded.window.height);
+                }
+                rl.set_target_fps(reloaded.window.fps_cap);
+                fly_camera.base_speed = reloaded.controls.fly_speed;
+                fly_camera.look_speed = reloaded.controls.mouse_sensitivity;
+                let handheld = handheld::enabled(reloaded.graphics.handheld, steam_deck.as_deref(), monitor);
+                display = DisplaySettings::resolve(&reloaded, handheld, (rl.get_screen_width(), rl.get_screen_height()));
+                effects.set_limits(display.particles.clone());
+                quick_menu.set_slots(reloaded.quick_menu.actions());
+                haptics.set_config(reloaded.haptics.clone());
+                camera_shake.set_intensity(reloaded.camera.shake_intensity);
+                starfield.set_density(reloaded.graphics.star_density);
+                starfield.config.brightness = reloaded.graphics.star_brightness;
+                if reloaded.chat != config.chat {
+                    if (&reloaded.chat.name, &reloaded.chat.team) != (&config.chat.name, &config.chat.team) {
+                        chat.leave(&config.chat.name);
+                        chat.join(&reloaded.chat.name, &reloaded.chat.team, listener - glam::Vec3::Y * EYE_HEIGHT);
+                        replicator.remove_avatar(&config.chat.name);
+                        let look = avatar::load_look(&profiles, &reloaded.chat.name).unwrap_or_else(|err| {
+                            notifications.push(LogSeverity::Warning, format!("Profile not loaded: {:#}", err));
+                            AvatarLook::default()
+                        });
+                        replicator.set_avatar(&reloaded.chat.name, look);
+                    }
+                    chat.set_config(reloaded.chat.clone());
+                }
+                analytics.set_config(reloaded.analytics.clone());
+                attract.idle_seconds = reloaded.ui.attract_after;
+                if reloaded.ui.language != config.ui.language {
+                    locale = load_locale(&reloaded.ui.language);
+                }
+                if reloaded.graphics.backdrop != config.graphics.backdrop && layout_backdrop.is_none() {
+                    backdrop = load_backdrop(&reloaded.graphics.backdrop);
+                }
+                if reloaded.simulation.tunables != config.simulation.tunables {
+                    tunables_file = TunablesFile::new(&reloaded.simulation.tunables);
+                    match Tunables::load(tunables_file.path()) {
+                        Ok(loaded) => {
+                            tunables = loaded;
+                            station.set_tunables(&research.upgraded(&tunables));
+                        }
+                        Err(err) => notifications.push(LogSeverity::Warning, format!("Tunables not loaded: {:#}", err)),
+                    }
+                }
+                config = reloaded;
+                notifications.push(LogSeverity::Info, "Config reloaded");
+            }
+            Some(Err(err)) => notifications.push(LogSeverity::Warning, format!("Config not reloaded: {:#}", err)),
+            None => {}
+        }
+
+        let mut save_request = input
+            .pressed(InputContext::Global, Action::QuickSave)
+            .then_some(SaveSlot::Quick);
+        let mut load_request = input
+            .pressed(InputContext::Global, Action::QuickLoad)
+            .then_some(SaveSlot::Quick);
+        let mut restart_request: Option<drill::DrillScenario> = None;
 
-            // Draw some "stars" outside
-            for z in 4..20 {
-                let star_color = Color::new(255, 255, 255, (255 - z * 10) as u8);
-                d.draw_sphere(Vector3::new(-2.0, 1.5, z as f32), 0.05, star_color);
-                d.draw_sphere(Vector3::new(0.0, 2.0, z as f32), 0.05, star_color);
-                d.draw_sphere(Vector3::new(2.0, 1.0, z as f32), 0.05, star_color);
+        // Menus close from the top down: tunables, saves, reader, journal, map, drill report
+        let menu = InputContext::Menu;
+        let back = input.pressed(menu, Action::Back);
+        if tuning_panel.visible {
+            if input.pressed(menu, Action::NavUp) {
+                tuning_panel.select_previous();
+            }
+            if input.pressed(menu, Action::NavDown) {
+                tuning_panel.select_next(&tunables);
+            }
+            let direction = input.pressed(menu, Action::NavRight) as i32 as f32
+                - input.pressed(menu, Action::NavLeft) as i32 as f32;
+            if direction != 0.0 && tuning_panel.nudge(&mut tunables, direction, modifier).is_some() {
+                station.set_tunables(&research.upgraded(&tunables));
+            }
+            if input.pressed(menu, Action::SaveSlot) {
+                match tunables_file.save(&tunables) {
+                    Ok(()) => notifications.push(LogSeverity::Info, "Tunables saved"),
+                    Err(err) => notifications.push(LogSeverity::Warning, format!("Saving tunables failed: {:#}", err)),
+                }
+            }
+            if back {
+                tuning_panel.visible = false;
+            }
+        } else if let Some(save_menu_state) = &mut save_menu {
+            if input.pressed(menu, Action::NavUp) {
+                save_menu_state.select_previous();
+            }
+            if input.pressed(menu, Action::NavDown) {
+                save_menu_state.select_next();
+            }
+            if let Some(summary) = save_menu_state.selected_slot() {
+                if input.pressed(menu, Action::SaveSlot) {
+                    save_request = Some(summary.slot);
+                }
+                if input.pressed(menu, Action::Confirm) && !summary.is_empty() {
+                    load_request = Some(summary.slot);
+                }
+            }
+            if back || input.pressed(menu, Action::ToggleSaveMenu) {
+                save_menu = None;
+            }
+        } else if reading_datapad.is_some() {
+            if back || input.pressed(menu, Action::Confirm) {
+                reading_datapad = None;
+            }
+        } else if let Some(selection) = &mut journal_selection {
+            if input.pressed(menu, Action::NavUp) {
+                *selection = selection.saturating_sub(1);
+            }
+            if input.pressed(menu, Action::NavDown) {
+                *selection = (*selection + 1).min(datapads.journal_len().saturating_sub(1));
+            }
+            if back || input.pressed(menu, Action::ToggleJournal) {
+                journal_selection = None;
+            }
+        } else if station_map.full_screen {
+            if back || input.pressed(menu, Action::ToggleMap) {
+                station_map.toggle_full_screen();
+            } else if let (true, Some(to)) = (input.pressed(menu, Action::Confirm), station_map.destination) {
+                let allowed = objectives.fast_travel_allowed() && active_drill.is_none() && eva.phase() == EvaPhase::Inside;
+                let (travelling, message) =
+                    start_fast_travel(&mut fast_travel, &mut stepper, &station, listener, to, allowed, &locale);
+                notifications.push(LogSeverity::Info, message);
+                if travelling {
+                    station_map.destination = None;
+                    station_map.toggle_full_screen();
+                }
+            }
+        } else if drill_report.is_some() && (back || input.pressed(menu, Action::Confirm)) {
+            drill_report = None;
+            // Shift+Enter goes back to the checkpoint; anything else gives up on it
+            let retry = retry_scenario.take();
+            if let Some(scenario) = retry.as_ref().filter(|_| modifier && !back) {
+                restart_request = Some(scenario.clone());
+            } else if let Some(scenario) = retry {
+                if let Err(err) = checkpoint::clear(&checkpoint_dir, &scenario.name) {
+                    eprintln!("Warning: failed to clear checkpoint: {:#}", err);
+                }
             }
         }
 
-        // Draw UI
-        d.draw_fps(10, 10);
-        d.draw_text(
-            "Controls: WASD to move, QE for up/down, Mouse to look, TAB to toggle mouse, ESC to exit",
-            10,
-            30,
-            20,
-            Color::WHITE,
-        );
-    }
+        if let Some(slot) = save_request {
+            let save = SaveGame::capture(&station, &markers, &research, listener, yaw)
+                .with_session(&inventory, &world_items, &datapads, &objectives, &crew);
+            match save.write(&slot.path(&save_dir)) {
+                Ok(()) => notifications.push(LogSeverity::Info, format!("Saved to {}", slot.label())),
+                Err(err) => notifications.push(LogSeverity::Warning, format!("Save failed: {:#}", err)),
+            }
+            if let Some(save_menu) = &mut save_menu {
+                save_menu.refresh(&save_dir);
+            }
+        }
+        // A slot load and a checkpoint restart put the station back the same way
+        let mut loaded = None;
+        if let Some(slot) = load_request {
+            match SaveGame::read(&slot.path(&save_dir)) {
+                Ok(save) => loaded = Some((save, format!("Loaded {}", slot.label()), None)),
+                Err(err) => notifications.push(LogSeverity::Warning, format!("Load failed: {:#}", err)),
+            }
+        }
+        if let Some(scenario) = restart_request {
+            match Checkpoint::read(&checkpoint_dir, &scenario.name) {
+                Ok(checkpoint) => {
+                    let message = locale.format("checkpoint.restarted", &[("milestone", &checkpoint.milestone)]);
+                    let drill = Drill::resume(scenario, checkpoint.drill);
+                    loaded = Some((checkpoint.save, message, Some(drill)));
+                }
+                Err(err) => notifications.push(LogSeverity::Warning, format!("Checkpoint lost: {:#}", err)),
+            }
+        }
+        if let Some((save, message, resumed)) = loaded {
+            // Element states come from the save, so don't reset the terminal's element
+            station = SpaceStation::from_snapshot(&save.station);
+            research.restore(&save.research);
+            station.set_tunables(&research.upgraded(&tunables));
+            station.set_flares_directed(incidents.as_ref().is_some_and(Incidents::directs_flares));
+            camera.position = to_vector3(save.player_position());
+            yaw = save.player.yaw;
+            markers = Markers::from_saved(save.markers);
+            // Saves from before the rest of the session start it afresh
+            match &save.inventory {
+                Some(state) => inventory.restore(state),
+                None => inventory = Inventory::new(),
+            }
+            repair = None;
+            match &save.world_items {
+                Some(state) => world_items.restore(state),
+                None => world_items = WorldItems::load("assets/items.toml", &station).unwrap_or_default(),
+            }
+            datapads.restore(&save.journal.unwrap_or_default());
+            reading_datapad = None;
+            journal_selection = None;
+            objectives.restore(&save.objectives.unwrap_or_default());
+            match &save.crew {
+                Some(state) => crew.restore(state),
+                None => crew = Crew::load("assets/crew.toml", &station).unwrap_or_default(),
+            }
+            terminal.close();
+            active_drill = resumed;
+            if active_evacuation.take().is_some() {
+                crew.stand_down();
+            }
+            docking.cancel();
+            save_menu = None;
+            notifications.push(LogSeverity::Info, message);
+        }
+
+        // H at a command centre terminal flies the ship on approach from
+        // there; Backspace hands it back to the autopilot
+        if input.pressed(InputContext::Seated, Action::TakeHelm) && !terminal.read_only() {
+            let at_command = terminal
+                .module()
+                .and_then(|module| station.modules().get(module))
+                .is_some_and(|module| module.module_type == ModuleType::CommandCenter);
+            let id = if !at_command {
+                "docking.not_here"
+            } else if docking.take_helm() {
+                if let Some((module_idx, element_idx)) = terminal.close() {
+                    station.set_element_state(module_idx, element_idx, ElementState::Inactive);
+                }
+                "docking.helm"
+            } else {
+                "docking.nothing"
+            };
+            notifications.push(LogSeverity::Info, locale.get(id).to_string());
+        }
+        if input.pressed(InputContext::Helm, Action::Back) {
+            docking.release_helm();
+            notifications.push(LogSeverity::Info, locale.get("docking.autopilot").to_string());
+        }
+
+        // Seated at a terminal, its screen takes the arrow keys
+        if input.pressed(InputContext::Seated, Action::Back) {
+            if let Some((module_idx, element_idx)) = terminal.close() {
+                station.set_element_state(module_idx, element_idx, ElementState::Inactive);
+            }
+        }
+        let console_ui = InputContext::ConsoleUi;
+        if input.pressed(console_ui, Action::NavRight) {
+            terminal.next_page();
+        }
+        if input.pressed(console_ui, Action::NavLeft) {
+            terminal.previous_page();
+        }
+        if input.pressed(console_ui, Action::NavUp) {
+            terminal.scroll_up();
+        }
+        if input.pressed(console_ui, Action::NavDown) {
+            terminal.scroll_down();
+        }
+        // Enter paints the walls, Shift+Enter the trim
+        if input.pressed(console_ui, Action::Confirm) {
+            if let Some((module_idx, swatch)) = terminal.selected_swatch() {
+                let surface = if modifier { Surface::Trim } else { Surface::Walls };
+                let (id, severity) = if station.paint_module(module_idx, surface, swatch) {
+                    ("paint.applied", LogSeverity::Info)
+                } else {
+                    ("paint.not_allowed", LogSeverity::Warning)
+                };
+                let key = format!("paint.{}", surface.id());
+                let surface = locale.get(&key);
+                notifications.push(severity, locale.format(id, &[("surface", &surface), ("swatch", &swatch.name)]));
+            }
+            // Or, on the RADIATION page, makes this module the storm shelter,
+            // or stands it down if it already is
+            if let Some(module_idx) = terminal.selected_shelter() {
+                let (shelter, id) = if station.storm_shelter() == Some(module_idx) {
+                    (None, "radiation.shelter_off")
+                } else {
+                    (Some(module_idx), "radiation.shelter_on")
+                };
+                station.set_storm_shelter(shelter);
+                notifications.push(LogSeverity::Info, locale.format(id, &[("module", &module_idx)]));
+            }
+            // Or, on the REFIT page, buys this module's next tier
+            if let Some(module_idx) = terminal.selected_refit() {
+                match research.refit_module(&mut station, module_idx) {
+                    Ok(tier) => {
+                        let message = locale.format("refit.fitted", &[("name", &tier.name), ("module", &module_idx)]);
+                        notifications.push(LogSeverity::Info, message);
+                    }
+                    Err(err) => notifications.push(LogSeverity::Warning, format!("{:#}", err)),
+                }
+            }
+            // Or, on the COMMS page, trims the dish a step
+            if terminal.selected_comms() {
+                let id = if comms.align(&station) { "comms.aligned" } else { "comms.aligned_best" };
+                let percent = format!("{:.0}", comms.alignment() * 100.0);
+                notifications.push(LogSeverity::Info, locale.format(id, &[("percent", &percent)]));
+            }
+            // Or, at the comms console, puts the picked ship back ten
+            // minutes, or brings it forward with Shift
+            if let Some(index) = terminal.selected_arrival() {
+                let now = station.clock().now().as_secs_f32();
+                let picked = docking.schedule().get(index).map(|arrival| (arrival.name.clone(), arrival.at));
+                if let Some((name, at)) = picked {
+                    let step = if modifier { -docking::RESCHEDULE_STEP } else { docking::RESCHEDULE_STEP };
+                    if let Some(moved) = docking.reschedule(index, at + step, now) {
+                        terminal.select_arrival(moved);
+                        let minutes = ((docking.schedule()[moved].at - now) / 60.0).ceil().max(0.0);
+                        let message = locale.format("docking.rescheduled", &[("name", &name), ("minutes", &minutes)]);
+                        notifications.push(LogSeverity::Info, message);
+                    }
+                }
+            }
+        }
+
+        for slot in 0..INVENTORY_SLOTS {
+            if input.pressed(InputContext::Gameplay, Action::Slot(slot as u8)) {
+                inventory.select(slot);
+            }
+        }
+        if input.pressed(InputContext::Gameplay, Action::UseItem) {
+            if let Some(message) = use_item(&mut inventory, &mut station, &mut work_lights, &mut repair, listener, yaw, &locale) {
+                notifications.push(LogSeverity::Info, message);
+            }
+        }
+        // The wet vac runs for as long as the use key is held
+        let wet_vac = inventory.selected_item().is_some_and(|stack| stack.kind == ItemKind::WetVac);
+        if wet_vac && input.held(InputContext::Gameplay, Action::UseItem) {
+            if steps > 0 {
+                let forward = glam::Vec3::new(look_dir.x, look_dir.y, look_dir.z);
+                vacuumed += spills.vacuum(listener, forward, rl.get_frame_time());
+            }
+        } else if vacuumed > 0.0 {
+            notifications.push(LogSeverity::Info, format!("Vacuumed up {:.1} L", vacuumed));
+            vacuumed = 0.0;
+        }
+        if let Some(job) = &mut repair {
+            if let Some(message) = job.update(&mut station, &mut inventory, listener, rl.get_frame_time()) {
+                // Abandoned jobs end short of done
+                if job.progress() >= 1.0 {
+                    if let Err(err) = analytics.repaired() {
+                        eprintln!("Warning: analytics not recorded: {:#}", err);
+                    }
+                }
+                notifications.push(LogSeverity::Info, message);
+                repair = None;
+            }
+        }
+        if input.pressed(InputContext::Gameplay, Action::ToggleHelmetLight) {
+            notifications.push(LogSeverity::Info, toggle_helmet_light(&mut helmet_light, &locale));
+        }
+
+        // V at the holotable fast-travels to the module selected on it
+        if input.pressed(InputContext::Gameplay, Action::FastTravel) {
+            let selected = holotable
+                .as_ref()
+                .filter(|table| table.top().distance(listener) <= HOLOTABLE_REACH)
+                .and_then(|table| table.selected)
+                .and_then(|id| station.module_index(id));
+            let message = match selected {
+                Some(to) => {
+                    let allowed = objectives.fast_travel_allowed() && active_drill.is_none() && eva.phase() == EvaPhase::Inside;
+                    start_fast_travel(&mut fast_travel, &mut stepper, &station, listener, to, allowed, &locale).1
+                }
+                None => locale.get("travel.pick_first").to_string(),
+            };
+            notifications.push(LogSeverity::Info, message);
+        }
+
+        // In build mode the wheel picks what to build and the ghost follows
+        // whichever side of this module the player faces
+        if input.pressed(InputContext::Gameplay, Action::ToggleBuildMode) {
+            construction.toggle();
+            let id = if construction.active { "build.on" } else { "build.off" };
+            notifications.push(LogSeverity::Info, locale.get(id).to_string());
+        }
+        let building = construction.active && !ui_captured && !station_map.full_screen && eva.phase() == EvaPhase::Inside;
+        if building && !fly_camera.active {
+            let wheel = rl.get_mouse_wheel_move();
+            if wheel != 0.0 {
+                construction.cycle(-wheel.signum() as i32);
+            }
+        }
+        let ghost = if building {
+            construction.ghost(&station, listener, glam::Vec3::new(look_dir.x, look_dir.y, look_dir.z))
+        } else {
+            None
+        };
+
+        // Z in build mode takes down the neighbour being faced, for parts.
+        // Not mid-evacuation: the pods are tracked by module.
+        if building && active_evacuation.is_none() && input.pressed(InputContext::Gameplay, Action::Deconstruct) {
+            let look = glam::Vec3::new(look_dir.x, look_dir.y, look_dir.z);
+            let result = construction
+                .salvage_target(&station, listener, look)
+                .ok_or(construction::BuildProblem::NoTarget)
+                .and_then(|module| {
+                    let module_type = station.modules()[module].module_type;
+                    let parts = construction.deconstruct(&mut station, &crew, module)?;
+                    Ok((module_type, parts))
+                });
+            let message = match result {
+                Ok((module_type, parts)) => {
+                    locale.format("build.salvaged", &[("module", &format!("{:?}", module_type)), ("parts", &parts)])
+                }
+                Err(problem) => locale.get(problem.id()).to_string(),
+            };
+            notifications.push(LogSeverity::Info, message);
+        }
+
+        // Clicking builds the ghost in build mode; otherwise pointing at a
+        // miniature on the holotable and clicking puts its module on the side screens
+        if clicked && cursor_focus.captured() && !ui_captured && !station_map.full_screen {
+            if let Some(ghost) = &ghost {
+                let message = match construction.build(&mut station, ghost) {
+                    Ok(_) => locale.format("build.built", &[("module", &format!("{:?}", ghost.module_type))]),
+                    Err(problem) => locale.get(problem.id()).to_string(),
+                };
+                notifications.push(LogSeverity::Info, message);
+            } else if let Some(table) = &mut holotable {
+                let forward = glam::Vec3::new(look_dir.x, look_dir.y, look_dir.z);
+                if let Some(module) = table.pick(&station, listener, forward) {
+                    table.select(station.modules()[module].id);
+                }
+            }
+        }
+
+        // F reads a nearby datapad, picks up an item or work light or uses the nearest element
+        let nearest_datapad = datapads.nearest(listener, INTERACT_REACH);
+        let nearest_item = world_items.nearest(listener, INTERACT_REACH);
+        let nearest_work_light = work_lights.nearest(listener, INTERACT_REACH);
+        let nearest_element = station.nearest_element(listener, INTERACT_REACH);
+        if input.pressed(InputContext::Gameplay, Action::Interact) && !eva.cycling() {
+            if eva.outside() {
+                let id = if eva.begin_entry(&mut station) { "eva.cycling_in" } else { "eva.no_hatch" };
+                notifications.push(LogSeverity::Info, locale.get(id).to_string());
+            } else if let Some(index) = nearest_datapad {
+                if let Some(datapad) = datapads.collect(index, &mut station) {
+                    let message = format!("Datapad added to journal: {}", datapad.title);
+                    notifications.push(LogSeverity::Info, message.clone());
+                    station.log_event(LogSeverity::Info, message);
+                    reading_datapad = Some(index);
+                }
+            } else if let Some(index) = nearest_item {
+                match world_items.pick_up(index, &mut inventory) {
+                    Some((kind, 0)) => notifications.push(
+                        LogSeverity::Warning,
+                        format!("No room for {}", kind.name()),
+                    ),
+                    Some((kind, count)) => notifications.push(
+                        LogSeverity::Info,
+                        format!("Picked up {} x{}", kind.name(), count),
+                    ),
+                    None => {}
+                }
+            } else if let Some(index) = nearest_work_light {
+                if inventory.add(ItemKind::WorkLight, 1) == 0 {
+                    work_lights.pick_up(index);
+                    notifications.push(LogSeverity::Info, locale.get("light.work_light_taken").to_string());
+                } else {
+                    notifications.push(
+                        LogSeverity::Warning,
+                        format!("No room for {}", ItemKind::WorkLight.name()),
+                    );
+                }
+            } else if let Some((module_idx, _)) = nearest_element.filter(|&(module_idx, element_idx)| {
+                modifier && station.modules()[module_idx].interactive_elements[element_idx].element_type == InteractionType::LightControl
+            }) {
+                // Shift+F on a light switch turns the dimmer down a quarter, then back round to full
+                let level = station.modules()[module_idx].light_level;
+                let level = if level <= 0.25 + f32::EPSILON { 1.0 } else { level - 0.25 };
+                station.set_light_level(module_idx, level);
+                let percent = (level * 100.0).round() as u32;
+                notifications.push(LogSeverity::Info, locale.format("light.dimmed", &[("percent", &percent)]));
+            } else if let Some(clearance) =
+                nearest_element.and_then(|(module, element)| datapads.missing_clearance(&station, module, element))
+            {
+                let message = locale.format("datapad.restricted", &[("clearance", &clearance)]);
+                notifications.push(LogSeverity::Warning, message);
+            } else if let Some((module_idx, element_idx)) = nearest_element {
+                if let Some(element_type) = station.activate_element(module_idx, element_idx) {
+                    if element_type == InteractionType::Door {
+                        haptics.play(HapticCategory::Door, DOOR_THUNK, 1.0);
+                    }
+                    if Terminal::opens_for(element_type) {
+                        terminal.open(module_idx, element_idx, element_type);
+                    } else if element_type == InteractionType::AirlockControl && eva.begin_exit(&mut station, module_idx) {
+                        notifications.push(LogSeverity::Info, locale.get("eva.cycling_out").to_string());
+                    } else if element_type.activation() == Activation::Container {
+                        // Containers are emptied in one go and close straight away
+                        let mut taken = world_items.take_from_container(station.modules()[module_idx].id, element_idx, &mut inventory);
+                        // Then carried stacks are topped up from the module's stores
+                        for resource in Resource::ALL {
+                            let Some(kind) = resource.item() else {
+                                continue;
+                            };
+                            let wanted = kind.max_stack().saturating_sub(inventory.count(kind));
+                            let available = station.resources().stock(station.modules()[module_idx].id, resource).floor() as u32;
+                            let count = wanted.min(available);
+                            let count = count - inventory.add(kind, count);
+                            if count == 0 {
+                                continue;
+                            }
+                            station.take_resource(module_idx, resource, count as f32);
+                            match taken.iter_mut().find(|(k, _)| *k == kind) {
+                                Some((_, total)) => *total += count,
+                                None => taken.push((kind, count)),
+                            }
+                        }
+                        let message = if taken.is_empty() {
+                            "Nothing you can carry in here".to_string()
+                        } else {
+                            let names: Vec<String> = taken
+                                .iter()
+                                .map(|(kind, count)| format!("{} x{}", kind.name(), count))
+                                .collect();
+                            format!("Took {}", names.join(", "))
+                        };
+                        notifications.push(LogSeverity::Info, message);
+                        station.set_element_state(module_idx, element_idx, ElementState::Inactive);
+                    }
+                }
+            }
+        }
+        let terminal_screen = terminal.screen(&station, &docking, &research, &comms);
+        // Messages stay marked new until the player's been through the page
+        let on_comms_page = terminal.is_open() && terminal.page() == TerminalPage::Comms;
+        if std::mem::replace(&mut reading_comms, on_comms_page) && !on_comms_page {
+            comms.mark_read();
+        }
+
+        let map_layout = MapLayout::build(&station);
+        signage.refresh(&station);
+        // The HUD is laid out in UI units, which are screen pixels over the UI scale
+        if rl.is_window_resized() {
+            display = DisplaySettings::resolve(&config, display.handheld, (rl.get_screen_width(), rl.get_screen_height()));
+        }
+        let (ui_width, ui_height) = display.ui_size((rl.get_screen_width(), rl.get_screen_height()));
+        let screen_size = glam::Vec2::new(ui_width as f32, ui_height as f32);
+        let full_map_view = station_map.full_view(&map_layout, screen_size * 0.5, screen_size);
+        if station_map.full_screen {
+            if rl.is_mouse_button_down(MouseButton::MOUSE_BUTTON_LEFT) {
+                let drag = glam::Vec2::new(mouse_delta.x, mouse_delta.y) / display.ui_scale;
+                station_map.pan_by(drag, &full_map_view);
+            }
+            station_map.zoom_by(rl.get_mouse_wheel_move());
+            if rl.is_mouse_button_pressed(MouseButton::MOUSE_BUTTON_RIGHT) {
+                let mouse = rl.get_mouse_position();
+                let cursor = glam::Vec2::new(mouse.x, mouse.y) / display.ui_scale;
+                if let Some(module) = map_layout.module_at(&full_map_view, cursor) {
+                    station_map.toggle_destination(module);
+                }
+            }
+        }
+        // The way from wherever the player is now; arriving ends it
+        let here = station.nearest_module(listener);
+        if let (Some(module), EvaPhase::Inside) = (here, eva.phase()) {
+            fast_travel.visit(station.modules()[module].id);
+        }
+        if station_map.destination == here {
+            station_map.destination = None;
+        }
+        let route = match (here, station_map.destination) {
+            (Some(from), Some(to)) => station.find_path(from, to),
+            _ => None,
+        };
+        let route_points = map_layout.route_points(route.as_deref().unwrap_or_default());
+
+        orbit_preview.update(rl.get_frame_time());
+        let lighting_time = orbit_preview.lighting_time(station.clock().now());
+        let orbit = station.orbit();
+        let sun_direction = orbit.sun_direction(lighting_time);
+        let sun_patch = match display.shadows {
+            ShadowQuality::Off => None,
+            ShadowQuality::Low => project_sun_patch(&window.unframed(), sun_direction, orbit.sunlight(lighting_time), 0.01),
+            ShadowQuality::High => project_sun_patch(&window, sun_direction, orbit.sunlight(lighting_time), 0.01),
+        };
+
+        starfield.update(rl.get_frame_time());
+        starfield.set_attitude(orbit.attitude(lighting_time));
+        if let Some(backdrop) = &mut backdrop {
+            backdrop.update(rl.get_frame_time());
+        }
+        camera_shake.update(rl.get_frame_time());
+        // Suit air outside, cabin air inside
+        // Inside, the player breathes whatever module they're in
+        let breathing = if eva.outside() {
+            eva.oxygen()
+        } else {
+            station
+                .nearest_module(listener)
+                .map_or(station.telemetry().oxygen_level, |module| station.modules()[module].atmosphere.oxygen_level())
+        };
+        haptics.set_oxygen(breathing);
+        // raylib 5.0 has no call to drive the motors, so the levels go to the
+        // F3 overlay until the binding gets SetGamepadVibration
+        debug_overlay.rumble = haptics.update(rl.get_frame_time());
+        if helmet_light.update(rl.get_frame_time()) {
+            notifications.push(LogSeverity::Warning, locale.get("light.helmet_flat").to_string());
+        }
+        // Cabin lights follow the grid; the helmet stays with the player even in the fly camera
+        let cabin_powered = station.nearest_module(listener).is_none_or(|module| station.modules()[module].powered);
+        let mut lights = LightSet::new(cabin_ambient(&station.telemetry(), cabin_powered));
+        alarm.update(incidents.as_ref().is_some_and(Incidents::alarm), rl.get_frame_time());
+        lights.alarm = alarm.redness();
+        if let Some(klaxon) = &mut klaxon {
+            if alarm.sounding() && !klaxon.is_playing() {
+                klaxon.play();
+            } else if !alarm.sounding() && klaxon.is_playing() {
+                klaxon.stop();
+            }
+        }
+        let forward = glam::Vec3::new(look_dir.x, look_dir.y, look_dir.z);
+        if let Some(light) = helmet_light.light(listener, forward) {
+            lights.add(light);
+        }
+        for light in ceiling_lights(&station, lights.alarm) {
+            lights.add(light);
+        }
+        for light in work_lights.lights() {
+            lights.add(light);
+        }
+        guidance_strips.update(&lights, rl.get_frame_time());
+        reverb_zones.update(&station, listener, rl.get_frame_time());
+        debug_overlay.reverb = reverb_zones.module().map(|module| (module, reverb_zones.current()));
+        debug_overlay.record_frame(rl.get_frame_time());
+
+        // The benchmark flies its own path through the fly camera, and ends the run with it
+        if let Some(benchmark) = &mut benchmark {
+            if !benchmark.advance(rl.get_frame_time()) {
+                break;
+            }
+            for (kind, position) in benchmark.bursts() {
+                effects.spawn(kind, position, BURST_DURATION);
+            }
+            fly_camera.active = true;
+            fly_camera.pose = benchmark.pose();
+        }
+
+        // Render from a shaken copy so the shake never accumulates into the player camera.
+        // The fly camera is a debugging view and is never shaken.
+        let (eye, target, up) = if let Some(pose) = attract.pose() {
+            (pose.position, pose.position + pose.forward(), glam::Vec3::Y)
+        } else if fly_camera.active {
+            let pose = fly_camera.pose;
+            (pose.position, pose.position + pose.forward(), glam::Vec3::Y)
+        } else {
+            camera_shake.offset().apply(listener, glam::Vec3::new(look_dir.x, look_dir.y, look_dir.z), player_up)
+        };
+        // A panorama takes over the view, one square cube face a frame
+        let panorama_view = panorama.as_mut().and_then(|capture| capture.next_view(eye, target - eye));
+        let (eye, target, up, fovy) = match panorama_view {
+            Some((eye, target, up)) => (eye, target, up, 90.0),
+            None => (eye, target, up, camera.fovy),
+        };
+        let view_camera = Camera3D::perspective(to_vector3(eye), to_vector3(target), to_vector3(up), fovy);
+        // Blend factor between the last two sim ticks
+        let sim_alpha = stepper.alpha();
+
+        let scene_size = match &panorama {
+            Some(capture) if panorama_view.is_some() => (capture.face_size as i32, capture.face_size as i32),
+            _ => display.scene_size((rl.get_screen_width(), rl.get_screen_height())),
+        };
+        if (scene_target.texture.width, scene_target.texture.height) != scene_size && failed_scene_size != Some(scene_size) {
+            // On failure the old target carries on, stretched over the window
+            match load_scene_target(&mut rl, &thread, scene_size) {
+                Ok(target) => {
+                    scene_target = target;
+                    failed_scene_size = None;
+                }
+                Err(err) => {
+                    eprintln!("Warning: couldn't resize the scene to {}x{}: {}", scene_size.0, scene_size.1, err);
+                    notifications.push(LogSeverity::Warning, locale.get("hud.render_target_failed"));
+                    failed_scene_size = Some(scene_size);
+                }
+            }
+        }
+
+        // Ask for the puff mips the backdrop needs at this size, then upload
+        // whatever the streamer settles on
+        if let Some(backdrop) = &backdrop {
+            let pixels_per_unit = scene_size.1 as f32 / (2.0 * (fovy.to_radians() / 2.0).tan());
+            for puff in backdrop.billboards(eye) {
+                let distance = puff.position.distance(eye).max(0.01);
+                texture_streamer.request(puff_id, puff.size * pixels_per_unit / distance);
+            }
+        }
+        let streamed = texture_streamer.update();
+        let puff_changed = streamed.iter().any(|command| match command {
+            StreamCommand::Load { texture, .. } | StreamCommand::Evict { texture, .. } => *texture == puff_id,
+        });
+        if puff_changed {
+            let level = texture_streamer.resident_level(puff_id).unwrap_or(0);
+            // A failed upload keeps the level already on the GPU
+            match load_streamed_texture(&mut rl, &thread, &puff_mips, level) {
+                Ok(texture) => puff_texture = texture,
+                Err(err) => eprintln!("Warning: couldn't stream backdrop mip {}: {}", level, err),
+            }
+        }
+        debug_overlay.textures = Some(texture_streamer.stats());
+
+        let mut d = rl.begin_drawing(&thread);
+        d.clear_background(Color::BLACK);
+
+        // Rough count for the debug overlay; raylib batches some of these
+        let mut draw_calls = 0;
+        let scene_start = Instant::now();
+
+        // 3D drawing
+        {
+            let mut target = d.begin_texture_mode(&thread, &mut scene_target);
+            target.clear_background(Color::BLACK);
+            let mut d = target.begin_mode3D(view_camera);
+
+            // Draw the backdrop first, additively, behind everything else
+            if let Some(backdrop) = &backdrop {
+                let mut d = d.begin_blend_mode(BlendMode::BLEND_ADDITIVE);
+                for puff in backdrop.billboards(eye) {
+                    d.draw_billboard(
+                        view_camera,
+                        &puff_texture,
+                        to_vector3(puff.position),
+                        puff.size,
+                        to_color(puff.color, puff.alpha),
+                    );
+                    draw_calls += 1;
+                }
+            }
+
+            // Surfaces are tinted by the light reaching their centre. The
+            // room's walls and window frame take its module's paint.
+            let room = station.nearest_module(glam::Vec3::ZERO).map(|i| &station.modules()[i]);
+            let coat = |surface: Surface, standard: Color| match room.and_then(|module| module.paint.coat(surface)) {
+                Some(coat) => to_color(glam::Vec3::from(coat.color), 1.0),
+                None => standard,
+            };
+            let (wall_paint, trim_paint) = (coat(Surface::Walls, Color::LIGHTGRAY), coat(Surface::Trim, Color::DARKGRAY));
+            let floor = lit(&lights, Color::GRAY, glam::Vec3::ZERO, glam::Vec3::Y);
+            let ceiling = lit(&lights, Color::GRAY, glam::Vec3::new(0.0, 3.0, 0.0), glam::Vec3::NEG_Y);
+            let back_wall = lit(&lights, wall_paint, glam::Vec3::new(0.0, 1.5, -2.9), glam::Vec3::Z);
+            let left_wall = lit(&lights, wall_paint, glam::Vec3::new(-2.9, 1.5, 0.0), glam::Vec3::X);
+            let right_wall = lit(&lights, wall_paint, glam::Vec3::new(2.9, 1.5, 0.0), glam::Vec3::NEG_X);
+            let front_wall = lit(&lights, wall_paint, glam::Vec3::new(0.0, 1.5, 2.9), glam::Vec3::NEG_Z);
+
+            // Draw floor
+            d.draw_plane(
+                Vector3::new(0.0, 0.0, 0.0),
+                Vector2::new(6.0, 6.0),
+                floor,
+            );
+            if let Some(module) = room {
+                draw_calls += draw_floor_grime(&mut d, module, &lights);
+            }
+            
+            // Draw ceiling
+            d.draw_plane(
+                Vector3::new(0.0, 3.0, 0.0),
+                Vector2::new(6.0, 6.0),
+                ceiling,
+            );
+
+            // Draw walls (excluding window wall)
+            // Back wall
+            d.draw_cube(Vector3::new(0.0, 1.5, -3.0), 6.0, 3.0, 0.2, back_wall);
+            // Left wall
+            d.draw_cube(Vector3::new(-3.0, 1.5, 0.0), 0.2, 3.0, 6.0, left_wall);
+            // Right wall
+            d.draw_cube(Vector3::new(3.0, 1.5, 0.0), 0.2, 3.0, 6.0, right_wall);
+
+            // Front wall with window
+            // Bottom part
+            d.draw_cube(Vector3::new(0.0, 0.5, 3.0), 6.0, 1.0, 0.2, front_wall);
+            // Top part
+            d.draw_cube(Vector3::new(0.0, 2.5, 3.0), 6.0, 1.0, 0.2, front_wall);
+            // Left part
+            d.draw_cube(Vector3::new(-2.0, 1.5, 3.0), 2.0, 1.0, 0.2, front_wall);
+            // Right part
+            d.draw_cube(Vector3::new(2.0, 1.5, 3.0), 2.0, 1.0, 0.2, front_wall);
+            
+            // Window (semi-transparent)
+            d.draw_cube(Vector3::new(0.0, 1.5, 3.0), 2.0, 1.0, 0.1, Color::new(100, 149, 237, 100));
+            d.draw_cube_wires(Vector3::new(0.0, 1.5, 3.0), 2.0, 1.0, 0.1, Color::DARKBLUE);
+            // Window frame mullion
+            d.draw_cube(Vector3::new(0.0, 1.5, 3.0), 0.08, 1.0, 0.2, trim_paint);
+            draw_calls += 12;
+
+            // Sunlight patches on the floor; the frame's shadow falls between them
+            if let Some(patch) = &sun_patch {
+                let color = to_color(glam::Vec3::new(1.0, 0.95, 0.8), 0.5 * patch.intensity);
+                for quad in &patch.quads {
+                    let [a, b, c, e] = quad.map(to_vector3);
+                    // Both windings so the patch shows regardless of projection direction
+                    d.draw_triangle3D(a, b, c, color);
+                    d.draw_triangle3D(a, c, e, color);
+                    d.draw_triangle3D(a, c, b, color);
+                    d.draw_triangle3D(a, e, c, color);
+                    draw_calls += 4;
+                }
+            }
+
+            for strip in guidance_strips.strips() {
+                draw_guidance_strip(&mut d, strip, &lights);
+                draw_calls += 1;
+            }
+
+            for (index, door) in station.doors().iter().enumerate() {
+                draw_door(&mut d, door, station.door_openness(index), &lights);
+                draw_calls += 1;
+            }
+
+            // The station from outside, on a spacewalk
+            if eva.phase() != EvaPhase::Inside {
+                draw_calls += draw_station_exterior(&mut d, &station, sun_direction);
+            }
+
+            // Pools from the helmet and work lights
+            if display.shadows != ShadowQuality::Off {
+                for pool in lights.lights().iter().filter_map(|light| light_pool(light, &TEST_ROOM)) {
+                    draw_light_pool(&mut d, &pool);
+                    draw_calls += 1;
+                }
+            }
+
+            // Draw the starfield in one instanced call
+            {
+                let [time, speed, amount, pixel_size] = star_uniforms;
+                let shader = star_material.shader_mut();
+                shader.set_shader_value(time, starfield.time());
+                shader.set_shader_value(speed, starfield.config.twinkle_speed);
+                shader.set_shader_value(amount, starfield.config.twinkle_amount);
+                shader.set_shader_value(pixel_size, Vector2::new(2.0 / scene_size.0 as f32, 2.0 / scene_size.1 as f32));
+                let transforms: Vec<raylib::ffi::Matrix> =
+                    starfield.instances(eye).map(|star| to_instance_matrix(star.packed())).collect();
+                let _blend = d.begin_blend_mode(BlendMode::BLEND_ADDITIVE);
+                unsafe {
+                    raylib::ffi::DrawMeshInstanced(
+                        *star_quad.as_ref(),
+                        *star_material.as_ref(),
+                        transforms.as_ptr(),
+                        transforms.len() as i32,
+                    )
+                };
+                draw_calls += 1;
+            }
+
+            for index in 0..props.props().len() {
+                draw_prop(&mut d, &props, index, &lights);
+                draw_calls += 1;
+            }
+
+            // Crew as capsules, coloured by what they're doing; those strapped
+            // into a pod are out of sight
+            for member in crew.members().iter().filter(|member| member.activity != Activity::Evacuated) {
+                let albedo = match member.activity {
+                    Activity::Working => glam::Vec3::new(0.9, 0.55, 0.15),
+                    Activity::Eating | Activity::Resting => glam::Vec3::new(0.3, 0.55, 0.9),
+                    Activity::Sleeping => glam::Vec3::new(0.35, 0.35, 0.5),
+                    Activity::Fleeing => glam::Vec3::new(0.95, 0.2, 0.2),
+                    Activity::Sheltering => glam::Vec3::new(0.6, 0.3, 0.85),
+                    Activity::Servicing => glam::Vec3::new(0.9, 0.85, 0.2),
+                    Activity::Cleaning => glam::Vec3::new(0.3, 0.8, 0.5),
+                    Activity::Evacuating | Activity::Evacuated => glam::Vec3::new(1.0, 0.4, 0.1),
+                };
+                let color = to_color(lights.shade(albedo, member.position + glam::Vec3::Y, glam::Vec3::Y), 1.0);
+                let (radius, height) = (0.3, 1.8);
+                let base = member.position + glam::Vec3::Y * radius;
+                d.draw_cylinder(to_vector3(base), radius, radius, height - radius * 2.0, 12, color);
+                d.draw_sphere(to_vector3(base), radius, color);
+                d.draw_sphere(to_vector3(base + glam::Vec3::Y * (height - radius * 2.0)), radius, color);
+                draw_calls += 3;
+            }
+
+            // The drone, with a light that blinks while it's out and working
+            let hull = to_color(lights.shade(glam::Vec3::new(0.75, 0.75, 0.7), drone.position, glam::Vec3::Y), 1.0);
+            d.draw_sphere(to_vector3(drone.position), 0.25, hull);
+            let blink = drone.state != DroneState::Docked && station.clock().now().as_millis() / 500 % 2 == 0;
+            let beacon = if blink { Color::ORANGE } else { Color::DARKGRAY };
+            d.draw_sphere(to_vector3(drone.position + glam::Vec3::Y * 0.25), 0.06, beacon);
+            draw_calls += 2;
+
+            // Other players in their own suits
+            for (name, _, position) in chat.members().filter(|(name, _, _)| *name != config.chat.name) {
+                let look = replicator.avatar(name).cloned().unwrap_or_default();
+                let gesture = chat.gesture(name);
+                draw_avatar(&mut d, &look, position, gesture, &lights);
+                draw_calls += 6;
+            }
+
+            if let Some(approach) = docking.approach() {
+                draw_ship(&mut d, approach);
+                draw_calls += 5;
+            }
+
+            if let Some(ghost) = &ghost {
+                draw_build_ghost(&mut d, &station, ghost);
+                draw_calls += 2;
+            }
+
+            // The holotable, its miniatures glowing over it
+            if let Some(table) = &holotable {
+                draw_holotable(&mut d, table, &lights);
+                let mut d = d.begin_blend_mode(BlendMode::BLEND_ADDITIVE);
+                for miniature in table.miniatures(&station) {
+                    let selected = table.selected == Some(station.modules()[miniature.module].id);
+                    draw_calls += draw_miniature(&mut d, &station.modules()[miniature.module], &miniature, selected);
+                }
+                draw_calls += 2;
+            }
+
+            // Uncollected datapads
+            for pad in datapads.placed().iter().filter(|pad| !pad.collected) {
+                let color = lit(&lights, Color::SKYBLUE, pad.position, glam::Vec3::Y);
+                d.draw_cube(to_vector3(pad.position), 0.25, 0.04, 0.18, color);
+                draw_calls += 1;
+            }
+
+            // Loose items
+            for item in world_items.items() {
+                let color = lit(&lights, item_color(item.kind), item.position, glam::Vec3::Y);
+                d.draw_cube(to_vector3(item.position), 0.3, 0.3, 0.3, color);
+                draw_calls += 1;
+            }
+
+            // Work lights: a stand with the lamp on top
+            for &position in work_lights.positions() {
+                d.draw_cube(to_vector3(position + glam::Vec3::Y * 0.1), 0.15, 0.2, 0.15, Color::DARKGRAY);
+                d.draw_sphere(to_vector3(position + glam::Vec3::Y * 0.3), 0.08, Color::new(255, 230, 180, 255));
+                draw_calls += 2;
+            }
+
+            // Player markers, on a stalk so they read as standing in place
+            for marker in markers.markers() {
+                let position = to_vector3(marker.position());
+                let color = to_color(marker.kind.color(), 1.0);
+                d.draw_line_3D(position, Vector3::new(position.x, position.y - 0.4, position.z), color);
+                d.draw_sphere(position, 0.08, color);
+                draw_calls += 2;
+            }
+
+            // Event particles
+            for effect in effects.emitters() {
+                let color = match effect.emitter.particle_type {
+                    ParticleType::Spark => Color::ORANGE,
+                    ParticleType::Fire => Color::RED,
+                    ParticleType::Smoke => Color::DARKGRAY,
+                    _ => Color::GRAY,
+                };
+                for particle in &effect.emitter.particles {
+                    let size = particle.size;
+                    let position = particle.interpolated_position(sim_alpha);
+                    d.draw_cube(to_vector3(position), size, size, size, color);
+                    draw_calls += 1;
+                }
+            }
+        }
+
+        if let Some(capture) = panorama.as_mut().filter(|_| panorama_view.is_some()) {
+            let mut image = unsafe { Image::from_raw(raylib::ffi::LoadImageFromTexture(scene_target.texture)) };
+            image.flip_vertical();
+            let rgba = image.get_image_data().iter().flat_map(|c| [c.r, c.g, c.b, c.a]).collect();
+            if let Err(err) = capture.push_face(rgba) {
+                eprintln!("Warning: {:#}", err);
+                panorama = None;
+            }
+        }
+        // Stitching a big panorama takes a moment, so it's written in the background
+        if let Some(capture) = panorama.take_if(|capture| capture.complete()) {
+            notifications.push(LogSeverity::Info, format!("Saving panorama to {}", capture.path.display()));
+            std::thread::spawn(move || match capture.write_png() {
+                Ok(()) => println!("Panorama saved to {}", capture.path.display()),
+                Err(err) => eprintln!("Warning: failed to save panorama: {:#}", err),
+            });
+        }
+
+        // Render textures are stored upside down
+        let (scene_width, scene_height) = (scene_target.texture.width as f32, scene_target.texture.height as f32);
+        let (screen_width, screen_height) = (d.get_screen_width() as f32, d.get_screen_height() as f32);
+        d.draw_texture_pro(
+            &scene_target,
+            Rectangle::new(0.0, 0.0, scene_width, -scene_height),
+            Rectangle::new(0.0, 0.0, screen_width, screen_height),
+            Vector2::zero(),
+            0.0,
+            Color::WHITE,
+        );
+        // Fast travel dips to black halfway through the hop
+        let fade = fast_travel.fade();
+        if fade > 0.0 {
+            d.draw_rectangle(0, 0, screen_width as i32, screen_height as i32, to_color(glam::Vec3::ZERO, fade));
+        }
+
+        let ui_start = Instant::now();
+        if let Some(benchmark) = &mut benchmark {
+            benchmark.record("scene", ui_start - scene_start);
+        }
+
+        // Draw UI, which the attract tour hides
+        if !attract.active() {
+            // Zoomed by the UI scale, so layouts work in UI units
+            let hud_camera = Camera2D {
+                offset: Vector2::zero(),
+                target: Vector2::zero(),
+                rotation: 0.0,
+                zoom: display.ui_scale,
+            };
+            let mut d = d.begin_mode2D(hud_camera);
+            d.draw_fps(10, 10);
+            d.draw_text(
+                locale.get("hud.controls"),
+                10,
+                30,
+                20,
+                Color::WHITE,
+            );
+            if observer.active() {
+                let status = match observer.following(&crew) {
+                    Some(member) => locale.format("observer.following", &[("name", &member.definition.name)]),
+                    None => locale.get("observer.free").to_string(),
+                };
+                d.draw_text(&status, 10, 80, 20, to_color(MarkerKind::Annotation.color(), 1.0));
+            } else if fly_camera.active {
+                d.draw_text(
+                    &locale.format("hud.fly_camera", &[("speed", &format!("{:.2}", fly_camera.speed_multiplier()))]),
+                    10,
+                    80,
+                    20,
+                    Color::SKYBLUE,
+                );
+            }
+            if orbit_preview.enabled {
+                d.draw_text(
+                    &locale.format("hud.orbit_preview", &[("speed", &format!("{:.0}", orbit_preview.speed))]),
+                    10,
+                    55,
+                    20,
+                    Color::YELLOW,
+                );
+            }
+
+            // Station map, either full screen or as a corner minimap
+            let player = map::to_map(listener);
+            let heading = glam::Vec2::new(look_dir.x, look_dir.z);
+            if station_map.full_screen {
+                let view = station_map.full_view(&map_layout, screen_size * 0.5, screen_size);
+                d.draw_rectangle(0, 0, screen_size.x as i32, screen_size.y as i32, Color::new(0, 0, 0, 220));
+                draw_station_map(&mut d, &map_layout, &view, &markers, player, heading, &route_points);
+                draw_marker_list(&mut d, (ui_width, ui_height), &markers);
+                d.draw_text(
+                    &locale.format("map.title", &[("zoom", &format!("{:.1}", station_map.zoom()))]),
+                    10,
+                    10,
+                    20,
+                    Color::WHITE,
+                );
+                if let Some(destination) = station_map.destination {
+                    let module = destination.to_string();
+                    let text = match &route {
+                        Some(path) => {
+                            let steps = (path.len() - 1).to_string();
+                            locale.format("map.route", &[("module", &module), ("steps", &steps)])
+                        }
+                        None => locale.format("map.no_route", &[("module", &module)]),
+                    };
+                    d.draw_text(&text, 10, 36, 18, Color::SKYBLUE);
+                }
+            } else {
+                let (size, margin) = (180, 10);
+                let x = screen_size.x as i32 - size - margin;
+                let center = glam::Vec2::new((x + size / 2) as f32, (margin + size / 2) as f32);
+                let view = station_map.minimap_view(player, center, glam::Vec2::splat(size as f32));
+                d.draw_rectangle(x, margin, size, size, Color::new(0, 0, 0, 160));
+                {
+                    // Scissor rectangles are in real pixels, not UI units
+                    let pixels = |v: i32| (v as f32 * display.ui_scale) as i32;
+                    let mut d = d.begin_scissor_mode(pixels(x), pixels(margin), pixels(size), pixels(size));
+                    draw_station_map(&mut d, &map_layout, &view, &markers, player, heading, &route_points);
+                }
+                d.draw_rectangle_lines(x, margin, size, size, Color::GRAY);
+            }
+
+            // Spilled liquid in the player's module, as screen-space metaballs
+            let view_forward = target - eye;
+            let here = station.nearest_module(eye).and_then(|index| station.module_id(index));
+            let screen_blobs: Vec<ScreenBlob> = spills
+                .blobs()
+                .iter()
+                .filter(|blob| Some(blob.module) == here && (blob.position - eye).dot(view_forward) > 0.0)
+                .map(|blob| {
+                    let side = view_forward.cross(up).normalize_or_zero() * blob.radius();
+                    let centre = d.get_world_to_screen(to_vector3(blob.position), view_camera) / display.ui_scale;
+                    let edge = d.get_world_to_screen(to_vector3(blob.position + side), view_camera) / display.ui_scale;
+                    ScreenBlob {
+                        centre: glam::Vec2::new(centre.x, centre.y),
+                        radius: (edge - centre).length(),
+                        stretch: blob.stretch(),
+                        color: blob.liquid.color(),
+                    }
+                })
+                .collect();
+            for cell in metaball_cells(&screen_blobs, screen_size) {
+                let size = cell.size.ceil() as i32;
+                d.draw_rectangle(cell.position.x as i32, cell.position.y as i32, size, size, to_color(cell.color, cell.alpha));
+            }
+
+            // Marker labels
+            for marker in markers.markers() {
+                let offset = marker.position() - eye;
+                if offset.length() > MARKER_VIEW_DISTANCE || offset.dot(view_forward) <= 0.0 {
+                    continue;
+                }
+                let label_at = to_vector3(marker.position() + glam::Vec3::Y * 0.2);
+                let screen = d.get_world_to_screen(label_at, view_camera) / display.ui_scale;
+                let x = screen.x as i32 - d.measure_text(&marker.label, 14) / 2;
+                d.draw_text(&marker.label, x, screen.y as i32, 14, to_color(marker.kind.color(), 1.0));
+            }
+
+            // Name tags over other players, in their role's colour
+            for (name, _, position) in chat.members().filter(|(name, _, _)| *name != config.chat.name) {
+                let at = position + glam::Vec3::Y * avatar::TAG_HEIGHT;
+                let offset = at - eye;
+                if offset.length() > MARKER_VIEW_DISTANCE || offset.dot(view_forward) <= 0.0 {
+                    continue;
+                }
+                let look = replicator.avatar(name).cloned().unwrap_or_default();
+                let tag = look.tag(name);
+                let screen = d.get_world_to_screen(to_vector3(at), view_camera) / display.ui_scale;
+                let x = screen.x as i32 - d.measure_text(tag, 14) / 2;
+                d.draw_text(tag, x, screen.y as i32, 14, to_color(look.role.color(), 1.0));
+            }
+
+            // Chat bubbles over whoever's talking nearby
+            for (position, text, alpha) in chat.bubbles_for(&config.chat.name) {
+                if (position - eye).dot(view_forward) <= 0.0 {
+                    continue;
+                }
+                let screen = d.get_world_to_screen(to_vector3(position), view_camera) / display.ui_scale;
+                let width = d.measure_text(text, 14) + 12;
+                let (x, y) = (screen.x as i32 - width / 2, screen.y as i32 - 22);
+                d.draw_rectangle(x, y, width, 22, Color::new(240, 240, 250, 220).alpha(alpha));
+                d.draw_text(text, x + 6, y + 4, 14, Color::BLACK.alpha(alpha));
+            }
+
+            // Signs, door labels only from the side they face
+            for sign in signage.signs() {
+                let offset = sign.position - eye;
+                if offset.length() > SIGN_VIEW_DISTANCE || offset.dot(view_forward) <= 0.0 {
+                    continue;
+                }
+                if sign.kind == SignKind::DoorLabel && offset.dot(sign.direction) >= 0.0 {
+                    continue;
+                }
+                draw_sign(&mut d, sign, view_camera, display.ui_scale);
+            }
+
+            if let Some(table) = &holotable {
+                let offset = table.top() - eye;
+                if offset.length() < SIGN_VIEW_DISTANCE && offset.dot(view_forward) > 0.0 {
+                    draw_holotable_screens(&mut d, table, &station, view_camera, display.ui_scale, &locale);
+                }
+            }
+
+            // Marker wheel and quick menu
+            if marker_wheel.open {
+                let labels = MarkerKind::ALL
+                    .iter()
+                    .map(|kind| (locale.get(&format!("marker.{}", kind.id())).to_string(), to_color(kind.color(), 1.0)))
+                    .chain(std::iter::once((locale.get("marker.remove").to_string(), Color::LIGHTGRAY)));
+                draw_radial_menu(&mut d, (ui_width, ui_height), &marker_wheel, labels);
+            }
+            if quick_menu.wheel.open {
+                let labels = quick_menu
+                    .slots()
+                    .iter()
+                    .map(|action| (locale.get(&action.label_id()).to_string(), Color::WHITE));
+                draw_radial_menu(&mut d, (ui_width, ui_height), &quick_menu.wheel, labels);
+            }
+
+            if construction.active {
+                let module_type = construction.module_type();
+                let cost = construction::build_cost(module_type);
+                let parts = station.resources().total(Resource::SpareParts);
+                let text = locale.format(
+                    "build.hud",
+                    &[("module", &format!("{:?}", module_type)), ("cost", &cost), ("parts", &parts.floor())],
+                );
+                let x = (ui_width - d.measure_text(&text, 20)) / 2;
+                d.draw_text(&text, x, ui_height - 90, 20, Color::GREEN);
+                if let Some(problem) = ghost.as_ref().and_then(|ghost| ghost.problem) {
+                    let text = locale.get(problem.id());
+                    let x = (ui_width - d.measure_text(text, 18)) / 2;
+                    d.draw_text(text, x, ui_height - 64, 18, Color::RED);
+                }
+            }
+
+            if cursor_focus.waiting_for_click() {
+                let text = locale.get("hud.click_to_capture");
+                let x = (ui_width - d.measure_text(text, 20)) / 2;
+                let y = ui_height / 2 - 40;
+                d.draw_text(text, x, y, 20, Color::YELLOW);
+            }
+
+            // Suit readout on a spacewalk, or the airlock cycling
+            match eva.phase() {
+                EvaPhase::Inside => {}
+                EvaPhase::CyclingOut { remaining, .. } | EvaPhase::CyclingIn { remaining, .. } => {
+                    let percent = ((1.0 - remaining / CYCLE_SECONDS) * 100.0).round();
+                    let text = locale.format("eva.cycling", &[("percent", &percent)]);
+                    let x = (ui_width - d.measure_text(&text, 20)) / 2;
+                    d.draw_text(&text, x, 60, 20, Color::SKYBLUE);
+                }
+                EvaPhase::Outside { .. } => {
+                    let tether = if eva.tethered {
+                        locale.format(
+                            "eva.tether",
+                            &[
+                                ("out", &format!("{:.1}", eva.tether_out(&station))),
+                                ("length", &TETHER_LENGTH),
+                            ],
+                        )
+                    } else {
+                        locale.get("eva.untethered").to_string()
+                    };
+                    let text = locale.format(
+                        "eva.status",
+                        &[
+                            ("oxygen", &(eva.oxygen() * 100.0).round()),
+                            ("fuel", &(eva.fuel() * 100.0).round()),
+                            ("speed", &format!("{:.1}", eva.speed())),
+                            ("tether", &tether),
+                        ],
+                    );
+                    let color = if eva.oxygen() < 0.25 { Color::ORANGE } else { Color::WHITE };
+                    let x = (ui_width - d.measure_text(&text, 20)) / 2;
+                    d.draw_text(&text, x, 60, 20, color);
+                }
+            }
+
+            // Interaction prompt
+            if !ui_captured && !fly_camera.active {
+                let nearest_item = nearest_item.map(|i| &world_items.items()[i]);
+                let prompt = match (nearest_datapad.and_then(|i| datapads.get(i)), nearest_item, nearest_element) {
+                    (Some(datapad), _, _) => Some(locale.format("prompt.read", &[("title", &datapad.title)])),
+                    (None, Some(item), _) => Some(locale.format(
+                        "prompt.pick_up",
+                        &[("item", &item.kind.name()), ("count", &item.count)],
+                    )),
+                    (None, None, _) if nearest_work_light.is_some() => {
+                        Some(locale.get("prompt.pick_up_light").to_string())
+                    }
+                    (None, None, Some((module_idx, element_idx))) => {
+                        let module = &station.modules()[module_idx];
+                        let element = &module.interactive_elements[element_idx];
+                        if !module.powered && element.element_type.needs_power() {
+                            Some(locale.format(
+                                "prompt.element_unpowered",
+                                &[("element", &format!("{:?}", element.element_type))],
+                            ))
+                        } else {
+                            Some(locale.format(
+                                "prompt.element",
+                                &[
+                                    ("element", &format!("{:?}", element.element_type)),
+                                    ("state", &format!("{:?}", element.state)),
+                                ],
+                            ))
+                        }
+                    }
+                    (None, None, None) => None,
+                };
+                if let Some(prompt) = prompt {
+                    let x = (ui_width - d.measure_text(&prompt, 20)) / 2;
+                    let y = ui_height / 2 + 30;
+                    d.draw_text(&prompt, x, y, 20, Color::WHITE);
+                }
+            }
+
+            // Save slots, datapad reader and journal
+            if tuning_panel.visible {
+                draw_tuning_panel(&mut d, (ui_width, ui_height), &tuning_panel, &tunables);
+            }
+            if let Some(save_menu) = &save_menu {
+                draw_save_menu(&mut d, (ui_width, ui_height), save_menu, &locale);
+            } else if let Some(datapad) = reading_datapad.and_then(|i| datapads.get(i)) {
+                draw_datapad(&mut d, (ui_width, ui_height), datapad, locale.get("datapad.close"), &locale);
+            } else if let Some(selection) = journal_selection {
+                draw_journal(&mut d, (ui_width, ui_height), &datapads, selection, &locale);
+            }
+
+            // Terminal console
+            if let Some(screen) = &terminal_screen {
+                let (width, height) = (560, 420);
+                let x = (ui_width - width) / 2;
+                let y = (ui_height - height) / 2;
+                let green = Color::new(90, 255, 140, 255);
+                d.draw_rectangle(x, y, width, height, Color::new(5, 15, 10, 235));
+                d.draw_rectangle_lines(x, y, width, height, green);
+                d.draw_text(&screen.title, x + 12, y + 10, 20, green);
+
+                // Page tabs, smaller if they'd run off the side
+                let tabs_width =
+                    |size| TerminalPage::ALL.iter().map(|page| d.measure_text(page.title(), size) + 8).sum::<i32>();
+                let tab_size = if tabs_width(12) <= width - 24 { 12 } else { 10 };
+                let mut tab_x = x + 12;
+                for page in TerminalPage::ALL {
+                    let color = if page == screen.page { Color::WHITE } else { Color::DARKGREEN };
+                    d.draw_text(page.title(), tab_x, y + 40, tab_size, color);
+                    tab_x += d.measure_text(page.title(), tab_size) + 8;
+                }
+
+                // Flares scramble the screen
+                let interference = station.interference().level();
+                for (row, line) in screen.lines.iter().enumerate() {
+                    let (line, jolt) = emi::glitch_line(line, interference, frame_number, row as u32);
+                    d.draw_text(&line, x + 12 + jolt as i32, y + 70 + row as i32 * 22, 18, green);
+                }
+                if screen.can_scroll_up {
+                    d.draw_text("^", x + width - 24, y + 70, 18, green);
+                }
+                if screen.can_scroll_down {
+                    d.draw_text("v", x + width - 24, y + height - 50, 18, green);
+                }
+                let footer = match screen.page {
+                    TerminalPage::Paint => "terminal.paint_footer",
+                    TerminalPage::Radiation if !terminal.read_only() => "terminal.radiation_footer",
+                    TerminalPage::Refit if !terminal.read_only() => "terminal.refit_footer",
+                    TerminalPage::Traffic if terminal.selected_arrival().is_some() => "terminal.traffic_footer",
+                    TerminalPage::Comms if terminal.selected_comms() => "terminal.comms_footer",
+                    _ => "terminal.footer",
+                };
+                d.draw_text(
+                    locale.get(footer),
+                    x + 12,
+                    y + height - 24,
+                    14,
+                    Color::DARKGREEN,
+                );
+            }
+
+            // Developer overlay: frame time graph plus live numbers
+            if debug_overlay.visible {
+                let (x, y, width, height) = (10, 140, 240, 60);
+                d.draw_rectangle(x, y, width, height + 10 + 18 * 12, Color::new(0, 0, 0, 180));
+
+                // One bar per frame; the line marks 60 fps
+                let scale = height as f32 / (2.0 / 60.0);
+                for (i, time) in debug_overlay.frame_times().enumerate() {
+                    let bar = ((time * scale) as i32).min(height);
+                    let color = if time > 1.0 / 30.0 { Color::RED } else { Color::LIME };
+                    d.draw_line(x + i as i32, y + height, x + i as i32, y + height - bar, color);
+                }
+                let target = y + height - (scale / 60.0) as i32;
+                d.draw_line(x, target, x + width, target, Color::YELLOW);
+
+                let lines = debug_overlay.lines(&station, &effects, draw_calls, listener, yaw);
+                for (row, line) in lines.iter().take(12).enumerate() {
+                    d.draw_text(line, x + 5, y + height + 8 + row as i32 * 18, 16, Color::WHITE);
+                }
+            }
+
+            // Last state diff from the console, until `diff off`
+            if let Some(diff) = &state_diff {
+                let (x, y, width) = (10, 130, 420);
+                let lines = diff.lines();
+                let rows = lines.len().min(((ui_height - y - 90) / 15).max(1) as usize);
+                d.draw_rectangle(x, y, width, rows as i32 * 15 + 10, Color::new(0, 0, 0, 200));
+                for (row, line) in lines.iter().take(rows).enumerate() {
+                    let color = match line.trim_start().chars().next() {
+                        Some('+') => Color::LIME,
+                        Some('-') => Color::RED,
+                        _ if line.contains("->") => Color::WHITE,
+                        _ => Color::SKYBLUE,
+                    };
+                    d.draw_text(line, x + 5, y + 5 + row as i32 * 15, 14, color);
+                }
+            }
+
+            // Input context stack, top first, with each context's bindings
+            if input.show_debug {
+                let (x, y, width) = (260, 140, 300);
+                let lines = input.debug_lines();
+                let rows = lines.len().min(((ui_height - y - 10) / 15).max(0) as usize);
+                d.draw_rectangle(x, y, width, rows as i32 * 15 + 10, Color::new(0, 0, 0, 180));
+                for (row, line) in lines.iter().take(rows).enumerate() {
+                    let color = if line.starts_with(' ') { Color::LIGHTGRAY } else { Color::YELLOW };
+                    d.draw_text(line, x + 5, y + 5 + row as i32 * 15, 14, color);
+                }
+            }
+
+            // Notifications, stacked under the minimap
+            let mut toast_y = 200;
+            for toast in notifications.visible() {
+                let (x, width) = (ui_width - 330, 320);
+                let accent = match toast.severity {
+                    LogSeverity::Info => Color::SKYBLUE,
+                    LogSeverity::Warning => Color::ORANGE,
+                    LogSeverity::Critical => Color::RED,
+                };
+                let text = if toast.count > 1 {
+                    format!("{} (x{})", toast.message, toast.count)
+                } else {
+                    toast.message.clone()
+                };
+                d.draw_rectangle(x, toast_y, width, 26, Color::new(10, 10, 20, 200).alpha(toast.alpha()));
+                d.draw_rectangle(x, toast_y, 4, 26, accent.alpha(toast.alpha()));
+                d.draw_text(&text, x + 10, toast_y + 5, 16, Color::WHITE.alpha(toast.alpha()));
+                toast_y += 30;
+            }
+
+            if stepper.paused {
+                let label = locale.format("hud.paused", &[("tick", &stepper.tick())]);
+                let x = (ui_width - d.measure_text(&label, 20)) / 2;
+                d.draw_text(&label, x, 40, 20, Color::YELLOW);
+            } else if stepper.time_scale() != 1.0 {
+                let scale = format!("{}", stepper.time_scale());
+                let label = locale.format("hud.time_scale", &[("scale", &scale)]);
+                let x = (ui_width - d.measure_text(&label, 20)) / 2;
+                d.draw_text(&label, x, 40, 20, Color::SKYBLUE);
+            }
+
+            // Current objectives
+            // Hidden under the F3 overlay and diff panel, which use the same space
+            if !objectives.is_empty() && !debug_overlay.visible && state_diff.is_none() {
+                let mut row = 130;
+                d.draw_text(
+                    &locale.format(
+                        "hud.objectives",
+                        &[("done", &objectives.completed_count()), ("total", &objectives.len())],
+                    ),
+                    10,
+                    row,
+                    16,
+                    Color::GOLD,
+                );
+                for objective in objectives.active() {
+                    row += 20;
+                    d.draw_text(&format!("- {}", objective.title), 10, row, 16, Color::WHITE);
+                    if !objective.description.is_empty() {
+                        row += 16;
+                        d.draw_text(&objective.description, 22, row, 12, Color::LIGHTGRAY);
+                    }
+                }
+            }
+
+            // Running drill and post-drill report
+            if let Some(drill) = &active_drill {
+                let remaining = (drill.scenario().time_limit - drill.elapsed()).max(0.0) as u32;
+                d.draw_text(
+                    &locale.format(
+                        "hud.drill",
+                        &[
+                            ("name", &drill.scenario().name),
+                            ("time", &format!("{}:{:02}", remaining / 60, remaining % 60)),
+                        ],
+                    ),
+                    10,
+                    105,
+                    20,
+                    Color::ORANGE,
+                );
+            }
+            if let Some(evacuation) = &active_evacuation {
+                let remaining = evacuation.remaining() as u32;
+                d.draw_text(
+                    &locale.format(
+                        "hud.evacuation",
+                        &[
+                            ("name", &evacuation.scenario().name),
+                            ("time", &format!("{}:{:02}", remaining / 60, remaining % 60)),
+                            ("souls", &evacuation.souls_evacuated()),
+                            ("crew", &crew.members().len()),
+                        ],
+                    ),
+                    10,
+                    130,
+                    20,
+                    Color::RED,
+                );
+            }
+            if let Some(approach) = docking.approach() {
+                if approach.manual {
+                    draw_docking_hud(&mut d, (ui_width, ui_height), approach, &locale);
+                } else if approach.stage == DockingStage::Inbound {
+                    let text = locale.format(
+                        "hud.docking",
+                        &[("name", &approach.arrival.name), ("range", &format!("{:.0}", approach.readout().range))],
+                    );
+                    d.draw_text(&text, 10, 155, 20, Color::SKYBLUE);
+                } else {
+                    let key = format!("docking.stage.{}", approach.stage.id());
+                    let stage = locale.get(&key);
+                    let text = locale.format("hud.berthed", &[("name", &approach.arrival.name), ("stage", &stage)]);
+                    d.draw_text(&text, 10, 155, 20, Color::SKYBLUE);
+                }
+            }
+            if let Some(report) = &drill_report {
+                draw_drill_report(&mut d, (ui_width, ui_height), report, retry_scenario.is_some(), &locale);
+            }
+
+            // Inventory bar
+            if !fly_camera.active {
+                let size = 56;
+                let x = (ui_width - INVENTORY_SLOTS as i32 * (size + 6)) / 2;
+                let y = ui_height - size - 12;
+                for (slot, stack) in inventory.slots().iter().enumerate() {
+                    let slot_x = x + slot as i32 * (size + 6);
+                    let border = if slot == inventory.selected { Color::WHITE } else { Color::DARKGRAY };
+                    d.draw_rectangle(slot_x, y, size, size, Color::new(10, 10, 20, 180));
+                    d.draw_rectangle_lines(slot_x, y, size, size, border);
+                    d.draw_text(&(slot + 1).to_string(), slot_x + 4, y + 3, 10, Color::GRAY);
+                    if let Some(stack) = stack {
+                        d.draw_rectangle(slot_x + 18, y + 12, 20, 20, item_color(stack.kind));
+                        let label = stack.kind.name().split(' ').next().unwrap_or("");
+                        d.draw_text(label, slot_x + 4, y + size - 14, 10, Color::WHITE);
+                        d.draw_text(&format!("x{}", stack.count), slot_x + size - 20, y + 3, 10, Color::WHITE);
+                    }
+                }
+                if let Some(job) = &repair {
+                    let width = INVENTORY_SLOTS as i32 * (size + 6) - 6;
+                    d.draw_text(&job.describe(), x, y - 44, 14, Color::WHITE);
+                    d.draw_rectangle(x, y - 26, width, 6, Color::new(10, 10, 20, 180));
+                    d.draw_rectangle(x, y - 26, (width as f32 * job.progress()) as i32, 6, Color::YELLOW);
+                } else if let Some(stack) = inventory.selected_item() {
+                    let label = locale.format("hud.use_item", &[("item", &stack.kind.name())]);
+                    d.draw_text(&label, x, y - 18, 14, Color::LIGHTGRAY);
+                }
+                // Suit battery, while it's in use or still charging back up
+                if helmet_light.on || helmet_light.battery() < 1.0 {
+                    let percent = (helmet_light.battery() * 100.0).round();
+                    let label = locale.format("hud.helmet_light", &[("percent", &percent)]);
+                    let color = if helmet_light.battery() < 0.2 { Color::ORANGE } else { Color::LIGHTGRAY };
+                    let right = x + INVENTORY_SLOTS as i32 * (size + 6) - 6;
+                    d.draw_text(&label, right - d.measure_text(&label, 14), y - 18, 14, color);
+                }
+                // Cabin temperature, and how the crew are taking it
+                if let Some(module) = station.nearest_module(listener).filter(|_| !eva.outside()) {
+                    let temperature = station.modules()[module].atmosphere.temperature;
+                    let comfort = Comfort::of(temperature);
+                    // The reading wanders during a flare; how it feels doesn't
+                    let temperature = station.interference().sensor(temperature, 8.0, 100 + module as u32);
+                    let label = locale.format(
+                        "hud.cabin",
+                        &[
+                            ("temperature", &format!("{:.1}", temperature - 273.15)),
+                            ("comfort", &locale.get(&format!("comfort.{}", comfort.id()))),
+                        ],
+                    );
+                    let color = match comfort {
+                        Comfort::Comfortable => Color::LIGHTGRAY,
+                        Comfort::Cool | Comfort::Warm => Color::YELLOW,
+                        Comfort::Cold => Color::SKYBLUE,
+                        Comfort::Hot => Color::ORANGE,
+                    };
+                    let right = x + INVENTORY_SLOTS as i32 * (size + 6) - 6;
+                    d.draw_text(&label, right - d.measure_text(&label, 14), y - 36, 14, color);
+                    // Spin gravity, in a ring section
+                    if let Some(ring) = station.ring_of(module) {
+                        let g = ring::in_g(ring.gravity(listener));
+                        let label = locale.format("hud.gravity", &[("g", &format!("{:.2}", g)), ("ring", &ring.name)]);
+                        d.draw_text(&label, right - d.measure_text(&label, 14), y - 72, 14, Color::LIGHTGRAY);
+                    }
+                }
+                // Dosimeter, inside or out
+                let label = locale.format(
+                    "hud.dosimeter",
+                    &[
+                        ("dose", &format!("{:.2}", dosimeter.dose())),
+                        ("rate", &format!("{:.2}", dosimeter.rate() * 3600.0)),
+                    ],
+                );
+                let color = match dosimeter.level() {
+                    DoseLevel::Danger => Color::RED,
+                    DoseLevel::Warning => Color::ORANGE,
+                    DoseLevel::Normal if dosimeter.rate() >= radiation::RATE_WARNING => Color::YELLOW,
+                    DoseLevel::Normal => Color::LIGHTGRAY,
+                };
+                let right = x + INVENTORY_SLOTS as i32 * (size + 6) - 6;
+                d.draw_text(&label, right - d.measure_text(&label, 14), y - 54, 14, color);
+            }
+
+            // The last few chat lines, above where tips go
+            let lines: Vec<String> = chat.inbox(&config.chat.name).rev().take(CHAT_LINES).map(|m| m.line()).collect();
+            if !lines.is_empty() {
+                let (x, width) = (10, 360);
+                let bottom = ui_height - 210;
+                let top = bottom - lines.len() as i32 * 18 - 8;
+                d.draw_rectangle(x, top, width, bottom - top, Color::new(0, 0, 0, 140));
+                for (row, line) in lines.iter().rev().enumerate() {
+                    let color = if line.starts_with("[TEAM]") { Color::SKYBLUE } else { Color::WHITE };
+                    d.draw_text(line, x + 6, top + 4 + row as i32 * 18, 14, color);
+                }
+            }
+
+            // Active tip, above the inventory bar
+            if let Some(hint) = hints.as_ref().and_then(|hints| hints.current()) {
+                let (x, y, width) = (10, ui_height - 200, ui_width - 20);
+                d.draw_rectangle(x, y, width, 100, Color::new(10, 20, 40, 200));
+                d.draw_rectangle_lines(x, y, width, 100, Color::SKYBLUE);
+                d.draw_text(&hint.title, x + 10, y + 10, 20, Color::SKYBLUE);
+                d.draw_text(&hint.text, x + 10, y + 40, 16, Color::WHITE);
+                d.draw_text(
+                    locale.get("hint.footer"),
+                    x + 10,
+                    y + 72,
+                    14,
+                    Color::GRAY,
+                );
+            }
+
+            // Flares break up the HUD with static
+            for (top, height, alpha) in emi::static_bands(station.interference().level(), frame_number) {
+                let band_y = (top * ui_height as f32) as i32;
+                let band_height = ((height * ui_height as f32) as i32).max(1);
+                d.draw_rectangle(0, band_y, ui_width, band_height, Color::new(200, 210, 220, (alpha * 255.0) as u8));
+            }
+
+            // Drop-down console over the top half of the screen
+            if console.open {
+                let (width, height) = (ui_width, ui_height / 2);
+                d.draw_rectangle(0, 0, width, height, Color::new(0, 0, 0, 210));
+                d.draw_line(0, height, width, height, Color::GRAY);
+
+                let rows = ((height - 40) / 18).max(0) as usize;
+                let mut row_y = height - 40;
+                for line in console.output().rev().take(rows) {
+                    let color = if line.starts_with("error:") { Color::RED } else { Color::LIGHTGRAY };
+                    d.draw_text(line, 10, row_y, 16, color);
+                    row_y -= 18;
+                }
+                d.draw_text(&format!("> {}_", console.input()), 10, height - 22, 18, Color::WHITE);
+            }
+        } else {
+            let prompt = locale.get("attract.prompt");
+            let x = (d.get_screen_width() - d.measure_text(prompt, 20)) / 2;
+            d.draw_text(prompt, x, d.get_screen_height() - 40, 20, Color::WHITE.alpha(0.6));
+        }
+
+        // The screenshot and clip frames have to be read before the frame is
+        // presented. A minimized window has no framebuffer to read back.
+        if let Some(benchmark) = &mut benchmark {
+            benchmark.record("ui", ui_start.elapsed());
+        }
+        let minimized = d.is_window_minimized();
+        if capture_bug_report && !minimized {
+            d.take_screenshot(&thread, BUG_REPORT_SCREENSHOT);
+        }
+        if clip_recorder.should_capture(d.get_frame_time()) && !minimized {
+            let mut image = d.load_image_from_screen(&thread);
+            let height = CLIP_WIDTH * image.height() / image.width().max(1);
+            image.resize(CLIP_WIDTH, height);
+            let rgba = image.get_image_data().iter().flat_map(|c| [c.r, c.g, c.b, c.a]).collect();
+            clip_recorder.push_frame(ClipFrame {
+                width: CLIP_WIDTH as u16,
+                height: height as u16,
+                rgba,
+            });
+        }
+        let present_start = Instant::now();
+        drop(d);
+        if let Some(benchmark) = &mut benchmark {
+            benchmark.record("present", present_start.elapsed());
+        }
+
+        // Encoding takes a moment, so clips are written on a background thread
+        if input.pressed(InputContext::Global, Action::ExportClip) {
+            let clip = clip_recorder.snapshot();
+            notifications.push(
+                LogSeverity::Info,
+                format!("Saving last {:.0}s as a clip", clip_recorder.duration()),
+            );
+            std::thread::spawn(move || {
+                let result = clip::export_path(&clip::clip_dir())
+                    .and_then(|path| clip.write_gif(&path).map(|_| path));
+                match result {
+                    Ok(path) => println!("Clip saved to {}", path.display()),
+                    Err(err) => eprintln!("Warning: failed to save clip: {:#}", err),
+                }
+            });
+        }
+
+        if capture_bug_report {
+            let system = SystemInfo::collect(
+                (rl.get_screen_width(), rl.get_screen_height()),
+                rl.get_fps(),
+            )
+            .with_gpu(Some(gpu_info.clone()));
+            if let Err(err) = clip_recorder.snapshot().write_gif(std::path::Path::new(BUG_REPORT_CLIP)) {
+                eprintln!("Warning: bug report will have no clip: {:#}", err);
+            }
+            let report = BugReport::new(system)
+                .with_station(&station)
+                .with_save(
+                    SaveGame::capture(&station, &markers, &research, listener, yaw)
+                        .with_session(&inventory, &world_items, &datapads, &objectives, &crew),
+                )
+                .attach("screenshot.png", BUG_REPORT_SCREENSHOT)
+                .attach("clip.gif", BUG_REPORT_CLIP)
+                .attach("hint_state.toml", "hint_state.toml")
+                .attach("config.toml", config_file.path())
+                .attach("tunables.toml", tunables_file.path());
+            match report.write_bundle(&bug_report::report_dir()) {
+                Ok(path) => {
+                    println!("Bug report saved to {}", path.display());
+                    // Optionally send it on to a collection endpoint without blocking the game
+                    if let Ok(endpoint) = std::env::var("SPACE_STATION_BUG_REPORT_URL") {
+                        std::thread::spawn(move || {
+                            if let Err(err) = bug_report::upload(&path, &endpoint) {
+                                eprintln!("Warning: {:#}", err);
+                            }
+                        });
+                    }
+                }
+                Err(err) => eprintln!("Warning: failed to write bug report: {:#}", err),
+            }
+            let _ = std::fs::remove_file(BUG_REPORT_SCREENSHOT);
+            let _ = std::fs::remove_file(BUG_REPORT_CLIP);
+        }
+    }
+
+    if let Some(benchmark) = &benchmark {
+        if let Err(err) = benchmark.finish() {
+            eprintln!("Warning: {:#}", err);
+        }
+    }
+}
+
+// None for "none"; an unknown preset gets the default sky
+fn load_backdrop(name: &str) -> Option<Backdrop> {
+    if name == "none" {
+        return None;
+    }
+    let config = BackdropConfig::preset(name).unwrap_or_else(|| {
+        eprintln!("Warning: no backdrop preset {:?}, using the default", name);
+        BackdropConfig::default()
+    });
+    Some(Backdrop::generate(config))
+}
+
+// Falls back to English, then to bare string IDs, rather than refusing to start
+fn load_locale(language: &str) -> Locale {
+    match Locale::load("assets/lang", language) {
+        Ok(locale) => {
+            let missing = locale.missing();
+            if !missing.is_empty() {
+                eprintln!("Warning: {} strings untranslated in {}: {}", missing.len(), language, missing.join(", "));
+            }
+            locale
+        }
+        Err(err) => {
+            eprintln!("Warning: UI language {} unavailable: {:#}", language, err);
+            Locale::load("assets/lang", locale::DEFAULT_LANGUAGE).unwrap_or_else(|err| {
+                eprintln!("Warning: no UI strings: {:#}", err);
+                Locale::new()
+            })
+        }
+    }
+}
+
+fn draw_datapad(d: &mut RaylibDrawHandle, ui_size: (i32, i32), datapad: &datapads::DatapadDefinition, footer: &str, locale: &Locale) {
+    let (width, height) = (520, 380);
+    let x = (ui_size.0 - width) / 2;
+    let y = (ui_size.1 - height) / 2;
+    d.draw_rectangle(x, y, width, height, Color::new(10, 20, 30, 240));
+    d.draw_rectangle_lines(x, y, width, height, Color::SKYBLUE);
+    d.draw_text(&datapad.title, x + 15, y + 12, 20, Color::SKYBLUE);
+    if !datapad.author.is_empty() {
+        d.draw_text(&datapad.author, x + 15, y + 38, 14, Color::GRAY);
+    }
+
+    let mut row = y + 62;
+    for line in datapads::wrap_text(datapad.text.trim(), 60).iter().take(13) {
+        d.draw_text(line, x + 15, row, 16, Color::WHITE);
+        row += 20;
+    }
+    if let Some(code) = &datapad.code {
+        d.draw_text(&locale.format("datapad.code", &[("code", code)]), x + 15, y + height - 48, 18, Color::YELLOW);
+    }
+    d.draw_text(footer, x + 15, y + height - 22, 14, Color::GRAY);
+}
+
+fn draw_tuning_panel(d: &mut RaylibDrawHandle, ui_size: (i32, i32), panel: &TuningPanel, tunables: &Tunables) {
+    let fields = tunables.fields();
+    let (width, height) = (420, 70 + fields.len() as i32 * 18);
+    let x = ui_size.0 - width - 10;
+    let y = 10;
+    d.draw_rectangle(x, y, width, height, Color::new(10, 15, 25, 235));
+    d.draw_rectangle_lines(x, y, width, height, Color::ORANGE);
+    d.draw_text("TUNABLES", x + 10, y + 8, 20, Color::ORANGE);
+
+    for (i, (name, value)) in fields.iter().enumerate() {
+        let row = y + 36 + i as i32 * 18;
+        let color = if i == panel.selected { Color::WHITE } else { Color::GRAY };
+        d.draw_text(name, x + 10, row, 14, color);
+        d.draw_text(&format!("{:.4}", value), x + width - 90, row, 14, color);
+    }
+    d.draw_text(
+        "[Up/Down] select   [Left/Right] adjust (Shift: bigger)   [S] save",
+        x + 10,
+        y + height - 22,
+        12,
+        Color::GRAY,
+    );
+}
+
+fn draw_save_menu(d: &mut RaylibDrawHandle, ui_size: (i32, i32), menu: &SaveMenu, locale: &Locale) {
+    let (width, height) = (460, 90 + menu.slots().len() as i32 * 30);
+    let x = (ui_size.0 - width) / 2;
+    let y = (ui_size.1 - height) / 2;
+    d.draw_rectangle(x, y, width, height, Color::new(10, 15, 25, 235));
+    d.draw_rectangle_lines(x, y, width, height, Color::SKYBLUE);
+    d.draw_text(locale.get("saves.title"), x + 15, y + 12, 20, Color::SKYBLUE);
+
+    for (i, summary) in menu.slots().iter().enumerate() {
+        let row = y + 48 + i as i32 * 30;
+        let selected = i == menu.selected;
+        if selected {
+            d.draw_rectangle(x + 8, row - 4, width - 16, 26, Color::new(40, 60, 90, 255));
+        }
+        let color = match (&summary.error, summary.is_empty()) {
+            (Some(_), _) => Color::RED,
+            (None, true) => Color::GRAY,
+            (None, false) => Color::WHITE,
+        };
+        d.draw_text(&summary.describe(), x + 15, row, 18, color);
+    }
+    d.draw_text(
+        locale.get("saves.footer"),
+        x + 15,
+        y + height - 24,
+        14,
+        Color::GRAY,
+    );
+}
+
+fn draw_journal(d: &mut RaylibDrawHandle, ui_size: (i32, i32), datapads: &Datapads, selection: usize, locale: &Locale) {
+    let (width, height) = (700, 420);
+    let x = (ui_size.0 - width) / 2;
+    let y = (ui_size.1 - height) / 2;
+    d.draw_rectangle(x, y, width, height, Color::new(10, 20, 30, 240));
+    d.draw_rectangle_lines(x, y, width, height, Color::SKYBLUE);
+    d.draw_text(
+        &locale.format("journal.title", &[("count", &datapads.journal_len())]),
+        x + 15,
+        y + 12,
+        20,
+        Color::SKYBLUE,
+    );
+
+    // Entry list on the left, selected entry on the right
+    for (i, entry) in datapads.journal().enumerate() {
+        let color = if i == selection { Color::WHITE } else { Color::GRAY };
+        d.draw_text(&entry.title, x + 15, y + 45 + i as i32 * 20, 16, color);
+    }
+    if let Some(entry) = datapads.journal_entry(selection) {
+        let mut row = y + 45;
+        for line in datapads::wrap_text(entry.text.trim(), 44).iter().take(14) {
+            d.draw_text(line, x + 250, row, 16, Color::WHITE);
+            row += 20;
+        }
+    } else {
+        d.draw_text(locale.get("journal.empty"), x + 250, y + 45, 16, Color::GRAY);
+    }
+
+    // Codes picked up along the way
+    let mut row = y + height - 30 - datapads.codes().len() as i32 * 18;
+    for (source, code) in datapads.codes() {
+        d.draw_text(&format!("{}: {}", source, code), x + 15, row, 14, Color::YELLOW);
+        row += 18;
+    }
+    d.draw_text(locale.get("journal.footer"), x + 15, y + height - 22, 14, Color::GRAY);
+}
+
+fn draw_drill_report(
+    d: &mut RaylibDrawHandle,
+    ui_size: (i32, i32),
+    report: &DrillReport,
+    can_restart: bool,
+    locale: &Locale,
+) {
+    let record = &report.result.record;
+    let (width, height) = (460, 340);
+    let x = (ui_size.0 - width) / 2;
+    let y = (ui_size.1 - height) / 2;
+    d.draw_rectangle(x, y, width, height, Color::new(15, 15, 25, 235));
+    d.draw_rectangle_lines(x, y, width, height, Color::ORANGE);
+    d.draw_text(&locale.format("drill_report.title", &[("scenario", &record.scenario)]), x + 15, y + 12, 20, Color::ORANGE);
+    d.draw_text(&format!("{}  {}/100", record.grade, record.score), x + 15, y + 42, 40, Color::WHITE);
+
+    let mut row = y + 95;
+    for line in &report.result.breakdown {
+        d.draw_text(line.label, x + 15, row, 18, Color::LIGHTGRAY);
+        d.draw_text(&format!("{:>4.0} / {:.0}", line.points, line.max), x + 300, row, 18, Color::WHITE);
+        row += 24;
+    }
+
+    let response = match record.average_response {
+        Some(seconds) => format!("{:.1}s", seconds),
+        None => "-".to_string(),
+    };
+    let details = locale.format(
+        "drill_report.details",
+        &[
+            ("response", &response),
+            ("unanswered", &record.unanswered),
+            ("injuries", &record.injuries),
+            ("lost", &record.modules_lost),
+        ],
+    );
+    d.draw_text(&details, x + 15, row + 8, 14, Color::GRAY);
+    row += 40;
+
+    // Comparison with earlier attempts
+    let compare = |label: &str, earlier: &Option<drill::DrillRecord>| match earlier {
+        Some(earlier) => format!(
+            "{}: {} {} ({:+})",
+            label,
+            earlier.grade,
+            earlier.score,
+            record.score as i32 - earlier.score as i32
+        ),
+        None => format!("{}: {}", label, locale.get("drill_report.none")),
+    };
+    d.draw_text(&compare(locale.get("drill_report.previous"), &report.previous), x + 15, row, 18, Color::SKYBLUE);
+    d.draw_text(&compare(locale.get("drill_report.best"), &report.best), x + 15, row + 24, 18, Color::SKYBLUE);
+
+    let footer = if can_restart { "drill_report.close_or_restart" } else { "drill_report.close" };
+    d.draw_text(locale.get(footer), x + 15, y + height - 24, 14, Color::GRAY);
+}
+
+fn draw_station_map(
+    d: &mut impl RaylibDraw,
+    layout: &MapLayout,
+    view: &MapView,
+    markers: &Markers,
+    player: glam::Vec2,
+    heading: glam::Vec2,
+    route: &[glam::Vec2],
+) {
+    for (a, b) in &layout.connections {
+        d.draw_line_ex(to_vector2(view.to_screen(*a)), to_vector2(view.to_screen(*b)), 3.0, Color::DARKGRAY);
+    }
+    // Planned route, from the player through each module centre on the way
+    let mut from = player;
+    for &point in route.iter().skip(1) {
+        d.draw_line_ex(to_vector2(view.to_screen(from)), to_vector2(view.to_screen(point)), 3.0, Color::SKYBLUE);
+        from = point;
+    }
+
+    for module in &layout.modules {
+        let color = to_color(module.color, 1.0);
+        let points: Vec<Vector2> = module.outline.iter().map(|p| to_vector2(view.to_screen(*p))).collect();
+        for (i, point) in points.iter().enumerate() {
+            d.draw_line_ex(*point, points[(i + 1) % points.len()], 2.0, color);
+        }
+    }
+
+    for marker in markers.markers() {
+        let position = view.to_screen(map::to_map(marker.position()));
+        d.draw_poly(to_vector2(position), 4, 5.0, 0.0, to_color(marker.kind.color(), 1.0));
+    }
+
+    // Player marker with a heading tick
+    let position = view.to_screen(player);
+    d.draw_circle_v(to_vector2(position), 4.0, Color::RED);
+    d.draw_line_ex(
+        to_vector2(position),
+        to_vector2(position + heading.normalize_or_zero() * 12.0),
+        2.0,
+        Color::RED,
+    );
+}
+
+// A sign projected onto the screen; arrows get a pointer the way they show
+fn draw_sign(d: &mut RaylibDrawHandle, sign: &Sign, camera: Camera3D, ui_scale: f32) {
+    let screen = d.get_world_to_screen(to_vector3(sign.position), camera) / ui_scale;
+    let (size, color) = match sign.kind {
+        SignKind::Nameplate => (16, Color::WHITE),
+        SignKind::DoorLabel => (14, Color::SKYBLUE),
+        SignKind::Arrow => (14, Color::YELLOW),
+    };
+    let x = screen.x as i32 - d.measure_text(&sign.text, size) / 2;
+    d.draw_text(&sign.text, x, screen.y as i32, size, color);
+    if sign.kind != SignKind::Arrow {
+        return;
+    }
+    let tip = d.get_world_to_screen(to_vector3(sign.position + sign.direction * 0.6), camera) / ui_scale;
+    let pointer = glam::Vec2::new(tip.x - screen.x, tip.y - screen.y);
+    let Some(along) = pointer.try_normalize() else {
+        return;
+    };
+    let start = screen - Vector2::new(0.0, 6.0);
+    let end = tip - Vector2::new(0.0, 6.0);
+    d.draw_line_ex(start, end, 2.0, color);
+    for side in [-1.0, 1.0] {
+        let barb = glam::Vec2::from_angle(side * 2.6).rotate(along) * 8.0;
+        d.draw_line_ex(end, end + to_vector2(barb), 2.0, color);
+    }
+}
+
+// Starts a fast-travel hop from wherever `eye` is to module `to` and runs the
+// simulation on by the walk it saves; returns whether it did and what to tell
+// the player
+fn start_fast_travel(
+    fast_travel: &mut FastTravel,
+    stepper: &mut SimStepper,
+    station: &SpaceStation,
+    eye: glam::Vec3,
+    to: usize,
+    allowed: bool,
+    locale: &Locale,
+) -> (bool, String) {
+    let Some(from) = station.nearest_module(eye) else {
+        return (false, locale.get("travel.no_route").to_string());
+    };
+    match fast_travel.begin(station, eye, from, to, allowed) {
+        Ok(walk_seconds) => {
+            stepper.fast_forward(walk_seconds);
+            let name = signage::designations(station).swap_remove(to);
+            let seconds = walk_seconds.round();
+            (true, locale.format("travel.departing", &[("module", &name), ("seconds", &seconds)]))
+        }
+        Err(refusal) => (false, locale.get(refusal.id()).to_string()),
+    }
+}
+
+// The module that would be built, green if it can be and red if not, and
+// the free ports of the module it would go onto
+fn draw_build_ghost<D: RaylibDraw3D>(d: &mut D, station: &SpaceStation, ghost: &Ghost) {
+    let (min, max) = ghost.bounds;
+    let (centre, size) = (to_vector3((min + max) * 0.5), max - min);
+    let tint = if ghost.problem.is_none() { glam::Vec3::new(0.2, 1.0, 0.4) } else { glam::Vec3::new(1.0, 0.25, 0.2) };
+    d.draw_cube(centre, size.x, size.y, size.z, to_color(tint, 0.15));
+    d.draw_cube_wires(centre, size.x, size.y, size.z, to_color(tint, 1.0));
+    for port in construction::ports(station, ghost.host) {
+        let color = if port.facing.dot(ghost.direction) > 0.99 { Color::YELLOW } else { Color::SKYBLUE };
+        d.draw_sphere(to_vector3(port.position), 0.15, color);
+    }
+}
+
+// Another player in their suit, feet at `position` and facing down -Z:
+// legs and torso in the suit fabric, trim at the waist and neck, the role
+// patch and chest stripe stuck on, arms as a string of segments out to
+// wherever the gesture has the hands, and the helmet with its visor
+fn draw_avatar<D: RaylibDraw3D>(d: &mut D, look: &AvatarLook, position: glam::Vec3, gesture: Option<(Emote, f32)>, lights: &LightSet) {
+    let materials = look.materials();
+    let shade = |material: &space_station_3d::material::Material, at: glam::Vec3, normal: glam::Vec3| {
+        to_color(lights.shade(material.albedo.truncate(), position + at, normal), material.alpha)
+    };
+    let suit = shade(&materials.suit, glam::Vec3::Y, glam::Vec3::Y);
+    let trim = shade(&materials.trim, glam::Vec3::Y, glam::Vec3::Y);
+    d.draw_cylinder(to_vector3(position), 0.22, 0.26, 0.9, 12, suit);
+    d.draw_cube(to_vector3(position + glam::Vec3::Y * 0.92), 0.56, 0.08, 0.42, trim);
+    d.draw_cylinder(to_vector3(position + glam::Vec3::Y * 0.96), 0.32, 0.28, 0.5, 12, suit);
+    d.draw_cylinder(to_vector3(position + glam::Vec3::Y * 1.46), 0.14, 0.16, 0.06, 12, trim);
+    for decal in look.decals() {
+        let size = glam::Vec3::splat(decal.size) - decal.normal.abs() * (decal.size - 0.01);
+        let color = to_color(lights.shade(decal.color, position + decal.offset, decal.normal), 1.0);
+        d.draw_cube(to_vector3(position + decal.offset), size.x, size.y, size.z, color);
+    }
+    for (shoulder, hand) in avatar::SHOULDERS.into_iter().zip(avatar::hands(gesture)) {
+        for step in 0..=4 {
+            let at = shoulder.lerp(hand, step as f32 / 4.0);
+            d.draw_sphere(to_vector3(position + at), 0.09, suit);
+        }
+        d.draw_sphere(to_vector3(position + hand), 0.07, trim);
+    }
+    let head = position + glam::Vec3::Y * 1.68;
+    let visor = shade(&materials.visor, glam::Vec3::Y * 1.68, glam::Vec3::NEG_Z);
+    if look.helmet != Helmet::Bubble {
+        d.draw_sphere(to_vector3(head), 0.22, shade(&materials.trim, glam::Vec3::Y * 1.68, glam::Vec3::Y));
+    }
+    d.draw_sphere(to_vector3(head + glam::Vec3::NEG_Z * 0.08), 0.18, visor);
+    if look.helmet == Helmet::Bubble {
+        d.draw_sphere(to_vector3(head), 0.24, visor);
+    }
+    if look.helmet == Helmet::Lamps {
+        for side in [-1.0, 1.0] {
+            d.draw_sphere(to_vector3(head + glam::Vec3::new(side * 0.22, 0.05, -0.05)), 0.05, Color::new(255, 240, 190, 255));
+        }
+    }
+}
+
+// The ship at the berth, a string of hull sections from tail to collar. On
+// the way in the axis it's meant to come in on is drawn too; latched, the
+// collar goes green, flashing while the hatches are open.
+fn draw_ship<D: RaylibDraw3D>(d: &mut D, approach: &Approach) {
+    let ship = &approach.ship;
+    for section in 0..4 {
+        let along = -docking::NOSE_LENGTH + section as f32 * docking::NOSE_LENGTH * 0.6;
+        d.draw_sphere(to_vector3(ship.position + ship.nose() * along), 1.4, Color::LIGHTGRAY);
+    }
+    let collar = match approach.stage {
+        DockingStage::Inbound | DockingStage::Departing => Color::ORANGE,
+        DockingStage::Unloading if (approach.elapsed * 2.0) as u32 % 2 == 1 => Color::DARKGREEN,
+        _ => Color::GREEN,
+    };
+    d.draw_sphere(to_vector3(ship.collar()), 0.5, collar);
+    if approach.stage == DockingStage::Inbound {
+        let port = &approach.port;
+        let out = port.position + port.facing * approach.readout().range.max(10.0);
+        d.draw_line_3D(to_vector3(port.position), to_vector3(out), Color::GREEN);
+    }
+}
+
+// Teleoperation display for flying a ship in: the collar's offset as a dot
+// and the nose's as a ring against the berth's crosshair, and the figures
+// against what the collar will take, green when they're inside it
+fn draw_docking_hud(d: &mut RaylibDrawHandle, ui_size: (i32, i32), approach: &Approach, locale: &Locale) {
+    let (width, height) = (420, 300);
+    let x = ui_size.0 - width - 20;
+    let y = (ui_size.1 - height) / 2;
+    d.draw_rectangle(x, y, width, height, Color::new(10, 20, 30, 220));
+    d.draw_rectangle_lines(x, y, width, height, Color::SKYBLUE);
+    d.draw_text(&locale.format("docking.title", &[("name", &approach.arrival.name)]), x + 12, y + 10, 20, Color::SKYBLUE);
+
+    // 2 m and 10 degrees to the edge of the scope
+    let readout = approach.readout();
+    let (cx, cy, reach) = (x + 100, y + 160, 80.0);
+    d.draw_circle_lines(cx, cy, reach, Color::DARKGRAY);
+    d.draw_line(cx - reach as i32, cy, cx + reach as i32, cy, Color::DARKGRAY);
+    d.draw_line(cx, cy - reach as i32, cx, cy + reach as i32, Color::DARKGRAY);
+    let scope = |offset: glam::Vec2, full: f32| {
+        let offset = (offset / full).clamp_length_max(1.0) * reach;
+        (cx + offset.x as i32, cy - offset.y as i32)
+    };
+    let (lx, ly) = scope(readout.lateral, 2.0);
+    d.draw_circle(lx, ly, 5.0, Color::YELLOW);
+    let (ax, ay) = scope(readout.attitude, 10.0);
+    d.draw_circle_lines(ax, ay, 9.0, Color::ORANGE);
+
+    let within = readout.within();
+    let rows = [
+        (locale.format("docking.range", &[("value", &format!("{:.1}", readout.range))]), true),
+        (locale.format("docking.closing", &[("value", &format!("{:.2}", readout.closing))]), within[0]),
+        (locale.format("docking.lateral", &[("value", &format!("{:.2}", readout.lateral.length()))]), within[1]),
+        (locale.format("docking.alignment", &[("value", &format!("{:.1}", readout.misalignment))]), within[2]),
+        (locale.format("docking.spin", &[("value", &format!("{:.1}", readout.spin))]), within[3]),
+    ];
+    for (i, (text, ok)) in rows.iter().enumerate() {
+        let color = if *ok { Color::GREEN } else { Color::RED };
+        d.draw_text(text, x + 200, y + 60 + i as i32 * 26, 18, color);
+    }
+    d.draw_text(locale.get("docking.controls"), x + 12, y + height - 24, 14, Color::GRAY);
+}
+
+// The table's pedestal and its glowing projector top
+fn draw_holotable<D: RaylibDraw3D>(d: &mut D, table: &Holotable, lights: &LightSet) {
+    let top = table.top();
+    let color = lit(lights, Color::DARKGRAY, top, glam::Vec3::Y);
+    d.draw_cylinder(to_vector3(table.position), 0.7, 0.8, top.y - table.position.y, 24, color);
+    d.draw_cylinder(to_vector3(top), 0.6, 0.6, 0.02, 24, Color::new(60, 160, 220, 255));
+}
+
+// A module's own mesh at miniature scale as a glowing wireframe, tinted by
+// its status; the selected one is filled in brighter
+fn draw_miniature<D: RaylibDraw3D>(d: &mut D, module: &StationModule, miniature: &Miniature, selected: bool) -> usize {
+    let tint = miniature.status.color();
+    let edges = to_color(tint, if selected { 1.0 } else { 0.7 });
+    let faces = to_color(tint, if selected { 0.35 } else { 0.08 });
+    let point = |i: u32| {
+        let position = glam::Vec3::from(module.mesh.vertices[i as usize].position);
+        to_vector3(miniature.matrix.transform_point3(position))
+    };
+    let mut draw_calls = 0;
+    for triangle in module.mesh.indices.chunks_exact(3) {
+        let [a, b, c] = [point(triangle[0]), point(triangle[1]), point(triangle[2])];
+        d.draw_triangle3D(a, b, c, faces);
+        d.draw_triangle3D(a, c, b, faces);
+        d.draw_line_3D(a, b, edges);
+        d.draw_line_3D(b, c, edges);
+        d.draw_line_3D(c, a, edges);
+        draw_calls += 5;
+    }
+    draw_calls
+}
+
+// The console screens either side of the table: the station at a glance on
+// the left, the selected module on the right
+fn draw_holotable_screens(
+    d: &mut RaylibDrawHandle,
+    table: &Holotable,
+    station: &SpaceStation,
+    camera: Camera3D,
+    ui_scale: f32,
+    locale: &Locale,
+) {
+    let mut overview = table.overview_lines(station);
+    overview.push(locale.get("holotable.hint").to_string());
+    let screens = [
+        (glam::Vec3::new(-1.0, 0.4, 0.0), Some(overview)),
+        (glam::Vec3::new(1.0, 0.4, 0.0), table.detail_lines(station)),
+    ];
+    for (offset, lines) in screens {
+        let Some(lines) = lines else {
+            continue;
+        };
+        let screen = d.get_world_to_screen(to_vector3(table.top() + offset), camera) / ui_scale;
+        let width = lines.iter().map(|line| d.measure_text(line, 12)).max().unwrap_or(0) + 16;
+        let height = lines.len() as i32 * 16 + 12;
+        let (x, y) = (screen.x as i32 - width / 2, screen.y as i32 - height / 2);
+        d.draw_rectangle(x, y, width, height, Color::new(10, 25, 40, 200));
+        d.draw_rectangle_lines(x, y, width, height, Color::new(60, 160, 220, 255));
+        for (i, line) in lines.iter().enumerate() {
+            d.draw_text(line, x + 8, y + 8 + i as i32 * 16, 12, Color::SKYBLUE);
+        }
+    }
+}
+
+// A little way in front of the player, named after its kind
+fn place_marker(markers: &mut Markers, kind: MarkerKind, eye: glam::Vec3, yaw: f32, locale: &Locale) {
+    let position = eye + glam::Vec3::new(yaw.cos(), 0.0, yaw.sin()) * MARKER_DISTANCE;
+    markers.place(kind, locale.get(&format!("marker.{}", kind.id())), position);
+}
+
+// Work lights are set down in front of the player; everything else goes to the inventory
+fn use_item(
+    inventory: &mut Inventory,
+    station: &mut SpaceStation,
+    work_lights: &mut WorkLights,
+    repair: &mut Option<RepairJob>,
+    eye: glam::Vec3,
+    yaw: f32,
+    locale: &Locale,
+) -> Option<String> {
+    match RepairJob::start(station, inventory, eye, INTERACT_REACH) {
+        Some(Ok(job)) => {
+            let message = job.describe();
+            *repair = Some(job);
+            return Some(message);
+        }
+        Some(Err(message)) => return Some(message),
+        None => {}
+    }
+    // Runs while held, which the main loop sees to
+    if inventory.selected_item()?.kind == ItemKind::WetVac {
+        return None;
+    }
+    if inventory.selected_item()?.kind != ItemKind::WorkLight {
+        return inventory.use_selected(station, eye, INTERACT_REACH);
+    }
+    let position = eye + glam::Vec3::new(yaw.cos(), 0.0, yaw.sin()) * WORK_LIGHT_DISTANCE;
+    work_lights.place(glam::Vec3::new(position.x, (eye.y - EYE_HEIGHT).max(0.0), position.z));
+    inventory.take_selected();
+    Some(locale.get("light.work_light_placed").to_string())
+}
+
+// Playtest events are best-effort; a full disk shouldn't stop the game
+fn track(analytics: &mut Analytics, event: AnalyticsEvent) {
+    if let Err(err) = analytics.record(event) {
+        eprintln!("Warning: analytics not recorded: {:#}", err);
+    }
+}
+
+fn toggle_helmet_light(helmet_light: &mut HelmetLight, locale: &Locale) -> String {
+    if !helmet_light.toggle() {
+        locale.get("light.helmet_flat").to_string()
+    } else if helmet_light.on {
+        locale.get("light.helmet_on").to_string()
+    } else {
+        locale.get("light.helmet_off").to_string()
+    }
+}
+
+fn remove_nearest_marker(markers: &mut Markers, eye: glam::Vec3, locale: &Locale) -> String {
+    match markers.nearest(eye, INTERACT_REACH).and_then(|id| markers.remove(id)) {
+        Some(marker) => locale.format("marker.removed", &[("label", &marker.label)]),
+        None => locale.get("marker.none_nearby").to_string(),
+    }
+}
+
+// Right stick of the first gamepad, y down like the screen
+fn gamepad_stick(rl: &RaylibHandle) -> glam::Vec2 {
+    if !rl.is_gamepad_available(0) {
+        return glam::Vec2::ZERO;
+    }
+    glam::Vec2::new(
+        rl.get_gamepad_axis_movement(0, GamepadAxis::GAMEPAD_AXIS_RIGHT_X),
+        rl.get_gamepad_axis_movement(0, GamepadAxis::GAMEPAD_AXIS_RIGHT_Y),
+    )
+}
+
+fn gamepad_left_stick(rl: &RaylibHandle) -> glam::Vec2 {
+    if !rl.is_gamepad_available(0) {
+        return glam::Vec2::ZERO;
+    }
+    glam::Vec2::new(
+        rl.get_gamepad_axis_movement(0, GamepadAxis::GAMEPAD_AXIS_LEFT_X),
+        rl.get_gamepad_axis_movement(0, GamepadAxis::GAMEPAD_AXIS_LEFT_Y),
+    )
+}
+
+// Rescaled so movement starts from zero at the edge of the deadzone
+fn deadzone(stick: glam::Vec2) -> glam::Vec2 {
+    let length = stick.length();
+    if length <= STICK_DEADZONE {
+        return glam::Vec2::ZERO;
+    }
+    stick / length * ((length - STICK_DEADZONE) / (1.0 - STICK_DEADZONE)).min(1.0)
+}
+
+fn load_scene_target(
+    rl: &mut RaylibHandle,
+    thread: &RaylibThread,
+    (width, height): (i32, i32),
+) -> Result<RenderTexture2D, raylib::error::Error> {
+    let target = rl.load_render_texture(thread, width as u32, height as u32)?;
+    // Smooths the stretch when the render scale is below 1
+    unsafe { raylib::ffi::SetTextureFilter(target.texture, TextureFilter::TEXTURE_FILTER_BILINEAR as i32) };
+    Ok(target)
+}
+
+// Each streamable level of `source`, downscaled with its coarser mips
+// generated, so streaming a level in is only an upload
+fn mip_chain(source: &Image) -> Vec<Image> {
+    let levels = 32 - source.width.max(source.height).max(1).leading_zeros();
+    (0..levels)
+        .map(|level| {
+            let mut image = source.clone();
+            image.resize((source.width >> level).max(1), (source.height >> level).max(1));
+            image.gen_mipmaps();
+            image
+        })
+        .collect()
+}
+
+// Uploads a texture from mip `level` down, the finer levels left out
+fn load_streamed_texture(
+    rl: &mut RaylibHandle,
+    thread: &RaylibThread,
+    mips: &[Image],
+    level: u32,
+) -> Result<Texture2D, raylib::error::Error> {
+    let image = &mips[(level as usize).min(mips.len() - 1)];
+    let texture = rl.load_texture_from_image(thread, image)?;
+    unsafe { raylib::ffi::SetTextureFilter(*texture.as_ref(), TextureFilter::TEXTURE_FILTER_TRILINEAR as i32) };
+    Ok(texture)
+}
+
+// Down the right edge of the full-screen map
+fn draw_marker_list(d: &mut RaylibDrawHandle, ui_size: (i32, i32), markers: &Markers) {
+    let x = ui_size.0 - 220;
+    let mut row = 40;
+    for marker in markers.markers() {
+        d.draw_poly(Vector2::new(x as f32, row as f32 + 7.0), 4, 5.0, 0.0, to_color(marker.kind.color(), 1.0));
+        d.draw_text(&marker.label, x + 12, row, 14, Color::WHITE);
+        row += 18;
+    }
+}
+
+fn draw_radial_menu(d: &mut RaylibDrawHandle, ui_size: (i32, i32), wheel: &RadialMenu, labels: impl Iterator<Item = (String, Color)>) {
+    let center = glam::Vec2::new(ui_size.0 as f32, ui_size.1 as f32) * 0.5;
+    let radius = 90.0;
+    d.draw_circle_v(to_vector2(center), radius + 30.0, Color::new(0, 0, 0, 150));
+    let selected = wheel.selected();
+    for (i, (label, color)) in labels.enumerate() {
+        let at = center + wheel.direction(i) * radius;
+        let size = if selected == Some(i) { 22 } else { 16 };
+        let color = if selected == Some(i) { color } else { color.alpha(0.6) };
+        d.draw_text(&label, at.x as i32 - d.measure_text(&label, size) / 2, at.y as i32 - size / 2, size, color);
+    }
+    d.draw_circle_v(to_vector2(center + wheel.cursor()), 4.0, Color::WHITE);
+}
+
+fn to_vector2(v: glam::Vec2) -> Vector2 {
+    Vector2::new(v.x, v.y)
+}
+
+// OpenGL's own description of the context raylib created. raylib links
+// the system GL library, so these resolve without a loader.
+extern "system" {
+    fn glGetString(name: u32) -> *const std::ffi::c_char;
+    fn glGetIntegerv(name: u32, data: *mut i32);
+}
+
+const GL_VENDOR: u32 = 0x1F00;
+const GL_RENDERER: u32 = 0x1F01;
+const GL_VERSION: u32 = 0x1F02;
+const GL_SHADING_LANGUAGE_VERSION: u32 = 0x8B8C;
+const GL_MAX_TEXTURE_SIZE: u32 = 0x0D33;
+
+// Needs the window (and so the GL context) to exist
+fn query_gpu_info() -> GpuInfo {
+    let string = |name| {
+        let ptr = unsafe { glGetString(name) };
+        if ptr.is_null() {
+            return "unknown".to_string();
+        }
+        unsafe { std::ffi::CStr::from_ptr(ptr) }.to_string_lossy().into_owned()
+    };
+    let mut max_texture_size = 0;
+    unsafe { glGetIntegerv(GL_MAX_TEXTURE_SIZE, &mut max_texture_size) };
+    GpuInfo {
+        vendor: string(GL_VENDOR),
+        renderer: string(GL_RENDERER),
+        version: string(GL_VERSION),
+        shading_language: string(GL_SHADING_LANGUAGE_VERSION),
+        max_texture_size,
+    }
+}
+
+// Keyboard state for the input system, read through raylib
+// Keys for a frame where nothing should register
+struct NoKeys;
+
+impl KeySource for NoKeys {
+    fn is_pressed(&self, _key: KeyCode) -> bool {
+        false
+    }
+
+    fn is_down(&self, _key: KeyCode) -> bool {
+        false
+    }
+}
+
+// Any key, click, scroll, mouse movement or pad input this frame
+fn any_input(rl: &mut RaylibHandle) -> bool {
+    let mouse = rl.get_mouse_delta();
+    let clicked = [MouseButton::MOUSE_BUTTON_LEFT, MouseButton::MOUSE_BUTTON_RIGHT, MouseButton::MOUSE_BUTTON_MIDDLE]
+        .into_iter()
+        .any(|button| rl.is_mouse_button_down(button));
+    let pad = rl.is_gamepad_available(0)
+        && ((1..=17).any(|button| unsafe { raylib::ffi::IsGamepadButtonDown(0, button) })
+            || deadzone(gamepad_left_stick(rl)) != glam::Vec2::ZERO
+            || deadzone(gamepad_stick(rl)) != glam::Vec2::ZERO);
+    rl.get_key_pressed().is_some() || mouse.x != 0.0 || mouse.y != 0.0 || rl.get_mouse_wheel_move() != 0.0 || clicked || pad
+}
+
+struct RaylibKeys<'a>(&'a RaylibHandle);
+
+// Gamepad buttons go straight to raylib's C API, which takes them as plain ints
+impl KeySource for RaylibKeys<'_> {
+    fn is_pressed(&self, key: KeyCode) -> bool {
+        match input::gamepad_button(key) {
+            Some(button) => unsafe { raylib::ffi::IsGamepadAvailable(0) && raylib::ffi::IsGamepadButtonPressed(0, button) },
+            None => raylib::core::input::key_from_i32(key).is_some_and(|key| self.0.is_key_pressed(key)),
+        }
+    }
+
+    fn is_down(&self, key: KeyCode) -> bool {
+        match input::gamepad_button(key) {
+            Some(button) => unsafe { raylib::ffi::IsGamepadAvailable(0) && raylib::ffi::IsGamepadButtonDown(0, button) },
+            None => raylib::core::input::key_from_i32(key).is_some_and(|key| self.0.is_key_down(key)),
+        }
+    }
+}
+
+fn item_color(kind: ItemKind) -> Color {
+    match kind {
+        ItemKind::RepairKit => Color::YELLOW,
+        ItemKind::OxygenCanister => Color::SKYBLUE,
+        ItemKind::SpareParts => Color::LIGHTGRAY,
+        ItemKind::WorkLight => Color::ORANGE,
+        ItemKind::WetVac => Color::DARKGREEN,
+    }
+}
+
+fn lit(lights: &LightSet, base: Color, point: glam::Vec3, normal: glam::Vec3) -> Color {
+    let rgb = glam::Vec3::new(base.r as f32, base.g as f32, base.b as f32) / 255.0;
+    to_color(lights.shade(rgb, point, normal), base.a as f32 / 255.0)
+}
+
+// Every module's mesh in place, lit by the sun, with ring sections turned as
+// far as they've spun. Both windings, since the meshes are built to be seen
+// from inside. Returns the draw count.
+fn draw_station_exterior<D: RaylibDraw3D>(d: &mut D, station: &SpaceStation, sun_direction: glam::Vec3) -> usize {
+    let mut draw_calls = 0;
+    let spin = |module: usize| station.ring_of(module).map_or(glam::Mat4::IDENTITY, |ring| ring.spin());
+    for (i, module) in station.modules().iter().enumerate() {
+        let matrix = spin(i) * module.transform.matrix();
+        let albedo = module.material.albedo.truncate();
+        let vertex = |i: u32| {
+            let v = &module.mesh.vertices[i as usize];
+            (
+                matrix.transform_point3(glam::Vec3::from(v.position)),
+                matrix.transform_vector3(glam::Vec3::from(v.normal)).normalize_or_zero(),
+            )
+        };
+        for triangle in module.mesh.indices.chunks_exact(3) {
+            let [(a, na), (b, nb), (c, nc)] = [vertex(triangle[0]), vertex(triangle[1]), vertex(triangle[2])];
+            let normal = (na + nb + nc).normalize_or_zero();
+            let light = 0.15 + 0.85 * normal.dot(sun_direction).abs();
+            let color = to_color(albedo * light, 1.0);
+            let [a, b, c] = [a, b, c].map(to_vector3);
+            d.draw_triangle3D(a, b, c, color);
+            d.draw_triangle3D(a, c, b, color);
+            draw_calls += 2;
+        }
+    }
+    // Solar array wings, both sides lit off whichever faces the sun
+    for module in station.modules() {
+        let Some(facing) = module.panel else {
+            continue;
+        };
+        let light = 0.2 + 0.8 * facing.dot(sun_direction).max(0.0);
+        let color = to_color(glam::Vec3::new(0.12, 0.18, 0.45) * light, 1.0);
+        for wing in solar::wings(module.transform.position, facing) {
+            let [a, b, c, e] = wing.map(to_vector3);
+            d.draw_triangle3D(a, b, c, color);
+            d.draw_triangle3D(a, c, b, color);
+            d.draw_triangle3D(a, c, e, color);
+            d.draw_triangle3D(a, e, c, color);
+            draw_calls += 4;
+        }
+    }
+    // The tubes and hatch collars between them; the ones across a ring's
+    // bearing stay put, which is as near as this gets to drawing it
+    for connector in station.connectors() {
+        let (Some(a), Some(b)) = (station.module_index(connector.modules.0), station.module_index(connector.modules.1)) else {
+            continue;
+        };
+        let same_ring = station.ring_of(a).zip(station.ring_of(b)).is_some_and(|(x, y)| std::ptr::eq(x, y));
+        let matrix = if same_ring { spin(a) * connector.matrix } else { connector.matrix };
+        let point = |i: u32| to_vector3(matrix.transform_point3(glam::Vec3::from(connector.mesh.vertices[i as usize].position)));
+        for triangle in connector.mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [point(triangle[0]), point(triangle[1]), point(triangle[2])];
+            let color = Color::new(120, 125, 135, 255);
+            d.draw_triangle3D(a, b, c, color);
+            d.draw_triangle3D(a, c, b, color);
+            draw_calls += 2;
+        }
+    }
+    draw_calls
+}
+
+// The door's panel, slid as far open as its element has got
+fn draw_door<D: RaylibDraw3D>(d: &mut D, door: &Door, openness: f32, lights: &LightSet) {
+    let matrix = door.panel_matrix(openness);
+    let normal = matrix.transform_vector3(glam::Vec3::Z).normalize_or_zero();
+    let point = |i: u32| matrix.transform_point3(glam::Vec3::from(door.mesh.vertices[i as usize].position));
+    for triangle in door.mesh.indices.chunks_exact(3) {
+        let [a, b, c] = [point(triangle[0]), point(triangle[1]), point(triangle[2])];
+        let color = lit(lights, Color::new(150, 155, 165, 255), (a + b + c) / 3.0, normal);
+        let [a, b, c] = [a, b, c].map(to_vector3);
+        // Both windings, like the sun patches
+        d.draw_triangle3D(a, b, c, color);
+        d.draw_triangle3D(a, c, b, color);
+    }
+}
+
+// The room's floor grime, a patch per dirty mask cell over the part of the
+// floor that's drawn, each the floor's colour with its grime worked in
+fn draw_floor_grime<D: RaylibDraw3D>(d: &mut D, module: &StationModule, lights: &LightSet) -> usize {
+    let (half_width, half_depth) = module.module_type.hull_half_extents();
+    let cells = grime::MASK_SIZE as f32;
+    let cell = Vector2::new(half_width * 2.0 / cells, half_depth * 2.0 / cells);
+    let floor = glam::Vec3::splat(Color::GRAY.r as f32 / 255.0);
+    let mut draw_calls = 0;
+    for (uv, amount) in module.grime.dirty_cells() {
+        let point = module.floor_point(uv);
+        if !(TEST_ROOM.min.x..TEST_ROOM.max.x).contains(&point.x) || !(TEST_ROOM.min.z..TEST_ROOM.max.z).contains(&point.z) {
+            continue;
+        }
+        let color = to_color(lights.shade(grime::soiled(floor, amount), point, glam::Vec3::Y), 1.0);
+        // Lifted just off the floor so they don't fight with it
+        d.draw_plane(to_vector3(point + glam::Vec3::Y * 0.01), cell, color);
+        draw_calls += 1;
+    }
+    draw_calls
+}
+
+// A prop's box, wherever its joint has swung or slid it
+fn draw_prop<D: RaylibDraw3D>(d: &mut D, props: &Props, index: usize, lights: &LightSet) {
+    let matrix = props.transform(index);
+    let half = glam::Vec3::from(props.props()[index].definition.size) * 0.5;
+    for axis in [glam::Vec3::X, glam::Vec3::Y, glam::Vec3::Z] {
+        for side in [-1.0, 1.0] {
+            let normal = axis * side;
+            let (u, v) = (normal.any_orthonormal_vector(), normal.cross(normal.any_orthonormal_vector()));
+            let corner = |a: f32, b: f32| matrix.transform_point3((normal + u * a + v * b) * half);
+            let [p0, p1, p2, p3] = [corner(-1.0, -1.0), corner(1.0, -1.0), corner(1.0, 1.0), corner(-1.0, 1.0)];
+            let world_normal = matrix.transform_vector3(normal).normalize_or_zero();
+            let color = lit(lights, Color::new(190, 150, 60, 255), (p0 + p2) * 0.5, world_normal);
+            let [p0, p1, p2, p3] = [p0, p1, p2, p3].map(to_vector3);
+            // Both windings, like the sun patches
+            d.draw_triangle3D(p0, p1, p2, color);
+            d.draw_triangle3D(p0, p2, p3, color);
+            d.draw_triangle3D(p0, p2, p1, color);
+            d.draw_triangle3D(p0, p3, p2, color);
+        }
+    }
+}
+
+// Lit paint plus whatever glow the strip has left
+fn draw_guidance_strip<D: RaylibDraw3D>(d: &mut D, strip: &GuidanceStrip, lights: &LightSet) {
+    const WIDTH: f32 = 0.08;
+    let albedo = strip.material.albedo.truncate();
+    let rgb = lights.shade(albedo, strip.midpoint(), glam::Vec3::Y) + strip.material.emission();
+    let color = to_color(rgb, 1.0);
+    let side = (strip.to - strip.from).cross(glam::Vec3::Y).normalize_or_zero() * WIDTH * 0.5;
+    let [a, b, c, e] = [strip.from - side, strip.to - side, strip.to + side, strip.from + side].map(to_vector3);
+    // Both windings, like the sun patches
+    d.draw_triangle3D(a, b, c, color);
+    d.draw_triangle3D(a, c, e, color);
+    d.draw_triangle3D(a, c, b, color);
+    d.draw_triangle3D(a, e, c, color);
+}
+
+// A flat disc lifted just off the surface so it doesn't fight with it
+fn draw_light_pool<D: RaylibDraw3D>(d: &mut D, pool: &LightPool) {
+    const SEGMENTS: usize = 16;
+    let center = pool.center + pool.normal * 0.02;
+    let right = pool.normal.any_orthonormal_vector() * pool.radius;
+    let up = pool.normal.cross(right);
+    let color = to_color(pool.color, 0.35 * pool.strength);
+    for i in 0..SEGMENTS {
+        let (a0, a1) = (i as f32, (i + 1) as f32);
+        let step = std::f32::consts::TAU / SEGMENTS as f32;
+        let p0 = center + right * (a0 * step).cos() + up * (a0 * step).sin();
+        let p1 = center + right * (a1 * step).cos() + up * (a1 * step).sin();
+        // Both windings, like the sun patches
+        d.draw_triangle3D(to_vector3(center), to_vector3(p0), to_vector3(p1), color);
+        d.draw_triangle3D(to_vector3(center), to_vector3(p1), to_vector3(p0), color);
+    }
+}
+
+fn to_vector3(v: glam::Vec3) -> Vector3 {
+    Vector3::new(v.x, v.y, v.z)
+}
+
+// Column-major, the order raylib uploads an instance transform in
+fn to_instance_matrix(m: [f32; 16]) -> raylib::ffi::Matrix {
+    raylib::ffi::Matrix {
+        m0: m[0], m1: m[1], m2: m[2], m3: m[3],
+        m4: m[4], m5: m[5], m6: m[6], m7: m[7],
+        m8: m[8], m9: m[9], m10: m[10], m11: m[11],
+        m12: m[12], m13: m[13], m14: m[14], m15: m[15],
+    }
+}
+
+fn to_color(rgb: glam::Vec3, alpha: f32) -> Color {
+    let channel = |c: f32| (c.clamp(0.0, 1.0) * 255.0) as u8;
+    Color::new(channel(rgb.x), channel(rgb.y), channel(rgb.z), channel(alpha))
 }
@@ -0,0 +1,106 @@
+use crate::station::{InteractiveElement, SpaceStation};
+use crate::tunables::StationTunables;
+
+// Chance per second a running machine breaks down. Next to nothing when
+// freshly serviced, the full rate at its service interval, and climbing
+// past it.
+pub fn failure_rate(element: &InteractiveElement, tunables: &StationTunables) -> f32 {
+    let Some(interval) = element.element_type.service_hours() else {
+        return 0.0;
+    };
+    let due = element.hours / interval;
+    tunables.element_failure_rate * due * due
+}
+
+// Chance it breaks down over the next `hours` of running, at today's rate
+pub fn predicted_failure(element: &InteractiveElement, tunables: &StationTunables, hours: f32) -> f32 {
+    let seconds = hours * tunables.usage_hour;
+    1.0 - (-failure_rate(element, tunables) * seconds).exp()
+}
+
+// How worn an element is, for the maintenance page: the share of its
+// service interval it's used, and its chance of failing in the next 10 hours
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WearReport {
+    pub module: usize,
+    pub element: usize,
+    pub wear: f32,
+    pub risk: f32,
+}
+
+// Every element that wears, most at risk first
+pub fn wear_report(station: &SpaceStation) -> Vec<WearReport> {
+    let tunables = station.tunables();
+    let mut report: Vec<WearReport> = station
+        .modules()
+        .iter()
+        .enumerate()
+        .flat_map(|(module, m)| {
+            m.interactive_elements.iter().enumerate().filter_map(move |(element, e)| {
+                let interval = e.element_type.service_hours()?;
+                Some(WearReport {
+                    module,
+                    element,
+                    wear: e.hours / interval,
+                    risk: predicted_failure(e, tunables, 10.0),
+                })
+            })
+        })
+        .collect();
+    report.sort_by(|a, b| b.risk.total_cmp(&a.risk).then(b.wear.total_cmp(&a.wear)));
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::resources::Resource;
+    use crate::station::{ElementState, InteractionType};
+    use crate::tunables::Tunables;
+
+    fn radiator(station: &SpaceStation, module: usize) -> usize {
+        station.modules()[module]
+            .interactive_elements
+            .iter()
+            .position(|element| element.element_type == InteractionType::Radiator)
+            .unwrap()
+    }
+
+    #[test]
+    fn running_hours_raise_the_failure_risk_until_serviced() {
+        let mut station = SpaceStation::create_default_layout();
+        let mut tunables = Tunables::default();
+        tunables.station.element_failure_rate = 0.0;
+        station.set_tunables(&tunables);
+        let cooler = radiator(&station, 0);
+        for _ in 0..120 {
+            station.update(1.0);
+        }
+        let hours = station.modules()[0].interactive_elements[cooler].hours;
+        assert!((hours - 2.0).abs() < 0.01);
+        // Switched-off kit doesn't clock up hours
+        assert_eq!(station.modules()[5].interactive_elements[0].hours, 0.0);
+
+        let defaults = StationTunables::default();
+        let element = &mut station.module_mut(0).unwrap().interactive_elements[cooler];
+        let fresh = predicted_failure(element, &defaults, 10.0);
+        element.hours = 600.0;
+        let overdue = predicted_failure(element, &defaults, 10.0);
+        assert!(fresh < 0.001 && overdue > 0.9);
+        let report = wear_report(&station);
+        assert_eq!((report[0].module, report[0].element), (0, cooler));
+        assert_eq!(report[0].wear, 2.0);
+
+        tunables.station.element_failure_rate = 1000.0;
+        station.set_tunables(&tunables);
+        station.update(0.1);
+        assert_eq!(station.element_state(0, cooler), Some(ElementState::Malfunction));
+        let parts = station.resources().total(Resource::SpareParts);
+        assert!(station.service_element(0, cooler));
+        assert_eq!(station.element_state(0, cooler), Some(ElementState::Inactive));
+        assert_eq!(station.modules()[0].interactive_elements[cooler].hours, 0.0);
+        assert_eq!(station.resources().total(Resource::SpareParts), parts - 1.0);
+        // Doors don't wear
+        assert!(!station.service_element(0, station.doors()[0].element));
+    }
+}
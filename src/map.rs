@@ -0,0 +1,222 @@
+use std::f32::consts::PI;
+use glam::{Vec2, Vec3};
+use crate::station::{ModuleType, SpaceStation};
+
+const OUTLINE_SEGMENTS: usize = 8;
+const MIN_ZOOM: f32 = 0.25;
+const MAX_ZOOM: f32 = 8.0;
+
+// A module as seen from above. Map space is the station's XZ plane.
+#[derive(Debug, Clone)]
+pub struct MapModule {
+    pub index: usize,
+    pub module_type: ModuleType,
+    pub center: Vec2,
+    pub outline: Vec<Vec2>,
+    pub color: Vec3,
+}
+
+#[derive(Debug, Clone)]
+pub struct MapLayout {
+    pub modules: Vec<MapModule>,
+    pub connections: Vec<(Vec2, Vec2)>,
+    pub min: Vec2,
+    pub max: Vec2,
+}
+
+impl MapLayout {
+    pub fn build(station: &SpaceStation) -> Self {
+        let modules = station.modules();
+        let mut layout = Self {
+            modules: Vec::with_capacity(modules.len()),
+            connections: Vec::new(),
+            min: Vec2::splat(f32::MAX),
+            max: Vec2::splat(f32::MIN),
+        };
+
+        for (index, module) in modules.iter().enumerate() {
+            let center = to_map(module.transform.position);
+
+            // Footprint from the mesh's horizontal extent
+            let mut half_extent = Vec2::ZERO;
+            for vertex in &module.mesh.vertices {
+                half_extent = half_extent.max(Vec2::new(vertex.position[0].abs(), vertex.position[2].abs()));
+            }
+            half_extent *= to_map(module.transform.scale).abs();
+
+            let outline: Vec<Vec2> = (0..OUTLINE_SEGMENTS)
+                .map(|i| {
+                    let angle = i as f32 / OUTLINE_SEGMENTS as f32 * 2.0 * PI;
+                    center + Vec2::new(angle.cos(), angle.sin()) * half_extent
+                })
+                .collect();
+
+            layout.min = layout.min.min(center - half_extent);
+            layout.max = layout.max.max(center + half_extent);
+            layout.modules.push(MapModule {
+                index,
+                module_type: module.module_type,
+                center,
+                outline,
+                color: module_color(module.module_type),
+            });
+
+            // Connections are stored on both ends; draw each once
+            for other in station.neighbours(index) {
+                if other > index {
+                    layout
+                        .connections
+                        .push((center, to_map(modules[other].transform.position)));
+                }
+            }
+        }
+
+        if layout.modules.is_empty() {
+            layout.min = Vec2::splat(-1.0);
+            layout.max = Vec2::splat(1.0);
+        }
+        layout
+    }
+
+    pub fn center(&self) -> Vec2 {
+        (self.min + self.max) * 0.5
+    }
+
+    pub fn size(&self) -> Vec2 {
+        self.max - self.min
+    }
+
+    // The module whose footprint a screen point falls in, the nearest
+    // centre winning where they overlap
+    pub fn module_at(&self, view: &MapView, screen: Vec2) -> Option<usize> {
+        let point = view.to_map(screen);
+        self.modules
+            .iter()
+            .filter(|module| {
+                let radius = module.outline.iter().map(|p| (*p - module.center).length()).fold(0.0, f32::max);
+                point.distance(module.center) <= radius
+            })
+            .min_by(|a, b| point.distance(a.center).total_cmp(&point.distance(b.center)))
+            .map(|module| module.index)
+    }
+
+    // Module centres along a path, for plotting it
+    pub fn route_points(&self, path: &[usize]) -> Vec<Vec2> {
+        path.iter().filter_map(|&i| self.modules.get(i)).map(|module| module.center).collect()
+    }
+}
+
+pub fn module_color(module_type: ModuleType) -> Vec3 {
+    match module_type {
+        ModuleType::Corridor => Vec3::new(0.6, 0.6, 0.6),
+        ModuleType::Hub => Vec3::new(0.7, 0.7, 0.9),
+        ModuleType::Airlock => Vec3::new(1.0, 0.6, 0.1),
+        ModuleType::LivingQuarters => Vec3::new(0.4, 0.8, 0.4),
+        ModuleType::CommandCenter => Vec3::new(0.3, 0.6, 1.0),
+        ModuleType::Laboratory => Vec3::new(0.8, 0.4, 0.9),
+        ModuleType::Storage => Vec3::new(0.8, 0.7, 0.4),
+        ModuleType::PowerPlant => Vec3::new(1.0, 0.9, 0.2),
+        ModuleType::EscapePod => Vec3::new(1.0, 0.35, 0.2),
+        ModuleType::SolarArray => Vec3::new(0.3, 0.45, 0.9),
+        ModuleType::Battery => Vec3::new(0.55, 0.85, 0.3),
+    }
+}
+
+// Top-down projection: world X goes right, world Z goes down the screen
+pub fn to_map(position: Vec3) -> Vec2 {
+    Vec2::new(position.x, position.z)
+}
+
+// Maps map space onto a screen rectangle
+#[derive(Debug, Clone, Copy)]
+pub struct MapView {
+    pub focus: Vec2,
+    pub pixels_per_meter: f32,
+    pub screen_center: Vec2,
+}
+
+impl MapView {
+    pub fn to_screen(self, point: Vec2) -> Vec2 {
+        self.screen_center + (point - self.focus) * self.pixels_per_meter
+    }
+
+    pub fn to_map(&self, screen: Vec2) -> Vec2 {
+        self.focus + (screen - self.screen_center) / self.pixels_per_meter
+    }
+}
+
+// Corner minimap plus a full-screen map (M) with pan and zoom. A module
+// picked on the full map is plotted a route to on both.
+#[derive(Debug)]
+pub struct StationMap {
+    pub full_screen: bool,
+    pub minimap_range: f32,
+    pub destination: Option<usize>,
+    pan: Vec2,
+    zoom: f32,
+}
+
+impl Default for StationMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StationMap {
+    pub fn new() -> Self {
+        Self {
+            full_screen: false,
+            minimap_range: 40.0,
+            destination: None,
+            pan: Vec2::ZERO,
+            zoom: 1.0,
+        }
+    }
+
+    // Opening the full map always starts from the whole-station view
+    pub fn toggle_full_screen(&mut self) {
+        self.full_screen = !self.full_screen;
+        self.pan = Vec2::ZERO;
+        self.zoom = 1.0;
+    }
+
+    // Picking the current destination again clears it
+    pub fn toggle_destination(&mut self, module: usize) {
+        self.destination = if self.destination == Some(module) { None } else { Some(module) };
+    }
+
+    pub fn zoom(&self) -> f32 {
+        self.zoom
+    }
+
+    // Drag in screen pixels; the map follows the cursor
+    pub fn pan_by(&mut self, screen_delta: Vec2, view: &MapView) {
+        self.pan -= screen_delta / view.pixels_per_meter;
+    }
+
+    // Positive notches zoom in
+    pub fn zoom_by(&mut self, notches: f32) {
+        self.zoom = (self.zoom * 1.2f32.powf(notches)).clamp(MIN_ZOOM, MAX_ZOOM);
+    }
+
+    // Minimap stays centred on the player at a fixed range
+    pub fn minimap_view(&self, player: Vec2, screen_center: Vec2, screen_size: Vec2) -> MapView {
+        MapView {
+            focus: player,
+            pixels_per_meter: screen_size.min_element() / self.minimap_range,
+            screen_center,
+        }
+    }
+
+    // Full map fits the whole station, then applies the player's pan and zoom
+    pub fn full_view(&self, layout: &MapLayout, screen_center: Vec2, screen_size: Vec2) -> MapView {
+        let margin = 0.9;
+        let size = layout.size().max(Vec2::splat(1.0));
+        let fit = (screen_size * margin / size).min_element();
+        MapView {
+            focus: layout.center() + self.pan,
+            pixels_per_meter: fit * self.zoom,
+            screen_center,
+        }
+    }
+}
@@ -0,0 +1,148 @@
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+
+// Oldest markers are dropped past this, so a station can't fill up with them
+pub const MAX_MARKERS: usize = 64;
+pub const MAX_LABEL_LEN: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarkerKind {
+    Note,
+    Leak,
+    Repair,
+    Stash,
+    Danger,
+    // Left by an observer for the crew
+    Annotation,
+}
+
+impl MarkerKind {
+    pub const ALL: [MarkerKind; 5] = [
+        MarkerKind::Note,
+        MarkerKind::Leak,
+        MarkerKind::Repair,
+        MarkerKind::Stash,
+        MarkerKind::Danger,
+    ];
+
+    // Observers' annotations aren't on the players' wheel
+    pub fn from_id(id: &str) -> Option<Self> {
+        Self::ALL.into_iter().chain([MarkerKind::Annotation]).find(|kind| kind.id() == id)
+    }
+
+    // Also the last part of its UI string ID, `marker.<id>`
+    pub fn id(self) -> &'static str {
+        match self {
+            MarkerKind::Note => "note",
+            MarkerKind::Leak => "leak",
+            MarkerKind::Repair => "repair",
+            MarkerKind::Stash => "stash",
+            MarkerKind::Danger => "danger",
+            MarkerKind::Annotation => "annotation",
+        }
+    }
+
+    pub fn color(self) -> Vec3 {
+        match self {
+            MarkerKind::Note => Vec3::new(0.9, 0.9, 0.9),
+            MarkerKind::Leak => Vec3::new(0.3, 0.7, 1.0),
+            MarkerKind::Repair => Vec3::new(1.0, 0.6, 0.1),
+            MarkerKind::Stash => Vec3::new(0.4, 1.0, 0.4),
+            MarkerKind::Danger => Vec3::new(1.0, 0.2, 0.2),
+            MarkerKind::Annotation => Vec3::new(0.8, 0.4, 1.0),
+        }
+    }
+}
+
+// A named point in the world left by a player
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Marker {
+    pub id: u32,
+    pub kind: MarkerKind,
+    pub label: String,
+    pub position: [f32; 3],
+}
+
+impl Marker {
+    pub fn position(&self) -> Vec3 {
+        Vec3::from(self.position)
+    }
+}
+
+// Every marker on the station; saved with the game
+#[derive(Debug, Clone, Default)]
+pub struct Markers {
+    markers: Vec<Marker>,
+    next_id: u32,
+}
+
+impl Markers {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // From a save; IDs carry on after the highest one in it
+    pub fn from_saved(markers: Vec<Marker>) -> Self {
+        let next_id = markers.iter().map(|marker| marker.id + 1).max().unwrap_or(0);
+        Self { markers, next_id }
+    }
+
+    pub fn markers(&self) -> &[Marker] {
+        &self.markers
+    }
+
+    pub fn get(&self, id: u32) -> Option<&Marker> {
+        self.markers.iter().find(|marker| marker.id == id)
+    }
+
+    pub fn place(&mut self, kind: MarkerKind, label: &str, position: Vec3) -> u32 {
+        if self.markers.len() >= MAX_MARKERS {
+            self.markers.remove(0);
+        }
+        let id = self.next_id;
+        self.next_id += 1;
+        self.markers.push(Marker {
+            id,
+            kind,
+            label: clean_label(label),
+            position: position.to_array(),
+        });
+        id
+    }
+
+    pub fn rename(&mut self, id: u32, label: &str) -> bool {
+        match self.markers.iter_mut().find(|marker| marker.id == id) {
+            Some(marker) => {
+                marker.label = clean_label(label);
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub fn remove(&mut self, id: u32) -> Option<Marker> {
+        let index = self.markers.iter().position(|marker| marker.id == id)?;
+        Some(self.markers.remove(index))
+    }
+
+    pub fn nearest(&self, position: Vec3, reach: f32) -> Option<u32> {
+        self.markers
+            .iter()
+            .map(|marker| (marker.id, marker.position().distance(position)))
+            .filter(|(_, distance)| *distance <= reach)
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(id, _)| id)
+    }
+}
+
+// One line, and short enough for a world label
+fn clean_label(label: &str) -> String {
+    label
+        .chars()
+        .filter(|c| !c.is_control())
+        .take(MAX_LABEL_LEN)
+        .collect::<String>()
+        .trim()
+        .to_string()
+}
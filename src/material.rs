@@ -2,6 +2,20 @@ use ash::vk;
 use gpu_allocator::vulkan::{Allocation, AllocationCreateDesc, AllocationScheme, Allocator};
 use glam::{Vec3, Vec4};
 
+// Seconds for a photoluminescent material to charge most of the way in full
+// light, and the time constant of its afterglow once the light goes
+const PHOSPHOR_CHARGE_TIME: f32 = 20.0;
+const PHOSPHOR_DECAY_TIME: f32 = 150.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum EmissiveMode {
+    // Glows at `emissive` all the time
+    Constant,
+    // Charges from the light falling on it and gives it back as a fading
+    // glow, scaled by the stored charge (0..1)
+    Photoluminescent { charge: f32 },
+}
+
 #[derive(Debug)]
 pub struct Material {
     pub albedo: Vec4,
@@ -9,6 +23,7 @@ pub struct Material {
     pub roughness: f32,
     pub alpha: f32,
     pub emissive: Vec3,
+    pub emissive_mode: EmissiveMode,
     pub normal_scale: f32,
     pub occlusion_strength: f32,
     pub alpha_cutoff: f32,
@@ -25,6 +40,7 @@ impl Material {
             roughness,
             alpha,
             emissive: Vec3::ZERO,
+            emissive_mode: EmissiveMode::Constant,
             normal_scale: 1.0,
             occlusion_strength: 1.0,
             alpha_cutoff: 0.5,
@@ -33,6 +49,25 @@ impl Material {
             allocation: None,
         }
     }
+
+    // The same finish in another colour, as a new instance with no GPU
+    // buffer yet; for per-object overrides like paint
+    pub fn with_albedo(&self, color: Vec3) -> Self {
+        Self {
+            albedo: color.extend(self.albedo.w),
+            metallic: self.metallic,
+            roughness: self.roughness,
+            alpha: self.alpha,
+            emissive: self.emissive,
+            emissive_mode: self.emissive_mode,
+            normal_scale: self.normal_scale,
+            occlusion_strength: self.occlusion_strength,
+            alpha_cutoff: self.alpha_cutoff,
+            double_sided: self.double_sided,
+            buffer: None,
+            allocation: None,
+        }
+    }
 }
 
 impl Default for Material {
@@ -43,6 +78,7 @@ impl Default for Material {
             roughness: 0.5,
             alpha: 1.0,
             emissive: Vec3::ZERO,
+            emissive_mode: EmissiveMode::Constant,
             normal_scale: 1.0,
             occlusion_strength: 1.0,
             alpha_cutoff: 0.5,
@@ -74,6 +110,7 @@ impl Material {
             roughness: 0.1,
             alpha: 1.0,
             emissive: Vec3::ZERO,
+            emissive_mode: EmissiveMode::Constant,
             normal_scale: 1.0,
             occlusion_strength: 1.0,
             alpha_cutoff: 0.5,
@@ -90,6 +127,7 @@ impl Material {
             roughness: 0.5,
             alpha: 1.0,
             emissive: Vec3::ZERO,
+            emissive_mode: EmissiveMode::Constant,
             normal_scale: 1.0,
             occlusion_strength: 1.0,
             alpha_cutoff: 0.5,
@@ -106,6 +144,7 @@ impl Material {
             roughness: 0.1,
             alpha,
             emissive: Vec3::ZERO,
+            emissive_mode: EmissiveMode::Constant,
             normal_scale: 1.0,
             occlusion_strength: 1.0,
             alpha_cutoff: 0.5,
@@ -115,6 +154,34 @@ impl Material {
         }
     }
 
+    // Glow-in-the-dark paint: dull by day, `glow` at full charge. Starts charged.
+    pub fn create_photoluminescent(color: Vec3, glow: Vec3) -> Self {
+        let mut material = Self::create_plastic(color);
+        material.emissive = glow;
+        material.emissive_mode = EmissiveMode::Photoluminescent { charge: 1.0 };
+        material
+    }
+
+    // `light` is the light reaching the surface, 0..1
+    pub fn update_emissive(&mut self, light: f32, delta_time: f32) {
+        if let EmissiveMode::Photoluminescent { charge } = &mut self.emissive_mode {
+            let light = light.clamp(0.0, 1.0);
+            if light > *charge {
+                *charge += (light - *charge) * (1.0 - (-delta_time / PHOSPHOR_CHARGE_TIME).exp());
+            } else {
+                *charge = (*charge * (-delta_time / PHOSPHOR_DECAY_TIME).exp()).max(light);
+            }
+        }
+    }
+
+    // Emissive colour as of now
+    pub fn emission(&self) -> Vec3 {
+        match self.emissive_mode {
+            EmissiveMode::Constant => self.emissive,
+            EmissiveMode::Photoluminescent { charge } => self.emissive * charge,
+        }
+    }
+
     pub fn create_buffer(
         &mut self,
         device: &ash::Device,
@@ -150,7 +217,7 @@ impl Material {
             albedo: self.albedo,
             metallic: self.metallic,
             roughness: self.roughness,
-            emissive: self.emissive,
+            emissive: self.emission(),
             normal_scale: self.normal_scale,
             occlusion_strength: self.occlusion_strength,
             alpha_cutoff: self.alpha_cutoff,
@@ -177,6 +244,7 @@ impl Clone for Material {
             roughness: self.roughness,
             alpha: self.alpha,
             emissive: self.emissive,
+            emissive_mode: self.emissive_mode,
             normal_scale: self.normal_scale,
             occlusion_strength: self.occlusion_strength,
             alpha_cutoff: self.alpha_cutoff,
@@ -0,0 +1,108 @@
+use serde::{Deserialize, Deserializer, Serialize};
+
+// A handle on a station module that stays good while other modules come and
+// go: the slot it was given, and that slot's generation at the time. Once the
+// module's removed the handle goes stale and looks up nothing, even after
+// the slot's reused.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ModuleId {
+    pub slot: u32,
+    pub generation: u32,
+}
+
+impl ModuleId {
+    // What a module has before it's put on a station
+    pub const UNPLACED: ModuleId = ModuleId { slot: u32::MAX, generation: 0 };
+
+    // The id a freshly built station gives its `index`th module, which is
+    // how asset files number them
+    pub fn from_layout(index: usize) -> ModuleId {
+        ModuleId { slot: index as u32, generation: 0 }
+    }
+}
+
+// For `deserialize_with` on asset fields that give a module by its place
+// in the layout
+pub fn layout_index<'de, D: Deserializer<'de>>(deserializer: D) -> Result<ModuleId, D::Error> {
+    usize::deserialize(deserializer).map(ModuleId::from_layout)
+}
+
+pub fn optional_layout_index<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<ModuleId>, D::Error> {
+    Ok(Option::<usize>::deserialize(deserializer)?.map(ModuleId::from_layout))
+}
+
+#[derive(Debug, Clone, Default)]
+struct Slot {
+    generation: u32,
+    // Where the module is in the station's list, while there is one
+    index: Option<usize>,
+}
+
+// Slot map from ids to where their modules sit in the station's list, which
+// stays dense for the simulation to run through in order
+#[derive(Debug, Clone, Default)]
+pub struct ModuleIds {
+    slots: Vec<Slot>,
+    free: Vec<u32>,
+}
+
+impl ModuleIds {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // An id for the module at `index`, in a freed slot if there is one
+    pub fn insert(&mut self, index: usize) -> ModuleId {
+        match self.free.pop() {
+            Some(slot) => {
+                let entry = &mut self.slots[slot as usize];
+                entry.generation += 1;
+                entry.index = Some(index);
+                ModuleId { slot, generation: entry.generation }
+            }
+            None => {
+                self.slots.push(Slot { generation: 0, index: Some(index) });
+                ModuleId { slot: self.slots.len() as u32 - 1, generation: 0 }
+            }
+        }
+    }
+
+    // Takes the same id again, as when a save is loaded; false if it's
+    // already taken
+    pub fn reinsert(&mut self, id: ModuleId, index: usize) -> bool {
+        let slot = id.slot as usize;
+        if slot >= self.slots.len() {
+            let start = self.slots.len() as u32;
+            self.slots.resize(slot + 1, Slot::default());
+            self.free.extend(start..id.slot);
+        }
+        let entry = &mut self.slots[slot];
+        if entry.index.is_some() {
+            return false;
+        }
+        self.free.retain(|&free| free != id.slot);
+        *entry = Slot { generation: id.generation, index: Some(index) };
+        true
+    }
+
+    pub fn get(&self, id: ModuleId) -> Option<usize> {
+        let entry = self.slots.get(id.slot as usize)?;
+        (entry.generation == id.generation).then_some(entry.index).flatten()
+    }
+
+    // Frees the id's slot; the modules after it in the list move down one.
+    // Returns where it was.
+    pub fn remove(&mut self, id: ModuleId) -> Option<usize> {
+        let removed = self.get(id)?;
+        self.slots[id.slot as usize].index = None;
+        self.free.push(id.slot);
+        for entry in &mut self.slots {
+            if let Some(index) = &mut entry.index {
+                if *index > removed {
+                    *index -= 1;
+                }
+            }
+        }
+        Some(removed)
+    }
+}
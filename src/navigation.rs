@@ -0,0 +1,119 @@
+use std::cmp::Ordering;
+use std::collections::BinaryHeap;
+use crate::station::{ElementState, SpaceStation};
+
+// A module reached at some distance, ordered nearest first for the heap
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Frontier {
+    distance: f32,
+    module: usize,
+}
+
+impl Eq for Frontier {}
+
+impl Ord for Frontier {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.distance.total_cmp(&self.distance).then(other.module.cmp(&self.module))
+    }
+}
+
+impl PartialOrd for Frontier {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+// Whether the way between two connected modules is open: a locked door
+// blocks it
+pub fn passable(station: &SpaceStation, a: usize, b: usize) -> bool {
+    let Some(door) = station.door_between(a, b) else {
+        return true;
+    };
+    let element = station.doors()[door].element;
+    station.door_modules(door).is_none_or(|(first, _)| station.element_state(first, element) != Some(ElementState::Locked))
+}
+
+// Shortest walk between module centres over the connection graph, from
+// `from` to `to` inclusive. Locked doors are in the way, and so are breached
+// modules, unless the walk starts or ends in one. None when there's no way
+// through.
+pub fn find_path(station: &SpaceStation, from: usize, to: usize) -> Option<Vec<usize>> {
+    let modules = station.modules();
+    if from >= modules.len() || to >= modules.len() {
+        return None;
+    }
+    let mut distance = vec![f32::INFINITY; modules.len()];
+    let mut previous = vec![None; modules.len()];
+    let mut frontier = BinaryHeap::from([Frontier { distance: 0.0, module: from }]);
+    distance[from] = 0.0;
+    while let Some(Frontier { distance: reached, module: current }) = frontier.pop() {
+        if current == to {
+            break;
+        }
+        // A stale entry, or a breach we can't walk through
+        if reached > distance[current] || (current != from && !modules[current].atmosphere_sealed) {
+            continue;
+        }
+        for next in station.neighbours(current) {
+            if !passable(station, current, next) {
+                continue;
+            }
+            let step = modules[current].transform.position.distance(modules[next].transform.position);
+            if reached + step < distance[next] {
+                distance[next] = reached + step;
+                previous[next] = Some(current);
+                frontier.push(Frontier { distance: reached + step, module: next });
+            }
+        }
+    }
+    if distance[to].is_infinite() {
+        return None;
+    }
+    let mut path = vec![to];
+    let mut step = to;
+    while let Some(before) = previous[step] {
+        path.push(before);
+        step = before;
+    }
+    path.reverse();
+    Some(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Vec3;
+    use crate::station::ModuleType;
+
+    #[test]
+    fn paths_take_the_shortest_walk_around_breaches_and_locked_doors() {
+        let mut station = SpaceStation::new();
+        let start = station.add_module(ModuleType::Corridor, Vec3::ZERO);
+        let end = station.add_module(ModuleType::Corridor, Vec3::new(20.0, 0.0, 0.0));
+        // Straight along in corridors, or the long way round through hubs
+        let straight: Vec<usize> =
+            [4.0, 8.0, 12.0, 16.0].iter().map(|&x| station.add_module(ModuleType::Corridor, Vec3::new(x, 0.0, 0.0))).collect();
+        let detour: Vec<usize> =
+            [0.0, 10.0, 20.0].iter().map(|&x| station.add_module(ModuleType::Hub, Vec3::new(x, 0.0, 8.0))).collect();
+        for way in [&straight, &detour] {
+            let route: Vec<usize> = [vec![start], way.clone(), vec![end]].concat();
+            for pair in route.windows(2) {
+                assert!(station.connect_modules(pair[0], pair[1]));
+            }
+        }
+        let near = straight[0];
+        // Fewer modules isn't a shorter walk
+        assert_eq!(station.find_path(start, end), Some([vec![start], straight.clone(), vec![end]].concat()));
+
+        station.module_mut(near).unwrap().atmosphere_sealed = false;
+        assert_eq!(station.find_path(start, end), Some([vec![start], detour.clone(), vec![end]].concat()));
+        // A breach can still be walked into or out of
+        assert_eq!(station.find_path(start, near), Some(vec![start, near]));
+        assert_eq!(station.find_path(near, end), Some([straight.clone(), vec![end]].concat()));
+
+        let door = station.door_between(start, detour[0]).unwrap();
+        station.set_door_locked(door, true);
+        assert_eq!(station.find_path(start, end), None);
+        assert_eq!(station.find_path(start, start), Some(vec![start]));
+    }
+}
@@ -0,0 +1,95 @@
+use std::collections::VecDeque;
+use crate::event_log::LogSeverity;
+
+// Seconds spent fading in and out at either end of a notification's life
+const FADE_TIME: f32 = 0.4;
+
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub severity: LogSeverity,
+    pub message: String,
+    pub count: u32,
+    pub age: f32,
+    pub lifetime: f32,
+}
+
+impl Notification {
+    pub fn alpha(&self) -> f32 {
+        let fade_in = self.age / FADE_TIME;
+        let fade_out = (self.lifetime - self.age) / FADE_TIME;
+        fade_in.min(fade_out).clamp(0.0, 1.0)
+    }
+}
+
+// Timed toasts stacked in the HUD, newest first. Repeats of a message that is
+// still on screen bump its count and restart its timer instead of stacking.
+#[derive(Debug)]
+pub struct Notifications {
+    pub max_visible: usize,
+    queue: VecDeque<Notification>,
+}
+
+impl Default for Notifications {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Notifications {
+    pub fn new() -> Self {
+        Self {
+            max_visible: 5,
+            queue: VecDeque::new(),
+        }
+    }
+
+    pub fn push(&mut self, severity: LogSeverity, message: impl Into<String>) {
+        let message = message.into();
+        if let Some(existing) = self.queue.iter_mut().find(|n| n.message == message) {
+            existing.count += 1;
+            existing.age = existing.age.min(FADE_TIME);
+            return;
+        }
+
+        self.queue.push_front(Notification {
+            severity,
+            message,
+            count: 1,
+            age: 0.0,
+            lifetime: Self::lifetime(severity),
+        });
+    }
+
+    // More serious notifications stay up longer
+    fn lifetime(severity: LogSeverity) -> f32 {
+        match severity {
+            LogSeverity::Info => 4.0,
+            LogSeverity::Warning => 6.0,
+            LogSeverity::Critical => 10.0,
+        }
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        // Only visible notifications age, so a burst queues up rather than expiring unseen
+        for notification in self.queue.iter_mut().take(self.max_visible) {
+            notification.age += delta_time;
+        }
+        self.queue.retain(|n| n.age < n.lifetime);
+    }
+
+    pub fn visible(&self) -> impl Iterator<Item = &Notification> {
+        self.queue.iter().take(self.max_visible)
+    }
+
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+}
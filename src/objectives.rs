@@ -0,0 +1,348 @@
+use std::fs;
+use std::path::Path;
+use anyhow::{bail, Context, Result};
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+use crate::events::StationEvent;
+use crate::station::{ElementState, SpaceStation};
+
+// A predicate over station state, written inline in the objective file:
+// `condition = { kind = "power_above", percent = 100 }`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Condition {
+    // Generation covers at least this much of demand
+    PowerAbove { percent: f32 },
+    OxygenAbove { percent: f32 },
+    StationIntegrityAbove { percent: f32 },
+    ModuleIntegrityAbove { module: usize, percent: f32 },
+    WiringAbove { module: usize, percent: f32 },
+    ModuleSealed { module: usize },
+    AllSealed,
+    ElementActive { module: usize, element: usize },
+    // Anything but broken
+    ElementWorking { module: usize, element: usize },
+    // No solar flare under way
+    NoInterference,
+    // The player is closer to this module than to any other
+    PlayerInModule { module: usize },
+    All { conditions: Vec<Condition> },
+    Any { conditions: Vec<Condition> },
+}
+
+impl Condition {
+    pub fn evaluate(&self, station: &SpaceStation, player: Vec3) -> bool {
+        let telemetry = station.telemetry();
+        let module = |idx: usize| station.modules().get(idx);
+        match self {
+            Condition::PowerAbove { percent } => {
+                telemetry.power_generation >= telemetry.power_consumption * percent / 100.0
+            }
+            Condition::OxygenAbove { percent } => telemetry.oxygen_level * 100.0 >= *percent,
+            Condition::StationIntegrityAbove { percent } => telemetry.structural_integrity * 100.0 >= *percent,
+            Condition::ModuleIntegrityAbove { module: idx, percent } => {
+                module(*idx).is_some_and(|m| m.structural_integrity * 100.0 >= *percent)
+            }
+            Condition::WiringAbove { module: idx, percent } => module(*idx).is_some_and(|m| m.wiring * 100.0 >= *percent),
+            Condition::ModuleSealed { module: idx } => module(*idx).is_some_and(|m| m.atmosphere_sealed),
+            Condition::AllSealed => telemetry.sealed_modules == telemetry.module_count,
+            Condition::ElementActive { module, element } => {
+                station.element_state(*module, *element) == Some(ElementState::Active)
+            }
+            Condition::ElementWorking { module, element } => {
+                station.element_state(*module, *element).is_some_and(|state| state != ElementState::Malfunction)
+            }
+            Condition::NoInterference => !station.interference().active(),
+            Condition::PlayerInModule { module } => station.nearest_module(player) == Some(*module),
+            Condition::All { conditions } => conditions.iter().all(|c| c.evaluate(station, player)),
+            Condition::Any { conditions } => conditions.iter().any(|c| c.evaluate(station, player)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ObjectiveDefinition {
+    pub id: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    pub condition: Condition,
+    // Objective started when this one completes
+    #[serde(default)]
+    pub next: Option<String>,
+    // Event kind (see `StationEvent::kind`) that starts this objective
+    #[serde(default)]
+    pub activate_on: Option<String>,
+    // Fast travel is off while this objective is active
+    #[serde(default)]
+    pub no_fast_travel: bool,
+}
+
+#[derive(Debug, Deserialize)]
+struct ObjectiveFile {
+    #[serde(rename = "objective", default)]
+    objectives: Vec<ObjectiveDefinition>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ObjectiveStatus {
+    Pending,
+    Active,
+    Completed,
+}
+
+// How the objectives stand, for saves
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ObjectivesState {
+    pub objectives: Vec<SavedObjective>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SavedObjective {
+    pub id: String,
+    pub status: ObjectiveStatus,
+    // Only for objectives made up during play, which no file has
+    #[serde(default)]
+    pub definition: Option<ObjectiveDefinition>,
+}
+
+// Tracks which objectives are running and completes them when their condition
+// holds. Objectives nothing chains into and that aren't event-triggered are
+// active from the start.
+#[derive(Debug, Default)]
+pub struct Objectives {
+    definitions: Vec<ObjectiveDefinition>,
+    status: Vec<ObjectiveStatus>,
+    // The first this many came from the file; the rest were added in play
+    defined: usize,
+}
+
+impl Objectives {
+    pub fn new(definitions: Vec<ObjectiveDefinition>) -> Result<Self> {
+        for definition in &definitions {
+            if let Some(next) = &definition.next {
+                if !definitions.iter().any(|d| &d.id == next) {
+                    bail!("objective {} chains to unknown objective {}", definition.id, next);
+                }
+            }
+        }
+
+        let status = (0..definitions.len()).map(|i| starting_status(&definitions, i)).collect();
+        let defined = definitions.len();
+        Ok(Self { definitions, status, defined })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("failed to read objectives from {}", path.display()))?;
+        let file: ObjectiveFile = toml::from_str(&source).context("invalid objective definitions")?;
+        Self::new(file.objectives)
+    }
+
+    // An objective made up during play, as for an incident; it's active
+    // straight away
+    pub fn add(&mut self, definition: ObjectiveDefinition) {
+        self.definitions.push(definition);
+        self.status.push(ObjectiveStatus::Active);
+    }
+
+    // Event-triggered objectives start (again) when their event comes in
+    pub fn handle_event(&mut self, event: &StationEvent) {
+        for (definition, status) in self.definitions.iter().zip(&mut self.status) {
+            if definition.activate_on.as_deref() == Some(event.kind()) {
+                *status = ObjectiveStatus::Active;
+            }
+        }
+    }
+
+    // Completes active objectives whose condition holds and starts whatever
+    // they chain into; returns how many completed
+    pub fn update(&mut self, station: &mut SpaceStation, player: Vec3) -> usize {
+        let mut completed = Vec::new();
+        for (i, definition) in self.definitions.iter().enumerate() {
+            if self.status[i] == ObjectiveStatus::Active && definition.condition.evaluate(station, player) {
+                completed.push(i);
+            }
+        }
+
+        for &i in &completed {
+            self.status[i] = ObjectiveStatus::Completed;
+            let definition = &self.definitions[i];
+            station.emit_event(StationEvent::ObjectiveCompleted {
+                id: definition.id.clone(),
+                title: definition.title.clone(),
+            });
+            if let Some(next) = &definition.next {
+                if let Some(j) = self.definitions.iter().position(|d| &d.id == next) {
+                    self.status[j] = ObjectiveStatus::Active;
+                }
+            }
+        }
+        completed.len()
+    }
+
+    pub fn active(&self) -> impl Iterator<Item = &ObjectiveDefinition> {
+        self.definitions
+            .iter()
+            .zip(&self.status)
+            .filter(|(_, status)| **status == ObjectiveStatus::Active)
+            .map(|(definition, _)| definition)
+    }
+
+    pub fn fast_travel_allowed(&self) -> bool {
+        !self.active().any(|definition| definition.no_fast_travel)
+    }
+
+    pub fn status(&self, id: &str) -> Option<ObjectiveStatus> {
+        let i = self.definitions.iter().position(|d| d.id == id)?;
+        Some(self.status[i])
+    }
+
+    pub fn completed_count(&self) -> usize {
+        self.status.iter().filter(|s| **s == ObjectiveStatus::Completed).count()
+    }
+
+    pub fn len(&self) -> usize {
+        self.definitions.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.definitions.is_empty()
+    }
+
+    pub fn state(&self) -> ObjectivesState {
+        let objectives = self
+            .definitions
+            .iter()
+            .zip(&self.status)
+            .enumerate()
+            .map(|(i, (definition, &status))| SavedObjective {
+                id: definition.id.clone(),
+                status,
+                definition: (i >= self.defined).then(|| definition.clone()),
+            })
+            .collect();
+        ObjectivesState { objectives }
+    }
+
+    // Picks up a saved state. Objectives from the file take their saved
+    // status, or start afresh if the save predates them; saved ones the file
+    // no longer has are dropped.
+    pub fn restore(&mut self, state: &ObjectivesState) {
+        self.definitions.truncate(self.defined);
+        self.status = (0..self.defined).map(|i| starting_status(&self.definitions, i)).collect();
+        for saved in &state.objectives {
+            match &saved.definition {
+                Some(definition) => {
+                    self.definitions.push(definition.clone());
+                    self.status.push(saved.status);
+                }
+                None => {
+                    if let Some(i) = self.definitions[..self.defined].iter().position(|d| d.id == saved.id) {
+                        self.status[i] = saved.status;
+                    }
+                }
+            }
+        }
+    }
+}
+
+// Pending if another objective or an event starts it, else active
+fn starting_status(definitions: &[ObjectiveDefinition], i: usize) -> ObjectiveStatus {
+    let definition = &definitions[i];
+    let chained = definitions.iter().any(|d| d.next.as_deref() == Some(definition.id.as_str()));
+    if chained || definition.activate_on.is_some() {
+        ObjectiveStatus::Pending
+    } else {
+        ObjectiveStatus::Active
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHAIN: &str = r#"
+        [[objective]]
+        id = "survey"
+        title = "Survey the damage"
+        condition = { kind = "power_above", percent = 0 }
+        next = "patch"
+
+        [[objective]]
+        id = "patch"
+        title = "Patch the lab"
+        condition = { kind = "module_integrity_above", module = 5, percent = 90 }
+        next = "report"
+
+        [[objective]]
+        id = "report"
+        title = "Report in"
+        condition = { kind = "power_above", percent = 0 }
+
+        [[objective]]
+        id = "respond"
+        title = "Answer the breach"
+        condition = { kind = "module_integrity_above", module = 5, percent = 90 }
+        activate_on = "hull_breach"
+        no_fast_travel = true
+    "#;
+
+    fn parse(source: &str) -> Result<Objectives> {
+        Objectives::new(toml::from_str::<ObjectiveFile>(source)?.objectives)
+    }
+
+    #[test]
+    fn completed_objectives_start_the_ones_they_chain_into() {
+        let mut station = SpaceStation::create_default_layout();
+        station.damage_module(5, 0.5);
+        station.drain_events();
+        let mut objectives = parse(CHAIN).unwrap();
+        let statuses = |objectives: &Objectives| {
+            ["survey", "patch", "report"].map(|id| objectives.status(id).unwrap())
+        };
+        use ObjectiveStatus::{Active, Completed, Pending};
+        assert_eq!(statuses(&objectives), [Active, Pending, Pending]);
+
+        // The next one starts but isn't checked until the update after
+        assert_eq!(objectives.update(&mut station, Vec3::ZERO), 1);
+        assert_eq!(statuses(&objectives), [Completed, Active, Pending]);
+        assert!(matches!(&station.drain_events()[..], [StationEvent::ObjectiveCompleted { id, .. }] if id == "survey"));
+        assert_eq!(objectives.update(&mut station, Vec3::ZERO), 0);
+
+        station.repair_module(5, 1.0);
+        assert_eq!(objectives.update(&mut station, Vec3::ZERO), 1);
+        assert_eq!(objectives.update(&mut station, Vec3::ZERO), 1);
+        assert_eq!(statuses(&objectives), [Completed; 3]);
+        assert_eq!(objectives.completed_count(), 3);
+
+        let broken = CHAIN.replace(r#"next = "report""#, r#"next = "debrief""#);
+        let err = parse(&broken).unwrap_err();
+        assert_eq!(err.to_string(), "objective patch chains to unknown objective debrief");
+    }
+
+    #[test]
+    fn event_triggered_objectives_wait_for_their_event_and_start_again_on_the_next() {
+        let mut station = SpaceStation::create_default_layout();
+        let mut objectives = parse(CHAIN).unwrap();
+        let breach = StationEvent::HullBreach { module: 5, position: Vec3::ZERO };
+        assert_eq!(objectives.status("respond"), Some(ObjectiveStatus::Pending));
+        assert!(objectives.fast_travel_allowed());
+
+        objectives.handle_event(&StationEvent::SolarFlareEnded);
+        assert_eq!(objectives.status("respond"), Some(ObjectiveStatus::Pending));
+        station.damage_module(5, 0.5);
+        objectives.handle_event(&breach);
+        assert_eq!(objectives.status("respond"), Some(ObjectiveStatus::Active));
+        assert!(!objectives.fast_travel_allowed());
+
+        station.repair_module(5, 1.0);
+        objectives.update(&mut station, Vec3::ZERO);
+        assert_eq!(objectives.status("respond"), Some(ObjectiveStatus::Completed));
+        assert!(objectives.fast_travel_allowed());
+        objectives.handle_event(&breach);
+        assert_eq!(objectives.status("respond"), Some(ObjectiveStatus::Active));
+    }
+}
@@ -0,0 +1,190 @@
+use glam::Vec3;
+use crate::crew::{Activity, Crew, CrewMember};
+use crate::fly_camera::CameraPose;
+use crate::markers::{MarkerKind, Markers};
+use crate::station::{InteractionType, SpaceStation};
+use crate::terminal::Terminal;
+
+// Where the follow camera sits: this far back from the crew member it's on,
+// at about head height, looking at their shoulders
+const FOLLOW_DISTANCE: f32 = 3.0;
+const FOLLOW_HEIGHT: f32 = 2.2;
+const FOLLOW_LOOK_HEIGHT: f32 = 1.4;
+// How quickly it catches them up, per second
+const FOLLOW_STIFFNESS: f32 = 5.0;
+// How far in front of the camera annotations go
+const ANNOTATION_DISTANCE: f32 = 2.0;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ObserverView {
+    // The fly camera, steered as usual
+    Free,
+    // Behind a crew member, by index
+    Follow(usize),
+}
+
+// Watching the station rather than crewing it, as an instructor running a
+// training scenario or someone streaming one would. An observer has no body
+// there: the player stays where they were, nothing can be worked, consoles
+// open read-only, and all they can leave behind is annotations for the crew.
+// There's no networking, so it's a role taken at this machine.
+#[derive(Debug)]
+pub struct Observer {
+    active: bool,
+    view: ObserverView,
+    // The follow camera, once it's on someone, and which way round them it
+    // looks from
+    pose: Option<CameraPose>,
+    orbit: f32,
+}
+
+impl Default for Observer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Observer {
+    pub fn new() -> Self {
+        Self { active: false, view: ObserverView::Free, pose: None, orbit: 0.0 }
+    }
+
+    pub fn active(&self) -> bool {
+        self.active
+    }
+
+    pub fn view(&self) -> ObserverView {
+        self.view
+    }
+
+    // Starts or stops observing, back in the free view; true if it started
+    pub fn toggle(&mut self) -> bool {
+        self.active = !self.active;
+        self.view = ObserverView::Free;
+        self.pose = None;
+        self.active
+    }
+
+    // Free, then each crew member still aboard in turn, then free again
+    pub fn next_view(&mut self, crew: &Crew) {
+        let aboard = |index: &usize| crew.members()[*index].activity != Activity::Evacuated;
+        let start = match self.view {
+            ObserverView::Free => 0,
+            ObserverView::Follow(index) => index + 1,
+        };
+        self.view = (start..crew.members().len()).find(aboard).map_or(ObserverView::Free, ObserverView::Follow);
+        self.pose = None;
+    }
+
+    pub fn following<'a>(&self, crew: &'a Crew) -> Option<&'a CrewMember> {
+        match self.view {
+            ObserverView::Free => None,
+            ObserverView::Follow(index) => crew.members().get(index),
+        }
+    }
+
+    // Eases the follow camera in behind whoever it's on, `turn` radians
+    // further round; None in the free view. Drops back to free if they've
+    // gone, into a pod or off the roster.
+    pub fn follow(&mut self, crew: &Crew, turn: f32, delta_time: f32) -> Option<CameraPose> {
+        let ObserverView::Follow(index) = self.view else {
+            return None;
+        };
+        let Some(member) = crew.members().get(index).filter(|member| member.activity != Activity::Evacuated) else {
+            self.view = ObserverView::Free;
+            self.pose = None;
+            return None;
+        };
+        self.orbit += turn;
+        let behind = Vec3::new(self.orbit.cos(), 0.0, self.orbit.sin());
+        let target = member.position + Vec3::Y * FOLLOW_HEIGHT - behind * FOLLOW_DISTANCE;
+        let position = match self.pose {
+            Some(pose) => pose.position.lerp(target, (FOLLOW_STIFFNESS * delta_time).min(1.0)),
+            None => target,
+        };
+        let look = member.position + Vec3::Y * FOLLOW_LOOK_HEIGHT - position;
+        let pose = CameraPose {
+            position,
+            yaw: look.z.atan2(look.x),
+            pitch: look.y.atan2(Vec3::new(look.x, 0.0, look.z).length()),
+        };
+        self.pose = Some(pose);
+        Some(pose)
+    }
+
+    // Leaves a note a little way in front of the camera for the crew to find
+    pub fn annotate(&self, markers: &mut Markers, label: &str, pose: &CameraPose) -> u32 {
+        markers.place(MarkerKind::Annotation, label, pose.position + pose.forward() * ANNOTATION_DISTANCE)
+    }
+}
+
+// A console in `module` to look over: its first terminal or main computer
+pub fn console_in(station: &SpaceStation, module: usize) -> Option<(usize, InteractionType)> {
+    station.modules().get(module)?.interactive_elements.iter().enumerate().find_map(|(i, element)| {
+        Terminal::opens_for(element.element_type).then_some((i, element.element_type))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::comms::Comms;
+    use crate::crew::CrewDefinition;
+    use crate::docking::Docking;
+    use crate::paint::StyleGuide;
+    use crate::research::Research;
+    use crate::terminal::TerminalPage;
+
+    #[test]
+    fn observers_follow_the_crew_and_read_consoles_without_touching_them() {
+        let station = SpaceStation::create_default_layout();
+        let mut crew = Crew::new();
+        crew.add(CrewDefinition { name: "Lindqvist".to_string(), quarters: 6, workstation: 5, shift_start: 0.0 }, &station).unwrap();
+        crew.add(CrewDefinition { name: "Okafor".to_string(), quarters: 6, workstation: 8, shift_start: 0.0 }, &station).unwrap();
+        let mut observer = Observer::new();
+        assert!(observer.toggle());
+
+        // Free camera, then each of the crew in turn, then free again
+        observer.next_view(&crew);
+        assert_eq!(observer.view(), ObserverView::Follow(0));
+        let pose = observer.follow(&crew, 0.0, 0.1).unwrap();
+        let member = crew.members()[0].position;
+        assert!(pose.position.distance(member) < 4.0);
+        assert!((pose.position + pose.forward()).distance(member) < pose.position.distance(member));
+        observer.next_view(&crew);
+        assert_eq!(observer.following(&crew).unwrap().definition.name, "Okafor");
+        observer.next_view(&crew);
+        assert_eq!(observer.view(), ObserverView::Free);
+        assert!(observer.follow(&crew, 0.0, 0.1).is_none());
+
+        // A console opened to look over can't paint and has nothing to switch off
+        let (element, element_type) = console_in(&station, 0).unwrap();
+        assert_eq!(element_type, InteractionType::MainComputer);
+        assert!(console_in(&station, 1).is_none());
+        let mut terminal = Terminal::new();
+        terminal.set_style_guide(StyleGuide::load("assets/style_guide.toml").unwrap());
+        let to_paint = |terminal: &mut Terminal| {
+            while terminal.page() != TerminalPage::Paint {
+                terminal.next_page();
+            }
+        };
+        terminal.open(0, element, element_type);
+        to_paint(&mut terminal);
+        assert!(terminal.selected_swatch().is_some());
+        assert_eq!(terminal.close(), Some((0, element)));
+        terminal.open_read_only(0, element, element_type);
+        assert!(terminal.screen(&station, &Docking::default(), &Research::default(), &Comms::new(Vec::new())).unwrap().title.contains("READ ONLY"));
+        to_paint(&mut terminal);
+        assert!(terminal.selected_swatch().is_none());
+        assert_eq!(terminal.close(), None);
+
+        // Annotations go down where the camera's looking
+        let mut markers = Markers::new();
+        let id = observer.annotate(&mut markers, "Check this valve", &pose);
+        let marker = markers.get(id).unwrap();
+        assert_eq!(marker.kind, MarkerKind::Annotation);
+        assert!(marker.position().distance(pose.position + pose.forward() * 2.0) < 1e-4);
+        assert_eq!(MarkerKind::from_id("annotation"), Some(MarkerKind::Annotation));
+        assert!(!MarkerKind::ALL.contains(&MarkerKind::Annotation));
+    }
+}
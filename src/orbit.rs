@@ -0,0 +1,99 @@
+use glam::{Quat, Vec3};
+use crate::clock::MissionTime;
+
+// Station-local frame: +Y points away from the planet (zenith), +X along the
+// direction of travel and +Z along the orbit normal. Over one orbit the sun
+// appears to sweep once around the Z axis.
+#[derive(Debug, Clone)]
+pub struct Orbit {
+    pub period: f32,
+    pub beta_angle: f32,
+    pub eclipse_fraction: f32,
+    pub phase_offset: f32,
+}
+
+impl Orbit {
+    pub fn low_earth() -> Self {
+        Self {
+            period: 92.0 * 60.0,
+            beta_angle: 20f32.to_radians(),
+            eclipse_fraction: 0.38,
+            phase_offset: 0.0,
+        }
+    }
+
+    pub fn orbit_angle(&self, time: f32) -> f32 {
+        let phase = (time / self.period + self.phase_offset).fract();
+        phase * std::f32::consts::TAU
+    }
+
+    // Unit vector pointing towards the sun
+    pub fn sun_direction(&self, time: f32) -> Vec3 {
+        let angle = self.orbit_angle(time);
+        let (sin_b, cos_b) = self.beta_angle.sin_cos();
+        Vec3::new(cos_b * angle.cos(), cos_b * angle.sin(), sin_b)
+    }
+
+    // How far the station frame has turned against the stars. Anything
+    // fixed in space, the sun included, is seen turned by its inverse.
+    pub fn attitude(&self, time: f32) -> Quat {
+        Quat::from_rotation_z(-self.orbit_angle(time))
+    }
+
+    // 1.0 in full sun, 0.0 in the planet's shadow, with a short penumbra ramp
+    pub fn sunlight(&self, time: f32) -> f32 {
+        let half_eclipse = self.eclipse_fraction * std::f32::consts::PI;
+        let from_nadir = self.sun_direction(time).angle_between(Vec3::NEG_Y);
+        let penumbra = 2f32.to_radians();
+        ((from_nadir - half_eclipse) / penumbra).clamp(0.0, 1.0)
+    }
+}
+
+impl Default for Orbit {
+    fn default() -> Self {
+        Self::low_earth()
+    }
+}
+
+// Runs orbital lighting faster than mission time so the sun's path can be
+// checked without waiting out a real orbit. Never touches the mission clock.
+#[derive(Debug, Clone)]
+pub struct OrbitPreview {
+    pub enabled: bool,
+    pub speed: f32,
+    offset: f32,
+}
+
+impl Default for OrbitPreview {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl OrbitPreview {
+    pub fn new() -> Self {
+        Self {
+            enabled: false,
+            speed: 120.0,
+            offset: 0.0,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.enabled = !self.enabled;
+        if !self.enabled {
+            self.offset = 0.0;
+        }
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        if self.enabled {
+            self.offset += delta_time * (self.speed - 1.0);
+        }
+    }
+
+    // Orbital time to light the scene with
+    pub fn lighting_time(&self, now: MissionTime) -> f32 {
+        now.as_secs_f32() + self.offset
+    }
+}
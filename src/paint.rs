@@ -0,0 +1,156 @@
+use std::fs;
+use std::path::Path;
+use anyhow::{Context, Result};
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+
+// Unpainted trim: bare brushed aluminium
+pub const TRIM_COLOR: Vec3 = Vec3::new(0.45, 0.47, 0.5);
+
+// The parts of a module's interior that can be painted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Surface {
+    Walls,
+    Trim,
+}
+
+impl Surface {
+    pub const ALL: [Surface; 2] = [Surface::Walls, Surface::Trim];
+
+    pub fn id(self) -> &'static str {
+        match self {
+            Surface::Walls => "walls",
+            Surface::Trim => "trim",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Surface::ALL.into_iter().find(|surface| surface.id() == name)
+    }
+}
+
+// A colour from the station's style guide, and what it may go on
+#[derive(Debug, Clone, Deserialize)]
+pub struct Swatch {
+    pub name: String,
+    pub color: [f32; 3],
+    pub surfaces: Vec<Surface>,
+}
+
+impl Swatch {
+    pub fn allows(&self, surface: Surface) -> bool {
+        self.surfaces.contains(&surface)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct StyleGuideFile {
+    #[serde(rename = "swatch", default)]
+    swatches: Vec<Swatch>,
+}
+
+// The colours crews are allowed to paint with
+#[derive(Debug, Default)]
+pub struct StyleGuide {
+    swatches: Vec<Swatch>,
+}
+
+impl StyleGuide {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("failed to read style guide from {}", path.display()))?;
+        let file: StyleGuideFile = toml::from_str(&source).context("invalid style guide")?;
+
+        let mut guide = Self::new();
+        for swatch in file.swatches {
+            if guide.get(&swatch.name).is_some() {
+                eprintln!("Warning: style guide lists {} twice", swatch.name);
+                continue;
+            }
+            guide.swatches.push(swatch);
+        }
+        Ok(guide)
+    }
+
+    pub fn swatches(&self) -> &[Swatch] {
+        &self.swatches
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Swatch> {
+        self.swatches.iter().find(|swatch| swatch.name == name)
+    }
+}
+
+// A surface's coat of paint. The colour is kept with the name so saves
+// look the same whatever the style guide says now.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Coat {
+    pub swatch: String,
+    pub color: [f32; 3],
+}
+
+// What a module's interior has been painted; unpainted surfaces keep the
+// module type's finish
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModulePaint {
+    pub walls: Option<Coat>,
+    pub trim: Option<Coat>,
+}
+
+impl ModulePaint {
+    pub fn coat(&self, surface: Surface) -> Option<&Coat> {
+        match surface {
+            Surface::Walls => self.walls.as_ref(),
+            Surface::Trim => self.trim.as_ref(),
+        }
+    }
+
+    pub fn set(&mut self, surface: Surface, coat: Option<Coat>) {
+        match surface {
+            Surface::Walls => self.walls = coat,
+            Surface::Trim => self.trim = coat,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.walls.is_none() && self.trim.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Vec4;
+    use crate::station::SpaceStation;
+
+    #[test]
+    fn paint_overrides_module_materials_within_the_style_guide_and_is_saved() {
+        let mut station = SpaceStation::create_default_layout();
+        let sage = Swatch { name: "pale_sage".to_string(), color: [0.66, 0.74, 0.62], surfaces: vec![Surface::Walls] };
+        let orange = Swatch { name: "safety_orange".to_string(), color: [0.95, 0.45, 0.1], surfaces: vec![Surface::Trim] };
+        let standard = station.modules()[6].material.albedo;
+
+        assert!(station.paint_module(6, Surface::Walls, &sage));
+        assert!(station.paint_module(6, Surface::Trim, &orange));
+        // Orange is for trim only
+        assert!(!station.paint_module(6, Surface::Walls, &orange));
+        let quarters = &station.modules()[6];
+        assert_eq!(quarters.material.albedo, Vec4::new(0.66, 0.74, 0.62, standard.w));
+        assert_eq!(quarters.trim.albedo.truncate(), Vec3::new(0.95, 0.45, 0.1));
+        // Only the one module's instance changes
+        assert_eq!(station.modules()[5].paint.coat(Surface::Walls), None);
+
+        let mut restored = SpaceStation::from_snapshot(&station.snapshot());
+        assert_eq!(restored.modules()[6].paint, station.modules()[6].paint);
+        assert_eq!(restored.modules()[6].material.albedo, station.modules()[6].material.albedo);
+        restored.strip_paint(6, Surface::Walls);
+        assert_eq!(restored.modules()[6].material.albedo, standard);
+        assert_eq!(restored.modules()[6].paint.coat(Surface::Trim).unwrap().swatch, "safety_orange");
+    }
+}
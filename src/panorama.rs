@@ -0,0 +1,244 @@
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::PathBuf;
+use anyhow::{bail, Context, Result};
+use glam::Vec3;
+
+pub const DEFAULT_FACE_SIZE: u32 = 1024;
+// Between the eyes, for stereo captures
+const EYE_SEPARATION: f32 = 0.064;
+
+// The six views of a cubemap, each a 90° square
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CubeFace {
+    PosX,
+    NegX,
+    PosY,
+    NegY,
+    PosZ,
+    NegZ,
+}
+
+impl CubeFace {
+    pub const ALL: [CubeFace; 6] = [
+        CubeFace::PosX,
+        CubeFace::NegX,
+        CubeFace::PosY,
+        CubeFace::NegY,
+        CubeFace::PosZ,
+        CubeFace::NegZ,
+    ];
+
+    pub fn forward(self) -> Vec3 {
+        match self {
+            CubeFace::PosX => Vec3::X,
+            CubeFace::NegX => Vec3::NEG_X,
+            CubeFace::PosY => Vec3::Y,
+            CubeFace::NegY => Vec3::NEG_Y,
+            CubeFace::PosZ => Vec3::Z,
+            CubeFace::NegZ => Vec3::NEG_Z,
+        }
+    }
+
+    // Straight up and down can't use Y as up, so they look with Z
+    pub fn up(self) -> Vec3 {
+        match self {
+            CubeFace::PosY => Vec3::Z,
+            CubeFace::NegY => Vec3::NEG_Z,
+            _ => Vec3::Y,
+        }
+    }
+
+    fn right(self) -> Vec3 {
+        self.forward().cross(self.up())
+    }
+
+    // The face a direction lands on, and where on it from 0,0 top left to
+    // 1,1 bottom right
+    fn project(direction: Vec3) -> (CubeFace, f32, f32) {
+        let abs = direction.abs();
+        let face = if abs.x >= abs.y && abs.x >= abs.z {
+            if direction.x > 0.0 { CubeFace::PosX } else { CubeFace::NegX }
+        } else if abs.y >= abs.z {
+            if direction.y > 0.0 { CubeFace::PosY } else { CubeFace::NegY }
+        } else if direction.z > 0.0 {
+            CubeFace::PosZ
+        } else {
+            CubeFace::NegZ
+        };
+        let depth = direction.dot(face.forward());
+        let x = direction.dot(face.right()) / depth;
+        let y = direction.dot(face.up()) / depth;
+        (face, (x + 1.0) * 0.5, (1.0 - y) * 0.5)
+    }
+}
+
+// One rendered face, RGBA rows top to bottom
+#[derive(Debug)]
+struct FaceImage {
+    size: u32,
+    rgba: Vec<u8>,
+}
+
+impl FaceImage {
+    // Bilinear, clamped to the face's edge
+    fn sample(&self, u: f32, v: f32) -> [f32; 3] {
+        let size = self.size as f32;
+        let x = (u * size - 0.5).clamp(0.0, size - 1.0);
+        let y = (v * size - 0.5).clamp(0.0, size - 1.0);
+        let (x0, y0) = (x.floor() as u32, y.floor() as u32);
+        let (x1, y1) = ((x0 + 1).min(self.size - 1), (y0 + 1).min(self.size - 1));
+        let (fx, fy) = (x.fract(), y.fract());
+        let texel = |x: u32, y: u32| {
+            let i = ((y * self.size + x) * 4) as usize;
+            Vec3::new(self.rgba[i] as f32, self.rgba[i + 1] as f32, self.rgba[i + 2] as f32)
+        };
+        let top = texel(x0, y0).lerp(texel(x1, y0), fx);
+        let bottom = texel(x0, y1).lerp(texel(x1, y1), fx);
+        top.lerp(bottom, fy).to_array()
+    }
+}
+
+// A 360° capture from where the camera is standing. The scene is rendered
+// once per cube face over the next few frames (twice over for stereo, a
+// cubemap per eye), then stitched into an equirectangular image centred on
+// the way the camera was facing. Stereo images are left eye on top, as VR
+// photo viewers expect.
+#[derive(Debug)]
+pub struct PanoramaCapture {
+    pub path: PathBuf,
+    pub face_size: u32,
+    stereo: bool,
+    // Eye and horizontal facing, fixed by the first face
+    origin: Option<(Vec3, Vec3)>,
+    faces: Vec<FaceImage>,
+}
+
+impl PanoramaCapture {
+    pub fn new(path: PathBuf, face_size: u32, stereo: bool) -> Self {
+        Self {
+            path,
+            face_size: face_size.max(1),
+            stereo,
+            origin: None,
+            faces: Vec::new(),
+        }
+    }
+
+    fn view_count(&self) -> usize {
+        if self.stereo { 12 } else { 6 }
+    }
+
+    pub fn complete(&self) -> bool {
+        self.faces.len() >= self.view_count()
+    }
+
+    // Eye, target and up for the next face to render; None once they're
+    // all in. The first call fixes where the panorama is taken from.
+    pub fn next_view(&mut self, eye: Vec3, forward: Vec3) -> Option<(Vec3, Vec3, Vec3)> {
+        if self.complete() {
+            return None;
+        }
+        let forward = Vec3::new(forward.x, 0.0, forward.z).try_normalize().unwrap_or(Vec3::NEG_Z);
+        let (eye, _) = *self.origin.get_or_insert((eye, forward));
+        let face = CubeFace::ALL[self.faces.len() % 6];
+        // Each eye sits to the side of the direction being looked in; straight
+        // up and down there's no side, so both eyes share the middle
+        let eye = if !self.stereo || face.forward().y != 0.0 {
+            eye
+        } else {
+            let side = if self.faces.len() < 6 { -0.5 } else { 0.5 };
+            eye + face.right() * EYE_SEPARATION * side
+        };
+        Some((eye, eye + face.forward(), face.up()))
+    }
+
+    // The face `next_view` asked for, as RGBA rows top to bottom
+    pub fn push_face(&mut self, rgba: Vec<u8>) -> Result<()> {
+        let expected = (self.face_size * self.face_size * 4) as usize;
+        if rgba.len() != expected {
+            bail!("panorama face is {} bytes, expected {}", rgba.len(), expected);
+        }
+        if !self.complete() {
+            self.faces.push(FaceImage { size: self.face_size, rgba });
+        }
+        Ok(())
+    }
+
+    // The equirectangular image, RGB, four face widths across
+    pub fn stitch(&self) -> (u32, u32, Vec<u8>) {
+        let width = self.face_size * 4;
+        let height = width / 2;
+        let eyes = if self.stereo { 2 } else { 1 };
+        let (_, forward) = self.origin.unwrap_or((Vec3::ZERO, Vec3::NEG_Z));
+        let right = forward.cross(Vec3::Y);
+        let mut rgb = Vec::with_capacity((width * height * 3 * eyes) as usize);
+        for cubemap in self.faces.chunks(6).take(eyes as usize) {
+            for row in 0..height {
+                let latitude = std::f32::consts::FRAC_PI_2 - (row as f32 + 0.5) / height as f32 * std::f32::consts::PI;
+                for column in 0..width {
+                    let longitude = (column as f32 + 0.5) / width as f32 * std::f32::consts::TAU - std::f32::consts::PI;
+                    let direction = forward * latitude.cos() * longitude.cos()
+                        + right * latitude.cos() * longitude.sin()
+                        + Vec3::Y * latitude.sin();
+                    let (face, u, v) = CubeFace::project(direction);
+                    let index = CubeFace::ALL.iter().position(|&f| f == face).unwrap_or(0);
+                    let color = cubemap.get(index).map_or([0.0; 3], |image| image.sample(u, v));
+                    rgb.extend(color.map(|c| c.round().clamp(0.0, 255.0) as u8));
+                }
+            }
+        }
+        (width, height * eyes, rgb)
+    }
+
+    // Stitches and writes the PNG to `path`
+    pub fn write_png(&self) -> Result<()> {
+        let path = &self.path;
+        if !self.complete() {
+            bail!("panorama has {} of {} faces", self.faces.len(), self.view_count());
+        }
+        let (width, height, rgb) = self.stitch();
+        let file = File::create(path).with_context(|| format!("failed to create {}", path.display()))?;
+        let mut encoder = png::Encoder::new(BufWriter::new(file), width, height);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().context("failed to write PNG header")?;
+        writer.write_image_data(&rgb).context("failed to write panorama")?;
+        writer.finish().context("failed to finish panorama")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panoramas_stitch_cube_faces_around_the_view() {
+        let size = 8;
+        let mut capture = PanoramaCapture::new("pano.png".into(), size, true);
+        let mut views = Vec::new();
+        // Each face a flat colour of its own
+        while let Some(view) = capture.next_view(Vec3::ONE, Vec3::new(0.0, 0.3, -1.0)) {
+            let shade = views.len() as u8 % 6 * 40;
+            views.push(view);
+            capture.push_face([shade, shade, shade, 255].repeat((size * size) as usize)).unwrap();
+        }
+        assert!(capture.complete() && views.len() == 12);
+        assert!(capture.push_face(Vec::new()).is_err());
+        // The eyes straddle the origin looking sideways, and meet looking up
+        assert!((views[4].0 + views[10].0 - Vec3::ONE * 2.0).length() < 1e-5);
+        assert!(views[4].0.distance(views[10].0) > 0.05);
+        assert_eq!(views[2].0, views[8].0);
+
+        let (width, height, rgb) = capture.stitch();
+        assert_eq!((width, height, rgb.len()), (32, 32, 32 * 32 * 3));
+        let pixel = |x: u32, y: u32| rgb[((y * width + x) * 3) as usize];
+        let shade = |face: CubeFace| CubeFace::ALL.iter().position(|&f| f == face).unwrap() as u8 * 40;
+        // Straight ahead is -Z, whatever the pitch; the top row is the sky
+        assert_eq!(pixel(16, 8), shade(CubeFace::NegZ));
+        assert_eq!(pixel(0, 8), shade(CubeFace::PosZ));
+        assert_eq!(pixel(24, 8), shade(CubeFace::PosX));
+        assert_eq!(pixel(5, 0), shade(CubeFace::PosY));
+        assert_eq!(pixel(16, 24), shade(CubeFace::NegZ));
+    }
+}
@@ -1,6 +1,8 @@
 use std::time::Duration;
 use glam::Vec3;
 use std::collections::HashMap;
+use crate::rng::{self, SimRng};
+use crate::tunables::ParticleTunables;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
 pub enum ParticleType {
@@ -87,6 +89,8 @@ impl Default for ParticleConfig {
 #[derive(Debug, Clone)]
 pub struct Particle {
     pub position: Vec3,
+    // Position before the last tick, for interpolating between ticks
+    pub previous_position: Vec3,
     pub velocity: Vec3,
     pub acceleration: Vec3,
     pub size: f32,
@@ -103,6 +107,7 @@ impl Default for Particle {
     fn default() -> Self {
         Self {
             position: Vec3::ZERO,
+            previous_position: Vec3::ZERO,
             velocity: Vec3::ZERO,
             acceleration: Vec3::ZERO,
             size: 1.0,
@@ -121,6 +126,7 @@ impl Particle {
     pub fn new(config: ParticleConfig) -> Self {
         Self {
             position: config.position,
+            previous_position: config.position,
             velocity: config.direction * config.speed,
             acceleration: Vec3::ZERO,
             size: config.size,
@@ -134,44 +140,50 @@ impl Particle {
         }
     }
 
-    pub fn update(&mut self, dt: f32) {
+    // Where to draw the particle `alpha` of the way into the next tick
+    pub fn interpolated_position(&self, alpha: f32) -> Vec3 {
+        self.previous_position.lerp(self.position, alpha)
+    }
+
+    pub fn update(&mut self, dt: f32, tunables: &ParticleTunables) {
+        self.previous_position = self.position;
         match self.particle_type {
             ParticleType::Debris => {
                 self.position += self.velocity * dt;
-                self.velocity *= 0.99; // Apply drag
+                self.velocity *= tunables.debris_drag; // Apply drag
                 self.lifetime = self.lifetime.saturating_sub(Duration::from_secs_f32(dt));
             }
             ParticleType::Smoke => {
                 self.position += self.velocity * dt;
-                self.velocity.y += 0.1 * dt; // Smoke rises
-                self.size *= 1.01; // Smoke expands
+                self.velocity.y += tunables.smoke_rise * dt; // Smoke rises
+                self.size *= tunables.smoke_growth; // Smoke expands
                 self.lifetime = self.lifetime.saturating_sub(Duration::from_secs_f32(dt));
             }
             ParticleType::Fire => {
                 self.position += self.velocity * dt;
-                self.velocity.y += 0.2 * dt; // Fire rises faster
-                self.size *= 0.99; // Fire shrinks
+                self.velocity.y += tunables.fire_rise * dt; // Fire rises faster
+                self.size *= tunables.fire_shrink; // Fire shrinks
                 self.lifetime = self.lifetime.saturating_sub(Duration::from_secs_f32(dt));
             }
             ParticleType::Spark => {
                 self.position += self.velocity * dt;
-                self.velocity.y -= 0.5 * dt; // Gravity
-                self.size *= 0.98; // Sparks shrink
+                self.velocity.y -= tunables.spark_gravity * dt; // Gravity
+                self.size *= tunables.spark_shrink; // Sparks shrink
                 self.lifetime = self.lifetime.saturating_sub(Duration::from_secs_f32(dt));
             }
             ParticleType::Glow => {
                 self.position += self.velocity * dt;
-                self.size *= 0.95; // Glow fades quickly
+                self.size *= tunables.glow_fade; // Glow fades quickly
                 self.lifetime = self.lifetime.saturating_sub(Duration::from_secs_f32(dt));
             }
             ParticleType::Flash => {
                 self.position += self.velocity * dt;
-                self.size *= 0.95; // Flash fades quickly
+                self.size *= tunables.glow_fade; // Flash fades quickly
                 self.lifetime = self.lifetime.saturating_sub(Duration::from_secs_f32(dt));
             }
             ParticleType::Shockwave => {
                 self.position += self.velocity * dt;
-                self.size *= 1.1; // Shockwave expands
+                self.size *= tunables.shockwave_growth; // Shockwave expands
                 self.lifetime = self.lifetime.saturating_sub(Duration::from_secs_f32(dt));
             }
             ParticleType::ElectricArc => {
@@ -227,6 +239,7 @@ pub struct ParticleEmitter {
     pub particle_lifetime: Duration,
     pub emit_timer: Duration,
     pub emission_interval: Duration,
+    rng: SimRng,
 }
 
 impl ParticleEmitter {
@@ -246,6 +259,7 @@ pub struct ParticleEmitterBuilder {
     initial_velocity: f32,
     particle_size: f32,
     particle_lifetime: Duration,
+    seed: u64,
 }
 
 impl ParticleEmitterBuilder {
@@ -298,6 +312,12 @@ impl ParticleEmitterBuilder {
         self
     }
 
+    // Seed for emission randomness, so the same seed emits the same particles
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = seed;
+        self
+    }
+
     pub fn build(self) -> ParticleEmitter {
         ParticleEmitter {
             position: self.position,
@@ -314,12 +334,13 @@ impl ParticleEmitterBuilder {
             max_particles: 100,
             emit_timer: Duration::from_secs(0),
             emission_interval: Duration::from_secs_f32(1.0),
+            rng: rng::seeded(self.seed),
         }
     }
 }
 
 impl ParticleEmitter {
-    pub fn update(&mut self, dt: f32) {
+    pub fn update(&mut self, dt: f32, tunables: &ParticleTunables) {
         // Update emission timer
         self.emit_timer += Duration::from_secs_f32(dt);
         if self.emit_timer >= self.emission_interval {
@@ -348,20 +369,20 @@ impl ParticleEmitter {
 
         // Update all particles
         self.particles.retain_mut(|particle| {
-            particle.update(dt);
+            particle.update(dt, tunables);
             particle.age < particle.lifetime
         });
     }
 
     pub fn emit(&mut self) {
-        if self.particles.len() >= 100 {
+        if self.particles.len() >= self.max_particles {
             return;
         }
 
         let spawn_pos = match &self.emission_pattern {
             EmissionPattern::Point => self.position,
             EmissionPattern::Sphere { radius } => {
-                let direction = random_direction();
+                let direction = rng::random_direction(&mut self.rng);
                 self.position + direction * *radius
             }
             EmissionPattern::Cone { radius, height } => {
@@ -409,15 +430,3 @@ impl ParticleEmitter {
         self.particles.push(particle);
     }
 }
-
-fn random_direction() -> Vec3 {
-    use rand::Rng;
-    let mut rng = rand::thread_rng();
-    let theta = rng.gen_range(0.0..std::f32::consts::TAU);
-    let phi = rng.gen_range(0.0..std::f32::consts::PI);
-    Vec3::new(
-        theta.cos() * phi.sin(),
-        phi.cos(),
-        theta.sin() * phi.sin()
-    )
-}
@@ -1,5 +1,6 @@
 use glam::Vec3;
 use std::collections::HashMap;
+use crate::rng::{self, SimRng};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
 pub enum BehaviorType {
@@ -231,7 +232,7 @@ pub struct PredatorBehavior {
 }
 
 impl PredatorBehavior {
-    pub fn calculate_force(&self, position: Vec3, prey_positions: &[Vec3]) -> Vec3 {
+    pub fn calculate_force(&self, position: Vec3, prey_positions: &[Vec3], rng: &mut SimRng) -> Vec3 {
         let mut closest_prey = None;
         let mut min_distance = f32::MAX;
 
@@ -255,7 +256,7 @@ impl PredatorBehavior {
             }
         } else {
             // Wander when no prey is visible
-            random_direction() * self.chase_speed * 0.5
+            rng::random_direction(rng) * self.chase_speed * 0.5
         }
     }
 }
@@ -270,16 +271,6 @@ fn limit_vector(v: Vec3, max_length: f32) -> Vec3 {
     }
 }
 
-fn random_direction() -> Vec3 {
-    let theta = rand::random::<f32>() * std::f32::consts::TAU;
-    let phi = rand::random::<f32>() * std::f32::consts::PI;
-    Vec3::new(
-        phi.sin() * theta.cos(),
-        phi.sin() * theta.sin(),
-        phi.cos()
-    )
-}
-
 // Simplex noise implementation (simplified for example)
 fn simplex_noise_3d(x: f32, y: f32, z: f32) -> (f64, f64, f64) {
     use noise::{NoiseFn, Simplex};
@@ -0,0 +1,115 @@
+use glam::{Mat4, Quat, Vec3};
+use crate::geometry::Mesh;
+use crate::module_id::ModuleId;
+use crate::station::{ModuleType, StationModule};
+
+// Middle of a hatch, up off the module's floor
+pub const HATCH_HEIGHT: f32 = 1.1;
+pub const HATCH_RADIUS: f32 = 1.2;
+// The longest tube that gets run between two ports
+pub const MAX_TUBE_LENGTH: f32 = 4.0;
+// Ports closer than this just get a collar round the seam
+const COLLAR_LENGTH: f32 = 0.3;
+// How squarely two ports have to face each other (cosine), and how far off
+// each other's line or into each other they can be and still mate
+const FACING_TOLERANCE: f32 = 0.98;
+const OFFSET_TOLERANCE: f32 = 0.3;
+
+// A hatch something can be connected to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DockingPort {
+    // Middle of the hatch, and the way out through it
+    pub position: Vec3,
+    pub facing: Vec3,
+}
+
+impl DockingPort {
+    // The type's ports are in the module's own space; this is where one is
+    // once the module's placed
+    pub fn placed(&self, module: &StationModule) -> DockingPort {
+        DockingPort {
+            position: module.transform.matrix().transform_point3(self.position),
+            facing: module.transform.rotation * self.facing,
+        }
+    }
+
+    // How far it is from here out to `other` if the two face each other
+    // squarely and line up, negative if they're a little into each other
+    pub fn gap_to(&self, other: &DockingPort) -> Option<f32> {
+        if self.facing.dot(other.facing) > -FACING_TOLERANCE {
+            return None;
+        }
+        let offset = other.position - self.position;
+        let gap = offset.dot(self.facing);
+        let sideways = (offset - self.facing * gap).length();
+        (sideways <= OFFSET_TOLERANCE && (-OFFSET_TOLERANCE..=MAX_TUBE_LENGTH).contains(&gap)).then_some(gap)
+    }
+}
+
+// One hatch in the middle of each side wall: -Z, +X, +Z, -X
+pub fn docking_ports(module_type: ModuleType) -> Vec<DockingPort> {
+    let (half_width, half_depth) = module_type.hull_half_extents();
+    [Vec3::NEG_Z, Vec3::X, Vec3::Z, Vec3::NEG_X]
+        .into_iter()
+        .map(|facing| DockingPort {
+            position: facing * Vec3::new(half_width, 0.0, half_depth) + Vec3::Y * HATCH_HEIGHT,
+            facing,
+        })
+        .collect()
+}
+
+// Of the listed ports on `a` and `b`, the pair that mates with the least
+// between them, as (a's port, b's port, gap)
+pub fn best_mate(a: &StationModule, a_ports: &[usize], b: &StationModule, b_ports: &[usize]) -> Option<(usize, usize, f32)> {
+    let (a_all, b_all) = (a.module_type.docking_ports(), b.module_type.docking_ports());
+    a_ports
+        .iter()
+        .flat_map(|&i| b_ports.iter().map(move |&j| (i, j)))
+        .filter_map(|(i, j)| {
+            let gap = a_all.get(i)?.placed(a).gap_to(&b_all.get(j)?.placed(b))?;
+            Some((i, j, gap))
+        })
+        .min_by(|x, y| x.2.abs().total_cmp(&y.2.abs()))
+}
+
+// The tube, or just the hatch collar, joining two mated ports
+#[derive(Debug)]
+pub struct Connector {
+    // The lower-numbered module first, as for doors
+    pub modules: (ModuleId, ModuleId),
+    // Which of each module's ports
+    pub ports: (usize, usize),
+    pub length: f32,
+    // Places the mesh, which runs up its Y axis
+    pub matrix: Mat4,
+    pub mesh: Mesh,
+}
+
+impl Connector {
+    // `from` is the first module's port, placed
+    pub fn new(modules: (ModuleId, ModuleId), ports: (usize, usize), from: &DockingPort, gap: f32) -> Self {
+        let length = gap.max(COLLAR_LENGTH);
+        // Centred on the gap, so a collar straddles the seam
+        let start = from.position + from.facing * (gap - length) * 0.5;
+        let matrix = Mat4::from_rotation_translation(Quat::from_rotation_arc(Vec3::Y, from.facing), start);
+        Self {
+            modules,
+            ports,
+            length,
+            matrix,
+            mesh: Mesh::create_cylinder(HATCH_RADIUS, length, 16),
+        }
+    }
+
+    pub fn uses(&self, module: ModuleId, port: usize) -> bool {
+        (self.modules.0 == module && self.ports.0 == port) || (self.modules.1 == module && self.ports.1 == port)
+    }
+
+    pub fn joins(&self, a: ModuleId, b: ModuleId) -> bool {
+        self.modules == (a, b) || self.modules == (b, a)
+    }
+
+    pub fn touches(&self, module: ModuleId) -> bool {
+        self.modules.0 == module || self.modules.1 == module
+    }
+}
@@ -0,0 +1,285 @@
+use anyhow::{bail, Result};
+use glam::Vec3;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use crate::rng::{self, SimRng};
+use crate::station::{ModuleType, SpaceStation, StationModule, MAX_CONNECTION_DISTANCE};
+
+// Boxes are allowed to touch: neighbours are placed wall to wall
+const TOUCHING: f32 = 0.05;
+// How many times a spine grows another corridor to find room for a module
+const MAX_GROWTH: usize = 8;
+
+const HORIZONTAL: [Vec3; 4] = [Vec3::NEG_Z, Vec3::X, Vec3::Z, Vec3::NEG_X];
+
+// What `SpaceStation::generate_procedural` grows
+#[derive(Debug, Clone)]
+pub struct LayoutParams {
+    // Corridor runs out from the command centre, 1 to 4
+    pub spines: usize,
+    // Corridors in each run to begin with; runs grow when they fill up
+    pub spine_length: usize,
+    // Functional modules, dealt out in this order and grouped by type
+    pub modules: Vec<ModuleType>,
+    pub airlocks: usize,
+}
+
+impl Default for LayoutParams {
+    fn default() -> Self {
+        Self {
+            spines: 4,
+            spine_length: 2,
+            modules: vec![
+                ModuleType::LivingQuarters,
+                ModuleType::LivingQuarters,
+                ModuleType::Laboratory,
+                ModuleType::Laboratory,
+                ModuleType::Storage,
+                ModuleType::Storage,
+                ModuleType::PowerPlant,
+            ],
+            airlocks: 2,
+        }
+    }
+}
+
+// Half the module's size along each axis
+fn half_extent(module_type: ModuleType) -> Vec3 {
+    let (min, max) = StationModule::new(module_type, Vec3::ZERO).bounds();
+    (max - min) * 0.5
+}
+
+// Where a module of this type sits wall to wall with `from` on the
+// `direction` side, and how far that is from `from`'s centre
+pub fn flush_position(station: &SpaceStation, from: usize, direction: Vec3, module_type: ModuleType) -> (Vec3, f32) {
+    let from_module = &station.modules()[from];
+    let reach = (half_extent(from_module.module_type) + half_extent(module_type)).dot(direction.abs());
+    (from_module.transform.position + direction * reach, reach)
+}
+
+// A free side of a corridor something could be attached to
+#[derive(Debug, Clone, Copy)]
+struct Socket {
+    corridor: usize,
+    direction: Vec3,
+    spine: usize,
+}
+
+struct Generator {
+    station: SpaceStation,
+    rng: SimRng,
+    sockets: Vec<Socket>,
+    // The last corridor of each spine and the way it runs
+    spine_ends: Vec<(usize, Vec3)>,
+}
+
+impl Generator {
+    // Where a module of this type goes against `from` on the `direction` side
+    fn place(&self, from: usize, direction: Vec3, module_type: ModuleType) -> Option<Vec3> {
+        let (position, reach) = flush_position(&self.station, from, direction, module_type);
+        if reach > MAX_CONNECTION_DISTANCE {
+            return None;
+        }
+        let bounds = StationModule::new(module_type, position).bounds();
+        let clear = self.station.modules().iter().all(|module| !overlaps(&bounds, &module.bounds()));
+        clear.then_some(position)
+    }
+
+    fn attach(&mut self, from: usize, position: Vec3, module_type: ModuleType) -> usize {
+        let index = self.station.add_module(module_type, position);
+        self.station.connect_modules(from, index);
+        index
+    }
+
+    // A corridor off `from` that carries a spine on, with its sides free
+    fn extend(&mut self, from: usize, direction: Vec3, spine: usize) -> Option<usize> {
+        let position = self.place(from, direction, ModuleType::Corridor)?;
+        let corridor = self.attach(from, position, ModuleType::Corridor);
+        self.sockets.retain(|socket| !(socket.corridor == from && socket.direction == direction));
+        for side in [direction.cross(Vec3::Y), -direction.cross(Vec3::Y), direction] {
+            self.sockets.push(Socket { corridor, direction: side, spine });
+        }
+        if spine == self.spine_ends.len() {
+            self.spine_ends.push((corridor, direction));
+        } else {
+            self.spine_ends[spine] = (corridor, direction);
+        }
+        Some(corridor)
+    }
+
+    // One more corridor on the end of a spine
+    fn grow(&mut self, spine: usize) -> bool {
+        let (end, direction) = self.spine_ends[spine];
+        self.extend(end, direction, spine).is_some()
+    }
+
+    // A new spine off the side of any corridor, for when every spine has
+    // run into something
+    fn branch(&mut self) -> bool {
+        let mut candidates = self.sockets.clone();
+        candidates.shuffle(&mut self.rng);
+        let spine = self.spine_ends.len();
+        candidates.iter().any(|socket| self.extend(socket.corridor, socket.direction, spine).is_some())
+    }
+
+    // Whether a socket is where its spine carries on from
+    fn spine_end(&self, socket: &Socket) -> bool {
+        self.spine_ends[socket.spine] == (socket.corridor, socket.direction)
+    }
+
+    // Attaches a module to a free socket, preferring `spine`'s and growing
+    // the spines when none fit. Returns where it went.
+    fn attach_anywhere(&mut self, module_type: ModuleType, spine: usize, perimeter: bool) -> Option<usize> {
+        for _ in 0..=MAX_GROWTH {
+            let mut candidates: Vec<usize> = (0..self.sockets.len()).collect();
+            candidates.shuffle(&mut self.rng);
+            if perimeter {
+                // Furthest out first, so airlocks face open space
+                let distance = |i: usize| {
+                    let socket = self.sockets[i];
+                    let corridor = self.station.modules()[socket.corridor].transform.position;
+                    (corridor + socket.direction).length()
+                };
+                candidates.sort_by(|&a, &b| distance(b).total_cmp(&distance(a)));
+            } else {
+                // Spine ends are kept free to grow from
+                candidates.retain(|&i| !self.spine_end(&self.sockets[i]));
+                candidates.sort_by_key(|&i| self.sockets[i].spine != spine);
+            }
+            for i in candidates {
+                let socket = self.sockets[i];
+                if let Some(position) = self.place(socket.corridor, socket.direction, module_type) {
+                    self.sockets.remove(i);
+                    return Some(self.attach(socket.corridor, position, module_type));
+                }
+            }
+            let grown = if perimeter { self.rng.gen_range(0..self.spine_ends.len()) } else { spine };
+            if !self.grow(grown) && !(0..self.spine_ends.len()).any(|spine| self.grow(spine)) && !self.branch() {
+                return None;
+            }
+        }
+        None
+    }
+}
+
+// Boxes as (min, max) corners, sharing more than a wall
+pub fn overlaps(a: &(Vec3, Vec3), b: &(Vec3, Vec3)) -> bool {
+    (a.0 + TOUCHING).cmplt(b.1).all() && (b.0 + TOUCHING).cmplt(a.1).all()
+}
+
+// Grows a station from a seed: a command centre core, corridor spines out
+// from it, functional modules clustered by type along the spines, and
+// airlocks on the outermost free sides. Every connection is within reach and
+// no two modules' boxes overlap; modules that can't be fitted are left out.
+pub fn generate(seed: u64, params: &LayoutParams) -> SpaceStation {
+    let mut generator = Generator {
+        station: SpaceStation::new(),
+        rng: rng::seeded(seed),
+        sockets: Vec::new(),
+        spine_ends: Vec::new(),
+    };
+    let core = generator.station.add_module(ModuleType::CommandCenter, Vec3::ZERO);
+
+    let mut directions = HORIZONTAL;
+    directions.shuffle(&mut generator.rng);
+    for &direction in directions.iter().take(params.spines.clamp(1, HORIZONTAL.len())) {
+        let spine = generator.spine_ends.len();
+        if generator.extend(core, direction, spine).is_none() {
+            continue;
+        }
+        for _ in 1..params.spine_length {
+            generator.grow(spine);
+        }
+    }
+
+    // Each type keeps to its own spine as far as it can
+    let mut types: Vec<ModuleType> = Vec::new();
+    for &module_type in &params.modules {
+        if !types.contains(&module_type) {
+            types.push(module_type);
+        }
+    }
+    for &module_type in &params.modules {
+        let spine = types.iter().position(|&t| t == module_type).unwrap_or(0) % generator.spine_ends.len().max(1);
+        if generator.spine_ends.is_empty() || generator.attach_anywhere(module_type, spine, false).is_none() {
+            eprintln!("Warning: no room for a {:?} in the generated layout", module_type);
+        }
+    }
+    for _ in 0..params.airlocks {
+        if generator.spine_ends.is_empty() || generator.attach_anywhere(ModuleType::Airlock, 0, true).is_none() {
+            eprintln!("Warning: no room for an airlock in the generated layout");
+        }
+    }
+
+    generator.station.reseed(seed);
+    generator.station
+}
+
+// Checks a layout holds together: every connection within reach and
+// through mated ports, no overlapping modules, and everything reachable from
+// the first module
+pub fn validate(station: &SpaceStation) -> Result<()> {
+    let modules = station.modules();
+    for (i, module) in modules.iter().enumerate() {
+        for j in station.neighbours(i) {
+            let distance = module.transform.position.distance(modules[j].transform.position);
+            if distance > MAX_CONNECTION_DISTANCE {
+                bail!("modules {} and {} are {:.1} m apart", i, j, distance);
+            }
+            if !station.connectors().iter().any(|connector| connector.joins(module.id, modules[j].id)) {
+                bail!("modules {} and {} are connected without docking ports that line up", i, j);
+            }
+        }
+        for (j, other) in modules.iter().enumerate().skip(i + 1) {
+            if overlaps(&module.bounds(), &other.bounds()) {
+                bail!("modules {} and {} overlap", i, j);
+            }
+        }
+    }
+    let mut reached = vec![false; modules.len()];
+    let mut stack = vec![0];
+    while let Some(i) = stack.pop() {
+        if i < modules.len() && !reached[i] {
+            reached[i] = true;
+            stack.extend(station.neighbours(i));
+        }
+    }
+    if let Some(stranded) = reached.iter().position(|&reached| !reached) {
+        bail!("module {} isn't connected to the rest", stranded);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn procedural_layouts_are_valid_and_repeat_from_their_seed() {
+        let params = LayoutParams::default();
+        for seed in 0..20 {
+            let station = SpaceStation::generate_procedural(seed, &params);
+            validate(&station).unwrap();
+            assert_eq!(station.modules()[0].module_type, ModuleType::CommandCenter);
+            let count = |module_type| station.modules().iter().filter(|m| m.module_type == module_type).count();
+            assert_eq!(count(ModuleType::Laboratory), 2);
+            assert_eq!(count(ModuleType::Airlock), params.airlocks);
+            // Airlocks hang off the end of a corridor with nothing beyond
+            for airlock in station.modules().iter().filter(|m| m.module_type == ModuleType::Airlock) {
+                let [corridor] = airlock.connected_modules[..] else {
+                    panic!("airlock with several connections");
+                };
+                let corridor = station.module_index(corridor).unwrap();
+                assert_eq!(station.modules()[corridor].module_type, ModuleType::Corridor);
+            }
+        }
+
+        let positions = |station: &SpaceStation| -> Vec<Vec3> {
+            station.modules().iter().map(|m| m.transform.position).collect()
+        };
+        let a = SpaceStation::generate_procedural(7, &params);
+        assert_eq!(positions(&a), positions(&SpaceStation::generate_procedural(7, &params)));
+        assert_ne!(positions(&a), positions(&SpaceStation::generate_procedural(8, &params)));
+        assert_eq!(a.seed(), 7);
+    }
+}
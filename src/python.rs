@@ -0,0 +1,238 @@
+// Python bindings over the headless simulation, built with
+// `maturin develop --release` (see pyproject.toml). Results come back as the
+// same dicts and lists the headless JSON report has, so notebooks can feed
+// them straight to pandas. Everything is deterministic for a given seed.
+use std::path::PathBuf;
+use glam::Vec3;
+use pyo3::exceptions::{PyIndexError, PyKeyError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use serde::Serialize;
+use crate::drill::{self, Drill};
+use crate::headless::{self, RecordedEvent};
+use crate::module_id::ModuleId;
+use crate::save::SaveGame;
+use crate::station::SpaceStation;
+use crate::stepping::STEP_DT;
+use crate::tunables::Tunables;
+
+// Round-trips through JSON so Python sees plain dicts, lists and numbers
+fn to_python<T: Serialize>(py: Python<'_>, value: &T) -> PyResult<PyObject> {
+    let json = serde_json::to_string(value).map_err(|e| PyValueError::new_err(e.to_string()))?;
+    Ok(py.import("json")?.call_method1("loads", (json,))?.unbind())
+}
+
+fn set_tunable(tunables: &mut Tunables, name: &str, value: f32) -> PyResult<()> {
+    match tunables.fields_mut().into_iter().find(|(field, _)| *field == name) {
+        Some((_, field)) => {
+            *field = value;
+            Ok(())
+        }
+        None => Err(PyKeyError::new_err(format!("unknown tunable {}", name))),
+    }
+}
+
+// Defaults overridden by a {"power.generator_output": 150.0, ...} dict
+fn tunables_from(overrides: Option<&Bound<'_, PyDict>>) -> PyResult<Tunables> {
+    let mut tunables = Tunables::default();
+    if let Some(overrides) = overrides {
+        for (name, value) in overrides.iter() {
+            set_tunable(&mut tunables, &name.extract::<String>()?, value.extract()?)?;
+        }
+    }
+    Ok(tunables)
+}
+
+// Modules go back and forth as (slot, generation) tuples
+type PyModuleId = (u32, u32);
+
+fn module_index(station: &SpaceStation, (slot, generation): PyModuleId) -> PyResult<usize> {
+    station
+        .module_index(ModuleId { slot, generation })
+        .ok_or_else(|| PyIndexError::new_err(format!("there is no module ({}, {})", slot, generation)))
+}
+
+fn seeded_station(seed: u64, save: Option<PathBuf>, tunables: &Tunables) -> PyResult<SpaceStation> {
+    let mut station = match save {
+        Some(path) => SpaceStation::from_snapshot(&SaveGame::read(&path)?.station),
+        None => SpaceStation::create_default_layout(),
+    };
+    station.set_tunables(tunables);
+    station.reseed(seed);
+    Ok(station)
+}
+
+// A station stepped one call at a time, for scripting scenarios
+#[pyclass(name = "Station", unsendable)]
+pub struct PyStation {
+    station: SpaceStation,
+    tunables: Tunables,
+    tick: u64,
+    // Raised since the last `events()` call
+    events: Vec<RecordedEvent>,
+}
+
+impl PyStation {
+    fn collect_events(&mut self) {
+        let tick = self.tick;
+        self.events.extend(self.station.drain_events().into_iter().map(|event| RecordedEvent {
+            tick,
+            kind: event.kind(),
+            description: event.describe(),
+        }));
+    }
+}
+
+#[pymethods]
+impl PyStation {
+    #[new]
+    #[pyo3(signature = (seed = 0, save = None, tunables = None))]
+    fn new(seed: u64, save: Option<PathBuf>, tunables: Option<&Bound<'_, PyDict>>) -> PyResult<Self> {
+        let tunables = tunables_from(tunables)?;
+        Ok(Self {
+            station: seeded_station(seed, save, &tunables)?,
+            tunables,
+            tick: 0,
+            events: Vec::new(),
+        })
+    }
+
+    // Steps the simulation `ticks` times at the fixed 60 Hz rate
+    #[pyo3(signature = (ticks = 1))]
+    fn tick(&mut self, ticks: u64) {
+        for _ in 0..ticks {
+            self.tick += 1;
+            self.station.update(STEP_DT);
+            self.collect_events();
+        }
+    }
+
+    #[getter]
+    fn ticks(&self) -> u64 {
+        self.tick
+    }
+
+    #[getter]
+    fn seconds(&self) -> f64 {
+        self.tick as f64 * STEP_DT as f64
+    }
+
+    #[getter]
+    fn seed(&self) -> u64 {
+        self.station.seed()
+    }
+
+    fn module_count(&self) -> usize {
+        self.station.modules().len()
+    }
+
+    // The current modules' ids, in the station's order
+    fn module_ids(&self) -> Vec<PyModuleId> {
+        self.station.modules().iter().map(|module| (module.id.slot, module.id.generation)).collect()
+    }
+
+    fn telemetry(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_python(py, &self.station.telemetry())
+    }
+
+    fn snapshot(&self, py: Python<'_>) -> PyResult<PyObject> {
+        to_python(py, &self.station.snapshot())
+    }
+
+    fn damage(&mut self, module: PyModuleId, amount: f32) -> PyResult<()> {
+        let module = module_index(&self.station, module)?;
+        self.station.damage_module(module, amount);
+        self.collect_events();
+        Ok(())
+    }
+
+    // Uses an element as the player would; false if it refused
+    fn interact(&mut self, module: PyModuleId, element: usize) -> PyResult<bool> {
+        let index = module_index(&self.station, module)?;
+        if self.station.element_state(index, element).is_none() {
+            return Err(PyIndexError::new_err(format!("there is no element {} in module {:?}", element, module)));
+        }
+        let taken = self.station.activate_element(index, element).is_some();
+        self.collect_events();
+        Ok(taken)
+    }
+
+    // Takes the events raised since the last call
+    fn events(&mut self, py: Python<'_>) -> PyResult<PyObject> {
+        to_python(py, &std::mem::take(&mut self.events))
+    }
+
+    fn tunables(&self) -> Vec<(&'static str, f32)> {
+        self.tunables.fields()
+    }
+
+    fn set_tunable(&mut self, name: &str, value: f32) -> PyResult<()> {
+        set_tunable(&mut self.tunables, name, value)?;
+        self.station.set_tunables(&self.tunables);
+        Ok(())
+    }
+
+    // Runs on from the current state and returns the headless report
+    #[pyo3(signature = (ticks, sample_every = 60))]
+    fn simulate(&mut self, py: Python<'_>, ticks: u64, sample_every: u64) -> PyResult<PyObject> {
+        let report = headless::simulate(&mut self.station, ticks, sample_every);
+        self.tick += ticks;
+        to_python(py, &report)
+    }
+}
+
+// One whole headless run, as `--headless` would do it. `damage` is a list of
+// (module id, amount) pairs applied before the first tick.
+#[pyfunction]
+#[pyo3(signature = (ticks, sample_every = 60, seed = 0, damage = None, tunables = None, save = None))]
+fn simulate(
+    py: Python<'_>,
+    ticks: u64,
+    sample_every: u64,
+    seed: u64,
+    damage: Option<Vec<(PyModuleId, f32)>>,
+    tunables: Option<&Bound<'_, PyDict>>,
+    save: Option<PathBuf>,
+) -> PyResult<PyObject> {
+    let mut station = seeded_station(seed, save, &tunables_from(tunables)?)?;
+    for (module, amount) in damage.unwrap_or_default() {
+        let module = module_index(&station, module)?;
+        station.damage_module(module, amount);
+    }
+    to_python(py, &headless::simulate(&mut station, ticks, sample_every))
+}
+
+// Plays the named drill from a scenario file with the player standing at
+// `player` throughout, and returns the scored record
+#[pyfunction]
+#[pyo3(signature = (path, name, seed = 0, player = (0.0, 0.0, 0.0), tunables = None))]
+fn run_drill(
+    py: Python<'_>,
+    path: PathBuf,
+    name: &str,
+    seed: u64,
+    player: (f32, f32, f32),
+    tunables: Option<&Bound<'_, PyDict>>,
+) -> PyResult<PyObject> {
+    let scenario = drill::load_scenarios(&path)?
+        .into_iter()
+        .find(|scenario| scenario.name == name)
+        .ok_or_else(|| PyKeyError::new_err(format!("no drill named {} in {}", name, path.display())))?;
+    let mut station = seeded_station(seed, None, &tunables_from(tunables)?)?;
+    let player = Vec3::from(player);
+    let mut drill = Drill::start(scenario, &station);
+    while !drill.update(&mut station, player, STEP_DT) {
+        station.update(STEP_DT);
+        station.drain_events();
+    }
+    to_python(py, &drill.finish(&station).record)
+}
+
+#[pymodule]
+fn space_station_3d(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    module.add_class::<PyStation>()?;
+    module.add_function(wrap_pyfunction!(simulate, module)?)?;
+    module.add_function(wrap_pyfunction!(run_drill, module)?)?;
+    module.add("TICK_SECONDS", STEP_DT)?;
+    Ok(())
+}
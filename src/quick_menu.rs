@@ -0,0 +1,157 @@
+use anyhow::{bail, Result};
+use glam::Vec2;
+use crate::chat::Emote;
+use crate::markers::MarkerKind;
+use crate::radial::RadialMenu;
+
+// More than this and the wedges get too thin to hit with a flick
+pub const MAX_SLOTS: usize = 8;
+
+pub const DEFAULT_SLOTS: [&str; 8] = [
+    "next_tool",
+    "use_tool",
+    "marker:leak",
+    "marker:repair",
+    "remove_marker",
+    "toggle_map",
+    "journal",
+    "helmet_light",
+];
+
+// Something the quick menu can do. Slots name them in config.toml.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum QuickAction {
+    NextTool,
+    PreviousTool,
+    UseTool,
+    PlaceMarker(MarkerKind),
+    RemoveMarker,
+    ToggleMap,
+    Journal,
+    HelmetLight,
+    CallDrone,
+    Emote(Emote),
+}
+
+impl QuickAction {
+    // `marker:<kind>` places that kind of marker, `emote:<id>` plays that emote
+    pub fn from_name(name: &str) -> Option<Self> {
+        if let Some(kind) = name.strip_prefix("marker:") {
+            return MarkerKind::from_id(kind).map(QuickAction::PlaceMarker);
+        }
+        if let Some(id) = name.strip_prefix("emote:") {
+            return Emote::parse(id).map(QuickAction::Emote);
+        }
+        match name {
+            "next_tool" => Some(QuickAction::NextTool),
+            "previous_tool" => Some(QuickAction::PreviousTool),
+            "use_tool" => Some(QuickAction::UseTool),
+            "remove_marker" => Some(QuickAction::RemoveMarker),
+            "toggle_map" => Some(QuickAction::ToggleMap),
+            "journal" => Some(QuickAction::Journal),
+            "helmet_light" => Some(QuickAction::HelmetLight),
+            "call_drone" => Some(QuickAction::CallDrone),
+            _ => None,
+        }
+    }
+
+    // UI string ID for its wedge
+    pub fn label_id(self) -> String {
+        match self {
+            QuickAction::PlaceMarker(kind) => format!("marker.{}", kind.id()),
+            QuickAction::Emote(emote) => format!("emote.{}", emote.id()),
+            QuickAction::NextTool => "quick.next_tool".to_string(),
+            QuickAction::PreviousTool => "quick.previous_tool".to_string(),
+            QuickAction::UseTool => "quick.use_tool".to_string(),
+            QuickAction::RemoveMarker => "quick.remove_marker".to_string(),
+            QuickAction::ToggleMap => "quick.toggle_map".to_string(),
+            QuickAction::Journal => "quick.journal".to_string(),
+            QuickAction::HelmetLight => "quick.helmet_light".to_string(),
+            QuickAction::CallDrone => "quick.call_drone".to_string(),
+        }
+    }
+}
+
+pub fn parse_slots(names: &[String]) -> Result<Vec<QuickAction>> {
+    if names.len() > MAX_SLOTS {
+        bail!("the quick menu has room for {} slots, not {}", MAX_SLOTS, names.len());
+    }
+    names
+        .iter()
+        .map(|name| match QuickAction::from_name(name) {
+            Some(action) => Ok(action),
+            None => bail!("unknown quick menu action '{}'", name),
+        })
+        .collect()
+}
+
+// Hold-to-open wheel of quick actions, steered by the mouse or right stick.
+// Letting go over a wedge runs it; letting go in the middle does nothing.
+#[derive(Debug)]
+pub struct QuickMenu {
+    slots: Vec<QuickAction>,
+    pub wheel: RadialMenu,
+}
+
+impl QuickMenu {
+    pub fn new(slots: Vec<QuickAction>) -> Self {
+        Self {
+            wheel: RadialMenu::new(slots.len()),
+            slots,
+        }
+    }
+
+    pub fn slots(&self) -> &[QuickAction] {
+        &self.slots
+    }
+
+    // Takes effect the next time the menu opens
+    pub fn set_slots(&mut self, slots: Vec<QuickAction>) {
+        self.slots = slots;
+    }
+
+    // Call every frame with whether the open button is held
+    pub fn update(&mut self, held: bool, mouse_delta: Vec2, stick: Vec2) -> Option<QuickAction> {
+        if held && !self.wheel.open {
+            self.wheel = RadialMenu::new(self.slots.len());
+            self.wheel.open();
+        } else if !held && self.wheel.open {
+            return self.wheel.close().and_then(|slot| self.slots.get(slot).copied());
+        }
+        self.wheel.update(mouse_delta);
+        self.wheel.steer(stick);
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_slots_parse_and_include_the_helmet_light() {
+        let names: Vec<String> = DEFAULT_SLOTS.iter().map(|slot| slot.to_string()).collect();
+        let slots = parse_slots(&names).unwrap();
+        assert_eq!(slots.len(), DEFAULT_SLOTS.len());
+        assert!(slots.contains(&QuickAction::HelmetLight));
+    }
+
+    #[test]
+    fn emote_slots_name_their_emote() {
+        assert_eq!(QuickAction::from_name("emote:wave"), Some(QuickAction::Emote(Emote::Wave)));
+        assert_eq!(QuickAction::Emote(Emote::ThumbsUp).label_id(), "emote.thumbs_up");
+        assert_eq!(QuickAction::from_name("emote:dance"), None);
+    }
+
+    #[test]
+    fn the_drone_can_be_called_from_a_slot() {
+        assert_eq!(parse_slots(&["call_drone".to_string()]).unwrap(), vec![QuickAction::CallDrone]);
+        assert_eq!(QuickAction::CallDrone.label_id(), "quick.call_drone");
+    }
+
+    #[test]
+    fn unknown_and_excess_slots_are_rejected() {
+        assert!(parse_slots(&["call_taxi".to_string()]).is_err());
+        assert!(parse_slots(&vec!["journal".to_string(); MAX_SLOTS + 1]).is_err());
+    }
+}
@@ -0,0 +1,77 @@
+use std::f32::consts::TAU;
+use glam::Vec2;
+
+// How far the virtual cursor has to move before anything is selected
+const DEADZONE: f32 = 20.0;
+// And how far it can go, so swinging back doesn't take forever
+const MAX_RADIUS: f32 = 80.0;
+// Stick deflection, 0..1, that counts as pointing somewhere
+const STICK_DEADZONE: f32 = 0.5;
+
+// Hold-to-open wheel of choices, picked by moving the mouse toward one.
+// Item 0 is at the top and the rest follow clockwise. The cursor is virtual
+// since the real one stays locked for mouse look; a gamepad stick can drive
+// it too.
+#[derive(Debug)]
+pub struct RadialMenu {
+    pub open: bool,
+    items: usize,
+    cursor: Vec2,
+}
+
+impl RadialMenu {
+    pub fn new(items: usize) -> Self {
+        Self {
+            open: false,
+            items,
+            cursor: Vec2::ZERO,
+        }
+    }
+
+    pub fn open(&mut self) {
+        self.open = true;
+        self.cursor = Vec2::ZERO;
+    }
+
+    // The highlighted item, if any; releasing in the middle cancels
+    pub fn close(&mut self) -> Option<usize> {
+        let selected = self.selected();
+        self.open = false;
+        selected
+    }
+
+    // Mouse movement in screen pixels (y down)
+    pub fn update(&mut self, mouse_delta: Vec2) {
+        if self.open {
+            self.cursor = (self.cursor + mouse_delta).clamp_length_max(MAX_RADIUS);
+        }
+    }
+
+    // Stick position, -1..1 per axis (y down). Pointing it sets the cursor
+    // outright; letting it spring back keeps the last choice.
+    pub fn steer(&mut self, stick: Vec2) {
+        if self.open && stick.length() > STICK_DEADZONE {
+            self.cursor = stick.clamp_length_max(1.0) * MAX_RADIUS;
+        }
+    }
+
+    pub fn selected(&self) -> Option<usize> {
+        if !self.open || self.items == 0 || self.cursor.length() < DEADZONE {
+            return None;
+        }
+        // Clockwise from straight up
+        let angle = self.cursor.x.atan2(-self.cursor.y).rem_euclid(TAU);
+        let sector = TAU / self.items as f32;
+        Some((angle / sector).round() as usize % self.items)
+    }
+
+    // Unit vector from the centre toward an item, in screen space
+    pub fn direction(&self, item: usize) -> Vec2 {
+        let angle = item as f32 * TAU / self.items.max(1) as f32;
+        Vec2::new(angle.sin(), -angle.cos())
+    }
+
+    pub fn cursor(&self) -> Vec2 {
+        self.cursor
+    }
+}
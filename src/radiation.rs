@@ -0,0 +1,71 @@
+use crate::emi::Interference;
+use crate::tunables::StationTunables;
+
+// Doses are in mSv and rates in mSv per second. A dosimeter reads amber past
+// the first of these and red past the second.
+pub const DOSE_WARNING: f32 = 10.0;
+pub const DOSE_DANGER: f32 = 50.0;
+// A rate worth getting out of; a strong flare puts a few times this through
+// the walls of an ordinary module
+pub const RATE_WARNING: f32 = 0.02;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DoseLevel {
+    Normal,
+    Warning,
+    Danger,
+}
+
+impl DoseLevel {
+    pub fn of(dose: f32) -> Self {
+        if dose >= DOSE_DANGER {
+            DoseLevel::Danger
+        } else if dose >= DOSE_WARNING {
+            DoseLevel::Warning
+        } else {
+            DoseLevel::Normal
+        }
+    }
+}
+
+// Outside the hull: the steady background from cosmic rays, with a flare's
+// protons on top for as long as it lasts
+pub fn exterior_rate(tunables: &StationTunables, interference: &Interference) -> f32 {
+    tunables.radiation_background + tunables.flare_radiation * interference.level()
+}
+
+// Share of the outside rate that gets through `shielding`; each unit of it
+// halves what's left
+pub fn transmission(shielding: f32) -> f32 {
+    0.5f32.powf(shielding.max(0.0))
+}
+
+// A personal dosimeter: what it's soaked up so far and the rate it read last
+#[derive(Debug, Clone, Default)]
+pub struct Dosimeter {
+    dose: f32,
+    rate: f32,
+}
+
+impl Dosimeter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&mut self, rate: f32, delta_time: f32) {
+        self.rate = rate.max(0.0);
+        self.dose += self.rate * delta_time;
+    }
+
+    pub fn dose(&self) -> f32 {
+        self.dose
+    }
+
+    pub fn rate(&self) -> f32 {
+        self.rate
+    }
+
+    pub fn level(&self) -> DoseLevel {
+        DoseLevel::of(self.dose)
+    }
+}
@@ -0,0 +1,353 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use glam::Vec3;
+use crate::door;
+use crate::module_id::ModuleId;
+use crate::station::{Activation, ElementState, SpaceStation};
+
+// How far from an element a player can work it, as the game allows, and the
+// slack the server gives on top for where it thinks they were
+pub const REACH: f32 = 2.5;
+const REACH_SLACK: f32 = 0.5;
+// Ticks of positions kept to rewind to; a request older than this is judged
+// from the oldest there is
+pub const REWIND_TICKS: u64 = 30;
+// A wrong guess is put right over this long rather than snapped
+pub const CORRECTION_SECONDS: f32 = 0.25;
+
+// Why the server turned a request down
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rejection {
+    NoSuchElement,
+    OutOfReach,
+    // The element needs a clearance the client hasn't got
+    Clearance,
+    Unpowered,
+    Locked,
+    // Mid-switch, cooling down or broken
+    Busy,
+    // Someone else got to the same element first
+    Conflict,
+}
+
+// A client pressing use on an element, stamped with the tick it saw and the
+// state it saw the element in. The module goes by id, so one taken off the
+// station in between can't send the request to whatever's renumbered into
+// its place.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InteractionRequest {
+    pub client: String,
+    pub sequence: u32,
+    pub tick: u64,
+    pub module: ModuleId,
+    pub element: usize,
+    pub seen: ElementState,
+}
+
+// The server's answer, with the element's state as it now stands for the
+// client to settle on
+#[derive(Debug, Clone, PartialEq)]
+pub struct Verdict {
+    pub client: String,
+    pub sequence: u32,
+    pub module: ModuleId,
+    pub element: usize,
+    pub result: Result<(), Rejection>,
+    pub state: Option<ElementState>,
+}
+
+// The server's side: where each client has been lately, the clearances
+// they hold, and the requests in since the last resolve
+#[derive(Debug, Default)]
+pub struct Arbiter {
+    positions: BTreeMap<String, VecDeque<(u64, Vec3)>>,
+    clearances: BTreeMap<String, BTreeSet<String>>,
+    requests: Vec<InteractionRequest>,
+}
+
+impl Arbiter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_position(&mut self, client: &str, tick: u64, position: Vec3) {
+        let history = self.positions.entry(client.to_string()).or_default();
+        history.push_back((tick, position));
+        while history.front().is_some_and(|&(old, _)| old + REWIND_TICKS < tick) {
+            history.pop_front();
+        }
+    }
+
+    // Where the client was at `tick`, as near as the history goes
+    pub fn position_at(&self, client: &str, tick: u64) -> Option<Vec3> {
+        let history = self.positions.get(client)?;
+        history.iter().rev().find(|&&(at, _)| at <= tick).or(history.front()).map(|&(_, position)| position)
+    }
+
+    // As from the datapads the client's read
+    pub fn grant(&mut self, client: &str, clearance: &str) {
+        self.clearances.entry(client.to_string()).or_default().insert(clearance.to_string());
+    }
+
+    pub fn submit(&mut self, request: InteractionRequest) {
+        self.requests.push(request);
+    }
+
+    // Settles everything in since last time, oldest tick first, then by
+    // client name and sequence, so the same requests always come out the
+    // same way. An element only switches once a resolve: anyone else who
+    // tried it in the same batch was going on the state it's just left.
+    pub fn resolve(&mut self, station: &mut SpaceStation) -> Vec<Verdict> {
+        let mut requests = std::mem::take(&mut self.requests);
+        requests.sort_by(|a, b| (a.tick, &a.client, a.sequence).cmp(&(b.tick, &b.client, b.sequence)));
+        let mut switched = BTreeSet::new();
+        requests
+            .into_iter()
+            .map(|request| {
+                let key = (request.module, request.element);
+                let index = station.module_index(request.module);
+                let result = match index {
+                    _ if switched.contains(&key) => Err(Rejection::Conflict),
+                    Some(index) => self.check(station, index, &request),
+                    None => Err(Rejection::NoSuchElement),
+                };
+                let result = result.and_then(|()| {
+                    // A damaged module can still fail the switch
+                    let index = index.ok_or(Rejection::NoSuchElement)?;
+                    station.activate_element(index, request.element).map(|_| ()).ok_or(Rejection::Busy)
+                });
+                if result.is_ok() {
+                    switched.insert(key);
+                }
+                Verdict {
+                    state: index.and_then(|index| station.element_state(index, request.element)),
+                    client: request.client,
+                    sequence: request.sequence,
+                    module: request.module,
+                    element: request.element,
+                    result,
+                }
+            })
+            .collect()
+    }
+
+    // Whether the request would be taken, judged from where the client was
+    // when they pressed
+    fn check(&self, station: &SpaceStation, index: usize, request: &InteractionRequest) -> Result<(), Rejection> {
+        let module = station.modules().get(index).ok_or(Rejection::NoSuchElement)?;
+        let element = module.interactive_elements.get(request.element).ok_or(Rejection::NoSuchElement)?;
+        let position = station.element_position(index, request.element).ok_or(Rejection::NoSuchElement)?;
+        let reach = self
+            .position_at(&request.client, request.tick)
+            .is_some_and(|player| player.distance(position) <= REACH + REACH_SLACK);
+        if !reach {
+            return Err(Rejection::OutOfReach);
+        }
+        if let Some(needed) = &element.clearance {
+            if !self.clearances.get(&request.client).is_some_and(|held| held.contains(needed)) {
+                return Err(Rejection::Clearance);
+            }
+        }
+        if !module.powered && element.element_type.needs_power() {
+            return Err(Rejection::Unpowered);
+        }
+        match element.state {
+            ElementState::Locked => Err(Rejection::Locked),
+            // Someone switched it since they looked, and this would only
+            // switch it back
+            ElementState::Inactive | ElementState::Active if element.state != request.seen => Err(Rejection::Conflict),
+            ElementState::Inactive | ElementState::Active if element.cooldown <= 0.0 => Ok(()),
+            _ => Err(Rejection::Busy),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Prediction {
+    sequence: u32,
+    module: ModuleId,
+    element: usize,
+    state: ElementState,
+}
+
+// The client's side: what it's guessed will happen before the server says,
+// and doors eased between the guess and the answer
+#[derive(Debug, Default)]
+pub struct Predictor {
+    client: String,
+    next_sequence: u32,
+    pending: Vec<Prediction>,
+    // Door openness as shown, by (module, element)
+    shown: BTreeMap<(ModuleId, usize), f32>,
+}
+
+impl Predictor {
+    pub fn new(client: &str) -> Self {
+        Self { client: client.to_string(), ..Self::default() }
+    }
+
+    // Switches the element in the client's eyes straight away, if it looks
+    // like it'd be taken, and returns the request to send. Modules are by
+    // index here, as the client sees the station now.
+    pub fn predict(&mut self, station: &SpaceStation, tick: u64, module: usize, element: usize) -> Option<InteractionRequest> {
+        let current = self.state(station, module, element)?;
+        let activation = station.modules()[module].interactive_elements[element].element_type.activation();
+        let module = station.module_id(module)?;
+        let state = match (current, activation) {
+            (_, Activation::Passive) => return None,
+            (ElementState::Inactive, Activation::Momentary) => ElementState::Inactive,
+            (ElementState::Inactive, _) => ElementState::Active,
+            (ElementState::Active, _) => ElementState::Inactive,
+            _ => return None,
+        };
+        self.next_sequence += 1;
+        let sequence = self.next_sequence;
+        self.pending.push(Prediction { sequence, module, element, state });
+        Some(InteractionRequest { client: self.client.clone(), sequence, tick, module, element, seen: current })
+    }
+
+    // The element as the client should show it: its latest guess, or what
+    // the server last said
+    pub fn state(&self, station: &SpaceStation, module: usize, element: usize) -> Option<ElementState> {
+        let id = station.module_id(module)?;
+        let guess = self.pending.iter().rev().find(|p| (p.module, p.element) == (id, element));
+        guess.map(|p| p.state).or_else(|| station.element_state(module, element))
+    }
+
+    pub fn pending(&self) -> usize {
+        self.pending.len()
+    }
+
+    // Drops the guess the verdict answers; a rejection is returned for the
+    // game to say why. The element falls back to the server's state, which
+    // `door_openness` eases it to.
+    pub fn reconcile(&mut self, verdict: &Verdict) -> Option<Rejection> {
+        if verdict.client != self.client {
+            return None;
+        }
+        self.pending.retain(|p| p.sequence != verdict.sequence);
+        verdict.result.err()
+    }
+
+    // How far open to draw a door, moved toward what it should be at no
+    // more than a full swing per CORRECTION_SECONDS
+    pub fn door_openness(&mut self, station: &SpaceStation, module: usize, element: usize, delta_time: f32) -> f32 {
+        let Some(module) = station.modules().get(module) else {
+            return 0.0;
+        };
+        let Some(door) = module.interactive_elements.get(element) else {
+            return 0.0;
+        };
+        let target = match self.pending.iter().rev().find(|p| (p.module, p.element) == (module.id, element)) {
+            Some(guess) if guess.state == ElementState::Active => 1.0,
+            Some(_) => 0.0,
+            None => door::openness(door),
+        };
+        let shown = self.shown.entry((module.id, element)).or_insert(target);
+        let step = delta_time / CORRECTION_SECONDS;
+        *shown += (target - *shown).clamp(-step, step);
+        *shown
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::station::InteractionType;
+
+    #[test]
+    fn racing_interactions_settle_the_same_way_and_wrong_guesses_ease_back() {
+        let mut station = SpaceStation::create_default_layout();
+        let door = station.door_between(0, 1).unwrap();
+        let (module, element) = (station.door_modules(door).unwrap().0, station.doors()[door].element);
+        let handle = station.element_position(module, element).unwrap();
+        station.set_element_state(module, element, ElementState::Inactive);
+        let mut arbiter = Arbiter::new();
+        // Ben was at the door a few ticks ago but has walked off since
+        for tick in 0..=10 {
+            arbiter.record_position("ana", tick, handle + Vec3::X);
+            let ben = if tick < 5 { handle } else { handle + Vec3::Z * 10.0 };
+            arbiter.record_position("ben", tick, ben);
+        }
+        let mut ana = Predictor::new("ana");
+        let mut ben = Predictor::new("ben");
+        let from_ana = ana.predict(&station, 8, module, element).unwrap();
+        let from_ben = ben.predict(&station, 3, module, element).unwrap();
+        assert_eq!(ana.state(&station, module, element), Some(ElementState::Active));
+        assert_eq!(ana.door_openness(&station, module, element, 0.0), 1.0);
+
+        // Both open the same door; Ben pressed first, however the requests
+        // arrive, and was in reach when he did
+        arbiter.submit(from_ana.clone());
+        arbiter.submit(from_ben.clone());
+        let verdicts = arbiter.resolve(&mut station);
+        assert_eq!(verdicts[0].client, "ben");
+        assert_eq!(verdicts[0].result, Ok(()));
+        assert_eq!(verdicts[1].result, Err(Rejection::Conflict));
+        assert_eq!(ben.reconcile(&verdicts[1]), None);
+        assert_eq!(ben.reconcile(&verdicts[0]), None);
+        assert_eq!(ana.reconcile(&verdicts[1]), Some(Rejection::Conflict));
+        assert_eq!(ana.pending(), 0);
+        assert!(matches!(verdicts[0].state, Some(ElementState::Transitioning(_))));
+
+        // Out of reach now, Ben's guess at shutting it is undone over a
+        // quarter second rather than in a frame
+        station.update(2.0);
+        let shut = ben.predict(&station, 10, module, element).unwrap();
+        assert_eq!(shut.seen, ElementState::Active);
+        assert_eq!(ben.door_openness(&station, module, element, 0.25), 0.0);
+        arbiter.submit(shut);
+        let verdict = arbiter.resolve(&mut station).remove(0);
+        assert_eq!(ben.reconcile(&verdict), Some(Rejection::OutOfReach));
+        assert_eq!(station.element_state(module, element), Some(ElementState::Active));
+        let eased = ben.door_openness(&station, module, element, CORRECTION_SECONDS * 0.5);
+        assert!((eased - 0.5).abs() < 1e-5);
+        assert_eq!(ben.door_openness(&station, module, element, 1.0), 1.0);
+
+        // A stale request from before someone else switched it is refused
+        // rather than switching it back
+        let stale = InteractionRequest { seen: ElementState::Inactive, sequence: 9, ..from_ana };
+        arbiter.submit(stale);
+        assert_eq!(arbiter.resolve(&mut station)[0].result, Err(Rejection::Conflict));
+        station.set_door_locked(station.door_between(0, 1).unwrap(), true);
+        arbiter.submit(InteractionRequest { seen: ElementState::Locked, sequence: 10, ..from_ben });
+        assert_eq!(arbiter.resolve(&mut station)[0].result, Err(Rejection::Locked));
+    }
+
+    #[test]
+    fn restricted_elements_need_the_clients_clearance_and_requests_follow_their_module() {
+        let mut station = SpaceStation::create_default_layout();
+        let storage = 7;
+        let access = station.modules()[storage]
+            .interactive_elements
+            .iter()
+            .position(|element| element.element_type == InteractionType::StorageAccess)
+            .unwrap();
+        assert_eq!(station.restrict(storage, InteractionType::StorageAccess, "storage"), 1);
+        let at = station.element_position(storage, access).unwrap();
+        let mut arbiter = Arbiter::new();
+        arbiter.record_position("ana", 0, at);
+        let mut ana = Predictor::new("ana");
+
+        // In reach and powered, but without the clearance
+        arbiter.submit(ana.predict(&station, 0, storage, access).unwrap());
+        let verdict = arbiter.resolve(&mut station).remove(0);
+        assert_eq!(ana.reconcile(&verdict), Some(Rejection::Clearance));
+
+        // With it, the request still finds the storage module after the
+        // quarters ahead of it in the list are taken off
+        arbiter.grant("ana", "storage");
+        let request = ana.predict(&station, 0, storage, access).unwrap();
+        station.remove_module(6).unwrap();
+        let moved = station.module_index(request.module).unwrap();
+        assert_eq!(moved, storage - 1);
+        arbiter.submit(request.clone());
+        let verdict = arbiter.resolve(&mut station).remove(0);
+        assert_eq!(verdict.result, Ok(()));
+        assert_eq!(verdict.module, station.module_id(moved).unwrap());
+
+        // And goes nowhere once it's gone
+        station.remove_module(moved).unwrap();
+        arbiter.submit(InteractionRequest { sequence: 9, ..request });
+        assert_eq!(arbiter.resolve(&mut station)[0].result, Err(Rejection::NoSuchElement));
+    }
+}
@@ -0,0 +1,169 @@
+use glam::Vec3;
+use crate::inventory::{Inventory, ItemKind};
+use crate::station::{ElementState, SpaceStation};
+
+// How much condition one part puts back
+pub const REPAIR_AMOUNT: f32 = 0.25;
+// Past this much wear a component is worth the time to fix
+const WORN: f32 = 0.999;
+
+// The parts of a module that wear out. The hull is the module's
+// structural_integrity; the other two have their own condition.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Component {
+    // Breaches under the threshold, and leaks slowly before that
+    Hull,
+    // Worn wiring wastes power, so the module draws more
+    Wiring,
+    // Scrubbers and fans; worn hardware cleans less air
+    LifeSupport,
+}
+
+impl Component {
+    pub const ALL: [Component; 3] = [Component::Hull, Component::Wiring, Component::LifeSupport];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Component::Hull => "hull",
+            Component::Wiring => "wiring",
+            Component::LifeSupport => "life support",
+        }
+    }
+
+    // What gets used up fixing it
+    pub fn part(self) -> ItemKind {
+        match self {
+            Component::Hull => ItemKind::RepairKit,
+            Component::Wiring | Component::LifeSupport => ItemKind::SpareParts,
+        }
+    }
+
+    // Hands-on time for one part's worth of repair
+    pub fn repair_seconds(self) -> f32 {
+        match self {
+            Component::Hull => 6.0,
+            Component::Wiring => 4.0,
+            Component::LifeSupport => 5.0,
+        }
+    }
+}
+
+// A repair under way: the player has to stay in the module until it's done,
+// and the part is only used up once it is
+#[derive(Debug)]
+pub struct RepairJob {
+    pub module: usize,
+    pub component: Component,
+    elapsed: f32,
+}
+
+impl RepairJob {
+    // Starts on the most worn component in the nearest module that the
+    // selected item is a part for. None when the item isn't a repair part,
+    // or is spare parts a failed element in reach wants instead.
+    pub fn start(station: &SpaceStation, inventory: &Inventory, player: Vec3, reach: f32) -> Option<Result<Self, String>> {
+        let kind = inventory.selected_item()?.kind;
+        if !Component::ALL.iter().any(|component| component.part() == kind) {
+            return None;
+        }
+        let failed_nearby = station
+            .nearest_element(player, reach)
+            .is_some_and(|(module, element)| station.element_state(module, element) == Some(ElementState::Malfunction));
+        if kind == ItemKind::SpareParts && failed_nearby {
+            return None;
+        }
+        let Some(module) = station.nearest_module(player) else {
+            return Some(Err("There's nothing here to repair".to_string()));
+        };
+        let worst = Component::ALL
+            .into_iter()
+            .filter(|component| component.part() == kind)
+            .map(|component| (component, station.modules()[module].condition(component)))
+            .filter(|&(_, condition)| condition < WORN)
+            .min_by(|a, b| a.1.total_cmp(&b.1));
+        Some(match worst {
+            Some((component, _)) => Ok(Self {
+                module,
+                component,
+                elapsed: 0.0,
+            }),
+            None => Err(format!("Nothing in module {} needs {}", module, kind.name().to_lowercase())),
+        })
+    }
+
+    // 0 to 1
+    pub fn progress(&self) -> f32 {
+        (self.elapsed / self.component.repair_seconds()).min(1.0)
+    }
+
+    pub fn describe(&self) -> String {
+        format!("Repairing {} in module {}", self.component.name(), self.module)
+    }
+
+    // Works on the repair. Some once the job is over, finished or abandoned,
+    // saying which; leaving the module or losing the part abandons it.
+    pub fn update(
+        &mut self,
+        station: &mut SpaceStation,
+        inventory: &mut Inventory,
+        player: Vec3,
+        delta_time: f32,
+    ) -> Option<String> {
+        if station.nearest_module(player) != Some(self.module) {
+            return Some(format!("Left the {} repair in module {} unfinished", self.component.name(), self.module));
+        }
+        let part = self.component.part();
+        if inventory.count(part) == 0 {
+            return Some(format!("Out of {} for the {} repair", part.name().to_lowercase(), self.component.name()));
+        }
+        self.elapsed += delta_time;
+        if self.progress() < 1.0 {
+            return None;
+        }
+        inventory.take(part);
+        station.repair_component(self.module, self.component, REPAIR_AMOUNT);
+        let condition = station.modules()[self.module].condition(self.component);
+        Some(format!(
+            "Repaired {} in module {} to {:.0}%",
+            self.component.name(),
+            self.module,
+            condition * 100.0
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repairs_take_time_and_use_a_part() {
+        use crate::inventory::{Inventory, ItemKind};
+        use {Component, RepairJob};
+
+        let mut station = SpaceStation::create_default_layout();
+        station.module_mut(3).unwrap().wiring = 0.5;
+        let player = station.modules()[3].transform.position;
+        let mut inventory = Inventory::new();
+        inventory.add(ItemKind::SpareParts, 2);
+        let mut job = RepairJob::start(&station, &inventory, player, 0.0).unwrap().unwrap();
+        assert_eq!((job.module, job.component), (3, Component::Wiring));
+
+        assert_eq!(job.update(&mut station, &mut inventory, player, 2.0), None);
+        assert_eq!(inventory.count(ItemKind::SpareParts), 2);
+        assert!(job.update(&mut station, &mut inventory, player, 2.0).is_some());
+        assert_eq!(inventory.count(ItemKind::SpareParts), 1);
+        assert!((station.modules()[3].wiring - 0.75).abs() < 1e-5);
+
+        // Walking off abandons it without using the part
+        let mut job = RepairJob::start(&station, &inventory, player, 0.0).unwrap().unwrap();
+        let away = station.modules()[0].transform.position;
+        assert!(job.update(&mut station, &mut inventory, away, 1.0).is_some());
+        assert_eq!(inventory.count(ItemKind::SpareParts), 1);
+
+        // Nothing worn that a repair kit fixes
+        inventory.add(ItemKind::RepairKit, 1);
+        inventory.select(1);
+        assert!(RepairJob::start(&station, &inventory, player, 0.0).unwrap().is_err());
+    }
+}
@@ -0,0 +1,477 @@
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use anyhow::{bail, Context, Result};
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use crate::avatar::AvatarLook;
+use crate::module_id::ModuleId;
+use crate::station::{ModuleSnapshot, StationSnapshot};
+use crate::stepping::STEP_DT;
+
+// Packets go out every this many ticks, 20 a second
+pub const SEND_INTERVAL: u64 = 3;
+// Every client gets a whole snapshot at least this often, so one that's
+// been losing acks isn't built on forever
+pub const KEYFRAME_TICKS: u64 = 300;
+// Modules this close to a client are sent to it, in metres
+pub const DEFAULT_RADIUS: f32 = 25.0;
+// Below this hull a module's an alert and goes to everyone
+const ALERT_INTEGRITY: f32 = 0.5;
+// Sent states kept waiting for an ack; older ones can't be a baseline
+const PENDING: usize = 64;
+
+// What a client wants to hear about: the modules around where it is. Modules
+// with an alert on (breached, burning, badly damaged, the storm shelter) and
+// the station-wide readings go to every client whatever.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Interest {
+    pub position: Vec3,
+    pub radius: f32,
+}
+
+impl Interest {
+    pub fn new(position: Vec3) -> Self {
+        Self { position, radius: DEFAULT_RADIUS }
+    }
+
+    // Station indices of the modules to send
+    pub fn modules(&self, snapshot: &StationSnapshot) -> BTreeSet<usize> {
+        let distance = |module: &ModuleSnapshot| Vec3::from(module.position).distance(self.position);
+        let mut modules: BTreeSet<usize> = alerting(snapshot).collect();
+        modules.extend((0..snapshot.modules.len()).filter(|&i| distance(&snapshot.modules[i]) <= self.radius));
+        // The one it's in, however big
+        modules.extend((0..snapshot.modules.len()).min_by(|&a, &b| {
+            distance(&snapshot.modules[a]).total_cmp(&distance(&snapshot.modules[b]))
+        }));
+        modules
+    }
+}
+
+// Modules everyone needs to know about
+pub fn alerting(snapshot: &StationSnapshot) -> impl Iterator<Item = usize> + '_ {
+    snapshot.modules.iter().enumerate().filter_map(|(i, module)| {
+        let alert = !module.atmosphere_sealed
+            || module.structural_integrity < ALERT_INTEGRITY
+            || snapshot.fires.iter().any(|fire| Some(fire.module) == module.id)
+            || module.id.is_some_and(|id| snapshot.storm_shelter == Some(id));
+        alert.then_some(i)
+    })
+}
+
+// A module as a packet carries it: where it sits in the station's list and
+// what's changed since the baseline, or all of it if the client hasn't got it
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModuleDelta {
+    pub id: ModuleId,
+    pub index: usize,
+    pub fields: Value,
+}
+
+// One tick's update for one client. A keyframe has no baseline and carries
+// everything in its interest; otherwise it's the changes since the last
+// state the client acked.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Packet {
+    pub tick: u64,
+    pub baseline: Option<u64>,
+    // The station-wide readings, or what's changed in them
+    pub globals: Option<Value>,
+    pub modules: Vec<ModuleDelta>,
+    // Gone, or out of interest, since the baseline
+    pub dropped: Vec<ModuleId>,
+}
+
+impl Packet {
+    pub fn is_keyframe(&self) -> bool {
+        self.baseline.is_none()
+    }
+
+    // Size on the wire
+    pub fn bytes(&self) -> usize {
+        serde_json::to_vec(self).map_or(0, |bytes| bytes.len())
+    }
+}
+
+// What a client knows of the station at a tick: the readings (players'
+// avatar looks among them) and the modules in its interest, as JSON so
+// deltas can be taken field by field
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ReplicaState {
+    pub tick: u64,
+    pub globals: Value,
+    pub modules: BTreeMap<ModuleId, (usize, Value)>,
+}
+
+impl ReplicaState {
+    // The part of `snapshot` a client with `interest` gets
+    fn of(tick: u64, snapshot: &StationSnapshot, globals: &Value, interest: &Interest) -> Result<Self> {
+        let mut modules = BTreeMap::new();
+        for index in interest.modules(snapshot) {
+            let module = &snapshot.modules[index];
+            // Snapshots from before ids have none to key them by
+            let Some(id) = module.id else {
+                continue;
+            };
+            modules.insert(id, (index, serde_json::to_value(module).context("failed to serialize module")?));
+        }
+        Ok(Self { tick, globals: globals.clone(), modules })
+    }
+
+    // What to send to get from here to `next`
+    fn delta_to(&self, next: &ReplicaState) -> Packet {
+        let modules = next
+            .modules
+            .iter()
+            .filter_map(|(&id, (index, value))| match self.modules.get(&id) {
+                Some((before, base)) => {
+                    let fields = delta(base, value);
+                    (fields.is_some() || before != index).then(|| ModuleDelta {
+                        id,
+                        index: *index,
+                        fields: fields.unwrap_or_else(|| Value::Object(Map::new())),
+                    })
+                }
+                None => Some(ModuleDelta { id, index: *index, fields: value.clone() }),
+            })
+            .collect();
+        Packet {
+            tick: next.tick,
+            baseline: Some(self.tick),
+            globals: delta(&self.globals, &next.globals),
+            modules,
+            dropped: self.modules.keys().filter(|id| !next.modules.contains_key(id)).copied().collect(),
+        }
+    }
+
+    fn keyframe(&self) -> Packet {
+        Packet {
+            tick: self.tick,
+            baseline: None,
+            globals: Some(self.globals.clone()),
+            modules: self
+                .modules
+                .iter()
+                .map(|(&id, (index, value))| ModuleDelta { id, index: *index, fields: value.clone() })
+                .collect(),
+            dropped: Vec::new(),
+        }
+    }
+
+    // This state with `packet` applied over it; a keyframe replaces it
+    pub fn apply(&self, packet: &Packet) -> Result<Self> {
+        let mut next = match packet.baseline {
+            None => Self::default(),
+            Some(tick) if tick == self.tick => self.clone(),
+            Some(tick) => bail!("packet for tick {} builds on tick {}, not {}", packet.tick, tick, self.tick),
+        };
+        next.tick = packet.tick;
+        if let Some(globals) = &packet.globals {
+            patch(&mut next.globals, globals);
+        }
+        for id in &packet.dropped {
+            next.modules.remove(id);
+        }
+        for module in &packet.modules {
+            let (index, value) = next.modules.entry(module.id).or_insert((module.index, Value::Null));
+            *index = module.index;
+            patch(value, &module.fields);
+        }
+        Ok(next)
+    }
+
+    pub fn module(&self, id: ModuleId) -> Option<ModuleSnapshot> {
+        let (_, value) = self.modules.get(&id)?;
+        serde_json::from_value(value.clone()).ok()
+    }
+
+    pub fn avatar(&self, name: &str) -> Option<AvatarLook> {
+        serde_json::from_value(self.globals.get("avatars")?.get(name)?.clone()).ok()
+    }
+}
+
+// What changed from `base` to `now`. Objects, and lists that kept their
+// length, only carry the entries that changed (lists keyed by position);
+// anything else is sent whole. None if nothing did.
+fn delta(base: &Value, now: &Value) -> Option<Value> {
+    match (base, now) {
+        (Value::Object(a), Value::Object(b)) if a.keys().all(|key| b.contains_key(key)) => {
+            let changed: Map<String, Value> = b
+                .iter()
+                .filter_map(|(key, value)| match a.get(key) {
+                    Some(before) => delta(before, value).map(|change| (key.clone(), change)),
+                    None => Some((key.clone(), value.clone())),
+                })
+                .collect();
+            (!changed.is_empty()).then_some(Value::Object(changed))
+        }
+        (Value::Array(a), Value::Array(b)) if a.len() == b.len() => {
+            let changed: Map<String, Value> = a
+                .iter()
+                .zip(b)
+                .enumerate()
+                .filter_map(|(i, (before, value))| delta(before, value).map(|change| (i.to_string(), change)))
+                .collect();
+            // A list that's mostly changed is smaller sent whole
+            match changed.len() {
+                0 => None,
+                n if n * 2 > b.len() => Some(now.clone()),
+                _ => Some(Value::Object(changed)),
+            }
+        }
+        _ => (base != now).then(|| now.clone()),
+    }
+}
+
+// The other half of `delta`: what an object or list delta lands on says how
+// to read it
+fn patch(base: &mut Value, delta: &Value) {
+    match (base, delta) {
+        (Value::Object(a), Value::Object(b)) => {
+            for (key, change) in b {
+                patch(a.entry(key.clone()).or_insert(Value::Null), change);
+            }
+        }
+        (Value::Array(a), Value::Object(b)) => {
+            for (key, change) in b {
+                if let Some(entry) = key.parse::<usize>().ok().and_then(|i| a.get_mut(i)) {
+                    patch(entry, change);
+                }
+            }
+        }
+        (base, delta) => *base = delta.clone(),
+    }
+}
+
+// Bytes sent to one client, and what sending it whole snapshots would have
+// cost, over the last second
+#[derive(Debug, Clone, Default)]
+pub struct ClientMetrics {
+    pub packets: u64,
+    pub keyframes: u64,
+    pub bytes: u64,
+    pub modules: usize,
+    recent: VecDeque<(u64, usize, usize)>,
+}
+
+impl ClientMetrics {
+    fn record(&mut self, tick: u64, packet: &Packet, bytes: usize, naive: usize, modules: usize) {
+        self.packets += 1;
+        self.keyframes += packet.is_keyframe() as u64;
+        self.bytes += bytes as u64;
+        self.modules = modules;
+        self.recent.push_back((tick, bytes, naive));
+        let second = (1.0 / STEP_DT).round() as u64;
+        while self.recent.front().is_some_and(|&(sent, _, _)| sent + second <= tick) {
+            self.recent.pop_front();
+        }
+    }
+
+    pub fn bytes_per_second(&self) -> usize {
+        self.recent.iter().map(|&(_, bytes, _)| bytes).sum()
+    }
+
+    pub fn naive_bytes_per_second(&self) -> usize {
+        self.recent.iter().map(|&(_, _, naive)| naive).sum()
+    }
+}
+
+#[derive(Debug)]
+struct Client {
+    interest: Interest,
+    // The last state it acked, which deltas are taken against
+    acked: Option<ReplicaState>,
+    // Sent, waiting on an ack
+    pending: VecDeque<ReplicaState>,
+    last_keyframe: Option<u64>,
+    metrics: ClientMetrics,
+}
+
+// The server side of replication: which clients want what, and what each has
+// acked. It doesn't do the sending; whatever carries the packets hands the
+// acks back.
+#[derive(Debug, Default)]
+pub struct Replicator {
+    clients: BTreeMap<String, Client>,
+    // Every player's look, which goes to everyone
+    avatars: BTreeMap<String, AvatarLook>,
+    // The tick of the last update
+    last_tick: u64,
+}
+
+impl Replicator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // A client that's already there just moves
+    pub fn connect(&mut self, name: &str, interest: Interest) {
+        if let Some(client) = self.clients.get_mut(name) {
+            client.interest = interest;
+            return;
+        }
+        let client = Client {
+            interest,
+            acked: None,
+            pending: VecDeque::new(),
+            last_keyframe: None,
+            metrics: ClientMetrics::default(),
+        };
+        self.clients.insert(name.to_string(), client);
+    }
+
+    pub fn disconnect(&mut self, name: &str) -> bool {
+        self.clients.remove(name).is_some()
+    }
+
+    pub fn set_interest(&mut self, name: &str, interest: Interest) -> bool {
+        let Some(client) = self.clients.get_mut(name) else {
+            return false;
+        };
+        client.interest = interest;
+        true
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.clients.is_empty()
+    }
+
+    pub fn set_avatar(&mut self, name: &str, look: AvatarLook) {
+        self.avatars.insert(name.to_string(), look);
+    }
+
+    pub fn remove_avatar(&mut self, name: &str) -> bool {
+        self.avatars.remove(name).is_some()
+    }
+
+    pub fn avatar(&self, name: &str) -> Option<&AvatarLook> {
+        self.avatars.get(name)
+    }
+
+    pub fn clients(&self) -> impl Iterator<Item = (&str, &Interest, &ClientMetrics)> {
+        self.clients.iter().map(|(name, client)| (name.as_str(), &client.interest, &client.metrics))
+    }
+
+    // The client has `tick`; later sends build on it
+    pub fn ack(&mut self, name: &str, tick: u64) {
+        let Some(client) = self.clients.get_mut(name) else {
+            return;
+        };
+        while let Some(state) = client.pending.pop_front() {
+            if state.tick == tick {
+                client.acked = Some(state);
+                break;
+            }
+        }
+    }
+
+    // Each client's packet for `tick`, if a send tick has come round since
+    // the last call. The host calls it once a frame, so that may have been
+    // several ticks ago.
+    pub fn update(&mut self, tick: u64, snapshot: &StationSnapshot) -> Result<Vec<(String, Packet)>> {
+        let due = tick / SEND_INTERVAL > self.last_tick / SEND_INTERVAL;
+        self.last_tick = tick;
+        if self.clients.is_empty() || !due {
+            return Ok(Vec::new());
+        }
+        let mut globals = serde_json::to_value(snapshot).context("failed to serialize snapshot")?;
+        let avatars = serde_json::to_value(&self.avatars).context("failed to serialize avatars")?;
+        let naive = globals.to_string().len() + avatars.to_string().len();
+        if let Value::Object(fields) = &mut globals {
+            fields.remove("modules");
+            fields.insert("avatars".into(), avatars);
+        }
+        let mut packets = Vec::with_capacity(self.clients.len());
+        for (name, client) in &mut self.clients {
+            let state = ReplicaState::of(tick, snapshot, &globals, &client.interest)?;
+            let due = client.last_keyframe.is_none_or(|last| tick >= last + KEYFRAME_TICKS);
+            let packet = match &client.acked {
+                Some(acked) if !due => acked.delta_to(&state),
+                _ => {
+                    client.last_keyframe = Some(tick);
+                    state.keyframe()
+                }
+            };
+            client.metrics.record(tick, &packet, packet.bytes(), naive, state.modules.len());
+            if client.pending.len() == PENDING {
+                client.pending.pop_front();
+            }
+            client.pending.push_back(state);
+            packets.push((name.clone(), packet));
+        }
+        Ok(packets)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::station::SpaceStation;
+
+    #[test]
+    fn replication_sends_what_a_client_can_see_and_only_what_changed() {
+        let mut station = SpaceStation::create_default_layout();
+        let mut replicator = Replicator::new();
+        // Out by the airlock, with the command centre too far to bother with
+        let airlock = station.modules()[9].transform.position;
+        replicator.connect("eva", Interest { position: airlock, radius: 5.0 });
+        let mut client = ReplicaState::default();
+        let send = |replicator: &mut Replicator, station: &SpaceStation, tick: u64| {
+            let packets = replicator.update(tick, &station.snapshot()).unwrap();
+            let (name, packet) = packets.into_iter().next()?;
+            replicator.ack(&name, packet.tick);
+            Some(packet)
+        };
+        assert!(send(&mut replicator, &station, 1).is_none());
+        let keyframe = send(&mut replicator, &station, 3).unwrap();
+        assert!(keyframe.is_keyframe());
+        client = client.apply(&keyframe).unwrap();
+        let ids: Vec<_> = client.modules.values().map(|(index, _)| *index).collect();
+        assert_eq!(ids, vec![9]);
+        assert_eq!(client.module(station.modules()[9].id).unwrap(), ModuleSnapshot::from(&station.modules()[9]));
+
+        // A quiet tick's next to nothing against the last one acked
+        station.update(STEP_DT);
+        let quiet = send(&mut replicator, &station, 6).unwrap();
+        assert_eq!(quiet.baseline, Some(3));
+        assert!(quiet.bytes() * 4 < keyframe.bytes());
+        client = client.apply(&quiet).unwrap();
+
+        // A breach anywhere goes to everyone; the state the client builds
+        // matches the station's
+        station.damage_module(0, 1.0);
+        station.module_mut(9).unwrap().structural_integrity = 0.75;
+        let alert = send(&mut replicator, &station, 9).unwrap();
+        client = client.apply(&alert).unwrap();
+        assert!(client.modules.contains_key(&station.modules()[0].id));
+        for index in [0, 9] {
+            assert_eq!(client.module(station.modules()[index].id).unwrap(), ModuleSnapshot::from(&station.modules()[index]));
+        }
+        assert!(client.apply(&keyframe).is_ok());
+        assert!(client.apply(&quiet).is_err());
+
+        let (_, _, metrics) = replicator.clients().next().unwrap();
+        assert_eq!((metrics.packets, metrics.keyframes, metrics.modules), (3, 1, 2));
+        assert!(metrics.bytes_per_second() * 4 < metrics.naive_bytes_per_second());
+        // Whole again once it's been long enough
+        let again = send(&mut replicator, &station, 3 + KEYFRAME_TICKS).unwrap();
+        assert!(again.is_keyframe());
+        assert!(replicator.disconnect("eva") && replicator.is_empty());
+    }
+
+    #[test]
+    fn frames_of_several_ticks_send_once_a_send_tick_has_passed() {
+        let station = SpaceStation::create_default_layout();
+        let mut replicator = Replicator::new();
+        replicator.connect("fast_forward", Interest::new(Vec3::ZERO));
+        let snapshot = station.snapshot();
+        // Four ticks a frame never lands on some send ticks
+        let sent: Vec<u64> = (1..=5)
+            .map(|frame| frame * 4)
+            .filter(|&tick| !replicator.update(tick, &snapshot).unwrap().is_empty())
+            .collect();
+        assert_eq!(sent, vec![4, 8, 12, 16, 20]);
+        // And one tick at a time, only on send ticks
+        assert!(!replicator.update(21, &snapshot).unwrap().is_empty());
+        assert!(replicator.update(22, &snapshot).unwrap().is_empty());
+    }
+}
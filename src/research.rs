@@ -0,0 +1,520 @@
+use std::collections::BTreeSet;
+use std::fs;
+use std::path::Path;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use crate::crew::CREW_HOUR;
+use crate::event_log::LogSeverity;
+use crate::events::StationEvent;
+use crate::module_id::ModuleId;
+use crate::resources::Resource;
+use crate::station::{ElementState, InteractionType, ModuleRefit, ModuleType, SpaceStation};
+use crate::tunables::Tunables;
+
+// Which of the lab's two benches an experiment runs on
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Bench {
+    ResearchStation,
+    LabEquipment,
+}
+
+impl Bench {
+    pub fn element_type(self) -> InteractionType {
+        match self {
+            Bench::ResearchStation => InteractionType::ResearchStation,
+            Bench::LabEquipment => InteractionType::LabEquipment,
+        }
+    }
+}
+
+// An experiment as the research file describes it: crew hours of work at
+// the bench, kW it draws on top of the bench while it runs, and the points
+// it's worth
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExperimentDefinition {
+    pub id: String,
+    pub name: String,
+    pub bench: Bench,
+    pub hours: f32,
+    pub power: f32,
+    pub points: u32,
+}
+
+// What research points buy: one tunable scaled by `factor` for good. The
+// module type is the one it's fitted to, for the listing.
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpgradeDefinition {
+    pub id: String,
+    pub name: String,
+    pub module: ModuleType,
+    pub cost: u32,
+    pub tunable: String,
+    pub factor: f32,
+    // Another upgrade that has to be unlocked first
+    #[serde(default)]
+    pub requires: Option<String>,
+}
+
+// A refit one module at a time can be given, bought at a terminal in it
+// with research points and spare parts. Tiers go 1, 2, ... per module type,
+// each on top of the last, and scale the module's scrubbers, generation and
+// hull by these factors.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TierDefinition {
+    pub module: ModuleType,
+    pub tier: u8,
+    pub name: String,
+    pub points: u32,
+    pub parts: f32,
+    #[serde(default = "unchanged")]
+    pub scrubbing: f32,
+    #[serde(default = "unchanged")]
+    pub generation: f32,
+    #[serde(default = "unchanged")]
+    pub hull: f32,
+}
+
+fn unchanged() -> f32 {
+    1.0
+}
+
+impl TierDefinition {
+    // What a module refitted `from` ends up with
+    pub fn apply(&self, from: ModuleRefit) -> ModuleRefit {
+        ModuleRefit {
+            tier: self.tier,
+            scrubbing: from.scrubbing * self.scrubbing,
+            generation: from.generation * self.generation,
+            hull: from.hull * self.hull,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct ResearchFile {
+    #[serde(rename = "experiment", default)]
+    experiments: Vec<ExperimentDefinition>,
+    #[serde(rename = "upgrade", default)]
+    upgrades: Vec<UpgradeDefinition>,
+    #[serde(rename = "tier", default)]
+    tiers: Vec<TierDefinition>,
+}
+
+// An experiment waiting at, or running at, one lab, with the crew hours put
+// in so far
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct QueuedExperiment {
+    pub experiment: String,
+    pub module: ModuleId,
+    pub progress: f32,
+}
+
+// How research stands, for saves
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct ResearchState {
+    pub points: u32,
+    pub unlocked: Vec<String>,
+    pub queue: Vec<QueuedExperiment>,
+}
+
+// Experiments queued at the station's labs, worked one at a time per lab
+// while its bench is switched on and powered, and the upgrades the points
+// go on. A lab that loses power spoils whatever it had under way.
+#[derive(Debug, Default)]
+pub struct Research {
+    experiments: Vec<ExperimentDefinition>,
+    upgrades: Vec<UpgradeDefinition>,
+    tiers: Vec<TierDefinition>,
+    queue: Vec<QueuedExperiment>,
+    points: u32,
+    unlocked: BTreeSet<String>,
+    // Set when an unlock changes the tunables, until `take_refit`
+    refit: bool,
+}
+
+impl Research {
+    pub fn new(
+        experiments: Vec<ExperimentDefinition>,
+        upgrades: Vec<UpgradeDefinition>,
+        tiers: Vec<TierDefinition>,
+    ) -> Result<Self> {
+        let mut ids = BTreeSet::new();
+        for experiment in &experiments {
+            if !ids.insert(&experiment.id) {
+                bail!("experiment '{}' is defined twice", experiment.id);
+            }
+            if experiment.hours <= 0.0 {
+                bail!("experiment '{}' takes no time", experiment.id);
+            }
+        }
+        let mut names = Tunables::default();
+        let tunables: Vec<&str> = names.fields_mut().into_iter().map(|(name, _)| name).collect();
+        let mut ids = BTreeSet::new();
+        for upgrade in &upgrades {
+            if !ids.insert(&upgrade.id) {
+                bail!("upgrade '{}' is defined twice", upgrade.id);
+            }
+            if !tunables.contains(&upgrade.tunable.as_str()) {
+                bail!("upgrade '{}' changes unknown tunable '{}'", upgrade.id, upgrade.tunable);
+            }
+        }
+        for upgrade in &upgrades {
+            if let Some(required) = upgrade.requires.as_ref().filter(|required| !ids.contains(required)) {
+                bail!("upgrade '{}' requires unknown upgrade '{}'", upgrade.id, required);
+            }
+        }
+        for tier in &tiers {
+            if tiers.iter().filter(|other| other.module == tier.module && other.tier == tier.tier).count() > 1 {
+                bail!("{:?} tier {} is defined twice", tier.module, tier.tier);
+            }
+            let below = tiers.iter().filter(|other| other.module == tier.module && other.tier < tier.tier).count();
+            if tier.tier == 0 || below + 1 != tier.tier as usize {
+                bail!("{:?} tier {} doesn't follow on from the tiers before it", tier.module, tier.tier);
+            }
+        }
+        Ok(Self { experiments, upgrades, tiers, ..Self::default() })
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("failed to read research from {}", path.display()))?;
+        let file: ResearchFile = toml::from_str(&source).context("invalid research definitions")?;
+        Self::new(file.experiments, file.upgrades, file.tiers)
+    }
+
+    pub fn experiments(&self) -> &[ExperimentDefinition] {
+        &self.experiments
+    }
+
+    pub fn upgrades(&self) -> &[UpgradeDefinition] {
+        &self.upgrades
+    }
+
+    pub fn tiers(&self) -> &[TierDefinition] {
+        &self.tiers
+    }
+
+    // The refit a module would get next, if its type has one left
+    pub fn next_tier(&self, station: &SpaceStation, module: usize) -> Option<&TierDefinition> {
+        let module = station.modules().get(module)?;
+        let tier = module.refit.tier + 1;
+        self.tiers.iter().find(|next| next.module == module.module_type && next.tier == tier)
+    }
+
+    // Pays for a module's next tier in points and parts and fits it
+    pub fn refit_module(&mut self, station: &mut SpaceStation, module: usize) -> Result<TierDefinition> {
+        let Some(next) = self.next_tier(station, module).cloned() else {
+            bail!("module {} has no refit left", module);
+        };
+        if self.points < next.points {
+            bail!("{} costs {} points, there are {}", next.name, next.points, self.points);
+        }
+        let parts = station.resources().total(Resource::SpareParts);
+        if parts < next.parts {
+            bail!("{} takes {:.0} spare parts, there are {:.0}", next.name, next.parts, parts);
+        }
+        station.spend_resource(Resource::SpareParts, next.parts);
+        self.points -= next.points;
+        let refit = next.apply(station.modules()[module].refit);
+        station.refit_module(module, refit);
+        let message = format!("Module {} refitted: {} (tier {})", module, next.name, next.tier);
+        station.log_event(LogSeverity::Info, message);
+        Ok(next)
+    }
+
+    pub fn experiment(&self, id: &str) -> Option<&ExperimentDefinition> {
+        self.experiments.iter().find(|experiment| experiment.id == id)
+    }
+
+    pub fn queue(&self) -> &[QueuedExperiment] {
+        &self.queue
+    }
+
+    pub fn points(&self) -> u32 {
+        self.points
+    }
+
+    pub fn is_unlocked(&self, id: &str) -> bool {
+        self.unlocked.contains(id)
+    }
+
+    // Lines up an experiment at a lab with the bench for it
+    pub fn enqueue(&mut self, station: &SpaceStation, id: &str, module: usize) -> Result<()> {
+        let Some(experiment) = self.experiment(id) else {
+            bail!("no experiment '{}'", id);
+        };
+        let Some(lab) = station.modules().get(module).filter(|lab| lab.module_type == ModuleType::Laboratory) else {
+            bail!("module {} isn't a laboratory", module);
+        };
+        let bench = experiment.bench.element_type();
+        if !lab.interactive_elements.iter().any(|element| element.element_type == bench) {
+            bail!("module {} has no {:?}", module, bench);
+        }
+        self.queue.push(QueuedExperiment { experiment: id.to_string(), module: lab.id, progress: 0.0 });
+        Ok(())
+    }
+
+    // Takes an experiment off the queue, by place; anything put in is lost
+    pub fn cancel(&mut self, station: &mut SpaceStation, index: usize) -> Option<QueuedExperiment> {
+        if index >= self.queue.len() {
+            return None;
+        }
+        let cancelled = self.queue.remove(index);
+        self.release(station, &cancelled);
+        Some(cancelled)
+    }
+
+    // The experiment each lab is working on: the first queued there
+    fn is_running(&self, index: usize) -> bool {
+        let module = self.queue[index].module;
+        self.queue.iter().position(|queued| queued.module == module) == Some(index)
+    }
+
+    // Works each lab's current experiment on for `delta_time`, at the pace
+    // the lab's being run. Finished ones are paid out, and a lab without
+    // power spoils what it had started.
+    pub fn update(&mut self, station: &mut SpaceStation, delta_time: f32) {
+        self.queue.retain(|queued| station.module_index(queued.module).is_some());
+        // One experiment a lab a step, even if the first finished
+        let mut worked = Vec::new();
+        let mut index = 0;
+        while index < self.queue.len() {
+            if !self.is_running(index) || worked.contains(&self.queue[index].module) {
+                index += 1;
+                continue;
+            }
+            worked.push(self.queue[index].module);
+            let queued = &self.queue[index];
+            let Some(experiment) = self.experiment(&queued.experiment).cloned() else {
+                self.queue.remove(index);
+                continue;
+            };
+            let Some(module) = station.module_index(queued.module) else {
+                index += 1;
+                continue;
+            };
+            let lab = &station.modules()[module];
+            let position = lab.transform.position;
+            let bench = experiment.bench.element_type();
+            let Some(element) = lab.interactive_elements.iter().position(|element| element.element_type == bench) else {
+                index += 1;
+                continue;
+            };
+
+            if !lab.powered {
+                if queued.progress > 0.0 {
+                    let spoilt = self.queue.remove(index);
+                    self.release(station, &spoilt);
+                    station.emit_event(StationEvent::ExperimentFailed { module, position, name: experiment.name });
+                    continue;
+                }
+                index += 1;
+                continue;
+            }
+            let working = station.element_state(module, element) == Some(ElementState::Active);
+            let pace = lab.productivity;
+            station.set_element_load(module, element, if working { experiment.power } else { 0.0 });
+            if working {
+                self.queue[index].progress += delta_time / CREW_HOUR * pace;
+            }
+            if self.queue[index].progress >= experiment.hours {
+                let done = self.queue.remove(index);
+                self.release(station, &done);
+                self.points += experiment.points;
+                station.emit_event(StationEvent::ExperimentCompleted {
+                    module,
+                    position,
+                    name: experiment.name,
+                    points: experiment.points,
+                });
+                continue;
+            }
+            index += 1;
+        }
+    }
+
+    // Back to the bench's own draw
+    fn release(&self, station: &mut SpaceStation, queued: &QueuedExperiment) {
+        let Some(experiment) = self.experiment(&queued.experiment) else {
+            return;
+        };
+        let Some(module) = station.module_index(queued.module) else {
+            return;
+        };
+        let bench = experiment.bench.element_type();
+        let element = station.modules()[module].interactive_elements.iter().position(|e| e.element_type == bench);
+        if let Some(element) = element {
+            station.set_element_load(module, element, 0.0);
+        }
+    }
+
+    // Spends points on an upgrade; the tunables want refitting after
+    pub fn unlock(&mut self, station: &mut SpaceStation, id: &str) -> Result<()> {
+        let Some(upgrade) = self.upgrades.iter().find(|upgrade| upgrade.id == id) else {
+            bail!("no upgrade '{}'", id);
+        };
+        if self.unlocked.contains(id) {
+            bail!("{} is already fitted", upgrade.name);
+        }
+        if let Some(required) = upgrade.requires.as_ref().filter(|required| !self.unlocked.contains(*required)) {
+            bail!("{} needs {} first", upgrade.name, required);
+        }
+        if self.points < upgrade.cost {
+            bail!("{} costs {} points, there are {}", upgrade.name, upgrade.cost, self.points);
+        }
+        self.points -= upgrade.cost;
+        station.log_event(LogSeverity::Info, format!("Upgrade fitted: {} ({:?})", upgrade.name, upgrade.module));
+        self.unlocked.insert(id.to_string());
+        self.refit = true;
+        Ok(())
+    }
+
+    // The tunables with every unlocked upgrade applied
+    pub fn upgraded(&self, tunables: &Tunables) -> Tunables {
+        let mut upgraded = tunables.clone();
+        for upgrade in self.upgrades.iter().filter(|upgrade| self.unlocked.contains(&upgrade.id)) {
+            if let Some((_, value)) = upgraded.fields_mut().into_iter().find(|(name, _)| *name == upgrade.tunable) {
+                *value *= upgrade.factor;
+            }
+        }
+        upgraded
+    }
+
+    // Whether an unlock since last asked means the station wants
+    // `upgraded` tunables
+    pub fn take_refit(&mut self) -> bool {
+        std::mem::take(&mut self.refit)
+    }
+
+    pub fn state(&self) -> ResearchState {
+        ResearchState {
+            points: self.points,
+            unlocked: self.unlocked.iter().cloned().collect(),
+            queue: self.queue.clone(),
+        }
+    }
+
+    // Picks up a saved state; upgrades and experiments no longer defined
+    // are dropped
+    pub fn restore(&mut self, state: &ResearchState) {
+        self.points = state.points;
+        self.unlocked = state
+            .unlocked
+            .iter()
+            .filter(|id| self.upgrades.iter().any(|upgrade| &upgrade.id == *id))
+            .cloned()
+            .collect();
+        self.queue = state.queue.iter().filter(|queued| self.experiment(&queued.experiment).is_some()).cloned().collect();
+        self.refit = true;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::comms::Comms;
+    use crate::docking::Docking;
+    use crate::terminal::{Terminal, TerminalPage};
+
+    #[test]
+    fn lab_experiments_earn_points_for_upgrades_and_spoil_without_power() {
+        let mut station = SpaceStation::create_default_layout();
+        let mut research = Research::load("assets/research.toml").unwrap();
+        let bench = station.modules()[5]
+            .interactive_elements
+            .iter()
+            .position(|element| element.element_type == InteractionType::LabEquipment)
+            .unwrap();
+        assert!(research.enqueue(&station, "protein_crystals", 0).is_err());
+        assert!(research.enqueue(&station, "no_such_thing", 5).is_err());
+        research.enqueue(&station, "protein_crystals", 5).unwrap();
+        research.enqueue(&station, "root_zone", 5).unwrap();
+
+        // Nothing moves with the bench switched off
+        station.set_element_state(5, bench, ElementState::Inactive);
+        let idle = station.modules()[5].interactive_elements[bench].power_draw;
+        research.update(&mut station, CREW_HOUR);
+        assert_eq!(research.queue()[0].progress, 0.0);
+
+        // Switched on it draws more, and three crew hours see the first one done
+        station.set_element_state(5, bench, ElementState::Active);
+        research.update(&mut station, CREW_HOUR);
+        assert!((research.queue()[0].progress - 1.0).abs() < 1e-4);
+        assert_eq!(station.modules()[5].interactive_elements[bench].power_draw, idle + 4.0);
+        research.update(&mut station, CREW_HOUR * 2.0);
+        assert_eq!((research.points(), research.queue().len()), (10, 1));
+        assert!(station.drain_events().iter().any(|e| matches!(e, StationEvent::ExperimentCompleted { points: 10, .. })));
+
+        // The lab blacks out halfway through the next
+        research.update(&mut station, CREW_HOUR);
+        station.module_mut(5).unwrap().powered = false;
+        research.update(&mut station, CREW_HOUR);
+        assert!(research.queue().is_empty());
+        assert_eq!(station.modules()[5].interactive_elements[bench].power_draw, idle);
+        assert!(station.drain_events().iter().any(|e| e.kind() == "experiment_failed"));
+        station.module_mut(5).unwrap().powered = true;
+
+        assert!(research.unlock(&mut station, "hydroponics").is_err());
+        research.enqueue(&station, "root_zone", 5).unwrap();
+        research.update(&mut station, CREW_HOUR * 6.0);
+        assert!(research.unlock(&mut station, "reactor_retune").is_err());
+        research.unlock(&mut station, "hydroponics").unwrap();
+        assert!(research.unlock(&mut station, "hydroponics").is_err());
+        assert_eq!(research.points(), 10);
+        assert!(research.take_refit() && !research.take_refit());
+        let tunables = Tunables::default();
+        let upgraded = research.upgraded(&tunables);
+        assert_eq!(upgraded.station.food_growth, tunables.station.food_growth * 1.5);
+        assert_eq!(upgraded.power, tunables.power);
+
+        let mut restored = Research::load("assets/research.toml").unwrap();
+        restored.restore(&research.state());
+        assert_eq!(restored.state(), research.state());
+    }
+
+    #[test]
+    fn module_refits_cost_points_and_parts_and_change_stats_and_looks() {
+        let mut station = SpaceStation::create_default_layout();
+        let mut research = Research::load("assets/research.toml").unwrap();
+        let plant = 8;
+        assert!(research.refit_module(&mut station, plant).is_err());
+        research.restore(&ResearchState { points: 100, ..ResearchState::default() });
+        station.stow_anywhere(Resource::SpareParts, 100.0);
+        let output = station.modules()[plant].power_output();
+        let parts = station.resources().total(Resource::SpareParts);
+        let vertices = station.modules()[plant].mesh.vertices.len();
+
+        let fitted = research.refit_module(&mut station, plant).unwrap();
+        assert_eq!((fitted.tier, research.points()), (1, 85));
+        assert_eq!(station.resources().total(Resource::SpareParts), parts - fitted.parts);
+        assert!((station.modules()[plant].power_output() - output * 1.25).abs() < 1e-3);
+        assert!(station.modules()[plant].mesh.vertices.len() > vertices);
+        assert_ne!(station.modules()[plant].trim.albedo, station.modules()[0].trim.albedo);
+        research.refit_module(&mut station, plant).unwrap();
+        assert!(research.refit_module(&mut station, plant).is_err());
+        assert!((station.modules()[plant].refit.generation - 1.5).abs() < 1e-4);
+
+        // Reinforced hulls shrug off some of a hit
+        station.stow_anywhere(Resource::SpareParts, 10.0);
+        research.refit_module(&mut station, 1).unwrap();
+        station.damage_module(1, 0.3);
+        station.damage_module(2, 0.3);
+        assert!((station.modules()[1].structural_integrity - 0.8).abs() < 1e-4);
+        assert!((station.modules()[2].structural_integrity - 0.7).abs() < 1e-4);
+
+        let restored = SpaceStation::from_snapshot(&station.snapshot());
+        assert_eq!(restored.modules()[plant].refit, station.modules()[plant].refit);
+        assert_eq!(restored.modules()[plant].mesh.vertices.len(), station.modules()[plant].mesh.vertices.len());
+
+        let mut terminal = Terminal::new();
+        terminal.open(6, 0, InteractionType::Terminal);
+        while terminal.page() != TerminalPage::Refit {
+            terminal.next_page();
+        }
+        assert_eq!(terminal.selected_refit(), Some(6));
+        let screen = terminal.screen(&station, &Docking::default(), &research, &Comms::new(Vec::new())).unwrap();
+        assert!(screen.lines.iter().any(|line| line.contains("Second scrubber bed")));
+    }
+}
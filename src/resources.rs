@@ -0,0 +1,314 @@
+use serde::{Deserialize, Serialize};
+use crate::inventory::ItemKind;
+use crate::life_support::LifeSupportKind;
+use crate::module_id::ModuleId;
+use crate::station::{ElementState, InteractionType, ModuleType, StationModule};
+use crate::tunables::StationTunables;
+
+// Consumables the station keeps in its storage modules
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Resource {
+    Water,
+    Food,
+    SpareParts,
+    OxygenCanisters,
+}
+
+impl Resource {
+    pub const ALL: [Resource; 4] = [Resource::Water, Resource::Food, Resource::SpareParts, Resource::OxygenCanisters];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Resource::Water => "Water",
+            Resource::Food => "Food",
+            Resource::SpareParts => "Spare parts",
+            Resource::OxygenCanisters => "O2 canisters",
+        }
+    }
+
+    // As typed in the console
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "water" => Some(Resource::Water),
+            "food" => Some(Resource::Food),
+            "parts" | "spare_parts" => Some(Resource::SpareParts),
+            "o2" | "oxygen_canisters" => Some(Resource::OxygenCanisters),
+            _ => None,
+        }
+    }
+
+    pub fn unit(self) -> &'static str {
+        match self {
+            Resource::Water => "L",
+            Resource::Food => "kg",
+            Resource::SpareParts | Resource::OxygenCanisters => "",
+        }
+    }
+
+    // Whether it comes in whole pieces rather than by volume or weight
+    pub fn counted(self) -> bool {
+        matches!(self, Resource::SpareParts | Resource::OxygenCanisters)
+    }
+
+    // What one storage module holds
+    pub fn capacity(self) -> f32 {
+        match self {
+            Resource::Water => 2000.0,
+            Resource::Food => 500.0,
+            Resource::SpareParts => 50.0,
+            Resource::OxygenCanisters => 20.0,
+        }
+    }
+
+    // What a storage module comes stocked with
+    pub fn starting_stock(self) -> f32 {
+        match self {
+            Resource::Water => 800.0,
+            Resource::Food => 200.0,
+            Resource::SpareParts => 20.0,
+            Resource::OxygenCanisters => 6.0,
+        }
+    }
+
+    // Below this, station-wide, it's reported as running short
+    pub fn low_mark(self) -> f32 {
+        match self {
+            Resource::Water => 100.0,
+            Resource::Food => 25.0,
+            Resource::SpareParts => 3.0,
+            Resource::OxygenCanisters => 2.0,
+        }
+    }
+
+    // The inventory item one unit of it is carried as, if it can be carried
+    pub fn item(self) -> Option<ItemKind> {
+        match self {
+            Resource::SpareParts => Some(ItemKind::SpareParts),
+            Resource::OxygenCanisters => Some(ItemKind::OxygenCanister),
+            Resource::Water | Resource::Food => None,
+        }
+    }
+
+    // What using up one unit of it costs a drill: a part or canister counts
+    // one, water and food by the ten
+    pub fn weight(self) -> f32 {
+        if self.counted() { 1.0 } else { 0.1 }
+    }
+
+    pub fn carried_as(kind: ItemKind) -> Option<Self> {
+        Resource::ALL.into_iter().find(|resource| resource.item() == Some(kind))
+    }
+
+    fn index(self) -> usize {
+        self as usize
+    }
+}
+
+// One storage module's stock, by Resource::ALL order
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Store {
+    pub module: ModuleId,
+    pub stock: [f32; 4],
+}
+
+impl Store {
+    pub fn amount(&self, resource: Resource) -> f32 {
+        self.stock[resource.index()]
+    }
+}
+
+// Every storage module's stock. Station-wide withdrawals come out of the
+// fullest store first.
+#[derive(Debug, Clone, Default)]
+pub struct ResourceLedger {
+    stores: Vec<Store>,
+}
+
+impl ResourceLedger {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // A store for a storage module, stocked with its starting load
+    pub fn add_store(&mut self, module: ModuleId) {
+        let stock = Resource::ALL.map(Resource::starting_stock);
+        self.stores.push(Store { module, stock });
+    }
+
+    // Takes a module's store out, for the module going; returns what it held
+    pub fn remove_store(&mut self, module: ModuleId) -> Option<Store> {
+        let index = self.stores.iter().position(|store| store.module == module)?;
+        Some(self.stores.remove(index))
+    }
+
+    pub fn stores(&self) -> &[Store] {
+        &self.stores
+    }
+
+    pub fn restore(&mut self, stores: Vec<Store>) {
+        self.stores = stores;
+    }
+
+    pub fn total(&self, resource: Resource) -> f32 {
+        self.stores.iter().map(|store| store.amount(resource)).sum()
+    }
+
+    pub fn stock(&self, module: ModuleId, resource: Resource) -> f32 {
+        self.stores.iter().find(|store| store.module == module).map_or(0.0, |store| store.amount(resource))
+    }
+
+    // Puts up to `amount` into a module's store; returns what fit
+    pub fn deposit(&mut self, module: ModuleId, resource: Resource, amount: f32) -> f32 {
+        let Some(store) = self.stores.iter_mut().find(|store| store.module == module) else {
+            return 0.0;
+        };
+        let held = &mut store.stock[resource.index()];
+        let added = amount.clamp(0.0, (resource.capacity() - *held).max(0.0));
+        *held += added;
+        added
+    }
+
+    // Puts up to `amount` wherever there's room; returns what fit
+    pub fn deposit_anywhere(&mut self, resource: Resource, amount: f32) -> f32 {
+        let mut stored = 0.0;
+        for i in 0..self.stores.len() {
+            let module = self.stores[i].module;
+            stored += self.deposit(module, resource, amount - stored);
+        }
+        stored
+    }
+
+    // Takes up to `amount` from one module's store; returns what it had
+    pub fn withdraw_from(&mut self, module: ModuleId, resource: Resource, amount: f32) -> f32 {
+        let Some(store) = self.stores.iter_mut().find(|store| store.module == module) else {
+            return 0.0;
+        };
+        let held = &mut store.stock[resource.index()];
+        let taken = amount.clamp(0.0, *held);
+        *held -= taken;
+        taken
+    }
+
+    // Takes up to `amount` from wherever it's kept; returns what there was
+    pub fn withdraw(&mut self, resource: Resource, amount: f32) -> f32 {
+        let mut wanted = amount.max(0.0);
+        let mut order: Vec<usize> = (0..self.stores.len()).collect();
+        order.sort_by(|&a, &b| self.stores[b].amount(resource).total_cmp(&self.stores[a].amount(resource)));
+        for i in order {
+            if wanted <= 0.0 {
+                break;
+            }
+            let held = &mut self.stores[i].stock[resource.index()];
+            let taken = wanted.min(*held);
+            *held -= taken;
+            wanted -= taken;
+        }
+        amount.max(0.0) - wanted
+    }
+
+    // Moves stock between two stores, as much as the one has and the
+    // other has room for; returns how much moved
+    pub fn transfer(&mut self, from: ModuleId, to: ModuleId, resource: Resource, amount: f32) -> f32 {
+        if from == to || !self.stores.iter().any(|store| store.module == to) {
+            return 0.0;
+        }
+        let room = resource.capacity() - self.stock(to, resource);
+        let taken = self.withdraw_from(from, resource, amount.min(room));
+        self.deposit(to, resource, taken)
+    }
+}
+
+// Steady production less consumption per second, by Resource::ALL order.
+// Crew drink and eat; water recyclers recover most of the water in the
+// modules they're running in, the lab's equipment grows food and its
+// research station turns out spare parts, while powered and switched on and
+// as well as the lab is being run.
+// Canisters are only used when the air runs thin, so they aren't a rate.
+pub fn rates(modules: &[StationModule], tunables: &StationTunables) -> [f32; 4] {
+    let mut rates = [0.0; 4];
+    for module in modules {
+        let crew = module.crew as f32;
+        let recycled = module.life_support_output(LifeSupportKind::WaterRecycler).min(1.0);
+        let recovery = tunables.water_recovery * recycled;
+        rates[Resource::Water.index()] -= crew * tunables.crew_water_use * (1.0 - recovery);
+        rates[Resource::Food.index()] -= crew * tunables.crew_food_use;
+        if !module.powered || module.module_type != ModuleType::Laboratory {
+            continue;
+        }
+        for element in &module.interactive_elements {
+            if element.state != ElementState::Active {
+                continue;
+            }
+            match element.element_type {
+                InteractionType::LabEquipment => {
+                    rates[Resource::Food.index()] += tunables.food_growth * module.productivity
+                }
+                InteractionType::ResearchStation => {
+                    rates[Resource::SpareParts.index()] += tunables.parts_fabrication * module.productivity
+                }
+                _ => {}
+            }
+        }
+    }
+    rates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use glam::Vec3;
+    use crate::event_log::LogSeverity;
+    use crate::events::StationEvent;
+    use crate::station::SpaceStation;
+    use crate::tunables::Tunables;
+
+    #[test]
+    fn crew_run_down_the_stores_until_shortages_are_reported() {
+        let mut station = SpaceStation::create_default_layout();
+        let ledger = station.resources();
+        assert_eq!(ledger.stores().len(), 1);
+        assert_eq!(ledger.total(Resource::Water), Resource::Water.starting_stock());
+        let rates = station.resource_rates();
+        assert!(rates[0] < 0.0 && rates[1] < 0.0);
+
+        let mut tunables = Tunables::default();
+        tunables.station.crew_food_use = 2.0;
+        station.set_tunables(&tunables);
+        station.drain_events();
+        for _ in 0..60 {
+            station.update(1.0);
+        }
+        let shortages: Vec<_> = station
+            .drain_events()
+            .into_iter()
+            .filter(|event| matches!(event, StationEvent::ResourceShortage { resource: Resource::Food, .. }))
+            .collect();
+        assert_eq!(shortages.len(), 2);
+        assert_eq!(shortages[1].severity(), LogSeverity::Critical);
+        assert_eq!(station.resources().total(Resource::Food), 0.0);
+    }
+
+    #[test]
+    fn stored_canisters_top_up_thin_air_and_stores_trade_stock() {
+        let mut station = SpaceStation::create_default_layout();
+        let depot = station.add_module(ModuleType::Storage, Vec3::new(0.0, 0.0, 40.0));
+        station.module_mut(5).unwrap().atmosphere.oxygen *= 0.5;
+        let oxygen = station.modules()[5].atmosphere.oxygen_level();
+        station.update(0.1);
+        assert!(station.modules()[5].atmosphere.oxygen_level() > oxygen + 0.05);
+        assert_eq!(station.resources().total(Resource::OxygenCanisters), 2.0 * 6.0 - 1.0);
+
+        // Only as much as there is, and as the far end has room for
+        assert_eq!(station.transfer_resource(depot, 7, Resource::SpareParts, 100.0), 20.0);
+        assert_eq!(station.transfer_resource(depot, 7, Resource::Water, 1500.0), 800.0);
+        // The canister came out of the first store
+        assert_eq!(station.transfer_resource(7, depot, Resource::OxygenCanisters, 50.0), 5.0);
+        let depot_id = station.modules()[depot].id;
+        assert_eq!(station.resources().stock(depot_id, Resource::OxygenCanisters), 11.0);
+        assert_eq!(station.transfer_resource(7, 0, Resource::Water, 10.0), 0.0);
+
+        let restored = SpaceStation::from_snapshot(&station.snapshot());
+        assert_eq!(restored.resources().stores(), station.resources().stores());
+    }
+}
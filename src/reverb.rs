@@ -0,0 +1,179 @@
+use std::fs;
+use std::path::Path;
+use anyhow::{Context, Result};
+use glam::Vec3;
+use serde::Deserialize;
+use crate::station::{ModuleType, SpaceStation};
+
+// Seconds to blend from one module's reverb to the next
+const CROSSFADE_TIME: f32 = 0.6;
+
+// Parameters for the positional audio reverb send
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+pub struct ReverbParams {
+    // Seconds for the tail to fall by 60 dB
+    pub decay: f32,
+    // 0 dry .. 1 fully wet
+    pub wet: f32,
+    // High-frequency absorption; padded rooms are high, bare metal is low
+    pub damping: f32,
+    pub pre_delay_ms: f32,
+}
+
+impl ReverbParams {
+    pub fn lerp(self, other: ReverbParams, t: f32) -> Self {
+        let mix = |a: f32, b: f32| a + (b - a) * t;
+        Self {
+            decay: mix(self.decay, other.decay),
+            wet: mix(self.wet, other.wet),
+            damping: mix(self.damping, other.damping),
+            pre_delay_ms: mix(self.pre_delay_ms, other.pre_delay_ms),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ReverbPreset {
+    MetalCorridor,
+    LargeHub,
+    PaddedQuarters,
+    Chamber,
+    Cargo,
+    Machinery,
+}
+
+impl ReverbPreset {
+    pub fn for_module(module_type: ModuleType) -> Self {
+        match module_type {
+            ModuleType::Corridor | ModuleType::Airlock | ModuleType::EscapePod | ModuleType::SolarArray => {
+                ReverbPreset::MetalCorridor
+            }
+            ModuleType::Hub => ReverbPreset::LargeHub,
+            ModuleType::LivingQuarters => ReverbPreset::PaddedQuarters,
+            ModuleType::CommandCenter | ModuleType::Laboratory => ReverbPreset::Chamber,
+            ModuleType::Storage => ReverbPreset::Cargo,
+            ModuleType::PowerPlant | ModuleType::Battery => ReverbPreset::Machinery,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ReverbPreset::MetalCorridor => "metal corridor",
+            ReverbPreset::LargeHub => "large hub",
+            ReverbPreset::PaddedQuarters => "padded quarters",
+            ReverbPreset::Chamber => "chamber",
+            ReverbPreset::Cargo => "cargo bay",
+            ReverbPreset::Machinery => "machinery hall",
+        }
+    }
+
+    pub fn params(self) -> ReverbParams {
+        let (decay, wet, damping, pre_delay_ms) = match self {
+            ReverbPreset::MetalCorridor => (1.1, 0.35, 0.2, 8.0),
+            ReverbPreset::LargeHub => (2.4, 0.45, 0.3, 25.0),
+            ReverbPreset::PaddedQuarters => (0.35, 0.12, 0.8, 3.0),
+            ReverbPreset::Chamber => (0.8, 0.25, 0.5, 10.0),
+            ReverbPreset::Cargo => (1.8, 0.4, 0.35, 18.0),
+            ReverbPreset::Machinery => (1.5, 0.3, 0.45, 15.0),
+        };
+        ReverbParams {
+            decay,
+            wet,
+            damping,
+            pre_delay_ms,
+        }
+    }
+}
+
+// A module whose reverb differs from its type's preset
+#[derive(Debug, Deserialize)]
+struct ReverbOverride {
+    module: usize,
+    #[serde(default)]
+    preset: Option<ReverbPreset>,
+    #[serde(default)]
+    params: Option<ReverbParams>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ReverbFile {
+    #[serde(rename = "override", default)]
+    overrides: Vec<ReverbOverride>,
+}
+
+// Picks the reverb for whichever module the listener is in and crossfades
+// to it when they move between modules
+#[derive(Debug)]
+pub struct ReverbZones {
+    overrides: Vec<ReverbOverride>,
+    module: Option<usize>,
+    from: ReverbParams,
+    target: ReverbParams,
+    // 0..1 through the current crossfade
+    blend: f32,
+}
+
+impl Default for ReverbZones {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReverbZones {
+    pub fn new() -> Self {
+        let dry = ReverbPreset::PaddedQuarters.params();
+        Self {
+            overrides: Vec::new(),
+            module: None,
+            from: dry,
+            target: dry,
+            blend: 1.0,
+        }
+    }
+
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("failed to read reverb zones from {}", path.display()))?;
+        let file: ReverbFile = toml::from_str(&source).context("invalid reverb zones")?;
+        let mut zones = Self::new();
+        zones.overrides = file.overrides;
+        Ok(zones)
+    }
+
+    // Reverb for a module: an override's own params, then its preset, then
+    // the module type's preset
+    pub fn params_for(&self, station: &SpaceStation, module_idx: usize) -> Option<ReverbParams> {
+        let module = station.modules().get(module_idx)?;
+        let overridden = self.overrides.iter().rev().find(|o| o.module == module_idx);
+        Some(match overridden {
+            Some(ReverbOverride { params: Some(params), .. }) => *params,
+            Some(ReverbOverride { preset: Some(preset), .. }) => preset.params(),
+            _ => ReverbPreset::for_module(module.module_type).params(),
+        })
+    }
+
+    pub fn update(&mut self, station: &SpaceStation, listener: Vec3, delta_time: f32) {
+        let module = station.nearest_module(listener);
+        if module != self.module {
+            // Start from wherever the last fade had got to
+            self.from = self.current();
+            self.target = module.and_then(|idx| self.params_for(station, idx)).unwrap_or(self.from);
+            self.blend = 0.0;
+            self.module = module;
+        }
+        self.blend = (self.blend + delta_time / CROSSFADE_TIME).min(1.0);
+    }
+
+    // What the audio mixer should use right now
+    pub fn current(&self) -> ReverbParams {
+        // Smoothstep so the change doesn't start or stop abruptly
+        let t = self.blend * self.blend * (3.0 - 2.0 * self.blend);
+        self.from.lerp(self.target, t)
+    }
+
+    pub fn module(&self) -> Option<usize> {
+        self.module
+    }
+}
@@ -0,0 +1,156 @@
+use glam::{Mat4, Vec3};
+use serde::{Deserialize, Serialize};
+use crate::module_id::ModuleId;
+
+// One g, in m/s²
+pub const STANDARD_GRAVITY: f32 = 9.81;
+
+// A ring section as saves list it: its modules, the axis they spin about
+// through `centre`, and how fast in turns a minute. Layout files give the
+// modules by index instead (see `layout::LayoutRing`).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RingSpec {
+    pub name: String,
+    pub centre: [f32; 3],
+    pub axis: [f32; 3],
+    pub rpm: f32,
+    pub modules: Vec<ModuleId>,
+    // How far round it's turned, in radians; only the outside view shows it
+    #[serde(default)]
+    pub angle: f32,
+}
+
+// Modules spun about an axis for artificial gravity. The simulation runs in
+// the ring's own turning frame, where nothing inside moves and the floor is
+// pressed outward, away from the axis, at ω²r.
+#[derive(Debug, Clone)]
+pub struct RingSection {
+    pub name: String,
+    pub centre: Vec3,
+    pub axis: Vec3,
+    pub rpm: f32,
+    pub modules: Vec<ModuleId>,
+    pub angle: f32,
+}
+
+impl RingSection {
+    pub fn new(name: &str, centre: Vec3, axis: Vec3, rpm: f32, modules: Vec<ModuleId>) -> Self {
+        Self {
+            name: name.to_string(),
+            centre,
+            axis: axis.try_normalize().unwrap_or(Vec3::X),
+            rpm,
+            modules,
+            angle: 0.0,
+        }
+    }
+
+    // Radians a second
+    pub fn angular_velocity(&self) -> f32 {
+        self.rpm * std::f32::consts::TAU / 60.0
+    }
+
+    // From the axis out to `point`, square to the axis
+    pub fn radial(&self, point: Vec3) -> Vec3 {
+        let offset = point - self.centre;
+        offset - self.axis * offset.dot(self.axis)
+    }
+
+    // m/s² at `point`, outward
+    pub fn gravity(&self, point: Vec3) -> Vec3 {
+        self.radial(point) * self.angular_velocity().powi(2)
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        self.angle = (self.angle + self.angular_velocity() * delta_time) % std::f32::consts::TAU;
+    }
+
+    // Where the ring's turned to, for drawing it from outside
+    pub fn spin(&self) -> Mat4 {
+        Mat4::from_translation(self.centre)
+            * Mat4::from_axis_angle(self.axis, self.angle)
+            * Mat4::from_translation(-self.centre)
+    }
+}
+
+// The decks outside any ring are held at one g, straight down
+pub fn deck_gravity() -> Vec3 {
+    Vec3::NEG_Y * STANDARD_GRAVITY
+}
+
+// In g, for readouts
+pub fn in_g(gravity: Vec3) -> f32 {
+    gravity.length() / STANDARD_GRAVITY
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::joints::{JointKind, PropDefinition, Props};
+    use crate::layout::StationLayout;
+    use crate::station::SpaceStation;
+    use crate::stepping::STEP_DT;
+
+    #[test]
+    fn ring_sections_spin_up_their_own_gravity() {
+        let mut station = SpaceStation::create_default_layout();
+        let lab = station.modules()[5].transform.position;
+        // Turning about an axis 50 m overhead, so the floor's still below
+        let spec = RingSpec {
+            name: "Hab ring".to_string(),
+            centre: (lab + Vec3::Y * 50.0).to_array(),
+            axis: [1.0, 0.0, 0.0],
+            rpm: 4.23,
+            modules: vec![ModuleId::from_layout(5)],
+            angle: 0.0,
+        };
+        assert_eq!(station.add_ring(&spec), Some(0));
+        // A module only turns with one ring, and there's no module 40
+        let modules = vec![ModuleId::from_layout(5), ModuleId::from_layout(6)];
+        assert_eq!(station.add_ring(&RingSpec { modules, ..spec.clone() }), None);
+        assert_eq!(station.add_ring(&RingSpec { modules: vec![ModuleId::from_layout(40)], ..spec.clone() }), None);
+
+        let gravity = station.gravity_at(lab);
+        assert!((in_g(gravity) - 1.0).abs() < 0.01);
+        assert!(station.local_up(lab, Vec3::Z).dot(Vec3::Y) > 0.999);
+        assert_eq!(station.gravity_at(station.modules()[8].transform.position), deck_gravity());
+        // Spun down, it's half the radius for the same pull
+        assert!(station.set_ring_rpm(0, 4.23 * 2.0f32.sqrt()));
+        assert!((in_g(station.rings()[0].gravity(lab + Vec3::Y * 25.0)) - 1.0).abs() < 0.01);
+        assert!(station.set_ring_rpm(0, 0.0));
+        assert_eq!(station.local_up(lab, Vec3::Z), Vec3::Z);
+        assert!(!station.set_ring_rpm(1, 1.0));
+        station.set_ring_rpm(0, 4.23);
+
+        // Loose props still come down on the floor
+        let mut props = Props::new();
+        let crate_prop = PropDefinition {
+            name: "crate".to_string(),
+            module: ModuleId::from_layout(5),
+            anchor: [0.0, 2.0, 0.0],
+            size: [0.5, 0.5, 0.5],
+            mass: 10.0,
+            joint: JointKind::Fixed,
+            axis: [0.0, 1.0, 0.0],
+            offset: [0.0; 3],
+            limits: None,
+            motor: None,
+            break_impulse: Some(1.0),
+            parent: None,
+        };
+        props.add(crate_prop, &station).unwrap();
+        assert!(props.knock(0, lab + Vec3::Y * 2.0, Vec3::X * 5.0));
+        for _ in 0..600 {
+            props.update(STEP_DT, |point| station.gravity_at(point));
+        }
+        assert!(props.centre(0).y < lab.y + 1.0);
+
+        station.update(1.0);
+        let angle = station.rings()[0].angle;
+        assert!(angle > 0.0);
+        let restored = SpaceStation::from_snapshot(&station.snapshot());
+        assert_eq!(restored.ring_specs(), station.ring_specs());
+        let rebuilt = StationLayout::of(&station).build().unwrap();
+        assert_eq!(rebuilt.ring_specs()[0].modules, vec![rebuilt.modules()[5].id]);
+    }
+}
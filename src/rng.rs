@@ -0,0 +1,55 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+use anyhow::{bail, Context, Result};
+use glam::Vec3;
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+// Random numbers for anything that affects the simulation. Nothing in the sim
+// uses thread_rng; each system gets its own generator forked from the
+// station's, so a run started from the same seed plays out the same way.
+pub type SimRng = StdRng;
+
+pub fn seeded(seed: u64) -> SimRng {
+    StdRng::seed_from_u64(seed)
+}
+
+// Child generator; forking in a fixed order keeps every system reproducible
+pub fn fork(rng: &mut SimRng) -> SimRng {
+    seeded(rng.gen())
+}
+
+// Seeds are stored in TOML saves, which only hold signed 64-bit integers
+pub const MAX_SEED: u64 = i64::MAX as u64;
+
+// Seed for a session nobody asked to reproduce
+pub fn session_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_nanos() as u64)
+        & MAX_SEED
+}
+
+// `--seed N` from the command line, if given
+pub fn seed_from_args(args: &[String]) -> Result<Option<u64>> {
+    let Some(i) = args.iter().position(|arg| arg == "--seed") else {
+        return Ok(None);
+    };
+    let value = args.get(i + 1).context("--seed needs a value")?;
+    parse_seed(value).map(Some)
+}
+
+pub fn parse_seed(value: &str) -> Result<u64> {
+    let seed: u64 = value.parse().context("--seed must be a whole number")?;
+    if seed > MAX_SEED {
+        bail!("--seed can be at most {}", MAX_SEED);
+    }
+    Ok(seed)
+}
+
+// Uniform over the sphere
+pub fn random_direction(rng: &mut impl Rng) -> Vec3 {
+    let theta = rng.gen_range(0.0..std::f32::consts::TAU);
+    let z: f32 = rng.gen_range(-1.0..1.0);
+    let r = (1.0 - z * z).sqrt();
+    Vec3::new(theta.cos() * r, z, theta.sin() * r)
+}
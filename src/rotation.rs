@@ -0,0 +1,265 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use crate::headless::{self, HeadlessReport};
+use crate::stepping::STEP_DT;
+
+// One round of the rotation: where the station starts from and how long it
+// runs, in simulated seconds. Without a seed each round gets its own.
+#[derive(Debug, Clone, Deserialize)]
+pub struct RotationScenario {
+    pub name: String,
+    pub duration: f32,
+    #[serde(default)]
+    pub save: Option<PathBuf>,
+    #[serde(default)]
+    pub tunables: Option<PathBuf>,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    // (module, amount) pairs applied before the first tick
+    #[serde(default)]
+    pub damage: Vec<(usize, f32)>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RotationConfig {
+    #[serde(rename = "scenario", default)]
+    pub scenarios: Vec<RotationScenario>,
+    // How many of the scenarios coming up are put to a vote between rounds;
+    // 1 or less just runs them in order
+    #[serde(default)]
+    pub vote_options: usize,
+    // Real seconds the vote stays open
+    #[serde(default)]
+    pub vote_seconds: f32,
+}
+
+impl RotationConfig {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("failed to read rotation from {}", path.display()))?;
+        let config: Self = toml::from_str(&source).context("invalid rotation")?;
+        if config.scenarios.is_empty() {
+            bail!("rotation in {} has no scenarios", path.display());
+        }
+        Ok(config)
+    }
+}
+
+// The scenario on the ballot with the most votes, the earliest on it winning
+// ties and no votes at all. Votes are names or ballot numbers from 1, one a
+// line; anything else is spoilt.
+pub fn tally(candidates: &[&str], votes: &str) -> usize {
+    let mut counts = vec![0usize; candidates.len()];
+    for vote in votes.lines().map(str::trim) {
+        let choice = match vote.parse::<usize>() {
+            Ok(number) => number.checked_sub(1).filter(|&i| i < candidates.len()),
+            Err(_) => candidates.iter().position(|name| name.eq_ignore_ascii_case(vote)),
+        };
+        if let Some(i) = choice {
+            counts[i] += 1;
+        }
+    }
+    // max_by_key keeps the last of equals, so go from the back
+    (0..candidates.len()).rev().max_by_key(|&i| counts[i]).unwrap_or(0)
+}
+
+// How each scenario has gone over every rotation this server's run, kept in
+// a JSON file so restarts carry on the count
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RotationStats {
+    pub rounds: u64,
+    pub scenarios: BTreeMap<String, ScenarioStats>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScenarioStats {
+    pub rounds: u64,
+    pub simulated_seconds: f64,
+    // Everything raised, by event kind
+    pub events: BTreeMap<String, u64>,
+    // Worst and average structural integrity at the end of a round
+    pub worst_integrity: f32,
+    pub mean_integrity: f32,
+}
+
+impl RotationStats {
+    // A missing file is a fresh start
+    pub fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let source = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+        serde_json::from_str(&source).with_context(|| format!("invalid rotation stats in {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let json = serde_json::to_string_pretty(self).context("failed to serialize rotation stats")?;
+        fs::write(path, json).with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    pub fn record(&mut self, scenario: &str, report: &HeadlessReport) {
+        self.rounds += 1;
+        let integrity = report.final_telemetry.structural_integrity;
+        let stats = self.scenarios.entry(scenario.to_string()).or_insert(ScenarioStats {
+            rounds: 0,
+            simulated_seconds: 0.0,
+            events: BTreeMap::new(),
+            worst_integrity: integrity,
+            mean_integrity: 0.0,
+        });
+        stats.rounds += 1;
+        stats.simulated_seconds += report.ticks as f64 * report.tick_seconds as f64;
+        for event in &report.events {
+            *stats.events.entry(event.kind.to_string()).or_default() += 1;
+        }
+        stats.worst_integrity = stats.worst_integrity.min(integrity);
+        stats.mean_integrity += (integrity - stats.mean_integrity) / stats.rounds as f32;
+    }
+}
+
+// What a standing server prints after each round, one JSON object a line
+#[derive(Debug, Clone, Serialize)]
+pub struct RoundSummary<'a> {
+    pub round: u64,
+    pub scenario: &'a str,
+    pub seed: u64,
+    pub ticks: u64,
+    pub events: usize,
+    pub structural_integrity: f32,
+}
+
+// Where the rotation's got to
+#[derive(Debug)]
+pub struct Rotation {
+    config: RotationConfig,
+    next: usize,
+}
+
+impl Rotation {
+    pub fn new(config: RotationConfig) -> Self {
+        Self { config, next: 0 }
+    }
+
+    pub fn config(&self) -> &RotationConfig {
+        &self.config
+    }
+
+    // The scenarios up for the next round, in rotation order
+    pub fn candidates(&self) -> Vec<usize> {
+        let count = self.config.scenarios.len();
+        (0..self.config.vote_options.clamp(1, count)).map(|i| (self.next + i) % count).collect()
+    }
+
+    // Settles the next round on the `choice`th candidate; the rotation
+    // carries on from after it
+    pub fn choose(&mut self, choice: usize) -> &RotationScenario {
+        let candidates = self.candidates();
+        let index = candidates.get(choice).copied().unwrap_or(candidates[0]);
+        self.next = (index + 1) % self.config.scenarios.len();
+        &self.config.scenarios[index]
+    }
+}
+
+// Builds a fresh station for the scenario and runs it through
+pub fn play(scenario: &RotationScenario, seed: u64) -> Result<HeadlessReport> {
+    let mut station = headless::build_station(
+        scenario.save.as_deref(),
+        scenario.tunables.as_deref(),
+        Some(scenario.seed.unwrap_or(seed)),
+        &scenario.damage,
+    )
+    .with_context(|| format!("scenario {}", scenario.name))?;
+    let ticks = (scenario.duration.max(0.0) / STEP_DT).round() as u64;
+    Ok(headless::simulate(&mut station, ticks, 0))
+}
+
+// `--headless --rotation`: plays `rounds` rounds, or keeps going with 0.
+// Between rounds the ballot goes to stderr and the votes file is read and
+// emptied once voting closes.
+pub fn serve(path: &Path, rounds: u64, base_seed: u64, stats_path: Option<&Path>, votes: Option<&Path>) -> Result<()> {
+    let mut rotation = Rotation::new(RotationConfig::load(path)?);
+    let mut stats = match stats_path {
+        Some(path) => RotationStats::load(path)?,
+        None => RotationStats::default(),
+    };
+    let mut round = 0;
+    while rounds == 0 || round < rounds {
+        round += 1;
+        let candidates = rotation.candidates();
+        let names: Vec<&str> = candidates.iter().map(|&i| rotation.config().scenarios[i].name.as_str()).collect();
+        let choice = match votes.filter(|_| names.len() > 1) {
+            Some(votes) => {
+                let ballot: Vec<String> = names.iter().enumerate().map(|(i, name)| format!("{}) {}", i + 1, name)).collect();
+                eprintln!("Round {} vote, {:.0} s: {}", round, rotation.config().vote_seconds, ballot.join("  "));
+                thread::sleep(Duration::from_secs_f32(rotation.config().vote_seconds.max(0.0)));
+                let cast = fs::read_to_string(votes).unwrap_or_default();
+                if !cast.is_empty() {
+                    fs::write(votes, "").with_context(|| format!("failed to clear {}", votes.display()))?;
+                }
+                tally(&names, &cast)
+            }
+            None => 0,
+        };
+        let scenario = rotation.choose(choice).clone();
+        let seed = base_seed.wrapping_add(stats.rounds);
+        let report = play(&scenario, seed)?;
+        stats.record(&scenario.name, &report);
+        if let Some(path) = stats_path {
+            stats.save(path)?;
+        }
+        let summary = RoundSummary {
+            round: stats.rounds,
+            scenario: &scenario.name,
+            seed: report.seed,
+            ticks: report.ticks,
+            events: report.events.len(),
+            structural_integrity: report.final_telemetry.structural_integrity,
+        };
+        println!("{}", serde_json::to_string(&summary).context("failed to serialize round summary")?);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rotation_votes_on_the_next_scenarios_and_keeps_stats() {
+        let config = RotationConfig::load("assets/rotation.toml").unwrap();
+        assert_eq!(config.scenarios[1].damage, vec![(7, 0.8)]);
+        let mut rotation = Rotation::new(config);
+        assert_eq!(rotation.candidates(), vec![0, 1, 2]);
+        // Names or ballot numbers; ties go to the earlier one
+        let names = ["Quiet shift", "Storage strike", "Plant trouble"];
+        assert_eq!(tally(&names, ""), 0);
+        assert_eq!(tally(&names, "plant trouble\n2\n3\n9\nnonsense\n"), 2);
+        assert_eq!(tally(&names, "2\n3\n"), 1);
+        assert_eq!(rotation.choose(2).name, "Plant trouble");
+        // On round from the winner, wrapping past the end
+        assert_eq!(rotation.candidates(), vec![3, 0, 1]);
+
+        let scenario = RotationScenario { duration: 2.0, ..rotation.config().scenarios[1].clone() };
+        let report = play(&scenario, 7).unwrap();
+        assert_eq!((report.seed, report.ticks), (7, 120));
+        let mut stats = RotationStats::default();
+        stats.record(&scenario.name, &report);
+        stats.record(&scenario.name, &play(&scenario, 8).unwrap());
+        let strike = &stats.scenarios["Storage strike"];
+        assert_eq!((stats.rounds, strike.rounds), (2, 2));
+        assert!(strike.worst_integrity < 1.0 && strike.mean_integrity >= strike.worst_integrity);
+        assert!(strike.events.contains_key("hull_breach"));
+
+        let path = std::env::temp_dir().join(format!("rotation_stats_{}.json", std::process::id()));
+        stats.save(&path).unwrap();
+        assert_eq!(RotationStats::load(&path).unwrap(), stats);
+        let _ = fs::remove_file(&path);
+        assert_eq!(RotationStats::load(&path).unwrap(), RotationStats::default());
+    }
+}
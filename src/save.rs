@@ -0,0 +1,546 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use anyhow::{bail, Context, Result};
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+use crate::clock::MissionTime;
+use crate::crew::{Crew, CrewState};
+use crate::datapads::{DatapadState, Datapads};
+use crate::inventory::{Inventory, InventoryState, WorldItems, WorldItemsState};
+use crate::markers::{Marker, Markers};
+use crate::module_id::ModuleId;
+use crate::objectives::{Objectives, ObjectivesState};
+use crate::research::{Research, ResearchState};
+use crate::station::{SpaceStation, StationSnapshot};
+
+// Bump when the format changes and add a step to `migrate`
+pub const SAVE_VERSION: u32 = 4;
+
+// Numbered slots in the save menu, besides the quicksave
+pub const SAVE_SLOTS: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct PlayerState {
+    pub position: [f32; 3],
+    pub yaw: f32,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SaveGame {
+    pub version: u32,
+    // Unix seconds
+    pub saved_at: u64,
+    pub player: PlayerState,
+    pub station: StationSnapshot,
+    // Older saves have none
+    #[serde(default)]
+    pub markers: Vec<Marker>,
+    // Points, upgrades and the lab queues; likewise
+    #[serde(default)]
+    pub research: ResearchState,
+    // The rest of the session; saves without them start these afresh
+    #[serde(default)]
+    pub inventory: Option<InventoryState>,
+    #[serde(default)]
+    pub world_items: Option<WorldItemsState>,
+    #[serde(default)]
+    pub journal: Option<DatapadState>,
+    #[serde(default)]
+    pub objectives: Option<ObjectivesState>,
+    #[serde(default)]
+    pub crew: Option<CrewState>,
+}
+
+impl SaveGame {
+    pub fn capture(station: &SpaceStation, markers: &Markers, research: &Research, player: Vec3, yaw: f32) -> Self {
+        Self {
+            version: SAVE_VERSION,
+            saved_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_secs()),
+            player: PlayerState {
+                position: player.to_array(),
+                yaw,
+            },
+            station: station.snapshot(),
+            markers: markers.markers().to_vec(),
+            research: research.state(),
+            inventory: None,
+            world_items: None,
+            journal: None,
+            objectives: None,
+            crew: None,
+        }
+    }
+
+    // What the player's carrying and has read, the items about the station,
+    // the objectives and the crew
+    pub fn with_session(
+        mut self,
+        inventory: &Inventory,
+        world_items: &WorldItems,
+        datapads: &Datapads,
+        objectives: &Objectives,
+        crew: &Crew,
+    ) -> Self {
+        self.inventory = Some(inventory.state());
+        self.world_items = Some(world_items.state());
+        self.journal = Some(datapads.state());
+        self.objectives = Some(objectives.state());
+        self.crew = Some(crew.state());
+        self
+    }
+
+    pub fn read(path: &Path) -> Result<Self> {
+        let source = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+        let mut value: toml::Table = toml::from_str(&source).context("save file is not valid TOML")?;
+
+        let version = value
+            .get("version")
+            .and_then(|v| v.as_integer())
+            .context("save file has no version")? as u32;
+        if version > SAVE_VERSION {
+            bail!("save is version {}, this build only reads up to {}", version, SAVE_VERSION);
+        }
+        migrate(&mut value, version)?;
+        value.try_into().context("save file doesn't match the save format")
+    }
+
+    // Writes to a temporary file first so a crash can't leave a half-written save
+    pub fn write(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+        }
+        let contents = toml::to_string(self).context("failed to serialize save")?;
+        let temp = path.with_extension("tmp");
+        fs::write(&temp, contents).with_context(|| format!("failed to write {}", temp.display()))?;
+        fs::rename(&temp, path).with_context(|| format!("failed to replace {}", path.display()))?;
+        Ok(())
+    }
+
+    pub fn player_position(&self) -> Vec3 {
+        Vec3::from(self.player.position)
+    }
+}
+
+// Upgrades an older save in place, one version at a time
+fn migrate(value: &mut toml::Table, from: u32) -> Result<()> {
+    for version in from..SAVE_VERSION {
+        match version {
+            1 => doors_by_module_id(value)?,
+            2 => module_refs_by_id(value)?,
+            // Version 3 saved only the station, research and markers; the
+            // rest of the session is left out and starts afresh
+            3 => {}
+            _ => bail!("no migration from save version {}", version),
+        }
+    }
+    value.insert("version".into(), toml::Value::Integer(SAVE_VERSION as i64));
+    Ok(())
+}
+
+// Version 1 saved a door's modules as indices into the module list; now
+// they're ids. Doors onto modules that aren't there are dropped, as loading
+// always did.
+fn doors_by_module_id(value: &mut toml::Table) -> Result<()> {
+    let Some(station) = value.get_mut("station").and_then(toml::Value::as_table_mut) else {
+        return Ok(());
+    };
+    let ids = module_ids(station)?;
+    let Some(doors) = station.get_mut("doors").and_then(toml::Value::as_array_mut) else {
+        return Ok(());
+    };
+    doors.retain_mut(|door| {
+        let Some(modules) = door.get_mut("modules").and_then(toml::Value::as_array_mut) else {
+            return false;
+        };
+        modules.iter_mut().all(|module| by_id(module, &ids))
+    });
+    Ok(())
+}
+
+// Version 2 still saved connections, fires, stores and ring members as
+// indices. Like the doors, whatever points at a module that isn't there is
+// dropped.
+fn module_refs_by_id(value: &mut toml::Table) -> Result<()> {
+    let Some(station) = value.get_mut("station").and_then(toml::Value::as_table_mut) else {
+        return Ok(());
+    };
+    let ids = module_ids(station)?;
+    let in_place = |list: &mut toml::Value| {
+        if let Some(list) = list.as_array_mut() {
+            list.retain_mut(|module| by_id(module, &ids));
+        }
+    };
+    if let Some(modules) = station.get_mut("modules").and_then(toml::Value::as_array_mut) {
+        for module in modules {
+            module.get_mut("connected_modules").map(in_place);
+        }
+    }
+    if let Some(rings) = station.get_mut("rings").and_then(toml::Value::as_array_mut) {
+        for ring in rings {
+            ring.get_mut("modules").map(in_place);
+        }
+    }
+    for list in ["fires", "stores"] {
+        if let Some(entries) = station.get_mut(list).and_then(toml::Value::as_array_mut) {
+            entries.retain_mut(|entry| entry.get_mut("module").is_some_and(|module| by_id(module, &ids)));
+        }
+    }
+    Ok(())
+}
+
+// Each saved module's id, by index. Modules saved without one get the id a
+// fresh load gives them.
+fn module_ids(station: &toml::Table) -> Result<Vec<toml::Value>> {
+    let modules = station.get("modules").and_then(toml::Value::as_array).map_or(&[][..], Vec::as_slice);
+    modules
+        .iter()
+        .enumerate()
+        .map(|(i, module)| match module.get("id") {
+            Some(id) => Ok(id.clone()),
+            None => toml::Value::try_from(ModuleId::from_layout(i)),
+        })
+        .collect::<Result<Vec<_>, _>>()
+        .context("failed to give an older save's modules ids")
+}
+
+// Swaps a module index for its id; false if there's no such module
+fn by_id(module: &mut toml::Value, ids: &[toml::Value]) -> bool {
+    match module.as_integer().and_then(|index| ids.get(index as usize)) {
+        Some(id) => {
+            *module = id.clone();
+            true
+        }
+        None => false,
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SaveSlot {
+    Quick,
+    Numbered(usize),
+}
+
+impl SaveSlot {
+    // Quicksave first, then the numbered slots
+    pub fn all() -> Vec<SaveSlot> {
+        std::iter::once(SaveSlot::Quick)
+            .chain((1..=SAVE_SLOTS).map(SaveSlot::Numbered))
+            .collect()
+    }
+
+    pub fn path(self, dir: &Path) -> PathBuf {
+        match self {
+            SaveSlot::Quick => dir.join("quicksave.toml"),
+            SaveSlot::Numbered(n) => dir.join(format!("slot_{}.toml", n)),
+        }
+    }
+
+    pub fn label(self) -> String {
+        match self {
+            SaveSlot::Quick => "Quicksave".to_string(),
+            SaveSlot::Numbered(n) => format!("Slot {}", n),
+        }
+    }
+}
+
+// Per-user directory saves are written to
+pub fn save_dir() -> PathBuf {
+    dirs::data_local_dir()
+        .unwrap_or_else(|| PathBuf::from("."))
+        .join("space_station_3d")
+        .join("saves")
+}
+
+#[derive(Debug, Clone)]
+pub struct SlotSummary {
+    pub slot: SaveSlot,
+    // None for empty slots
+    pub mission_time: Option<MissionTime>,
+    pub saved_at: Option<u64>,
+    pub error: Option<String>,
+}
+
+impl SlotSummary {
+    pub fn read(slot: SaveSlot, dir: &Path) -> Self {
+        let path = slot.path(dir);
+        let mut summary = Self {
+            slot,
+            mission_time: None,
+            saved_at: None,
+            error: None,
+        };
+        if !path.exists() {
+            return summary;
+        }
+        match SaveGame::read(&path) {
+            Ok(save) => {
+                summary.mission_time = Some(MissionTime::from_millis(save.station.mission_time_ms));
+                summary.saved_at = Some(save.saved_at);
+            }
+            Err(err) => summary.error = Some(format!("{:#}", err)),
+        }
+        summary
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.mission_time.is_none() && self.error.is_none()
+    }
+
+    pub fn describe(&self) -> String {
+        match (&self.mission_time, &self.error) {
+            (_, Some(error)) => format!("{}: unreadable ({})", self.slot.label(), error),
+            (Some(time), None) => format!("{}: {}", self.slot.label(), time.format_met()),
+            (None, None) => format!("{}: empty", self.slot.label()),
+        }
+    }
+}
+
+// Slot picker; the list is read from disk when the menu opens
+#[derive(Debug)]
+pub struct SaveMenu {
+    pub selected: usize,
+    slots: Vec<SlotSummary>,
+}
+
+impl SaveMenu {
+    pub fn open(dir: &Path) -> Self {
+        let mut menu = Self {
+            selected: 0,
+            slots: Vec::new(),
+        };
+        menu.refresh(dir);
+        menu
+    }
+
+    pub fn refresh(&mut self, dir: &Path) {
+        self.slots = SaveSlot::all().into_iter().map(|slot| SlotSummary::read(slot, dir)).collect();
+        self.selected = self.selected.min(self.slots.len().saturating_sub(1));
+    }
+
+    pub fn slots(&self) -> &[SlotSummary] {
+        &self.slots
+    }
+
+    pub fn selected_slot(&self) -> Option<&SlotSummary> {
+        self.slots.get(self.selected)
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1).min(self.slots.len().saturating_sub(1));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::inventory::ItemKind;
+    use crate::markers::MarkerKind;
+    use crate::objectives::{Condition, ObjectiveDefinition, ObjectiveStatus};
+    use crate::ring::RingSpec;
+    use crate::station::ElementState;
+    use crate::stepping::STEP_DT;
+
+    fn temp_path(name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("{}_{}.toml", name, std::process::id()))
+    }
+
+    #[test]
+    fn saves_read_back_as_written() {
+        let mut station = SpaceStation::create_default_layout();
+        station.damage_module(5, 0.4);
+        let mut markers = Markers::new();
+        markers.place(MarkerKind::Danger, "Loose panel", Vec3::new(2.0, 0.0, -3.0));
+        let save = SaveGame::capture(&station, &markers, &Research::default(), Vec3::new(1.0, 1.7, -4.0), 0.25);
+
+        let path = temp_path("save_round_trip");
+        save.write(&path).unwrap();
+        let read = SaveGame::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(read, save);
+        assert_eq!(read.player_position(), Vec3::new(1.0, 1.7, -4.0));
+        assert_eq!(SpaceStation::from_snapshot(&read.station).snapshot(), station.snapshot());
+        assert!(!path.with_extension("tmp").exists());
+    }
+
+    #[test]
+    fn the_rest_of_the_session_reads_back_and_restores_over_another() {
+        let mut station = SpaceStation::create_default_layout();
+        let mut inventory = Inventory::new();
+        inventory.add(ItemKind::RepairKit, 2);
+        inventory.select(3);
+        let mut world_items = WorldItems::load("assets/items.toml", &station).unwrap();
+        world_items.place(ItemKind::WorkLight, 1, Vec3::new(1.0, 0.0, -2.0));
+        let mut datapads = Datapads::load("assets/datapads.toml", &mut station).unwrap();
+        let memo = datapads.placed().iter().position(|pad| pad.definition.id == "quartermaster_memo").unwrap();
+        datapads.collect(memo, &mut station).unwrap();
+        let mut objectives = Objectives::load("assets/objectives.toml").unwrap();
+        station.set_element_state(0, 0, ElementState::Active);
+        objectives.update(&mut station, Vec3::ZERO);
+        objectives.add(ObjectiveDefinition {
+            id: "incident1".to_string(),
+            title: "Seal module 3".to_string(),
+            description: String::new(),
+            condition: Condition::ModuleSealed { module: 3 },
+            next: None,
+            activate_on: None,
+            no_fast_travel: false,
+        });
+        let mut crew = Crew::load("assets/crew.toml", &station).unwrap();
+        crew.schedule_maintenance(station.modules()[1].id, 0);
+        for _ in 0..600 {
+            station.update(STEP_DT);
+            crew.update(&mut station, STEP_DT);
+        }
+        let save = SaveGame::capture(&station, &Markers::new(), &Research::default(), Vec3::ZERO, 0.0)
+            .with_session(&inventory, &world_items, &datapads, &objectives, &crew);
+
+        let path = temp_path("save_session");
+        save.write(&path).unwrap();
+        let read = SaveGame::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(read, save);
+
+        // Loaded over a session that's gone its own way
+        let mut other = SpaceStation::create_default_layout();
+        let mut restored_inventory = Inventory::new();
+        restored_inventory.add(ItemKind::WetVac, 1);
+        let mut restored_items = WorldItems::new();
+        let mut restored_datapads = Datapads::load("assets/datapads.toml", &mut other).unwrap();
+        let mut restored_objectives = Objectives::load("assets/objectives.toml").unwrap();
+        let mut restored_crew = Crew::new();
+        restored_inventory.restore(read.inventory.as_ref().unwrap());
+        restored_items.restore(read.world_items.as_ref().unwrap());
+        restored_datapads.restore(read.journal.as_ref().unwrap());
+        restored_objectives.restore(read.objectives.as_ref().unwrap());
+        restored_crew.restore(read.crew.as_ref().unwrap());
+        assert_eq!(restored_inventory.state(), inventory.state());
+        assert_eq!(restored_items.state(), world_items.state());
+        assert_eq!(restored_datapads.state(), datapads.state());
+        assert!(restored_datapads.placed()[memo].collected);
+        assert_eq!(restored_objectives.state(), objectives.state());
+        assert_eq!(restored_objectives.status("log_in"), Some(ObjectiveStatus::Completed));
+        assert_eq!(restored_objectives.status("incident1"), Some(ObjectiveStatus::Active));
+        assert_eq!(restored_crew.state(), crew.state());
+        assert_eq!(restored_crew.tasks().len(), 1);
+    }
+
+    // Puts a save's connections, fires, stores and ring members back to
+    // indices, as version 2 wrote them
+    fn by_index(value: &mut toml::Table, station: &SpaceStation) {
+        let index = |id: &toml::Value| {
+            let id: ModuleId = id.clone().try_into().unwrap();
+            toml::Value::Integer(station.module_index(id).unwrap() as i64)
+        };
+        let indices = |list: &mut toml::Value| *list = toml::Value::Array(list.as_array().unwrap().iter().map(index).collect());
+        let saved = value["station"].as_table_mut().unwrap();
+        for module in saved["modules"].as_array_mut().unwrap() {
+            indices(&mut module["connected_modules"]);
+        }
+        for ring in saved["rings"].as_array_mut().unwrap() {
+            indices(&mut ring["modules"]);
+        }
+        for list in ["fires", "stores"] {
+            for entry in saved[list].as_array_mut().unwrap() {
+                entry["module"] = index(&entry["module"]);
+            }
+        }
+    }
+
+    #[test]
+    fn version_2_saves_have_connections_fires_stores_and_rings_moved_onto_module_ids() {
+        let mut station = SpaceStation::create_default_layout();
+        station.ignite(3);
+        let spec = RingSpec {
+            name: "Lab ring".to_string(),
+            centre: [0.0, 50.0, 0.0],
+            axis: [1.0, 0.0, 0.0],
+            rpm: 1.0,
+            modules: vec![station.modules()[5].id],
+            angle: 0.0,
+        };
+        station.add_ring(&spec).unwrap();
+        let save = SaveGame::capture(&station, &Markers::new(), &Research::default(), Vec3::ZERO, 0.0);
+        assert!(!save.station.fires.is_empty() && !save.station.rings.is_empty());
+
+        // Written as version 2 did, with a fire in a module that's gone
+        let mut value = toml::Table::try_from(&save).unwrap();
+        value.insert("version".into(), toml::Value::Integer(2));
+        by_index(&mut value, &station);
+        let fires = value["station"]["fires"].as_array_mut().unwrap();
+        let mut stale = fires[0].clone();
+        stale["module"] = toml::Value::Integer(40);
+        fires.push(stale);
+
+        let path = temp_path("save_version_2");
+        fs::write(&path, toml::to_string(&value).unwrap()).unwrap();
+        let read = SaveGame::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(read.version, SAVE_VERSION);
+        assert_eq!(read.station, save.station);
+    }
+
+    #[test]
+    fn version_1_saves_have_their_doors_moved_onto_module_ids() {
+        let mut station = SpaceStation::create_default_layout();
+        let door = station.door_between(7, 11).unwrap();
+        station.set_door_locked(door, true);
+        let save = SaveGame::capture(&station, &Markers::new(), &Research::default(), Vec3::ZERO, 0.0);
+
+        // Written as version 1 did: door modules by index, one of them stale
+        let mut value = toml::Table::try_from(&save).unwrap();
+        value.insert("version".into(), toml::Value::Integer(1));
+        by_index(&mut value, &station);
+        let doors = value["station"]["doors"].as_array().unwrap().clone();
+        let mut old_doors: Vec<toml::Value> = doors
+            .into_iter()
+            .map(|mut door| {
+                let [a, b]: [ModuleId; 2] = door["modules"].clone().try_into().unwrap();
+                let index = |id: ModuleId| toml::Value::Integer(station.module_index(id).unwrap() as i64);
+                door["modules"] = toml::Value::Array(vec![index(a), index(b)]);
+                door
+            })
+            .collect();
+        let mut stale = old_doors[0].clone();
+        stale["modules"] = toml::Value::Array(vec![toml::Value::Integer(0), toml::Value::Integer(40)]);
+        old_doors.push(stale);
+        value["station"].as_table_mut().unwrap().insert("doors".into(), toml::Value::Array(old_doors));
+
+        let path = temp_path("save_version_1");
+        fs::write(&path, toml::to_string(&value).unwrap()).unwrap();
+        let read = SaveGame::read(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(read.version, SAVE_VERSION);
+        assert_eq!(read.station, save.station);
+    }
+
+    #[test]
+    fn saves_from_a_newer_build_are_refused() {
+        let save = SaveGame::capture(
+            &SpaceStation::create_default_layout(),
+            &Markers::new(),
+            &Research::default(),
+            Vec3::ZERO,
+            0.0,
+        );
+        let path = temp_path("save_from_the_future");
+        SaveGame { version: SAVE_VERSION + 1, ..save }.write(&path).unwrap();
+        let err = SaveGame::read(&path).unwrap_err();
+        assert!(format!("{:#}", err).contains(&format!("only reads up to {}", SAVE_VERSION)));
+
+        // And the menu shows the slot as unreadable rather than empty
+        let dir = std::env::temp_dir().join(format!("save_slots_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::rename(&path, SaveSlot::Quick.path(&dir)).unwrap();
+        let summary = SlotSummary::read(SaveSlot::Quick, &dir);
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert!(!summary.is_empty() && summary.mission_time.is_none());
+        assert!(summary.describe().starts_with("Quicksave: unreadable"));
+    }
+}
@@ -0,0 +1,232 @@
+use glam::Vec3;
+use crate::door::DOOR_HEIGHT;
+use crate::station::{ModuleType, SpaceStation};
+
+// How far above a module's centre its nameplate hangs
+const NAMEPLATE_HEIGHT: f32 = 2.4;
+// Corridor arrows hang a little lower, and this far along towards where they point
+const ARROW_HEIGHT: f32 = 1.8;
+const ARROW_OFFSET: f32 = 1.5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignKind {
+    // The module's designation, over its middle
+    Nameplate,
+    // Over a doorway, naming the module it leads into
+    DoorLabel,
+    // In a corridor, pointing the way to the modules listed
+    Arrow,
+}
+
+#[derive(Debug, Clone)]
+pub struct Sign {
+    pub kind: SignKind,
+    pub module: usize,
+    pub position: Vec3,
+    // Which way it reads from: the side it can be seen from for door labels,
+    // the way to go for arrows, zero for nameplates seen from anywhere
+    pub direction: Vec3,
+    pub text: String,
+}
+
+// Short code and role painted on a module of each type
+fn code(module_type: ModuleType) -> (&'static str, &'static str) {
+    match module_type {
+        ModuleType::Corridor => ("COR", "TRANSIT"),
+        ModuleType::Hub => ("HUB", "JUNCTION"),
+        ModuleType::Airlock => ("ALK", "EVA"),
+        ModuleType::LivingQuarters => ("HAB", "QUARTERS"),
+        ModuleType::CommandCenter => ("CMD", "COMMAND"),
+        ModuleType::Laboratory => ("LAB", "RESEARCH"),
+        ModuleType::Storage => ("STO", "STORES"),
+        ModuleType::PowerPlant => ("PWR", "POWER"),
+        ModuleType::EscapePod => ("POD", "ESCAPE"),
+        ModuleType::SolarArray => ("SOL", "SOLAR"),
+        ModuleType::Battery => ("BAT", "STORAGE CELLS"),
+    }
+}
+
+// Whether corridors point the way to modules of this type
+fn key_module(module_type: ModuleType) -> bool {
+    !matches!(module_type, ModuleType::Corridor | ModuleType::Hub)
+}
+
+// What the signs were made for; when it changes they're made again
+#[derive(Debug, Clone, PartialEq)]
+struct LayoutKey {
+    modules: Vec<(ModuleType, Vec3, Vec<usize>)>,
+}
+
+impl LayoutKey {
+    fn of(station: &SpaceStation) -> Self {
+        let modules = station
+            .modules()
+            .iter()
+            .enumerate()
+            .map(|(i, module)| (module.module_type, module.transform.position, station.neighbours(i)))
+            .collect();
+        Self { modules }
+    }
+}
+
+// Module designations like "LAB-1 // RESEARCH", numbered through the
+// modules of each type in order
+pub fn designations(station: &SpaceStation) -> Vec<String> {
+    let mut counts: Vec<(ModuleType, usize)> = Vec::new();
+    station
+        .modules()
+        .iter()
+        .map(|module| {
+            let count = match counts.iter_mut().find(|(t, _)| *t == module.module_type) {
+                Some((_, count)) => {
+                    *count += 1;
+                    *count
+                }
+                None => {
+                    counts.push((module.module_type, 1));
+                    1
+                }
+            };
+            let (code, role) = code(module.module_type);
+            format!("{}-{} // {}", code, count, role)
+        })
+        .collect()
+}
+
+// Signs made from the station's layout: a nameplate for every module, a
+// label each side of every doorway, and arrows in corridors towards every
+// module that isn't just a way through
+#[derive(Debug)]
+pub struct Signage {
+    signs: Vec<Sign>,
+    key: Option<LayoutKey>,
+}
+
+impl Default for Signage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Signage {
+    pub fn new() -> Self {
+        Self { signs: Vec::new(), key: None }
+    }
+
+    pub fn signs(&self) -> &[Sign] {
+        &self.signs
+    }
+
+    // Makes the signs again if the layout has changed since they were made;
+    // true if it did
+    pub fn refresh(&mut self, station: &SpaceStation) -> bool {
+        let key = LayoutKey::of(station);
+        if self.key.as_ref() == Some(&key) {
+            return false;
+        }
+        self.signs = generate(station);
+        self.key = Some(key);
+        true
+    }
+}
+
+pub fn generate(station: &SpaceStation) -> Vec<Sign> {
+    let modules = station.modules();
+    let names = designations(station);
+    let short = |i: usize| names[i].split(" // ").next().unwrap_or_default().to_string();
+    let mut signs = Vec::new();
+
+    for (i, module) in modules.iter().enumerate() {
+        signs.push(Sign {
+            kind: SignKind::Nameplate,
+            module: i,
+            position: module.transform.position + Vec3::Y * NAMEPLATE_HEIGHT,
+            direction: Vec3::ZERO,
+            text: names[i].clone(),
+        });
+    }
+
+    // Each side of a doorway reads from the module it's seen from
+    for (index, door) in station.doors().iter().enumerate() {
+        let Some((first, second)) = station.door_modules(index) else {
+            continue;
+        };
+        let over = door.position + Vec3::Y * (DOOR_HEIGHT * 0.5 + 0.2);
+        for (from, to, facing) in [(first, second, -door.facing), (second, first, door.facing)] {
+            signs.push(Sign {
+                kind: SignKind::DoorLabel,
+                module: from,
+                position: over + facing * 0.05,
+                direction: facing,
+                text: short(to),
+            });
+        }
+    }
+
+    // One arrow per way out of a corridor, listing what's that way
+    for (i, module) in modules.iter().enumerate() {
+        if module.module_type != ModuleType::Corridor {
+            continue;
+        }
+        let mut ways: Vec<(usize, Vec<String>)> = Vec::new();
+        for target in (0..modules.len()).filter(|&t| t != i && key_module(modules[t].module_type)) {
+            let Some(&next) = station.find_path(i, target).as_ref().and_then(|path| path.get(1)) else {
+                continue;
+            };
+            match ways.iter_mut().find(|(way, _)| *way == next) {
+                Some((_, targets)) => targets.push(short(target)),
+                None => ways.push((next, vec![short(target)])),
+            }
+        }
+        for (next, targets) in ways {
+            let toward = modules[next].transform.position - module.transform.position;
+            let direction = Vec3::new(toward.x, 0.0, toward.z).normalize_or_zero();
+            signs.push(Sign {
+                kind: SignKind::Arrow,
+                module: i,
+                position: module.transform.position + Vec3::Y * ARROW_HEIGHT + direction * ARROW_OFFSET,
+                direction,
+                text: targets.join("  "),
+            });
+        }
+    }
+    signs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn signage_names_modules_labels_doors_and_points_the_way() {
+        let mut station = SpaceStation::create_default_layout();
+        let names = designations(&station);
+        assert_eq!(names[0], "CMD-1 // COMMAND");
+        assert_eq!(names[3], "COR-3 // TRANSIT");
+        assert_eq!(names[5], "LAB-1 // RESEARCH");
+
+        let mut signage = Signage::new();
+        assert!(signage.refresh(&station));
+        assert!(!signage.refresh(&station));
+        // Leaving the east corridor for the quarters, the door says where it goes
+        let door = station.doors()[station.door_between(2, 6).unwrap()].position;
+        let label = signage
+            .signs()
+            .iter()
+            .find(|sign| sign.kind == SignKind::DoorLabel && sign.module == 2 && sign.position.distance(door) < 2.0)
+            .unwrap();
+        assert_eq!(label.text, "HAB-1");
+        // Everything but the quarters is back through the hub
+        let arrows: Vec<_> = signage.signs().iter().filter(|sign| sign.kind == SignKind::Arrow && sign.module == 2).collect();
+        assert_eq!(arrows.len(), 2);
+        let back = arrows.iter().find(|sign| sign.text.contains("LAB-1")).unwrap();
+        assert!(back.text.contains("PWR-1") && !back.text.contains("HAB-1"));
+        let toward_hub = station.modules()[0].transform.position - station.modules()[2].transform.position;
+        assert!(back.direction.dot(toward_hub) > 0.0);
+
+        let lab = station.add_module(ModuleType::Laboratory, station.modules()[5].transform.position + Vec3::X * 8.0);
+        station.connect_modules(5, lab);
+        assert!(signage.refresh(&station));
+        assert!(signage.signs().iter().any(|sign| sign.kind == SignKind::Nameplate && sign.text == "LAB-2 // RESEARCH"));
+    }
+}
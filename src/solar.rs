@@ -0,0 +1,38 @@
+use glam::{Quat, Vec3};
+
+// Each wing of a solar array: how far its near edge stands off the hub, and
+// its length and width
+const WING_OFFSET: f32 = 2.5;
+const WING_LENGTH: f32 = 10.0;
+const WING_WIDTH: f32 = 3.5;
+
+// Share of full output the panels get facing `facing`, with `sunlight` 0 in
+// the planet's shadow to 1 in full sun. Nothing from behind.
+pub fn exposure(facing: Vec3, sun_direction: Vec3, sunlight: f32) -> f32 {
+    facing.dot(sun_direction).max(0.0) * sunlight.clamp(0.0, 1.0)
+}
+
+// Turns `facing` toward `target` by at most `max_angle` radians, as the
+// tracking motor does in one update
+pub fn slew(facing: Vec3, target: Vec3, max_angle: f32) -> Vec3 {
+    let angle = facing.angle_between(target);
+    if angle <= max_angle {
+        return target.try_normalize().unwrap_or(facing);
+    }
+    // Straight away from the sun there's no preferred way round; turn about the
+    // orbit normal
+    let axis = facing.cross(target).try_normalize().unwrap_or(Vec3::Z);
+    (Quat::from_axis_angle(axis, max_angle) * facing).normalize()
+}
+
+// Corners of the two wings either side of the hub at `centre`, spread along
+// the orbit normal and turned to face `facing`
+pub fn wings(centre: Vec3, facing: Vec3) -> [[Vec3; 4]; 2] {
+    let span = (Vec3::Z - facing * facing.z).try_normalize().unwrap_or(Vec3::X);
+    let across = facing.cross(span) * (WING_WIDTH * 0.5);
+    [1.0, -1.0].map(|side| {
+        let near = centre + span * (side * WING_OFFSET);
+        let far = centre + span * (side * (WING_OFFSET + WING_LENGTH));
+        [near - across, far - across, far + across, near + across]
+    })
+}
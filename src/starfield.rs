@@ -0,0 +1,270 @@
+use glam::{Quat, Vec3};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+const STARS_PER_LAYER: f32 = 8000.0;
+
+#[derive(Debug, Clone)]
+pub struct StarfieldConfig {
+    pub seed: u64,
+    pub layer_count: u32,
+    pub density: f32,
+    pub brightness: f32,
+    pub twinkle_amount: f32,
+    pub twinkle_speed: f32,
+    pub near_distance: f32,
+    pub far_distance: f32,
+}
+
+impl Default for StarfieldConfig {
+    fn default() -> Self {
+        Self {
+            seed: 0x5747_4152,
+            layer_count: 4,
+            density: 1.0,
+            brightness: 1.0,
+            twinkle_amount: 0.35,
+            twinkle_speed: 1.5,
+            near_distance: 80.0,
+            far_distance: 400.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Star {
+    pub direction: Vec3,
+    pub magnitude: f32,
+    pub color: Vec3,
+    pub size: f32,
+    pub twinkle_phase: f32,
+}
+
+#[derive(Debug, Clone)]
+pub struct StarLayer {
+    pub distance: f32,
+    // How much the layer follows the camera: 1.0 behaves like a skybox,
+    // lower values let nearer layers drift slightly as the viewer moves
+    pub camera_follow: f32,
+    pub stars: Vec<Star>,
+}
+
+// Per-instance data for the star shader; the twinkle is added on the GPU
+#[derive(Debug, Clone, Copy)]
+pub struct StarInstance {
+    pub position: Vec3,
+    pub size: f32,
+    pub color: Vec3,
+    pub alpha: f32,
+    pub twinkle_phase: f32,
+}
+
+impl StarInstance {
+    // Packed into the 4x4 instance transform raylib feeds the vertex shader,
+    // column-major: column 3 holds the position as usual, column 0 carries
+    // size, alpha and twinkle phase, and the bottom row the colour.
+    pub fn packed(&self) -> [f32; 16] {
+        [
+            self.size, self.alpha, self.twinkle_phase, self.color.x,
+            0.0, 0.0, 0.0, self.color.y,
+            0.0, 0.0, 0.0, self.color.z,
+            self.position.x, self.position.y, self.position.z, 1.0,
+        ]
+    }
+}
+
+// Each star is a unit quad in the XZ plane, turned to face the camera and
+// sized in pixels. The twinkle is a few incommensurate sines offset by the
+// star's phase, so no two stars flicker together.
+pub const STAR_VERTEX_SHADER: &str = r#"#version 330
+in vec3 vertexPosition;
+in mat4 instanceTransform;
+
+uniform mat4 matView;
+uniform mat4 matProjection;
+uniform float time;
+uniform float twinkleSpeed;
+uniform float twinkleAmount;
+uniform vec2 pixelSize;
+
+out vec4 starColor;
+out vec2 spriteOffset;
+
+void main()
+{
+    vec3 center = instanceTransform[3].xyz;
+    float size = instanceTransform[0].x;
+    float alpha = instanceTransform[0].y;
+    float phase = instanceTransform[0].z;
+    vec3 color = vec3(instanceTransform[0].w, instanceTransform[1].w, instanceTransform[2].w);
+
+    float t = time*twinkleSpeed + phase;
+    float noise = sin(t*1.7)*0.5 + sin(t*2.9 + 1.3)*0.3 + sin(t*5.3 + 2.1)*0.2;
+    float twinkle = 1.0 - twinkleAmount*(noise*0.5 + 0.5);
+    starColor = vec4(color, clamp(alpha*twinkle, 0.0, 1.0));
+
+    spriteOffset = vertexPosition.xz*2.0;
+    gl_Position = matProjection*matView*vec4(center, 1.0);
+    gl_Position.xy += vertexPosition.xz*size*pixelSize*gl_Position.w;
+}
+"#;
+
+pub const STAR_FRAGMENT_SHADER: &str = r#"#version 330
+in vec4 starColor;
+in vec2 spriteOffset;
+
+out vec4 finalColor;
+
+void main()
+{
+    // Round, soft-edged points rather than squares
+    float falloff = 1.0 - smoothstep(0.5, 1.0, length(spriteOffset));
+    finalColor = vec4(starColor.rgb, starColor.a*falloff);
+}
+"#;
+
+#[derive(Debug)]
+pub struct Starfield {
+    pub config: StarfieldConfig,
+    layers: Vec<StarLayer>,
+    attitude: Quat,
+    time: f32,
+}
+
+impl Starfield {
+    pub fn generate(config: StarfieldConfig) -> Self {
+        let mut rng = StdRng::seed_from_u64(config.seed);
+        let layer_count = config.layer_count.max(1);
+        let stars_per_layer = (STARS_PER_LAYER * config.density.max(0.0)) as usize;
+
+        let layers = (0..layer_count)
+            .map(|i| {
+                let t = if layer_count > 1 {
+                    i as f32 / (layer_count - 1) as f32
+                } else {
+                    1.0
+                };
+                let distance = config.near_distance + (config.far_distance - config.near_distance) * t;
+                let stars = (0..stars_per_layer).map(|_| random_star(&mut rng)).collect();
+
+                StarLayer {
+                    distance,
+                    camera_follow: 0.9 + 0.1 * t,
+                    stars,
+                }
+            })
+            .collect();
+
+        Self {
+            config,
+            layers,
+            attitude: Quat::IDENTITY,
+            time: 0.0,
+        }
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        self.time += delta_time;
+    }
+
+    // The station's orientation against the stars, which are drawn turned
+    // the other way
+    pub fn set_attitude(&mut self, attitude: Quat) {
+        self.attitude = attitude;
+    }
+
+    // A new density deals the stars again from the same seed
+    pub fn set_density(&mut self, density: f32) {
+        if density != self.config.density {
+            let config = StarfieldConfig { density, ..self.config.clone() };
+            self.layers = Starfield::generate(config.clone()).layers;
+            self.config = config;
+        }
+    }
+
+    // Seconds since generation, for the shader's twinkle
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn instances(&self, camera_position: Vec3) -> impl Iterator<Item = StarInstance> + '_ {
+        let rotation = self.attitude.inverse();
+
+        self.layers.iter().flat_map(move |layer| {
+            let origin = camera_position * layer.camera_follow;
+            layer.stars.iter().map(move |star| StarInstance {
+                position: origin + rotation * star.direction * layer.distance,
+                size: star.size,
+                color: star.color,
+                alpha: (star.magnitude * self.config.brightness).clamp(0.0, 1.0),
+                twinkle_phase: star.twinkle_phase,
+            })
+        })
+    }
+}
+
+fn random_star(rng: &mut StdRng) -> Star {
+    // Uniform direction on the unit sphere
+    let z: f32 = rng.gen_range(-1.0..1.0);
+    let theta: f32 = rng.gen_range(0.0..std::f32::consts::TAU);
+    let r = (1.0 - z * z).sqrt();
+    let direction = Vec3::new(r * theta.cos(), z, r * theta.sin());
+
+    // Most stars are faint; a few are bright
+    let magnitude = rng.gen::<f32>().powf(4.0) * 0.85 + 0.15;
+
+    let color = match rng.gen_range(0..10) {
+        0 => Vec3::new(0.7, 0.8, 1.0),
+        1 | 2 => Vec3::new(1.0, 0.95, 0.8),
+        3 => Vec3::new(1.0, 0.8, 0.6),
+        _ => Vec3::ONE,
+    };
+
+    Star {
+        direction,
+        magnitude,
+        color,
+        size: 0.5 + magnitude * 1.5,
+        twinkle_phase: rng.gen_range(0.0..std::f32::consts::TAU),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::orbit::Orbit;
+
+    fn one_layer(density: f32) -> Starfield {
+        Starfield::generate(StarfieldConfig {
+            layer_count: 1,
+            density,
+            near_distance: 100.0,
+            far_distance: 100.0,
+            ..StarfieldConfig::default()
+        })
+    }
+
+    #[test]
+    fn stars_turn_with_the_orbit_like_the_sun() {
+        let orbit = Orbit::low_earth();
+        let mut starfield = one_layer(0.01);
+        let before: Vec<Vec3> = starfield.instances(Vec3::ZERO).map(|star| star.position / 100.0).collect();
+        let time = orbit.period / 4.0;
+        starfield.set_attitude(orbit.attitude(time));
+        // The sun keeps its place among the stars
+        let turn = orbit.attitude(time).inverse();
+        assert!((turn * orbit.sun_direction(0.0)).distance(orbit.sun_direction(time)) < 1e-4);
+        for (star, was) in starfield.instances(Vec3::ZERO).zip(before) {
+            assert!((star.position / 100.0).distance(turn * was) < 1e-4);
+        }
+    }
+
+    #[test]
+    fn density_changes_deal_the_stars_again() {
+        let mut starfield = one_layer(0.01);
+        let sparse = starfield.instances(Vec3::ZERO).count();
+        starfield.set_density(0.02);
+        assert_eq!(starfield.instances(Vec3::ZERO).count(), sparse * 2);
+        assert_eq!(starfield.config.density, 0.02);
+    }
+}
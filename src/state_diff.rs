@@ -0,0 +1,251 @@
+use std::collections::VecDeque;
+use anyhow::{Context, Result};
+use toml::Value;
+use crate::station::StationSnapshot;
+
+// Floats closer than this count as unchanged
+const EPSILON: f64 = 1e-6;
+
+// Snapshots kept for diffs; ten seconds at 60 frames a second
+pub const HISTORY_TICKS: usize = 600;
+
+// Recent snapshots, one a frame under the tick it ended on, so
+// `diff tick:1000 tick:1001` works after the fact
+#[derive(Debug)]
+pub struct SnapshotHistory {
+    capacity: usize,
+    entries: VecDeque<(u64, StationSnapshot)>,
+}
+
+impl SnapshotHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            entries: VecDeque::with_capacity(capacity),
+        }
+    }
+
+    pub fn record(&mut self, tick: u64, snapshot: StationSnapshot) {
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back((tick, snapshot));
+    }
+
+    pub fn get(&self, tick: u64) -> Option<&StationSnapshot> {
+        self.entries.iter().find(|(t, _)| *t == tick).map(|(_, snapshot)| snapshot)
+    }
+
+    // Oldest and newest recorded ticks
+    pub fn range(&self) -> Option<(u64, u64)> {
+        Some((self.entries.front()?.0, self.entries.back()?.0))
+    }
+
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    Modified { from: String, to: String },
+    Added(String),
+    Removed(String),
+}
+
+// One field or group in the diff tree; groups only exist if something under
+// them changed
+#[derive(Debug, Clone)]
+pub struct DiffNode {
+    pub name: String,
+    pub change: Option<Change>,
+    pub children: Vec<DiffNode>,
+}
+
+impl DiffNode {
+    fn change_count(&self) -> usize {
+        self.change.is_some() as usize + self.children.iter().map(DiffNode::change_count).sum::<usize>()
+    }
+
+    fn write_lines(&self, depth: usize, lines: &mut Vec<String>) {
+        let indent = "  ".repeat(depth);
+        let line = match &self.change {
+            Some(Change::Modified { from, to }) => format!("{}{}: {} -> {}", indent, self.name, from, to),
+            Some(Change::Added(value)) => format!("{}+ {}: {}", indent, self.name, value),
+            Some(Change::Removed(value)) => format!("{}- {}: {}", indent, self.name, value),
+            None => format!("{}{}", indent, self.name),
+        };
+        lines.push(line);
+        for child in &self.children {
+            child.write_lines(depth + 1, lines);
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StateDiff {
+    pub from: String,
+    pub to: String,
+    root: Option<DiffNode>,
+}
+
+impl StateDiff {
+    pub fn between(from_label: &str, from: &StationSnapshot, to_label: &str, to: &StationSnapshot) -> Result<Self> {
+        let from_value = Value::try_from(from).context("failed to serialize snapshot")?;
+        let to_value = Value::try_from(to).context("failed to serialize snapshot")?;
+        Ok(Self {
+            from: from_label.to_string(),
+            to: to_label.to_string(),
+            root: diff_values("station".to_string(), Some(&from_value), Some(&to_value)),
+        })
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn change_count(&self) -> usize {
+        self.root.as_ref().map_or(0, DiffNode::change_count)
+    }
+
+    // Indented tree, one changed field per line
+    pub fn lines(&self) -> Vec<String> {
+        let mut lines = vec![format!("{} -> {}: {} change(s)", self.from, self.to, self.change_count())];
+        if let Some(root) = &self.root {
+            root.write_lines(0, &mut lines);
+        }
+        lines
+    }
+}
+
+fn diff_values(name: String, from: Option<&Value>, to: Option<&Value>) -> Option<DiffNode> {
+    let leaf = |change| {
+        Some(DiffNode {
+            name: name.clone(),
+            change: Some(change),
+            children: Vec::new(),
+        })
+    };
+    match (from, to) {
+        (Some(Value::Table(a)), Some(Value::Table(b))) => {
+            let mut keys: Vec<&String> = a.keys().collect();
+            keys.extend(b.keys().filter(|key| !a.contains_key(*key)));
+            let children = keys
+                .into_iter()
+                .filter_map(|key| diff_values(key.clone(), a.get(key), b.get(key)))
+                .collect();
+            group(name, children)
+        }
+        // Lists of plain values (positions, connections) read better on one line
+        (Some(Value::Array(a)), Some(Value::Array(b))) if a.iter().any(Value::is_table) || b.iter().any(Value::is_table) => {
+            let children = (0..a.len().max(b.len()))
+                .filter_map(|i| {
+                    let label = a.get(i).or(b.get(i)).map_or(String::new(), entry_label);
+                    diff_values(format!("[{}]{}", i, label), a.get(i), b.get(i))
+                })
+                .collect();
+            group(name, children)
+        }
+        (Some(a), Some(b)) if values_equal(a, b) => None,
+        (Some(a), Some(b)) => leaf(Change::Modified {
+            from: format_value(a),
+            to: format_value(b),
+        }),
+        (None, Some(b)) => leaf(Change::Added(format_value(b))),
+        (Some(a), None) => leaf(Change::Removed(format_value(a))),
+        (None, None) => None,
+    }
+}
+
+fn group(name: String, children: Vec<DiffNode>) -> Option<DiffNode> {
+    (!children.is_empty()).then_some(DiffNode {
+        name,
+        change: None,
+        children,
+    })
+}
+
+// Names list entries after their type, e.g. "[5] Laboratory"
+fn entry_label(value: &Value) -> String {
+    ["module_type", "element_type"]
+        .iter()
+        .find_map(|key| value.get(key).and_then(Value::as_str))
+        .map_or(String::new(), |kind| format!(" {}", kind))
+}
+
+fn values_equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Float(x), Value::Float(y)) => (x - y).abs() <= EPSILON,
+        (Value::Array(x), Value::Array(y)) => x.len() == y.len() && x.iter().zip(y).all(|(x, y)| values_equal(x, y)),
+        _ => a == b,
+    }
+}
+
+fn format_value(value: &Value) -> String {
+    match value {
+        Value::Float(x) => format!("{:.4}", x),
+        Value::String(s) => s.clone(),
+        Value::Array(items) => {
+            let items: Vec<String> = items.iter().map(format_value).collect();
+            format!("[{}]", items.join(", "))
+        }
+        other => other.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::station::SpaceStation;
+
+    #[test]
+    fn diffs_list_only_what_changed_under_the_module_it_changed_in() {
+        let mut station = SpaceStation::create_default_layout();
+        let before = station.snapshot();
+        let unchanged = StateDiff::between("a", &before, "b", &station.snapshot()).unwrap();
+        assert!(unchanged.is_empty());
+        assert_eq!(unchanged.lines(), ["a -> b: 0 change(s)"]);
+
+        station.damage_module(5, 0.4);
+        let diff = StateDiff::between("tick:1", &before, "tick:2", &station.snapshot()).unwrap();
+        assert_eq!(diff.change_count(), 3);
+        assert_eq!(
+            diff.lines()[1..5],
+            ["station", "  modules", "    [5] Laboratory", "      life_support: 1.0000 -> 0.8000"]
+        );
+        assert!(diff.lines().contains(&"      structural_integrity: 1.0000 -> 0.6000".to_string()));
+    }
+
+    #[test]
+    fn fields_come_and_go_and_float_noise_is_ignored() {
+        let from: Value = toml::from_str("power = 1.0\nold = \"gone\"\npos = [1.0, 2.0]").unwrap();
+        let to: Value = toml::from_str("power = 1.0000001\nnew = 3\npos = [1.0, 2.5]").unwrap();
+        let root = diff_values("root".to_string(), Some(&from), Some(&to)).unwrap();
+        let changes: Vec<_> = root.children.iter().map(|node| (node.name.as_str(), node.change.clone())).collect();
+        assert_eq!(
+            changes,
+            [
+                ("old", Some(Change::Removed("gone".to_string()))),
+                (
+                    "pos",
+                    Some(Change::Modified { from: "[1.0000, 2.0000]".to_string(), to: "[1.0000, 2.5000]".to_string() }),
+                ),
+                ("new", Some(Change::Added("3".to_string()))),
+            ]
+        );
+    }
+
+    #[test]
+    fn history_keeps_only_the_latest_ticks() {
+        let snapshot = SpaceStation::create_default_layout().snapshot();
+        let mut history = SnapshotHistory::new(3);
+        assert_eq!(history.range(), None);
+        for tick in 10..15 {
+            history.record(tick, snapshot.clone());
+        }
+        assert_eq!(history.range(), Some((12, 14)));
+        assert!(history.get(11).is_none() && history.get(12).is_some());
+        history.clear();
+        assert_eq!(history.range(), None);
+    }
+}
@@ -1,9 +1,41 @@
-use std::sync::Arc;
-use glam::{Vec3, Quat, Mat4, Vec4};
+use std::fmt::Write;
+use std::path::Path;
+use anyhow::Result;
+use glam::{Vec2, Vec3, Quat, Mat4, Vec4};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use crate::atmosphere::{self, Atmosphere, NOMINAL_OXYGEN};
+use crate::door::{self, Door, DoorSnapshot};
+use crate::emi::Interference;
 use crate::geometry::Mesh;
+use crate::gltf;
+use crate::graphviz::{self, GraphKind};
+use crate::grime::{self, GrimeMask};
+use crate::hazard::{self, Fire, FireChange, FireSnapshot};
+use crate::layout::StationLayout;
+use crate::life_support::{self, LifeSupportKind, LifeSupportUnit};
+use crate::maintenance;
 use crate::material::Material;
+use crate::module_id::{ModuleId, ModuleIds};
+use crate::navigation;
+use crate::orbit::Orbit;
+use crate::paint::{self, Coat, ModulePaint, Surface, Swatch};
+use crate::ports::{self, Connector, DockingPort};
+use crate::procgen::{self, LayoutParams};
+use crate::radiation;
+use crate::repair::Component;
+use crate::resources::{self, Resource, ResourceLedger, Store};
+use crate::ring::{self, RingSection, RingSpec};
+use crate::solar;
+use crate::structure::{self, Body, Joint};
+use crate::thermal;
+use crate::clock::{MissionClock, MissionTime, StationEpoch};
+use crate::event_log::{EventLog, LogSeverity};
+use crate::events::{EventBus, StationEvent};
+use rand::Rng;
+use crate::rng::{self, SimRng};
+use crate::tunables::{PowerTunables, StationTunables, Tunables};
 
-#[derive(Debug, Clone, Copy, PartialEq)]
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum ModuleType {
     Corridor,
     Hub,
@@ -13,9 +45,109 @@ pub enum ModuleType {
     Laboratory,
     Storage,
     PowerPlant,
+    // A lifeboat docked to the station; see `evacuation`
+    EscapePod,
+    // Panels out either side that generate by how squarely they face the sun
+    SolarArray,
+    // Banks of cells that add to the station battery
+    Battery,
 }
 
-#[derive(Debug)]
+impl ModuleType {
+    // When the grid can't carry every load, the lowest priorities are shed
+    // first; life support, the bridge and the reactor's own systems last
+    pub fn load_priority(self) -> u8 {
+        match self {
+            ModuleType::Storage => 0,
+            ModuleType::Laboratory => 1,
+            ModuleType::Corridor | ModuleType::Hub => 2,
+            ModuleType::LivingQuarters | ModuleType::Airlock => 3,
+            ModuleType::CommandCenter | ModuleType::EscapePod => 4,
+            ModuleType::PowerPlant | ModuleType::SolarArray | ModuleType::Battery => 5,
+        }
+    }
+
+    // Air volume in m³
+    pub fn volume(self) -> f32 {
+        match self {
+            ModuleType::Corridor => 100.0,
+            ModuleType::EscapePod => 20.0,
+            ModuleType::SolarArray => 30.0,
+            ModuleType::Battery => 80.0,
+            ModuleType::Airlock => 40.0,
+            ModuleType::Hub | ModuleType::Storage => 150.0,
+            ModuleType::LivingQuarters | ModuleType::Laboratory | ModuleType::PowerPlant => 200.0,
+            ModuleType::CommandCenter => 250.0,
+        }
+    }
+
+    // Tonnes, fitted out, for the structural loads
+    pub fn mass(self) -> f32 {
+        match self {
+            ModuleType::EscapePod => 3.0,
+            ModuleType::Corridor | ModuleType::SolarArray => 5.0,
+            ModuleType::Airlock => 6.0,
+            ModuleType::Hub => 10.0,
+            ModuleType::Battery | ModuleType::LivingQuarters => 15.0,
+            ModuleType::Laboratory => 18.0,
+            ModuleType::Storage => 20.0,
+            ModuleType::CommandCenter => 25.0,
+            ModuleType::PowerPlant => 30.0,
+        }
+    }
+
+    // Who's normally breathing in here
+    pub fn default_crew(self) -> u32 {
+        match self {
+            ModuleType::LivingQuarters => 2,
+            ModuleType::CommandCenter | ModuleType::Laboratory => 1,
+            _ => 0,
+        }
+    }
+
+    // How many times over the walls halve the radiation outside; stores and
+    // heavy plant stop the most
+    pub fn shielding(self) -> f32 {
+        match self {
+            ModuleType::Airlock | ModuleType::SolarArray => 0.5,
+            ModuleType::Corridor | ModuleType::EscapePod => 1.0,
+            ModuleType::Hub | ModuleType::Laboratory => 1.5,
+            ModuleType::LivingQuarters | ModuleType::CommandCenter | ModuleType::Battery => 2.0,
+            ModuleType::PowerPlant => 2.5,
+            ModuleType::Storage => 3.0,
+        }
+    }
+
+    // Half the hull's width and depth, as generate_module_geometry builds it
+    pub fn hull_half_extents(self) -> (f32, f32) {
+        match self {
+            ModuleType::Corridor | ModuleType::Airlock => (2.0, 2.0),
+            ModuleType::Hub => (4.0, 4.0),
+            ModuleType::LivingQuarters => (5.0, 5.0),
+            ModuleType::CommandCenter | ModuleType::PowerPlant => (6.0, 6.0),
+            ModuleType::Laboratory => (4.5, 4.5),
+            ModuleType::Storage => (5.0, 7.5),
+            ModuleType::EscapePod => (1.5, 1.5),
+            ModuleType::SolarArray => (2.0, 2.0),
+            ModuleType::Battery => (3.0, 3.0),
+        }
+    }
+
+    // Crew it can carry away in an evacuation
+    pub fn seats(self) -> usize {
+        match self {
+            ModuleType::EscapePod => 3,
+            _ => 0,
+        }
+    }
+
+    // The hatches other modules can be connected to, in the module's own space
+    pub fn docking_ports(self) -> Vec<DockingPort> {
+        ports::docking_ports(self)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum InteractionType {
     None,
     Door,
@@ -36,9 +168,145 @@ pub enum InteractionType {
     AirlockControl,
     PressureControl,
     EnvironmentControl,
+    EmergencyShutoff,
+    LightControl,
+    StorageAccess,
+    // Floods the module with extinguishant while it's on
+    FireSuppression,
+    // Pumps heat out to the panels outside; on from the start
+    Radiator,
+    // Turns a solar array's panels to follow the sun while it's on
+    SunTracker,
 }
 
-#[derive(Debug)]
+// What using an element does to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Activation {
+    // Nothing to operate
+    Passive,
+    // Switches on and off
+    Toggle,
+    // Pressed: active for MOMENTARY_SECONDS, then springs back
+    Momentary,
+    // Opens a terminal screen; switched off again when it closes
+    Panel,
+    // Opened and emptied in one go, then closed straight away
+    Container,
+    // Starts an airlock cycle, which the EVA controller runs to completion
+    Cycle,
+}
+
+// How long a pressed button stays down
+pub const MOMENTARY_SECONDS: f32 = 0.5;
+// Chance a switch fails, per unit of structural damage to its module
+const FAILURE_PER_DAMAGE: f32 = 0.5;
+// Extra draw from completely worn wiring, as a share of the module's load
+const WIRING_LOSS: f32 = 0.5;
+// Furthest apart two module centres can be and still be connected
+pub const MAX_CONNECTION_DISTANCE: f32 = 10.0;
+// Share of a module's air lost through a fully open hatch when what it led
+// to is taken away, before the emergency bulkhead drops
+const DETACH_VENT: f32 = 0.6;
+// Air thinner than this, as a share of normal oxygen, hurts the crew
+// breathing it
+const HYPOXIC: f32 = 0.5;
+
+impl InteractionType {
+    pub fn activation(self) -> Activation {
+        match self {
+            InteractionType::None | InteractionType::Window => Activation::Passive,
+            InteractionType::Door
+            | InteractionType::Console
+            | InteractionType::Light
+            | InteractionType::LightControl
+            | InteractionType::PowerControl
+            | InteractionType::LifeSupport
+            | InteractionType::EnvironmentControl
+            | InteractionType::PressureControl
+            | InteractionType::Experiment
+            | InteractionType::ResearchStation
+            | InteractionType::LabEquipment
+            | InteractionType::Communications
+            | InteractionType::StationControl
+            | InteractionType::FireSuppression
+            | InteractionType::Radiator
+            | InteractionType::SunTracker => Activation::Toggle,
+            // Trips the module's generator while it's thrown
+            InteractionType::EmergencyShutoff => Activation::Toggle,
+            InteractionType::Button => Activation::Momentary,
+            InteractionType::Terminal | InteractionType::MainComputer => Activation::Panel,
+            InteractionType::Storage | InteractionType::StorageAccess => Activation::Container,
+            InteractionType::AirlockControl => Activation::Cycle,
+        }
+    }
+
+    // Seconds a switch spends in Transitioning before it lands; 0 switches
+    // at once. Airlock cycles are timed by the EVA controller instead.
+    pub fn transition_seconds(self) -> f32 {
+        match self {
+            InteractionType::Button => MOMENTARY_SECONDS,
+            InteractionType::Door => 1.0,
+            InteractionType::PowerControl => 1.5,
+            InteractionType::PressureControl | InteractionType::LifeSupport => 2.0,
+            InteractionType::Experiment => 3.0,
+            InteractionType::EnvironmentControl
+            | InteractionType::ResearchStation
+            | InteractionType::LabEquipment
+            | InteractionType::Communications
+            | InteractionType::Radiator
+            | InteractionType::SunTracker => 1.0,
+            InteractionType::StationControl | InteractionType::FireSuppression => 0.5,
+            _ => 0.0,
+        }
+    }
+
+    // Dead in a module the grid has shed. Breakers, manual airlock cranks,
+    // buttons, storage lockers and the stored-pressure fire suppression work
+    // without power.
+    pub fn needs_power(self) -> bool {
+        !matches!(
+            self,
+            InteractionType::None
+                | InteractionType::Window
+                | InteractionType::Button
+                | InteractionType::PowerControl
+                | InteractionType::EmergencyShutoff
+                | InteractionType::AirlockControl
+                | InteractionType::Storage
+                | InteractionType::StorageAccess
+                | InteractionType::FireSuppression
+        )
+    }
+
+    // Running hours between services for machinery that wears with use;
+    // None for anything that doesn't
+    pub fn service_hours(self) -> Option<f32> {
+        match self {
+            InteractionType::Radiator | InteractionType::LifeSupport | InteractionType::EnvironmentControl => Some(300.0),
+            InteractionType::PowerControl
+            | InteractionType::PressureControl
+            | InteractionType::Communications
+            | InteractionType::StationControl
+            | InteractionType::SunTracker => Some(200.0),
+            InteractionType::ResearchStation | InteractionType::LabEquipment | InteractionType::Experiment => Some(150.0),
+            InteractionType::Light | InteractionType::LightControl | InteractionType::Console => Some(400.0),
+            _ => None,
+        }
+    }
+
+    // Lockout after a switch lands, during which requests are refused
+    pub fn cooldown_seconds(self) -> f32 {
+        match self {
+            InteractionType::EmergencyShutoff => 5.0,
+            InteractionType::PowerControl => 3.0,
+            InteractionType::PressureControl => 2.0,
+            InteractionType::Door => 0.5,
+            _ => 0.0,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
 pub enum ElementState {
     Inactive,
     Active,
@@ -49,6 +317,40 @@ pub enum ElementState {
     Malfunction,
 }
 
+// What taking a module off the station left behind. Indices are where the
+// modules sit after the removal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Removal {
+    // No longer finds anything
+    pub id: ModuleId,
+    pub module_type: ModuleType,
+    pub structural_integrity: f32,
+    // Neighbours whose hatch onto it was open, and lost air
+    pub vented: Vec<usize>,
+    // Modules with no way back to the command centre any more
+    pub stranded: Vec<usize>,
+}
+
+// Read-only numbers for HUDs and in-world screens
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct StationTelemetry {
+    pub power_generation: f32,
+    pub power_consumption: f32,
+    pub grid_stability: f32,
+    // Fraction of the battery's capacity that's charged
+    pub battery_level: f32,
+    // Averaged over the station; each module has its own Atmosphere
+    pub oxygen_level: f32,
+    // Partial pressure in atm
+    pub co2: f32,
+    pub temperature: f32,
+    pub pressure: f32,
+    pub structural_integrity: f32,
+    pub module_count: usize,
+    pub sealed_modules: usize,
+    pub powered_modules: usize,
+}
+
 #[derive(Debug)]
 pub struct Transform {
     pub position: Vec3,
@@ -56,6 +358,12 @@ pub struct Transform {
     pub scale: Vec3,
 }
 
+impl Default for Transform {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Transform {
     pub fn new() -> Self {
         Self {
@@ -94,17 +402,67 @@ impl Transform {
     }
 }
 
+// What a module's been refitted with (see `research`): its tier, and how
+// much that's scaled its scrubbers, its generation and the hull's strength
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModuleRefit {
+    pub tier: u8,
+    pub scrubbing: f32,
+    pub generation: f32,
+    pub hull: f32,
+}
+
+impl Default for ModuleRefit {
+    fn default() -> Self {
+        Self { tier: 0, scrubbing: 1.0, generation: 1.0, hull: 1.0 }
+    }
+}
+
+// Trim on refitted modules goes from bare aluminium to dark steel to
+// anodised bronze, so a tier shows at a glance
+const TIER_TRIM: [Vec3; 3] = [paint::TRIM_COLOR, Vec3::new(0.3, 0.32, 0.36), Vec3::new(0.62, 0.45, 0.22)];
+
 #[derive(Debug)]
 pub struct StationModule {
+    // Stays the same while the module's on the station, wherever it sits in
+    // the list (see `module_id`)
+    pub id: ModuleId,
     pub module_type: ModuleType,
     pub transform: Transform,
     pub mesh: Mesh,
+    // Wall and trim finishes, with any paint applied over the module
+    // type's own (see `paint`)
     pub material: Material,
-    pub connected_modules: Vec<usize>,
+    pub trim: Material,
+    pub paint: ModulePaint,
+    pub refit: ModuleRefit,
+    // Where people have walked its floor (see `grime`)
+    pub grime: GrimeMask,
+    pub connected_modules: Vec<ModuleId>,
     pub structural_integrity: f32,
+    // Condition of the module's wiring and life support hardware, 0 ruined
+    // to 1 new; structural_integrity is the hull's
+    pub wiring: f32,
+    pub life_support: f32,
     pub power_consumption: f32,
     pub power_generation: f32,
     pub atmosphere_sealed: bool,
+    // False while the grid has shed this module's load; its lights drop to
+    // emergency level and its doors and consoles stop responding
+    pub powered: bool,
+    pub atmosphere: Atmosphere,
+    pub crew: u32,
+    // How well the crew assigned to it are running it, 0 to 1; modules
+    // nobody works in run at 1
+    pub productivity: f32,
+    // Which way a solar array's panels face, in the station frame; None for
+    // everything else
+    pub panel: Option<Vec3>,
+    // Dimmer on the ceiling lights its LightControl switches, 0 to 1
+    pub light_level: f32,
+    // O2 generators, CO2 scrubbers and water recyclers installed here
+    pub life_support_units: Vec<LifeSupportUnit>,
     pub interactive_elements: Vec<InteractiveElement>,
 }
 
@@ -114,32 +472,59 @@ pub struct InteractiveElement {
     pub state: ElementState,
     pub position: Vec3,
     pub power_draw: f32,
+    // Where the current transition lands; None while idle, or while
+    // something else (an airlock cycle) is driving the progress
+    pub target: Option<ElementState>,
+    // Seconds until it accepts another request
+    pub cooldown: f32,
+    // Running hours since it was last serviced
+    pub hours: f32,
+    // Handprints from being used, 0 clean to 1 filthy
+    pub grime: f32,
+    // Only someone holding this clearance may work it
+    pub clearance: Option<String>,
 }
 
 impl StationModule {
     pub fn new(module_type: ModuleType, position: Vec3) -> Self {
         let (mesh, material) = Self::generate_module_geometry(&module_type);
         let mut module = Self {
+            id: ModuleId::UNPLACED,
             module_type,
             transform: Transform::from_position(position),
             mesh,
             material,
+            trim: Material::create_metal(paint::TRIM_COLOR),
+            paint: ModulePaint::default(),
+            refit: ModuleRefit::default(),
+            grime: GrimeMask::default(),
             connected_modules: Vec::new(),
             structural_integrity: 1.0,
+            wiring: 1.0,
+            life_support: 1.0,
             power_consumption: 0.0,
             power_generation: 0.0,
             atmosphere_sealed: true,
+            powered: true,
+            atmosphere: Atmosphere::nominal(),
+            crew: module_type.default_crew(),
+            productivity: 1.0,
+            panel: None,
+            light_level: 1.0,
+            life_support_units: life_support::standard_fit(module_type),
             interactive_elements: Vec::new(),
         };
 
         // Configure module-specific properties
         match module_type {
             ModuleType::PowerPlant => {
-                module.power_generation = 100.0;
+                module.power_generation = PowerTunables::default().generator_output;
                 module.power_consumption = 10.0;
                 module.add_interactive_elements(&[
                     (InteractionType::PowerControl, Vec3::new(2.0, 0.0, 0.0)),
                     (InteractionType::EmergencyShutoff, Vec3::new(-2.0, 0.0, 0.0)),
+                    (InteractionType::FireSuppression, Vec3::new(0.0, 2.0, 2.0)),
+                    (InteractionType::Radiator, Vec3::new(0.0, 0.0, -2.0)),
                 ]);
             }
             ModuleType::LivingQuarters => {
@@ -147,6 +532,8 @@ impl StationModule {
                 module.add_interactive_elements(&[
                     (InteractionType::LightControl, Vec3::new(1.0, 2.0, 0.0)),
                     (InteractionType::EnvironmentControl, Vec3::new(-1.0, 2.0, 0.0)),
+                    (InteractionType::FireSuppression, Vec3::new(0.0, 2.0, 2.0)),
+                    (InteractionType::Radiator, Vec3::new(0.0, 0.0, -2.0)),
                 ]);
             }
             ModuleType::CommandCenter => {
@@ -155,6 +542,8 @@ impl StationModule {
                     (InteractionType::MainComputer, Vec3::ZERO),
                     (InteractionType::Communications, Vec3::new(2.0, 0.0, 2.0)),
                     (InteractionType::StationControl, Vec3::new(-2.0, 0.0, 2.0)),
+                    (InteractionType::FireSuppression, Vec3::new(0.0, 2.0, -2.0)),
+                    (InteractionType::Radiator, Vec3::new(-2.0, 0.0, -2.0)),
                 ]);
             }
             ModuleType::Laboratory => {
@@ -162,6 +551,8 @@ impl StationModule {
                 module.add_interactive_elements(&[
                     (InteractionType::ResearchStation, Vec3::new(2.0, 0.0, 0.0)),
                     (InteractionType::LabEquipment, Vec3::new(-2.0, 0.0, 0.0)),
+                    (InteractionType::FireSuppression, Vec3::new(0.0, 2.0, 2.0)),
+                    (InteractionType::Radiator, Vec3::new(0.0, 0.0, -2.0)),
                 ]);
             }
             ModuleType::Airlock => {
@@ -190,46 +581,245 @@ impl StationModule {
                     (InteractionType::EnvironmentControl, Vec3::new(2.0, 0.0, 0.0)),
                 ]);
             }
+            ModuleType::EscapePod => {
+                module.power_consumption = 1.0;
+                module.add_interactive_elements(&[
+                    (InteractionType::LightControl, Vec3::new(0.0, 2.0, 0.0)),
+                ]);
+            }
+            ModuleType::SolarArray => {
+                // Output follows the sun each update; the panels start out
+                // facing away from the planet
+                module.power_consumption = 1.0;
+                module.panel = Some(Vec3::Y);
+                module.add_interactive_elements(&[
+                    (InteractionType::SunTracker, Vec3::new(1.5, 0.0, 0.0)),
+                    (InteractionType::LightControl, Vec3::new(0.0, 2.0, 0.0)),
+                ]);
+            }
+            ModuleType::Battery => {
+                module.power_consumption = 2.0;
+                module.add_interactive_elements(&[
+                    (InteractionType::PowerControl, Vec3::new(2.0, 0.0, 0.0)),
+                    (InteractionType::FireSuppression, Vec3::new(0.0, 2.0, 0.0)),
+                    (InteractionType::Radiator, Vec3::new(0.0, 0.0, -2.0)),
+                ]);
+            }
         }
 
         module
     }
 
+    // An element fitted beyond the type's own, in its usual state unless
+    // `state` says otherwise
+    pub fn add_element(&mut self, element_type: InteractionType, position: Vec3, state: Option<ElementState>) -> usize {
+        self.add_interactive_elements(&[(element_type, position)]);
+        let index = self.interactive_elements.len() - 1;
+        if let Some(state) = state {
+            self.interactive_elements[index].state = state;
+        }
+        index
+    }
+
     fn add_interactive_elements(&mut self, elements: &[(InteractionType, Vec3)]) {
         for (element_type, position) in elements {
+            // The station would cook without its radiators
+            let state = if *element_type == InteractionType::Radiator {
+                ElementState::Active
+            } else {
+                ElementState::Inactive
+            };
             self.interactive_elements.push(InteractiveElement {
                 element_type: *element_type,
-                state: ElementState::Inactive,
+                state,
                 position: *position,
-                power_draw: match element_type {
-                    InteractionType::MainComputer => 5.0,
-                    InteractionType::Communications => 3.0,
-                    InteractionType::StationControl => 4.0,
-                    InteractionType::PowerControl => 2.0,
-                    InteractionType::EnvironmentControl => 2.0,
-                    InteractionType::LightControl => 1.0,
-                    _ => 0.5,
-                },
+                power_draw: PowerTunables::default().element_draw(*element_type),
+                target: None,
+                cooldown: 0.0,
+                hours: 0.0,
+                grime: 0.0,
+                clearance: None,
             });
         }
     }
 
-    pub fn update(&mut self, delta_time: f32) {
+    // Returns the elements whose transition landed this update
+    pub fn update(&mut self, delta_time: f32, tunables: &StationTunables) -> Vec<usize> {
+        let mut settled = Vec::new();
         // Update interactive elements
-        for element in &mut self.interactive_elements {
+        for (i, element) in self.interactive_elements.iter_mut().enumerate() {
+            element.cooldown = (element.cooldown - delta_time).max(0.0);
             match element.state {
-                ElementState::Active => {
-                    self.power_consumption += element.power_draw * delta_time;
-                }
-                ElementState::Inactive => {}
                 ElementState::Malfunction => {
-                    self.structural_integrity -= 0.01 * delta_time;
+                    self.structural_integrity -= tunables.malfunction_damage_rate * delta_time;
+                }
+                ElementState::Transitioning(progress) => {
+                    let Some(target) = element.target else {
+                        continue;
+                    };
+                    let progress = progress + delta_time / element.element_type.transition_seconds().max(f32::EPSILON);
+                    if progress >= 1.0 {
+                        element.state = target;
+                        element.target = None;
+                        element.cooldown = element.element_type.cooldown_seconds();
+                        settled.push(i);
+                    } else {
+                        element.state = ElementState::Transitioning(progress);
+                    }
                 }
+                ElementState::Inactive
+                | ElementState::Active
+                | ElementState::Locked
+                | ElementState::Warning
+                | ElementState::Emergency => {}
             }
         }
 
         // Clamp structural integrity
         self.structural_integrity = self.structural_integrity.clamp(0.0, 1.0);
+        settled
+    }
+
+    // World-space box around the module's hull, as min and max corners
+    pub fn bounds(&self) -> (Vec3, Vec3) {
+        let matrix = self.transform.matrix();
+        self.mesh
+            .vertices
+            .iter()
+            .map(|vertex| matrix.transform_point3(Vec3::from(vertex.position)))
+            .fold((Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)), |(min, max), p| (min.min(p), max.max(p)))
+    }
+
+    // Where `position` falls on the floor, 0 to 1 across the hull's width
+    // and depth
+    pub fn floor_uv(&self, position: Vec3) -> Vec2 {
+        let local = self.transform.matrix().inverse().transform_point3(position);
+        let (half_width, half_depth) = self.module_type.hull_half_extents();
+        Vec2::new(local.x / (half_width * 2.0), local.z / (half_depth * 2.0)) + Vec2::splat(0.5)
+    }
+
+    // The other way: the world point on the floor at `uv`
+    pub fn floor_point(&self, uv: Vec2) -> Vec3 {
+        let (half_width, half_depth) = self.module_type.hull_half_extents();
+        let local = (uv - Vec2::splat(0.5)) * Vec2::new(half_width * 2.0, half_depth * 2.0);
+        self.transform.matrix().transform_point3(Vec3::new(local.x, 0.0, local.y))
+    }
+
+    // Its dirtiest patch of floor or handled element
+    pub fn worst_grime(&self) -> f32 {
+        self.interactive_elements.iter().map(|element| element.grime).fold(self.grime.worst(), f32::max)
+    }
+
+    pub fn condition(&self, component: Component) -> f32 {
+        match component {
+            Component::Hull => self.structural_integrity,
+            Component::Wiring => self.wiring,
+            Component::LifeSupport => self.life_support,
+        }
+    }
+
+    fn condition_mut(&mut self, component: Component) -> &mut f32 {
+        match component {
+            Component::Hull => &mut self.structural_integrity,
+            Component::Wiring => &mut self.wiring,
+            Component::LifeSupport => &mut self.life_support,
+        }
+    }
+
+    // What one kind of life support hardware is putting out right now: its
+    // running units through their filters, plus a bed for any LifeSupport
+    // panel switched on, while powered and with no environment control
+    // failed, scaled by the hardware's condition
+    pub fn life_support_output(&self, kind: LifeSupportKind) -> f32 {
+        let failed = self.interactive_elements.iter().any(|element| {
+            element.element_type == InteractionType::EnvironmentControl && element.state == ElementState::Malfunction
+        });
+        if !self.powered || failed {
+            return 0.0;
+        }
+        let units: f32 =
+            self.life_support_units.iter().filter(|unit| unit.kind == kind).map(LifeSupportUnit::output).sum();
+        let panels = self
+            .interactive_elements
+            .iter()
+            .filter(|element| element.element_type == InteractionType::LifeSupport && element.state == ElementState::Active)
+            .count();
+        (units + panels as f32) * self.life_support * self.refit.scrubbing
+    }
+
+    pub fn scrubber_output(&self) -> f32 {
+        self.life_support_output(LifeSupportKind::Co2Scrubber)
+    }
+
+    // Generation, unless an emergency shutoff has tripped it
+    pub fn power_output(&self) -> f32 {
+        let shut_off = self.interactive_elements.iter().any(|element| {
+            element.element_type == InteractionType::EmergencyShutoff && element.state == ElementState::Active
+        });
+        if shut_off {
+            0.0
+        } else {
+            self.power_generation * self.refit.generation
+        }
+    }
+
+    // Base consumption plus whatever elements and life support are switched
+    // on (dimmed lights drawing less), and what worn wiring wastes on top
+    pub fn power_draw(&self) -> f32 {
+        let elements: f32 = self
+            .interactive_elements
+            .iter()
+            .filter(|element| element.state == ElementState::Active)
+            .map(|element| match element.element_type {
+                InteractionType::LightControl => element.power_draw * self.light_level,
+                _ => element.power_draw,
+            })
+            .sum();
+        let life_support: f32 = self.life_support_units.iter().map(LifeSupportUnit::power_draw).sum();
+        (self.power_consumption + elements + life_support) * (1.0 + (1.0 - self.wiring) * WIRING_LOSS)
+    }
+
+    // Whether the ceiling lights are switched on and have the power to be
+    pub fn lights_on(&self) -> bool {
+        self.powered
+            && self
+                .interactive_elements
+                .iter()
+                .any(|element| element.element_type == InteractionType::LightControl && element.state == ElementState::Active)
+    }
+
+    // Puts the module's finishes back to its type's, newer panels for each
+    // refit tier, then paints over them
+    fn apply_paint(&mut self) {
+        let (_, mut material) = Self::generate_module_geometry(&self.module_type);
+        let tier = self.refit.tier.min(TIER_TRIM.len() as u8 - 1);
+        material.roughness = (material.roughness - 0.05 * tier as f32).max(0.05);
+        self.material = match &self.paint.walls {
+            Some(coat) => material.with_albedo(Vec3::from(coat.color)),
+            None => material,
+        };
+        let trim = self.paint.trim.as_ref().map_or(TIER_TRIM[tier as usize], |coat| Vec3::from(coat.color));
+        self.trim = Material::create_metal(trim);
+    }
+
+    // The type's hull with a stiffening band round it for each refit tier
+    fn apply_refit(&mut self) {
+        let (mut mesh, _) = Self::generate_module_geometry(&self.module_type);
+        let (min, max) = mesh_bounds(&mesh);
+        let size = max - min;
+        let tiers = self.refit.tier as usize;
+        for band in 0..tiers {
+            let mut hoop = Mesh::create_octagonal_room(size.x * 1.04, size.y * 0.08, size.z * 1.04);
+            let (hoop_min, hoop_max) = mesh_bounds(&hoop);
+            let y = min.y + size.y * (band + 1) as f32 / (tiers + 1) as f32;
+            let centre = Vec3::new((min.x + max.x) / 2.0, y, (min.z + max.z) / 2.0);
+            hoop.transform(&Mat4::from_translation(centre - (hoop_min + hoop_max) / 2.0));
+            let base = mesh.vertices.len() as u32;
+            mesh.vertices.extend(hoop.vertices);
+            mesh.indices.extend(hoop.indices.iter().map(|i| i + base));
+        }
+        self.mesh = mesh;
+        self.apply_paint();
     }
 
     fn generate_module_geometry(module_type: &ModuleType) -> (Mesh, Material) {
@@ -314,25 +904,117 @@ impl StationModule {
                 );
                 (mesh, material)
             }
+            ModuleType::EscapePod => {
+                let mesh = Mesh::create_octagonal_room(3.0, 3.0, 3.0);
+                let material = Material::new(
+                    Vec4::new(0.9, 0.5, 0.15, 1.0),
+                    0.6,
+                    0.4,
+                    1.0,
+                );
+                (mesh, material)
+            }
+            ModuleType::SolarArray => {
+                let mesh = Mesh::create_octagonal_room(4.0, 3.0, 4.0);
+                let material = Material::new(
+                    Vec4::new(0.75, 0.65, 0.35, 1.0),
+                    0.8,
+                    0.3,
+                    1.0,
+                );
+                (mesh, material)
+            }
+            ModuleType::Battery => {
+                let mesh = Mesh::create_octagonal_room(6.0, 4.0, 6.0);
+                let material = Material::new(
+                    Vec4::new(0.35, 0.5, 0.4, 1.0),
+                    0.7,
+                    0.4,
+                    1.0,
+                );
+                (mesh, material)
+            }
         }
     }
 }
 
+// Corners of a mesh in its own space
+fn mesh_bounds(mesh: &Mesh) -> (Vec3, Vec3) {
+    mesh.vertices
+        .iter()
+        .map(|vertex| Vec3::from(vertex.position))
+        .fold((Vec3::splat(f32::MAX), Vec3::splat(f32::MIN)), |(min, max), p| (min.min(p), max.max(p)))
+}
+
 #[derive(Debug)]
 pub struct SpaceStation {
     modules: Vec<StationModule>,
+    module_ids: ModuleIds,
+    // One per connection
+    doors: Vec<Door>,
+    // One per connection whose ports mate
+    connectors: Vec<Connector>,
+    fires: Vec<Fire>,
+    // Where the sun is, for the solar arrays
+    orbit: Orbit,
+    interference: Interference,
+    // Whether flares are left to the event director rather than rolled here
+    flares_directed: bool,
+    // The module the crew hole up in through a flare, walled in with water
+    // and stores
+    storm_shelter: Option<ModuleId>,
+    // Sections spun for artificial gravity
+    rings: Vec<RingSection>,
+    resources: ResourceLedger,
+    // Running totals of stock used up on jobs and emergencies, and of crew
+    // hurt, for drills to score against
+    resources_used: f32,
+    injuries: u32,
     power_grid: PowerGrid,
-    life_support: LifeSupport,
     structural_integrity: f32,
+    // The load on each connection, from the last structural pass
+    joints: Vec<Joint>,
+    clock: MissionClock,
+    log: EventLog,
+    events: EventBus,
+    tunables: StationTunables,
+    power_tunables: PowerTunables,
+    seed: u64,
+    rng: SimRng,
+}
+
+impl Default for SpaceStation {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl SpaceStation {
     pub fn new() -> Self {
         Self {
             modules: Vec::new(),
+            module_ids: ModuleIds::new(),
+            doors: Vec::new(),
+            connectors: Vec::new(),
+            fires: Vec::new(),
+            orbit: Orbit::low_earth(),
+            interference: Interference::new(),
+            flares_directed: false,
+            storm_shelter: None,
+            rings: Vec::new(),
+            resources: ResourceLedger::new(),
+            resources_used: 0.0,
+            injuries: 0,
             power_grid: PowerGrid::new(),
-            life_support: LifeSupport::new(),
             structural_integrity: 1.0,
+            joints: Vec::new(),
+            clock: MissionClock::new(),
+            log: EventLog::default(),
+            events: EventBus::new(),
+            tunables: StationTunables::default(),
+            power_tunables: PowerTunables::default(),
+            seed: 0,
+            rng: rng::seeded(0),
         }
     }
 
@@ -381,7 +1063,7 @@ impl SpaceStation {
 
         let storage_idx = station.add_module(
             ModuleType::Storage,
-            Vec3::new(0.0, 0.0, 16.0)
+            Vec3::new(0.0, 0.0, 17.5)
         );
         station.connect_modules(south_corridor, storage_idx);
 
@@ -398,12 +1080,37 @@ impl SpaceStation {
         );
         station.connect_modules(lab_idx, airlock_idx);
 
+        // Escape pods docked off the quarters and the side of the stores
+        let quarters_pod = station.add_module(ModuleType::EscapePod, Vec3::new(24.0, 0.0, 0.0));
+        station.connect_modules(living_idx, quarters_pod);
+        let stores_pod = station.add_module(ModuleType::EscapePod, Vec3::new(8.0, 0.0, 17.5));
+        station.connect_modules(storage_idx, stores_pod);
+
         station
     }
 
+    // A station built from a layout file (see `layout`)
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Self> {
+        StationLayout::load(path)?.build()
+    }
+
+    pub fn layout(&self) -> StationLayout {
+        StationLayout::of(self)
+    }
+
+    // A station grown from `seed`; the same seed and params give the same layout
+    pub fn generate_procedural(seed: u64, params: &LayoutParams) -> Self {
+        procgen::generate(seed, params)
+    }
+
     pub fn add_module(&mut self, module_type: ModuleType, position: Vec3) -> usize {
-        let module = StationModule::new(module_type, position);
+        let mut module = StationModule::new(module_type, position);
+        let id = self.module_ids.insert(self.modules.len());
+        module.id = id;
         self.modules.push(module);
+        if module_type == ModuleType::Storage {
+            self.resources.add_store(id);
+        }
         self.modules.len() - 1
     }
 
@@ -417,16 +1124,20 @@ impl SpaceStation {
         let pos2 = self.modules[module2_idx].transform.position;
         let distance = (pos2 - pos1).length();
 
-        // Maximum connection distance (should be sum of module radii)
-        let max_distance = 10.0;
+        if distance > MAX_CONNECTION_DISTANCE {
+            return false;
+        }
 
-        if distance > max_distance {
+        // They also need a free port each, facing each other squarely
+        if !self.add_connector(module1_idx, module2_idx) {
             return false;
         }
 
         // Add connection references
-        self.modules[module1_idx].connected_modules.push(module2_idx);
-        self.modules[module2_idx].connected_modules.push(module1_idx);
+        let (id1, id2) = (self.modules[module1_idx].id, self.modules[module2_idx].id);
+        self.modules[module1_idx].connected_modules.push(id2);
+        self.modules[module2_idx].connected_modules.push(id1);
+        self.add_door(module1_idx, module2_idx);
 
         // Update structural integrity
         self.update_structural_integrity();
@@ -434,98 +1145,2646 @@ impl SpaceStation {
         true
     }
 
-    pub fn update(&mut self, delta_time: f32) {
-        // Update power distribution
-        self.power_grid.update(delta_time);
-
-        // Update life support systems
-        self.life_support.update(delta_time);
+    // Fits a door halfway between two connected modules. Doors start open,
+    // so air moves freely until someone shuts one.
+    fn add_door(&mut self, a: usize, b: usize) {
+        let (first, second) = (a.min(b), a.max(b));
+        let from = self.modules[first].transform.position;
+        let to = self.modules[second].transform.position;
+        let modules = (self.modules[first].id, self.modules[second].id);
+        let door = Door::new(modules, self.modules[first].interactive_elements.len(), from, to);
+        let module = &mut self.modules[first];
+        let position = module.transform.matrix().inverse().transform_point3(door.position);
+        module.add_interactive_elements(&[(InteractionType::Door, position)]);
+        module.interactive_elements[door.element].state = ElementState::Active;
+        self.doors.push(door);
+    }
 
-        // Update all modules
-        for module in &mut self.modules {
-            module.update(delta_time);
+    // Takes a door out along with its element, which lives in the
+    // lower-numbered module
+    fn take_door(&mut self, door_idx: usize) -> Door {
+        let door = self.doors.remove(door_idx);
+        if let Some(first) = self.module_index(door.modules.0) {
+            self.modules[first].interactive_elements.remove(door.element);
         }
+        for other in self.doors.iter_mut().filter(|other| other.modules.0 == door.modules.0) {
+            if other.element > door.element {
+                other.element -= 1;
+            }
+        }
+        door
+    }
 
-        // Update structural integrity
-        self.update_structural_integrity();
+    pub fn doors(&self) -> &[Door] {
+        &self.doors
     }
 
-    fn update_structural_integrity(&mut self) {
-        // Base integrity starts at 1.0
-        let mut total_integrity = 1.0;
+    // Where a door's two modules are in the list now
+    pub fn door_modules(&self, door_idx: usize) -> Option<(usize, usize)> {
+        let (a, b) = self.doors.get(door_idx)?.modules;
+        Some((self.module_index(a)?, self.module_index(b)?))
+    }
+
+    // Where a module's neighbours are in the list now, leaving out any that
+    // have gone
+    pub fn neighbours(&self, module_idx: usize) -> Vec<usize> {
+        self.modules.get(module_idx).map_or(Vec::new(), |module| {
+            module.connected_modules.iter().filter_map(|&id| self.module_index(id)).collect()
+        })
+    }
+
+    // Every connection once, lower index first
+    fn connections(&self) -> Vec<(usize, usize)> {
+        (0..self.modules.len())
+            .flat_map(|a| self.neighbours(a).into_iter().filter(move |&b| b > a).map(move |b| (a, b)))
+            .collect()
+    }
+
+    // Mates the closest-lined-up free ports on two modules and runs the
+    // hatch or tube between them; false if no two of them line up
+    fn add_connector(&mut self, a: usize, b: usize) -> bool {
+        let (first, second) = (a.min(b), a.max(b));
+        let (free1, free2) = (self.free_ports(first), self.free_ports(second));
+        let (module1, module2) = (&self.modules[first], &self.modules[second]);
+        let Some((port1, port2, gap)) = ports::best_mate(module1, &free1, module2, &free2) else {
+            return false;
+        };
+        let from = module1.module_type.docking_ports()[port1].placed(module1);
+        self.connectors.push(Connector::new((module1.id, module2.id), (port1, port2), &from, gap));
+        true
+    }
 
-        // Check each module's individual integrity
-        for module in &self.modules {
-            total_integrity = total_integrity.min(module.structural_integrity);
+    pub fn connectors(&self) -> &[Connector] {
+        &self.connectors
+    }
+
+    // The module's ports with nothing mated to them, as indices into its
+    // type's docking ports. An airlock only ever takes the one connection;
+    // its other sides are for going outside.
+    pub fn free_ports(&self, module: usize) -> Vec<usize> {
+        let Some(station_module) = self.modules.get(module) else {
+            return Vec::new();
+        };
+        let id = station_module.id;
+        let mated = self.connectors.iter().any(|connector| connector.touches(id));
+        if station_module.module_type == ModuleType::Airlock && mated {
+            return Vec::new();
         }
+        (0..station_module.module_type.docking_ports().len())
+            .filter(|&port| !self.connectors.iter().any(|connector| connector.uses(id, port)))
+            .collect()
+    }
 
-        // Check connection stresses
-        for (i, module) in self.modules.iter().enumerate() {
-            for &connected_idx in &module.connected_modules {
-                let stress = self.calculate_connection_stress(i, connected_idx);
-                total_integrity = total_integrity.min(1.0 - stress);
+    // Takes a module off the station. Its connections go with it: a
+    // neighbour whose hatch onto it was shut stays sealed, one whose hatch
+    // was open loses air before its bulkhead drops. Anything it kept in
+    // stores is carried to other stores if there's room. Every module after
+    // it moves down one in the list; connections, doors, fires and stores
+    // hold on to ModuleIds, so nothing needs renumbering. Crew are the
+    // caller's to get out first. Modules cut off from the command centre
+    // are left where they are, and reported.
+    pub fn remove_module(&mut self, module_idx: usize) -> Option<Removal> {
+        let module = self.modules.get(module_idx)?;
+        let (id, module_type, structural_integrity) = (module.id, module.module_type, module.structural_integrity);
+
+        // Vent or seal each neighbour, and take its side of the door out
+        let mut vented = Vec::new();
+        let mut door_idx = 0;
+        while door_idx < self.doors.len() {
+            let (first, second) = self.door_modules(door_idx).unwrap_or((usize::MAX, usize::MAX));
+            if first != module_idx && second != module_idx {
+                door_idx += 1;
+                continue;
+            }
+            let openness = self.door_openness(door_idx);
+            let door = self.take_door(door_idx);
+            let neighbour = if first == module_idx { second } else { first };
+            if openness > 0.0 {
+                self.modules[neighbour].atmosphere.vent(openness * DETACH_VENT);
+                vented.push((self.modules[neighbour].id, door.position));
+            }
+        }
+        self.connectors.retain(|connector| !connector.touches(id));
+        self.fires.retain(|fire| fire.module != id);
+        let store = self.resources.remove_store(id);
+        self.modules.remove(module_idx);
+        self.module_ids.remove(id);
+        for module in &mut self.modules {
+            module.connected_modules.retain(|&other| other != id);
+        }
+        if let Some(store) = store {
+            for resource in Resource::ALL {
+                self.resources.deposit_anywhere(resource, store.amount(resource));
             }
         }
+        self.update_structural_integrity();
 
-        self.structural_integrity = total_integrity;
+        let stranded = self.stranded();
+        self.log_event(LogSeverity::Info, format!("Removed {:?} module {}", module_type, module_idx));
+        if !stranded.is_empty() {
+            self.log_event(
+                LogSeverity::Warning,
+                format!("Modules {:?} are cut off from the command centre", stranded),
+            );
+        }
+        let vented: Vec<usize> = vented
+            .into_iter()
+            .filter_map(|(neighbour, position)| {
+                let module = self.module_index(neighbour)?;
+                self.emit_event(StationEvent::Decompression { module, position });
+                Some(module)
+            })
+            .collect();
+        Some(Removal { id, module_type, structural_integrity, vented, stranded })
     }
 
-    fn calculate_connection_stress(&self, module1_idx: usize, module2_idx: usize) -> f32 {
-        let pos1 = self.modules[module1_idx].transform.position;
-        let pos2 = self.modules[module2_idx].transform.position;
-        
-        // Calculate stress based on distance and angle
-        let distance = (pos2 - pos1).length();
-        let optimal_distance = 8.0; // Optimal connection distance
-        
-        // Distance stress increases quadratically with deviation from optimal
-        let distance_stress = ((distance - optimal_distance) / optimal_distance).powi(2) * 0.5;
-        
-        // Add other stress factors (could include module mass, vibration, etc.)
-        distance_stress
+    // Modules that would be cut off from the command centre if `module_idx`
+    // were taken away, as they're numbered now
+    pub fn would_strand(&self, module_idx: usize) -> Vec<usize> {
+        let reached = self.reachable(Some(module_idx));
+        (0..self.modules.len()).filter(|&i| i != module_idx && !reached[i]).collect()
     }
-}
 
-#[derive(Debug)]
-struct PowerGrid {
-    total_output: f32,
-    total_consumption: f32,
-    grid_stability: f32,
-}
+    // Modules with no connection back to the command centre
+    pub fn stranded(&self) -> Vec<usize> {
+        let reached = self.reachable(None);
+        (0..self.modules.len()).filter(|&i| !reached[i]).collect()
+    }
 
-impl PowerGrid {
-    fn new() -> Self {
-        Self {
-            total_output: 0.0,
-            total_consumption: 0.0,
-            grid_stability: 1.0,
+    // Which modules connect back to the command centre, or to the first
+    // module if there isn't one, going round `without`. Locks and breaches
+    // don't count; it's the structure that matters.
+    fn reachable(&self, without: Option<usize>) -> Vec<bool> {
+        let mut reached = vec![false; self.modules.len()];
+        let root = (0..self.modules.len())
+            .filter(|&i| Some(i) != without)
+            .find(|&i| self.modules[i].module_type == ModuleType::CommandCenter)
+            .or_else(|| (0..self.modules.len()).find(|&i| Some(i) != without));
+        let mut frontier: Vec<usize> = root.into_iter().collect();
+        while let Some(module) = frontier.pop() {
+            if reached[module] {
+                continue;
+            }
+            reached[module] = true;
+            frontier.extend(self.neighbours(module).into_iter().filter(|&next| Some(next) != without && !reached[next]));
         }
+        reached
     }
 
-    fn update(&mut self, delta_time: f32) {
-        // Update power generation and consumption
-        // This would be expanded based on active modules and systems
+    pub fn fires(&self) -> &[Fire] {
+        &self.fires
     }
-}
 
-#[derive(Debug)]
-struct LifeSupport {
-    oxygen_level: f32,
-    temperature: f32,
-    pressure: f32,
-}
+    // Something burning, open to space or short of power
+    pub fn in_emergency(&self) -> bool {
+        !self.fires.is_empty()
+            || self.power_grid.in_deficit
+            || self.modules.iter().any(|module| !module.atmosphere_sealed)
+    }
 
-impl LifeSupport {
-    fn new() -> Self {
-        Self {
-            oxygen_level: 1.0,
-            temperature: 293.15, // 20°C in Kelvin
-            pressure: 1.0, // 1 atm
+    // Lights a fire at a module's centre, as a fault arcing over would;
+    // false if there's no such module or it's already burning
+    pub fn ignite(&mut self, module_idx: usize) -> bool {
+        let Some(module) = self.modules.get(module_idx) else {
+            return false;
+        };
+        if self.fires.iter().any(|fire| fire.module == module.id) {
+            return false;
+        }
+        let position = module.transform.position;
+        self.fires.push(Fire { module: module.id, position, intensity: 0.1 });
+        self.hurt_crew(module_idx, "by fire");
+        self.emit_event(StationEvent::FireStarted { module: module_idx, position });
+        true
+    }
+
+    pub fn door_between(&self, a: usize, b: usize) -> Option<usize> {
+        let (a, b) = (self.module_id(a)?, self.module_id(b)?);
+        self.doors.iter().position(|door| door.joins(a, b))
+    }
+
+    // Paints a module's walls or trim, if the swatch is meant for it
+    pub fn paint_module(&mut self, module_idx: usize, surface: Surface, swatch: &Swatch) -> bool {
+        if !swatch.allows(surface) {
+            return false;
+        }
+        let Some(module) = self.modules.get_mut(module_idx) else {
+            return false;
+        };
+        module.paint.set(surface, Some(Coat { swatch: swatch.name.clone(), color: swatch.color }));
+        module.apply_paint();
+        true
+    }
+
+    // Fits a refit tier: its stats go in at once, along with the newer
+    // finish and the extra hull bands
+    pub fn refit_module(&mut self, module_idx: usize, refit: ModuleRefit) -> bool {
+        let Some(module) = self.modules.get_mut(module_idx) else {
+            return false;
+        };
+        module.refit = refit;
+        module.apply_refit();
+        self.update_structural_integrity();
+        true
+    }
+
+    // Sets a module's dimmer; false if it has no lights to dim
+    pub fn set_light_level(&mut self, module_idx: usize, level: f32) -> bool {
+        let Some(module) = self.modules.get_mut(module_idx) else {
+            return false;
+        };
+        if !module.interactive_elements.iter().any(|e| e.element_type == InteractionType::LightControl) {
+            return false;
         }
+        module.light_level = level.clamp(0.0, 1.0);
+        true
+    }
+
+    // Back to the module type's own finish
+    pub fn strip_paint(&mut self, module_idx: usize, surface: Surface) -> bool {
+        let Some(module) = self.modules.get_mut(module_idx) else {
+            return false;
+        };
+        module.paint.set(surface, None);
+        module.apply_paint();
+        true
     }
 
-    fn update(&mut self, delta_time: f32) {
-        // Update life support parameters
-        // This would be expanded based on module states and crew activities
+    // Shortest way from one module to another, both included, around locked
+    // doors and breached sections
+    pub fn find_path(&self, from: usize, to: usize) -> Option<Vec<usize>> {
+        navigation::find_path(self, from, to)
+    }
+
+    // 0 shut to 1 open
+    pub fn door_openness(&self, door_idx: usize) -> f32 {
+        self.doors
+            .get(door_idx)
+            .and_then(|door| self.modules.get(self.module_index(door.modules.0)?)?.interactive_elements.get(door.element))
+            .map_or(0.0, door::openness)
+    }
+
+    // Locking shuts the door at once; unlocking leaves it shut
+    pub fn set_door_locked(&mut self, door_idx: usize, locked: bool) -> bool {
+        let Some(&Door { modules: (module, _), element, .. }) = self.doors.get(door_idx) else {
+            return false;
+        };
+        let Some(module) = self.module_index(module) else {
+            return false;
+        };
+        let state = if locked { ElementState::Locked } else { ElementState::Inactive };
+        self.set_element_state(module, element, state)
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        // Advance mission time first so everything below is stamped consistently
+        self.clock.advance(delta_time);
+
+        // Update power distribution
+        self.update_solar(delta_time);
+        let storage = self.storage();
+        let shed = self.power_grid.update(&mut self.modules, storage, delta_time);
+        for module in shed {
+            let position = self.modules[module].transform.position;
+            self.emit_event(StationEvent::Brownout { module, position });
+        }
+
+        // Only report a deficit when it starts, not every frame it lasts
+        let deficit = self.power_grid.shortfall;
+        if deficit > 0.0 && !self.power_grid.in_deficit {
+            self.emit_event(StationEvent::PowerDeficit { deficit });
+        }
+        self.power_grid.in_deficit = deficit > 0.0;
+
+        let breathable: Vec<bool> = self.modules.iter().map(|module| module.atmosphere.oxygen_level() >= HYPOXIC).collect();
+        self.update_atmosphere(delta_time);
+        self.update_heat(delta_time);
+        self.update_wear(delta_time);
+        self.update_flares(delta_time);
+        self.update_resources(delta_time);
+        for ring in &mut self.rings {
+            ring.update(delta_time);
+        }
+
+        let doors: Vec<(usize, usize, f32)> = (0..self.doors.len())
+            .filter_map(|i| self.door_modules(i).map(|(a, b)| (a, b, self.door_openness(i))))
+            .collect();
+        let changes =
+            hazard::update_fires(&mut self.fires, &mut self.modules, &doors, &self.tunables, &mut self.rng, delta_time);
+        for change in changes {
+            if let FireChange::Started { module, .. } = change {
+                self.hurt_crew(module, "by fire");
+            }
+            self.emit_event(match change {
+                FireChange::Started { module, position } => StationEvent::FireStarted { module, position },
+                FireChange::Out { module, position } => StationEvent::FireOut { module, position },
+            });
+        }
+
+        // Crew are hurt as the air around them thins past breathing
+        for (i, breathable) in breathable.into_iter().enumerate() {
+            if breathable && self.modules[i].atmosphere.oxygen_level() < HYPOXIC {
+                self.hurt_crew(i, "by thin air");
+            }
+        }
+
+        // Update all modules, then report the switches that finished
+        let mut settled = Vec::new();
+        for (i, module) in self.modules.iter_mut().enumerate() {
+            for element in module.update(delta_time, &self.tunables) {
+                settled.push((i, element, module.interactive_elements[element].state));
+            }
+        }
+        for (module, element, state) in settled {
+            if let Some(position) = self.element_position(module, element) {
+                self.emit_event(element_event(module, element, position, state));
+            }
+        }
+
+        // Unseal modules whose hull has failed
+        let mut breaches = Vec::new();
+        for (i, module) in self.modules.iter_mut().enumerate() {
+            if module.atmosphere_sealed && module.structural_integrity < self.tunables.hull_breach_threshold {
+                module.atmosphere_sealed = false;
+                breaches.push(StationEvent::HullBreach {
+                    module: i,
+                    position: module.transform.position,
+                });
+            }
+        }
+        for event in breaches {
+            self.emit_event(event);
+        }
+
+        // Update structural integrity
+        self.update_structural_integrity();
+        self.update_joints(delta_time);
+    }
+
+    // Swaps in new balancing values. Power draws and generator output are
+    // rewritten on the spot; everything else applies from the next update.
+    pub fn set_tunables(&mut self, tunables: &Tunables) {
+        self.tunables = tunables.station.clone();
+        self.power_tunables = tunables.power.clone();
+        self.power_grid.battery_charge = self.power_grid.battery_charge.min(self.storage().capacity);
+        for module in &mut self.modules {
+            if module.module_type == ModuleType::PowerPlant {
+                module.power_generation = tunables.power.generator_output;
+            }
+            for element in &mut module.interactive_elements {
+                element.power_draw = tunables.power.element_draw(element.element_type);
+            }
+        }
+        self.update_structural_integrity();
+    }
+
+    pub fn tunables(&self) -> &StationTunables {
+        &self.tunables
+    }
+
+    // Restarts the station's random numbers; the same seed replays the same run
+    pub fn reseed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.rng = rng::seeded(seed);
+    }
+
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    // Anything random in the simulation draws from here, or from a
+    // generator forked off it
+    pub fn rng(&mut self) -> &mut SimRng {
+        &mut self.rng
+    }
+
+    pub fn modules(&self) -> &[StationModule] {
+        &self.modules
+    }
+
+    // What an element draws over its tunable base, as for an experiment
+    // running on it; set_tunables puts it back to the base
+    pub fn set_element_load(&mut self, module_idx: usize, element_idx: usize, extra: f32) -> bool {
+        let base = self.power_tunables.clone();
+        let Some(element) = self.modules.get_mut(module_idx).and_then(|m| m.interactive_elements.get_mut(element_idx)) else {
+            return false;
+        };
+        element.power_draw = base.element_draw(element.element_type) + extra;
+        true
+    }
+
+    pub fn module_mut(&mut self, module_idx: usize) -> Option<&mut StationModule> {
+        self.modules.get_mut(module_idx)
+    }
+
+    pub fn module_id(&self, module_idx: usize) -> Option<ModuleId> {
+        self.modules.get(module_idx).map(|module| module.id)
+    }
+
+    // Where the module is in the list now; None once it's gone
+    pub fn module_index(&self, id: ModuleId) -> Option<usize> {
+        self.module_ids.get(id).filter(|&idx| self.modules.get(idx).is_some_and(|module| module.id == id))
+    }
+
+    pub fn structural_integrity(&self) -> f32 {
+        self.structural_integrity
+    }
+
+    // Releases every locked element in a module; returns how many were unlocked
+    pub fn unlock_module(&mut self, module_idx: usize) -> usize {
+        let Some(module) = self.modules.get_mut(module_idx) else {
+            return 0;
+        };
+        let mut unlocked = 0;
+        for element in &mut module.interactive_elements {
+            if element.state == ElementState::Locked {
+                element.state = ElementState::Inactive;
+                unlocked += 1;
+            }
+        }
+        if unlocked > 0 {
+            self.log_event(LogSeverity::Info, format!("Module {} unlocked", module_idx));
+        }
+        unlocked
+    }
+
+    // Puts a module's elements of one type behind a clearance; returns how
+    // many there were
+    pub fn restrict(&mut self, module_idx: usize, element_type: InteractionType, clearance: &str) -> usize {
+        let Some(module) = self.modules.get_mut(module_idx) else {
+            return 0;
+        };
+        let mut restricted = 0;
+        for element in module.interactive_elements.iter_mut().filter(|element| element.element_type == element_type) {
+            element.clearance = Some(clearance.to_string());
+            restricted += 1;
+        }
+        restricted
+    }
+
+    pub fn clearance_needed(&self, module_idx: usize, element_idx: usize) -> Option<&str> {
+        self.modules.get(module_idx)?.interactive_elements.get(element_idx)?.clearance.as_deref()
+    }
+
+    // DOT source for the module, power or atmosphere network
+    pub fn export_graphviz(&self, kind: GraphKind) -> String {
+        graphviz::export(self, kind)
+    }
+
+    // Module meshes, materials and door panels as a glTF model (.glb or .gltf)
+    pub fn export_gltf<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        gltf::export(self, path.as_ref())
+    }
+
+    pub fn nearest_module(&self, position: Vec3) -> Option<usize> {
+        self.modules
+            .iter()
+            .enumerate()
+            .map(|(i, module)| (i, module.transform.position.distance(position)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+            .map(|(i, _)| i)
+    }
+
+    // Someone walked `distance` across the floor at `position`
+    pub fn tread(&mut self, position: Vec3, distance: f32) {
+        let Some(module_idx) = self.nearest_module(position).filter(|_| distance > 0.0) else {
+            return;
+        };
+        let module = &mut self.modules[module_idx];
+        let uv = module.floor_uv(position);
+        module.grime.paint(uv, distance * grime::TREAD_PER_METRE);
+    }
+
+    // Cleans `amount` off a module's floor and elements
+    pub fn scrub_module(&mut self, module_idx: usize, amount: f32) -> bool {
+        let Some(module) = self.modules.get_mut(module_idx) else {
+            return false;
+        };
+        module.grime.scrub(amount);
+        for element in &mut module.interactive_elements {
+            element.grime = (element.grime - amount).max(0.0);
+        }
+        true
+    }
+
+    // Restores integrity to a module; false if it was already intact
+    pub fn repair_module(&mut self, module_idx: usize, amount: f32) -> bool {
+        let Some(module) = self.modules.get_mut(module_idx) else {
+            return false;
+        };
+        if module.structural_integrity >= 1.0 {
+            return false;
+        }
+        module.structural_integrity = (module.structural_integrity + amount).min(1.0);
+        let integrity = module.structural_integrity;
+
+        // A patched hull holds air again
+        let resealed = !module.atmosphere_sealed && integrity >= self.tunables.hull_breach_threshold;
+        if resealed {
+            module.atmosphere_sealed = true;
+        }
+        self.log_event(
+            LogSeverity::Info,
+            format!("Module {} repaired to {:.0}%", module_idx, integrity * 100.0),
+        );
+        if resealed {
+            self.log_event(LogSeverity::Info, format!("Module {} resealed", module_idx));
+        }
+        true
+    }
+
+    // Restores a component's condition; false if it was already like new
+    pub fn repair_component(&mut self, module_idx: usize, component: Component, amount: f32) -> bool {
+        if component == Component::Hull {
+            return self.repair_module(module_idx, amount);
+        }
+        let Some(module) = self.modules.get_mut(module_idx) else {
+            return false;
+        };
+        let condition = module.condition_mut(component);
+        if *condition >= 1.0 {
+            return false;
+        }
+        *condition = (*condition + amount).min(1.0);
+        let message = format!("Module {} {} repaired to {:.0}%", module_idx, component.name(), *condition * 100.0);
+        self.log_event(LogSeverity::Info, message);
+        true
+    }
+
+    // Every so often a module's component wears a little. Past half worn
+    // it's worth a warning.
+    fn update_wear(&mut self, delta_time: f32) {
+        let tunables = &self.tunables;
+        let mut worn = Vec::new();
+        for (i, module) in self.modules.iter_mut().enumerate() {
+            if self.rng.gen::<f32>() >= tunables.wear_chance * delta_time {
+                continue;
+            }
+            let component = Component::ALL[self.rng.gen_range(0..Component::ALL.len())];
+            let condition = module.condition_mut(component);
+            let before = *condition;
+            *condition = (before - tunables.wear_amount).max(0.0);
+            if before > 0.5 && *condition <= 0.5 {
+                worn.push((i, component));
+            }
+        }
+        for (module, component) in worn {
+            self.log_event(LogSeverity::Warning, format!("Module {} {} is badly worn", module, component.name()));
+        }
+
+        // Machinery clocks up hours while it runs, and the longer since its
+        // last service the likelier it is to break down
+        let mut failures = Vec::new();
+        for (i, module) in self.modules.iter_mut().enumerate() {
+            for (j, element) in module.interactive_elements.iter_mut().enumerate() {
+                let running = element.state == ElementState::Active && (module.powered || !element.element_type.needs_power());
+                if !running || element.element_type.service_hours().is_none() {
+                    continue;
+                }
+                element.hours += delta_time / self.tunables.usage_hour;
+                if self.rng.gen::<f32>() < maintenance::failure_rate(element, &self.tunables) * delta_time {
+                    failures.push((i, j));
+                }
+            }
+        }
+        for (module, element) in failures {
+            self.set_element_state(module, element, ElementState::Malfunction);
+        }
+
+        // Life support filters clog as the units run, and a clogged one stops
+        let mut clogged = Vec::new();
+        for (i, module) in self.modules.iter_mut().enumerate() {
+            if !module.powered {
+                continue;
+            }
+            for unit in &mut module.life_support_units {
+                if unit.wear(delta_time / self.tunables.usage_hour) {
+                    clogged.push((i, unit.kind, module.transform.position));
+                }
+            }
+        }
+        for (module, kind, position) in clogged {
+            self.emit_event(StationEvent::LifeSupportFailed { module, position, kind });
+        }
+    }
+
+    // A new filter for a life support unit, for a spare part from the
+    // stores; a failed one starts up again. False if there's no such unit or
+    // no parts.
+    pub fn replace_filter(&mut self, module_idx: usize, unit_idx: usize) -> bool {
+        let exists = self.modules.get(module_idx).is_some_and(|module| unit_idx < module.life_support_units.len());
+        if !exists || self.resources.total(Resource::SpareParts) < 1.0 {
+            return false;
+        }
+        self.use_resource(Resource::SpareParts, 1.0);
+        let unit = &mut self.modules[module_idx].life_support_units[unit_idx];
+        unit.replace_filter();
+        let name = unit.kind.name();
+        self.log_event(LogSeverity::Info, format!("{} in module {} has a new filter", name, module_idx));
+        true
+    }
+
+    // Switches a life support unit on or off; false if there's no such unit
+    // or it's failed
+    pub fn switch_life_support(&mut self, module_idx: usize, unit_idx: usize, on: bool) -> bool {
+        self.modules
+            .get_mut(module_idx)
+            .and_then(|module| module.life_support_units.get_mut(unit_idx))
+            .is_some_and(|unit| unit.switch(on))
+    }
+
+    // A service: its hours go back to zero and a fault is put right, for a
+    // spare part from the stores. False if there's no such element, it
+    // doesn't wear, or there are no parts.
+    pub fn service_element(&mut self, module_idx: usize, element_idx: usize) -> bool {
+        let wears = self
+            .element_mut(module_idx, element_idx)
+            .is_some_and(|element| element.element_type.service_hours().is_some());
+        if !wears || self.resources.total(Resource::SpareParts) < 1.0 {
+            return false;
+        }
+        self.use_resource(Resource::SpareParts, 1.0);
+        if let Some(element) = self.element_mut(module_idx, element_idx) {
+            element.hours = 0.0;
+            if element.state == ElementState::Malfunction {
+                element.state = ElementState::Inactive;
+                element.target = None;
+            }
+        }
+        self.log_event(LogSeverity::Info, format!("Element {} in module {} serviced", element_idx, module_idx));
+        true
+    }
+
+    // Heat builds and spreads; past the overheat line running equipment
+    // starts to fail, and crossing it is reported
+    fn update_heat(&mut self, delta_time: f32) {
+        let connections = self.connections();
+        let before = thermal::update_heat(&mut self.modules, &connections, &self.tunables, delta_time);
+        let limit = self.tunables.overheat_temperature;
+        let mut overheated = Vec::new();
+        let mut failures = Vec::new();
+        for (i, module) in self.modules.iter().enumerate() {
+            let temperature = module.atmosphere.temperature;
+            if temperature <= limit {
+                continue;
+            }
+            if before[i] <= limit {
+                let position = module.transform.position;
+                overheated.push(StationEvent::Overheating { module: i, position, temperature });
+            }
+            let chance = self.tunables.overheat_failure_chance * (temperature - limit) * delta_time;
+            for (j, element) in module.interactive_elements.iter().enumerate() {
+                if element.state == ElementState::Active && self.rng.gen::<f32>() < chance {
+                    failures.push((i, j));
+                }
+            }
+        }
+        for event in overheated {
+            self.emit_event(event);
+        }
+        for (module, element) in failures {
+            self.set_element_state(module, element, ElementState::Malfunction);
+        }
+    }
+
+    // Stores run down and fill up at the modules' rates, and thin air is
+    // topped up from stored canisters. Falling below a low mark, and
+    // running out, are reported.
+    fn update_resources(&mut self, delta_time: f32) {
+        let before = Resource::ALL.map(|resource| self.resources.total(resource));
+        let rates = resources::rates(&self.modules, &self.tunables);
+        for (resource, rate) in Resource::ALL.into_iter().zip(rates) {
+            if rate < 0.0 {
+                self.resources.withdraw(resource, -rate * delta_time);
+            } else {
+                self.resources.deposit_anywhere(resource, rate * delta_time);
+            }
+        }
+
+        let mut vented = Vec::new();
+        let mut canisters = self.resources.total(Resource::OxygenCanisters);
+        for (i, module) in self.modules.iter().enumerate() {
+            let thin = module.atmosphere_sealed && module.atmosphere.oxygen_level() < self.tunables.canister_threshold;
+            if thin && canisters >= 1.0 {
+                canisters -= 1.0;
+                vented.push(i);
+            }
+        }
+        for module in vented {
+            self.use_resource(Resource::OxygenCanisters, 1.0);
+            self.add_oxygen(module, self.tunables.canister_oxygen);
+            self.log_event(LogSeverity::Info, format!("Stored O2 canister vented into module {}", module));
+        }
+
+        for (resource, before) in Resource::ALL.into_iter().zip(before) {
+            let remaining = self.resources.total(resource);
+            let low = resource.low_mark();
+            if (before > low && remaining <= low) || (before > 0.0 && remaining <= 0.0) {
+                self.emit_event(StationEvent::ResourceShortage { resource, remaining });
+            }
+        }
+    }
+
+    pub fn resources(&self) -> &ResourceLedger {
+        &self.resources
+    }
+
+    pub fn resource_rates(&self) -> [f32; 4] {
+        resources::rates(&self.modules, &self.tunables)
+    }
+
+    // Moves stock between storage modules, logging what moved
+    pub fn transfer_resource(&mut self, from: usize, to: usize, resource: Resource, amount: f32) -> f32 {
+        let (Some(from_id), Some(to_id)) = (self.module_id(from), self.module_id(to)) else {
+            return 0.0;
+        };
+        let moved = self.resources.transfer(from_id, to_id, resource, amount);
+        if moved > 0.0 {
+            let message = format!("Moved {:.0}{} {} from module {} to {}", moved, resource.unit(), resource.name(), from, to);
+            self.log_event(LogSeverity::Info, message);
+        }
+        moved
+    }
+
+    // Takes `amount` out of the stores, only if there's that much in all
+    pub fn spend_resource(&mut self, resource: Resource, amount: f32) -> bool {
+        if self.resources.total(resource) < amount {
+            return false;
+        }
+        self.use_resource(resource, amount);
+        true
+    }
+
+    // Hand transfers at a storage module's access point. What's carried off
+    // counts as used until it's put back.
+    pub fn take_resource(&mut self, module_idx: usize, resource: Resource, amount: f32) -> f32 {
+        let taken = self.module_id(module_idx).map_or(0.0, |id| self.resources.withdraw_from(id, resource, amount));
+        self.resources_used += taken * resource.weight();
+        taken
+    }
+
+    pub fn stow_resource(&mut self, module_idx: usize, resource: Resource, amount: f32) -> f32 {
+        let stowed = self.module_id(module_idx).map_or(0.0, |id| self.resources.deposit(id, resource, amount));
+        self.resources_used -= stowed * resource.weight();
+        stowed
+    }
+
+    // Out of the stores for a job or an emergency, rather than the crew's
+    // upkeep, so it's tallied
+    fn use_resource(&mut self, resource: Resource, amount: f32) -> f32 {
+        let used = self.resources.withdraw(resource, amount);
+        self.resources_used += used * resource.weight();
+        used
+    }
+
+    // What's been used up on jobs and emergencies so far, by Resource::weight
+    pub fn resources_used(&self) -> f32 {
+        self.resources_used
+    }
+
+    // Crew hurt so far, by fire, thin air or radiation
+    pub fn injuries(&self) -> u32 {
+        self.injuries
+    }
+
+    // Someone hurt away from the station's own hazards, as by a radiation dose
+    pub fn record_injury(&mut self) {
+        self.injuries += 1;
+    }
+
+    fn hurt_crew(&mut self, module_idx: usize, cause: &str) {
+        let crew = self.modules.get(module_idx).map_or(0, |module| module.crew);
+        if crew == 0 {
+            return;
+        }
+        self.injuries += crew;
+        self.log_event(LogSeverity::Warning, format!("{} crew hurt {} in module {}", crew, cause, module_idx));
+    }
+
+    // Wherever there's room; returns what fit
+    pub fn stow_anywhere(&mut self, resource: Resource, amount: f32) -> f32 {
+        self.resources.deposit_anywhere(resource, amount)
+    }
+
+    pub fn orbit(&self) -> &Orbit {
+        &self.orbit
+    }
+
+    // Unit vector toward the sun now, in the station frame
+    pub fn sun_direction(&self) -> Vec3 {
+        self.orbit.sun_direction(self.clock.now().as_secs_f32())
+    }
+
+    // 1 in full sun, 0 in the planet's shadow
+    pub fn sunlight(&self) -> f32 {
+        self.orbit.sunlight(self.clock.now().as_secs_f32())
+    }
+
+    // The station battery with every battery module's cells added on
+    fn storage(&self) -> Storage {
+        let tunables = &self.power_tunables;
+        let banks = self.modules.iter().filter(|module| module.module_type == ModuleType::Battery).count() as f32;
+        Storage {
+            capacity: tunables.battery_capacity + banks * tunables.battery_module_capacity,
+            charge_rate: tunables.battery_charge_rate + banks * tunables.battery_module_rate,
+            discharge_rate: tunables.battery_discharge_rate + banks * tunables.battery_module_rate,
+        }
+    }
+
+    // Tracking motors that are on and powered turn their panels after the
+    // sun, then each array makes what its panels catch
+    fn update_solar(&mut self, delta_time: f32) {
+        let sun = self.sun_direction();
+        let sunlight = self.sunlight();
+        let step = self.power_tunables.tracking_rate.to_radians() * delta_time;
+        for module in &mut self.modules {
+            let Some(facing) = module.panel else {
+                continue;
+            };
+            let tracking = module.powered
+                && module.interactive_elements.iter().any(|element| {
+                    element.element_type == InteractionType::SunTracker && element.state == ElementState::Active
+                });
+            let facing = if tracking { solar::slew(facing, sun, step) } else { facing };
+            module.panel = Some(facing);
+            module.power_generation =
+                self.power_tunables.solar_output * solar::exposure(facing, sun, sunlight) * module.structural_integrity;
+        }
+    }
+
+    // Flares come out of nowhere now and then, at a random strength, unless
+    // the event director's seeing to them
+    fn update_flares(&mut self, delta_time: f32) {
+        if self.interference.update(delta_time) {
+            self.emit_event(StationEvent::SolarFlareEnded);
+        }
+        if !self.flares_directed && !self.interference.active() && self.rng.gen::<f32>() < self.tunables.flare_chance * delta_time {
+            let intensity = self.rng.gen_range(0.3..1.0);
+            self.begin_flare(intensity, self.tunables.flare_duration);
+        }
+    }
+
+    // Sets off a solar flare of `intensity` 0 to 1 lasting `duration` seconds
+    pub fn begin_flare(&mut self, intensity: f32, duration: f32) {
+        self.interference.begin(intensity, duration);
+        self.emit_event(StationEvent::SolarFlare { intensity: intensity.clamp(0.0, 1.0) });
+    }
+
+    pub fn set_flares_directed(&mut self, directed: bool) {
+        self.flares_directed = directed;
+    }
+
+    pub fn interference(&self) -> &Interference {
+        &self.interference
+    }
+
+    // Spins the spec's modules as a ring section; None if one's missing or
+    // already turning in another ring
+    pub fn add_ring(&mut self, spec: &RingSpec) -> Option<usize> {
+        let mut modules = Vec::with_capacity(spec.modules.len());
+        for &id in &spec.modules {
+            let index = self.module_index(id)?;
+            if self.ring_of(index).is_some() || modules.contains(&id) {
+                return None;
+            }
+            modules.push(id);
+        }
+        let mut ring = RingSection::new(&spec.name, Vec3::from(spec.centre), Vec3::from(spec.axis), spec.rpm, modules);
+        ring.angle = spec.angle;
+        self.rings.push(ring);
+        Some(self.rings.len() - 1)
+    }
+
+    pub fn rings(&self) -> &[RingSection] {
+        &self.rings
+    }
+
+    // Spins a ring up or down; it's there straight away, there's no motor
+    pub fn set_ring_rpm(&mut self, ring: usize, rpm: f32) -> bool {
+        let Some(ring) = self.rings.get_mut(ring) else {
+            return false;
+        };
+        ring.rpm = rpm;
+        true
+    }
+
+    pub fn ring_of(&self, module: usize) -> Option<&RingSection> {
+        let id = self.module_id(module)?;
+        self.rings.iter().find(|ring| ring.modules.contains(&id))
+    }
+
+    // As saves list them, leaving out modules that have gone
+    pub fn ring_specs(&self) -> Vec<RingSpec> {
+        self.rings
+            .iter()
+            .map(|ring| RingSpec {
+                name: ring.name.clone(),
+                centre: ring.centre.to_array(),
+                axis: ring.axis.to_array(),
+                rpm: ring.rpm,
+                modules: ring.modules.iter().copied().filter(|&id| self.module_index(id).is_some()).collect(),
+                angle: ring.angle,
+            })
+            .collect()
+    }
+
+    // Gravity at `position`, in m/s²: the ring's spin in a ring section's
+    // modules, the deck's everywhere else
+    pub fn gravity_at(&self, position: Vec3) -> Vec3 {
+        match self.nearest_module(position).and_then(|module| self.ring_of(module)) {
+            Some(ring) => ring.gravity(position),
+            None => ring::deck_gravity(),
+        }
+    }
+
+    // Which way is up for someone standing at `position`; a ring that's
+    // stopped has no floor, so they keep the one they had
+    pub fn local_up(&self, position: Vec3, previous: Vec3) -> Vec3 {
+        (-self.gravity_at(position)).try_normalize().unwrap_or(previous)
+    }
+
+    pub fn storm_shelter(&self) -> Option<usize> {
+        self.storm_shelter.and_then(|id| self.module_index(id))
+    }
+
+    // Makes `module` the storm shelter, in place of any other, or stands the
+    // shelter down with None; false if there's no such module
+    pub fn set_storm_shelter(&mut self, module: Option<usize>) -> bool {
+        let Some(index) = module else {
+            self.storm_shelter = None;
+            self.log_event(LogSeverity::Info, "Storm shelter stood down".to_string());
+            return true;
+        };
+        let Some(id) = self.module_id(index) else {
+            return false;
+        };
+        self.storm_shelter = Some(id);
+        let message = format!("Module {} ({:?}) designated storm shelter", index, self.modules[index].module_type);
+        self.log_event(LogSeverity::Info, message);
+        true
+    }
+
+    // Radiation outside the hull, in mSv/s
+    pub fn exterior_radiation(&self) -> f32 {
+        radiation::exterior_rate(&self.tunables, &self.interference)
+    }
+
+    // What a module's walls stop, in halvings: less as the hull's damaged,
+    // and more in the storm shelter
+    pub fn shielding(&self, module: usize) -> f32 {
+        let Some(here) = self.modules.get(module) else {
+            return 0.0;
+        };
+        let shelter = if self.storm_shelter() == Some(module) { self.tunables.shelter_shielding } else { 0.0 };
+        (here.module_type.shielding() + shelter) * here.structural_integrity
+    }
+
+    // Radiation inside a module, in mSv/s
+    pub fn radiation(&self, module: usize) -> f32 {
+        self.exterior_radiation() * radiation::transmission(self.shielding(module))
+    }
+
+    // Crew breathe, scrubbers clean, generators top up, air evens out
+    // through open doors, and breached modules vent to space
+    fn update_atmosphere(&mut self, delta_time: f32) {
+        let tunables = &self.tunables;
+        for module in &mut self.modules {
+            let volume = module.module_type.volume();
+            let scrubbers = module.scrubber_output();
+            let generators = module.life_support_output(LifeSupportKind::OxygenGenerator);
+            let air = &mut module.atmosphere;
+            air.breathe(module.crew as f32 * tunables.crew_oxygen_use * delta_time / volume);
+            air.scrub(scrubbers * tunables.scrubber_rate * delta_time / volume);
+            if module.atmosphere_sealed {
+                let leak = tunables.hull_leak_rate * (1.0 - module.structural_integrity);
+                air.vent(1.0 - (-leak * delta_time).exp());
+                air.replenish(generators * tunables.replenish_rate * delta_time / volume);
+            } else {
+                air.vent(1.0 - (-tunables.vent_rate * delta_time).exp());
+            }
+        }
+
+        let fraction = 1.0 - (-tunables.equalize_rate * delta_time).exp();
+        for door_idx in 0..self.doors.len() {
+            let openness = self.door_openness(door_idx);
+            let Some((i, j)) = self.door_modules(door_idx) else {
+                continue;
+            };
+            if openness <= 0.0 || i >= j {
+                continue;
+            }
+            let (left, right) = self.modules.split_at_mut(j);
+            let (a, b) = (&mut left[i], &mut right[0]);
+            let (volume_a, volume_b) = (a.module_type.volume(), b.module_type.volume());
+            atmosphere::equalize(&mut a.atmosphere, volume_a, &mut b.atmosphere, volume_b, fraction * openness);
+        }
+    }
+
+    // Station-wide air, averaged over every module's volume:
+    // (oxygen level, CO2 partial pressure, temperature, pressure)
+    fn air_summary(&self) -> (f32, f32, f32, f32) {
+        let total: f32 = self.modules.iter().map(|m| m.module_type.volume()).sum();
+        if total <= 0.0 {
+            return (0.0, 0.0, atmosphere::NOMINAL_TEMPERATURE, 0.0);
+        }
+        let average = |value: &dyn Fn(&Atmosphere) -> f32| {
+            self.modules.iter().map(|m| value(&m.atmosphere) * m.module_type.volume()).sum::<f32>() / total
+        };
+        (
+            average(&Atmosphere::oxygen_level),
+            average(&|air| air.co2),
+            average(&|air| air.temperature),
+            average(&Atmosphere::pressure),
+        )
+    }
+
+    // Vents oxygen into a module, `amount` as a fraction of a normal
+    // supply; returns how much it actually took
+    pub fn add_oxygen(&mut self, module_idx: usize, amount: f32) -> f32 {
+        let Some(module) = self.modules.get_mut(module_idx) else {
+            return 0.0;
+        };
+        let room = (NOMINAL_OXYGEN - module.atmosphere.oxygen).max(0.0);
+        let added = (amount * NOMINAL_OXYGEN).min(room);
+        module.atmosphere.oxygen += added;
+        added / NOMINAL_OXYGEN
+    }
+
+    // Knocks integrity off a module, and a share of that off its wiring and
+    // life support; a breach is raised on the next update
+    pub fn damage_module(&mut self, module_idx: usize, amount: f32) -> bool {
+        let share = self.tunables.component_damage_share;
+        match self.modules.get_mut(module_idx) {
+            Some(module) => {
+                // Reinforced hulls take less of it
+                module.structural_integrity = (module.structural_integrity - amount / module.refit.hull).clamp(0.0, 1.0);
+                module.wiring = (module.wiring - amount * share).clamp(0.0, 1.0);
+                module.life_support = (module.life_support - amount * share).clamp(0.0, 1.0);
+                if amount > 0.0 {
+                    let event = StationEvent::ModuleDamaged {
+                        module: module_idx,
+                        position: module.transform.position,
+                        integrity: module.structural_integrity,
+                    };
+                    self.emit_event(event);
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    // A spike through a module's wiring burns some of it out
+    pub fn power_surge(&mut self, module_idx: usize, amount: f32) -> bool {
+        let Some(module) = self.modules.get_mut(module_idx) else {
+            return false;
+        };
+        module.wiring = (module.wiring - amount).clamp(0.0, 1.0);
+        let message = format!("Power surge in module {}: wiring down to {:.0}%", module_idx, module.wiring * 100.0);
+        self.log_event(LogSeverity::Warning, message);
+        true
+    }
+
+    pub fn telemetry(&self) -> StationTelemetry {
+        let (oxygen_level, co2, temperature, pressure) = self.air_summary();
+        StationTelemetry {
+            power_generation: self.modules.iter().map(|m| m.power_output()).sum(),
+            power_consumption: self.modules.iter().map(|m| m.power_draw()).sum(),
+            grid_stability: self.power_grid.grid_stability,
+            battery_level: self.power_grid.battery_charge / self.storage().capacity.max(f32::EPSILON),
+            oxygen_level,
+            co2,
+            temperature,
+            pressure,
+            structural_integrity: self.structural_integrity,
+            module_count: self.modules.len(),
+            sealed_modules: self.modules.iter().filter(|m| m.atmosphere_sealed).count(),
+            powered_modules: self.modules.iter().filter(|m| m.powered).count(),
+        }
+    }
+
+    // Telemetry as the station's sensors report it, noisy during a flare
+    pub fn sensor_telemetry(&self) -> StationTelemetry {
+        let truth = self.telemetry();
+        let noise = &self.interference;
+        StationTelemetry {
+            power_generation: noise.sensor(truth.power_generation, 5.0, 0).max(0.0),
+            power_consumption: noise.sensor(truth.power_consumption, 5.0, 1).max(0.0),
+            grid_stability: noise.sensor(truth.grid_stability, 0.2, 2).clamp(0.0, 1.0),
+            battery_level: noise.sensor(truth.battery_level, 0.15, 3).clamp(0.0, 1.0),
+            oxygen_level: noise.sensor(truth.oxygen_level, 0.1, 4).max(0.0),
+            co2: noise.sensor(truth.co2, 0.01, 5).max(0.0),
+            temperature: noise.sensor(truth.temperature, 8.0, 6),
+            pressure: noise.sensor(truth.pressure, 0.1, 7).max(0.0),
+            structural_integrity: noise.sensor(truth.structural_integrity, 0.1, 8).clamp(0.0, 1.0),
+            ..truth
+        }
+    }
+
+    pub fn element_position(&self, module_idx: usize, element_idx: usize) -> Option<Vec3> {
+        let module = self.modules.get(module_idx)?;
+        let element = module.interactive_elements.get(element_idx)?;
+        Some(module.transform.matrix().transform_point3(element.position))
+    }
+
+    // Closest interactive element within `reach` of `position`, as (module, element)
+    pub fn nearest_element(&self, position: Vec3, reach: f32) -> Option<(usize, usize)> {
+        let mut nearest = None;
+        let mut nearest_distance = reach;
+        for (module_idx, module) in self.modules.iter().enumerate() {
+            for element_idx in 0..module.interactive_elements.len() {
+                let Some(element_position) = self.element_position(module_idx, element_idx) else {
+                    continue;
+                };
+                let distance = element_position.distance(position);
+                if distance <= nearest_distance {
+                    nearest_distance = distance;
+                    nearest = Some((module_idx, element_idx));
+                }
+            }
+        }
+        nearest
+    }
+
+    // A request to use an element as its Activation says; returns what it is
+    // if the request was taken. Switches with a transition time go through
+    // Transitioning and land in a later update, others land now; either way
+    // landing emits ElementActivated. Passive, locked, busy, cooling down and
+    // failed elements refuse. In a damaged module the switch may fail instead,
+    // leaving the element malfunctioning. Whether it's taken or not, the hand
+    // that tried leaves some grime on it.
+    pub fn activate_element(&mut self, module_idx: usize, element_idx: usize) -> Option<InteractionType> {
+        let element = self.element_mut(module_idx, element_idx)?;
+        element.grime = (element.grime + grime::TOUCH).min(1.0);
+        self.request_element(module_idx, element_idx)
+    }
+
+    fn request_element(&mut self, module_idx: usize, element_idx: usize) -> Option<InteractionType> {
+        let position = self.element_position(module_idx, element_idx)?;
+        let damage = 1.0 - self.modules[module_idx].structural_integrity;
+        let powered = self.modules[module_idx].powered;
+        let element = self.modules[module_idx].interactive_elements.get_mut(element_idx)?;
+        let element_type = element.element_type;
+        let activation = element_type.activation();
+        if activation == Activation::Passive || element.cooldown > 0.0 || (!powered && element_type.needs_power()) {
+            return None;
+        }
+        let target = match element.state {
+            // Buttons go down and come back up on their own
+            ElementState::Inactive if activation == Activation::Momentary => ElementState::Inactive,
+            ElementState::Inactive => ElementState::Active,
+            ElementState::Active => ElementState::Inactive,
+            ElementState::Locked if element_type == InteractionType::Door => {
+                self.emit_event(StationEvent::DoorLocked { module: module_idx, position });
+                return None;
+            }
+            _ => return None,
+        };
+        let failed = damage > 0.0 && self.rng.gen::<f32>() < damage * FAILURE_PER_DAMAGE;
+        let target = if failed { ElementState::Malfunction } else { target };
+
+        if element_type.transition_seconds() > 0.0 {
+            element.state = ElementState::Transitioning(0.0);
+            element.target = Some(target);
+            if activation == Activation::Momentary && !failed {
+                self.emit_event(StationEvent::ElementActivated {
+                    module: module_idx,
+                    element: element_idx,
+                    position,
+                    active: true,
+                });
+            }
+            return Some(element_type);
+        }
+        element.state = target;
+        element.cooldown = element_type.cooldown_seconds();
+        self.emit_event(element_event(module_idx, element_idx, position, target));
+        (!failed).then_some(element_type)
+    }
+
+    // `activate_element` sent over the station network rather than by hand.
+    // A flare may swallow the command, which is logged.
+    pub fn remote_activate(&mut self, module_idx: usize, element_idx: usize) -> Option<InteractionType> {
+        let loss = self.interference.level() * self.tunables.flare_command_loss;
+        if loss > 0.0 && self.rng.gen::<f32>() < loss {
+            let message = format!("Command to element {} in module {} lost to interference", element_idx, module_idx);
+            self.log_event(LogSeverity::Warning, message);
+            return None;
+        }
+        self.request_element(module_idx, element_idx)
+    }
+
+    pub fn element_state(&self, module_idx: usize, element_idx: usize) -> Option<ElementState> {
+        Some(self.modules.get(module_idx)?.interactive_elements.get(element_idx)?.state)
+    }
+
+    // Forces a state, cancelling any transition in progress. Breaking an
+    // element this way still reports ElementFailed.
+    pub fn set_element_state(&mut self, module_idx: usize, element_idx: usize, state: ElementState) -> bool {
+        let Some(position) = self.element_position(module_idx, element_idx) else {
+            return false;
+        };
+        let Some(element) = self.element_mut(module_idx, element_idx) else {
+            return false;
+        };
+        let failed = state == ElementState::Malfunction && element.state != ElementState::Malfunction;
+        element.state = state;
+        element.target = None;
+        if failed {
+            self.emit_event(StationEvent::ElementFailed {
+                module: module_idx,
+                element: element_idx,
+                position,
+            });
+        }
+        true
+    }
+
+    fn element_mut(&mut self, module_idx: usize, element_idx: usize) -> Option<&mut InteractiveElement> {
+        self.modules.get_mut(module_idx)?.interactive_elements.get_mut(element_idx)
+    }
+
+    pub fn snapshot(&self) -> StationSnapshot {
+        let (oxygen_level, _, temperature, pressure) = self.air_summary();
+        StationSnapshot {
+            mission_time_ms: self.clock.now().as_millis(),
+            seed: self.seed,
+            epoch: self.clock.epoch(),
+            grid_stability: self.power_grid.grid_stability,
+            in_power_deficit: self.power_grid.in_deficit,
+            battery_charge: self.power_grid.battery_charge,
+            oxygen_level,
+            temperature,
+            pressure,
+            modules: self
+                .modules
+                .iter()
+                .map(ModuleSnapshot::from)
+                .collect(),
+            fires: self.fires.iter().map(FireSnapshot::from).collect(),
+            stores: Some(self.resources.stores().to_vec()),
+            storm_shelter: self.storm_shelter,
+            rings: self.ring_specs(),
+            doors: Some(
+                self.doors
+                    .iter()
+                    .map(|door| DoorSnapshot {
+                        modules: [door.modules.0, door.modules.1],
+                        element: door.element,
+                    })
+                    .collect(),
+            ),
+        }
+    }
+
+    // Rebuilds a station from a snapshot. The event log starts empty.
+    pub fn from_snapshot(snapshot: &StationSnapshot) -> Self {
+        let mut station = Self::new();
+        station.reseed(snapshot.seed);
+        station.clock = MissionClock::resume(snapshot.epoch, MissionTime::from_millis(snapshot.mission_time_ms));
+        station.power_grid.grid_stability = snapshot.grid_stability;
+        station.power_grid.in_deficit = snapshot.in_power_deficit;
+        station.power_grid.battery_charge = snapshot.battery_charge;
+
+        let module_count = snapshot.modules.len();
+        for saved in &snapshot.modules {
+            let mut module = StationModule::from(saved);
+            // Saves from before per-module air only had the station's totals
+            if saved.atmosphere.is_none() {
+                module.atmosphere = Atmosphere {
+                    oxygen: NOMINAL_OXYGEN * snapshot.oxygen_level,
+                    temperature: snapshot.temperature,
+                    ..Atmosphere::nominal()
+                };
+            }
+            station.modules.push(module);
+        }
+        // Saved ids go back first, so the fresh ones can't take their slots
+        for (i, saved) in snapshot.modules.iter().enumerate() {
+            if let Some(id) = saved.id.filter(|&id| station.module_ids.reinsert(id, i)) {
+                station.modules[i].id = id;
+            }
+        }
+        for i in 0..module_count {
+            if station.modules[i].id == ModuleId::UNPLACED {
+                station.modules[i].id = station.module_ids.insert(i);
+            }
+        }
+        for i in 0..module_count {
+            let mut connected = std::mem::take(&mut station.modules[i].connected_modules);
+            connected.retain(|&id| station.module_index(id).is_some());
+            station.modules[i].connected_modules = connected;
+        }
+        match &snapshot.doors {
+            Some(doors) => {
+                for saved in doors {
+                    let [a, b] = saved.modules;
+                    let (Some(first), Some(second)) = (station.module_index(a), station.module_index(b)) else {
+                        continue;
+                    };
+                    let (from, to) = (station.modules[first].transform.position, station.modules[second].transform.position);
+                    station.doors.push(Door::new((a, b), saved.element, from, to));
+                }
+            }
+            None => {
+                for a in 0..module_count {
+                    for b in station.neighbours(a) {
+                        if a < b {
+                            station.add_door(a, b);
+                        }
+                    }
+                }
+            }
+        }
+        // Connectors aren't saved; older saves can have connections whose
+        // ports don't line up, which just go without
+        for a in 0..module_count {
+            for b in station.neighbours(a) {
+                if a < b {
+                    station.add_connector(a, b);
+                }
+            }
+        }
+        let exists = |station: &SpaceStation, id: ModuleId| station.module_index(id).is_some();
+        station.fires = snapshot.fires.iter().filter(|fire| exists(&station, fire.module)).map(Fire::from).collect();
+        match &snapshot.stores {
+            Some(stores) => {
+                let stores = stores.iter().filter(|store| exists(&station, store.module)).cloned().collect();
+                station.resources.restore(stores);
+            }
+            None => {
+                let stores: Vec<ModuleId> = station
+                    .modules
+                    .iter()
+                    .filter(|module| module.module_type == ModuleType::Storage)
+                    .map(|module| module.id)
+                    .collect();
+                for id in stores {
+                    station.resources.add_store(id);
+                }
+            }
+        }
+        station.storm_shelter = snapshot.storm_shelter.filter(|&id| exists(&station, id));
+        for spec in &snapshot.rings {
+            let modules = spec.modules.iter().copied().filter(|&id| exists(&station, id)).collect();
+            station.add_ring(&RingSpec { modules, ..spec.clone() });
+        }
+        station.update_structural_integrity();
+        station
+    }
+
+    // Human-readable dump of everything the simulation tracks, for debugging
+    pub fn dump_state(&self) -> String {
+        let mut out = String::new();
+        let clock = &self.clock;
+        let _ = writeln!(out, "{} ({})", clock.now().format_met(), clock.station_date());
+        let _ = writeln!(out, "seed: {}", self.seed);
+        let _ = writeln!(out, "structural integrity: {:.4}", self.structural_integrity);
+        let _ = writeln!(
+            out,
+            "power grid: output {:.3} kW, consumption {:.3} kW, stability {:.4}, in deficit {}, battery {:.3}",
+            self.power_grid.total_output,
+            self.power_grid.total_consumption,
+            self.power_grid.grid_stability,
+            self.power_grid.in_deficit,
+            self.power_grid.battery_charge,
+        );
+        for (i, module) in self.modules.iter().enumerate() {
+            let _ = writeln!(
+                out,
+                "module {} {:?} at {}: integrity {:.4}, sealed {}, power {:.3}/{:.3}, connected {:?}",
+                i,
+                module.module_type,
+                module.transform.position,
+                module.structural_integrity,
+                module.atmosphere_sealed,
+                module.power_draw(),
+                module.power_generation,
+                module.connected_modules,
+            );
+            let air = &module.atmosphere;
+            let _ = writeln!(
+                out,
+                "    air: O2 {:.4}, CO2 {:.4}, N2 {:.4} atm, {:.2} K, crew {}",
+                air.oxygen, air.co2, air.nitrogen, air.temperature, module.crew,
+            );
+            for unit in &module.life_support_units {
+                let _ = writeln!(
+                    out,
+                    "    {}: {:?}, throughput {:.2}, filter {:.4}",
+                    unit.kind.name(),
+                    unit.state,
+                    unit.throughput,
+                    unit.filter,
+                );
+            }
+            for (j, element) in module.interactive_elements.iter().enumerate() {
+                let _ = writeln!(
+                    out,
+                    "    element {} {:?} at {}: {:?}, draw {:.3}",
+                    j, element.element_type, element.position, element.state, element.power_draw,
+                );
+            }
+        }
+        out
+    }
+
+    pub fn clock(&self) -> &MissionClock {
+        &self.clock
+    }
+
+    pub fn log(&self) -> &EventLog {
+        &self.log
+    }
+
+    pub fn log_event(&mut self, severity: LogSeverity, message: impl Into<String>) {
+        self.log.push(self.clock.now(), severity, message);
+    }
+
+    // Every emitted event is also written to the event log
+    pub fn emit_event(&mut self, event: StationEvent) {
+        self.log_event(event.severity(), event.describe());
+        self.events.emit(event);
+    }
+
+    pub fn drain_events(&mut self) -> Vec<StationEvent> {
+        self.events.drain()
+    }
+
+    // Works out the load on every connection, then rates the station by the
+    // joint nearest its limit. A module held by nothing stands on its own hull.
+    fn update_structural_integrity(&mut self) {
+        let pairs = self.connections();
+        let centre = structure::centre_of_mass(&self.bodies(None));
+        let bodies = self.bodies(Some(centre));
+        let loads = structure::joint_loads(&bodies, &pairs);
+
+        let previous = std::mem::take(&mut self.joints);
+        self.joints = pairs
+            .into_iter()
+            .zip(loads)
+            .map(|((a, b), load)| {
+                let ids = (self.modules[a].id, self.modules[b].id);
+                let (overloaded_for, creaking) = previous
+                    .iter()
+                    .find(|joint| joint.ids == ids)
+                    .map_or((0.0, false), |joint| (joint.overloaded_for, joint.creaking));
+                let capacity = self.joint_capacity(a, b);
+                Joint { modules: (a, b), ids, load, capacity, overloaded_for, creaking }
+            })
+            .collect();
+
+        let joints = self.joints.iter().map(|joint| 1.0 - joint.ratio());
+        let loose = self.modules.iter().filter(|module| module.connected_modules.is_empty());
+        self.structural_integrity =
+            joints.chain(loose.map(|module| module.structural_integrity)).fold(1.0f32, f32::min).clamp(0.0, 1.0);
+    }
+
+    // Every module's mass and where it sits, loaded by the slew about
+    // `centre` and by any ring it spins with; without a centre, unloaded
+    fn bodies(&self, centre: Option<Vec3>) -> Vec<Body> {
+        (0..self.modules.len())
+            .map(|i| {
+                let position = self.modules[i].transform.position;
+                let acceleration = centre.map_or(0.0, |centre| {
+                    let spin = self.ring_of(i).map_or(0.0, |ring| ring.gravity(position).length());
+                    self.tunables.slew_acceleration * position.distance(centre) + spin
+                });
+                Body { mass: self.modules[i].module_type.mass(), position, acceleration }
+            })
+            .collect()
+    }
+
+    // What the joint between two modules takes: a quarter of its strength
+    // with both hulls gone, and less the further it's stretched from the
+    // best spacing
+    fn joint_capacity(&self, a: usize, b: usize) -> f32 {
+        let (first, second) = (&self.modules[a], &self.modules[b]);
+        let hull = first.structural_integrity.min(second.structural_integrity).clamp(0.0, 1.0);
+        let distance = first.transform.position.distance(second.transform.position);
+        let optimal = self.tunables.optimal_connection_distance;
+        let stretch = ((distance - optimal) / optimal).powi(2) * self.tunables.connection_stress_factor;
+        let reinforced = first.refit.hull.min(second.refit.hull);
+        self.tunables.connection_strength * reinforced * (0.25 + 0.75 * hull) / (1.0 + stretch)
+    }
+
+    pub fn joints(&self) -> &[Joint] {
+        &self.joints
+    }
+
+    // Creaks from connections nearing their limit, and tears those held
+    // over it too long
+    fn update_joints(&mut self, delta_time: f32) {
+        let (warning, limit) = (self.tunables.strain_warning, self.tunables.overload_seconds);
+        let mut creaks = Vec::new();
+        let mut failed = Vec::new();
+        for joint in &mut self.joints {
+            if joint.creaks(warning) {
+                creaks.push((joint.modules, joint.ratio()));
+            }
+            if joint.fails(limit, delta_time) {
+                failed.push(joint.modules);
+            }
+        }
+        for (modules, load) in creaks {
+            let position = self.joint_position(modules);
+            self.emit_event(StationEvent::StructuralStrain { modules, position, load });
+        }
+        for (a, b) in failed {
+            let position = self.joint_position((a, b));
+            self.emit_event(StationEvent::ConnectionFailed { modules: (a, b), position });
+            self.sever_connection(a, b);
+        }
+    }
+
+    fn joint_position(&self, (a, b): (usize, usize)) -> Vec3 {
+        (self.modules[a].transform.position + self.modules[b].transform.position) * 0.5
+    }
+
+    // Parts two modules: the connector and door go, and air goes out
+    // through the hatch if it was open. Either side can end up cut off from
+    // the command centre. False if they weren't connected.
+    pub fn sever_connection(&mut self, a: usize, b: usize) -> bool {
+        let (first, second) = (a.min(b), a.max(b));
+        if second >= self.modules.len() || !self.neighbours(first).contains(&second) {
+            return false;
+        }
+        let ids = (self.modules[first].id, self.modules[second].id);
+        let mut vented = Vec::new();
+        if let Some(door_idx) = self.door_between(first, second) {
+            let openness = self.door_openness(door_idx);
+            let door = self.take_door(door_idx);
+            if openness > 0.0 {
+                for module in [first, second] {
+                    self.modules[module].atmosphere.vent(openness * DETACH_VENT);
+                    vented.push((module, door.position));
+                }
+            }
+        }
+        self.connectors.retain(|connector| !connector.joins(ids.0, ids.1));
+        self.modules[first].connected_modules.retain(|&other| other != ids.1);
+        self.modules[second].connected_modules.retain(|&other| other != ids.0);
+        self.update_structural_integrity();
+
+        self.log_event(LogSeverity::Warning, format!("Modules {} and {} parted", first, second));
+        let stranded = self.stranded();
+        if !stranded.is_empty() {
+            self.log_event(
+                LogSeverity::Warning,
+                format!("Modules {:?} are cut off from the command centre", stranded),
+            );
+        }
+        for (module, position) in vented {
+            self.emit_event(StationEvent::Decompression { module, position });
+        }
+        true
+    }
+}
+
+// Serializable copy of the simulation state. Meshes and materials aren't
+// stored; they're rebuilt from each module's type when the snapshot is loaded.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct StationSnapshot {
+    pub mission_time_ms: u64,
+    // Loading restarts the generator from this seed, not from where it was
+    #[serde(default)]
+    pub seed: u64,
+    pub epoch: StationEpoch,
+    pub grid_stability: f32,
+    pub in_power_deficit: bool,
+    // Saves from before the battery load with it empty
+    #[serde(default)]
+    pub battery_charge: f32,
+    pub oxygen_level: f32,
+    pub temperature: f32,
+    pub pressure: f32,
+    pub modules: Vec<ModuleSnapshot>,
+    // Saves from before doors get an open one on every connection
+    #[serde(default)]
+    pub doors: Option<Vec<DoorSnapshot>>,
+    #[serde(default)]
+    pub fires: Vec<FireSnapshot>,
+    // Saves from before the stores load with a fresh starting stock
+    #[serde(default)]
+    pub stores: Option<Vec<Store>>,
+    #[serde(default)]
+    pub storm_shelter: Option<ModuleId>,
+    #[serde(default)]
+    pub rings: Vec<RingSpec>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ModuleSnapshot {
+    // Saves from before module ids get fresh ones
+    #[serde(default)]
+    pub id: Option<ModuleId>,
+    pub module_type: ModuleType,
+    pub position: [f32; 3],
+    pub rotation: [f32; 4],
+    pub scale: [f32; 3],
+    pub connected_modules: Vec<ModuleId>,
+    pub structural_integrity: f32,
+    // Saves from before component wear have everything like new
+    #[serde(default)]
+    pub wiring: Option<f32>,
+    #[serde(default)]
+    pub life_support: Option<f32>,
+    pub power_consumption: f32,
+    pub power_generation: f32,
+    pub atmosphere_sealed: bool,
+    #[serde(default)]
+    pub atmosphere: Option<Atmosphere>,
+    #[serde(default)]
+    pub crew: Option<u32>,
+    #[serde(default)]
+    pub paint: ModulePaint,
+    #[serde(default)]
+    pub grime: GrimeMask,
+    #[serde(default)]
+    pub panel: Option<[f32; 3]>,
+    #[serde(default)]
+    pub refit: ModuleRefit,
+    #[serde(default)]
+    pub light_level: Option<f32>,
+    // Saves from before life support hardware get the module type's fit
+    #[serde(default)]
+    pub life_support_units: Option<Vec<LifeSupportUnit>>,
+    pub elements: Vec<ElementSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ElementSnapshot {
+    pub element_type: InteractionType,
+    pub state: ElementState,
+    pub position: [f32; 3],
+    pub power_draw: f32,
+    #[serde(default)]
+    pub target: Option<ElementState>,
+    #[serde(default)]
+    pub cooldown: f32,
+    #[serde(default)]
+    pub hours: f32,
+    #[serde(default)]
+    pub grime: f32,
+    #[serde(default)]
+    pub clearance: Option<String>,
+}
+
+impl From<&StationModule> for ModuleSnapshot {
+    fn from(module: &StationModule) -> Self {
+        Self {
+            id: Some(module.id),
+            module_type: module.module_type,
+            position: module.transform.position.to_array(),
+            rotation: module.transform.rotation.to_array(),
+            scale: module.transform.scale.to_array(),
+            connected_modules: module.connected_modules.clone(),
+            structural_integrity: module.structural_integrity,
+            wiring: Some(module.wiring),
+            life_support: Some(module.life_support),
+            power_consumption: module.power_consumption,
+            power_generation: module.power_generation,
+            atmosphere_sealed: module.atmosphere_sealed,
+            atmosphere: Some(module.atmosphere),
+            crew: Some(module.crew),
+            paint: module.paint.clone(),
+            grime: module.grime.clone(),
+            panel: module.panel.map(|facing| facing.to_array()),
+            refit: module.refit,
+            light_level: Some(module.light_level),
+            life_support_units: Some(module.life_support_units.clone()),
+            elements: module.interactive_elements.iter().map(ElementSnapshot::from).collect(),
+        }
+    }
+}
+
+// The mesh and materials come from the module type and its refit, with the
+// paint over them
+impl From<&ModuleSnapshot> for StationModule {
+    fn from(saved: &ModuleSnapshot) -> Self {
+        let mut module = StationModule::new(saved.module_type, Vec3::from(saved.position));
+        module.transform.rotation = Quat::from_array(saved.rotation);
+        module.transform.scale = Vec3::from(saved.scale);
+        module.connected_modules = saved.connected_modules.clone();
+        module.structural_integrity = saved.structural_integrity;
+        module.wiring = saved.wiring.unwrap_or(1.0);
+        module.life_support = saved.life_support.unwrap_or(1.0);
+        module.power_consumption = saved.power_consumption;
+        module.power_generation = saved.power_generation;
+        module.atmosphere_sealed = saved.atmosphere_sealed;
+        module.atmosphere = saved.atmosphere.unwrap_or_else(Atmosphere::nominal);
+        module.crew = saved.crew.unwrap_or(module.crew);
+        module.paint = saved.paint.clone();
+        module.refit = saved.refit;
+        module.apply_refit();
+        module.grime = saved.grime.clone();
+        module.panel = saved.panel.map(Vec3::from).or(module.panel);
+        module.light_level = saved.light_level.unwrap_or(1.0);
+        if let Some(units) = &saved.life_support_units {
+            module.life_support_units = units.clone();
+        }
+        module.interactive_elements = saved.elements.iter().map(InteractiveElement::from).collect();
+        module
+    }
+}
+
+impl From<&InteractiveElement> for ElementSnapshot {
+    fn from(element: &InteractiveElement) -> Self {
+        Self {
+            element_type: element.element_type,
+            state: element.state,
+            position: element.position.to_array(),
+            power_draw: element.power_draw,
+            target: element.target,
+            cooldown: element.cooldown,
+            hours: element.hours,
+            grime: element.grime,
+            clearance: element.clearance.clone(),
+        }
+    }
+}
+
+impl From<&ElementSnapshot> for InteractiveElement {
+    fn from(saved: &ElementSnapshot) -> Self {
+        Self {
+            element_type: saved.element_type,
+            state: saved.state,
+            position: Vec3::from(saved.position),
+            power_draw: saved.power_draw,
+            target: saved.target,
+            cooldown: saved.cooldown,
+            hours: saved.hours,
+            grime: saved.grime,
+            clearance: saved.clearance.clone(),
+        }
+    }
+}
+
+// The live types serialize as their snapshots, so a station, a module or an
+// element can go straight into anything serde writes
+impl Serialize for SpaceStation {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        self.snapshot().serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SpaceStation {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        StationSnapshot::deserialize(deserializer).map(|snapshot| SpaceStation::from_snapshot(&snapshot))
+    }
+}
+
+impl Serialize for StationModule {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        ModuleSnapshot::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for StationModule {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        ModuleSnapshot::deserialize(deserializer).map(|saved| StationModule::from(&saved))
+    }
+}
+
+impl Serialize for InteractiveElement {
+    fn serialize<S: Serializer>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error> {
+        ElementSnapshot::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for InteractiveElement {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> std::result::Result<Self, D::Error> {
+        ElementSnapshot::deserialize(deserializer).map(|saved| InteractiveElement::from(&saved))
+    }
+}
+
+// What the station can store: its own battery and every battery module's
+#[derive(Debug, Clone, Copy)]
+struct Storage {
+    // kW·s
+    capacity: f32,
+    // kW
+    charge_rate: f32,
+    discharge_rate: f32,
+}
+
+#[derive(Debug)]
+struct PowerGrid {
+    total_output: f32,
+    // What's actually being supplied, after any load shedding
+    total_consumption: f32,
+    // Fraction of demand that generation and the battery can meet
+    grid_stability: f32,
+    in_deficit: bool,
+    // Demand the grid couldn't meet this update, in kW
+    shortfall: f32,
+    // kW·s stored
+    battery_charge: f32,
+}
+
+impl PowerGrid {
+    fn new() -> Self {
+        Self {
+            total_output: 0.0,
+            total_consumption: 0.0,
+            grid_stability: 1.0,
+            in_deficit: false,
+            shortfall: 0.0,
+            battery_charge: PowerTunables::default().battery_capacity,
+        }
+    }
+
+    // Balances generation against demand: spare output charges the battery,
+    // a shortfall drains it, and whatever the battery can't cover is met by
+    // cutting modules off in load_priority order. Returns the modules that
+    // lost power this update.
+    fn update(&mut self, modules: &mut [StationModule], storage: Storage, delta_time: f32) -> Vec<usize> {
+        let delta_time = delta_time.max(f32::EPSILON);
+        // A battery module taken away takes its share of the charge with it
+        self.battery_charge = self.battery_charge.min(storage.capacity);
+        self.total_output = modules.iter().map(|m| m.power_output()).sum();
+        let demand: f32 = modules.iter().map(|m| m.power_draw()).sum();
+
+        let battery_output = storage.discharge_rate.min(self.battery_charge / delta_time);
+        let supply = self.total_output + battery_output;
+        self.shortfall = (demand - supply).max(0.0);
+        self.grid_stability = if demand > 0.0 { (supply / demand).min(1.0) } else { 1.0 };
+
+        // Lowest priority first; among equals the most recently added goes first
+        let mut order: Vec<usize> = (0..modules.len()).collect();
+        order.sort_by_key(|&i| (modules[i].module_type.load_priority(), std::cmp::Reverse(i)));
+        let mut load = demand;
+        let mut shed = Vec::new();
+        for i in order {
+            let module = &mut modules[i];
+            let powered = load <= supply;
+            if !powered {
+                load -= module.power_draw();
+                if module.powered {
+                    shed.push(i);
+                }
+            }
+            module.powered = powered;
+        }
+        self.total_consumption = load.max(0.0);
+
+        // The battery makes up what generation can't, or soaks up what's spare
+        let net = self.total_output - self.total_consumption;
+        if net < 0.0 {
+            self.battery_charge = (self.battery_charge + net * delta_time).max(0.0);
+        } else {
+            let room = (storage.capacity - self.battery_charge).max(0.0);
+            self.battery_charge += (net.min(storage.charge_rate) * delta_time).min(room);
+        }
+        shed
+    }
+}
+
+// What an element landing on `state` tells the rest of the game
+fn element_event(module: usize, element: usize, position: Vec3, state: ElementState) -> StationEvent {
+    match state {
+        ElementState::Malfunction => StationEvent::ElementFailed { module, element, position },
+        _ => StationEvent::ElementActivated {
+            module,
+            element,
+            position,
+            active: state == ElementState::Active,
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::life_support::{LifeSupportKind, UnitState};
+    use crate::thermal::Comfort;
+
+    const MODULE_TYPES: [ModuleType; 11] = [
+        ModuleType::Corridor,
+        ModuleType::Hub,
+        ModuleType::Airlock,
+        ModuleType::LivingQuarters,
+        ModuleType::CommandCenter,
+        ModuleType::Laboratory,
+        ModuleType::Storage,
+        ModuleType::PowerPlant,
+        ModuleType::EscapePod,
+        ModuleType::SolarArray,
+        ModuleType::Battery,
+    ];
+
+    // Exhaustive, so a new module type doesn't compile until it's listed here
+    fn expected_elements(module_type: ModuleType) -> &'static [InteractionType] {
+        match module_type {
+            ModuleType::Corridor => &[InteractionType::LightControl],
+            ModuleType::Hub => &[InteractionType::LightControl, InteractionType::EnvironmentControl],
+            ModuleType::Airlock => &[InteractionType::AirlockControl, InteractionType::PressureControl],
+            ModuleType::LivingQuarters => &[
+                InteractionType::LightControl,
+                InteractionType::EnvironmentControl,
+                InteractionType::FireSuppression,
+                InteractionType::Radiator,
+            ],
+            ModuleType::CommandCenter => &[
+                InteractionType::MainComputer,
+                InteractionType::Communications,
+                InteractionType::StationControl,
+                InteractionType::FireSuppression,
+                InteractionType::Radiator,
+            ],
+            ModuleType::Laboratory => &[
+                InteractionType::ResearchStation,
+                InteractionType::LabEquipment,
+                InteractionType::FireSuppression,
+                InteractionType::Radiator,
+            ],
+            ModuleType::Storage => &[InteractionType::StorageAccess],
+            ModuleType::PowerPlant => &[
+                InteractionType::PowerControl,
+                InteractionType::EmergencyShutoff,
+                InteractionType::FireSuppression,
+                InteractionType::Radiator,
+            ],
+            ModuleType::EscapePod => &[InteractionType::LightControl],
+            ModuleType::SolarArray => &[InteractionType::SunTracker, InteractionType::LightControl],
+            ModuleType::Battery => &[
+                InteractionType::PowerControl,
+                InteractionType::FireSuppression,
+                InteractionType::Radiator,
+            ],
+        }
+    }
+
+    fn station_with(module_type: ModuleType) -> SpaceStation {
+        let mut station = SpaceStation::new();
+        station.add_module(module_type, Vec3::ZERO);
+        station
+    }
+
+    #[test]
+    fn every_module_type_has_its_default_elements() {
+        for module_type in MODULE_TYPES {
+            let module = StationModule::new(module_type, Vec3::ZERO);
+            let types: Vec<InteractionType> = module.interactive_elements.iter().map(|e| e.element_type).collect();
+            assert_eq!(types, expected_elements(module_type), "{:?}", module_type);
+        }
+    }
+
+    #[test]
+    fn default_elements_start_at_rest_and_usable() {
+        for module_type in MODULE_TYPES {
+            let module = StationModule::new(module_type, Vec3::ZERO);
+            for element in &module.interactive_elements {
+                // Radiators are the one thing left running
+                let resting = if element.element_type == InteractionType::Radiator {
+                    ElementState::Active
+                } else {
+                    ElementState::Inactive
+                };
+                assert_eq!(element.state, resting, "{:?} in {:?}", element.element_type, module_type);
+                assert_ne!(element.element_type.activation(), Activation::Passive, "{:?}", element.element_type);
+            }
+        }
+    }
+
+    const STEP: f32 = 1.0 / 60.0;
+
+    // Runs an element's transition and cooldown out
+    fn settle(station: &mut SpaceStation, element_type: InteractionType) {
+        station.update(element_type.transition_seconds());
+        station.update(element_type.cooldown_seconds());
+    }
+
+    #[test]
+    fn activating_default_elements_follows_their_activation() {
+        for module_type in MODULE_TYPES {
+            let mut station = station_with(module_type);
+            for (i, &element_type) in expected_elements(module_type).iter().enumerate() {
+                station.set_element_state(0, i, ElementState::Inactive);
+                assert_eq!(station.activate_element(0, i), Some(element_type));
+                if element_type.transition_seconds() > 0.0 {
+                    assert_eq!(station.element_state(0, i), Some(ElementState::Transitioning(0.0)));
+                }
+                settle(&mut station, element_type);
+                let state = station.element_state(0, i).unwrap();
+                match element_type.activation() {
+                    Activation::Momentary => assert_eq!(state, ElementState::Inactive),
+                    _ => assert_eq!(state, ElementState::Active, "{:?}", element_type),
+                }
+                // A second use switches toggles back off
+                if element_type.activation() == Activation::Toggle {
+                    station.activate_element(0, i);
+                    settle(&mut station, element_type);
+                    assert_eq!(station.element_state(0, i), Some(ElementState::Inactive));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn passive_elements_do_nothing() {
+        let mut station = station_with(ModuleType::Corridor);
+        station.modules[0].add_interactive_elements(&[(InteractionType::Window, Vec3::ZERO)]);
+        assert_eq!(station.activate_element(0, 1), None);
+        assert_eq!(station.element_state(0, 1), Some(ElementState::Inactive));
+    }
+
+    #[test]
+    fn buttons_spring_back() {
+        let mut station = station_with(ModuleType::Corridor);
+        station.modules[0].add_interactive_elements(&[(InteractionType::Button, Vec3::ZERO)]);
+        station.activate_element(0, 1);
+        station.update(MOMENTARY_SECONDS * 0.5);
+        assert!(matches!(station.element_state(0, 1), Some(ElementState::Transitioning(_))));
+        station.update(MOMENTARY_SECONDS);
+        assert_eq!(station.element_state(0, 1), Some(ElementState::Inactive));
+    }
+
+    #[test]
+    fn emergency_shutoff_trips_the_generator() {
+        let mut station = station_with(ModuleType::PowerPlant);
+        assert!(station.telemetry().power_generation > 0.0);
+        station.activate_element(0, 1);
+        assert_eq!(station.telemetry().power_generation, 0.0);
+        station.update(InteractionType::EmergencyShutoff.cooldown_seconds());
+        station.activate_element(0, 1);
+        assert!(station.telemetry().power_generation > 0.0);
+    }
+
+    #[test]
+    fn battery_covers_a_short_deficit() {
+        let mut station = SpaceStation::create_default_layout();
+        let demand: f32 = station.modules.iter().map(|m| m.power_draw()).sum();
+        let plant = station.modules.iter().position(|m| m.module_type == ModuleType::PowerPlant).unwrap();
+        station.modules[plant].power_generation = demand - 20.0;
+        station.update(1.0);
+        assert!(station.modules.iter().all(|m| m.powered));
+        assert!(station.telemetry().battery_level < 1.0);
+        assert!(station.drain_events().iter().all(|e| !matches!(e, StationEvent::PowerDeficit { .. })));
+    }
+
+    #[test]
+    fn deficits_count_switched_on_elements_and_are_reported_once_when_they_start() {
+        let mut station = SpaceStation::create_default_layout();
+        let (module, element) = (0..station.modules.len())
+            .find_map(|i| {
+                let elements = &station.modules[i].interactive_elements;
+                let idle = elements.iter().position(|e| e.state == ElementState::Inactive && e.power_draw > 0.0)?;
+                Some((i, idle))
+            })
+            .unwrap();
+        let idle = station.modules[module].power_draw();
+        station.modules[module].interactive_elements[element].state = ElementState::Active;
+        let draw = station.modules[module].interactive_elements[element].power_draw;
+        assert!((station.modules[module].power_draw() - idle - draw).abs() < 1e-4);
+        // Drawn for as long as it's on, not piling up from one update to the next
+        station.update(STEP);
+        assert!((station.modules[module].power_draw() - idle - draw).abs() < 1e-4);
+
+        let deficits = |station: &mut SpaceStation| {
+            let events = station.drain_events();
+            events.iter().filter(|e| matches!(e, StationEvent::PowerDeficit { .. })).count()
+        };
+        let demand: f32 = station.modules.iter().map(|m| m.power_draw()).sum();
+        let plant = station.modules.iter().position(|m| m.module_type == ModuleType::PowerPlant).unwrap();
+        station.drain_events();
+        station.power_grid.battery_charge = 0.0;
+        station.modules[plant].power_generation = demand - 1.0;
+        for _ in 0..3 {
+            station.update(STEP);
+        }
+        assert_eq!(deficits(&mut station), 1);
+        station.modules[plant].power_generation = demand * 2.0;
+        station.update(STEP);
+        assert_eq!(deficits(&mut station), 0);
+        station.power_grid.battery_charge = 0.0;
+        station.modules[plant].power_generation = demand - 1.0;
+        station.update(STEP);
+        assert_eq!(deficits(&mut station), 1);
+    }
+
+    #[test]
+    fn solar_arrays_follow_the_sun_and_batteries_add_storage() {
+        let mut fixed = station_with(ModuleType::SolarArray);
+        let mut tracking = station_with(ModuleType::SolarArray);
+        tracking.activate_element(0, 0);
+        settle(&mut tracking, InteractionType::SunTracker);
+        settle(&mut fixed, InteractionType::SunTracker);
+        for _ in 0..120 {
+            fixed.update(1.0);
+            tracking.update(1.0);
+        }
+        // Still face up with the sun low on the horizon, the fixed one catches
+        // hardly any of it
+        assert!(fixed.modules[0].power_generation < 0.2 * fixed.power_tunables.solar_output);
+        assert!(tracking.modules[0].power_generation > 0.5 * tracking.power_tunables.solar_output);
+        // Nothing in the planet's shadow, however it's turned
+        tracking.orbit.phase_offset = 0.75;
+        tracking.update(STEP);
+        assert_eq!(tracking.sunlight(), 0.0);
+        assert_eq!(tracking.modules[0].power_generation, 0.0);
+
+        // Where it had turned to is saved
+        let restored = SpaceStation::from_snapshot(&tracking.snapshot());
+        assert_eq!(restored.modules[0].panel, tracking.modules[0].panel);
+
+        // Another bank of cells, so the same charge fills less of it
+        let mut station = SpaceStation::create_default_layout();
+        let capacity = station.storage().capacity;
+        station.add_module(ModuleType::Battery, Vec3::new(0.0, 0.0, 60.0));
+        assert_eq!(station.storage().capacity, capacity + station.power_tunables.battery_module_capacity);
+        assert!(station.telemetry().battery_level < 1.0);
+    }
+
+    #[test]
+    fn shortfalls_shed_the_lowest_priority_modules_first() {
+        let mut station = SpaceStation::create_default_layout();
+        station.power_grid.battery_charge = 0.0;
+        let demand: f32 = station.modules.iter().map(|m| m.power_draw()).sum();
+        let storage = station.modules.iter().position(|m| m.module_type == ModuleType::Storage).unwrap();
+        // Just short of carrying everything
+        let plant = station.modules.iter().position(|m| m.module_type == ModuleType::PowerPlant).unwrap();
+        station.modules[plant].power_generation = demand - 1.0;
+        station.update(STEP);
+        let unpowered: Vec<usize> = (0..station.modules.len()).filter(|&i| !station.modules[i].powered).collect();
+        assert_eq!(unpowered, vec![storage]);
+        assert!(station
+            .drain_events()
+            .iter()
+            .any(|e| matches!(e, StationEvent::Brownout { module, .. } if *module == storage)));
+    }
+
+    #[test]
+    fn unpowered_modules_refuse_powered_elements() {
+        let mut station = station_with(ModuleType::Corridor);
+        station.modules[0].add_interactive_elements(&[
+            (InteractionType::Door, Vec3::ZERO),
+            (InteractionType::EmergencyShutoff, Vec3::ZERO),
+        ]);
+        station.modules[0].powered = false;
+        assert_eq!(station.activate_element(0, 0), None);
+        assert_eq!(station.activate_element(0, 1), None);
+        assert_eq!(station.activate_element(0, 2), Some(InteractionType::EmergencyShutoff));
+    }
+
+    #[test]
+    fn breaches_vent_and_draw_air_from_neighbours() {
+        let mut station = SpaceStation::new();
+        let lab = station.add_module(ModuleType::Laboratory, Vec3::ZERO);
+        let airlock = station.add_module(ModuleType::Airlock, Vec3::new(0.0, 0.0, -8.0));
+        station.connect_modules(lab, airlock);
+        station.modules[airlock].atmosphere_sealed = false;
+        for _ in 0..600 {
+            station.update(STEP);
+        }
+        let (lab_air, airlock_air) = (station.modules[lab].atmosphere, station.modules[airlock].atmosphere);
+        assert!(airlock_air.pressure() < 0.5, "{:?}", airlock_air);
+        assert!(lab_air.pressure() < 1.0 && lab_air.pressure() > airlock_air.pressure());
+    }
+
+    #[test]
+    fn crew_breathe_and_scrubbers_clean_up() {
+        let mut station = station_with(ModuleType::Laboratory);
+        station.update(60.0);
+        assert!(station.modules[0].atmosphere.co2 > 0.0);
+
+        let mut station = station_with(ModuleType::LivingQuarters);
+        for _ in 0..600 {
+            station.update(STEP);
+        }
+        let air = station.modules[0].atmosphere;
+        assert!(air.co2 < 1e-4 && (air.oxygen_level() - 1.0).abs() < 1e-3, "{:?}", air);
+    }
+
+    #[test]
+    fn transitions_land_in_later_updates_and_report() {
+        let mut station = station_with(ModuleType::Corridor);
+        station.modules[0].add_interactive_elements(&[(InteractionType::Door, Vec3::ZERO)]);
+        station.activate_element(0, 1);
+        station.update(0.4);
+        assert_eq!(station.element_state(0, 1), Some(ElementState::Transitioning(0.4)));
+        assert!(station.drain_events().iter().all(|e| !matches!(e, StationEvent::ElementActivated { .. })));
+        station.update(0.6);
+        assert_eq!(station.element_state(0, 1), Some(ElementState::Active));
+        assert!(station
+            .drain_events()
+            .iter()
+            .any(|e| matches!(e, StationEvent::ElementActivated { module: 0, element: 1, active: true, .. })));
+    }
+
+    #[test]
+    fn cooldowns_refuse_requests_until_they_run_out() {
+        let mut station = station_with(ModuleType::PowerPlant);
+        station.activate_element(0, 1);
+        assert_eq!(station.activate_element(0, 1), None);
+        station.update(InteractionType::EmergencyShutoff.cooldown_seconds() * 0.5);
+        assert_eq!(station.activate_element(0, 1), None);
+        station.update(InteractionType::EmergencyShutoff.cooldown_seconds());
+        assert_eq!(station.activate_element(0, 1), Some(InteractionType::EmergencyShutoff));
+    }
+
+    #[test]
+    fn wrecked_modules_fail_switches() {
+        let mut station = station_with(ModuleType::Corridor);
+        station.modules[0].structural_integrity = 0.0;
+        let mut failures = 0;
+        for _ in 0..40 {
+            station.set_element_state(0, 0, ElementState::Inactive);
+            station.activate_element(0, 0);
+            if station.element_state(0, 0) == Some(ElementState::Malfunction) {
+                failures += 1;
+            }
+        }
+        assert!(failures > 0 && failures < 40, "{} failures", failures);
+        assert!(station.drain_events().iter().any(|e| matches!(e, StationEvent::ElementFailed { module: 0, .. })));
+    }
+
+    #[test]
+    fn locked_doors_refuse_and_report() {
+        let mut station = station_with(ModuleType::Corridor);
+        station.modules[0].add_interactive_elements(&[(InteractionType::Door, Vec3::ZERO)]);
+        station.set_element_state(0, 1, ElementState::Locked);
+        assert_eq!(station.activate_element(0, 1), None);
+        assert!(matches!(station.drain_events()[..], [StationEvent::DoorLocked { module: 0, .. }]));
+    }
+
+    #[test]
+    fn connections_get_doors_that_slide_shut() {
+        let mut station = SpaceStation::create_default_layout();
+        assert_eq!(station.doors().len(), 11);
+        let door = station.door_between(3, 0).unwrap();
+        let (module, element) = (station.door_modules(door).unwrap().0, station.doors()[door].element);
+        assert_eq!(station.door_openness(door), 1.0);
+        assert_eq!(station.element_position(module, element), Some(Vec3::new(0.0, 0.0, 4.0)));
+
+        assert_eq!(station.activate_element(module, element), Some(InteractionType::Door));
+        station.update(InteractionType::Door.transition_seconds() * 0.5);
+        assert!((station.door_openness(door) - 0.5).abs() < 1e-4);
+        settle(&mut station, InteractionType::Door);
+        assert_eq!(station.door_openness(door), 0.0);
+
+        assert!(station.set_door_locked(door, true));
+        assert_eq!(station.activate_element(module, element), None);
+        assert!(matches!(station.drain_events().last(), Some(StationEvent::DoorLocked { .. })));
+
+        let restored = SpaceStation::from_snapshot(&station.snapshot());
+        assert_eq!(restored.doors().len(), 11);
+        assert_eq!(restored.element_state(module, element), Some(ElementState::Locked));
+    }
+
+    #[test]
+    fn doors_keep_their_modules_when_an_earlier_one_is_removed() {
+        let mut station = SpaceStation::create_default_layout();
+        let (storage, pod) = (station.modules[7].id, station.modules[11].id);
+        let door = station.door_between(7, 11).unwrap();
+        assert!(station.set_door_locked(door, true));
+
+        // Taking the lab out moves both down one; the door goes with them
+        station.remove_module(5).unwrap();
+        let door = station.door_between(6, 10).unwrap();
+        assert_eq!(station.doors()[door].modules, (storage, pod));
+        assert_eq!(station.door_modules(door), Some((6, 10)));
+        assert_eq!(station.element_state(6, station.doors()[door].element), Some(ElementState::Locked));
+
+        let restored = SpaceStation::from_snapshot(&station.snapshot());
+        assert_eq!(restored.door_modules(restored.door_between(6, 10).unwrap()), Some((6, 10)));
+    }
+
+    #[test]
+    fn shut_doors_hold_air_against_a_breach() {
+        let mut station = SpaceStation::create_default_layout();
+        let door = station.door_between(0, 3).unwrap();
+        station.set_door_locked(door, true);
+        station.modules[3].atmosphere_sealed = false;
+        for _ in 0..600 {
+            station.update(STEP);
+        }
+        assert!(station.modules[3].atmosphere.pressure() < 0.5);
+        assert!(station.modules[0].atmosphere.pressure() > 0.95);
+        assert!(station.export_graphviz(GraphKind::Atmosphere).contains("m0 -- m3 [style=dotted, label=\"locked\"]"));
+    }
+
+    #[test]
+    fn worn_components_cost_power_air_and_pressure() {
+        let mut station = SpaceStation::create_default_layout();
+        let draw = station.modules()[0].power_draw();
+        let scrubbers = station.modules()[0].scrubber_output();
+        assert!(station.damage_module(0, 0.4));
+        let module = &station.modules()[0];
+        assert!((module.wiring - 0.8).abs() < 1e-5 && (module.life_support - 0.8).abs() < 1e-5);
+        assert!(module.power_draw() > draw);
+        assert!(module.scrubber_output() < scrubbers);
+
+        // Still sealed, but the worn hull lets air out
+        let mut leaky = SpaceStation::create_default_layout();
+        leaky.modules[3].structural_integrity = 0.5;
+        leaky.modules[3].atmosphere.oxygen = 0.1;
+        let mut tight = SpaceStation::create_default_layout();
+        tight.modules[3].atmosphere.oxygen = 0.1;
+        leaky.update_atmosphere(1.0);
+        tight.update_atmosphere(1.0);
+        assert!(leaky.modules[3].atmosphere_sealed);
+        assert!(leaky.modules[3].atmosphere.nitrogen < tight.modules[3].atmosphere.nitrogen);
+
+        let snapshot = station.snapshot();
+        let restored = SpaceStation::from_snapshot(&snapshot);
+        assert_eq!(restored.modules()[0].wiring, station.modules()[0].wiring);
+    }
+
+    #[test]
+    fn fires_burn_air_and_yield_to_suppression() {
+        let mut station = SpaceStation::create_default_layout();
+        for door in 0..station.doors().len() {
+            station.set_door_locked(door, true);
+        }
+        assert!(station.ignite(0));
+        assert!(!station.ignite(0));
+        for _ in 0..600 {
+            station.update(STEP);
+        }
+        let module = &station.modules[0];
+        assert!(station.fires()[0].intensity > 0.1);
+        assert!(module.atmosphere.co2 > 0.0 && module.atmosphere.temperature > atmosphere::NOMINAL_TEMPERATURE);
+        assert!(module.structural_integrity < 1.0 && module.wiring < 1.0);
+        // Shut doors keep it to the one module
+        assert_eq!(station.fires().len(), 1);
+
+        let suppression = module
+            .interactive_elements
+            .iter()
+            .position(|element| element.element_type == InteractionType::FireSuppression)
+            .unwrap();
+        station.set_element_state(0, suppression, ElementState::Active);
+        station.drain_events();
+        for _ in 0..600 {
+            station.update(STEP);
+        }
+        assert!(station.fires().is_empty());
+        assert!(station.drain_events().iter().any(|event| matches!(event, StationEvent::FireOut { module: 0, .. })));
+    }
+
+    #[test]
+    fn faults_light_fires_that_spread_through_open_doors_and_die_in_vacuum() {
+        let mut tunables = Tunables::default();
+        tunables.station.fire_ignition_chance = 1000.0;
+        tunables.station.fire_spread_chance = 1000.0;
+        let mut station = SpaceStation::create_default_layout();
+        station.set_tunables(&tunables);
+        let locked = station.door_between(0, 3).unwrap();
+        station.set_door_locked(locked, true);
+        station.set_element_state(0, 0, ElementState::Malfunction);
+        let burning = |station: &SpaceStation, module: usize| station.fires().iter().any(|fire| fire.module == station.modules[module].id);
+        station.update(STEP);
+        assert!(burning(&station, 0));
+        assert!(station.drain_events().iter().any(|event| matches!(event, StationEvent::FireStarted { module: 0, .. })));
+        for _ in 0..60 {
+            station.update(STEP);
+        }
+        for neighbour in station.neighbours(0) {
+            assert_eq!(burning(&station, neighbour), neighbour != 3, "module {}", neighbour);
+        }
+
+        // Venting starves it
+        let restored = SpaceStation::from_snapshot(&station.snapshot());
+        assert_eq!(restored.fires().len(), station.fires().len());
+        let mut station = SpaceStation::create_default_layout();
+        for door in 0..station.doors().len() {
+            station.set_door_locked(door, true);
+        }
+        station.ignite(3);
+        station.modules[3].atmosphere_sealed = false;
+        for _ in 0..1200 {
+            station.update(STEP);
+        }
+        assert!(!burning(&station, 3));
+    }
+
+    #[test]
+    fn radiators_hold_the_cabins_comfortable_and_without_them_equipment_cooks() {
+        let mut station = SpaceStation::create_default_layout();
+        for _ in 0..480 {
+            station.update(0.25);
+        }
+        for module in &station.modules {
+            assert_eq!(Comfort::of(module.atmosphere.temperature), Comfort::Comfortable, "{:?}", module.module_type);
+        }
+
+        let mut tunables = Tunables::default();
+        tunables.station.power_heat = 20.0;
+        tunables.station.overheat_failure_chance = 1.0;
+        station.set_tunables(&tunables);
+        for module in 0..station.modules.len() {
+            for element in 0..station.modules[module].interactive_elements.len() {
+                if station.modules[module].interactive_elements[element].element_type == InteractionType::Radiator {
+                    station.set_element_state(module, element, ElementState::Inactive);
+                }
+            }
+        }
+        station.drain_events();
+        for _ in 0..240 {
+            station.update(0.25);
+        }
+        let events = station.drain_events();
+        assert!(events.iter().any(|event| matches!(event, StationEvent::Overheating { module: 0, .. })));
+        assert!(events.iter().any(|event| matches!(event, StationEvent::ElementFailed { .. })));
+    }
+
+    #[test]
+    fn heat_soaks_through_connections_and_out_through_the_hull() {
+        let mut station = SpaceStation::create_default_layout();
+        station.modules[5].atmosphere.temperature = 400.0;
+        // Unpowered and empty, so nothing warms the lab back up
+        station.modules[5].powered = false;
+        station.modules[5].crew = 0;
+        let tunables = StationTunables::default();
+        for _ in 0..600 {
+            let connections = station.connections();
+            thermal::update_heat(&mut station.modules, &connections, &tunables, 0.1);
+        }
+        let temperature = |module: usize| station.modules[module].atmosphere.temperature;
+        // The corridor next to it has taken heat the far side hasn't
+        assert!(temperature(1) > temperature(3) + 5.0);
+        assert!(temperature(5) < 400.0 && temperature(5) > temperature(1));
+    }
+
+    #[test]
+    fn solar_flares_build_add_sensor_noise_and_pass() {
+        let mut station = SpaceStation::create_default_layout();
+        station.drain_events();
+        station.begin_flare(1.0, 10.0);
+        assert_eq!(station.interference().level(), 0.0);
+        station.update(5.0);
+        assert!((station.interference().level() - 1.0).abs() < 1e-4);
+        let truth = station.telemetry();
+        let reported = station.sensor_telemetry();
+        assert_ne!(reported.temperature, truth.temperature);
+        assert!((reported.temperature - truth.temperature).abs() <= 8.0);
+        assert_eq!(reported.module_count, truth.module_count);
+
+        station.update(6.0);
+        let events = station.drain_events();
+        assert!(matches!(events[0], StationEvent::SolarFlare { .. }));
+        assert!(events.contains(&StationEvent::SolarFlareEnded));
+        assert_eq!(station.sensor_telemetry().temperature, station.telemetry().temperature);
+    }
+
+    #[test]
+    fn flares_swallow_remote_commands() {
+        let mut station = SpaceStation::create_default_layout();
+        let mut tunables = Tunables::default();
+        tunables.station.flare_command_loss = 1.0;
+        station.set_tunables(&tunables);
+        let comms = station.modules[0]
+            .interactive_elements
+            .iter()
+            .position(|element| element.element_type == InteractionType::Communications)
+            .unwrap();
+        station.begin_flare(1.0, 10.0);
+        station.update(5.0);
+        let logged = station.log().len();
+        assert_eq!(station.remote_activate(0, comms), None);
+        assert_eq!(station.log().len(), logged + 1);
+
+        station.update(6.0);
+        assert_eq!(station.remote_activate(0, comms), Some(InteractionType::Communications));
+    }
+
+    #[test]
+    fn overloaded_connections_creak_then_tear_the_station_apart() {
+        let mut station = SpaceStation::create_default_layout();
+        station.update(0.1);
+        // At rest the slew barely loads anything, and the hub carries most
+        let hub = station.joints().iter().find(|joint| joint.modules == (0, 1)).unwrap().ratio();
+        let lab = station.joints().iter().find(|joint| joint.modules == (1, 5)).unwrap().ratio();
+        assert!(hub > lab && station.structural_integrity() > 0.95);
+        assert!(station.drain_events().iter().all(|event| event.kind() != "structural_strain"));
+
+        // The lab spun hard pulls on everything between it and the middle
+        let centre = station.modules[5].transform.position + Vec3::Y * 50.0;
+        let spec = RingSpec {
+            name: "Lab ring".to_string(),
+            centre: centre.to_array(),
+            axis: [1.0, 0.0, 0.0],
+            rpm: 8.0,
+            modules: vec![station.modules[5].id],
+            angle: 0.0,
+        };
+        station.add_ring(&spec);
+        station.update(0.1);
+        assert!(station.structural_integrity() < 0.1);
+        let kinds = |events: Vec<StationEvent>| events.iter().map(|event| event.kind()).collect::<Vec<_>>();
+        assert_eq!(kinds(station.drain_events()), ["structural_strain"]);
+        for _ in 0..40 {
+            station.update(0.1);
+        }
+        let events: Vec<StationEvent> =
+            station.drain_events().into_iter().filter(|event| event.kind() != "resource_shortage").collect();
+        assert!(matches!(events[0], StationEvent::ConnectionFailed { modules: (0, 1), .. }));
+        // The hatch was open, so both sides lose air
+        assert_eq!(kinds(events[1..].to_vec()), ["decompression", "decompression"]);
+        assert_eq!(station.stranded(), vec![1, 5, 9]);
+        assert_eq!((station.doors().len(), station.connectors().len(), station.joints().len()), (10, 10, 10));
+        // Cut loose, the lab's piece holds from the lab itself
+        assert!(station.structural_integrity() > 0.95);
+        assert!(!station.sever_connection(0, 1));
+        assert!(station.sever_connection(9, 5));
+        assert_eq!(station.modules[5].interactive_elements.iter().filter(|e| e.element_type == InteractionType::Door).count(), 0);
+    }
+
+    #[test]
+    fn connections_need_free_ports_that_face_each_other_and_get_tubes() {
+        let station = SpaceStation::create_default_layout();
+        assert_eq!(station.connectors().len(), station.doors().len());
+        procgen::validate(&station).unwrap();
+        // The command centre's north hatch meets the corridor's wall to wall,
+        // the lab sits back from its corridor and gets a tube
+        let north = station.connectors().iter().find(|connector| connector.joins(station.modules[0].id, station.modules[1].id)).unwrap();
+        assert_eq!(north.ports, (0, 2));
+        let lab = station.connectors().iter().find(|connector| connector.joins(station.modules[1].id, station.modules[5].id)).unwrap();
+        assert!((lab.length - 1.5).abs() < 1e-4, "{}", lab.length);
+
+        let mut station = SpaceStation::new();
+        let hub = station.add_module(ModuleType::Hub, Vec3::ZERO);
+        // Close enough, but corner to corner, or off to one side of the hatch
+        let diagonal = station.add_module(ModuleType::Corridor, Vec3::new(6.0, 0.0, 6.0));
+        let offset = station.add_module(ModuleType::Corridor, Vec3::new(7.0, 0.0, 1.0));
+        assert!(!station.connect_modules(hub, diagonal));
+        assert!(!station.connect_modules(hub, offset));
+        assert!(station.modules[hub].connected_modules.is_empty());
+        // Turned side on, the storage module's side hatch faces the hub
+        let north = station.add_module(ModuleType::Storage, Vec3::new(0.0, 0.0, -9.0));
+        station.modules[north].transform.rotation = Quat::from_rotation_y(std::f32::consts::FRAC_PI_2);
+        assert!(station.connect_modules(hub, north));
+        assert_eq!(station.free_ports(hub), vec![1, 2, 3]);
+        assert!(matches!(station.connectors().last().unwrap().ports.1, 1 | 3));
+        // Skewed 45 degrees, none of them do
+        let south = station.add_module(ModuleType::Corridor, Vec3::new(0.0, 0.0, 7.0));
+        station.modules[south].transform.rotation = Quat::from_rotation_y(std::f32::consts::FRAC_PI_4);
+        assert!(!station.connect_modules(hub, south));
+        station.modules[south].transform.rotation = Quat::IDENTITY;
+        assert!(station.connect_modules(hub, south));
+
+        // An airlock only takes the one
+        let airlock = station.add_module(ModuleType::Airlock, Vec3::new(-6.0, 0.0, 0.0));
+        let spare = station.add_module(ModuleType::Corridor, Vec3::new(-6.0, 0.0, -4.0));
+        assert!(station.connect_modules(hub, airlock));
+        assert!(station.free_ports(airlock).is_empty());
+        assert!(!station.connect_modules(airlock, spare));
+
+        // Connectors come back with a save
+        let restored = SpaceStation::from_snapshot(&station.snapshot());
+        let mates = |station: &SpaceStation| station.connectors().iter().map(|c| (c.modules, c.ports)).collect::<Vec<_>>();
+        assert_eq!(mates(&restored), mates(&station));
+    }
+
+    #[test]
+    fn life_support_hardware_feeds_the_air_and_wears_out_its_filters() {
+        let mut station = station_with(ModuleType::LivingQuarters);
+        let kinds: Vec<LifeSupportKind> = station.modules[0].life_support_units.iter().map(|unit| unit.kind).collect();
+        assert_eq!(kinds, LifeSupportKind::ALL);
+        assert!(station_with(ModuleType::Laboratory).modules[0].life_support_units.is_empty());
+
+        // Switched off, the scrubber stops drawing power and CO2 builds up
+        let draw = station.modules[0].power_draw();
+        assert!(station.switch_life_support(0, 1, false));
+        assert!(station.modules[0].power_draw() < draw);
+        assert_eq!(station.modules[0].scrubber_output(), 0.0);
+        for _ in 0..600 {
+            station.update(STEP);
+        }
+        assert!(station.modules[0].atmosphere.co2 > 1e-4);
+        assert!(station.switch_life_support(0, 1, true));
+
+        // Past half its life a filter lets less through; clogged, the unit
+        // fails and won't come back on until it's had a new one
+        let mut station = SpaceStation::create_default_layout();
+        let quarters = 6;
+        let recycler = 2;
+        // Water's first in the rates
+        let water = station.resource_rates()[0];
+        station.modules[quarters].life_support_units[recycler].filter = 0.25;
+        let unit = &station.modules[quarters].life_support_units[recycler];
+        assert!(unit.efficiency() < 1.0 && unit.efficiency() > 0.25);
+        station.modules[quarters].life_support_units[recycler].filter = 1e-5;
+        station.update(1.0);
+        assert_eq!(station.modules[quarters].life_support_units[recycler].state, UnitState::Failed);
+        assert!(station.drain_events().iter().any(|event| matches!(
+            event,
+            StationEvent::LifeSupportFailed { module: 6, kind: LifeSupportKind::WaterRecycler, .. }
+        )));
+        assert!(station.resource_rates()[0] < water);
+        assert!(!station.switch_life_support(quarters, recycler, true));
+
+        let parts = station.resources().total(Resource::SpareParts);
+        assert!(station.replace_filter(quarters, recycler));
+        assert_eq!(station.resources().total(Resource::SpareParts), parts - 1.0);
+        let unit = &station.modules[quarters].life_support_units[recycler];
+        assert_eq!((unit.state, unit.filter), (UnitState::Running, 1.0));
+        assert!(!station.replace_filter(quarters, 9));
+
+        // Kept in saves
+        station.switch_life_support(quarters, 0, false);
+        let restored = SpaceStation::from_snapshot(&station.snapshot());
+        assert_eq!(restored.modules[quarters].life_support_units[0].state, UnitState::Off);
     }
 }
@@ -0,0 +1,174 @@
+use std::marker::PhantomData;
+use anyhow::{bail, Result};
+use glam::Vec3;
+use crate::station::{ModuleType, SpaceStation};
+
+// Centre-to-centre distance between neighbouring modules, as in the
+// default layout; well inside connect_modules' reach
+pub const MODULE_SPACING: f32 = 8.0;
+// Closer than this to an existing module and the new one would overlap it
+const MIN_SEPARATION: f32 = 1.0;
+
+// Which way the next module goes from the current one
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Direction {
+    North,
+    East,
+    South,
+    West,
+    Up,
+    Down,
+}
+
+impl Direction {
+    pub fn offset(self) -> Vec3 {
+        match self {
+            Direction::North => Vec3::NEG_Z,
+            Direction::East => Vec3::X,
+            Direction::South => Vec3::Z,
+            Direction::West => Vec3::NEG_X,
+            Direction::Up => Vec3::Y,
+            Direction::Down => Vec3::NEG_Y,
+        }
+    }
+}
+
+// Builder states. Nothing can be attached until the first module is placed,
+// and an empty station can't be built; both are type errors.
+#[derive(Debug)]
+pub struct Empty;
+#[derive(Debug)]
+pub struct Anchored;
+
+// Fluent construction of custom stations for code embedding the simulation:
+//
+//     let station = StationBuilder::new()
+//         .hub(Vec3::ZERO)
+//         .branch(|b| b.corridor(Direction::North).module(ModuleType::Laboratory))
+//         .corridor(Direction::West)
+//         .module(ModuleType::PowerPlant)
+//         .build()?;
+//
+// Each step adds a module one MODULE_SPACING from the current one, connects
+// the two, and makes the new one current. Overlaps and failed connections
+// are reported by `build`.
+#[derive(Debug)]
+pub struct StationBuilder<S = Empty> {
+    station: SpaceStation,
+    current: usize,
+    // The way the last step went; `module` keeps going this way
+    heading: Direction,
+    seed: Option<u64>,
+    error: Option<String>,
+    _state: PhantomData<S>,
+}
+
+impl Default for StationBuilder<Empty> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StationBuilder<Empty> {
+    pub fn new() -> Self {
+        Self {
+            station: SpaceStation::new(),
+            current: 0,
+            heading: Direction::North,
+            seed: None,
+            error: None,
+            _state: PhantomData,
+        }
+    }
+
+    // Starts from a hub module at `at`
+    pub fn hub(self, at: Vec3) -> StationBuilder<Anchored> {
+        self.root(ModuleType::Hub, at)
+    }
+
+    // Starts from any module type at `at`
+    pub fn root(mut self, module_type: ModuleType, at: Vec3) -> StationBuilder<Anchored> {
+        self.current = self.station.add_module(module_type, at);
+        StationBuilder {
+            station: self.station,
+            current: self.current,
+            heading: self.heading,
+            seed: self.seed,
+            error: self.error,
+            _state: PhantomData,
+        }
+    }
+}
+
+impl<S> StationBuilder<S> {
+    // Seeds the station's random rolls, for reproducible runs
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+}
+
+impl StationBuilder<Anchored> {
+    // A corridor off the current module towards `to`
+    pub fn corridor(self, to: Direction) -> Self {
+        self.toward(ModuleType::Corridor, to)
+    }
+
+    // A module further along the way the last step went
+    pub fn module(self, module_type: ModuleType) -> Self {
+        let heading = self.heading;
+        self.toward(module_type, heading)
+    }
+
+    pub fn toward(mut self, module_type: ModuleType, direction: Direction) -> Self {
+        if self.error.is_some() {
+            return self;
+        }
+        let from = self.station.modules()[self.current].transform.position;
+        let position = from + direction.offset() * MODULE_SPACING;
+        if let Some(existing) = self
+            .station
+            .modules()
+            .iter()
+            .position(|m| m.transform.position.distance(position) < MIN_SEPARATION)
+        {
+            self.error = Some(format!(
+                "{:?} {:?} of module {} would overlap module {}",
+                module_type, direction, self.current, existing
+            ));
+            return self;
+        }
+        let index = self.station.add_module(module_type, position);
+        if !self.station.connect_modules(self.current, index) {
+            self.error = Some(format!("couldn't connect module {} to module {}", index, self.current));
+            return self;
+        }
+        self.current = index;
+        self.heading = direction;
+        self
+    }
+
+    // Builds a side branch, then carries on from where the branch started
+    pub fn branch(self, build: impl FnOnce(Self) -> Self) -> Self {
+        let (current, heading) = (self.current, self.heading);
+        let mut builder = build(self);
+        builder.current = current;
+        builder.heading = heading;
+        builder
+    }
+
+    // Index of the current module, for hooking up elements or items later
+    pub fn current(&self) -> usize {
+        self.current
+    }
+
+    pub fn build(mut self) -> Result<SpaceStation> {
+        if let Some(error) = self.error {
+            bail!("invalid station layout: {}", error);
+        }
+        if let Some(seed) = self.seed {
+            self.station.reseed(seed);
+        }
+        Ok(self.station)
+    }
+}
@@ -0,0 +1,246 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use crate::station::SpaceStation;
+
+// Fixed simulation tick, independent of the render frame rate
+pub const STEP_DT: f32 = 1.0 / 60.0;
+
+// After a long stall (debugger, window drag) the sim skips ahead rather than
+// running hundreds of ticks to catch up
+const MAX_STEPS_PER_FRAME: u32 = 8;
+// Fast-forwarded ticks are spread out at this many a frame
+const FAST_FORWARD_PER_FRAME: u32 = 120;
+
+// Speeds the time controls step through, slowest to fastest
+pub const TIME_SCALES: [f32; 9] = [0.1, 0.25, 0.5, 1.0, 2.0, 4.0, 8.0, 16.0, 32.0];
+
+// Where F2 and the `dump` console command write station state
+pub const DUMP_DIR: &str = "state_dumps";
+
+// Runs the simulation at a fixed rate and holds the debugger controls. While
+// paused nothing advances except the ticks explicitly asked for. The time
+// scale speeds the simulation up or slows it down by running more or fewer
+// ticks per frame; the camera and UI keep running at full rate regardless.
+#[derive(Debug)]
+pub struct SimStepper {
+    pub paused: bool,
+    time_scale: f32,
+    pending: u32,
+    tick: u64,
+    // Frame time not yet covered by a whole tick
+    accumulator: f32,
+    // Ticks still owed by `fast_forward`
+    forward: u32,
+}
+
+impl Default for SimStepper {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SimStepper {
+    pub fn new() -> Self {
+        Self {
+            paused: false,
+            time_scale: 1.0,
+            pending: 0,
+            tick: 0,
+            accumulator: 0.0,
+            forward: 0,
+        }
+    }
+
+    pub fn toggle_pause(&mut self) {
+        self.paused = !self.paused;
+        self.pending = 0;
+        self.accumulator = 0.0;
+    }
+
+    // Queues ticks to run on the next frame; stepping always pauses first
+    pub fn step(&mut self, ticks: u32) {
+        self.paused = true;
+        self.accumulator = 0.0;
+        self.pending = self.pending.saturating_add(ticks);
+    }
+
+    // Runs the simulation on by `seconds` over the next few frames, on top of
+    // the usual ticks and whether or not it's paused
+    pub fn fast_forward(&mut self, seconds: f32) {
+        let ticks = (seconds.max(0.0) / STEP_DT).round() as u32;
+        self.forward = self.forward.saturating_add(ticks);
+    }
+
+    pub fn fast_forwarding(&self) -> bool {
+        self.forward > 0
+    }
+
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+
+    // Simulated seconds per real second, clamped to the range of TIME_SCALES
+    pub fn set_time_scale(&mut self, scale: f32) {
+        let (min, max) = (TIME_SCALES[0], TIME_SCALES[TIME_SCALES.len() - 1]);
+        if scale.is_finite() {
+            self.time_scale = scale.clamp(min, max);
+        }
+    }
+
+    // Next speed up or down the list; returns the new scale
+    pub fn faster(&mut self) -> f32 {
+        if let Some(&next) = TIME_SCALES.iter().find(|&&scale| scale > self.time_scale) {
+            self.time_scale = next;
+        }
+        self.time_scale
+    }
+
+    pub fn slower(&mut self) -> f32 {
+        if let Some(&next) = TIME_SCALES.iter().rev().find(|&&scale| scale < self.time_scale) {
+            self.time_scale = next;
+        }
+        self.time_scale
+    }
+
+    // Simulation ticks completed so far
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    // How many STEP_DT ticks to run this frame
+    pub fn take_steps(&mut self, frame_time: f32) -> u32 {
+        let steps = if self.paused {
+            std::mem::take(&mut self.pending)
+        } else {
+            self.accumulator += frame_time * self.time_scale;
+            let due = (self.accumulator / STEP_DT) as u32;
+            self.accumulator -= due as f32 * STEP_DT;
+            // Fast-forward needs the headroom, or it would hit the cap every frame
+            let max_steps = MAX_STEPS_PER_FRAME * self.time_scale.ceil().max(1.0) as u32;
+            if due > max_steps {
+                self.accumulator = 0.0;
+            }
+            due.min(max_steps)
+        };
+        let forward = self.forward.min(FAST_FORWARD_PER_FRAME);
+        self.forward -= forward;
+        let steps = steps + forward;
+        self.tick += steps as u64;
+        steps
+    }
+
+    // How far rendering is between the last tick and the next, 0..1
+    pub fn alpha(&self) -> f32 {
+        if self.paused {
+            1.0
+        } else {
+            (self.accumulator / STEP_DT).clamp(0.0, 1.0)
+        }
+    }
+}
+
+// Writes the full station state at the current tick to a text file
+pub fn write_dump(station: &SpaceStation, tick: u64, dir: &Path) -> Result<PathBuf> {
+    fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    let path = dir.join(format!("tick_{:08}.txt", tick));
+    let contents = format!("tick {}\n{}", tick, station.dump_state());
+    fs::write(&path, contents).with_context(|| format!("failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_time_left_over_from_a_tick_carries_into_the_next_frame() {
+        let mut stepper = SimStepper::new();
+        assert_eq!(stepper.take_steps(STEP_DT * 0.7), 0);
+        assert!((stepper.alpha() - 0.7).abs() < 1e-3);
+
+        // Eight more frames of 0.7 ticks: six whole ticks, 0.3 of one left over
+        let steps: u32 = (0..8).map(|_| stepper.take_steps(STEP_DT * 0.7)).sum();
+        assert_eq!(steps, 6);
+        assert_eq!(stepper.tick(), 6);
+        assert!((stepper.alpha() - 0.3).abs() < 1e-3);
+    }
+
+    #[test]
+    fn a_stall_runs_a_capped_number_of_ticks_and_drops_the_rest() {
+        let mut stepper = SimStepper::new();
+        assert_eq!(stepper.take_steps(1.0), MAX_STEPS_PER_FRAME);
+        assert_eq!(stepper.alpha(), 0.0);
+
+        // Faster speeds get more headroom
+        stepper.set_time_scale(4.0);
+        assert_eq!(stepper.take_steps(STEP_DT * 2.6), 10);
+        assert_eq!(stepper.take_steps(1.0), MAX_STEPS_PER_FRAME * 4);
+    }
+
+    #[test]
+    fn paused_it_runs_only_the_ticks_stepped() {
+        let mut stepper = SimStepper::new();
+        stepper.take_steps(STEP_DT * 0.5);
+        stepper.toggle_pause();
+        assert_eq!(stepper.take_steps(1.0), 0);
+        assert_eq!(stepper.alpha(), 1.0);
+
+        stepper.step(1);
+        assert_eq!(stepper.take_steps(0.0), 1);
+        assert_eq!(stepper.take_steps(1.0), 0);
+        stepper.step(1);
+        stepper.step(10);
+        assert_eq!(stepper.take_steps(STEP_DT), 11);
+        assert_eq!(stepper.tick(), 12);
+
+        // Unpausing drops what was queued and starts the clock afresh
+        stepper.step(5);
+        stepper.toggle_pause();
+        assert!(!stepper.paused);
+        assert_eq!(stepper.take_steps(STEP_DT * 0.5), 0);
+        assert!((stepper.alpha() - 0.5).abs() < 1e-3);
+    }
+
+    #[test]
+    fn time_scales_stay_within_the_list() {
+        let mut stepper = SimStepper::new();
+        assert_eq!(stepper.faster(), 2.0);
+        assert_eq!(stepper.slower(), 1.0);
+
+        stepper.set_time_scale(100.0);
+        assert_eq!(stepper.time_scale(), TIME_SCALES[TIME_SCALES.len() - 1]);
+        assert_eq!(stepper.faster(), 32.0);
+        stepper.set_time_scale(0.0);
+        assert_eq!(stepper.time_scale(), TIME_SCALES[0]);
+        assert_eq!(stepper.slower(), 0.1);
+        stepper.set_time_scale(f32::NAN);
+        assert_eq!(stepper.time_scale(), 0.1);
+
+        // Off the list, the next step goes to the nearest entry that way
+        stepper.set_time_scale(3.0);
+        assert_eq!(stepper.faster(), 4.0);
+        stepper.set_time_scale(3.0);
+        assert_eq!(stepper.slower(), 2.0);
+    }
+
+    #[test]
+    fn fast_forward_spreads_its_ticks_over_frames_even_when_paused() {
+        let mut stepper = SimStepper::new();
+        stepper.toggle_pause();
+        stepper.fast_forward(3.0);
+        assert!(stepper.fast_forwarding());
+        assert_eq!(stepper.take_steps(STEP_DT), FAST_FORWARD_PER_FRAME);
+        assert_eq!(stepper.take_steps(STEP_DT), 180 - FAST_FORWARD_PER_FRAME);
+        assert!(!stepper.fast_forwarding());
+        assert_eq!(stepper.take_steps(STEP_DT), 0);
+        assert_eq!(stepper.tick(), 180);
+
+        // Running, it comes on top of the usual ticks
+        stepper.toggle_pause();
+        stepper.fast_forward(-1.0);
+        assert!(!stepper.fast_forwarding());
+        stepper.fast_forward(STEP_DT * 2.0);
+        assert_eq!(stepper.take_steps(STEP_DT * 1.5), 3);
+    }
+}
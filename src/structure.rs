@@ -0,0 +1,181 @@
+use glam::Vec3;
+use crate::module_id::ModuleId;
+
+// Load ratio a creaking joint has to drop back under before it can be heard
+// again, as a share of the warning level
+const CREAK_RESET: f32 = 0.9;
+
+// A module as the load pass sees it: tonnes, where it sits, and the
+// acceleration it has to be held against in m/s²
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Body {
+    pub mass: f32,
+    pub position: Vec3,
+    pub acceleration: f32,
+}
+
+// One connection as the structure sees it, lower-numbered module first
+#[derive(Debug, Clone, PartialEq)]
+pub struct Joint {
+    pub modules: (usize, usize),
+    pub ids: (ModuleId, ModuleId),
+    // Bending load and what the joint can take, kN·m
+    pub load: f32,
+    pub capacity: f32,
+    // Seconds it's been over capacity; it gives at the tunable limit
+    pub overloaded_for: f32,
+    pub creaking: bool,
+}
+
+impl Joint {
+    pub fn ratio(&self) -> f32 {
+        if self.capacity > 0.0 {
+            self.load / self.capacity
+        } else if self.load > 0.0 {
+            f32::INFINITY
+        } else {
+            0.0
+        }
+    }
+
+    // Whether it's just started creaking at `warning` times its capacity;
+    // it has to ease off a way before it'll creak again
+    pub fn creaks(&mut self, warning: f32) -> bool {
+        let ratio = self.ratio();
+        if self.creaking {
+            self.creaking = ratio >= warning * CREAK_RESET;
+            return false;
+        }
+        self.creaking = ratio >= warning;
+        self.creaking
+    }
+
+    // Whether it's been over capacity for `limit` seconds now
+    pub fn fails(&mut self, limit: f32, delta_time: f32) -> bool {
+        self.overloaded_for = if self.ratio() >= 1.0 { self.overloaded_for + delta_time } else { 0.0 };
+        self.overloaded_for >= limit
+    }
+}
+
+pub fn centre_of_mass(bodies: &[Body]) -> Vec3 {
+    let mass: f32 = bodies.iter().map(|body| body.mass).sum();
+    if mass <= 0.0 {
+        return Vec3::ZERO;
+    }
+    bodies.iter().map(|body| body.position * body.mass).sum::<Vec3>() / mass
+}
+
+// Bending load on each joint, in kN·m: everything hanging off it on the far
+// side from the centre of mass, times how far out from the joint it hangs.
+// Each connected piece is taken on its own, held from the module nearest its
+// own centre of mass. A joint in a loop only takes half of what's beyond it;
+// the other way round carries the rest.
+pub fn joint_loads(bodies: &[Body], joints: &[(usize, usize)]) -> Vec<f32> {
+    let count = bodies.len();
+    let mut neighbours = vec![Vec::new(); count];
+    for &(a, b) in joints.iter().filter(|&&(a, b)| a < count && b < count) {
+        neighbours[a].push(b);
+        neighbours[b].push(a);
+    }
+
+    // Breadth first from each piece's root, so every module knows what it
+    // hangs from
+    let mut parent = vec![None; count];
+    let mut depth = vec![usize::MAX; count];
+    let mut order = Vec::with_capacity(count);
+    for piece in pieces(&neighbours) {
+        let members: Vec<Body> = piece.iter().map(|&i| bodies[i]).collect();
+        let centre = centre_of_mass(&members);
+        let root = piece
+            .iter()
+            .copied()
+            .min_by(|&a, &b| bodies[a].position.distance(centre).total_cmp(&bodies[b].position.distance(centre)))
+            .unwrap_or(piece[0]);
+        depth[root] = 0;
+        let mut queue = std::collections::VecDeque::from([root]);
+        while let Some(module) = queue.pop_front() {
+            order.push(module);
+            for &next in &neighbours[module] {
+                if depth[next] == usize::MAX {
+                    depth[next] = depth[module] + 1;
+                    parent[next] = Some(module);
+                    queue.push_back(next);
+                }
+            }
+        }
+    }
+    let mut beyond = vec![Vec::new(); count];
+    for &module in order.iter().rev() {
+        let mut hanging = std::mem::take(&mut beyond[module]);
+        hanging.push(module);
+        if let Some(up) = parent[module] {
+            beyond[up].extend(hanging.iter().copied());
+        }
+        beyond[module] = hanging;
+    }
+
+    joints
+        .iter()
+        .map(|&(a, b)| {
+            if a >= count || b >= count {
+                return 0.0;
+            }
+            let far = if depth[a] > depth[b] { a } else { b };
+            let joint = (bodies[a].position + bodies[b].position) * 0.5;
+            let moment: f32 = beyond[far]
+                .iter()
+                .map(|&i| bodies[i].mass * bodies[i].acceleration * bodies[i].position.distance(joint))
+                .sum();
+            if bridges(&neighbours, a, b) {
+                moment
+            } else {
+                moment * 0.5
+            }
+        })
+        .collect()
+}
+
+// The modules in each connected piece
+fn pieces(neighbours: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let mut seen = vec![false; neighbours.len()];
+    let mut pieces = Vec::new();
+    for start in 0..neighbours.len() {
+        if seen[start] {
+            continue;
+        }
+        let mut piece = Vec::new();
+        let mut frontier = vec![start];
+        seen[start] = true;
+        while let Some(module) = frontier.pop() {
+            piece.push(module);
+            for &next in &neighbours[module] {
+                if !seen[next] {
+                    seen[next] = true;
+                    frontier.push(next);
+                }
+            }
+        }
+        pieces.push(piece);
+    }
+    pieces
+}
+
+// Whether taking the a-b joint out would leave b with no way back to a
+fn bridges(neighbours: &[Vec<usize>], a: usize, b: usize) -> bool {
+    let mut seen = vec![false; neighbours.len()];
+    let mut frontier = vec![a];
+    seen[a] = true;
+    while let Some(module) = frontier.pop() {
+        for &next in &neighbours[module] {
+            if (module, next) == (a, b) || (module, next) == (b, a) || seen[next] {
+                continue;
+            }
+            if next == b {
+                return false;
+            }
+            seen[next] = true;
+            frontier.push(next);
+        }
+    }
+    true
+}
@@ -0,0 +1,611 @@
+use crate::comms::{self, Comms};
+use crate::docking::{Docking, DockingStage};
+use crate::life_support::UnitState;
+use crate::maintenance;
+use crate::paint::{StyleGuide, Surface, Swatch};
+use crate::research::Research;
+use crate::resources::Resource;
+use crate::station::{Activation, ElementState, InteractionType, SpaceStation};
+
+// Rows of body text a terminal screen shows at once
+pub const TERMINAL_ROWS: usize = 14;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerminalPage {
+    Overview,
+    Power,
+    LifeSupport,
+    Radiation,
+    Modules,
+    Stores,
+    Traffic,
+    Comms,
+    Maintenance,
+    Paint,
+    Refit,
+}
+
+impl TerminalPage {
+    pub const ALL: [TerminalPage; 11] = [
+        TerminalPage::Overview,
+        TerminalPage::Power,
+        TerminalPage::LifeSupport,
+        TerminalPage::Radiation,
+        TerminalPage::Modules,
+        TerminalPage::Stores,
+        TerminalPage::Traffic,
+        TerminalPage::Comms,
+        TerminalPage::Maintenance,
+        TerminalPage::Paint,
+        TerminalPage::Refit,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            TerminalPage::Overview => "OVERVIEW",
+            TerminalPage::Power => "POWER GRID",
+            TerminalPage::LifeSupport => "LIFE SUPPORT",
+            TerminalPage::Radiation => "RADIATION",
+            TerminalPage::Modules => "MODULES",
+            TerminalPage::Stores => "STORES",
+            TerminalPage::Traffic => "TRAFFIC",
+            TerminalPage::Comms => "COMMS",
+            TerminalPage::Maintenance => "SERVICE",
+            TerminalPage::Paint => "PAINT",
+            TerminalPage::Refit => "REFIT",
+        }
+    }
+}
+
+// What the renderer draws for the current page
+#[derive(Debug, Clone)]
+pub struct TerminalScreen {
+    pub title: String,
+    pub page: TerminalPage,
+    pub lines: Vec<String>,
+    pub can_scroll_up: bool,
+    pub can_scroll_down: bool,
+}
+
+// Console UI opened from a `Terminal`, `MainComputer` or `Communications`
+// element. Its PAINT page picks colours from the style guide for the module
+// it's in, the REFIT page buys that module's next tier, and from the comms
+// console the TRAFFIC page moves ships about on the schedule and the COMMS
+// page trims the dish.
+#[derive(Debug)]
+pub struct Terminal {
+    source: Option<(usize, usize, InteractionType)>,
+    // Opened by an observer: every page can be read, nothing can be done
+    read_only: bool,
+    page: usize,
+    scroll: usize,
+    style_guide: StyleGuide,
+    swatch: usize,
+    // Picked on the TRAFFIC page, by place on the schedule
+    arrival: usize,
+}
+
+impl Default for Terminal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Terminal {
+    pub fn new() -> Self {
+        Self {
+            source: None,
+            read_only: false,
+            page: 0,
+            scroll: 0,
+            style_guide: StyleGuide::new(),
+            swatch: 0,
+            arrival: 0,
+        }
+    }
+
+    pub fn set_style_guide(&mut self, style_guide: StyleGuide) {
+        self.style_guide = style_guide;
+        self.swatch = 0;
+    }
+
+    pub fn style_guide(&self) -> &StyleGuide {
+        &self.style_guide
+    }
+
+    // The module the terminal's in and the colour picked on the PAINT page
+    pub fn selected_swatch(&self) -> Option<(usize, &Swatch)> {
+        let (module_idx, _, _) = self.source?;
+        if self.read_only || self.page() != TerminalPage::Paint {
+            return None;
+        }
+        Some((module_idx, self.style_guide.swatches().get(self.swatch)?))
+    }
+
+    // The module the terminal's in, to make the storm shelter from the
+    // RADIATION page
+    pub fn selected_shelter(&self) -> Option<usize> {
+        let (module_idx, _, _) = self.source?;
+        (!self.read_only && self.page() == TerminalPage::Radiation).then_some(module_idx)
+    }
+
+    // The module the terminal's in, to refit from the REFIT page
+    pub fn selected_refit(&self) -> Option<usize> {
+        let (module_idx, _, _) = self.source?;
+        (!self.read_only && self.page() == TerminalPage::Refit).then_some(module_idx)
+    }
+
+    // Whether the COMMS page is up at the comms console itself, where the
+    // dish can be trimmed
+    pub fn selected_comms(&self) -> bool {
+        let Some((_, _, element_type)) = self.source else {
+            return false;
+        };
+        element_type == InteractionType::Communications && !self.read_only && self.page() == TerminalPage::Comms
+    }
+
+    // The ship picked on the TRAFFIC page, for rescheduling; only the comms
+    // console can talk to them
+    pub fn selected_arrival(&self) -> Option<usize> {
+        let (_, _, element_type) = self.source?;
+        let schedules = element_type == InteractionType::Communications && !self.read_only;
+        (schedules && self.page() == TerminalPage::Traffic).then_some(self.arrival)
+    }
+
+    // Keeps the pick on a ship that's moved on the schedule
+    pub fn select_arrival(&mut self, index: usize) {
+        self.arrival = index;
+    }
+
+    pub fn opens_for(element_type: InteractionType) -> bool {
+        element_type.activation() == Activation::Panel || element_type == InteractionType::Communications
+    }
+
+    // The comms console opens on the TRAFFIC page
+    pub fn open(&mut self, module_idx: usize, element_idx: usize, element_type: InteractionType) {
+        self.source = Some((module_idx, element_idx, element_type));
+        self.read_only = false;
+        let first = if element_type == InteractionType::Communications { TerminalPage::Traffic } else { TerminalPage::Overview };
+        self.page = TerminalPage::ALL.iter().position(|&page| page == first).unwrap_or(0);
+        self.scroll = 0;
+        self.arrival = 0;
+    }
+
+    // Looks over a console without switching it on
+    pub fn open_read_only(&mut self, module_idx: usize, element_idx: usize, element_type: InteractionType) {
+        self.open(module_idx, element_idx, element_type);
+        self.read_only = true;
+    }
+
+    // Returns the (module, element) the terminal was opened from, unless it
+    // was only being looked over and there's nothing to switch off
+    pub fn close(&mut self) -> Option<(usize, usize)> {
+        let source = self.source.take();
+        source.filter(|_| !self.read_only).map(|(module_idx, element_idx, _)| (module_idx, element_idx))
+    }
+
+    pub fn read_only(&self) -> bool {
+        self.read_only
+    }
+
+    // The module it was opened in
+    pub fn module(&self) -> Option<usize> {
+        self.source.map(|(module_idx, _, _)| module_idx)
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.source.is_some()
+    }
+
+    pub fn page(&self) -> TerminalPage {
+        TerminalPage::ALL[self.page]
+    }
+
+    pub fn next_page(&mut self) {
+        self.page = (self.page + 1) % TerminalPage::ALL.len();
+        self.scroll = 0;
+    }
+
+    pub fn previous_page(&mut self) {
+        self.page = (self.page + TerminalPage::ALL.len() - 1) % TerminalPage::ALL.len();
+        self.scroll = 0;
+    }
+
+    // On the PAINT page these move the colour picked instead, and at the
+    // comms console's TRAFFIC page the ship
+    pub fn scroll_up(&mut self) {
+        if self.page() == TerminalPage::Paint {
+            self.swatch = self.swatch.saturating_sub(1);
+        } else if self.selected_arrival().is_some() {
+            self.arrival = self.arrival.saturating_sub(1);
+        } else {
+            self.scroll = self.scroll.saturating_sub(1);
+        }
+    }
+
+    // Clamped against the page length when the screen is built
+    pub fn scroll_down(&mut self) {
+        if self.page() == TerminalPage::Paint {
+            self.swatch = (self.swatch + 1).min(self.style_guide.swatches().len().saturating_sub(1));
+        } else if self.selected_arrival().is_some() {
+            self.arrival += 1;
+        } else {
+            self.scroll += 1;
+        }
+    }
+
+    pub fn screen(
+        &mut self,
+        station: &SpaceStation,
+        docking: &Docking,
+        research: &Research,
+        comms: &Comms,
+    ) -> Option<TerminalScreen> {
+        let (module_idx, _, element_type) = self.source?;
+        let lines = match self.page() {
+            TerminalPage::Paint => {
+                let lines = self.paint_lines(station, module_idx);
+                // Keep the picked colour on screen
+                let row = (lines.len() + self.swatch).saturating_sub(self.style_guide.swatches().len());
+                self.scroll = (row + 1).saturating_sub(TERMINAL_ROWS);
+                lines
+            }
+            TerminalPage::Traffic => {
+                self.arrival = self.arrival.min(docking.schedule().len().saturating_sub(1));
+                let lines = self.traffic_lines(station, docking);
+                if self.selected_arrival().is_some() {
+                    let row = (lines.len() + self.arrival).saturating_sub(docking.schedule().len());
+                    self.scroll = (row + 1).saturating_sub(TERMINAL_ROWS);
+                }
+                lines
+            }
+            TerminalPage::Refit => Self::refit_lines(station, research, module_idx),
+            TerminalPage::Comms => Self::comms_lines(station, comms),
+            page => Self::page_lines(page, station),
+        };
+
+        self.scroll = self.scroll.min(lines.len().saturating_sub(TERMINAL_ROWS));
+        let visible = lines.iter().skip(self.scroll).take(TERMINAL_ROWS).cloned().collect();
+
+        let title = match element_type {
+            InteractionType::MainComputer => "MAIN COMPUTER",
+            InteractionType::Communications => "COMMUNICATIONS",
+            _ => "STATION TERMINAL",
+        };
+        let access = if self.read_only { "  READ ONLY" } else { "" };
+
+        Some(TerminalScreen {
+            title: format!("{}{}  {}", title, access, station.clock().format_hud()),
+            page: self.page(),
+            lines: visible,
+            can_scroll_up: self.scroll > 0,
+            can_scroll_down: self.scroll + TERMINAL_ROWS < lines.len(),
+        })
+    }
+
+    fn page_lines(page: TerminalPage, station: &SpaceStation) -> Vec<String> {
+        // Screens show what the sensors say, flare noise and all
+        let telemetry = station.sensor_telemetry();
+        match page {
+            TerminalPage::Overview => vec![
+                format!("Structural integrity  {:>6.1}%", telemetry.structural_integrity * 100.0),
+                format!("Modules               {:>6}", telemetry.module_count),
+                format!("Sealed modules        {:>6}", telemetry.sealed_modules),
+                format!(
+                    "Power balance         {:>+6.1} kW",
+                    telemetry.power_generation - telemetry.power_consumption
+                ),
+                format!("Oxygen                {:>6.1}%", telemetry.oxygen_level * 100.0),
+                String::new(),
+                "Recent events:".to_string(),
+            ]
+            .into_iter()
+            .chain(station.log().recent(5).map(|entry| format!("  {}", entry)))
+            .collect(),
+            TerminalPage::Power => vec![
+                format!("Generation            {:>6.1} kW", telemetry.power_generation),
+                format!("Consumption           {:>6.1} kW", telemetry.power_consumption),
+                format!("Grid stability        {:>6.1}%", telemetry.grid_stability * 100.0),
+                format!("Battery               {:>6.1}%", telemetry.battery_level * 100.0),
+                format!("Sunlight              {:>6.0}%", station.sunlight() * 100.0),
+                format!(
+                    "Powered modules       {:>3}/{}",
+                    telemetry.powered_modules, telemetry.module_count
+                ),
+                String::new(),
+            ]
+            .into_iter()
+            .chain(
+                station
+                    .modules()
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, module)| module.power_generation > 0.0)
+                    .map(|(i, module)| {
+                        format!("  [{}] {:?}  +{:.1} kW", i, module.module_type, module.power_generation)
+                    }),
+            )
+            .chain(
+                station
+                    .modules()
+                    .iter()
+                    .enumerate()
+                    .filter(|(_, module)| !module.powered)
+                    .map(|(i, module)| format!("  [{}] {:?}  SHED", i, module.module_type)),
+            )
+            .collect(),
+            TerminalPage::LifeSupport => vec![
+                format!("Oxygen                {:>6.1}%", telemetry.oxygen_level * 100.0),
+                format!("Temperature           {:>6.1} C", telemetry.temperature - 273.15),
+                format!("Pressure              {:>6.2} atm", telemetry.pressure),
+                format!("CO2                   {:>6.2} kPa", telemetry.co2 * 101.325),
+                format!(
+                    "Sealed modules        {:>3}/{}",
+                    telemetry.sealed_modules, telemetry.module_count
+                ),
+                String::new(),
+            ]
+            .into_iter()
+            .chain(station.modules().iter().enumerate().map(|(i, module)| {
+                let air = &module.atmosphere;
+                format!(
+                    "  [{}] {:<15} {:>4.2} atm  O2 {:>5.1}%  CO2 {:>4.2} kPa",
+                    i,
+                    format!("{:?}", module.module_type),
+                    air.pressure(),
+                    air.oxygen_level() * 100.0,
+                    air.co2 * 101.325,
+                )
+            }))
+            .chain([String::new(), "Hardware:".to_string()])
+            .chain(station.modules().iter().enumerate().flat_map(|(i, module)| {
+                module.life_support_units.iter().enumerate().map(move |(j, unit)| {
+                    let state = match unit.state {
+                        UnitState::Running => "RUNNING",
+                        UnitState::Off => "OFF",
+                        UnitState::Failed => "FAILED",
+                    };
+                    format!(
+                        "  [{}:{}] {:<15} {:<8} filter {:>3.0}%  flow {:>3.0}%",
+                        i,
+                        j,
+                        unit.kind.name(),
+                        state,
+                        unit.filter * 100.0,
+                        unit.efficiency() * 100.0,
+                    )
+                })
+            }))
+            .collect(),
+            // Each module's shielding in halvings and the rate inside, in
+            // mSv an hour
+            TerminalPage::Radiation => vec![
+                format!("Outside               {:>6.2} mSv/h", station.exterior_radiation() * 3600.0),
+                match station.storm_shelter() {
+                    Some(i) => format!("Storm shelter         [{}] {:?}", i, station.modules()[i].module_type),
+                    None => "Storm shelter           none".to_string(),
+                },
+                String::new(),
+            ]
+            .into_iter()
+            .chain(station.modules().iter().enumerate().map(|(i, module)| {
+                format!(
+                    "  [{}] {:<15} {:>4.1}  {:>7.3} mSv/h{}",
+                    i,
+                    format!("{:?}", module.module_type),
+                    station.shielding(i),
+                    station.radiation(i) * 3600.0,
+                    if station.storm_shelter() == Some(i) { "  SHELTER" } else { "" },
+                )
+            }))
+            .collect(),
+            TerminalPage::Modules => station
+                .modules()
+                .iter()
+                .enumerate()
+                // Hull, wiring and life support condition
+                .map(|(i, module)| {
+                    format!(
+                        "[{:>2}] {:<15} {:>3.0}/{:>3.0}/{:>3.0}%  {:>6.1} kW {:>5.1} C  {}",
+                        i,
+                        format!("{:?}", module.module_type),
+                        module.structural_integrity * 100.0,
+                        module.wiring * 100.0,
+                        module.life_support * 100.0,
+                        module.power_draw(),
+                        module.atmosphere.temperature - 273.15,
+                        if station.fires().iter().any(|fire| fire.module == module.id) {
+                            "FIRE"
+                        } else if module.atmosphere_sealed {
+                            "SEALED"
+                        } else {
+                            "VENTING"
+                        },
+                    )
+                })
+                .collect(),
+            // Totals and net rate per hour, then each store
+            TerminalPage::Stores => {
+                let ledger = station.resources();
+                let rates = station.resource_rates();
+                Resource::ALL
+                    .into_iter()
+                    .zip(rates)
+                    .map(|(resource, rate)| {
+                        format!(
+                            "{:<14} {:>7.0} {:<2}  {:>+7.1}/h",
+                            resource.name(),
+                            ledger.total(resource),
+                            resource.unit(),
+                            rate * 3600.0
+                        )
+                    })
+                    .chain(std::iter::once(String::new()))
+                    .chain(ledger.stores().iter().map(|store| {
+                        let stock: Vec<String> = Resource::ALL
+                            .into_iter()
+                            .map(|resource| {
+                                if resource.counted() {
+                                    format!("{:.0}", store.amount(resource).floor())
+                                } else {
+                                    format!("{:.0}", store.amount(resource))
+                                }
+                            })
+                            .collect();
+                        let module = station.module_index(store.module).map_or("-".to_string(), |i| i.to_string());
+                        format!("  [{}] {}", module, stock.join(" / "))
+                    }))
+                    .collect()
+            }
+            // Running hours against the service interval, and the chance
+            // of a breakdown in the next 10 hours, worst first
+            TerminalPage::Maintenance => maintenance::wear_report(station)
+                .into_iter()
+                .map(|report| {
+                    let element = &station.modules()[report.module].interactive_elements[report.element];
+                    format!(
+                        "[{:>2}:{}] {:<20} {:>4.0}%  risk {:>5.1}%  {}",
+                        report.module,
+                        report.element,
+                        format!("{:?}", element.element_type),
+                        report.wear * 100.0,
+                        report.risk * 100.0,
+                        if element.state == ElementState::Malfunction { "FAILED" } else { "" },
+                    )
+                })
+                .collect(),
+            TerminalPage::Paint | TerminalPage::Traffic | TerminalPage::Comms | TerminalPage::Refit => Vec::new(),
+        }
+    }
+
+    // The ship at the berth and what it's doing, then those still to come
+    // with the time to go, the picked one marked
+    fn traffic_lines(&self, station: &SpaceStation, docking: &Docking) -> Vec<String> {
+        let berth = match docking.approach() {
+            Some(approach) => {
+                let airlock = station.module_index(approach.airlock).map_or("-".to_string(), |i| i.to_string());
+                let status = match approach.remaining() {
+                    Some(left) => format!("{}  {:.0} s", approach.stage.id().to_uppercase(), left),
+                    None if approach.stage == DockingStage::Inbound => format!("INBOUND  {:.0} m", approach.readout().range),
+                    None => approach.stage.id().to_uppercase(),
+                };
+                format!("Airlock {}  {:<10} {}", airlock, approach.arrival.name, status)
+            }
+            None => "Berth clear".to_string(),
+        };
+        let now = station.clock().now().as_secs_f32();
+        let picked = self.selected_arrival();
+        let mut lines = vec![berth, String::new()];
+        for (i, arrival) in docking.schedule().iter().enumerate() {
+            let to_go = arrival.at - now;
+            let eta = if to_go > 0.0 {
+                let seconds = to_go.ceil() as u32;
+                format!("T-{}:{:02}", seconds / 60, seconds % 60)
+            } else {
+                "DUE".to_string()
+            };
+            let cargo: Vec<String> = arrival
+                .cargo
+                .iter()
+                .map(|consignment| format!("{:.0} {}", consignment.amount, consignment.resource.name()))
+                .collect();
+            let cargo = if cargo.is_empty() { "no cargo".to_string() } else { cargo.join(", ") };
+            let every = arrival.every.map_or(String::new(), |every| format!("  every {:.0} min", every / 60.0));
+            let cursor = if picked == Some(i) { ">" } else { " " };
+            lines.push(format!("{} {:<10} {:<11} {:>8}  {}{}", cursor, arrival.name, arrival.ship.name(), eta, cargo, every));
+        }
+        lines
+    }
+
+    // What this module's refitted with and what the next tier costs, then
+    // every module that's been refitted
+    // The link, then the inbox newest first with unread ones starred
+    fn comms_lines(station: &SpaceStation, comms: &Comms) -> Vec<String> {
+        let delay = comms
+            .delay(station)
+            .map_or_else(|| "NO LOCK".to_string(), |seconds| format!("{:.1} s", seconds));
+        let mut lines = vec![
+            format!(
+                "Dish alignment        {:>6.0}%  (best {:.0}%)",
+                comms.alignment() * 100.0,
+                Comms::alignment_limit(station) * 100.0
+            ),
+            format!("Link                  {:>6.0}%", comms.link(station) * 100.0),
+            format!("Delay                 {:>8}  (relay {:.1} s)", delay, comms::RELAY_SECONDS),
+            format!("In transit            {:>6}", comms.in_transit()),
+            String::new(),
+        ];
+        if comms.inbox().next().is_none() {
+            lines.push("No messages".to_string());
+        }
+        for (message, received) in comms.inbox() {
+            let minutes = (received.at / 60.0).floor() as u32;
+            let unread = if received.read { ' ' } else { '*' };
+            lines.push(format!("{}{:>4} min  {}: {}", unread, minutes, message.from, message.subject));
+            if !message.body.is_empty() {
+                lines.push(format!("    {}", message.body));
+            }
+        }
+        lines
+    }
+
+    fn refit_lines(station: &SpaceStation, research: &Research, module_idx: usize) -> Vec<String> {
+        let Some(module) = station.modules().get(module_idx) else {
+            return Vec::new();
+        };
+        let refit = module.refit;
+        let mut lines = vec![
+            format!("Module {}  {:?}  tier {}", module_idx, module.module_type, refit.tier),
+            format!("  scrubbers x{:.2}  generation x{:.2}  hull x{:.2}", refit.scrubbing, refit.generation, refit.hull),
+            String::new(),
+        ];
+        match research.next_tier(station, module_idx) {
+            Some(next) => {
+                let changes: Vec<String> = [("scrubbers", next.scrubbing), ("generation", next.generation), ("hull", next.hull)]
+                    .into_iter()
+                    .filter(|&(_, factor)| factor != 1.0)
+                    .map(|(stat, factor)| format!("{} {:+.0}%", stat, (factor - 1.0) * 100.0))
+                    .collect();
+                lines.push(format!("Next: tier {}  {}", next.tier, next.name));
+                lines.push(format!("  {}", changes.join("  ")));
+                lines.push(format!("  {} points, {:.0} spare parts", next.points, next.parts));
+            }
+            None => lines.push("No further refits for this module".to_string()),
+        }
+        lines.push(format!(
+            "Research points {}  spare parts {:.0}",
+            research.points(),
+            station.resources().total(Resource::SpareParts)
+        ));
+        lines.push(String::new());
+        lines.extend(
+            station
+                .modules()
+                .iter()
+                .enumerate()
+                .filter(|(_, module)| module.refit.tier > 0)
+                .map(|(i, module)| format!("  [{}] {:?}  tier {}", i, module.module_type, module.refit.tier)),
+        );
+        lines
+    }
+
+    // The module's current finishes, then the style guide with the picked
+    // colour marked and what it may go on
+    fn paint_lines(&self, station: &SpaceStation, module_idx: usize) -> Vec<String> {
+        let Some(module) = station.modules().get(module_idx) else {
+            return Vec::new();
+        };
+        let mut lines = vec![format!("Module {}  {:?}", module_idx, module.module_type)];
+        for surface in Surface::ALL {
+            let coat = module.paint.coat(surface).map_or("standard", |coat| coat.swatch.as_str());
+            lines.push(format!("  {:<6} {}", surface.id(), coat));
+        }
+        lines.push(String::new());
+        for (i, swatch) in self.style_guide.swatches().iter().enumerate() {
+            let surfaces: Vec<&str> = swatch.surfaces.iter().map(|surface| surface.id()).collect();
+            let cursor = if i == self.swatch { ">" } else { " " };
+            lines.push(format!("{} {:<16} {}", cursor, swatch.name, surfaces.join(", ")));
+        }
+        lines
+    }
+}
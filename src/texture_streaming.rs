@@ -0,0 +1,280 @@
+// Mip residency for streamed textures. This only decides which mip levels
+// should be in video memory; the renderer carries out the loads and
+// evictions it asks for. Level 0 is full resolution.
+
+// Mips at or below this size are loaded up front and never evicted, so every
+// texture always has something to draw with
+pub const LOW_MIP_SIZE: u32 = 64;
+
+// Finer levels streamed in per texture per frame
+const LOADS_PER_TEXTURE: u32 = 1;
+
+pub type TextureId = usize;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StreamCommand {
+    Load { texture: TextureId, level: u32 },
+    Evict { texture: TextureId, level: u32 },
+}
+
+#[derive(Debug, Clone)]
+struct StreamedTexture {
+    name: String,
+    width: u32,
+    height: u32,
+    mip_count: u32,
+    // Finest level in memory; everything coarser is resident too
+    finest_resident: u32,
+    // Finest level any visible use asked for this frame
+    wanted: Option<u32>,
+    last_used: u64,
+}
+
+impl StreamedTexture {
+    fn level_bytes(&self, level: u32) -> u64 {
+        let width = (self.width >> level).max(1) as u64;
+        let height = (self.height >> level).max(1) as u64;
+        width * height * 4
+    }
+
+    fn resident_bytes(&self) -> u64 {
+        (self.finest_resident..self.mip_count).map(|level| self.level_bytes(level)).sum()
+    }
+
+    // Coarsest level that is always kept
+    fn floor_level(&self) -> u32 {
+        let largest = self.width.max(self.height);
+        let mut level = 0;
+        while level + 1 < self.mip_count && largest >> level > LOW_MIP_SIZE {
+            level += 1;
+        }
+        level
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ResidencyStats {
+    pub textures: usize,
+    pub resident_bytes: u64,
+    pub budget_bytes: u64,
+    // Textures showing a coarser mip than their footprint asks for
+    pub starved: usize,
+    pub loads: usize,
+    pub evictions: usize,
+}
+
+#[derive(Debug)]
+pub struct TextureStreamer {
+    textures: Vec<StreamedTexture>,
+    budget_bytes: u64,
+    frame: u64,
+    stats: ResidencyStats,
+}
+
+impl TextureStreamer {
+    pub fn new(budget_bytes: u64) -> Self {
+        Self {
+            textures: Vec::new(),
+            budget_bytes,
+            frame: 0,
+            stats: ResidencyStats::default(),
+        }
+    }
+
+    // Starts with only the low mips resident
+    pub fn register(&mut self, name: &str, width: u32, height: u32) -> TextureId {
+        let mip_count = 32 - width.max(height).max(1).leading_zeros();
+        let mut texture = StreamedTexture {
+            name: name.to_string(),
+            width,
+            height,
+            mip_count,
+            finest_resident: 0,
+            wanted: None,
+            last_used: 0,
+        };
+        texture.finest_resident = texture.floor_level();
+        self.textures.push(texture);
+        self.textures.len() - 1
+    }
+
+    pub fn name(&self, texture: TextureId) -> Option<&str> {
+        self.textures.get(texture).map(|t| t.name.as_str())
+    }
+
+    // Finest level the renderer may sample this frame
+    pub fn resident_level(&self, texture: TextureId) -> Option<u32> {
+        self.textures.get(texture).map(|t| t.finest_resident)
+    }
+
+    // Reports a visible use covering about `footprint` pixels across on screen
+    pub fn request(&mut self, texture: TextureId, footprint: f32) {
+        let frame = self.frame;
+        let Some(texture) = self.textures.get_mut(texture) else {
+            return;
+        };
+        // One texel per pixel: each halving of the footprint drops a level
+        let ratio = texture.width.max(texture.height) as f32 / footprint.max(1.0);
+        let level = (ratio.log2().floor().max(0.0) as u32).min(texture.mip_count - 1);
+        texture.wanted = Some(texture.wanted.map_or(level, |wanted| wanted.min(level)));
+        texture.last_used = frame;
+    }
+
+    // Works out this frame's loads and evictions and starts the next frame
+    pub fn update(&mut self) -> Vec<StreamCommand> {
+        let mut commands = Vec::new();
+        let mut resident: u64 = self.textures.iter().map(StreamedTexture::resident_bytes).sum();
+
+        // Stream finer levels toward what each visible texture wants, making
+        // room from textures that weren't used this frame
+        for id in 0..self.textures.len() {
+            let Some(wanted) = self.textures[id].wanted else {
+                continue;
+            };
+            for _ in 0..LOADS_PER_TEXTURE {
+                let texture = &self.textures[id];
+                if texture.finest_resident <= wanted {
+                    break;
+                }
+                let bytes = texture.level_bytes(texture.finest_resident - 1);
+                while resident + bytes > self.budget_bytes && self.evict_least_recent(&mut resident, &mut commands) {}
+                if resident + bytes > self.budget_bytes {
+                    break;
+                }
+                let texture = &mut self.textures[id];
+                texture.finest_resident -= 1;
+                resident += bytes;
+                commands.push(StreamCommand::Load {
+                    texture: id,
+                    level: texture.finest_resident,
+                });
+            }
+        }
+
+        // The budget may have shrunk since last frame
+        while resident > self.budget_bytes && self.evict_least_recent(&mut resident, &mut commands) {}
+
+        self.stats = ResidencyStats {
+            textures: self.textures.len(),
+            resident_bytes: resident,
+            budget_bytes: self.budget_bytes,
+            starved: self
+                .textures
+                .iter()
+                .filter(|t| t.wanted.is_some_and(|wanted| wanted < t.finest_resident))
+                .count(),
+            loads: commands.iter().filter(|c| matches!(c, StreamCommand::Load { .. })).count(),
+            evictions: commands.iter().filter(|c| matches!(c, StreamCommand::Evict { .. })).count(),
+        };
+        for texture in &mut self.textures {
+            texture.wanted = None;
+        }
+        self.frame += 1;
+        commands
+    }
+
+    pub fn set_budget(&mut self, budget_bytes: u64) {
+        self.budget_bytes = budget_bytes;
+    }
+
+    pub fn stats(&self) -> ResidencyStats {
+        self.stats
+    }
+
+    // Drops the finest streamed level of the texture used longest ago.
+    // Textures in use this frame are never evicted, so loads can't thrash.
+    fn evict_least_recent(&mut self, resident: &mut u64, commands: &mut Vec<StreamCommand>) -> bool {
+        let victim = self
+            .textures
+            .iter()
+            .enumerate()
+            .filter(|(_, t)| t.wanted.is_none() && t.finest_resident < t.floor_level())
+            .min_by_key(|(_, t)| (t.last_used, std::cmp::Reverse(t.level_bytes(t.finest_resident))))
+            .map(|(id, _)| id);
+        let Some(id) = victim else {
+            return false;
+        };
+        let texture = &mut self.textures[id];
+        *resident -= texture.level_bytes(texture.finest_resident);
+        commands.push(StreamCommand::Evict {
+            texture: id,
+            level: texture.finest_resident,
+        });
+        texture.finest_resident += 1;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 1024 px, so levels 4 (64 px) and coarser start resident
+    const SIZE: u32 = 1024;
+    const LOW_MIPS: u64 = 4 * (64 * 64 + 32 * 32 + 16 * 16 + 8 * 8 + 4 * 4 + 2 * 2 + 1);
+    const LEVEL_3: u64 = 128 * 128 * 4;
+
+    #[test]
+    fn finer_levels_stream_in_one_per_frame() {
+        let mut streamer = TextureStreamer::new(u64::MAX);
+        streamer.register("hull", SIZE, SIZE);
+        assert_eq!(streamer.resident_level(0), Some(4));
+        let mut loads = Vec::new();
+        for _ in 0..6 {
+            streamer.request(0, SIZE as f32);
+            let commands = streamer.update();
+            assert!(commands.len() <= 1);
+            loads.extend(commands);
+        }
+        let levels: Vec<_> = (0..4).rev().map(|level| StreamCommand::Load { texture: 0, level }).collect();
+        assert_eq!(loads, levels);
+        assert_eq!(streamer.resident_level(0), Some(0));
+
+        // A use half the size only wants the next level down
+        let mut streamer = TextureStreamer::new(u64::MAX);
+        streamer.register("hull", SIZE, SIZE);
+        for _ in 0..6 {
+            streamer.request(0, SIZE as f32 / 2.0);
+            streamer.update();
+        }
+        assert_eq!(streamer.resident_level(0), Some(1));
+    }
+
+    #[test]
+    fn over_budget_the_least_recently_used_level_goes_first() {
+        // Room for four textures' low mips and three level 3s
+        let mut streamer = TextureStreamer::new(4 * LOW_MIPS + 3 * LEVEL_3);
+        for name in ["hull", "floor", "panel", "door"] {
+            streamer.register(name, SIZE, SIZE);
+        }
+        for id in 0..3 {
+            streamer.request(id, 128.0);
+            assert_eq!(streamer.update(), [StreamCommand::Load { texture: id, level: 3 }]);
+        }
+
+        // No room for the door's: the texture seen longest ago gives way
+        streamer.request(1, 128.0);
+        streamer.request(3, 128.0);
+        assert_eq!(
+            streamer.update(),
+            [StreamCommand::Evict { texture: 0, level: 3 }, StreamCommand::Load { texture: 3, level: 3 }]
+        );
+        assert_eq!(streamer.resident_level(0), Some(4));
+        let stats = streamer.stats();
+        assert_eq!(stats.resident_bytes, stats.budget_bytes);
+        assert_eq!((stats.loads, stats.evictions, stats.starved), (1, 1, 0));
+
+        // Textures in use aren't evicted for one another; the loser waits
+        for id in 0..4 {
+            streamer.request(id, 128.0);
+        }
+        assert!(streamer.update().is_empty());
+        assert_eq!(streamer.stats().starved, 1);
+
+        // A shrunk budget sheds streamed levels, but the low mips stay
+        streamer.set_budget(0);
+        streamer.update();
+        assert_eq!(streamer.stats().resident_bytes, 4 * LOW_MIPS);
+        assert!((0..4).all(|id| streamer.resident_level(id) == Some(4)));
+    }
+}
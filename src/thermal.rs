@@ -0,0 +1,94 @@
+use crate::atmosphere::NOMINAL_TEMPERATURE;
+use crate::station::{ElementState, InteractionType, StationModule};
+use crate::tunables::StationTunables;
+
+// What a hull in the station's shadow would settle at with nothing inside
+// keeping it warm, in kelvin
+const HULL_SINK: f32 = 200.0;
+
+// How the crew feel about a module's air
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comfort {
+    Cold,
+    Cool,
+    Comfortable,
+    Warm,
+    Hot,
+}
+
+impl Comfort {
+    pub fn of(temperature: f32) -> Self {
+        let celsius = temperature - 273.15;
+        if celsius < 10.0 {
+            Comfort::Cold
+        } else if celsius < 18.0 {
+            Comfort::Cool
+        } else if celsius <= 26.0 {
+            Comfort::Comfortable
+        } else if celsius <= 32.0 {
+            Comfort::Warm
+        } else {
+            Comfort::Hot
+        }
+    }
+
+    pub fn id(self) -> &'static str {
+        match self {
+            Comfort::Cold => "cold",
+            Comfort::Cool => "cool",
+            Comfort::Comfortable => "comfortable",
+            Comfort::Warm => "warm",
+            Comfort::Hot => "hot",
+        }
+    }
+}
+
+// Heat the module's radiators are dumping right now. They hold the air at
+// nominal, running harder the further over it is up to their capacity, and
+// need power and working hardware.
+pub fn radiator_output(module: &StationModule, tunables: &StationTunables) -> f32 {
+    if !module.powered {
+        return 0.0;
+    }
+    let running = module
+        .interactive_elements
+        .iter()
+        .filter(|element| element.element_type == InteractionType::Radiator && element.state == ElementState::Active)
+        .count();
+    let demand = tunables.radiator_gain * (module.atmosphere.temperature - NOMINAL_TEMPERATURE);
+    demand.clamp(0.0, tunables.radiator_capacity * running as f32)
+}
+
+// Heat in K·m³/s, like fires: everything powered ends up as heat, and so
+// does the crew's body heat. It soaks through the structure into connected
+// modules whether or not the doors are open, leaks slowly out through the
+// hull, and goes out through radiators. `connections` lists each once, by
+// index. Returns each module's temperature from before the update.
+pub fn update_heat(
+    modules: &mut [StationModule],
+    connections: &[(usize, usize)],
+    tunables: &StationTunables,
+    delta_time: f32,
+) -> Vec<f32> {
+    let before: Vec<f32> = modules.iter().map(|module| module.atmosphere.temperature).collect();
+    let mut heat = vec![0.0; modules.len()];
+    for (i, module) in modules.iter().enumerate() {
+        let temperature = before[i];
+        if module.powered {
+            heat[i] += module.power_draw() * tunables.power_heat;
+        }
+        heat[i] += module.crew as f32 * tunables.crew_heat;
+        heat[i] -= tunables.hull_cooling * (temperature - HULL_SINK);
+        heat[i] -= radiator_output(module, tunables);
+    }
+    for &(a, b) in connections {
+        let flow = tunables.heat_conduction * (before[a] - before[b]);
+        heat[a] -= flow;
+        heat[b] += flow;
+    }
+    for (module, heat) in modules.iter_mut().zip(heat) {
+        let volume = module.module_type.volume();
+        module.atmosphere.temperature = (module.atmosphere.temperature + heat * delta_time / volume).max(0.0);
+    }
+    before
+}
@@ -0,0 +1,465 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use crate::station::InteractionType;
+
+// Balancing numbers for the simulation, loaded from assets/tunables.toml and
+// editable live from the Shift+F3 panel. Anything missing from the file keeps
+// its default.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Tunables {
+    pub station: StationTunables,
+    pub power: PowerTunables,
+    pub particles: ParticleTunables,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct StationTunables {
+    // Integrity lost per second for each malfunctioning element in a module
+    pub malfunction_damage_rate: f32,
+    // Module integrity below which the hull is considered breached
+    pub hull_breach_threshold: f32,
+    // Share of a breached module's air lost per second, roughly
+    pub vent_rate: f32,
+    // How fast pressure evens out between connected modules, per second
+    pub equalize_rate: f32,
+    // Gas amounts are atm·m³ per second: oxygen each crew member breathes,
+    // and CO2 scrubbed and air made up per unit of scrubber capacity
+    pub crew_oxygen_use: f32,
+    pub scrubber_rate: f32,
+    pub replenish_rate: f32,
+    // Module spacing that puts no stress on a connection
+    pub optimal_connection_distance: f32,
+    pub connection_stress_factor: f32,
+    // Bending load in kN·m a sound connection at the best spacing takes
+    // before it starts to give, and the share of that it creaks at
+    pub connection_strength: f32,
+    pub strain_warning: f32,
+    // Seconds a connection holds over its limit before it tears
+    pub overload_seconds: f32,
+    // Angular acceleration the attitude thrusters swing the station at, in
+    // rad/s²; it loads modules more the further out from the centre of mass
+    pub slew_acceleration: f32,
+    // Share of hull damage that also lands on wiring and life support
+    pub component_damage_share: f32,
+    // Chance per second each module wears a random component, and by how much
+    pub wear_chance: f32,
+    pub wear_amount: f32,
+    // Share of a sealed module's air lost per second through a fully worn
+    // hull; scales down with hull condition
+    pub hull_leak_rate: f32,
+    // Chance per second a malfunctioning element arcs and lights a fire
+    pub fire_ignition_chance: f32,
+    // Oxygen level fires need; above it they grow at `fire_growth` per
+    // second per unit of oxygen to spare, below it they die back
+    pub fire_min_oxygen: f32,
+    pub fire_growth: f32,
+    // Intensity a running suppression system takes off per second
+    pub fire_suppression_rate: f32,
+    // At full intensity: oxygen burnt (atm·m³/s), heat (K·m³/s), hull and
+    // wiring lost per second, and the chance per second of catching
+    // through a fully open door
+    pub fire_oxygen_use: f32,
+    pub fire_heat: f32,
+    pub fire_damage_rate: f32,
+    pub fire_spread_chance: f32,
+    // Heat in K·m³/s, like `fire_heat`: per unit of power drawn, per crew
+    // member, and per kelvin of difference across each connection
+    pub power_heat: f32,
+    pub crew_heat: f32,
+    pub heat_conduction: f32,
+    // Heat lost through the hull per kelvin above an unheated hull
+    pub hull_cooling: f32,
+    // What one radiator can dump, and how hard they run per kelvin over nominal
+    pub radiator_capacity: f32,
+    pub radiator_gain: f32,
+    // Above this (K), each running element risks failing, at this chance
+    // per second per kelvin over
+    pub overheat_temperature: f32,
+    pub overheat_failure_chance: f32,
+    // Chance per second a solar flare hits, how long one lasts (s), and the
+    // share of network commands lost at its height
+    pub flare_chance: f32,
+    pub flare_duration: f32,
+    pub flare_command_loss: f32,
+    // Radiation outside the hull (mSv/s): always, and added at a flare's
+    // height. A storm shelter gets this many more halvings on top of its
+    // module's shielding.
+    pub radiation_background: f32,
+    pub flare_radiation: f32,
+    pub shelter_shielding: f32,
+    // Per crew member per second: water drunk (L) and food eaten (kg), and
+    // the share of that water life support wins back
+    pub crew_water_use: f32,
+    pub crew_food_use: f32,
+    pub water_recovery: f32,
+    // Per second from each running piece of lab kit: food grown (kg) and
+    // spare parts made
+    pub food_growth: f32,
+    pub parts_fabrication: f32,
+    // Oxygen level at which a sealed module is topped up from a stored
+    // canister, and how much of a full module's oxygen one holds
+    pub canister_threshold: f32,
+    pub canister_oxygen: f32,
+    // Seconds of running that count as an hour of machinery use, and the
+    // chance per second a machine fails once it's run its full service
+    // interval; the chance goes with the square of the hours
+    pub usage_hour: f32,
+    pub element_failure_rate: f32,
+}
+
+impl Default for StationTunables {
+    fn default() -> Self {
+        Self {
+            malfunction_damage_rate: 0.01,
+            hull_breach_threshold: 0.25,
+            vent_rate: 0.5,
+            equalize_rate: 0.5,
+            crew_oxygen_use: 0.01,
+            scrubber_rate: 0.03,
+            replenish_rate: 0.5,
+            optimal_connection_distance: 8.0,
+            connection_stress_factor: 0.5,
+            connection_strength: 5000.0,
+            strain_warning: 0.8,
+            overload_seconds: 3.0,
+            slew_acceleration: 0.01,
+            component_damage_share: 0.5,
+            wear_chance: 0.002,
+            wear_amount: 0.05,
+            hull_leak_rate: 0.02,
+            fire_ignition_chance: 0.02,
+            fire_min_oxygen: 0.5,
+            fire_growth: 0.05,
+            fire_suppression_rate: 0.2,
+            fire_oxygen_use: 0.2,
+            fire_heat: 100.0,
+            fire_damage_rate: 0.005,
+            fire_spread_chance: 0.05,
+            power_heat: 1.0,
+            crew_heat: 0.1,
+            heat_conduction: 2.0,
+            hull_cooling: 0.02,
+            radiator_capacity: 40.0,
+            radiator_gain: 10.0,
+            overheat_temperature: 318.15,
+            overheat_failure_chance: 0.002,
+            flare_chance: 0.0005,
+            flare_duration: 60.0,
+            flare_command_loss: 0.5,
+            radiation_background: 0.0002,
+            flare_radiation: 0.2,
+            shelter_shielding: 3.0,
+            crew_water_use: 0.003,
+            crew_food_use: 0.0002,
+            water_recovery: 0.9,
+            food_growth: 0.0005,
+            parts_fabrication: 0.001,
+            canister_threshold: 0.8,
+            canister_oxygen: 0.1,
+            usage_hour: 60.0,
+            element_failure_rate: 0.001,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PowerTunables {
+    pub generator_output: f32,
+    pub main_computer_draw: f32,
+    pub communications_draw: f32,
+    pub station_control_draw: f32,
+    pub power_control_draw: f32,
+    pub environment_control_draw: f32,
+    pub light_control_draw: f32,
+    // Every other element type
+    pub default_draw: f32,
+    // Station battery, in kW·s, and how fast it can take and give power, in kW
+    pub battery_capacity: f32,
+    pub battery_charge_rate: f32,
+    pub battery_discharge_rate: f32,
+    // What each battery module adds to the capacity, in kW·s, and to both
+    // rates, in kW
+    pub battery_module_capacity: f32,
+    pub battery_module_rate: f32,
+    // A solar array's output square-on to the sun, in kW, and how fast its
+    // tracking motor turns the panels, in degrees a second
+    pub solar_output: f32,
+    pub tracking_rate: f32,
+}
+
+impl Default for PowerTunables {
+    fn default() -> Self {
+        Self {
+            generator_output: 100.0,
+            main_computer_draw: 5.0,
+            communications_draw: 3.0,
+            station_control_draw: 4.0,
+            power_control_draw: 2.0,
+            environment_control_draw: 2.0,
+            light_control_draw: 1.0,
+            default_draw: 0.5,
+            battery_capacity: 3000.0,
+            battery_charge_rate: 20.0,
+            battery_discharge_rate: 40.0,
+            battery_module_capacity: 150000.0,
+            battery_module_rate: 40.0,
+            solar_output: 60.0,
+            tracking_rate: 0.5,
+        }
+    }
+}
+
+impl PowerTunables {
+    // Power an element uses while switched on
+    pub fn element_draw(&self, element_type: InteractionType) -> f32 {
+        match element_type {
+            InteractionType::MainComputer => self.main_computer_draw,
+            InteractionType::Communications => self.communications_draw,
+            InteractionType::StationControl => self.station_control_draw,
+            InteractionType::PowerControl => self.power_control_draw,
+            InteractionType::EnvironmentControl => self.environment_control_draw,
+            InteractionType::LightControl => self.light_control_draw,
+            // Doors hold where they are without power, and suppression
+            // runs off its own pressure
+            InteractionType::Door | InteractionType::FireSuppression => 0.0,
+            _ => self.default_draw,
+        }
+    }
+}
+
+// Per-tick multipliers are for the fixed 60 Hz sim tick
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ParticleTunables {
+    pub debris_drag: f32,
+    pub smoke_rise: f32,
+    pub smoke_growth: f32,
+    pub fire_rise: f32,
+    pub fire_shrink: f32,
+    pub spark_gravity: f32,
+    pub spark_shrink: f32,
+    pub glow_fade: f32,
+    pub shockwave_growth: f32,
+}
+
+impl Default for ParticleTunables {
+    fn default() -> Self {
+        Self {
+            debris_drag: 0.99,
+            smoke_rise: 0.1,
+            smoke_growth: 1.01,
+            fire_rise: 0.2,
+            fire_shrink: 0.99,
+            spark_gravity: 0.5,
+            spark_shrink: 0.98,
+            glow_fade: 0.95,
+            shockwave_growth: 1.1,
+        }
+    }
+}
+
+impl Tunables {
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let source = fs::read_to_string(path)
+            .with_context(|| format!("failed to read tunables from {}", path.display()))?;
+        toml::from_str(&source).context("invalid tunables")
+    }
+
+    pub fn save<P: AsRef<Path>>(&self, path: P) -> Result<()> {
+        let path = path.as_ref();
+        let contents = toml::to_string(self).context("failed to serialize tunables")?;
+        fs::write(path, contents).with_context(|| format!("failed to write {}", path.display()))
+    }
+
+    // Every value by its dotted name, in panel order
+    pub fn fields_mut(&mut self) -> Vec<(&'static str, &mut f32)> {
+        let Tunables { station, power, particles } = self;
+        vec![
+            ("station.malfunction_damage_rate", &mut station.malfunction_damage_rate),
+            ("station.hull_breach_threshold", &mut station.hull_breach_threshold),
+            ("station.vent_rate", &mut station.vent_rate),
+            ("station.equalize_rate", &mut station.equalize_rate),
+            ("station.crew_oxygen_use", &mut station.crew_oxygen_use),
+            ("station.scrubber_rate", &mut station.scrubber_rate),
+            ("station.replenish_rate", &mut station.replenish_rate),
+            ("station.optimal_connection_distance", &mut station.optimal_connection_distance),
+            ("station.connection_stress_factor", &mut station.connection_stress_factor),
+            ("station.connection_strength", &mut station.connection_strength),
+            ("station.strain_warning", &mut station.strain_warning),
+            ("station.overload_seconds", &mut station.overload_seconds),
+            ("station.slew_acceleration", &mut station.slew_acceleration),
+            ("station.component_damage_share", &mut station.component_damage_share),
+            ("station.wear_chance", &mut station.wear_chance),
+            ("station.wear_amount", &mut station.wear_amount),
+            ("station.hull_leak_rate", &mut station.hull_leak_rate),
+            ("station.fire_ignition_chance", &mut station.fire_ignition_chance),
+            ("station.fire_min_oxygen", &mut station.fire_min_oxygen),
+            ("station.fire_growth", &mut station.fire_growth),
+            ("station.fire_suppression_rate", &mut station.fire_suppression_rate),
+            ("station.fire_oxygen_use", &mut station.fire_oxygen_use),
+            ("station.fire_heat", &mut station.fire_heat),
+            ("station.fire_damage_rate", &mut station.fire_damage_rate),
+            ("station.fire_spread_chance", &mut station.fire_spread_chance),
+            ("station.power_heat", &mut station.power_heat),
+            ("station.crew_heat", &mut station.crew_heat),
+            ("station.heat_conduction", &mut station.heat_conduction),
+            ("station.hull_cooling", &mut station.hull_cooling),
+            ("station.radiator_capacity", &mut station.radiator_capacity),
+            ("station.radiator_gain", &mut station.radiator_gain),
+            ("station.overheat_temperature", &mut station.overheat_temperature),
+            ("station.overheat_failure_chance", &mut station.overheat_failure_chance),
+            ("station.flare_chance", &mut station.flare_chance),
+            ("station.flare_duration", &mut station.flare_duration),
+            ("station.flare_command_loss", &mut station.flare_command_loss),
+            ("station.radiation_background", &mut station.radiation_background),
+            ("station.flare_radiation", &mut station.flare_radiation),
+            ("station.shelter_shielding", &mut station.shelter_shielding),
+            ("station.crew_water_use", &mut station.crew_water_use),
+            ("station.crew_food_use", &mut station.crew_food_use),
+            ("station.water_recovery", &mut station.water_recovery),
+            ("station.food_growth", &mut station.food_growth),
+            ("station.parts_fabrication", &mut station.parts_fabrication),
+            ("station.canister_threshold", &mut station.canister_threshold),
+            ("station.canister_oxygen", &mut station.canister_oxygen),
+            ("station.usage_hour", &mut station.usage_hour),
+            ("station.element_failure_rate", &mut station.element_failure_rate),
+            ("power.generator_output", &mut power.generator_output),
+            ("power.main_computer_draw", &mut power.main_computer_draw),
+            ("power.communications_draw", &mut power.communications_draw),
+            ("power.station_control_draw", &mut power.station_control_draw),
+            ("power.power_control_draw", &mut power.power_control_draw),
+            ("power.environment_control_draw", &mut power.environment_control_draw),
+            ("power.light_control_draw", &mut power.light_control_draw),
+            ("power.default_draw", &mut power.default_draw),
+            ("power.battery_capacity", &mut power.battery_capacity),
+            ("power.battery_charge_rate", &mut power.battery_charge_rate),
+            ("power.battery_discharge_rate", &mut power.battery_discharge_rate),
+            ("power.battery_module_capacity", &mut power.battery_module_capacity),
+            ("power.battery_module_rate", &mut power.battery_module_rate),
+            ("power.solar_output", &mut power.solar_output),
+            ("power.tracking_rate", &mut power.tracking_rate),
+            ("particles.debris_drag", &mut particles.debris_drag),
+            ("particles.smoke_rise", &mut particles.smoke_rise),
+            ("particles.smoke_growth", &mut particles.smoke_growth),
+            ("particles.fire_rise", &mut particles.fire_rise),
+            ("particles.fire_shrink", &mut particles.fire_shrink),
+            ("particles.spark_gravity", &mut particles.spark_gravity),
+            ("particles.spark_shrink", &mut particles.spark_shrink),
+            ("particles.glow_fade", &mut particles.glow_fade),
+            ("particles.shockwave_growth", &mut particles.shockwave_growth),
+        ]
+    }
+
+    pub fn fields(&self) -> Vec<(&'static str, f32)> {
+        self.clone()
+            .fields_mut()
+            .into_iter()
+            .map(|(name, value)| (name, *value))
+            .collect()
+    }
+}
+
+// How often the tunables file is checked for edits, in seconds
+const POLL_INTERVAL: f32 = 1.0;
+
+// Reloads the tunables whenever their file changes on disk
+#[derive(Debug)]
+pub struct TunablesFile {
+    path: PathBuf,
+    modified: Option<SystemTime>,
+    since_poll: f32,
+}
+
+impl TunablesFile {
+    pub fn new<P: AsRef<Path>>(path: P) -> Self {
+        let path = path.as_ref().to_path_buf();
+        Self {
+            modified: modified_time(&path),
+            path,
+            since_poll: 0.0,
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    // Some when the file changed since the last load
+    pub fn poll(&mut self, delta_time: f32) -> Option<Result<Tunables>> {
+        self.since_poll += delta_time;
+        if self.since_poll < POLL_INTERVAL {
+            return None;
+        }
+        self.since_poll = 0.0;
+        let modified = modified_time(&self.path);
+        if modified.is_none() || modified == self.modified {
+            return None;
+        }
+        self.modified = modified;
+        Some(Tunables::load(&self.path))
+    }
+
+    // Writes the values back and remembers the new timestamp, so our own
+    // save doesn't come back as a reload
+    pub fn save(&mut self, tunables: &Tunables) -> Result<()> {
+        tunables.save(&self.path)?;
+        self.modified = modified_time(&self.path);
+        Ok(())
+    }
+}
+
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+// Shift+F3 panel: up/down picks a value, left/right nudges it (Shift for
+// bigger steps), S writes the file
+#[derive(Debug)]
+pub struct TuningPanel {
+    pub visible: bool,
+    pub selected: usize,
+}
+
+impl Default for TuningPanel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TuningPanel {
+    pub fn new() -> Self {
+        Self {
+            visible: false,
+            selected: 0,
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    pub fn select_next(&mut self, tunables: &Tunables) {
+        self.selected = (self.selected + 1).min(tunables.fields().len() - 1);
+    }
+
+    // Moves the selected value by a fraction of itself; returns the new value
+    pub fn nudge(&self, tunables: &mut Tunables, direction: f32, coarse: bool) -> Option<f32> {
+        let mut fields = tunables.fields_mut();
+        let (_, value) = fields.get_mut(self.selected)?;
+        let fraction = if coarse { 0.25 } else { 0.05 };
+        let step = (value.abs() * fraction).max(0.001);
+        **value = (**value + step * direction.signum()).max(0.0);
+        Some(**value)
+    }
+}
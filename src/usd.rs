@@ -0,0 +1,259 @@
+use std::fmt::Write as _;
+use std::fs;
+use std::path::Path;
+use anyhow::{Context, Result};
+use glam::{Mat4, Vec3};
+use crate::door::DOOR_COLOR;
+use crate::effects::EventEffects;
+use crate::geometry::Mesh;
+use crate::station::SpaceStation;
+
+// Film rate; recordings are sampled at this many frames per second
+pub const USD_FPS: u32 = 24;
+// Ten minutes; recording stops taking frames past this
+const MAX_FRAMES: usize = 10 * 60 * USD_FPS as usize;
+
+// Everything that moves, at one instant
+#[derive(Debug, Clone)]
+pub struct SceneFrame {
+    pub modules: Vec<Mat4>,
+    pub doors: Vec<Mat4>,
+    // Position and size of every live particle
+    pub particles: Vec<(Vec3, f32)>,
+    pub camera: Option<Mat4>,
+}
+
+impl SceneFrame {
+    // `camera` is the eye and the point it looks at
+    pub fn capture(station: &SpaceStation, effects: &EventEffects, camera: Option<(Vec3, Vec3)>) -> Self {
+        Self {
+            modules: station.modules().iter().map(|module| module.transform.matrix()).collect(),
+            doors: (0..station.doors().len())
+                .map(|i| station.doors()[i].panel_matrix(station.door_openness(i)))
+                .collect(),
+            particles: effects
+                .emitters()
+                .iter()
+                .flat_map(|effect| effect.emitter.particles.iter().map(|p| (p.position, p.size)))
+                .collect(),
+            // USD cameras look down -Z from their origin, like a view matrix inverted
+            camera: camera.map(|(eye, target)| Mat4::look_at_rh(eye, target, Vec3::Y).inverse()),
+        }
+    }
+}
+
+// Samples the scene at USD_FPS between `start` and `stop`, for exporting
+// recorded station events to DCC tools
+#[derive(Debug)]
+pub struct SceneRecorder {
+    timer: f32,
+    frames: Option<Vec<SceneFrame>>,
+}
+
+impl Default for SceneRecorder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SceneRecorder {
+    pub fn new() -> Self {
+        Self {
+            timer: 0.0,
+            frames: None,
+        }
+    }
+
+    pub fn recording(&self) -> bool {
+        self.frames.is_some()
+    }
+
+    pub fn start(&mut self) {
+        self.timer = 0.0;
+        self.frames = Some(Vec::new());
+    }
+
+    // The frames recorded since `start`, if it was running
+    pub fn stop(&mut self) -> Option<Vec<SceneFrame>> {
+        self.frames.take()
+    }
+
+    pub fn frame_count(&self) -> usize {
+        self.frames.as_ref().map_or(0, Vec::len)
+    }
+
+    // Advances the sample timer; true when this frame should be captured
+    pub fn should_capture(&mut self, delta_time: f32) -> bool {
+        let Some(frames) = &self.frames else {
+            return false;
+        };
+        if frames.len() >= MAX_FRAMES {
+            return false;
+        }
+        // The first frame is taken straight away
+        if frames.is_empty() {
+            self.timer = 0.0;
+            return true;
+        }
+        self.timer += delta_time;
+        let interval = 1.0 / USD_FPS as f32;
+        if self.timer < interval {
+            return false;
+        }
+        self.timer -= interval;
+        true
+    }
+
+    pub fn push(&mut self, frame: SceneFrame) {
+        if let Some(frames) = &mut self.frames {
+            frames.push(frame);
+        }
+    }
+}
+
+fn vec3(v: impl Into<Vec3>) -> String {
+    let v = v.into();
+    format!("({}, {}, {})", v.x, v.y, v.z)
+}
+
+// USD matrices are row-vector, so glam's columns are its rows
+fn matrix(m: &Mat4) -> String {
+    let rows: Vec<String> = m
+        .to_cols_array_2d()
+        .iter()
+        .map(|c| format!("({}, {}, {}, {})", c[0], c[1], c[2], c[3]))
+        .collect();
+    format!("( {} )", rows.join(", "))
+}
+
+fn join<T>(items: impl Iterator<Item = T>, format: impl Fn(T) -> String) -> String {
+    items.map(format).collect::<Vec<_>>().join(", ")
+}
+
+// A transform, with time samples if there are any
+fn write_transform(out: &mut String, indent: &str, rest: &Mat4, samples: &[Option<&Mat4>]) {
+    let _ = writeln!(out, "{}matrix4d xformOp:transform = {}", indent, matrix(rest));
+    if samples.iter().any(Option::is_some) {
+        let _ = writeln!(out, "{}matrix4d xformOp:transform.timeSamples = {{", indent);
+        for (frame, sample) in samples.iter().enumerate() {
+            if let Some(sample) = sample {
+                let _ = writeln!(out, "{}    {}: {},", indent, frame, matrix(sample));
+            }
+        }
+        let _ = writeln!(out, "{}}}", indent);
+    }
+    let _ = writeln!(out, "{}uniform token[] xformOpOrder = [\"xformOp:transform\"]", indent);
+}
+
+fn write_mesh(out: &mut String, indent: &str, name: &str, mesh: &Mesh, color: Vec3) {
+    let _ = writeln!(out, "{}def Mesh \"{}\"\n{}{{", indent, name, indent);
+    let triangles = mesh.indices.len() / 3;
+    let _ = writeln!(out, "{}    int[] faceVertexCounts = [{}]", indent, join(0..triangles, |_| "3".to_string()));
+    let _ = writeln!(out, "{}    int[] faceVertexIndices = [{}]", indent, join(mesh.indices.iter(), |i| i.to_string()));
+    let _ = writeln!(out, "{}    point3f[] points = [{}]", indent, join(mesh.vertices.iter(), |v| vec3(v.position)));
+    let _ = writeln!(
+        out,
+        "{}    normal3f[] normals = [{}] (\n{}        interpolation = \"vertex\"\n{}    )",
+        indent,
+        join(mesh.vertices.iter(), |v| vec3(v.normal)),
+        indent,
+        indent
+    );
+    let _ = writeln!(out, "{}    color3f[] primvars:displayColor = [{}]", indent, vec3(color));
+    // Modules are built to be seen from inside as well as out
+    let _ = writeln!(out, "{}    uniform bool doubleSided = 1", indent);
+    let _ = writeln!(out, "{}}}", indent);
+}
+
+// The station's hierarchy as USD ASCII: each module an Xform holding its
+// mesh, then the door panels, then (with frames) the particles and the
+// camera, animated over the recording
+pub fn to_usda(station: &SpaceStation, frames: &[SceneFrame]) -> String {
+    let mut out = String::new();
+    let last = frames.len().saturating_sub(1);
+    let _ = writeln!(
+        out,
+        "#usda 1.0\n(\n    defaultPrim = \"Station\"\n    upAxis = \"Y\"\n    metersPerUnit = 1\n    \
+         startTimeCode = 0\n    endTimeCode = {}\n    timeCodesPerSecond = {}\n)\n",
+        last, USD_FPS
+    );
+    out.push_str("def Xform \"Station\"\n{\n");
+
+    for (i, module) in station.modules().iter().enumerate() {
+        let _ = writeln!(out, "    def Xform \"Module_{}\" (\n        doc = \"{:?}\"\n    )\n    {{", i, module.module_type);
+        let samples: Vec<Option<&Mat4>> = frames.iter().map(|frame| frame.modules.get(i)).collect();
+        write_transform(&mut out, "        ", &module.transform.matrix(), &samples);
+        write_mesh(&mut out, "        ", "Hull", &module.mesh, module.material.albedo.truncate());
+        out.push_str("    }\n");
+    }
+
+    for (i, door) in station.doors().iter().enumerate() {
+        let Some((a, b)) = station.door_modules(i) else {
+            continue;
+        };
+        let _ = writeln!(out, "    def Xform \"Door_{}_{}\"\n    {{", a, b);
+        let samples: Vec<Option<&Mat4>> = frames.iter().map(|frame| frame.doors.get(i)).collect();
+        write_transform(&mut out, "        ", &door.panel_matrix(station.door_openness(i)), &samples);
+        write_mesh(&mut out, "        ", "Panel", &door.mesh, DOOR_COLOR);
+        out.push_str("    }\n");
+    }
+
+    if !frames.is_empty() {
+        out.push_str("    def Points \"Particles\"\n    {\n        point3f[] points.timeSamples = {\n");
+        for (frame, sample) in frames.iter().enumerate() {
+            let _ = writeln!(out, "            {}: [{}],", frame, join(sample.particles.iter(), |(p, _)| vec3(*p)));
+        }
+        out.push_str("        }\n        float[] widths.timeSamples = {\n");
+        for (frame, sample) in frames.iter().enumerate() {
+            let _ = writeln!(out, "            {}: [{}],", frame, join(sample.particles.iter(), |(_, s)| s.to_string()));
+        }
+        out.push_str("        }\n    }\n");
+
+        let cameras: Vec<Option<&Mat4>> = frames.iter().map(|frame| frame.camera.as_ref()).collect();
+        if let Some(first) = cameras.iter().flatten().next() {
+            out.push_str("    def Camera \"Camera\"\n    {\n");
+            write_transform(&mut out, "        ", first, &cameras);
+            out.push_str("    }\n");
+        }
+    }
+    out.push_str("}\n");
+    out
+}
+
+// Writes a .usda; without frames it's the station as it stands
+pub fn export(station: &SpaceStation, frames: &[SceneFrame], path: &Path) -> Result<()> {
+    fs::write(path, to_usda(station, frames)).with_context(|| format!("failed to write {}", path.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rng;
+
+    const STEP: f32 = 1.0 / 60.0;
+
+    #[test]
+    fn usd_recording_animates_doors_and_particles() {
+        let mut station = SpaceStation::create_default_layout();
+        let effects = EventEffects::new(rng::seeded(1));
+        let mut recorder = SceneRecorder::new();
+        recorder.start();
+        for _ in 0..30 {
+            if recorder.should_capture(STEP) {
+                recorder.push(SceneFrame::capture(&station, &effects, Some((Vec3::ONE, Vec3::ZERO))));
+            }
+            station.update(STEP);
+        }
+        let frames = recorder.stop().unwrap();
+        assert_eq!(frames.len(), 12);
+        let usda = to_usda(&station, &frames);
+        assert!(usda.starts_with("#usda 1.0"));
+        assert!(usda.contains("endTimeCode = 11"));
+        assert_eq!(usda.matches("def Mesh").count(), station.modules().len() + station.doors().len());
+        assert!(usda.contains("def Xform \"Door_0_1\""));
+        assert!(usda.contains("xformOp:transform.timeSamples"));
+        assert!(usda.contains("def Points \"Particles\""));
+        assert!(usda.contains("def Camera \"Camera\""));
+        assert!(!to_usda(&station, &[]).contains("timeSamples"));
+    }
+}
@@ -0,0 +1,398 @@
+use std::collections::VecDeque;
+use std::f32::consts::{FRAC_PI_4, TAU};
+use glam::Vec3;
+use serde::{Deserialize, Serialize};
+use crate::station::SpaceStation;
+
+// The pieces of voice chat for multiplayer crews: when to transmit, how a
+// frame is packed, and how a voice should sound where it's heard. Nothing
+// calls them yet. There's no microphone capture, audio output or transport,
+// so no voice goes between players.
+
+pub const SAMPLE_RATE: u32 = 48_000;
+// 20 ms of mono, the frame size Opus is usually run at
+pub const FRAME_SAMPLES: usize = 960;
+
+// Closer than this a voice is at full volume
+const REFERENCE_DISTANCE: f32 = 1.5;
+// Clarity lost to a shut door on the way, and to each module passed through
+const DOOR_MUFFLE: f32 = 0.6;
+const MODULE_MUFFLE: f32 = 0.1;
+// Low-pass cutoffs for a clear and a fully muffled voice, in Hz
+const CLEAR_CUTOFF: f32 = 16_000.0;
+const MUFFLED_CUTOFF: f32 = 400.0;
+// Suit radios pass about the telephone band
+const RADIO_LOW_CUT: f32 = 300.0;
+const RADIO_HIGH_CUT: f32 = 3_400.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VoiceMode {
+    Off,
+    // Transmits while the push-to-talk key is held
+    PushToTalk,
+    // Transmits whenever the microphone picks up more than the threshold
+    OpenMic,
+}
+
+// The [voice] section of config.toml
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct VoiceConfig {
+    pub mode: VoiceMode,
+    // Open mic level, 0..1 RMS, and how many seconds it stays open after
+    // the level drops so the ends of words aren't clipped
+    pub open_mic_threshold: f32,
+    pub hold_seconds: f32,
+    // Playback volumes, 0..1, for voices in the air and on the suit radio
+    pub volume: f32,
+    pub radio_volume: f32,
+    // Metres beyond which a voice in the air isn't heard
+    pub hearing_range: f32,
+}
+
+impl Default for VoiceConfig {
+    fn default() -> Self {
+        Self {
+            mode: VoiceMode::PushToTalk,
+            open_mic_threshold: 0.02,
+            hold_seconds: 0.4,
+            volume: 1.0,
+            radio_volume: 0.8,
+            hearing_range: 25.0,
+        }
+    }
+}
+
+// Decides frame by frame whether the microphone goes out
+#[derive(Debug, Default)]
+pub struct VoiceGate {
+    // Seconds of open mic left after the last loud frame
+    hold: f32,
+}
+
+impl VoiceGate {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, config: &VoiceConfig, push_to_talk: bool, frame: &[i16], delta_time: f32) -> bool {
+        match config.mode {
+            VoiceMode::Off => false,
+            VoiceMode::PushToTalk => push_to_talk,
+            VoiceMode::OpenMic => {
+                if level(frame) >= config.open_mic_threshold {
+                    self.hold = config.hold_seconds;
+                    true
+                } else {
+                    self.hold -= delta_time;
+                    self.hold > 0.0
+                }
+            }
+        }
+    }
+}
+
+// RMS of a frame, 0 silent .. 1 full scale
+pub fn level(frame: &[i16]) -> f32 {
+    if frame.is_empty() {
+        return 0.0;
+    }
+    let sum: f32 = frame.iter().map(|&sample| (sample as f32 / 32768.0).powi(2)).sum();
+    (sum / frame.len() as f32).sqrt()
+}
+
+// Packs a frame of mono samples for the transport and unpacks it again.
+// Opus would go behind this; MuLawCodec needs no library.
+pub trait VoiceCodec {
+    fn encode(&mut self, frame: &[i16]) -> Vec<u8>;
+    // A lost packet is decoded from an empty one
+    fn decode(&mut self, packet: &[u8]) -> Vec<i16>;
+}
+
+// G.711 mu-law: a byte a sample, half the size of the raw samples
+#[derive(Debug, Default, Clone, Copy)]
+pub struct MuLawCodec;
+
+const MU_LAW_BIAS: i32 = 0x84;
+const MU_LAW_CLIP: i32 = 32635;
+
+impl VoiceCodec for MuLawCodec {
+    fn encode(&mut self, frame: &[i16]) -> Vec<u8> {
+        frame.iter().map(|&sample| mu_law_encode(sample)).collect()
+    }
+
+    // Silence stands in for a lost frame
+    fn decode(&mut self, packet: &[u8]) -> Vec<i16> {
+        if packet.is_empty() {
+            return vec![0; FRAME_SAMPLES];
+        }
+        packet.iter().map(|&byte| mu_law_decode(byte)).collect()
+    }
+}
+
+fn mu_law_encode(sample: i16) -> u8 {
+    let sign = if sample < 0 { 0x80 } else { 0 };
+    let magnitude = (sample as i32).abs().min(MU_LAW_CLIP) + MU_LAW_BIAS;
+    // Segment from the highest set bit, bits 7 to 14
+    let exponent = (31 - magnitude.leading_zeros()).saturating_sub(7).min(7);
+    let mantissa = (magnitude >> (exponent + 3)) & 0x0f;
+    !(sign | (exponent << 4) as u8 | mantissa as u8)
+}
+
+fn mu_law_decode(byte: u8) -> i16 {
+    let byte = !byte;
+    let exponent = (byte >> 4) & 0x07;
+    let mantissa = (byte & 0x0f) as i32;
+    let magnitude = (((mantissa << 3) + MU_LAW_BIAS) << exponent) - MU_LAW_BIAS;
+    if byte & 0x80 != 0 {
+        -magnitude as i16
+    } else {
+        magnitude as i16
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VoiceChannel {
+    // Heard from the speaker's avatar, through the station's air
+    Proximity,
+    // Suit comms on a spacewalk: flat, band-limited and heard anywhere
+    Radio,
+}
+
+// Where someone talking or listening is, and whether they're out on EVA
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VoiceSource {
+    pub position: Vec3,
+    pub outside: bool,
+}
+
+// How one speaker should be played to one listener
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct VoicePlayback {
+    pub channel: VoiceChannel,
+    pub gain: f32,
+    // -1 left .. 1 right
+    pub pan: f32,
+    // Band the voice is filtered to, in Hz; 0 leaves the low end alone
+    pub low_cut: f32,
+    pub high_cut: f32,
+}
+
+// None when the listener can't hear the speaker at all. Anyone outside is
+// on the suit radio, and the crew inside hear them on it too.
+pub fn playback(
+    station: &SpaceStation,
+    config: &VoiceConfig,
+    speaker: VoiceSource,
+    listener: VoiceSource,
+    listener_right: Vec3,
+) -> Option<VoicePlayback> {
+    if speaker.outside || listener.outside {
+        return Some(VoicePlayback {
+            channel: VoiceChannel::Radio,
+            gain: config.radio_volume,
+            pan: 0.0,
+            low_cut: RADIO_LOW_CUT,
+            high_cut: RADIO_HIGH_CUT,
+        });
+    }
+    let distance = speaker.position.distance(listener.position);
+    if distance > config.hearing_range {
+        return None;
+    }
+    let muffle = muffling(station, speaker.position, listener.position)?;
+    let falloff = REFERENCE_DISTANCE / distance.max(REFERENCE_DISTANCE);
+    Some(VoicePlayback {
+        channel: VoiceChannel::Proximity,
+        gain: config.volume * falloff * (1.0 - muffle),
+        pan: (speaker.position - listener.position).normalize_or_zero().dot(listener_right.normalize_or_zero()),
+        low_cut: 0.0,
+        // Geometric, so each bit of muffling takes off a similar share of the top end
+        high_cut: CLEAR_CUTOFF * (MUFFLED_CUTOFF / CLEAR_CUTOFF).powf(muffle),
+    })
+}
+
+// 0 clear .. 1 walled off, from the modules and doors between two points.
+// Sound takes the fewest modules, through shut and locked doors alike, but
+// not through vacuum: None when a breach or a gap is in the way.
+pub fn muffling(station: &SpaceStation, from: Vec3, to: Vec3) -> Option<f32> {
+    let path = sound_path(station, station.nearest_module(from)?, station.nearest_module(to)?)?;
+    let mut clarity = 1.0;
+    for pair in path.windows(2) {
+        clarity *= 1.0 - MODULE_MUFFLE;
+        if let Some(door) = station.door_between(pair[0], pair[1]) {
+            clarity *= 1.0 - DOOR_MUFFLE * (1.0 - station.door_openness(door));
+        }
+    }
+    Some(1.0 - clarity)
+}
+
+// Breadth first over connections between sealed modules, both ends included
+fn sound_path(station: &SpaceStation, from: usize, to: usize) -> Option<Vec<usize>> {
+    let modules = station.modules();
+    let sealed = |index: usize| modules.get(index).is_some_and(|module| module.atmosphere_sealed);
+    if !sealed(from) || !sealed(to) {
+        return None;
+    }
+    let mut previous = vec![None; modules.len()];
+    let mut queue = VecDeque::from([from]);
+    previous[from] = Some(from);
+    while let Some(current) = queue.pop_front() {
+        if current == to {
+            let mut path = vec![to];
+            while let Some(&last) = path.last().filter(|&&last| last != from) {
+                path.push(previous[last]?);
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for next in station.neighbours(current) {
+            if sealed(next) && previous[next].is_none() {
+                previous[next] = Some(current);
+                queue.push_back(next);
+            }
+        }
+    }
+    None
+}
+
+// Filters and pans one speaker's decoded frames into an interleaved stereo
+// mix. Keep one per speaker so the filters carry over between frames.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct VoiceFilter {
+    below_high_cut: f32,
+    below_low_cut: f32,
+}
+
+impl VoiceFilter {
+    pub fn mix(&mut self, playback: &VoicePlayback, frame: &[i16], out: &mut [f32]) {
+        let high = one_pole(playback.high_cut);
+        let low = one_pole(playback.low_cut);
+        // Equal power across the pan
+        let angle = (playback.pan.clamp(-1.0, 1.0) + 1.0) * FRAC_PI_4;
+        let (left, right) = (angle.cos() * playback.gain, angle.sin() * playback.gain);
+        for (&sample, stereo) in frame.iter().zip(out.chunks_exact_mut(2)) {
+            let sample = sample as f32 / 32768.0;
+            self.below_high_cut += high * (sample - self.below_high_cut);
+            self.below_low_cut += low * (self.below_high_cut - self.below_low_cut);
+            let band = self.below_high_cut - self.below_low_cut;
+            stereo[0] += band * left;
+            stereo[1] += band * right;
+        }
+    }
+}
+
+// Smoothing factor for a one-pole low-pass at `cutoff` Hz
+fn one_pole(cutoff: f32) -> f32 {
+    1.0 - (-TAU * cutoff / SAMPLE_RATE as f32).exp()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::station::{ElementState, ModuleType};
+
+    fn corridors(count: usize) -> (SpaceStation, Vec<usize>) {
+        let mut station = SpaceStation::new();
+        let modules: Vec<usize> =
+            (0..count).map(|i| station.add_module(ModuleType::Corridor, Vec3::new(i as f32 * 4.0, 0.0, 0.0))).collect();
+        for pair in modules.windows(2) {
+            assert!(station.connect_modules(pair[0], pair[1]));
+        }
+        (station, modules)
+    }
+
+    fn inside(x: f32) -> VoiceSource {
+        VoiceSource { position: Vec3::new(x, 0.0, 0.0), outside: false }
+    }
+
+    #[test]
+    fn mu_law_halves_the_frame_and_comes_back_close() {
+        let frame: Vec<i16> = (0..FRAME_SAMPLES).map(|i| ((i as f32 * 0.05).sin() * 20000.0) as i16).collect();
+        let mut codec = MuLawCodec;
+        let packet = codec.encode(&frame);
+        assert_eq!(packet.len(), FRAME_SAMPLES);
+        let decoded = codec.decode(&packet);
+        for (&original, &decoded) in frame.iter().zip(&decoded) {
+            // Mu-law steps grow with the level; a few percent is its resolution
+            assert!((original as i32 - decoded as i32).abs() <= (original as i32).abs() / 16 + 8);
+        }
+        assert_eq!(codec.decode(&[]), vec![0; FRAME_SAMPLES]);
+    }
+
+    #[test]
+    fn push_to_talk_sends_only_while_held_and_open_mic_holds_briefly() {
+        let loud = vec![8000i16; FRAME_SAMPLES];
+        let quiet = vec![0i16; FRAME_SAMPLES];
+        let mut gate = VoiceGate::new();
+        let mut config = VoiceConfig::default();
+        assert!(gate.update(&config, true, &quiet, 0.02));
+        assert!(!gate.update(&config, false, &loud, 0.02));
+
+        config.mode = VoiceMode::OpenMic;
+        assert!(!gate.update(&config, false, &quiet, 0.02));
+        assert!(gate.update(&config, false, &loud, 0.02));
+        assert!(gate.update(&config, false, &quiet, 0.2));
+        assert!(!gate.update(&config, false, &quiet, 0.3));
+
+        config.mode = VoiceMode::Off;
+        assert!(!gate.update(&config, true, &loud, 0.02));
+    }
+
+    #[test]
+    fn voices_fall_off_pan_and_muffle_behind_shut_doors() {
+        let (mut station, modules) = corridors(3);
+        let config = VoiceConfig::default();
+        let right = Vec3::X;
+
+        let near = playback(&station, &config, inside(1.0), inside(0.0), right).unwrap();
+        assert_eq!(near.channel, VoiceChannel::Proximity);
+        assert!(near.pan > 0.9);
+        assert_eq!(near.high_cut, CLEAR_CUTOFF);
+
+        let open = playback(&station, &config, inside(8.0), inside(0.0), right).unwrap();
+        assert!(open.gain < near.gain && open.high_cut < near.high_cut);
+
+        let door = station.door_between(modules[0], modules[1]).unwrap();
+        let (module, _) = station.door_modules(door).unwrap();
+        station.set_element_state(module, station.doors()[door].element, ElementState::Locked);
+        let shut = playback(&station, &config, inside(8.0), inside(0.0), right).unwrap();
+        assert!(shut.gain < open.gain && shut.high_cut < open.high_cut);
+
+        // No air, no sound, and nothing beyond hearing range
+        station.module_mut(modules[1]).unwrap().atmosphere_sealed = false;
+        assert_eq!(playback(&station, &config, inside(8.0), inside(0.0), right), None);
+        let deaf = VoiceConfig { hearing_range: 5.0, ..config };
+        assert_eq!(playback(&station, &deaf, inside(8.0), inside(0.0), right), None);
+    }
+
+    #[test]
+    fn spacewalkers_talk_on_the_suit_radio() {
+        let (station, _) = corridors(1);
+        let config = VoiceConfig::default();
+        let far_outside = VoiceSource { position: Vec3::new(500.0, 0.0, 0.0), outside: true };
+        let radio = playback(&station, &config, far_outside, inside(0.0), Vec3::X).unwrap();
+        assert_eq!(radio.channel, VoiceChannel::Radio);
+        assert_eq!((radio.gain, radio.pan), (config.radio_volume, 0.0));
+        assert_eq!(playback(&station, &config, inside(0.0), far_outside, Vec3::X).unwrap().channel, VoiceChannel::Radio);
+    }
+
+    #[test]
+    fn the_radio_band_cuts_the_low_end() {
+        let radio = VoicePlayback {
+            channel: VoiceChannel::Radio,
+            gain: 1.0,
+            pan: 0.0,
+            low_cut: RADIO_LOW_CUT,
+            high_cut: RADIO_HIGH_CUT,
+        };
+        let mut filter = VoiceFilter::default();
+        // Steady offset: all low end
+        let frame = vec![16000i16; FRAME_SAMPLES];
+        let mut out = vec![0.0; FRAME_SAMPLES * 2];
+        filter.mix(&radio, &frame, &mut out);
+        assert!(out[out.len() - 2].abs() < 0.01);
+        assert_eq!(out[out.len() - 2], out[out.len() - 1]);
+    }
+}
@@ -0,0 +1,95 @@
+use glam::Vec3;
+
+// A window opening split into panes by its frame. `right` and `up` are the
+// full edge vectors of the opening; `normal` points out of the room.
+#[derive(Debug, Clone)]
+pub struct WindowAperture {
+    pub center: Vec3,
+    pub right: Vec3,
+    pub up: Vec3,
+    pub normal: Vec3,
+    pub panes_x: u32,
+    pub panes_y: u32,
+    pub frame_width: f32,
+}
+
+impl WindowAperture {
+    // The whole opening as a single pane, for when the frame's shadow isn't drawn
+    pub fn unframed(&self) -> Self {
+        Self {
+            panes_x: 1,
+            panes_y: 1,
+            frame_width: 0.0,
+            ..self.clone()
+        }
+    }
+
+    // Corners of each glass pane, with the frame bars left out
+    pub fn panes(&self) -> Vec<[Vec3; 4]> {
+        let mut panes = Vec::new();
+        let width = self.right.length();
+        let height = self.up.length();
+        let right = self.right.normalize_or_zero();
+        let up = self.up.normalize_or_zero();
+        let origin = self.center - self.right * 0.5 - self.up * 0.5;
+
+        let pane_w = (width - self.frame_width * (self.panes_x + 1) as f32) / self.panes_x as f32;
+        let pane_h = (height - self.frame_width * (self.panes_y + 1) as f32) / self.panes_y as f32;
+        if pane_w <= 0.0 || pane_h <= 0.0 {
+            return panes;
+        }
+
+        for ix in 0..self.panes_x {
+            for iy in 0..self.panes_y {
+                let x0 = self.frame_width + ix as f32 * (pane_w + self.frame_width);
+                let y0 = self.frame_width + iy as f32 * (pane_h + self.frame_width);
+                let corner = origin + right * x0 + up * y0;
+                panes.push([
+                    corner,
+                    corner + right * pane_w,
+                    corner + right * pane_w + up * pane_h,
+                    corner + up * pane_h,
+                ]);
+            }
+        }
+
+        panes
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SunPatch {
+    pub quads: Vec<[Vec3; 4]>,
+    pub intensity: f32,
+}
+
+// Projects each pane along the incoming sunlight onto a horizontal floor.
+// The gaps between the projected panes are the frame's shadow.
+pub fn project_sun_patch(
+    window: &WindowAperture,
+    sun_direction: Vec3,
+    sunlight: f32,
+    floor_height: f32,
+) -> Option<SunPatch> {
+    // The sun has to be outside the window and above the floor plane
+    let facing = sun_direction.dot(window.normal);
+    if sunlight <= 0.0 || facing <= 0.0 || sun_direction.y <= 0.0 {
+        return None;
+    }
+
+    let project = |point: Vec3| {
+        let t = (point.y - floor_height) / sun_direction.y;
+        Vec3::new(point.x, floor_height, point.z) - Vec3::new(sun_direction.x, 0.0, sun_direction.z) * t
+    };
+
+    let quads = window
+        .panes()
+        .iter()
+        .map(|pane| [project(pane[0]), project(pane[1]), project(pane[2]), project(pane[3])])
+        .collect();
+
+    Some(SunPatch {
+        quads,
+        intensity: sunlight * facing,
+    })
+}